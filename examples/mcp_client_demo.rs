@@ -13,7 +13,6 @@
 //! ```
 
 use nekoclaw::tools::{McpClient, McpClientError};
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), McpClientError> {
@@ -1,19 +1,18 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use nekoclaw::core::traits::Config;
+use nekoclaw::core::config::{parse_json, parse_toml};
 
 fuzz_target!(|data: &[u8]| {
-    // Fuzz config parsing喵
+    // Fuzz config deserialization喵（JSON 与 TOML 两种格式）
     if let Ok(config_string) = std::str::from_utf8(data) {
-        // 尝试解析各种格式的配置喵
         if data.len() < 10000 {
-            // JSON 格式
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(config_string) {
-                // 验证 JSON 结构喵
-                let _ = json_value.get("api_key");
-                let _ = json_value.get("default_provider");
+            if let Ok(config) = parse_json(config_string) {
+                // 解析成功的配置必须能再次序列化喵
+                let json = serde_json::to_string(&config).expect("config must serialize");
+                parse_json(&json).expect("serialized config must parse again");
             }
+            let _ = parse_toml(config_string);
         }
     }
 });
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nekoclaw::tools::mcp::{decode_frame, encode_frame, JsonRpcRequest};
+
+fuzz_target!(|data: &[u8]| {
+    // Fuzz MCP JSON-RPC 分帧喵（MCP server 输出不可信）
+    if let Ok(line) = std::str::from_utf8(data) {
+        let _ = decode_frame(line);
+
+        // 任意内容编码后都必须是单行帧喵
+        let request = JsonRpcRequest::new(line.to_string(), Some(serde_json::json!({ "data": line })));
+        let frame = encode_frame(&request).expect("request must serialize");
+        assert_eq!(frame.matches('\n').count(), 1);
+    }
+});
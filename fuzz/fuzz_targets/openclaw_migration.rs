@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nekoclaw::config::migrate::{MigrationReport, OPENCLAW_CONFIG};
+
+fuzz_target!(|data: &[u8]| {
+    // Fuzz `nekoclaw migrate` 的迁移计划喵（openclaw.json 与 AGENTS.md 来自不可信目录）
+    // 格式: 第一个 NUL 之前为 openclaw.json，其余为 AGENTS.md
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (config, agents) = text.split_once('\0').unwrap_or((text, ""));
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join(OPENCLAW_CONFIG), config).expect("write openclaw.json");
+    std::fs::write(dir.path().join("AGENTS.md"), agents).expect("write AGENTS.md");
+    if let Ok(report) = MigrationReport::plan(dir.path()) {
+        // 报告只列字段路径，渲染不能失败喵
+        let _ = report.render();
+    }
+});
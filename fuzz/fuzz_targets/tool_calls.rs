@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nekoclaw::tools::parse_tool_calls;
+
+fuzz_target!(|data: &[u8]| {
+    // Fuzz 模型输出中的工具调用解析喵（模型输出不可信）
+    if let Ok(text) = std::str::from_utf8(data) {
+        for call in parse_tool_calls(text) {
+            assert!(!call.tool_name.is_empty());
+        }
+    }
+});
@@ -0,0 +1,32 @@
+#![no_main]
+
+use axum::http::{HeaderMap, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+use nekoclaw::gateway::{WebhookConfig, WebhookManager};
+
+fuzz_target!(|data: &[u8]| {
+    // Fuzz webhook 请求头与负载解析喵
+    // 格式: 第一行为 x-event-type，第二行为 x-event-id，其余为请求体
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut parts = text.splitn(3, '\n');
+    let (event_type, event_id, body) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    );
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let _guard = runtime.enter();
+    let manager = WebhookManager::new(WebhookConfig::default());
+
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(event_type) {
+        headers.insert("x-event-type", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(event_id) {
+        headers.insert("x-event-id", value);
+    }
+    let _ = manager.parse_event(&headers, body);
+});
@@ -0,0 +1,192 @@
+//! Agent 限额执行 ⏱️
+//!
+//! @诺诺 的 `agents.agent.<name>.limits` 守门员喵
+//!
+//! 功能：
+//! - `max_requests_per_hour`：最近一小时（滚动窗口）内该 Agent 的模型请求数
+//! - `max_token_limit`：该 Agent 当天（本地时区，零点重置）累计的 token 数
+//! - `max_session_hours`：单次会话从开始起的最长时长，新会话重新计时
+//!
+//! 请求数与 token 直接从费用账本统计，因此多个进程（CLI / 守护进程）共享同一份用量；
+//! 无痕模式使用内存账本，只统计本次会话喵
+//!
+//! ```toml
+//! [agents.agent.muse.limits]
+//! max_requests_per_hour = 30
+//! max_token_limit = 200000
+//! max_session_hours = 2.0
+//! ```
+//!
+//! 🔒 SAFETY: 超出任一限额时拒绝继续请求，不会截断已经发出的请求
+//!
+//! 实现者: 诺诺 (Nono) ⚡
+
+use crate::core::agent::AgentLimits;
+use crate::providers::cost::{day_start, CostError, CostLedger};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// 🔒 SAFETY: 限额错误喵
+#[derive(Debug, Error)]
+pub enum LimitError {
+    /// 账本读取失败
+    #[error(transparent)]
+    Ledger(#[from] CostError),
+
+    /// 最近一小时请求过多
+    #[error("Agent {agent} request limit reached: {requests} requests in the last hour (limit {limit}), retry after {retry_after}s")]
+    RequestRate {
+        agent: String,
+        requests: u64,
+        limit: u32,
+        retry_after: i64,
+    },
+
+    /// 当天 token 用完
+    #[error("Agent {agent} token limit reached: {tokens} tokens used today (limit {limit}), resets at local midnight")]
+    TokenLimit { agent: String, tokens: u64, limit: u64 },
+
+    /// 会话时长用完
+    #[error("Agent {agent} session limit reached: running for {hours:.2}h (limit {limit}h), start a new session")]
+    SessionExpired { agent: String, hours: f64, limit: f64 },
+}
+
+/// Agent 当前用量喵（`nekoclaw status` 展示）
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LimitUsage {
+    /// 最近一小时的请求数
+    pub requests_last_hour: u64,
+    /// 当天累计 token
+    pub tokens_today: u64,
+}
+
+/// 🔒 SAFETY: 单个 Agent 的限额执行器喵
+pub struct LimitsEnforcer {
+    ledger: Arc<CostLedger>,
+    agent: String,
+    limits: AgentLimits,
+    session_started: Option<DateTime<Utc>>,
+}
+
+impl LimitsEnforcer {
+    pub fn new(ledger: Arc<CostLedger>, agent: &str, limits: AgentLimits) -> Self {
+        Self {
+            ledger,
+            agent: agent.to_string(),
+            limits,
+            session_started: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 从 `started` 起计算会话时长喵（未设置时不检查 `max_session_hours`）
+    pub fn with_session_start(mut self, started: DateTime<Utc>) -> Self {
+        self.session_started = Some(started);
+        self
+    }
+
+    /// 当前用量喵
+    pub fn usage(&self, now: DateTime<Utc>) -> Result<LimitUsage, LimitError> {
+        let (requests_last_hour, _) = self.ledger.agent_usage(&self.agent, now - Duration::hours(1))?;
+        let (_, tokens_today) = self.ledger.agent_usage(&self.agent, day_start(now))?;
+        Ok(LimitUsage {
+            requests_last_hour,
+            tokens_today,
+        })
+    }
+
+    /// 🔒 SAFETY: 请求前检查限额喵（已达到任一限额时返回对应错误）
+    pub fn check(&self, now: DateTime<Utc>) -> Result<LimitUsage, LimitError> {
+        if let (Some(limit), Some(started)) = (self.limits.max_session_hours, self.session_started) {
+            let hours = (now - started).num_seconds() as f64 / 3600.0;
+            if hours >= limit {
+                return Err(LimitError::SessionExpired {
+                    agent: self.agent.clone(),
+                    hours,
+                    limit,
+                });
+            }
+        }
+
+        let usage = self.usage(now)?;
+        if let Some(limit) = self.limits.max_requests_per_hour {
+            if usage.requests_last_hour >= limit as u64 {
+                // 窗口中最早的请求滑出一小时后恢复喵
+                let retry_after = self
+                    .ledger
+                    .nth_request_since(&self.agent, now - Duration::hours(1), usage.requests_last_hour - limit as u64)?
+                    .map(|oldest| (oldest + Duration::hours(1) - now).num_seconds().max(1))
+                    .unwrap_or(3600);
+                return Err(LimitError::RequestRate {
+                    agent: self.agent.clone(),
+                    requests: usage.requests_last_hour,
+                    limit,
+                    retry_after,
+                });
+            }
+        }
+        if let Some(limit) = self.limits.max_token_limit {
+            if usage.tokens_today >= limit {
+                return Err(LimitError::TokenLimit {
+                    agent: self.agent.clone(),
+                    tokens: usage.tokens_today,
+                    limit,
+                });
+            }
+        }
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::Usage;
+
+    fn usage(total: u32) -> Usage {
+        Usage {
+            prompt_tokens: total,
+            completion_tokens: 0,
+            total_tokens: total,
+        }
+    }
+
+    #[test]
+    fn test_limits_reject_and_reset() {
+        let ledger = Arc::new(CostLedger::in_memory().unwrap());
+        let now = Utc::now();
+        let limits = AgentLimits {
+            max_requests_per_hour: Some(2),
+            max_token_limit: Some(1_000),
+            max_session_hours: Some(1.0),
+            ..Default::default()
+        };
+        let enforcer = LimitsEnforcer::new(ledger.clone(), "muse", limits.clone()).with_session_start(now);
+        assert_eq!(enforcer.check(now).unwrap(), LimitUsage::default());
+
+        ledger.record("s1", "muse", "gpt-4o", &usage(100), 0.0, now - Duration::minutes(30)).unwrap();
+        ledger.record("s1", "nia", "gpt-4o", &usage(5_000), 0.0, now).unwrap();
+        assert_eq!(enforcer.check(now).unwrap().requests_last_hour, 1);
+
+        ledger.record("s1", "muse", "gpt-4o", &usage(100), 0.0, now - Duration::minutes(10)).unwrap();
+        match enforcer.check(now) {
+            Err(LimitError::RequestRate { requests: 2, retry_after, .. }) => assert!((29 * 60..=30 * 60).contains(&retry_after)),
+            other => panic!("expected request limit, got {:?}", other),
+        }
+        // 最早的请求滑出一小时窗口后恢复喵
+        assert_eq!(enforcer.usage(now + Duration::minutes(31)).unwrap().requests_last_hour, 1);
+
+        ledger.record("s1", "muse", "gpt-4o", &usage(800), 0.0, now - Duration::hours(2)).unwrap();
+        let daily = LimitsEnforcer::new(ledger.clone(), "muse", AgentLimits { max_requests_per_hour: None, ..limits });
+        match day_start(now) <= now - Duration::hours(2) {
+            true => assert!(matches!(daily.check(now), Err(LimitError::TokenLimit { tokens: 1_000, .. }))),
+            // 凌晨两点前运行时，两小时前的用量属于昨天喵
+            false => assert_eq!(daily.check(now).unwrap().tokens_today, 200),
+        }
+        // 会话时长只对设置了开始时间的执行器生效喵
+        assert!(matches!(
+            enforcer.check(now + Duration::minutes(61)),
+            Err(LimitError::SessionExpired { .. })
+        ));
+    }
+}
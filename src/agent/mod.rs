@@ -15,8 +15,10 @@
 pub mod runtime;
 pub mod session;
 pub mod context;
+pub mod limits;
 
 // 🔒 SAFETY: 重新导出公共接口喵
 pub use runtime::{Agent, AgentConfig, AgentMessage, AgentResponse, AgentStats, AgentError};
 pub use session::{SessionManager, SessionManagerConfig, SessionInfo, SessionState, SessionStats};
 pub use context::{ContextManager, ContextConfig, PrioritizedMessage, MessagePriority, ContextStats};
+pub use limits::{LimitError, LimitUsage, LimitsEnforcer};
@@ -0,0 +1,186 @@
+//!
+//! # OAuth Loopback Callback
+//!
+//! ⚠️ SAFETY: 只监听回环地址，state 不匹配的回调一律拒绝喵
+//!
+//! ## 功能说明
+//! - 按 redirect_uri 在 127.0.0.1 / localhost 上临时监听喵
+//! - redirect_uri 未写端口时随机分配，并改写成实际地址喵
+//! - 收到第一个匹配路径的回调后返回授权码并关闭监听喵
+
+use super::AuthError;
+use oauth2::url::Url;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// 临时回调监听器喵
+pub struct CallbackServer {
+    listener: TcpListener,
+    redirect_uri: String,
+    path: String,
+}
+
+impl CallbackServer {
+    /// 按 redirect_uri 绑定回环地址喵
+    pub async fn bind(redirect_uri: &str) -> Result<Self, AuthError> {
+        let mut url = Url::parse(redirect_uri)
+            .map_err(|e| AuthError::ConfigError(format!("Invalid redirect_uri {}: {}", redirect_uri, e)))?;
+        let host = match url.host_str() {
+            Some("localhost") | Some("127.0.0.1") => "127.0.0.1",
+            _ => {
+                return Err(AuthError::ConfigError(format!(
+                    "redirect_uri must point to localhost or 127.0.0.1 for CLI login: {}",
+                    redirect_uri
+                )))
+            }
+        };
+        if url.scheme() != "http" {
+            return Err(AuthError::ConfigError(format!("Loopback redirect_uri must use http: {}", redirect_uri)));
+        }
+
+        let listener = TcpListener::bind((host, url.port().unwrap_or(0)))
+            .await
+            .map_err(|e| AuthError::ConfigError(format!("Failed to listen on {}: {}", redirect_uri, e)))?;
+        if url.port().is_none() {
+            let port = listener.local_addr().map_err(|e| AuthError::ConfigError(e.to_string()))?.port();
+            let _ = url.set_port(Some(port));
+        }
+
+        Ok(Self {
+            listener,
+            path: url.path().to_string(),
+            redirect_uri: url.to_string(),
+        })
+    }
+
+    /// 实际使用的 redirect_uri（需与授权请求一致）喵
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// 等待授权回调喵
+    ///
+    /// 路径不匹配的请求（如 favicon）返回 404 后继续等待；
+    /// state 不匹配或提供商返回 error 时结束并报错
+    ///
+    /// ## Returns
+    /// 授权码
+    pub async fn wait_for_code(self, state: &str, timeout: Duration) -> Result<String, AuthError> {
+        tokio::time::timeout(timeout, self.accept_loop(state))
+            .await
+            .map_err(|_| AuthError::AuthenticationFailed(format!("No callback received within {}s", timeout.as_secs())))?
+    }
+
+    async fn accept_loop(&self, state: &str) -> Result<String, AuthError> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| AuthError::AuthenticationFailed(e.to_string()))?;
+
+            let mut buf = vec![0u8; 8192];
+            let n = match stream.read(&mut buf).await {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let target = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or("/");
+            let url = match Url::parse(&format!("http://localhost{}", target)) {
+                Ok(url) if url.path() == self.path => url,
+                _ => {
+                    let _ = respond(&mut stream, "404 Not Found", "Not found").await;
+                    continue;
+                }
+            };
+
+            let result = parse_callback(&url, state);
+            let body = match &result {
+                Ok(_) => "Login complete, you can close this window.",
+                Err(_) => "Login failed, check the terminal for details.",
+            };
+            let _ = respond(&mut stream, "200 OK", body).await;
+            return result;
+        }
+    }
+}
+
+/// 从回调 URL 中取出授权码喵
+fn parse_callback(url: &Url, state: &str) -> Result<String, AuthError> {
+    let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+
+    if let Some(error) = param("error") {
+        let description = param("error_description").unwrap_or_default();
+        return Err(AuthError::AuthenticationFailed(format!("{} {}", error, description).trim().to_string()));
+    }
+    if param("state").as_deref() != Some(state) {
+        return Err(AuthError::AuthenticationFailed("State mismatch in OAuth callback".to_string()));
+    }
+    param("code").ok_or_else(|| AuthError::AuthenticationFailed("Missing code in OAuth callback".to_string()))
+}
+
+async fn respond(stream: &mut tokio::net::TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// 用系统默认浏览器打开 URL 喵（失败时由调用方提示手动打开）
+pub fn open_browser(url: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = std::process::Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = std::process::Command::new("xdg-open");
+
+    command
+        .arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_callback_server_returns_code_and_checks_state() {
+        let server = CallbackServer::bind("http://127.0.0.1/callback").await.unwrap();
+        let redirect = server.redirect_uri().to_string();
+        assert!(!redirect.starts_with("http://127.0.0.1/"));
+
+        let client = tokio::spawn(async move {
+            let http = reqwest::Client::new();
+            let miss = http.get(format!("{}/favicon.ico", redirect.trim_end_matches("/callback"))).send().await.unwrap();
+            assert_eq!(miss.status(), 404);
+            http.get(format!("{}?code=abc&state=s1", redirect)).send().await.unwrap().status()
+        });
+        let code = server.wait_for_code("s1", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(code, "abc");
+        assert!(client.await.unwrap().is_success());
+
+        let server = CallbackServer::bind("http://localhost/cb").await.unwrap();
+        let redirect = server.redirect_uri().to_string();
+        tokio::spawn(async move { reqwest::get(format!("{}?code=abc&state=evil", redirect)).await });
+        assert!(server.wait_for_code("s1", Duration::from_secs(5)).await.is_err());
+
+        assert!(CallbackServer::bind("https://example.com/cb").await.is_err());
+    }
+}
@@ -8,17 +8,24 @@
 //! - 凭证安全存储和加密喵
 //! - 认证配置文件解析喵
 //! - Token 自动刷新喵
+//! - 回环地址回调完成 CLI 登录（PKCE）喵
+//! - 渠道与 Gateway 共用的角色权限（`rbac`）喵
 //!
 //! ## OpenClaw 兼容
 //! - 兼容 `auth.profiles` 配置格式喵
 //! - 支持 Discord OAuth喵
 //! - 支持 Google OAuth喵
 
-use crate::security::CryptoService;
+pub mod callback;
+pub mod rbac;
+
+pub use rbac::{Identity, Permission, Rbac, RbacConfig, RbacError, Role, RBAC_DB_FILE};
+
+use crate::security::{CryptoService, KeyRing};
 use chrono::{Duration, Utc};
 use oauth2::basic::BasicClient;
 use oauth2::reqwest::async_http_client;
-use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, RefreshToken, TokenResponse, TokenUrl};
+use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenResponse, TokenUrl};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -219,6 +226,22 @@ impl AuthSession {
     }
 }
 
+/// 引入密钥环之前凭证文件使用的全零密钥（版本 0，仅用于解密历史文件）喵
+const LEGACY_CREDENTIAL_KEY: [u8; 32] = [0u8; 32];
+
+/// 凭证密钥环喵（`<root>/keys/credentials.keyring`）
+pub fn credential_keyring(root: &std::path::Path) -> Result<KeyRing, AuthError> {
+    KeyRing::open(&KeyRing::path_for(root, "credentials"), Some(&LEGACY_CREDENTIAL_KEY))
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))
+}
+
+/// 凭证文件主密钥喵
+pub fn master_crypto(root: &std::path::Path) -> Result<CryptoService, AuthError> {
+    credential_keyring(root)?
+        .crypto()
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))
+}
+
 /// 凭证存储喵
 #[derive(Clone)]
 pub struct CredentialStore {
@@ -299,6 +322,108 @@ impl CredentialStore {
         Some(token)
     }
 
+    /// 已保存的凭证名称喵（不解密）
+    pub fn keys(&self) -> Result<Vec<String>, AuthError> {
+        let entries = std::fs::read_dir(&self.storage_path)
+            .map_err(|e| AuthError::ConfigError(format!("Failed to read storage directory: {}", e)))?;
+        let mut keys: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.strip_suffix(".cred").map(str::to_string))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// 凭证文件是否存在喵（不解密，其他进程删除后立即返回 false）
+    pub fn contains(&self, key: &str) -> bool {
+        self.storage_path.join(format!("{}.cred", key)).is_file()
+    }
+
+    /// 删除已过期且无法刷新的凭证文件喵
+    ///
+    /// 无法解密的文件原样保留（可能只是换了密钥），只记录日志
+    ///
+    /// ## Returns
+    /// (删除数量, 释放字节数)
+    pub async fn purge_expired(&self, now: chrono::DateTime<Utc>) -> Result<(usize, u64), AuthError> {
+        let entries = std::fs::read_dir(&self.storage_path)
+            .map_err(|e| AuthError::ConfigError(format!("Failed to read storage directory: {}", e)))?;
+
+        let mut removed = 0;
+        let mut bytes = 0;
+        for path in entries.flatten().map(|e| e.path()) {
+            let Some(key) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".cred"))
+            else {
+                continue;
+            };
+            let Ok(encrypted) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let token = match self.crypto.decrypt(&encrypted) {
+                Ok(json) => serde_json::from_str::<TokenInfo>(&json).ok(),
+                Err(e) => {
+                    tracing::warn!("Skipping undecryptable credential {}: {}", key, e);
+                    continue;
+                }
+            };
+            let Some(token) = token else { continue };
+            if token.expires_at > now || token.refresh_token.is_some() {
+                continue;
+            }
+
+            std::fs::remove_file(&path).map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+            self.cache.lock().await.remove(key);
+            removed += 1;
+            bytes += encrypted.len() as u64;
+        }
+
+        Ok((removed, bytes))
+    }
+
+    /// 用当前版本密钥重新加密所有凭证文件喵（密钥轮换后调用）
+    ///
+    /// 无法解密的文件原样保留并记录日志
+    ///
+    /// ## Returns
+    /// (重新加密数量, 失败数量)
+    pub fn reencrypt(&self) -> Result<(usize, usize), AuthError> {
+        let entries = std::fs::read_dir(&self.storage_path)
+            .map_err(|e| AuthError::ConfigError(format!("Failed to read storage directory: {}", e)))?;
+
+        let mut migrated = 0;
+        let mut failed = 0;
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("cred") {
+                continue;
+            }
+            let Ok(encrypted) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if self.crypto.is_current(&encrypted) {
+                continue;
+            }
+            let resealed = self
+                .crypto
+                .decrypt(&encrypted)
+                .and_then(|plain| self.crypto.encrypt(&plain));
+            match resealed {
+                Ok(resealed) => {
+                    std::fs::write(&path, resealed).map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+                    migrated += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping undecryptable credential {}: {}", path.display(), e);
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok((migrated, failed))
+    }
+
     pub async fn delete(&self, key: &str) -> Result<(), AuthError> {
         let file_path = self.storage_path.join(format!("{}.cred", key));
         if file_path.exists() {
@@ -334,7 +459,6 @@ pub struct AuthProfile {
 pub struct AuthManager {
     config: OAuthConfig,
     store: CredentialStore,
-    sessions: Arc<Mutex<HashMap<String, AuthSession>>>,
     oauth2_client: Option<BasicClient>,
 }
 
@@ -349,17 +473,14 @@ impl AuthManager {
                 .join(".nekoclaw/credentials")
         });
 
-        let crypto = CryptoService::new(&[0u8; 32]) // TODO: 使用实际的主密钥
-            .map_err(|e| AuthError::EncryptionError(e.to_string()))?;
-
+        let root = storage_path.parent().unwrap_or(&storage_path).to_path_buf();
+        let crypto = master_crypto(&root)?;
         let store = CredentialStore::new(storage_path, crypto)?;
-        let sessions = Arc::new(Mutex::new(HashMap::new()));
         let oauth2_client = config.to_oauth2_client().ok();
 
         Ok(Self {
             config,
             store,
-            sessions,
             oauth2_client,
         })
     }
@@ -367,7 +488,7 @@ impl AuthManager {
     pub async fn create_authorization_url(
         &self,
         state: &str,
-        _pkce_code_verifier: Option<&str>,
+        pkce_code_verifier: Option<&str>,
     ) -> Result<String, AuthError> {
         let client = self
             .oauth2_client
@@ -380,6 +501,11 @@ impl AuthManager {
             request = request.add_scope(oauth2::Scope::new(scope.to_string()));
         }
 
+        if let Some(verifier) = pkce_code_verifier {
+            let verifier = oauth2::PkceCodeVerifier::new(verifier.to_string());
+            request = request.set_pkce_challenge(oauth2::PkceCodeChallenge::from_code_verifier_sha256(&verifier));
+        }
+
         let (auth_url, _) = request.url();
         Ok(auth_url.to_string())
     }
@@ -421,6 +547,35 @@ impl AuthManager {
             user_id: None,
         })
     }
+
+    /// 保存 Token 到凭证存储喵
+    pub async fn save_token(&self, key: &str, token: &TokenInfo) -> Result<(), AuthError> {
+        self.store.save(key, token).await
+    }
+}
+
+/// 生成随机 PKCE code verifier喵
+pub fn new_pkce_verifier() -> String {
+    let (_, verifier) = oauth2::PkceCodeChallenge::new_random_sha256();
+    verifier.secret().to_string()
+}
+
+impl AuthProfiles {
+    /// 按名称查找启用的配置喵（未指定时使用默认配置）
+    pub fn find(&self, name: Option<&str>) -> Result<&AuthProfile, AuthError> {
+        let name = name.or(self.default_profile.as_deref());
+        match name {
+            Some(name) => self
+                .profiles
+                .iter()
+                .find(|p| p.name == name && p.enabled)
+                .ok_or_else(|| AuthError::ConfigError(format!("Profile '{}' not found or disabled", name))),
+            None => self
+                .profiles
+                .first()
+                .ok_or_else(|| AuthError::ConfigError("No profiles available".to_string())),
+        }
+    }
 }
 
 pub async fn create_auth_manager_from_profiles(
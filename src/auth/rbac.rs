@@ -0,0 +1,452 @@
+//!
+//! # Role-Based Access Control
+//!
+//! ⚠️ SAFETY: 所有渠道与 Gateway 共用的角色权限模块喵
+//!
+//! ## 功能说明
+//! - 用户身份按渠道区分：`telegram:<用户 ID>`、`discord:<用户 ID>`、`gateway:<API Key 名称>` 喵
+//! - 角色从低到高：ReadOnly < Agent < Admin < Owner，角色分配持久化在 `<工作区>/rbac.db`（SQLite）喵
+//! - 权限映射到所需的最低角色：命令（各渠道给出默认值，`[rbac.commands]` 覆盖）、
+//!   工具（`tool_role`，`[rbac.tools]` 覆盖）、与 Agent 对话（`chat_role`）、Gateway 管理端点（Admin）喵
+//! - 未分配角色的用户使用 `default_role`；`owners` 中的身份始终是 Owner 喵
+//!
+//! ```toml
+//! [rbac]
+//! default_role = "agent"
+//! owners = ["telegram:123456"]
+//!
+//! [rbac.commands]
+//! status = "admin"
+//!
+//! [rbac.tools]
+//! shell = "owner"
+//! ```
+//!
+//! ```text
+//! nekoclaw rbac assign discord:42 admin
+//! nekoclaw rbac list
+//! ```
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// 角色数据库文件名喵
+pub const RBAC_DB_FILE: &str = "rbac.db";
+
+/// 权限错误喵
+#[derive(Error, Debug)]
+pub enum RbacError {
+    /// 角色不足喵
+    #[error("{identity} ({actual}) needs role {required} for {permission}")]
+    Forbidden {
+        identity: Identity,
+        permission: String,
+        required: Role,
+        actual: Role,
+    },
+
+    /// 未知角色喵
+    #[error("Unknown role: {0} (readonly, agent, admin, owner)")]
+    InvalidRole(String),
+
+    /// 身份格式错误喵
+    #[error("Invalid identity {0:?} (expected <channel>:<id>)")]
+    InvalidIdentity(String),
+
+    /// 数据库错误喵
+    #[error("RBAC store error: {0}")]
+    Store(String),
+}
+
+impl From<rusqlite::Error> for RbacError {
+    fn from(e: rusqlite::Error) -> Self {
+        RbacError::Store(e.to_string())
+    }
+}
+
+/// 权限角色喵（从低到高）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    ReadOnly = 0,
+    Agent = 1,
+    Admin = 2,
+    Owner = 3,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ReadOnly => "readonly",
+            Role::Agent => "agent",
+            Role::Admin => "admin",
+            Role::Owner => "owner",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = RbacError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "readonly" | "read_only" => Ok(Role::ReadOnly),
+            "agent" => Ok(Role::Agent),
+            "admin" => Ok(Role::Admin),
+            "owner" => Ok(Role::Owner),
+            other => Err(RbacError::InvalidRole(other.to_string())),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 渠道内的用户身份喵（`<channel>:<id>`）
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Identity {
+    pub channel: String,
+    pub user_id: String,
+}
+
+impl Identity {
+    pub fn new(channel: &str, user_id: impl ToString) -> Self {
+        Self {
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.channel, self.user_id)
+    }
+}
+
+impl FromStr for Identity {
+    type Err = RbacError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((channel, id)) if !channel.is_empty() && !id.is_empty() => Ok(Identity::new(channel, id)),
+            _ => Err(RbacError::InvalidIdentity(s.to_string())),
+        }
+    }
+}
+
+/// 需要检查的权限喵
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission<'a> {
+    /// 与 Agent 对话
+    Chat,
+    /// 斜杠命令（名称，渠道给出的默认最低角色）
+    Command(&'a str, Role),
+    /// Agent 执行工具
+    Tool(&'a str),
+    /// Gateway 管理端点（`/admin/*`）
+    Admin,
+}
+
+impl fmt::Display for Permission<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::Chat => f.write_str("chat"),
+            Permission::Command(name, _) => write!(f, "command /{}", name),
+            Permission::Tool(name) => write!(f, "tool {}", name),
+            Permission::Admin => f.write_str("admin endpoints"),
+        }
+    }
+}
+
+fn default_role() -> Role {
+    Role::Agent
+}
+
+/// 角色到权限的映射配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RbacConfig {
+    /// 未分配角色的用户
+    #[serde(default = "default_role")]
+    pub default_role: Role,
+
+    /// 与 Agent 对话所需的角色
+    #[serde(default = "default_role")]
+    pub chat_role: Role,
+
+    /// 执行工具所需的默认角色
+    #[serde(default = "default_role")]
+    pub tool_role: Role,
+
+    /// 始终为 Owner 的身份（`<channel>:<id>`）
+    #[serde(default)]
+    pub owners: Vec<String>,
+
+    /// 按命令覆盖所需角色（命令名 → 角色）
+    #[serde(default)]
+    pub commands: BTreeMap<String, Role>,
+
+    /// 按工具覆盖所需角色（工具名 → 角色）
+    #[serde(default)]
+    pub tools: BTreeMap<String, Role>,
+}
+
+impl Default for RbacConfig {
+    fn default() -> Self {
+        Self {
+            default_role: default_role(),
+            chat_role: default_role(),
+            tool_role: default_role(),
+            owners: Vec::new(),
+            commands: BTreeMap::new(),
+            tools: BTreeMap::new(),
+        }
+    }
+}
+
+impl RbacConfig {
+    /// 权限所需的最低角色喵
+    pub fn required_role(&self, permission: Permission<'_>) -> Role {
+        match permission {
+            Permission::Chat => self.chat_role,
+            Permission::Command(name, default) => self.commands.get(name).copied().unwrap_or(default),
+            Permission::Tool(name) => self.tools.get(name).copied().unwrap_or(self.tool_role),
+            Permission::Admin => Role::Admin,
+        }
+    }
+}
+
+/// 一条角色分配喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleAssignment {
+    pub identity: Identity,
+    pub role: Role,
+    pub granted_by: String,
+    pub granted_at: DateTime<Utc>,
+}
+
+/// 🔒 SAFETY: 角色管理器喵（配置映射 + 持久化的角色分配）
+pub struct Rbac {
+    config: RbacConfig,
+    owners: Vec<Identity>,
+    /// None = 只使用配置（不持久化角色分配）
+    conn: Option<Mutex<Connection>>,
+}
+
+impl fmt::Debug for Rbac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rbac")
+            .field("config", &self.config)
+            .field("persistent", &self.conn.is_some())
+            .finish()
+    }
+}
+
+impl Default for Rbac {
+    fn default() -> Self {
+        Self::new(RbacConfig::default())
+    }
+}
+
+impl Rbac {
+    /// 只按配置判定（没有角色分配）喵
+    pub fn new(config: RbacConfig) -> Self {
+        let owners = config.owners.iter().filter_map(|s| s.parse().ok()).collect();
+        Self {
+            config,
+            owners,
+            conn: None,
+        }
+    }
+
+    /// 打开（或创建）角色数据库喵
+    pub fn open(path: &Path, config: RbacConfig) -> Result<Self, RbacError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| RbacError::Store(e.to_string()))?;
+        }
+        Self::initialize(Connection::open(path)?, config)
+    }
+
+    /// 内存数据库（测试用）喵
+    #[cfg(test)]
+    pub fn in_memory(config: RbacConfig) -> Result<Self, RbacError> {
+        Self::initialize(Connection::open_in_memory()?, config)
+    }
+
+    fn initialize(conn: Connection, config: RbacConfig) -> Result<Self, RbacError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS role_assignments (
+                channel TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                granted_by TEXT NOT NULL,
+                granted_at TEXT NOT NULL,
+                PRIMARY KEY (channel, user_id)
+            );",
+        )?;
+        let mut rbac = Self::new(config);
+        rbac.conn = Some(Mutex::new(conn));
+        Ok(rbac)
+    }
+
+    /// 🔒 SAFETY: 追加始终为 Owner 的身份喵（如 Telegram 的 `owner_user_ids`）
+    pub fn with_owner(mut self, identity: Identity) -> Self {
+        self.owners.push(identity);
+        self
+    }
+
+    pub fn config(&self) -> &RbacConfig {
+        &self.config
+    }
+
+    /// 用户当前的角色喵
+    pub fn role_of(&self, identity: &Identity) -> Role {
+        if self.owners.contains(identity) {
+            return Role::Owner;
+        }
+        self.assigned(identity).ok().flatten().unwrap_or(self.config.default_role)
+    }
+
+    fn assigned(&self, identity: &Identity) -> Result<Option<Role>, RbacError> {
+        let Some(conn) = &self.conn else {
+            return Ok(None);
+        };
+        let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+        let role: Option<String> = conn
+            .query_row(
+                "SELECT role FROM role_assignments WHERE channel = ?1 AND user_id = ?2",
+                params![identity.channel, identity.user_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        role.map(|r| r.parse()).transpose()
+    }
+
+    /// 🔒 SAFETY: 检查权限喵，通过时返回用户的角色
+    pub fn check(&self, identity: &Identity, permission: Permission<'_>) -> Result<Role, RbacError> {
+        let actual = self.role_of(identity);
+        let required = self.config.required_role(permission);
+        if actual < required {
+            return Err(RbacError::Forbidden {
+                identity: identity.clone(),
+                permission: permission.to_string(),
+                required,
+                actual,
+            });
+        }
+        Ok(actual)
+    }
+
+    /// 🔒 SAFETY: 分配角色喵（覆盖已有分配）
+    pub fn assign(&self, identity: &Identity, role: Role, granted_by: &str) -> Result<(), RbacError> {
+        let conn = self.store()?;
+        conn.execute(
+            "INSERT INTO role_assignments (channel, user_id, role, granted_by, granted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (channel, user_id) DO UPDATE
+             SET role = excluded.role, granted_by = excluded.granted_by, granted_at = excluded.granted_at",
+            params![identity.channel, identity.user_id, role.as_str(), granted_by, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// 撤销角色分配喵（恢复为 `default_role`，不存在时返回 false）
+    pub fn revoke(&self, identity: &Identity) -> Result<bool, RbacError> {
+        let conn = self.store()?;
+        let removed = conn.execute(
+            "DELETE FROM role_assignments WHERE channel = ?1 AND user_id = ?2",
+            params![identity.channel, identity.user_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// 所有角色分配喵
+    pub fn list(&self) -> Result<Vec<RoleAssignment>, RbacError> {
+        let conn = self.store()?;
+        let mut stmt = conn.prepare(
+            "SELECT channel, user_id, role, granted_by, granted_at FROM role_assignments ORDER BY channel, user_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                Identity::new(&row.get::<_, String>(0)?, row.get::<_, String>(1)?),
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        let mut assignments = Vec::new();
+        for row in rows {
+            let (identity, role, granted_by, granted_at) = row?;
+            assignments.push(RoleAssignment {
+                identity,
+                role: role.parse()?,
+                granted_by,
+                granted_at: DateTime::parse_from_rfc3339(&granted_at)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(assignments)
+    }
+
+    fn store(&self) -> Result<std::sync::MutexGuard<'_, Connection>, RbacError> {
+        self.conn
+            .as_ref()
+            .map(|c| c.lock().unwrap_or_else(|e| e.into_inner()))
+            .ok_or_else(|| RbacError::Store("role assignments are not persisted".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_map_to_permissions_and_persist() {
+        let config = RbacConfig {
+            owners: vec!["telegram:1".to_string()],
+            commands: BTreeMap::from([("status".to_string(), Role::Admin)]),
+            tools: BTreeMap::from([("shell".to_string(), Role::Owner)]),
+            ..RbacConfig::default()
+        };
+        let rbac = Rbac::in_memory(config).unwrap().with_owner(Identity::new("discord", "9"));
+        let owner: Identity = "telegram:1".parse().unwrap();
+        let user = Identity::new("telegram", 2);
+        assert!("telegram".parse::<Identity>().is_err());
+
+        assert_eq!(rbac.role_of(&owner), Role::Owner);
+        assert_eq!(rbac.role_of(&Identity::new("discord", "9")), Role::Owner);
+        assert_eq!(rbac.role_of(&user), Role::Agent);
+        assert!(rbac.check(&user, Permission::Chat).is_ok());
+        assert!(rbac.check(&user, Permission::Tool("fs_read")).is_ok());
+        assert!(rbac.check(&user, Permission::Tool("shell")).is_err());
+        assert!(rbac.check(&user, Permission::Command("ping", Role::ReadOnly)).is_ok());
+        assert!(rbac.check(&user, Permission::Command("pair", Role::Owner)).is_err());
+        // 配置覆盖渠道给出的默认角色喵
+        let err = rbac.check(&user, Permission::Command("status", Role::ReadOnly)).unwrap_err();
+        assert_eq!(err.to_string(), "telegram:2 (agent) needs role admin for command /status");
+        assert!(rbac.check(&owner, Permission::Admin).is_ok());
+
+        rbac.assign(&user, Role::Admin, "cli:test").unwrap();
+        assert!(rbac.check(&user, Permission::Command("status", Role::ReadOnly)).is_ok());
+        rbac.assign(&user, Role::ReadOnly, "cli:test").unwrap();
+        assert!(rbac.check(&user, Permission::Chat).is_err());
+        assert_eq!(rbac.list().unwrap().len(), 1);
+        assert_eq!(rbac.list().unwrap()[0].role, Role::ReadOnly);
+
+        assert!(rbac.revoke(&user).unwrap());
+        assert!(!rbac.revoke(&user).unwrap());
+        assert_eq!(rbac.role_of(&user), Role::Agent);
+        assert!(Rbac::default().assign(&user, Role::Admin, "cli:test").is_err());
+    }
+}
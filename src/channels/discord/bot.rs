@@ -8,38 +8,30 @@
  * - Discord Bot 核心实现
  * - 事件处理 (消息、反应、连接)
  * - 集成 Provider 和 Memory 系统
+ * - 对机器人回复的 👍 / 👎 表情记录为用户反馈
  */
 
 use crate::core::traits::*;
-use async_trait::async_trait;
+use crate::telemetry::{Feedback, MetricsRecorder, Rating};
 use futures::{Stream, StreamExt};
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 /// Discord Bot 配置
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DiscordConfig {
     pub token: String,
     pub allowed_users: Vec<String>,
     pub allowed_channels: Option<Vec<String>>,
 }
 
-impl Default for DiscordConfig {
-    fn default() -> Self {
-        Self {
-            token: String::new(),
-            allowed_users: vec![],
-            allowed_channels: None,
-        }
-    }
-}
-
 /// Discord Bot
 pub struct DiscordBot {
     config: DiscordConfig,
     provider: Option<Arc<dyn Provider>>,
     memory: Option<Arc<dyn Memory>>,
+    feedback: Option<MetricsRecorder>,
     event_tx: mpsc::UnboundedSender<DiscordEvent>,
 }
 
@@ -55,6 +47,7 @@ impl DiscordBot {
             config,
             provider: None,
             memory: None,
+            feedback: None,
             event_tx,
         }
     }
@@ -71,6 +64,12 @@ impl DiscordBot {
         self
     }
 
+    /// 记录 👍 / 👎 反馈
+    pub fn with_feedback(mut self, recorder: MetricsRecorder) -> Self {
+        self.feedback = Some(recorder);
+        self
+    }
+
     /// 启动 Bot
     pub async fn start(&self) -> Result<()> {
         // TODO: 实现 Discord 连接逻辑
@@ -86,7 +85,7 @@ impl DiscordBot {
     }
 
     /// 处理接收到的消息
-    async fn handle_message(
+    pub async fn handle_message(
         &self,
         author_id: String,
         channel_id: String,
@@ -120,6 +119,33 @@ impl DiscordBot {
         Ok(event)
     }
 
+    /// 处理表情回应 (👍 / 👎 记录为对该条回复的反馈)
+    ///
+    /// 返回识别出的评价；未授权用户的回应直接忽略
+    pub async fn handle_reaction(
+        &self,
+        user_id: String,
+        channel_id: String,
+        message_id: String,
+        emoji: String,
+    ) -> Result<Option<Rating>> {
+        if !self.config.allowed_users.contains(&user_id) {
+            return Ok(None);
+        }
+
+        let rating = Rating::from_emoji(&emoji);
+        if let (Some(rating), Some(recorder)) = (rating, &self.feedback) {
+            let feedback = Feedback::new(&message_id, rating, "discord").with_user(&user_id);
+            recorder.record_feedback(&feedback)?;
+        }
+
+        self.event_tx
+            .send(DiscordEvent::Reaction(user_id, channel_id, message_id, emoji))
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+
+        Ok(rating)
+    }
+
     /// 事件监听器 (后台任务)
     async fn event_listener(mut event_rx: mpsc::UnboundedReceiver<DiscordEvent>) {
         while let Some(event) = event_rx.recv().await {
@@ -130,10 +156,10 @@ impl DiscordBot {
                 DiscordEvent::Typing(user_id, channel_id) => {
                     println!("⌨️  User {} is typing in channel {}", user_id, channel_id);
                 }
-                DiscordEvent::Reaction(user_id, channel_id, emoji) => {
+                DiscordEvent::Reaction(user_id, channel_id, message_id, emoji) => {
                     println!(
-                        "😀 User {} reacted with {} in channel {}",
-                        user_id, emoji, channel_id
+                        "😀 User {} reacted with {} to message {} in channel {}",
+                        user_id, emoji, message_id, channel_id
                     );
                 }
             }
@@ -160,8 +186,7 @@ impl Channel for DiscordBot {
         })
         .ok();
 
-        let stream =
-            tokio_stream::wrappers::UnboundedReceiverStream::new(rx).map(|event| Ok(event));
+        let stream = tokio_stream::wrappers::UnboundedReceiverStream::new(rx).map(Ok);
 
         Box::pin(stream)
     }
@@ -180,5 +205,5 @@ impl Channel for DiscordBot {
 pub enum DiscordEvent {
     Message(ChannelEvent),
     Typing(String, String),           // user_id, channel_id
-    Reaction(String, String, String), // user_id, channel_id, emoji
+    Reaction(String, String, String, String), // user_id, channel_id, message_id, emoji
 }
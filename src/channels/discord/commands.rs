@@ -7,13 +7,17 @@
  * 功能:
  * - Discord 斜杠命令 (/command) 处理
  * - 命令注册和路由
- * - 权限验证
+ * - 权限验证（共享的 `Rbac`，身份 `discord:<用户 ID>`，`[rbac.commands]` 可覆盖命令所需角色）
+ * - 命令定义可导出为 Discord application command，启动时注册为原生斜杠命令
  */
 
+use crate::auth::{Identity, Permission, Rbac, Role};
 use crate::core::traits::*;
+use crate::gateway::ChatBackend;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// 命令上下文
 #[derive(Debug, Clone)]
@@ -32,6 +36,30 @@ pub struct CommandResult {
     pub ephemeral: bool, // 仅用户可见
 }
 
+/// 命令的字符串参数（注册为斜杠命令的 option）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandOption {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+impl CommandOption {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            required: false,
+        }
+    }
+
+    /// 必填参数
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+}
+
 /// 命令处理器 Trait
 #[async_trait]
 pub trait CommandHandler: Send + Sync {
@@ -41,11 +69,21 @@ pub trait CommandHandler: Send + Sync {
     /// 命令描述
     fn description(&self) -> &str;
 
+    /// 命令参数（斜杠命令的各参数按此顺序以空格拼接为 `args`）
+    fn options(&self) -> Vec<CommandOption> {
+        Vec::new()
+    }
+
     /// 执行命令
     async fn execute(&self, ctx: CommandContext, args: Option<String>) -> Result<CommandResult>;
 
+    /// 所需的最低角色（默认所有人可用）
+    fn required_role(&self) -> Role {
+        Role::ReadOnly
+    }
+
     /// 检查权限
-    fn check_permission(&self, ctx: &CommandContext) -> bool {
+    fn check_permission(&self, _ctx: &CommandContext) -> bool {
         // 默认允许所有人执行
         true
     }
@@ -54,6 +92,7 @@ pub trait CommandHandler: Send + Sync {
 /// 命令管理器
 pub struct CommandManager {
     commands: HashMap<String, Box<dyn CommandHandler>>,
+    rbac: Arc<Rbac>,
 }
 
 impl CommandManager {
@@ -61,9 +100,16 @@ impl CommandManager {
     pub fn new() -> Self {
         Self {
             commands: HashMap::new(),
+            rbac: Arc::new(Rbac::default()),
         }
     }
 
+    /// 使用共享的角色权限
+    pub fn with_rbac(mut self, rbac: Arc<Rbac>) -> Self {
+        self.rbac = rbac;
+        self
+    }
+
     /// 注册命令
     pub fn register(&mut self, handler: Box<dyn CommandHandler>) {
         let name = handler.name().to_string();
@@ -78,13 +124,25 @@ impl CommandManager {
         ctx: CommandContext,
         args: Option<String>,
     ) -> Result<CommandResult> {
-        let handler = self
-            .commands
-            .get(command_name)
-            .ok_or_else(|| format!("Command '{}' not found", command_name))?;
+        let Some(handler) = self.commands.get(command_name) else {
+            let message = match self.suggest(command_name) {
+                Some(suggestion) => format!(
+                    "❓ Unknown command `/{}`. Did you mean `/{}`?",
+                    command_name, suggestion
+                ),
+                None => format!("❓ Unknown command `/{}`. Try `/help`.", command_name),
+            };
+            return Ok(CommandResult {
+                success: false,
+                message,
+                ephemeral: true,
+            });
+        };
 
         // 检查权限
-        if !handler.check_permission(&ctx) {
+        let identity = Identity::new("discord", &ctx.user_id);
+        let permission = Permission::Command(handler.name(), handler.required_role());
+        if self.rbac.check(&identity, permission).is_err() || !handler.check_permission(&ctx) {
             return Ok(CommandResult {
                 success: false,
                 message: "🚫 You don't have permission to use this command".to_string(),
@@ -95,10 +153,31 @@ impl CommandManager {
         handler.execute(ctx, args).await
     }
 
+    /// 为拼错的命令找最接近的已注册命令
+    pub fn suggest(&self, command_name: &str) -> Option<&str> {
+        crate::core::closest_match(command_name, self.commands.keys().map(String::as_str))
+    }
+
     /// 列出所有命令
     pub fn list_commands(&self) -> Vec<String> {
         self.commands.keys().cloned().collect()
     }
+
+    /// 命令的参数定义（未注册的命令为空）
+    pub fn options(&self, command_name: &str) -> Vec<CommandOption> {
+        self.commands.get(command_name).map(|h| h.options()).unwrap_or_default()
+    }
+
+    /// 导出为 Discord application command 定义（按名称排序）
+    pub fn application_commands(&self) -> Vec<super::interactions::ApplicationCommand> {
+        let mut commands: Vec<_> = self
+            .commands
+            .values()
+            .map(|handler| super::interactions::ApplicationCommand::from_handler(handler.as_ref()))
+            .collect();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        commands
+    }
 }
 
 impl Default for CommandManager {
@@ -179,6 +258,10 @@ impl CommandHandler for MemoryCommand {
         "Query memory system"
     }
 
+    fn options(&self) -> Vec<CommandOption> {
+        vec![CommandOption::new("query", "What to look up (default: recent)")]
+    }
+
     async fn execute(&self, _ctx: CommandContext, args: Option<String>) -> Result<CommandResult> {
         let query = args.unwrap_or_else(|| "recent".to_string());
 
@@ -207,10 +290,8 @@ impl CommandHandler for ConfigCommand {
         "Show/Edit configuration (Admin only)"
     }
 
-    fn check_permission(&self, ctx: &CommandContext) -> bool {
-        // TODO: 实现管理员权限检查
-        // 简化实现: 假设特定用户 ID 是管理员
-        ctx.user_id == "admin_user_id"
+    fn required_role(&self) -> Role {
+        Role::Admin
     }
 
     async fn execute(&self, _ctx: CommandContext, _args: Option<String>) -> Result<CommandResult> {
@@ -224,6 +305,211 @@ impl CommandHandler for ConfigCommand {
     }
 }
 
+/// 放行申请审批命令 (/approve, /deny)，仅 Owner 角色可用
+pub struct EscalationDecisionCommand {
+    approve: bool,
+    manager: std::sync::Arc<crate::security::EscalationManager>,
+}
+
+impl EscalationDecisionCommand {
+    pub fn approve(manager: std::sync::Arc<crate::security::EscalationManager>) -> Self {
+        Self { approve: true, manager }
+    }
+
+    pub fn deny(manager: std::sync::Arc<crate::security::EscalationManager>) -> Self {
+        Self { approve: false, manager }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for EscalationDecisionCommand {
+    fn name(&self) -> &str {
+        if self.approve {
+            "approve"
+        } else {
+            "deny"
+        }
+    }
+
+    fn description(&self) -> &str {
+        if self.approve {
+            "Approve a blocked operation once (Owner only)"
+        } else {
+            "Deny a blocked operation (Owner only)"
+        }
+    }
+
+    fn options(&self) -> Vec<CommandOption> {
+        vec![CommandOption::new("id", "Escalation id").required()]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Owner
+    }
+
+    async fn execute(&self, ctx: CommandContext, args: Option<String>) -> Result<CommandResult> {
+        let id = args.as_deref().map(str::trim).unwrap_or_default();
+        if id.is_empty() {
+            return Ok(CommandResult {
+                success: false,
+                message: format!("Usage: /{} <escalation id>", self.name()),
+                ephemeral: true,
+            });
+        }
+
+        let approver = format!("discord:{}", ctx.user_id);
+        let decision = if self.approve {
+            self.manager.approve(id, &approver)
+        } else {
+            self.manager.deny(id, &approver)
+        };
+
+        Ok(match decision {
+            Ok(request) => CommandResult {
+                success: true,
+                message: format!(
+                    "{} `{}` ({} → {:?})",
+                    if self.approve { "✅ Approved once:" } else { "🚫 Denied:" },
+                    request.id,
+                    request.violation.policy,
+                    request.invocation
+                ),
+                ephemeral: true,
+            },
+            Err(e) => CommandResult {
+                success: false,
+                message: format!("❌ {}", e),
+                ephemeral: true,
+            },
+        })
+    }
+}
+
+/// 会话语言命令 (/lang [code|auto])
+pub struct LangCommand {
+    languages: crate::core::SessionLanguages,
+}
+
+impl LangCommand {
+    pub fn new(languages: crate::core::SessionLanguages) -> Self {
+        Self { languages }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for LangCommand {
+    fn name(&self) -> &str {
+        "lang"
+    }
+
+    fn description(&self) -> &str {
+        "Show or set the reply language for this channel (e.g. /lang zh, /lang auto)"
+    }
+
+    fn options(&self) -> Vec<CommandOption> {
+        vec![CommandOption::new("language", "Language code, or auto")]
+    }
+
+    async fn execute(&self, ctx: CommandContext, args: Option<String>) -> Result<CommandResult> {
+        let conversation = format!("discord:{}", ctx.channel_id);
+        let message = self.languages.handle_command(&conversation, args.as_deref());
+        Ok(CommandResult {
+            success: !message.starts_with('❌'),
+            message,
+            ephemeral: false,
+        })
+    }
+}
+
+/// Agent 对话命令 (/ask <prompt>)
+///
+/// 通过斜杠命令调用时，Agent 超过几秒未回复会先延迟响应，完成后再编辑原消息
+pub struct AskCommand {
+    agent: Arc<ChatBackend>,
+    system_prompt: Option<String>,
+    languages: Option<crate::core::SessionLanguages>,
+}
+
+impl AskCommand {
+    pub fn new(agent: Arc<ChatBackend>) -> Self {
+        Self {
+            agent,
+            system_prompt: None,
+            languages: None,
+        }
+    }
+
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        self.system_prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// 按频道检测回复语言，与 /lang 共享同一份状态喵
+    pub fn with_languages(mut self, languages: crate::core::SessionLanguages) -> Self {
+        self.languages = Some(languages);
+        self
+    }
+}
+
+#[async_trait]
+impl CommandHandler for AskCommand {
+    fn name(&self) -> &str {
+        "ask"
+    }
+
+    fn description(&self) -> &str {
+        "Ask the agent"
+    }
+
+    fn options(&self) -> Vec<CommandOption> {
+        vec![CommandOption::new("prompt", "What to ask").required()]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Agent
+    }
+
+    async fn execute(&self, ctx: CommandContext, args: Option<String>) -> Result<CommandResult> {
+        let prompt = args.as_deref().map(str::trim).unwrap_or_default();
+        if prompt.is_empty() {
+            return Ok(CommandResult {
+                success: false,
+                message: "Usage: /ask <prompt>".to_string(),
+                ephemeral: true,
+            });
+        }
+
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &self.system_prompt {
+            messages.push(Message::system(system_prompt.clone()));
+        }
+        if let Some(languages) = &self.languages {
+            let conversation = format!("discord:{}", ctx.channel_id);
+            languages.observe(&conversation, prompt);
+            if let Some(instruction) = languages.instruction(&conversation) {
+                messages.push(Message::system(instruction));
+            }
+        }
+        messages.push(Message::user(prompt.to_string()));
+        let identity = Identity::new("discord", &ctx.user_id);
+        Ok(match self.agent.complete_as(&identity, messages).await {
+            Ok(reply) => CommandResult {
+                success: true,
+                message: reply,
+                ephemeral: false,
+            },
+            Err(e) => {
+                tracing::warn!("[discord] /ask failed for {}: {}", ctx.user_id, e);
+                CommandResult {
+                    success: false,
+                    message: "⚠️ The agent failed to answer, please try again later".to_string(),
+                    ephemeral: true,
+                }
+            }
+        })
+    }
+}
+
 /// 创建默认命令管理器
 pub fn create_default_commands() -> CommandManager {
     let mut manager = CommandManager::new();
@@ -235,9 +521,3 @@ pub fn create_default_commands() -> CommandManager {
 
     manager
 }
-
-// 修复 tokio_stream 导入
-use tokio_stream as tokio_stream_m;
-
-// 或者直接不使用 tokio_stream，改用标准 Stream trait
-// 这里使用占位符实现
@@ -0,0 +1,558 @@
+/*!
+ * Discord Interactions (Application Commands)
+ *
+ * 功能:
+ * - 启动时把 `CommandManager` 中的命令注册为 Discord 原生斜杠命令（批量覆盖，删除已移除的命令）
+ * - 处理 interaction：Gateway 的 `/discord/interactions` 端点（Ed25519 签名校验）
+ * - 命令几秒内未完成（如 `/ask` 的长时间 Agent 运行）时先返回延迟响应，完成后编辑原消息
+ *
+ * ```toml
+ * [discord]
+ * enabled = true
+ * token = ""                          # 优先读取 DISCORD_BOT_TOKEN 环境变量
+ * allowed_users = ["1157325229287284747"]
+ * require_mention = false
+ * application_id = "1234567890"
+ * public_key = "<Developer Portal 中的 Public Key>"
+ * guild_id = "987654321"              # 可选：只注册到该服务器（立即生效）
+ * ```
+ */
+
+use super::commands::{CommandContext, CommandHandler, CommandManager, CommandResult};
+use crate::security::crypto::decode_hex;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Discord REST API 地址
+pub const DISCORD_API: &str = "https://discord.com/api/v10";
+
+/// 超过该时间未完成的命令先返回延迟响应（Discord 要求 3 秒内响应）
+pub const DEFAULT_DEFER_AFTER: Duration = Duration::from_millis(2000);
+
+/// 消息内容长度上限
+const MESSAGE_LIMIT: usize = 2000;
+/// 仅调用者可见的消息标志
+const EPHEMERAL_FLAG: u64 = 1 << 6;
+/// 命令名称与描述的长度上限
+const NAME_LIMIT: usize = 32;
+const DESCRIPTION_LIMIT: usize = 100;
+
+/// Interaction 类型
+const PING: u8 = 1;
+const APPLICATION_COMMAND: u8 = 2;
+/// 响应类型
+const PONG: u8 = 1;
+const CHANNEL_MESSAGE: u8 = 4;
+const DEFERRED_CHANNEL_MESSAGE: u8 = 5;
+/// Application command 与参数类型
+const CHAT_INPUT: u8 = 1;
+const STRING_OPTION: u8 = 3;
+
+/// Interaction 错误
+#[derive(Debug, thiserror::Error)]
+pub enum InteractionError {
+    /// 公钥不是 32 字节的十六进制
+    #[error("Invalid Discord public key: {0}")]
+    PublicKey(String),
+
+    /// 签名缺失或不匹配
+    #[error("Invalid interaction signature")]
+    Signature,
+
+    /// REST API 调用失败
+    #[error("Discord API error: {0}")]
+    Api(String),
+}
+
+impl From<reqwest::Error> for InteractionError {
+    fn from(e: reqwest::Error) -> Self {
+        InteractionError::Api(e.to_string())
+    }
+}
+
+/// 斜杠命令的参数定义
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApplicationCommandOption {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+/// 斜杠命令定义（`PUT /applications/{id}/commands` 的请求体元素）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApplicationCommand {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub name: String,
+    pub description: String,
+    pub options: Vec<ApplicationCommandOption>,
+}
+
+impl ApplicationCommand {
+    /// 由命令处理器生成（名称转小写，描述截断；必填参数排在可选参数前，这是 Discord 的要求）
+    pub fn from_handler(handler: &dyn CommandHandler) -> Self {
+        let mut options: Vec<_> = handler
+            .options()
+            .into_iter()
+            .map(|option| ApplicationCommandOption {
+                kind: STRING_OPTION,
+                name: command_name(&option.name),
+                description: description(&option.description),
+                required: option.required,
+            })
+            .collect();
+        options.sort_by_key(|option| !option.required);
+        Self {
+            kind: CHAT_INPUT,
+            name: command_name(handler.name()),
+            description: description(handler.description()),
+            options,
+        }
+    }
+}
+
+fn command_name(name: &str) -> String {
+    name.to_lowercase().chars().take(NAME_LIMIT).collect()
+}
+
+fn description(text: &str) -> String {
+    match text.trim() {
+        "" => "-".to_string(),
+        text => truncate(text, DESCRIPTION_LIMIT),
+    }
+}
+
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(limit - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// 收到的 interaction（只解析用到的字段）
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interaction {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub token: String,
+    #[serde(default)]
+    pub data: Option<InteractionData>,
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    /// 服务器内调用
+    #[serde(default)]
+    pub member: Option<InteractionMember>,
+    /// 私信中调用
+    #[serde(default)]
+    pub user: Option<InteractionUser>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InteractionData {
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<InteractionOption>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InteractionOption {
+    pub name: String,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InteractionMember {
+    pub user: InteractionUser,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InteractionUser {
+    pub id: String,
+}
+
+impl Interaction {
+    /// 调用者的用户 ID
+    pub fn user_id(&self) -> Option<&str> {
+        self.member
+            .as_ref()
+            .map(|m| m.user.id.as_str())
+            .or(self.user.as_ref().map(|u| u.id.as_str()))
+    }
+}
+
+/// 不解析消息中的任何提及（Agent 回复里的 @everyone 不会通知任何人）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AllowedMentions {
+    pub parse: Vec<String>,
+}
+
+/// 消息内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionMessage {
+    pub content: String,
+    pub flags: u64,
+    pub allowed_mentions: AllowedMentions,
+}
+
+impl InteractionMessage {
+    fn new(content: &str, ephemeral: bool) -> Self {
+        Self {
+            content: truncate(content, MESSAGE_LIMIT),
+            flags: if ephemeral { EPHEMERAL_FLAG } else { 0 },
+            allowed_mentions: AllowedMentions::default(),
+        }
+    }
+}
+
+/// Interaction 响应
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionResponse {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<InteractionMessage>,
+}
+
+impl InteractionResponse {
+    pub fn pong() -> Self {
+        Self { kind: PONG, data: None }
+    }
+
+    pub fn message(content: &str, ephemeral: bool) -> Self {
+        Self {
+            kind: CHANNEL_MESSAGE,
+            data: Some(InteractionMessage::new(content, ephemeral)),
+        }
+    }
+
+    /// 延迟响应（Discord 显示“正在思考”，稍后编辑原消息）
+    pub fn deferred() -> Self {
+        Self {
+            kind: DEFERRED_CHANNEL_MESSAGE,
+            data: None,
+        }
+    }
+}
+
+/// Discord 斜杠命令的注册与 interaction 处理
+pub struct DiscordInteractions {
+    application_id: String,
+    public_key: Vec<u8>,
+    token: String,
+    guild_id: Option<String>,
+    allowed_users: Vec<String>,
+    commands: Arc<CommandManager>,
+    api_base: String,
+    defer_after: Duration,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for DiscordInteractions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscordInteractions")
+            .field("application_id", &self.application_id)
+            .field("guild_id", &self.guild_id)
+            .field("commands", &self.commands.list_commands())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DiscordInteractions {
+    /// 创建（`public_key` 为 Developer Portal 中的十六进制公钥，`token` 为 Bot Token）
+    pub fn new(
+        application_id: &str,
+        public_key: &str,
+        token: &str,
+        commands: CommandManager,
+    ) -> Result<Self, InteractionError> {
+        let public_key = decode_hex(public_key.trim())
+            .filter(|key| key.len() == 32)
+            .ok_or_else(|| InteractionError::PublicKey(public_key.to_string()))?;
+        Ok(Self {
+            application_id: application_id.to_string(),
+            public_key,
+            token: token.to_string(),
+            guild_id: None,
+            allowed_users: Vec::new(),
+            commands: Arc::new(commands),
+            api_base: DISCORD_API.to_string(),
+            defer_after: DEFAULT_DEFER_AFTER,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+        })
+    }
+
+    /// 只注册到指定服务器（全局命令最长需要一小时才生效）
+    pub fn with_guild(mut self, guild_id: &str) -> Self {
+        self.guild_id = Some(guild_id.to_string());
+        self
+    }
+
+    /// 允许使用斜杠命令的用户（与消息命令共用 `allowed_users`，为空时不响应任何人）
+    pub fn with_allowed_users(mut self, users: Vec<String>) -> Self {
+        self.allowed_users = users;
+        self
+    }
+
+    /// 校验 interaction 签名（`X-Signature-Ed25519` 对 `X-Signature-Timestamp` + 请求体）
+    pub fn verify(&self, signature: &str, timestamp: &str, body: &[u8]) -> Result<(), InteractionError> {
+        let signature = decode_hex(signature.trim()).ok_or(InteractionError::Signature)?;
+        let mut message = timestamp.as_bytes().to_vec();
+        message.extend_from_slice(body);
+        UnparsedPublicKey::new(&ED25519, &self.public_key)
+            .verify(&message, &signature)
+            .map_err(|_| InteractionError::Signature)
+    }
+
+    /// 注册斜杠命令（批量覆盖），返回注册的命令数
+    pub async fn register_commands(&self) -> Result<usize, InteractionError> {
+        let commands = self.commands.application_commands();
+        let url = match &self.guild_id {
+            Some(guild_id) => format!(
+                "{}/applications/{}/guilds/{}/commands",
+                self.api_base, self.application_id, guild_id
+            ),
+            None => format!("{}/applications/{}/commands", self.api_base, self.application_id),
+        };
+        let response = self
+            .client
+            .put(url)
+            .header("Authorization", format!("Bot {}", self.token))
+            .json(&commands)
+            .send()
+            .await?;
+        check_status(response).await?;
+        info!("[discord] Registered {} slash commands", commands.len());
+        Ok(commands.len())
+    }
+
+    /// 处理一个 interaction，返回需要立即回复的响应
+    ///
+    /// 命令超过 `defer_after` 未完成时返回延迟响应，结果在后台完成后编辑原消息
+    pub async fn handle(&self, interaction: Interaction) -> InteractionResponse {
+        match interaction.kind {
+            PING => InteractionResponse::pong(),
+            APPLICATION_COMMAND => self.handle_command(interaction).await,
+            kind => {
+                warn!("[discord] Unsupported interaction type {}", kind);
+                InteractionResponse::message("Unsupported interaction", true)
+            }
+        }
+    }
+
+    async fn handle_command(&self, interaction: Interaction) -> InteractionResponse {
+        let (Some(data), Some(user_id)) = (&interaction.data, interaction.user_id()) else {
+            return InteractionResponse::message("Invalid command", true);
+        };
+        if !self.allowed_users.iter().any(|user| user == user_id) {
+            warn!("[discord] Unauthorized slash command /{} from {}", data.name, user_id);
+            return InteractionResponse::message("🚫 Unauthorized access", true);
+        }
+
+        let ctx = CommandContext {
+            user_id: user_id.to_string(),
+            channel_id: interaction.channel_id.clone().unwrap_or_default(),
+            guild_id: interaction.guild_id.clone(),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        let args = self.arguments(data);
+        let commands = self.commands.clone();
+        let name = data.name.clone();
+        let mut task = tokio::spawn(async move { commands.execute(&name, ctx, args).await });
+        if let Ok(joined) = tokio::time::timeout(self.defer_after, &mut task).await {
+            let (content, ephemeral) = command_reply(&data.name, joined.map_err(|e| e.to_string()));
+            return InteractionResponse::message(&content, ephemeral);
+        }
+
+        // 超时：先延迟响应，完成后编辑原消息
+        info!("[discord] Deferring /{} for {}", data.name, user_id);
+        let url = format!(
+            "{}/webhooks/{}/{}/messages/@original",
+            self.api_base, self.application_id, interaction.token
+        );
+        let client = self.client.clone();
+        let name = data.name.clone();
+        tokio::spawn(async move {
+            let (content, _) = command_reply(&name, task.await.map_err(|e| e.to_string()));
+            let result = match client.patch(url).json(&InteractionMessage::new(&content, false)).send().await {
+                Ok(response) => check_status(response).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                warn!("[discord] Failed to edit deferred /{} response: {}", name, e);
+            }
+        });
+        InteractionResponse::deferred()
+    }
+
+    /// 斜杠命令参数按命令定义的顺序拼接为 `args`
+    fn arguments(&self, data: &InteractionData) -> Option<String> {
+        let values: Vec<String> = self
+            .commands
+            .options(&data.name)
+            .iter()
+            .filter_map(|option| data.options.iter().find(|o| o.name == option.name))
+            .filter_map(|option| match option.value.as_ref()? {
+                Value::String(value) => Some(value.clone()),
+                value => Some(value.to_string()),
+            })
+            .collect();
+        (!values.is_empty()).then(|| values.join(" "))
+    }
+}
+
+/// 命令结果转换为回复内容（错误不暴露详情）
+fn command_reply(name: &str, joined: Result<crate::core::traits::Result<CommandResult>, String>) -> (String, bool) {
+    match joined {
+        Ok(Ok(result)) => (result.message, result.ephemeral),
+        Ok(Err(e)) => {
+            warn!("[discord] /{} failed: {}", name, e);
+            (format!("❌ /{} failed", name), true)
+        }
+        Err(e) => {
+            warn!("[discord] /{} panicked: {}", name, e);
+            (format!("❌ /{} failed", name), true)
+        }
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<(), InteractionError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(InteractionError::Api(format!("{} {}", status, truncate(&body, 200))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::discord::commands::{CommandOption, StatusCommand};
+    use crate::core::traits::Result;
+    use async_trait::async_trait;
+    use axum::{extract::State, routing::any, Json, Router};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    type Requests = Arc<Mutex<Vec<(String, String, Value)>>>;
+
+    struct SlowCommand;
+
+    #[async_trait]
+    impl CommandHandler for SlowCommand {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "Takes a while"
+        }
+
+        fn options(&self) -> Vec<CommandOption> {
+            vec![
+                CommandOption::new("style", "Reply style"),
+                CommandOption::new("topic", "What to think about").required(),
+            ]
+        }
+
+        async fn execute(&self, _ctx: CommandContext, args: Option<String>) -> Result<CommandResult> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(CommandResult {
+                success: true,
+                message: format!("thought about {}", args.unwrap_or_default()),
+                ephemeral: false,
+            })
+        }
+    }
+
+    async fn record(
+        State(requests): State<Requests>,
+        method: axum::http::Method,
+        uri: axum::http::Uri,
+        body: Option<Json<Value>>,
+    ) -> Json<Value> {
+        let body = body.map(|Json(body)| body).unwrap_or_default();
+        requests.lock().unwrap().push((method.to_string(), uri.path().to_string(), body));
+        Json(json!([]))
+    }
+
+    fn command(name: &str, user: &str, options: Value) -> Interaction {
+        serde_json::from_value(json!({
+            "id": "1", "type": 2, "token": "tok", "channel_id": "c1", "guild_id": "g1",
+            "member": { "user": { "id": user } },
+            "data": { "name": name, "options": options },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_register_verify_and_defer_slash_commands() {
+        let requests = Requests::default();
+        let app = Router::new().fallback(any(record)).with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let key = Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap();
+        let public_key: String = key.public_key().as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        let mut commands = CommandManager::new();
+        commands.register(Box::new(StatusCommand));
+        commands.register(Box::new(SlowCommand));
+        let mut interactions = DiscordInteractions::new("app", &public_key, "bot-token", commands)
+            .unwrap()
+            .with_guild("g1")
+            .with_allowed_users(vec!["42".to_string()]);
+        interactions.api_base = api_base;
+        interactions.defer_after = Duration::from_millis(50);
+
+        // 注册：按名称排序，必填参数在前喵
+        assert_eq!(interactions.register_commands().await.unwrap(), 2);
+        let (method, path, body) = requests.lock().unwrap()[0].clone();
+        assert_eq!((method.as_str(), path.as_str()), ("PUT", "/applications/app/guilds/g1/commands"));
+        assert_eq!(body[0]["name"], "slow");
+        assert_eq!(
+            body[0]["options"][0],
+            json!({ "type": 3, "name": "topic", "description": "What to think about", "required": true })
+        );
+        assert_eq!(body[1]["name"], "status");
+
+        let body = br#"{"type":1}"#;
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let signature = hex(key.sign(b"1700000000{\"type\":1}").as_ref());
+        interactions.verify(&signature, "1700000000", body).unwrap();
+        assert!(interactions.verify(&signature, "1700000001", body).is_err());
+        let ping = serde_json::from_slice(br#"{"id":"1","type":1,"token":"tok"}"#).unwrap();
+        assert_eq!(interactions.handle(ping).await, InteractionResponse::pong());
+
+        let response = interactions.handle(command("status", "42", json!([]))).await;
+        assert_eq!(response.kind, CHANNEL_MESSAGE);
+        assert!(response.data.unwrap().content.contains("System Status"));
+        let response = interactions.handle(command("status", "7", json!([]))).await;
+        assert_eq!(response, InteractionResponse::message("🚫 Unauthorized access", true));
+
+        // 慢命令先延迟响应，完成后编辑原消息喵
+        let options = json!([{ "name": "topic", "value": "cats" }, { "name": "style", "value": "brief" }]);
+        let response = interactions.handle(command("slow", "42", options)).await;
+        assert_eq!(response, InteractionResponse::deferred());
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let (method, path, body) = requests.lock().unwrap()[1].clone();
+        assert_eq!((method.as_str(), path.as_str()), ("PATCH", "/webhooks/app/tok/messages/@original"));
+        assert_eq!(body["content"], "thought about brief cats");
+        assert_eq!(body["allowed_mentions"], json!({ "parse": [] }));
+    }
+}
@@ -7,13 +7,16 @@
 
 pub mod bot;
 pub mod commands;
+pub mod interactions;
 
 // 重新导出公共接口
 pub use bot::{DiscordBot, DiscordConfig, DiscordEvent};
 pub use commands::{
-    create_default_commands, CommandContext, CommandHandler, CommandManager, CommandResult,
-    ConfigCommand, HelpCommand, MemoryCommand, StatusCommand,
+    create_default_commands, AskCommand, CommandContext, CommandHandler, CommandManager, CommandResult,
+    ConfigCommand, EscalationDecisionCommand, HelpCommand, LangCommand, MemoryCommand,
+    StatusCommand,
 };
+pub use interactions::{DiscordInteractions, Interaction, InteractionResponse};
 
 // Note: Channel trait implementation for DiscordBot is in bot.rs
 // This avoids duplicate implementation
@@ -0,0 +1,392 @@
+//!
+//! # Email Bot 实现
+//!
+//! ⚠️ SAFETY: 邮件渠道模块，轮询收件箱并通过 SMTP 回复喵
+//!
+//! ## 功能说明
+//! - `poll_once` 取回未读邮件，白名单发件人（`allowed_senders`）的邮件正文交给 Agent，
+//!   回复通过 SMTP 发回并串在原邮件的会话中，回复成功后才标记为已读喵
+//! - 不在白名单、自动发送（退信、自动回复、邮件列表）或来自本邮箱的邮件保持未读，
+//!   本进程内不再取回；没有配置白名单时不回复任何人喵
+//! - 对话与 Agent 的工具调用按发件人角色（`Rbac`，身份 `email:<地址>`）检查权限喵
+//! - 每封邮件独立对话，不保留上下文；适合没有聊天应用的无界面服务器喵
+//!
+//! ⚠️ 发件人地址来自 `From` 头，可以被伪造；请只在启用了 SPF / DKIM / DMARC 检查的邮箱上使用喵
+//!
+//! ```toml
+//! [email]
+//! imap_host = "imap.example.com"
+//! smtp_host = "smtp.example.com"
+//! username = "neko@example.com"
+//! password = "enc:..."          # 为空时读取 EMAIL_PASSWORD 环境变量
+//! allowed_senders = ["owner@example.com", "@ops.example.com"]
+//! poll_interval_secs = 120
+//! ```
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::mail::{extract_address, sender_allowed, IncomingMail, OutgoingMail};
+use super::transport::{EmailError, FetchedMail, MailServer, MailTransport};
+use crate::auth::{Identity, Permission, Rbac};
+use crate::core::traits::{ChannelEvent, Message};
+use crate::gateway::ChatBackend;
+
+/// Agent 出错时回复给发件人的提示（不暴露错误详情）喵
+const FAILURE_NOTICE: &str = "⚠️ 处理邮件时出错了喵，请稍后再试";
+
+fn default_imap_port() -> u16 {
+    993
+}
+fn default_smtp_port() -> u16 {
+    465
+}
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+fn default_poll_interval_secs() -> u64 {
+    60
+}
+fn default_max_body_chars() -> usize {
+    8_000
+}
+
+/// 邮件渠道配置喵
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub imap_host: String,
+    #[serde(default = "default_imap_port")]
+    pub imap_port: u16,
+    pub smtp_host: String,
+    /// 465 为隐式 TLS；587 需同时设置 `smtp_starttls = true`
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub smtp_starttls: bool,
+    /// IMAP / SMTP 登录名
+    pub username: String,
+    /// 密码（支持 `enc:` 加密值；为空时读取 EMAIL_PASSWORD 环境变量）
+    #[serde(default)]
+    pub password: String,
+    /// 回复的发件人（默认为 `username`），可含显示名
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    /// 允许的发件人：完整地址或 `@domain`（为空时不回复任何人）
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// 交给 Agent 的正文最大字符数
+    #[serde(default = "default_max_body_chars")]
+    pub max_body_chars: usize,
+}
+
+impl std::fmt::Debug for EmailConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailConfig")
+            .field("imap", &format!("{}:{}", self.imap_host, self.imap_port))
+            .field("smtp", &format!("{}:{}", self.smtp_host, self.smtp_port))
+            .field("smtp_starttls", &self.smtp_starttls)
+            .field("username", &self.username)
+            .field("from", &self.from)
+            .field("mailbox", &self.mailbox)
+            .field("allowed_senders", &self.allowed_senders)
+            .field("poll_interval_secs", &self.poll_interval_secs)
+            .field("max_body_chars", &self.max_body_chars)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EmailConfig {
+    /// 回复的发件人喵
+    pub fn sender(&self) -> String {
+        self.from.clone().unwrap_or_else(|| self.username.clone())
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs.max(10))
+    }
+
+    /// 🔒 SAFETY: 网络收发实现喵（`password` 为已解密的密码）
+    pub fn server(&self, password: String) -> MailServer {
+        MailServer {
+            imap_host: self.imap_host.clone(),
+            imap_port: self.imap_port,
+            smtp_host: self.smtp_host.clone(),
+            smtp_port: self.smtp_port,
+            smtp_starttls: self.smtp_starttls,
+            username: self.username.clone(),
+            password,
+            mailbox: self.mailbox.clone(),
+        }
+    }
+}
+
+/// 一次轮询的结果喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PollReport {
+    pub replied: usize,
+    /// 不回复的邮件（白名单外、自动发送、无法解析）
+    pub ignored: usize,
+    /// 回复发送失败（保持未读，下次轮询重试）
+    pub failed: usize,
+}
+
+/// 🔒 SAFETY: 邮件渠道喵
+pub struct EmailBot {
+    config: EmailConfig,
+    transport: Arc<dyn MailTransport>,
+    agent: Option<Arc<ChatBackend>>,
+    rbac: Arc<Rbac>,
+    system_prompt: Option<String>,
+    /// 不回复的邮件 UID（本进程内不再取回）
+    ignored: Mutex<HashSet<u32>>,
+}
+
+impl std::fmt::Debug for EmailBot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailBot")
+            .field("config", &self.config)
+            .field("agent", &self.agent.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl EmailBot {
+    pub fn new(config: EmailConfig, transport: Arc<dyn MailTransport>) -> Self {
+        Self {
+            config,
+            transport,
+            agent: None,
+            rbac: Arc::new(Rbac::default()),
+            system_prompt: None,
+            ignored: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 🔒 SAFETY: 邮件正文交给 Agent 喵
+    pub fn with_agent(mut self, agent: Arc<ChatBackend>) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// 🔒 SAFETY: 发件人按角色检查对话权限喵
+    pub fn with_rbac(mut self, rbac: Arc<Rbac>) -> Self {
+        self.rbac = rbac;
+        self
+    }
+
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        self.system_prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// 🔒 SAFETY: 轮询一次收件箱喵
+    pub async fn poll_once(&self) -> Result<PollReport, EmailError> {
+        let skip = self.ignored.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let fetched = self.transport.fetch_unseen(&skip).await?;
+        let mut report = PollReport::default();
+        let mut seen = Vec::new();
+        for FetchedMail { uid, raw } in fetched {
+            let mail = match IncomingMail::parse(&raw) {
+                Ok(mail) => mail,
+                Err(e) => {
+                    warn!("[email] Ignoring message {}: {}", uid, e);
+                    self.ignore(uid, &mut report);
+                    continue;
+                }
+            };
+            if let Err(reason) = self.admit(&mail) {
+                info!("[email] Ignoring message {} from {}: {}", uid, mail.from, reason);
+                self.ignore(uid, &mut report);
+                continue;
+            }
+            let reply = self.ask_agent(&mail).await;
+            match self.transport.send(&OutgoingMail::reply(&self.config.sender(), &mail, &reply)).await {
+                Ok(()) => {
+                    info!("[email] Replied to {} ({})", mail.from, mail.subject);
+                    seen.push(uid);
+                    report.replied += 1;
+                }
+                Err(e) => {
+                    warn!("[email] Failed to reply to {}: {}", mail.from, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        self.transport.mark_seen(&seen).await?;
+        Ok(report)
+    }
+
+    fn ignore(&self, uid: u32, report: &mut PollReport) {
+        self.ignored.lock().unwrap_or_else(|e| e.into_inner()).insert(uid);
+        report.ignored += 1;
+    }
+
+    /// 是否回复这封邮件喵（返回不回复的原因）
+    fn admit(&self, mail: &IncomingMail) -> Result<(), String> {
+        if mail.auto_generated {
+            return Err("auto-generated message".to_string());
+        }
+        let own = extract_address(&self.config.sender()).unwrap_or_default();
+        if mail.from == own || mail.from == self.config.username.to_ascii_lowercase() {
+            return Err("sent by this mailbox".to_string());
+        }
+        if !sender_allowed(&self.config.allowed_senders, &mail.from) {
+            return Err("sender is not in allowed_senders".to_string());
+        }
+        self.rbac
+            .check(&Identity::new("email", &mail.from), Permission::Chat)
+            .map_err(|e| e.to_string())?;
+        match mail.body.trim().is_empty() && mail.subject.trim().is_empty() {
+            true => Err("empty message".to_string()),
+            false => Ok(()),
+        }
+    }
+
+    /// 把邮件交给 Agent 喵（出错时回复通用提示）
+    async fn ask_agent(&self, mail: &IncomingMail) -> String {
+        let Some(agent) = &self.agent else {
+            return "🤖 Agent 未启用喵".to_string();
+        };
+        let mut messages = Vec::new();
+        if let Some(prompt) = &self.system_prompt {
+            messages.push(Message::system(prompt.clone()));
+        }
+        messages.push(Message::user(mail.prompt(self.config.max_body_chars)));
+        match agent.complete_as(&Identity::new("email", &mail.from), messages).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!("[email] Agent failed for {}: {}", mail.from, e);
+                FAILURE_NOTICE.to_string()
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::core::traits::Channel for EmailBot {
+    async fn send(&self, content: &str, target: Option<&str>) -> crate::core::traits::Result<()> {
+        let to = target
+            .and_then(extract_address)
+            .ok_or("Target email address required")?;
+        let mail = OutgoingMail::new(&self.config.sender(), &to, "nekoclaw", content);
+        self.transport.send(&mail).await?;
+        Ok(())
+    }
+
+    async fn receive(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = crate::core::traits::Result<ChannelEvent>> + Send>> {
+        // 收件由 `poll_once` 定时轮询负责，这里不提供事件流喵
+        Box::pin(futures::stream::empty())
+    }
+
+    fn name(&self) -> &str {
+        &self.config.username
+    }
+
+    fn channel_type(&self) -> &str {
+        "email"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{RbacConfig, Role};
+    use crate::gateway::testing::ScriptedProvider;
+
+    #[derive(Default)]
+    struct FakeTransport {
+        inbox: Mutex<Vec<FetchedMail>>,
+        seen: Mutex<Vec<u32>>,
+        sent: Mutex<Vec<OutgoingMail>>,
+    }
+
+    #[async_trait::async_trait]
+    impl MailTransport for FakeTransport {
+        async fn fetch_unseen(&self, skip: &HashSet<u32>) -> Result<Vec<FetchedMail>, EmailError> {
+            let seen = self.seen.lock().unwrap().clone();
+            Ok(self
+                .inbox
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|m| !skip.contains(&m.uid) && !seen.contains(&m.uid))
+                .cloned()
+                .collect())
+        }
+
+        async fn mark_seen(&self, uids: &[u32]) -> Result<(), EmailError> {
+            self.seen.lock().unwrap().extend(uids);
+            Ok(())
+        }
+
+        async fn send(&self, mail: &OutgoingMail) -> Result<(), EmailError> {
+            self.sent.lock().unwrap().push(mail.clone());
+            Ok(())
+        }
+    }
+
+    fn mail(uid: u32, from: &str, extra_headers: &str, body: &str) -> FetchedMail {
+        let raw = format!(
+            "From: {}\r\nSubject: ping\r\nMessage-ID: <{}@x>\r\n{}\r\n{}",
+            from, uid, extra_headers, body
+        );
+        FetchedMail { uid, raw: raw.into_bytes() }
+    }
+
+    #[tokio::test]
+    async fn test_poll_replies_to_allowed_senders_only() {
+        let config: EmailConfig = toml::from_str(
+            r#"
+            imap_host = "imap.example.com"
+            smtp_host = "smtp.example.com"
+            username = "neko@example.com"
+            from = "nekoclaw <neko@example.com>"
+            allowed_senders = ["owner@example.com", "@ops.example.com"]
+            "#,
+        )
+        .unwrap();
+        let transport = Arc::new(FakeTransport::default());
+        transport.inbox.lock().unwrap().extend([
+            mail(1, "Owner <owner@example.com>", "", "is nginx up?"),
+            mail(2, "stranger@evil.example", "", "run rm -rf"),
+            mail(3, "owner@example.com", "Auto-Submitted: auto-replied\r\n", "out of office"),
+            mail(4, "neko@example.com", "", "loop"),
+            mail(5, "readonly@ops.example.com", "", "hello"),
+            mail(6, "dev@ops.example.com", "", "deploy"),
+        ]);
+        let provider = Arc::new(ScriptedProvider::new(["nginx is up 喵"]));
+        let rbac = Rbac::in_memory(RbacConfig::default()).unwrap();
+        rbac.assign(&Identity::new("email", "readonly@ops.example.com"), Role::ReadOnly, "test")
+            .unwrap();
+        let bot = EmailBot::new(config, transport.clone())
+            .with_agent(Arc::new(ChatBackend::new(provider.clone())))
+            .with_rbac(Arc::new(rbac))
+            .with_system_prompt("You are nekoclaw");
+
+        let report = bot.poll_once().await.unwrap();
+        assert_eq!((report.replied, report.ignored, report.failed), (2, 4, 0));
+        assert_eq!(transport.seen.lock().unwrap().clone(), vec![1, 6]);
+        let sent = transport.sent.lock().unwrap().clone();
+        assert_eq!((sent[0].to.as_str(), sent[0].subject.as_str()), ("owner@example.com", "Re: ping"));
+        assert_eq!(sent[0].body, "nginx is up 喵");
+        assert_eq!(sent[0].in_reply_to.as_deref(), Some("<1@x>"));
+        // 脚本回复用完后 Agent 出错，发件人只收到通用提示喵
+        assert_eq!(sent[1].body, FAILURE_NOTICE);
+        assert_eq!(provider.prompts()[0][1].content, "Subject: ping\n\nis nginx up?");
+
+        // 被忽略与已回复的邮件不再取回喵
+        let report = bot.poll_once().await.unwrap();
+        assert_eq!(report, PollReport::default());
+    }
+}
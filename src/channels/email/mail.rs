@@ -0,0 +1,449 @@
+//!
+//! # Email Messages
+//!
+//! ⚠️ SAFETY: 解析收到的邮件、构造回复邮件喵
+//!
+//! ## 功能说明
+//! - 解析 RFC 5322 邮件头（含折叠行与 RFC 2047 编码的主题）喵
+//! - 正文取第一个 `text/plain` 部分（没有时取 `text/html` 并去掉标签），
+//!   解码 base64 / quoted-printable，去掉引用的历史邮件喵
+//! - `Auto-Submitted`、`Precedence: bulk` 等自动邮件标记为自动发送，不回复，避免邮件循环喵
+//! - 回复邮件带 `In-Reply-To` / `References`，正文 UTF-8 + base64 喵
+
+use base64::Engine;
+use chrono::Utc;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 邮件解析错误喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailParseError(pub String);
+
+impl std::fmt::Display for MailParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid email: {}", self.0)
+    }
+}
+
+/// 🔒 SAFETY: 收到的邮件喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncomingMail {
+    /// 发件人地址（小写，不含显示名）
+    pub from: String,
+    pub subject: String,
+    pub message_id: Option<String>,
+    pub references: Vec<String>,
+    /// 纯文本正文（已去掉引用部分）
+    pub body: String,
+    /// 自动发送的邮件（退信、自动回复、邮件列表）
+    pub auto_generated: bool,
+}
+
+impl IncomingMail {
+    /// 🔒 SAFETY: 解析原始邮件喵
+    pub fn parse(raw: &[u8]) -> Result<Self, MailParseError> {
+        let (headers, body) = split_message(raw);
+        let headers = parse_headers(&headers);
+        let header = |name: &str| header_value(&headers, name);
+
+        let from = header("from")
+            .and_then(extract_address)
+            .ok_or_else(|| MailParseError("missing From address".to_string()))?;
+        let auto_submitted = header("auto-submitted").is_some_and(|v| !v.trim().eq_ignore_ascii_case("no"));
+        let bulk = header("precedence")
+            .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "bulk" | "junk" | "list"));
+        let mailer_daemon = from.starts_with("mailer-daemon@") || from.starts_with("postmaster@");
+
+        Ok(Self {
+            subject: header("subject").map(decode_encoded_words).unwrap_or_default(),
+            message_id: header("message-id").map(|v| v.trim().to_string()).filter(|v| !v.is_empty()),
+            references: header("references")
+                .map(|v| v.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            body: strip_quoted(&extract_text(&headers, &body)),
+            auto_generated: auto_submitted || bulk || mailer_daemon || header("list-id").is_some(),
+            from,
+        })
+    }
+
+    /// 交给 Agent 的内容喵
+    pub fn prompt(&self, max_chars: usize) -> String {
+        let body: String = self.body.chars().take(max_chars).collect();
+        match self.subject.trim() {
+            "" => body,
+            subject => format!("Subject: {}\n\n{}", subject, body),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 待发送的邮件喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingMail {
+    /// 发件人（可含显示名，如 `nekoclaw <neko@example.com>`）
+    pub from: String,
+    /// 收件人地址
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub in_reply_to: Option<String>,
+    pub references: Vec<String>,
+}
+
+impl OutgoingMail {
+    pub fn new(from: &str, to: &str, subject: &str, body: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            in_reply_to: None,
+            references: Vec::new(),
+        }
+    }
+
+    /// 🔒 SAFETY: 回复一封邮件喵（主题加 `Re:`，串到原邮件的会话中）
+    pub fn reply(from: &str, to: &IncomingMail, body: &str) -> Self {
+        let subject = match to.subject.trim() {
+            "" => "Re: nekoclaw".to_string(),
+            s if s.to_ascii_lowercase().starts_with("re:") => s.to_string(),
+            s => format!("Re: {}", s),
+        };
+        let mut references = to.references.clone();
+        references.extend(to.message_id.clone());
+        Self {
+            in_reply_to: to.message_id.clone(),
+            references,
+            ..Self::new(from, &to.from, &subject, body)
+        }
+    }
+
+    /// 发件人地址（去掉显示名）喵
+    pub fn sender_address(&self) -> String {
+        extract_address(&self.from).unwrap_or_else(|| self.from.clone())
+    }
+
+    /// 🔒 SAFETY: 序列化为 RFC 5322 邮件喵（CRLF 换行，头部值去掉换行防止头注入）
+    pub fn to_rfc5322(&self) -> String {
+        let clean = |s: &str| s.replace(['\r', '\n'], " ");
+        let from_address = self.sender_address();
+        let domain = from_address.rsplit_once('@').map(|(_, d)| d).unwrap_or("localhost");
+        let mut out = String::new();
+        let mut header = |name: &str, value: &str| {
+            out.push_str(name);
+            out.push_str(": ");
+            out.push_str(value);
+            out.push_str("\r\n");
+        };
+        header("From", &clean(&self.from));
+        header("To", &clean(&self.to));
+        header("Subject", &encode_header(&clean(&self.subject)));
+        header("Date", &Utc::now().to_rfc2822());
+        header("Message-ID", &format!("<{}@{}>", uuid::Uuid::new_v4(), domain));
+        if let Some(id) = &self.in_reply_to {
+            header("In-Reply-To", &clean(id));
+        }
+        if !self.references.is_empty() {
+            header("References", &clean(&self.references.join(" ")));
+        }
+        header("Auto-Submitted", "auto-replied");
+        header("MIME-Version", "1.0");
+        header("Content-Type", "text/plain; charset=utf-8");
+        header("Content-Transfer-Encoding", "base64");
+        out.push_str("\r\n");
+        let encoded = base64::engine::general_purpose::STANDARD.encode(self.body.as_bytes());
+        for line in encoded.as_bytes().chunks(76) {
+            out.push_str(std::str::from_utf8(line).unwrap_or_default());
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+/// 发件人是否在白名单中喵（完整地址，或 `@domain` 匹配整个域）
+pub fn sender_allowed(allowed: &[String], address: &str) -> bool {
+    let address = address.to_ascii_lowercase();
+    allowed.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        match entry.strip_prefix('@') {
+            Some(domain) => address.rsplit_once('@').is_some_and(|(_, d)| d == domain),
+            None => entry == address,
+        }
+    })
+}
+
+/// 从 `Name <addr>` 或裸地址中取出地址喵
+pub fn extract_address(value: &str) -> Option<String> {
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value.trim(),
+    };
+    let address = address.trim().to_ascii_lowercase();
+    (address.contains('@') && !address.contains(char::is_whitespace)).then_some(address)
+}
+
+/// 拆分头部与正文喵（以第一个空行为界，兼容 CRLF 与 LF）
+fn split_message(raw: &[u8]) -> (String, Vec<u8>) {
+    let find = |separator: &[u8]| {
+        raw.windows(separator.len())
+            .position(|w| w == separator)
+            .map(|pos| (pos, pos + separator.len()))
+    };
+    match [find(b"\r\n\r\n"), find(b"\n\n")].into_iter().flatten().min() {
+        Some((end, body)) => (String::from_utf8_lossy(&raw[..end]).into_owned(), raw[body..].to_vec()),
+        None => (String::from_utf8_lossy(raw).into_owned(), Vec::new()),
+    }
+}
+
+/// 解析头部（展开折叠行，名称小写）喵
+fn parse_headers(block: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// 头部参数喵（如 Content-Type 的 `boundary`）
+fn header_param(value: &str, param: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(param)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 取出纯文本正文喵（multipart 中优先 text/plain）
+fn extract_text(headers: &[(String, String)], body: &[u8]) -> String {
+    let content_type = header_value(headers, "content-type").unwrap_or("text/plain");
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    if mime.starts_with("multipart/") {
+        let Some(boundary) = header_param(content_type, "boundary") else {
+            return String::new();
+        };
+        let parts = split_multipart(body, &boundary);
+        let parsed: Vec<_> = parts
+            .iter()
+            .map(|part| {
+                let (headers, body) = split_message(part);
+                (parse_headers(&headers), body)
+            })
+            .collect();
+        let kind = |headers: &[(String, String)]| {
+            header_value(headers, "content-type")
+                .unwrap_or("text/plain")
+                .to_ascii_lowercase()
+        };
+        // 先找 text/plain（含嵌套的 multipart/alternative），再退回 text/html 喵
+        for wanted in ["text/plain", "multipart/", "text/html"] {
+            for (headers, body) in &parsed {
+                if kind(headers).starts_with(wanted) {
+                    let text = extract_text(headers, body);
+                    if !text.trim().is_empty() {
+                        return text;
+                    }
+                }
+            }
+        }
+        return String::new();
+    }
+    if !mime.starts_with("text/") {
+        return String::new();
+    }
+    let decoded = match header_value(headers, "content-transfer-encoding").map(|v| v.trim().to_ascii_lowercase()) {
+        Some(e) if e == "base64" => {
+            let compact: String = String::from_utf8_lossy(body).split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD.decode(compact).unwrap_or_default()
+        }
+        Some(e) if e == "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+    let text = String::from_utf8_lossy(&decoded).replace("\r\n", "\n");
+    match mime.as_str() {
+        "text/html" => strip_html(&text),
+        _ => text,
+    }
+}
+
+fn split_multipart(body: &[u8], boundary: &str) -> Vec<Vec<u8>> {
+    let body = String::from_utf8_lossy(body);
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut current: Option<String> = None;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == delimiter || trimmed == format!("{}--", delimiter) {
+            parts.extend(current.take().map(String::into_bytes));
+            if trimmed == delimiter {
+                current = Some(String::new());
+            }
+            continue;
+        }
+        if let Some(part) = current.as_mut() {
+            part.push_str(line);
+        }
+    }
+    parts
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        match body[i] {
+            b'=' if body[i + 1..].starts_with(b"\r\n") => i += 3,
+            b'=' if body[i + 1..].starts_with(b"\n") => i += 2,
+            b'=' if i + 2 < body.len() => {
+                match u8::from_str_radix(&String::from_utf8_lossy(&body[i + 1..i + 3]), 16) {
+                    Ok(byte) => {
+                        out.push(byte);
+                        i += 3;
+                    }
+                    Err(_) => {
+                        out.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// 解码 RFC 2047 编码词喵（`=?UTF-8?B?...?=` / `=?UTF-8?Q?...?=`）
+fn decode_encoded_words(value: &str) -> String {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    static GAP: OnceLock<Regex> = OnceLock::new();
+    let re = WORD.get_or_init(|| Regex::new(r"=\?([^?]+)\?([bBqQ])\?([^?]*)\?=").unwrap());
+    // 相邻编码词之间的空白不属于内容喵
+    let value = GAP.get_or_init(|| Regex::new(r"\?=\s+=\?").unwrap()).replace_all(value, "?==?");
+    re.replace_all(&value, |caps: &regex::Captures| {
+        let bytes = match &caps[2] {
+            "b" | "B" => base64::engine::general_purpose::STANDARD.decode(&caps[3]).unwrap_or_default(),
+            _ => decode_quoted_printable(caps[3].replace('_', " ").as_bytes()),
+        };
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+    .into_owned()
+}
+
+/// 非 ASCII 头部值编码为 RFC 2047 喵
+fn encode_header(value: &str) -> String {
+    match value.is_ascii() {
+        true => value.to_string(),
+        false => format!("=?UTF-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(value)),
+    }
+}
+
+fn strip_html(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let re = TAG.get_or_init(|| {
+        Regex::new(r"(?is)<(script|style)[^>]*>.*?</(script|style)>|<br\s*/?>|</p>|<[^>]+>").unwrap()
+    });
+    let text = re.replace_all(html, |caps: &regex::Captures| match caps[0].to_ascii_lowercase() {
+        tag if tag.starts_with("<br") || tag == "</p>" => "\n".to_string(),
+        _ => String::new(),
+    });
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// 去掉回复中引用的历史邮件喵（`>` 开头的行与 “On ... wrote:” 之后的内容）
+fn strip_quoted(body: &str) -> String {
+    static ATTRIBUTION: OnceLock<Regex> = OnceLock::new();
+    let re = ATTRIBUTION.get_or_init(|| Regex::new(r"^(On .+wrote:|.+写道：?)$").unwrap());
+    let mut lines = Vec::new();
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if re.is_match(trimmed) || trimmed == "-----Original Message-----" {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            lines.push(line.trim_end());
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multipart_and_build_reply() {
+        let raw = concat!(
+            "From: \"Owner\" <Owner@Example.com>\r\n",
+            "To: neko@example.com\r\n",
+            "Subject: =?UTF-8?B?5Za15Za1?= status\r\n",
+            "Message-ID: <abc@example.com>\r\n",
+            "Content-Type: multipart/alternative;\r\n",
+            "  boundary=\"XYZ\"\r\n",
+            "\r\n",
+            "--XYZ\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "\r\n",
+            "Is nginx up? caf=C3=A9 =\r\n",
+            "ok\r\n",
+            "\r\n",
+            "On Mon, Owner wrote:\r\n",
+            "> old message\r\n",
+            "--XYZ\r\n",
+            "Content-Type: text/html\r\n",
+            "\r\n",
+            "<p>Is nginx up?</p>\r\n",
+            "--XYZ--\r\n",
+        );
+        let mail = IncomingMail::parse(raw.as_bytes()).unwrap();
+        assert_eq!(mail.from, "owner@example.com");
+        assert_eq!(mail.subject, "喵喵 status");
+        assert_eq!(mail.body, "Is nginx up? café ok");
+        assert!(!mail.auto_generated);
+        assert_eq!(mail.prompt(8), "Subject: 喵喵 status\n\nIs nginx");
+
+        let bounce = "From: MAILER-DAEMON@example.com\r\nSubject: failure\r\n\r\nundeliverable";
+        assert!(IncomingMail::parse(bounce.as_bytes()).unwrap().auto_generated);
+        let auto = "From: a@b.c\nAuto-Submitted: auto-replied\n\nout of office";
+        assert!(IncomingMail::parse(auto.as_bytes()).unwrap().auto_generated);
+        assert!(IncomingMail::parse(b"Subject: x\r\n\r\nno sender").is_err());
+
+        let allowed = vec!["owner@example.com".to_string(), "@corp.example".to_string()];
+        assert!(sender_allowed(&allowed, "OWNER@example.com"));
+        assert!(sender_allowed(&allowed, "ops@corp.example"));
+        assert!(!sender_allowed(&allowed, "ops@evilcorp.example"));
+        assert!(!sender_allowed(&allowed, "other@example.com"));
+
+        let reply = OutgoingMail::reply("nekoclaw <neko@example.com>", &mail, "nginx is up 喵");
+        assert_eq!((reply.to.as_str(), reply.subject.as_str()), ("owner@example.com", "Re: 喵喵 status"));
+        assert_eq!(reply.references, vec!["<abc@example.com>"]);
+        let wire = reply.to_rfc5322();
+        assert!(wire.contains("In-Reply-To: <abc@example.com>\r\n"));
+        assert!(wire.contains("Subject: =?UTF-8?B?"));
+        assert!(wire.contains("Auto-Submitted: auto-replied\r\n"));
+
+        // 回复邮件能被自己的解析器读回喵
+        let parsed = IncomingMail::parse(wire.as_bytes()).unwrap();
+        assert_eq!((parsed.from.as_str(), parsed.body.as_str()), ("neko@example.com", "nginx is up 喵"));
+        assert_eq!(parsed.subject, "Re: 喵喵 status");
+    }
+}
@@ -0,0 +1,15 @@
+//!
+//! # Email 渠道
+//!
+//! ⚠️ SAFETY: 邮件渠道模块（IMAP 收件 + SMTP 回复）喵
+//!
+//! ## 功能说明
+//! - `mail`: 邮件解析与回复构造
+//! - `transport`: IMAP / SMTP over TLS 收发
+//! - `bot`: 轮询收件箱、白名单检查、交给 Agent 并回复
+
+pub mod bot;
+pub mod mail;
+pub mod transport;
+
+pub use bot::{EmailBot, EmailConfig, PollReport};
@@ -0,0 +1,470 @@
+//!
+//! # Email Transport
+//!
+//! ⚠️ SAFETY: 最小化的 IMAP / SMTP 客户端喵（只实现邮件渠道用到的命令）
+//!
+//! ## 功能说明
+//! - IMAP 使用隐式 TLS（993）：`LOGIN` → `SELECT` → `UID SEARCH UNSEEN` → `UID FETCH BODY.PEEK[]`，
+//!   处理完成后 `UID STORE +FLAGS (\Seen)`；读取时不改变已读状态喵
+//! - SMTP 使用隐式 TLS（465）或 STARTTLS（587）：`EHLO` → `AUTH PLAIN` → `MAIL FROM` / `RCPT TO` / `DATA` 喵
+//! - 协议会话对任意 `AsyncRead + AsyncWrite` 通用，测试时用内存管道代替网络喵
+//! - 密码不会出现在日志或错误信息中喵
+
+use async_trait::async_trait;
+use base64::Engine;
+use std::collections::HashSet;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector, TlsStream};
+
+use super::mail::OutgoingMail;
+
+/// 单次网络操作超时喵
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+/// 单封邮件的最大字节数（更大的邮件被丢弃，取回的内容为空）喵
+pub const MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+/// 每次轮询最多取回的邮件数喵
+pub const MAX_FETCH_PER_POLL: usize = 20;
+
+/// 邮件渠道错误喵
+#[derive(Debug, Error)]
+pub enum EmailError {
+    /// 连接或 TLS 失败喵
+    #[error("Email connection failed: {0}")]
+    Connect(String),
+
+    /// 服务器拒绝或响应无法解析喵
+    #[error("Email protocol error: {0}")]
+    Protocol(String),
+
+    /// 登录失败喵
+    #[error("Email authentication failed for {0}")]
+    Auth(String),
+}
+
+impl From<std::io::Error> for EmailError {
+    fn from(e: std::io::Error) -> Self {
+        EmailError::Connect(e.to_string())
+    }
+}
+
+/// 取回的一封未读邮件喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchedMail {
+    pub uid: u32,
+    pub raw: Vec<u8>,
+}
+
+/// 🔒 SAFETY: 收发邮件的抽象喵（网络实现为 `MailServer`，测试时替换）
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    /// 取回未读邮件（跳过 `skip` 中的 UID），不改变已读状态
+    async fn fetch_unseen(&self, skip: &HashSet<u32>) -> Result<Vec<FetchedMail>, EmailError>;
+    /// 标记为已读
+    async fn mark_seen(&self, uids: &[u32]) -> Result<(), EmailError>;
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), EmailError>;
+}
+
+/// 邮件服务器连接参数喵
+#[derive(Clone)]
+pub struct MailServer {
+    pub imap_host: String,
+    pub imap_port: u16,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    /// SMTP 先明文连接再 STARTTLS（587），否则隐式 TLS（465）
+    pub smtp_starttls: bool,
+    pub username: String,
+    pub password: String,
+    pub mailbox: String,
+}
+
+impl std::fmt::Debug for MailServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MailServer")
+            .field("imap", &format!("{}:{}", self.imap_host, self.imap_port))
+            .field("smtp", &format!("{}:{}", self.smtp_host, self.smtp_port))
+            .field("smtp_starttls", &self.smtp_starttls)
+            .field("username", &self.username)
+            .field("mailbox", &self.mailbox)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MailServer {
+    async fn tls<S: AsyncRead + AsyncWrite + Unpin>(host: &str, stream: S) -> Result<TlsStream<S>, EmailError> {
+        let connector = native_tls::TlsConnector::new().map_err(|e| EmailError::Connect(e.to_string()))?;
+        timeout(TlsConnector::from(connector).connect(host, stream))
+            .await?
+            .map_err(|e| EmailError::Connect(format!("TLS handshake with {} failed: {}", host, e)))
+    }
+
+    async fn tcp(host: &str, port: u16) -> Result<TcpStream, EmailError> {
+        timeout(TcpStream::connect((host, port)))
+            .await?
+            .map_err(|e| EmailError::Connect(format!("{}:{}: {}", host, port, e)))
+    }
+
+    async fn imap(&self) -> Result<ImapSession<TlsStream<TcpStream>>, EmailError> {
+        let stream = Self::tls(&self.imap_host, Self::tcp(&self.imap_host, self.imap_port).await?).await?;
+        let mut session = ImapSession::connect(stream).await?;
+        session.login(&self.username, &self.password).await?;
+        session.select(&self.mailbox).await?;
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl MailTransport for MailServer {
+    async fn fetch_unseen(&self, skip: &HashSet<u32>) -> Result<Vec<FetchedMail>, EmailError> {
+        let mut session = self.imap().await?;
+        let uids: Vec<u32> = session
+            .search_unseen()
+            .await?
+            .into_iter()
+            .filter(|uid| !skip.contains(uid))
+            .take(MAX_FETCH_PER_POLL)
+            .collect();
+        let mut mails = Vec::new();
+        for uid in uids {
+            if let Some(raw) = session.fetch(uid).await? {
+                mails.push(FetchedMail { uid, raw });
+            }
+        }
+        session.logout().await;
+        Ok(mails)
+    }
+
+    async fn mark_seen(&self, uids: &[u32]) -> Result<(), EmailError> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let mut session = self.imap().await?;
+        session.mark_seen(uids).await?;
+        session.logout().await;
+        Ok(())
+    }
+
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), EmailError> {
+        let tcp = Self::tcp(&self.smtp_host, self.smtp_port).await?;
+        if self.smtp_starttls {
+            let mut plain = SmtpSession::connect(tcp).await?;
+            plain.starttls().await?;
+            let stream = Self::tls(&self.smtp_host, plain.into_inner()).await?;
+            let mut session = SmtpSession::resume(stream).await?;
+            return session.deliver(&self.username, &self.password, mail).await;
+        }
+        let stream = Self::tls(&self.smtp_host, tcp).await?;
+        let mut session = SmtpSession::connect(stream).await?;
+        session.deliver(&self.username, &self.password, mail).await
+    }
+}
+
+async fn timeout<F: std::future::Future>(future: F) -> Result<F::Output, EmailError> {
+    tokio::time::timeout(IO_TIMEOUT, future)
+        .await
+        .map_err(|_| EmailError::Connect("timed out".to_string()))
+}
+
+/// IMAP 引号字符串喵
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 🔒 SAFETY: IMAP 会话喵
+pub struct ImapSession<S> {
+    stream: BufReader<S>,
+    tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ImapSession<S> {
+    /// 读取服务器问候喵
+    pub async fn connect(stream: S) -> Result<Self, EmailError> {
+        let mut session = Self {
+            stream: BufReader::new(stream),
+            tag: 0,
+        };
+        let greeting = session.read_line().await?;
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            return Err(EmailError::Protocol(format!("unexpected IMAP greeting: {}", greeting.trim())));
+        }
+        Ok(session)
+    }
+
+    async fn read_line(&mut self) -> Result<String, EmailError> {
+        let mut line = String::new();
+        if timeout(self.stream.read_line(&mut line)).await?? == 0 {
+            return Err(EmailError::Protocol("IMAP server closed the connection".to_string()));
+        }
+        Ok(line)
+    }
+
+    /// 发送带标签的命令，返回未标记的响应喵（字面量 `{n}` 的内容按原样附在所在行之后）
+    async fn command(&mut self, command: &str) -> Result<Vec<(String, Vec<u8>)>, EmailError> {
+        self.tag += 1;
+        let tag = format!("a{}", self.tag);
+        let stream = self.stream.get_mut();
+        timeout(stream.write_all(format!("{} {}\r\n", tag, command).as_bytes())).await??;
+        timeout(stream.flush()).await??;
+
+        let mut responses = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(&format!("{} ", tag)) {
+                return match status.starts_with("OK") {
+                    true => Ok(responses),
+                    false => Err(EmailError::Protocol(status.trim().to_string())),
+                };
+            }
+            // 行尾为 `{n}` 时紧跟 n 字节字面量，之后该行继续；只保留首行判断响应类型喵
+            let mut literal = Vec::new();
+            let mut tail = line.clone();
+            while let Some(size) = literal_size(&tail) {
+                if size > MAX_MESSAGE_BYTES {
+                    tracing::warn!("[email] Skipping {} byte message (limit {})", size, MAX_MESSAGE_BYTES);
+                    let mut discard = (&mut self.stream).take(size as u64);
+                    timeout(tokio::io::copy(&mut discard, &mut tokio::io::sink())).await??;
+                } else {
+                    let mut buf = vec![0; size];
+                    timeout(self.stream.read_exact(&mut buf)).await??;
+                    literal.extend(buf);
+                }
+                tail = self.read_line().await?;
+            }
+            responses.push((line, literal));
+        }
+    }
+
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<(), EmailError> {
+        self.command(&format!("LOGIN {} {}", quote(username), quote(password)))
+            .await
+            .map(|_| ())
+            .map_err(|_| EmailError::Auth(username.to_string()))
+    }
+
+    pub async fn select(&mut self, mailbox: &str) -> Result<(), EmailError> {
+        self.command(&format!("SELECT {}", quote(mailbox))).await.map(|_| ())
+    }
+
+    /// 未读邮件的 UID 喵
+    pub async fn search_unseen(&mut self) -> Result<Vec<u32>, EmailError> {
+        let responses = self.command("UID SEARCH UNSEEN").await?;
+        Ok(responses
+            .iter()
+            .filter_map(|(line, _)| line.strip_prefix("* SEARCH"))
+            .flat_map(|ids| ids.split_whitespace().filter_map(|id| id.parse().ok()))
+            .collect())
+    }
+
+    /// 取回完整邮件喵（BODY.PEEK 不设置 \Seen；超过大小上限时内容为空）
+    pub async fn fetch(&mut self, uid: u32) -> Result<Option<Vec<u8>>, EmailError> {
+        let responses = self.command(&format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        Ok(responses
+            .into_iter()
+            .find(|(line, _)| line.contains(" FETCH "))
+            .map(|(_, literal)| literal))
+    }
+
+    pub async fn mark_seen(&mut self, uids: &[u32]) -> Result<(), EmailError> {
+        let set: Vec<String> = uids.iter().map(u32::to_string).collect();
+        self.command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", set.join(",")))
+            .await
+            .map(|_| ())
+    }
+
+    /// 退出登录喵（失败时忽略，连接随后关闭）
+    pub async fn logout(&mut self) {
+        let _ = self.command("LOGOUT").await;
+    }
+}
+
+/// 行尾的 `{n}` 字面量长度喵
+fn literal_size(line: &str) -> Option<usize> {
+    let line = line.trim_end();
+    let start = line.rfind('{')?;
+    line.strip_suffix('}')?[start + 1..].parse().ok()
+}
+
+/// 🔒 SAFETY: SMTP 会话喵
+pub struct SmtpSession<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SmtpSession<S> {
+    /// 读取问候并 EHLO 喵
+    pub async fn connect(stream: S) -> Result<Self, EmailError> {
+        let mut session = Self {
+            stream: BufReader::new(stream),
+        };
+        session.expect(220).await?;
+        session.ehlo().await?;
+        Ok(session)
+    }
+
+    /// STARTTLS 之后重新 EHLO 喵（升级后的连接不再有问候）
+    pub async fn resume(stream: S) -> Result<Self, EmailError> {
+        let mut session = Self {
+            stream: BufReader::new(stream),
+        };
+        session.ehlo().await?;
+        Ok(session)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    async fn ehlo(&mut self) -> Result<(), EmailError> {
+        self.send_line("EHLO nekoclaw").await?;
+        self.expect(250).await.map(|_| ())
+    }
+
+    pub async fn starttls(&mut self) -> Result<(), EmailError> {
+        self.send_line("STARTTLS").await?;
+        self.expect(220).await.map(|_| ())
+    }
+
+    async fn send_line(&mut self, line: &str) -> Result<(), EmailError> {
+        let stream = self.stream.get_mut();
+        timeout(stream.write_all(format!("{}\r\n", line).as_bytes())).await??;
+        timeout(stream.flush()).await??;
+        Ok(())
+    }
+
+    /// 读取（可能多行的）回复并检查状态码喵
+    async fn expect(&mut self, code: u16) -> Result<String, EmailError> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if timeout(self.stream.read_line(&mut line)).await?? == 0 {
+                return Err(EmailError::Protocol("SMTP server closed the connection".to_string()));
+            }
+            reply.push_str(&line);
+            // `250-` 为续行，`250 ` 为最后一行喵
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        match reply.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(actual) if actual == code => Ok(reply),
+            _ => Err(EmailError::Protocol(format!("expected {}, got: {}", code, reply.trim()))),
+        }
+    }
+
+    /// 🔒 SAFETY: 登录并发送一封邮件喵
+    pub async fn deliver(&mut self, username: &str, password: &str, mail: &OutgoingMail) -> Result<(), EmailError> {
+        let credentials = format!("\0{}\0{}", username, password);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        self.send_line(&format!("AUTH PLAIN {}", encoded)).await?;
+        self.expect(235).await.map_err(|_| EmailError::Auth(username.to_string()))?;
+
+        let to = mail.to.replace(['\r', '\n', '<', '>'], "");
+        self.send_line(&format!("MAIL FROM:<{}>", mail.sender_address())).await?;
+        self.expect(250).await?;
+        self.send_line(&format!("RCPT TO:<{}>", to.trim())).await?;
+        self.expect(250).await?;
+        self.send_line("DATA").await?;
+        self.expect(354).await?;
+        // 以 `.` 开头的行需要转义喵
+        let mut data = String::new();
+        for line in mail.to_rfc5322().split("\r\n") {
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        let data = data.trim_end_matches("\r\n").to_string();
+        self.send_line(&format!("{}\r\n.", data)).await?;
+        self.expect(250).await?;
+        self.send_line("QUIT").await?;
+        let _ = self.expect(221).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按脚本应答的服务器喵：读到客户端的一行后写出下一段回复（SMTP `354` 之后读到 `.` 行为止）
+    async fn scripted_server(
+        mut server: tokio::io::DuplexStream,
+        greeting: &str,
+        replies: Vec<String>,
+    ) -> Vec<String> {
+        server.write_all(greeting.as_bytes()).await.unwrap();
+        let mut reader = BufReader::new(server);
+        let mut received = Vec::new();
+        let mut data = false;
+        for reply in replies {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            while data && !line.ends_with("\r\n.\r\n") {
+                reader.read_line(&mut line).await.unwrap();
+            }
+            received.push(line.trim_end().to_string());
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            data = reply.starts_with("354");
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn test_imap_and_smtp_sessions() {
+        let mail = "From: owner@example.com\r\nSubject: hi\r\n\r\nping\r\n";
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(scripted_server(
+            server,
+            "* OK IMAP ready\r\n",
+            vec![
+                "a1 OK LOGIN completed\r\n".to_string(),
+                "* 3 EXISTS\r\na2 OK [READ-WRITE] SELECT completed\r\n".to_string(),
+                "* SEARCH 7 9\r\na3 OK SEARCH completed\r\n".to_string(),
+                format!("* 1 FETCH (UID 7 BODY[] {{{}}}\r\n{})\r\na4 OK FETCH completed\r\n", mail.len(), mail),
+                "a5 OK STORE completed\r\n".to_string(),
+                "a6 NO [NONEXISTENT] no such mailbox\r\n".to_string(),
+            ],
+        ));
+        let mut session = ImapSession::connect(client).await.unwrap();
+        session.login("neko@example.com", "p\"w").await.unwrap();
+        session.select("INBOX").await.unwrap();
+        assert_eq!(session.search_unseen().await.unwrap(), vec![7, 9]);
+        assert_eq!(session.fetch(7).await.unwrap().unwrap(), mail.as_bytes());
+        session.mark_seen(&[7, 9]).await.unwrap();
+        assert!(matches!(session.select("Archive").await, Err(EmailError::Protocol(_))));
+        let received = server.await.unwrap();
+        assert_eq!(received[0], r#"a1 LOGIN "neko@example.com" "p\"w""#);
+        assert_eq!(received[3], "a4 UID FETCH 7 BODY.PEEK[]");
+        assert_eq!(received[4], r"a5 UID STORE 7,9 +FLAGS.SILENT (\Seen)");
+
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(scripted_server(
+            server,
+            "220 smtp.example.com ESMTP\r\n",
+            vec![
+                "250-smtp.example.com\r\n250 AUTH PLAIN\r\n".to_string(),
+                "235 Authenticated\r\n".to_string(),
+                "250 OK\r\n".to_string(),
+                "250 OK\r\n".to_string(),
+                "354 Go ahead\r\n".to_string(),
+                "250 Queued\r\n".to_string(),
+                "221 Bye\r\n".to_string(),
+            ],
+        ));
+        let mut session = SmtpSession::connect(client).await.unwrap();
+        let outgoing = OutgoingMail::new("nekoclaw <neko@example.com>", "owner@example.com", "hi", ".pong");
+        session.deliver("neko@example.com", "secret", &outgoing).await.unwrap();
+        let received = server.await.unwrap();
+        assert_eq!(received[0], "EHLO nekoclaw");
+        assert_eq!(
+            received[1],
+            format!("AUTH PLAIN {}", base64::engine::general_purpose::STANDARD.encode("\0neko@example.com\0secret"))
+        );
+        assert_eq!(received[2..5], ["MAIL FROM:<neko@example.com>", "RCPT TO:<owner@example.com>", "DATA"]);
+        assert!(received[5].contains("Subject: hi\r\n") && received[5].ends_with("\r\n."));
+        assert_eq!(received[6], "QUIT");
+    }
+}
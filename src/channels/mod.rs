@@ -5,4 +5,8 @@
  */
 
 pub mod discord;
+pub mod email;
+pub mod outbox;
 pub mod telegram;
+
+pub use outbox::{DeadLetterChannel, Outbox};
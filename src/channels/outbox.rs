@@ -0,0 +1,379 @@
+/*!
+ * Dead-letter Outbox
+ *
+ * 功能:
+ * - 发送到 Discord / Telegram 失败（限流、权限等）的消息写入死信表，连同错误原因
+ * - 守护进程按指数退避定时重试
+ * - `nekoclaw outbox list|retry` 手动查看与重试
+ *
+ * 🔒 SAFETY: 回复不会被静默丢弃喵
+ */
+
+use crate::core::traits::{Channel, ChannelEvent, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::Stream;
+use rusqlite::{params, Connection, Row};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// 首次重试延迟（秒）喵
+const BASE_RETRY_DELAY_SECS: i64 = 60;
+/// 最大重试延迟（秒）喵
+const MAX_RETRY_DELAY_SECS: i64 = 6 * 3600;
+/// 超过此次数后不再自动重试（仍可手动重试）喵
+pub const MAX_AUTO_ATTEMPTS: u32 = 8;
+
+/// 死信队列错误喵
+#[derive(Debug, Error)]
+pub enum OutboxError {
+    /// 数据库错误喵
+    #[error("Outbox store error: {0}")]
+    Store(#[from] rusqlite::Error),
+
+    /// 无法创建数据目录喵
+    #[error("Outbox directory error: {0}")]
+    Io(String),
+
+    /// 找不到条目喵
+    #[error("Outbox entry not found: {0}")]
+    NotFound(i64),
+}
+
+/// 死信条目喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    pub id: i64,
+    /// 渠道名称（discord / telegram）喵
+    pub channel: String,
+    /// 目标（频道 ID / Chat ID）喵
+    pub target: Option<String>,
+    pub content: String,
+    /// 最近一次失败原因喵
+    pub error: String,
+    /// 已尝试次数（含首次发送）喵
+    pub attempts: u32,
+    pub created_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+/// 重试结果统计喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryReport {
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+/// 死信队列喵
+pub struct Outbox {
+    conn: Mutex<Connection>,
+}
+
+impl Outbox {
+    /// 打开（或创建）死信数据库喵
+    pub fn open<P: AsRef<Path>>(path: P) -> std::result::Result<Self, OutboxError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).map_err(|e| OutboxError::Io(e.to_string()))?;
+        }
+        Self::initialize(Connection::open(path)?)
+    }
+
+    /// 内存队列（测试用）喵
+    #[cfg(test)]
+    pub fn in_memory() -> std::result::Result<Self, OutboxError> {
+        Self::initialize(Connection::open_in_memory()?)
+    }
+
+    fn initialize(conn: Connection) -> std::result::Result<Self, OutboxError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS dead_letters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel TEXT NOT NULL,
+                target TEXT,
+                content TEXT NOT NULL,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                next_attempt_at TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 第 n 次失败后的重试延迟（指数退避）喵
+    fn retry_delay(attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(16);
+        Duration::seconds((BASE_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS))
+    }
+
+    /// 记录一次发送失败喵
+    pub fn record(
+        &self,
+        channel: &str,
+        target: Option<&str>,
+        content: &str,
+        error: &str,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<i64, OutboxError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO dead_letters (channel, target, content, error, attempts, created_at, next_attempt_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6)",
+            params![
+                channel,
+                target,
+                content,
+                error,
+                now.to_rfc3339(),
+                (now + Self::retry_delay(1)).to_rfc3339()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn row_to_letter(row: &Row<'_>) -> rusqlite::Result<DeadLetter> {
+        let time = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now())
+        };
+        Ok(DeadLetter {
+            id: row.get(0)?,
+            channel: row.get(1)?,
+            target: row.get(2)?,
+            content: row.get(3)?,
+            error: row.get(4)?,
+            attempts: row.get(5)?,
+            created_at: time(row.get(6)?),
+            next_attempt_at: time(row.get(7)?),
+        })
+    }
+
+    fn query(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> std::result::Result<Vec<DeadLetter>, OutboxError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, channel, target, content, error, attempts, created_at, next_attempt_at
+             FROM dead_letters {} ORDER BY id",
+            filter
+        ))?;
+        let letters = stmt
+            .query_map(args, Self::row_to_letter)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(letters)
+    }
+
+    /// 列出全部死信喵
+    pub fn list(&self) -> std::result::Result<Vec<DeadLetter>, OutboxError> {
+        self.query("", &[])
+    }
+
+    /// 按 ID 查找喵
+    pub fn get(&self, id: i64) -> std::result::Result<DeadLetter, OutboxError> {
+        self.query("WHERE id = ?1", &[&id])?
+            .pop()
+            .ok_or(OutboxError::NotFound(id))
+    }
+
+    /// 到期且未超过自动重试次数的死信喵
+    pub fn due(&self, now: DateTime<Utc>) -> std::result::Result<Vec<DeadLetter>, OutboxError> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|l| l.attempts < MAX_AUTO_ATTEMPTS && l.next_attempt_at <= now)
+            .collect())
+    }
+
+    /// 再次失败：累加次数并按退避重新排期喵
+    pub fn reschedule(&self, id: i64, error: &str, now: DateTime<Utc>) -> std::result::Result<(), OutboxError> {
+        let letter = self.get(id)?;
+        let attempts = letter.attempts + 1;
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "UPDATE dead_letters SET error = ?1, attempts = ?2, next_attempt_at = ?3 WHERE id = ?4",
+            params![error, attempts, (now + Self::retry_delay(attempts)).to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// 送达后移除喵
+    pub fn remove(&self, id: i64) -> std::result::Result<(), OutboxError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        match conn.execute("DELETE FROM dead_letters WHERE id = ?1", params![id])? {
+            0 => Err(OutboxError::NotFound(id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// 重试单条死信喵
+    ///
+    /// ## Returns
+    /// Ok(true) = 已送达并移除，Ok(false) = 仍失败并已重新排期喵
+    pub async fn retry(
+        &self,
+        letter: &DeadLetter,
+        channel: &dyn Channel,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<bool, OutboxError> {
+        match channel.send(&letter.content, letter.target.as_deref()).await {
+            Ok(()) => {
+                self.remove(letter.id)?;
+                Ok(true)
+            }
+            Err(e) => {
+                tracing::warn!("Outbox retry {} via {} failed: {}", letter.id, letter.channel, e);
+                self.reschedule(letter.id, &e.to_string(), now)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// 重试所有到期死信喵
+    ///
+    /// `resolve` 按渠道名称构建渠道；无法构建的渠道计为失败喵
+    pub async fn retry_due<F>(&self, now: DateTime<Utc>, resolve: F) -> std::result::Result<RetryReport, OutboxError>
+    where
+        F: Fn(&str) -> Result<Arc<dyn Channel>>,
+    {
+        let mut report = RetryReport::default();
+        for letter in self.due(now)? {
+            let delivered = match resolve(&letter.channel) {
+                Ok(channel) => self.retry(&letter, channel.as_ref(), now).await?,
+                Err(e) => {
+                    self.reschedule(letter.id, &e.to_string(), now)?;
+                    false
+                }
+            };
+            if delivered {
+                report.delivered += 1;
+            } else {
+                report.failed += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// 发送失败时写入死信队列的渠道包装喵
+pub struct DeadLetterChannel {
+    inner: Arc<dyn Channel>,
+    outbox: Arc<Outbox>,
+    name: String,
+}
+
+impl DeadLetterChannel {
+    /// 包装渠道喵（`name` 为重试时用于重建渠道的名称）
+    pub fn new(name: &str, inner: Arc<dyn Channel>, outbox: Arc<Outbox>) -> Self {
+        Self {
+            inner,
+            outbox,
+            name: name.to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for DeadLetterChannel {
+    async fn send(&self, content: &str, target: Option<&str>) -> Result<()> {
+        let Err(e) = self.inner.send(content, target).await else {
+            return Ok(());
+        };
+        let id = self
+            .outbox
+            .record(&self.name, target, content, &e.to_string(), Utc::now())?;
+        tracing::warn!("Delivery via {} failed, queued as outbox #{}: {}", self.name, id, e);
+        Err(e)
+    }
+
+    async fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<ChannelEvent>> + Send>> {
+        self.inner.receive().await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn channel_type(&self) -> &str {
+        self.inner.channel_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// 可切换成功/失败的测试渠道喵
+    struct FlakyChannel {
+        up: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for FlakyChannel {
+        async fn send(&self, _content: &str, _target: Option<&str>) -> Result<()> {
+            if self.up.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("429 Too Many Requests".into())
+            }
+        }
+
+        async fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<ChannelEvent>> + Send>> {
+            Box::pin(futures::stream::empty())
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn channel_type(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_is_queued_and_retried() {
+        let outbox = Arc::new(Outbox::in_memory().unwrap());
+        let flaky = Arc::new(FlakyChannel {
+            up: AtomicBool::new(false),
+        });
+        let channel = DeadLetterChannel::new("discord", flaky.clone(), outbox.clone());
+
+        assert!(channel.send("你好喵", Some("42")).await.is_err());
+        let letters = outbox.list().unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].target.as_deref(), Some("42"));
+        assert!(letters[0].error.contains("429"));
+
+        // 未到期时不重试喵
+        let now = Utc::now();
+        assert!(outbox.due(now).unwrap().is_empty());
+
+        let later = now + Duration::minutes(5);
+        let resolve = |_: &str| -> Result<Arc<dyn Channel>> { Ok(flaky.clone()) };
+        let report = outbox.retry_due(later, resolve).await.unwrap();
+        assert_eq!(report, RetryReport { delivered: 0, failed: 1 });
+        assert_eq!(outbox.get(letters[0].id).unwrap().attempts, 2);
+
+        flaky.up.store(true, Ordering::SeqCst);
+        let report = outbox
+            .retry_due(later + Duration::hours(1), resolve)
+            .await
+            .unwrap();
+        assert_eq!(report, RetryReport { delivered: 1, failed: 0 });
+        assert!(outbox.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay_backoff_is_capped() {
+        assert_eq!(Outbox::retry_delay(1), Duration::seconds(60));
+        assert_eq!(Outbox::retry_delay(2), Duration::seconds(120));
+        assert_eq!(Outbox::retry_delay(30), Duration::seconds(MAX_RETRY_DELAY_SECS));
+        assert!(matches!(
+            Outbox::in_memory().unwrap().remove(7),
+            Err(OutboxError::NotFound(7))
+        ));
+    }
+}
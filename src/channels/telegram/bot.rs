@@ -7,13 +7,45 @@
 //! - 实现 Telegram Bot 的消息接收和发送喵
 //! - 支持斜杠命令处理喵
 //! - 集成安全消息过滤喵
+//! - 对机器人回复的 👍 / 👎 表情回应记录为用户反馈喵
+//! - `run_polling` 长轮询 getUpdates：斜杠命令交给 `CommandService`，
+//!   普通消息交给 Agent（`ChatBackend`），每个 Chat 独立的对话上下文与会话记录喵
+//! - 配置事件总线后，普通消息先进入 `EventBus` 排队，由 worker 按并发上限交给 Agent 喵
+//! - 命令、普通消息与 Agent 的工具调用按用户角色（`Rbac`，身份 `telegram:<用户 ID>`）检查权限，
+//!   主人（`owner_user_ids`）可以使用 Owner 命令，如 `/pair approve <配对码>` 批准设备配对喵
 
 use futures::Stream;
+use std::collections::HashMap;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use teloxide::prelude::*;
-use teloxide::types::{ChatId, Update, UpdateKind};
+use teloxide::types::{AllowedUpdate, ChatId, ReactionType, Update, UpdateKind};
+use teloxide::{ApiError, RequestError};
 use thiserror::Error;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use super::commands::{CommandConfig, CommandService};
+use crate::auth::{Identity, Permission, Rbac};
+use crate::core::session::resume_messages;
+use crate::core::traits::{ChannelEvent, Message};
+use crate::core::{BusError, EventBus, EventHandler, SessionInfo, SessionLanguages, SessionStore};
+use crate::gateway::{ChatBackend, DeviceCodes};
+use crate::telemetry::{Feedback, MetricsRecorder, Rating};
+
+/// getUpdates 长轮询等待时间（秒）喵
+const POLL_TIMEOUT_SECS: u32 = 30;
+/// 轮询失败后的重试间隔喵
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// 每个 Chat 在内存中保留的最大消息数喵
+const MAX_HISTORY_MESSAGES: usize = 40;
+/// 恢复已保存对话时的 token 预算喵
+const RESUME_TOKEN_BUDGET: usize = 6_000;
+/// Agent 出错时回复给用户的提示（不暴露错误详情）喵
+const FAILURE_NOTICE: &str = "⚠️ 处理消息时出错了喵，请稍后再试";
+/// 关闭期间收到新消息时的提示喵
+const SHUTDOWN_NOTICE: &str = "⏹️ 服务正在关闭喵，请稍后再发送这条消息";
 
 // 为 future 版本预留
 // use teloxide::types::Dialogue;
@@ -36,6 +68,10 @@ pub enum TelegramError {
     /// 安全过滤失败喵
     #[error("Security filter rejected message: {0}")]
     SecurityFilterError(String),
+
+    /// Agent 生成回复失败喵
+    #[error("Agent failed: {0}")]
+    AgentError(String),
 }
 
 /// Telegram Bot 配置喵
@@ -55,10 +91,6 @@ pub struct TelegramConfig {
 ///
 /// 🔐 SAFETY: 持有 Bot Token，必须安全存储喵
 pub struct TelegramBot {
-    /// Bot Token喵
-    /// ⚠️ SAFETY: 核心敏感配置喵
-    token: String,
-
     /// Bot 名称喵
     bot_name: String,
 
@@ -68,6 +100,41 @@ pub struct TelegramBot {
     /// 发送者白名单（Chat IDs）喵
     /// 🔐 SAFETY: 权限控制喵
     allowed_chat_ids: Arc<std::collections::HashSet<i64>>,
+
+    /// 用户角色与权限喵
+    /// 🔐 SAFETY: 权限控制喵
+    rbac: Arc<Rbac>,
+
+    /// 设备配对申请（None = /pair 不可用）喵
+    device_codes: Option<Arc<DeviceCodes>>,
+
+    /// 反馈记录器（None = 不记录）喵
+    feedback: Option<MetricsRecorder>,
+
+    /// Telegram Bot API 客户端喵
+    api: Bot,
+
+    /// 斜杠命令路由喵
+    commands: CommandService,
+
+    /// 处理普通消息的 Agent（None = 只响应命令）喵
+    agent: Option<Arc<ChatBackend>>,
+
+    /// 每轮对话开头的系统提示词喵
+    system_prompt: Option<String>,
+
+    /// 会话记录（None = 不落盘）喵
+    sessions: Option<SessionStore>,
+
+    /// 每个 Chat 独立的对话上下文喵
+    /// 🔐 SAFETY: 按 Chat ID 隔离，同一 Chat 的消息串行处理喵
+    chats: Mutex<HashMap<i64, Arc<tokio::sync::Mutex<Vec<Message>>>>>,
+
+    /// 普通消息的事件队列（None = 每条消息直接交给 Agent）喵
+    bus: Option<Arc<EventBus>>,
+
+    /// 每个 Chat 的回复语言（自动检测，/lang 可固定）喵
+    languages: SessionLanguages,
 }
 
 impl TelegramBot {
@@ -87,13 +154,23 @@ impl TelegramBot {
         }
 
         // 从 token 提取 bot 名称（格式: 123456:ABC-DEF1234ghIkl-zyx57W2v1u123ew11）
-        let bot_name = format!("nekoclaw_bot");
+        let bot_name = "nekoclaw_bot".to_string();
 
         Ok(Self {
-            token,
+            api: Bot::new(token),
             bot_name,
             config,
             allowed_chat_ids: Arc::new(std::collections::HashSet::new()),
+            rbac: Arc::new(Rbac::default()),
+            device_codes: None,
+            feedback: None,
+            commands: CommandService::new(CommandConfig::default()),
+            agent: None,
+            system_prompt: None,
+            sessions: None,
+            chats: Mutex::new(HashMap::new()),
+            bus: None,
+            languages: SessionLanguages::new(),
         })
     }
 
@@ -109,6 +186,100 @@ impl TelegramBot {
         self.allowed_chat_ids = Arc::new(new_set);
     }
 
+    /// 🔐 SAFETY: 使用共享的角色权限喵（默认所有用户为 `default_role`，没有 Owner）
+    pub fn with_rbac(mut self, rbac: Arc<Rbac>) -> Self {
+        self.rbac = rbac;
+        self
+    }
+
+    /// 用户角色与权限喵
+    pub fn rbac(&self) -> &Rbac {
+        &self.rbac
+    }
+
+    /// 回复语言注册表（可与其他渠道共享同一份持久化文件）喵
+    pub fn with_languages(mut self, languages: SessionLanguages) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// 各 Chat 的回复语言喵
+    pub fn languages(&self) -> &SessionLanguages {
+        &self.languages
+    }
+
+    /// 🔒 SAFETY: 启用 `/pair` 审批设备配对喵
+    pub fn with_device_pairing(mut self, codes: Arc<DeviceCodes>) -> Self {
+        self.device_codes = Some(codes);
+        self
+    }
+
+    /// 设备配对申请喵
+    pub fn device_codes(&self) -> Option<&DeviceCodes> {
+        self.device_codes.as_deref()
+    }
+
+    /// 记录 👍 / 👎 反馈喵
+    pub fn with_feedback(mut self, recorder: MetricsRecorder) -> Self {
+        self.feedback = Some(recorder);
+        self
+    }
+
+    /// 普通消息交给 Agent 回复喵
+    pub fn with_agent(mut self, agent: Arc<ChatBackend>) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// 设置系统提示词喵
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        self.system_prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// 🔒 SAFETY: 每个 Chat 的对话保存为独立会话 `telegram-<chat_id>` 喵
+    pub fn with_sessions(mut self, store: SessionStore) -> Self {
+        self.sessions = Some(store);
+        self
+    }
+
+    /// 🔒 SAFETY: 普通消息发布到事件总线排队处理（需另行 `register("telegram", bot)`）喵
+    pub fn with_event_bus(mut self, bus: Arc<EventBus>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// 🔐 SAFETY: 是否为白名单 Chat 喵
+    fn is_allowed(&self, chat_id: i64) -> bool {
+        self.allowed_chat_ids.contains(&chat_id)
+    }
+
+    /// 处理表情回应喵
+    ///
+    /// ## Returns
+    /// 识别出的评价；非回应事件、未授权 Chat 或其他表情返回 None 喵
+    ///
+    /// 🔐 PERMISSION: 仅白名单 Chat 的回应会被记录喵
+    pub fn handle_reaction(&self, event: &TelegramEvent) -> Result<Option<Rating>, TelegramError> {
+        let TelegramEvent::Reaction { chat_id, user_id, message_id, emoji, .. } = event else {
+            return Ok(None);
+        };
+        if !self.allowed_chat_ids.contains(chat_id) {
+            return Ok(None);
+        }
+
+        let rating = Rating::from_emoji(emoji);
+        if let (Some(rating), Some(recorder)) = (rating, &self.feedback) {
+            // 消息 ID 只在 Chat 内唯一，拼上 Chat ID 作为回复 ID 喵
+            let feedback = Feedback::new(&format!("{}:{}", chat_id, message_id), rating, "telegram")
+                .with_user(&user_id.to_string());
+            recorder
+                .record_feedback(&feedback)
+                .map_err(|e| TelegramError::ParseError(e.to_string()))?;
+        }
+        Ok(rating)
+    }
+
     /// 发送消息喵
     ///
     /// ## Arguments
@@ -133,20 +304,240 @@ impl TelegramBot {
             return Err(TelegramError::SendError("Message too long".to_string()));
         }
 
-        // 3. 发送消息喵
-        // 注意：这里使用占位符，实际实现需要 teloxide 的 Bot 实例喵
-        // 下面的代码是伪代码，用于文档说明喵
-        /*
-        let bot = Bot::new(&self.token);
-        bot.send_message(ChatId(chat_id), text)
-            .parse_mode(ParseMode::Html)
+        // 3. 发送消息喵（纯文本：模型回复里的 `<` 等字符不会被当成 HTML）
+        self.api
+            .send_message(ChatId(chat_id), text)
             .await
             .map_err(|e| TelegramError::SendError(e.to_string()))?;
-        */
 
         Ok(())
     }
 
+    /// 发送回复，超长时按长度上限分段喵
+    pub async fn send_reply(&self, chat_id: i64, text: &str) -> Result<(), TelegramError> {
+        for chunk in split_message(text, self.config.max_message_length) {
+            self.send_message(chat_id, chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 长轮询 getUpdates 并处理消息，直到 Token 失效喵
+    ///
+    /// 每条更新在独立任务中处理，不同 Chat 互不阻塞；
+    /// 本函数被取消时正在处理的消息一并取消喵
+    ///
+    /// 配置事件总线时白名单 Chat 的普通消息改为发布到总线，
+    /// 队列已满时暂停拉取新的更新（背压）喵
+    pub async fn run_polling(self: Arc<Self>) -> Result<(), TelegramError> {
+        info!("📡 Telegram 长轮询启动喵（{} 个允许的 Chat）", self.allowed_chat_ids.len());
+        let mut offset = 0;
+        let mut handlers = JoinSet::new();
+        loop {
+            while handlers.try_join_next().is_some() {}
+
+            let updates = match self
+                .api
+                .get_updates()
+                .offset(offset)
+                .timeout(POLL_TIMEOUT_SECS)
+                .allowed_updates([AllowedUpdate::Message, AllowedUpdate::MessageReaction])
+                .await
+            {
+                Ok(updates) => updates,
+                Err(RequestError::Api(ApiError::InvalidToken)) => return Err(TelegramError::InvalidToken),
+                Err(e) => {
+                    warn!("Telegram getUpdates 失败，{:?} 后重试喵: {}", POLL_RETRY_DELAY, e);
+                    tokio::time::sleep(POLL_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            for update in updates {
+                // 确认已收到的更新，下次轮询不再返回喵
+                offset = offset.max(update.id.0 as i32 + 1);
+                let event = match TelegramEvent::try_from(update) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        debug!("Skipping Telegram update: {}", e);
+                        continue;
+                    }
+                };
+                if let Some(bus) = self.bus.as_ref().filter(|_| self.is_allowed(event.chat_id())) {
+                    if let Some(channel_event) = event.to_channel_event() {
+                        let published = match bus.try_publish(channel_event.clone()) {
+                            Err(BusError::Full(queued)) => {
+                                warn!("事件队列已满（{} 条排队），暂停拉取 Telegram 更新喵", queued);
+                                bus.publish(channel_event).await
+                            }
+                            result => result,
+                        };
+                        match published {
+                            Ok(()) => {}
+                            Err(BusError::ShuttingDown) => {
+                                if let Err(e) = self.send_reply(event.chat_id(), SHUTDOWN_NOTICE).await {
+                                    warn!("Telegram 回复发送失败喵（chat {}）: {}", event.chat_id(), e);
+                                }
+                            }
+                            Err(e) => warn!("Telegram 消息进入事件队列失败喵: {}", e),
+                        }
+                        continue;
+                    }
+                }
+                let bot = self.clone();
+                handlers.spawn(async move { bot.handle_event(event).await });
+            }
+        }
+    }
+
+    /// 处理一条事件并把回复发回对应 Chat 喵
+    async fn handle_event(&self, event: TelegramEvent) {
+        let chat_id = event.chat_id();
+        let reply = match self.dispatch(&event).await {
+            Ok(Some(reply)) => reply,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Telegram chat {} 处理失败喵: {}", chat_id, e);
+                FAILURE_NOTICE.to_string()
+            }
+        };
+        if let Err(e) = self.send_reply(chat_id, &reply).await {
+            warn!("Telegram 回复发送失败喵（chat {}）: {}", chat_id, e);
+        }
+    }
+
+    /// 🔒 SAFETY: 路由一条事件喵
+    ///
+    /// ## Returns
+    /// 要发回该 Chat 的回复；非白名单 Chat、表情回应和其他消息返回 None 喵
+    pub async fn dispatch(&self, event: &TelegramEvent) -> Result<Option<String>, TelegramError> {
+        match event {
+            TelegramEvent::Reaction { .. } => {
+                if let Err(e) = self.handle_reaction(event) {
+                    warn!("记录 Telegram 反馈失败喵: {}", e);
+                }
+                Ok(None)
+            }
+            TelegramEvent::Command { chat_id, .. } | TelegramEvent::TextMessage { chat_id, .. }
+                if !self.is_allowed(*chat_id) =>
+            {
+                warn!("⚠️ 忽略未授权 Telegram chat {} 的消息喵", chat_id);
+                Ok(None)
+            }
+            TelegramEvent::Command { .. } => match self.commands.handle_command(self, event).await {
+                Ok(response) => Ok(Some(response.text).filter(|t| !t.is_empty())),
+                Err(e) => Ok(Some(format!("⚠️ {}", e))),
+            },
+            TelegramEvent::TextMessage { chat_id, user_id, text, .. } => {
+                let caller = Identity::new("telegram", user_id);
+                if let Err(e) = self.rbac.check(&caller, Permission::Chat) {
+                    warn!("⚠️ {}", e);
+                    return Ok(Some("🚫 你没有与 Agent 对话的权限喵".to_string()));
+                }
+                self.ask_agent(*chat_id, Message::user(text.to_string()), Some(&caller)).await.map(Some)
+            }
+            TelegramEvent::OtherMessage { .. } => Ok(None),
+        }
+    }
+
+    /// 🔒 SAFETY: 把系统事件（如监视目录的文件变化）注入某个 Chat 的 Agent 对话，并发回 Agent 的回应喵
+    ///
+    /// 非白名单 Chat 或未启用 Agent 时不注入喵
+    pub async fn notify_event(&self, chat_id: i64, event: &str) -> Result<(), TelegramError> {
+        if !self.is_allowed(chat_id) {
+            warn!("⚠️ Chat {} 不在白名单中，忽略系统事件喵", chat_id);
+            return Ok(());
+        }
+        if self.agent.is_none() {
+            return Ok(());
+        }
+        let reply = self.ask_agent(chat_id, Message::system(event.to_string()), None).await?;
+        self.send_reply(chat_id, &reply).await
+    }
+
+    /// 把普通消息（或系统事件）交给 Agent，上下文只包含本 Chat 的对话喵
+    ///
+    /// `caller` 为发消息的用户时，工具调用按其角色检查；系统事件为 None 喵
+    async fn ask_agent(
+        &self,
+        chat_id: i64,
+        incoming: Message,
+        caller: Option<&Identity>,
+    ) -> Result<String, TelegramError> {
+        let Some(agent) = &self.agent else {
+            return Ok("🤖 Agent 未启用喵，目前只能处理斜杠命令（/help）".to_string());
+        };
+        let history = self.chat_history(chat_id);
+        // 同一 Chat 的消息按顺序处理，避免上下文交错喵
+        let mut history = history.lock().await;
+
+        let mut messages: Vec<Message> = self.system_prompt.iter().cloned().map(Message::system).collect();
+        let conversation = format!("telegram:{}", chat_id);
+        if incoming.role == "user" {
+            self.languages.observe(&conversation, &incoming.content);
+        }
+        messages.extend(self.languages.instruction(&conversation).map(Message::system));
+        messages.extend(history.iter().cloned());
+        messages.push(incoming.clone());
+        let reply = match caller {
+            Some(caller) => agent.complete_as(caller, messages).await,
+            None => agent.complete(messages).await,
+        }
+        .map_err(|e| TelegramError::AgentError(e.to_string()))?;
+
+        if let Err(e) = self.save_turn(chat_id, &incoming, &reply) {
+            warn!("保存 Telegram 会话失败喵（chat {}）: {}", chat_id, e);
+        }
+        history.push(incoming);
+        history.push(Message::assistant(reply.clone()));
+        let excess = history.len().saturating_sub(MAX_HISTORY_MESSAGES);
+        history.drain(..excess);
+        Ok(reply)
+    }
+
+    /// 取得某个 Chat 的对话上下文；首次出现时从已保存的会话恢复喵
+    fn chat_history(&self, chat_id: i64) -> Arc<tokio::sync::Mutex<Vec<Message>>> {
+        let mut chats = self.chats.lock().unwrap();
+        chats
+            .entry(chat_id)
+            .or_insert_with(|| {
+                let restored = self
+                    .sessions
+                    .as_ref()
+                    .and_then(|store| store.load_messages(&session_id(chat_id)).ok())
+                    .map(|stored| resume_messages(&stored, RESUME_TOKEN_BUDGET))
+                    .unwrap_or_default();
+                let restored = restored
+                    .into_iter()
+                    .map(|m| Message { role: m.role, content: m.content })
+                    .collect();
+                Arc::new(tokio::sync::Mutex::new(restored))
+            })
+            .clone()
+    }
+
+    /// 把一轮问答写入该 Chat 的会话喵
+    fn save_turn(&self, chat_id: i64, incoming: &Message, reply: &str) -> crate::core::traits::Result<()> {
+        let Some(store) = &self.sessions else {
+            return Ok(());
+        };
+        let id = session_id(chat_id);
+        let mut info = store.load(&id)?.unwrap_or_else(|| SessionInfo::new(&id, "telegram"));
+        store.append_messages(
+            &id,
+            &[
+                match incoming.role.as_str() {
+                    "system" => crate::providers::Message::system(incoming.content.clone()),
+                    _ => crate::providers::Message::user(incoming.content.clone()),
+                },
+                crate::providers::Message::assistant(reply.to_string()),
+            ],
+        )?;
+        info.record_message();
+        info.record_message();
+        info.encrypted |= store.is_encrypted();
+        store.save(&info)
+    }
+
     /// 接收消息流喵
     ///
     /// ## Returns
@@ -206,7 +597,7 @@ impl TelegramBot {
     /// Ok(()) = 安全喵，Err = 检测到注入喵
     ///
     /// 🔐 PERMISSION: 安全过滤喵
-    fn check_command_injection(&self, command: &str) -> Result<(), String> {
+    pub fn check_command_injection(&self, command: &str) -> Result<(), String> {
         let dangerous_patterns = [
             "|",  // 管道喵
             ";",  // 分号喵
@@ -249,6 +640,15 @@ pub enum TelegramEvent {
         timestamp: chrono::DateTime<chrono::Utc>,
     },
 
+    /// 表情回应喵
+    Reaction {
+        chat_id: i64,
+        user_id: i64,
+        message_id: i32,
+        emoji: String,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+
     /// 其他消息类型喵（图片、文件等）
     OtherMessage {
         chat_id: i64,
@@ -257,6 +657,98 @@ pub enum TelegramEvent {
     },
 }
 
+impl TelegramEvent {
+    /// 事件所在的 Chat ID 喵
+    pub fn chat_id(&self) -> i64 {
+        match self {
+            TelegramEvent::TextMessage { chat_id, .. }
+            | TelegramEvent::Command { chat_id, .. }
+            | TelegramEvent::Reaction { chat_id, .. }
+            | TelegramEvent::OtherMessage { chat_id, .. } => *chat_id,
+        }
+    }
+
+    /// 普通消息转换为事件总线上的 `ChannelEvent` 喵（`sender_id` 为 Chat ID，其余字段放在 metadata）
+    pub fn to_channel_event(&self) -> Option<ChannelEvent> {
+        let TelegramEvent::TextMessage {
+            chat_id,
+            user_id,
+            username,
+            text,
+            timestamp,
+        } = self
+        else {
+            return None;
+        };
+        Some(ChannelEvent {
+            source: "telegram".to_string(),
+            sender_id: chat_id.to_string(),
+            message: text.clone(),
+            metadata: Some(serde_json::json!({
+                "user_id": user_id,
+                "username": username,
+                "timestamp": timestamp,
+            })),
+        })
+    }
+}
+
+impl TryFrom<ChannelEvent> for TelegramEvent {
+    type Error = TelegramError;
+
+    fn try_from(event: ChannelEvent) -> Result<Self, Self::Error> {
+        let chat_id = event
+            .sender_id
+            .parse()
+            .map_err(|_| TelegramError::ParseError(format!("Invalid chat id '{}'", event.sender_id)))?;
+        let metadata = event.metadata.unwrap_or_default();
+        Ok(TelegramEvent::TextMessage {
+            chat_id,
+            user_id: metadata["user_id"].as_i64().unwrap_or(0),
+            username: metadata["username"].as_str().map(str::to_string),
+            text: event.message,
+            timestamp: serde_json::from_value(metadata["timestamp"].clone())
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        })
+    }
+}
+
+/// 事件总线的 Telegram worker：交给 Agent 并把回复发回对应 Chat 喵
+#[async_trait::async_trait]
+impl EventHandler for TelegramBot {
+    async fn handle(&self, event: ChannelEvent) {
+        match TelegramEvent::try_from(event) {
+            Ok(event) => self.handle_event(event).await,
+            Err(e) => warn!("忽略无法解析的 Telegram 队列事件喵: {}", e),
+        }
+    }
+}
+
+/// 某个 Chat 对应的会话 ID 喵
+fn session_id(chat_id: i64) -> String {
+    format!("telegram-{}", chat_id)
+}
+
+/// 按字节上限把消息切成多段（不切断 UTF-8 字符）喵
+fn split_message(text: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_len {
+        let mut end = max_len;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // 尽量在换行处断开喵
+        if let Some(newline) = rest[..end].rfind('\n').filter(|&i| i > 0) {
+            end = newline + 1;
+        }
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks.push(rest);
+    chunks
+}
+
 /// 从 Update 创建事件喵
 ///
 /// ## Arguments
@@ -274,6 +766,24 @@ impl TryFrom<Update> for TelegramEvent {
 
         // 获取消息喵 - teloxide 0.13 使用 kind 访问
         let message = match update.kind {
+            UpdateKind::MessageReaction(reaction) => {
+                // 只关心新增的普通表情喵（撤销回应时 new_reaction 为空）
+                let emoji = reaction
+                    .new_reaction
+                    .iter()
+                    .find_map(|r| match r {
+                        ReactionType::Emoji { emoji } => Some(emoji.clone()),
+                        _ => None,
+                    })
+                    .ok_or_else(|| TelegramError::ParseError("No emoji reaction".to_string()))?;
+                return Ok(TelegramEvent::Reaction {
+                    chat_id: reaction.chat.id.0,
+                    user_id: reaction.user.map(|u| u.id.0 as i64).unwrap_or(0),
+                    message_id: reaction.message_id.0,
+                    emoji,
+                    timestamp,
+                });
+            }
             UpdateKind::Message(m) => m,
             UpdateKind::EditedMessage(m) => m,
             UpdateKind::ChannelPost(m) => m,
@@ -283,10 +793,8 @@ impl TryFrom<Update> for TelegramEvent {
 
         // 获取 Chat ID 和 User ID 喵
         let chat_id = message.chat.id.0;
-        let user_id = message.from().map(|u| u.id.0 as i64).unwrap_or(0);
-        let username = message
-            .from()
-            .and_then(|u| u.username.as_ref().map(|s| s.clone()));
+        let user_id = message.from.as_ref().map(|u| u.id.0 as i64).unwrap_or(0);
+        let username = message.from.as_ref().and_then(|u| u.username.clone());
 
         if let Some(text) = message.text() {
             // 检查是否为命令喵
@@ -338,12 +846,41 @@ impl Default for TelegramConfig {
     }
 }
 
+/// 通用渠道接口（用于主动推送，例如放行申请）喵
+#[async_trait::async_trait]
+impl crate::core::traits::Channel for TelegramBot {
+    async fn send(&self, content: &str, target: Option<&str>) -> crate::core::traits::Result<()> {
+        let chat_id: i64 = target
+            .ok_or("Target chat ID required")?
+            .parse()
+            .map_err(|e| format!("Invalid Telegram chat ID: {}", e))?;
+        self.send_message(chat_id, content).await?;
+        Ok(())
+    }
+
+    async fn receive(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = crate::core::traits::Result<crate::core::traits::ChannelEvent>> + Send>>
+    {
+        // 接收由 teloxide 的 dispatcher 负责，这里不提供事件流喵
+        Box::pin(futures::stream::empty())
+    }
+
+    fn name(&self) -> &str {
+        &self.bot_name
+    }
+
+    fn channel_type(&self) -> &str {
+        "telegram"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     /// 测试 XSS 过滤喵
-    #[tokio::test]
+    #[test]
     fn test_xss_filter() {
         let bot = TelegramBot::new("test_token".to_string(), TelegramConfig::default()).unwrap();
 
@@ -358,7 +895,7 @@ mod tests {
     }
 
     /// 测试命令注入防护喵
-    #[tokio::test]
+    #[test]
     fn test_command_injection_protection() {
         let bot = TelegramBot::new("test_token".to_string(), TelegramConfig::default()).unwrap();
 
@@ -371,4 +908,130 @@ mod tests {
         assert!(bot.check_command_injection("start").is_ok());
         assert!(bot.check_command_injection("help").is_ok());
     }
+
+    /// 测试表情回应解析与白名单喵
+    #[test]
+    fn test_reaction_feedback() {
+        let update: Update = serde_json::from_str(
+            r#"{
+                "update_id": 1,
+                "message_reaction": {
+                    "chat": {"id": 42, "type": "private", "first_name": "Alice"},
+                    "message_id": 7,
+                    "user": {"id": 1001, "is_bot": false, "first_name": "Alice"},
+                    "date": 1700000000,
+                    "old_reaction": [],
+                    "new_reaction": [{"type": "emoji", "emoji": "👎"}]
+                }
+            }"#,
+        )
+        .unwrap();
+        let event = TelegramEvent::try_from(update).unwrap();
+        assert!(matches!(
+            &event,
+            TelegramEvent::Reaction { chat_id: 42, user_id: 1001, message_id: 7, emoji, .. } if emoji == "👎"
+        ));
+
+        let mut bot = TelegramBot::new("test_token".to_string(), TelegramConfig::default()).unwrap();
+        assert_eq!(bot.handle_reaction(&event).unwrap(), None);
+        bot.add_allowed_chat_id(42);
+        assert_eq!(bot.handle_reaction(&event).unwrap(), Some(Rating::Down));
+    }
+
+    /// 测试命令路由与按 Chat 隔离的对话上下文喵
+    #[tokio::test]
+    async fn test_dispatch_isolates_chats() {
+        use crate::gateway::testing::ScriptedProvider;
+
+        let provider = Arc::new(ScriptedProvider::new(["A1", "B1", "A2"]));
+        let sessions = tempfile::tempdir().unwrap();
+        let mut bot = TelegramBot::new("test_token".to_string(), TelegramConfig::default())
+            .unwrap()
+            .with_agent(Arc::new(ChatBackend::new(provider.clone())))
+            .with_sessions(SessionStore::new(sessions.path()));
+        bot.add_allowed_chat_id(1);
+        bot.add_allowed_chat_id(2);
+
+        let text = |chat_id: i64, text: &str| TelegramEvent::TextMessage {
+            chat_id,
+            user_id: chat_id,
+            username: None,
+            text: text.to_string(),
+            timestamp: chrono::Utc::now(),
+        };
+        let ping = TelegramEvent::Command {
+            chat_id: 1,
+            user_id: 1,
+            username: None,
+            command: "ping".to_string(),
+            args: vec![],
+            timestamp: chrono::Utc::now(),
+        };
+
+        // 命令不经过 Agent，未授权 Chat 不回复喵
+        assert!(bot.dispatch(&ping).await.unwrap().unwrap().contains("PONG"));
+        assert_eq!(bot.dispatch(&text(3, "hi")).await.unwrap(), None);
+
+        assert_eq!(bot.dispatch(&text(1, "a1")).await.unwrap().as_deref(), Some("A1"));
+        assert_eq!(bot.dispatch(&text(2, "b1")).await.unwrap().as_deref(), Some("B1"));
+        assert_eq!(bot.dispatch(&text(1, "a2")).await.unwrap().as_deref(), Some("A2"));
+
+        let prompts = provider.prompts();
+        assert_eq!(prompts.len(), 3);
+        // Chat 2 看不到 Chat 1 的对话，Chat 1 的第二轮带着自己的上下文喵
+        assert_eq!(prompts[1].len(), 1);
+        let contents: Vec<&str> = prompts[2].iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["a1", "A1", "a2"]);
+
+        let store = SessionStore::new(sessions.path());
+        assert_eq!(store.load("telegram-1").unwrap().unwrap().message_count, 4);
+        assert_eq!(store.load_messages("telegram-2").unwrap().len(), 2);
+    }
+
+    /// 测试回复语言检测与 /lang 固定喵
+    #[tokio::test]
+    async fn test_reply_language_follows_chat() {
+        use crate::gateway::testing::ScriptedProvider;
+
+        let provider = Arc::new(ScriptedProvider::new(["R1", "R2"]));
+        let mut bot = TelegramBot::new("test_token".to_string(), TelegramConfig::default())
+            .unwrap()
+            .with_agent(Arc::new(ChatBackend::new(provider.clone())));
+        bot.add_allowed_chat_id(1);
+
+        let text = |text: &str| TelegramEvent::TextMessage {
+            chat_id: 1,
+            user_id: 1,
+            username: None,
+            text: text.to_string(),
+            timestamp: chrono::Utc::now(),
+        };
+        let lang = TelegramEvent::Command {
+            chat_id: 1,
+            user_id: 1,
+            username: None,
+            command: "lang".to_string(),
+            args: vec!["ja".to_string()],
+            timestamp: chrono::Utc::now(),
+        };
+
+        bot.dispatch(&text("今天的天气怎么样？我想出去散步一下。")).await.unwrap();
+        assert!(bot.dispatch(&lang).await.unwrap().unwrap().contains("jpn"));
+        bot.dispatch(&text("What is the weather like today? I would like to go for a walk."))
+            .await
+            .unwrap();
+
+        let prompts = provider.prompts();
+        assert!(prompts[0][0].content.contains("Mandarin"));
+        // 固定后英文消息也不会切换语言喵
+        assert!(prompts[1][0].content.contains("Japanese"));
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), ["short"]);
+        assert_eq!(split_message("line one\nline two", 12), ["line one\n", "line two"]);
+        // 不会切断多字节字符喵
+        assert!(split_message("喵喵喵", 4).iter().all(|c| c.len() <= 4 && !c.is_empty()));
+    }
 }
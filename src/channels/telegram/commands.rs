@@ -6,8 +6,11 @@
 //! ## 功能说明
 //! - 解析和路由斜杠命令喵
 //! - 提供命令帮助信息喵
-//! - 集成权限控制喵
+//! - 集成权限控制喵（共享的 `Rbac`：每个命令给出默认最低角色，`[rbac.commands]` 可覆盖）
+//! - `/pair` 审批设备配对喵
 
+pub use crate::auth::Role;
+use crate::auth::{Identity, Permission};
 use crate::channels::telegram::bot::{TelegramBot, TelegramEvent};
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -17,9 +20,9 @@ use thiserror::Error;
 /// 命令错误类型喵
 #[derive(Error, Debug)]
 pub enum CommandError {
-    /// 未知命令喵
-    #[error("Unknown command: {0}")]
-    UnknownCommand(String),
+    /// 未知命令喵（附带最接近的候选）
+    #[error("Unknown command: {0}{}", .1.as_ref().map(|s| format!(" (did you mean /{}?)", s)).unwrap_or_default())]
+    UnknownCommand(String, Option<String>),
 
     /// 权限不足喵
     #[error("Insufficient permission for command: {0}")]
@@ -66,20 +69,10 @@ pub struct CommandDefinition {
     pub handler: Box<dyn CommandHandler + Send + Sync>,
 }
 
-/// 权限角色喵
-#[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
-pub enum Role {
-    ReadOnly = 0,
-    Agent = 1,
-    Admin = 2,
-    Owner = 3,
-}
-
 /// 命令服务喵
 pub struct CommandService {
     prefix: char,
     commands: HashMap<String, CommandDefinition>,
-    role_permissions: HashMap<String, Role>,
 }
 
 impl CommandService {
@@ -87,10 +80,8 @@ impl CommandService {
         let mut service = Self {
             prefix: config.prefix,
             commands: HashMap::new(),
-            role_permissions: HashMap::new(),
         };
         service.register_default_commands();
-        service.set_default_permissions();
         service
     }
 
@@ -149,11 +140,28 @@ impl CommandService {
                 handler: Box::new(ShutdownCommandHandler),
             },
         );
-    }
 
-    fn set_default_permissions(&mut self) {
-        self.role_permissions
-            .insert("default".to_string(), Role::ReadOnly);
+        self.commands.insert(
+            "pair".to_string(),
+            CommandDefinition {
+                name: "pair".to_string(),
+                description: "审批设备配对（仅 Owner）".to_string(),
+                usage: "/pair 或 /pair approve|deny <配对码>".to_string(),
+                required_role: Role::Owner,
+                handler: Box::new(PairCommandHandler),
+            },
+        );
+
+        self.commands.insert(
+            "lang".to_string(),
+            CommandDefinition {
+                name: "lang".to_string(),
+                description: "查看或固定本 Chat 的回复语言".to_string(),
+                usage: "/lang 或 /lang <code|auto>".to_string(),
+                required_role: Role::ReadOnly,
+                handler: Box::new(LangCommandHandler),
+            },
+        );
     }
 
     pub async fn handle_command(
@@ -161,7 +169,7 @@ impl CommandService {
         bot: &TelegramBot,
         event: &TelegramEvent,
     ) -> Result<CommandResponse, CommandError> {
-        if let TelegramEvent::Command { command, args, .. } = event {
+        if let TelegramEvent::Command { command, args, user_id, .. } = event {
             let cmd_name = if self.prefix == '/' {
                 command.trim_start_matches('/').to_lowercase()
             } else {
@@ -171,15 +179,14 @@ impl CommandService {
             let cmd_def = self
                 .commands
                 .get(&cmd_name)
-                .ok_or_else(|| CommandError::UnknownCommand(command.clone()))?;
-
-            let user_role = self
-                .role_permissions
-                .get("default")
-                .cloned()
-                .unwrap_or(Role::ReadOnly);
-
-            if user_role < cmd_def.required_role {
+                .ok_or_else(|| {
+                    CommandError::UnknownCommand(command.clone(), self.suggest(&cmd_name))
+                })?;
+
+            let identity = Identity::new("telegram", user_id);
+            let permission = Permission::Command(&cmd_def.name, cmd_def.required_role);
+            if let Err(e) = bot.rbac().check(&identity, permission) {
+                tracing::warn!("⚠️ {}", e);
                 return Err(CommandError::InsufficientPermission(command.clone()));
             }
 
@@ -194,6 +201,12 @@ impl CommandService {
         }
     }
 
+    /// 为拼错的命令找最接近的已注册命令喵
+    pub fn suggest(&self, name: &str) -> Option<String> {
+        crate::core::closest_match(name, self.commands.keys().map(String::as_str))
+            .map(str::to_string)
+    }
+
     pub fn get_help(&self, command: Option<&str>) -> String {
         if let Some(cmd_name) = command {
             if let Some(cmd) = self.commands.get(&cmd_name.to_lowercase()) {
@@ -206,7 +219,7 @@ impl CommandService {
         }
 
         let mut help = "**可用命令:**\n\n".to_string();
-        for (_, cmd) in &self.commands {
+        for cmd in self.commands.values() {
             help.push_str(&format!("• /{} - {}\n", cmd.name, cmd.description));
         }
         help.push_str("\n输入 /help <command> 查看命令详情喵");
@@ -308,6 +321,81 @@ impl CommandHandler for ShutdownCommandHandler {
     }
 }
 
+struct LangCommandHandler;
+
+#[async_trait]
+impl CommandHandler for LangCommandHandler {
+    async fn handle(
+        &self,
+        bot: &TelegramBot,
+        event: &TelegramEvent,
+        args: &[&str],
+    ) -> CommandResponse {
+        let conversation = format!("telegram:{}", event.chat_id());
+        let args = args.join(" ");
+        CommandResponse {
+            text: bot.languages().handle_command(&conversation, Some(args.as_str())),
+            reply: true,
+            parse_mode: ParseMode::Html,
+        }
+    }
+}
+
+struct PairCommandHandler;
+
+#[async_trait]
+impl CommandHandler for PairCommandHandler {
+    async fn handle(
+        &self,
+        bot: &TelegramBot,
+        event: &TelegramEvent,
+        args: &[&str],
+    ) -> CommandResponse {
+        let reply = |text: String| CommandResponse {
+            text,
+            reply: true,
+            parse_mode: ParseMode::Html,
+        };
+        let Some(codes) = bot.device_codes() else {
+            return reply("📱 设备配对未启用喵".to_string());
+        };
+        let TelegramEvent::Command { user_id, .. } = event else {
+            return reply(String::new());
+        };
+        let approver = format!("telegram:{}", user_id);
+
+        let text = match args {
+            [] | ["list"] => match codes.pending() {
+                Ok(pending) if pending.is_empty() => "📭 没有等待审批的设备喵".to_string(),
+                Ok(pending) => {
+                    let mut text = "📱 等待审批的设备:\n".to_string();
+                    for request in pending {
+                        text.push_str(&format!(
+                            "• {} — {}（{}）\n",
+                            request.user_code,
+                            request.device_name,
+                            request.requested_from.as_deref().unwrap_or("unknown")
+                        ));
+                    }
+                    text.push_str("\n/pair approve <配对码> 批准喵");
+                    text
+                }
+                Err(e) => format!("❌ {}", e),
+            },
+            ["approve", code] => match codes.approve(code, &approver) {
+                Ok(request) => format!("✅ 已批准设备 {}，设备下次轮询时领取 Key 喵", request.device_name),
+                Err(e) => format!("❌ {}", e),
+            },
+            ["deny", code] => match codes.deny(code, &approver) {
+                Ok(request) => format!("🚫 已拒绝设备 {} 喵", request.device_name),
+                Err(e) => format!("❌ {}", e),
+            },
+            _ => "用法: /pair 或 /pair approve|deny <配对码>".to_string(),
+        };
+        reply(text)
+    }
+}
+
 impl Default for CommandConfig {
     fn default() -> Self {
         Self {
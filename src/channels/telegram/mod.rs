@@ -13,7 +13,7 @@
 //! - `commands`: 命令解析和路由喵
 //!
 //! ## 使用说明
-//! ```rust
+//! ```ignore
 //! use nekoclaw::channels::telegram::{TelegramBot, TelegramConfig};
 //!
 //! let config = TelegramConfig::default();
@@ -0,0 +1,1230 @@
+//! Agent 会话 🐾
+//!
+//! `nekoclaw agent` 的会话装配与对话循环喵
+//!
+//! - `AgentSession::open` 按配置装配 Provider、工具、提示词、记账与会话存储
+//! - `AgentSession::run_turn` 是一轮对话的工具循环，一次性模式与交互模式共用
+//! - `run_prompts` 处理 `-m` / `-` / `--file` 的一次性与批量消息
+
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+use nekoclaw::core::preset::SamplingParams;
+use nekoclaw::core::{
+    self, AgentEvent, CanaryVerdict, ClaimVerifier, Config, FewShotLibrary, HookRegistry, PromptCanary, ReplyContext,
+    Result, SessionLanguages,
+};
+use nekoclaw::performance::compress::ContextCompressor;
+use nekoclaw::providers::{self, ChatRequest, Message as OpenAIMessage, OpenAIClient, OpenAIConfig, ProviderError, ToolSpec};
+use nekoclaw::skills::{SharedSkills, SkillsManager};
+use nekoclaw::tools::delegate::DelegationUsage;
+use nekoclaw::tools::prompt::PromptSource;
+use nekoclaw::tools::{
+    self, estimate_tokens, format_tool_error_for_llm, format_tool_result_for_llm, format_tools_compact,
+    format_tools_for_llm, parse_tool_calls, render_tools_prompt, AgentInfo, BrainTool, DelegateTool, EchoTool,
+    EscalationTool, FileSystemTool, FsPatchTool, FsSearchTool, FsWriteTool, HistorySearchTool, McpShellTool,
+    PromptCache, ReadTracker, ShellTool, SkillTool, ToolBudget, ToolCallRequest, ToolDescription, ToolPromptMode,
+    ToolRegistry,
+};
+use nekoclaw::{agent, channels, gateway, memory, security, telemetry};
+
+use super::output::{prompt_tool_approval, Output};
+use crate::{
+    build_channel, build_reply_hooks, build_webhook_notifier, cli_actor, memory_embedder, open_audit_log,
+    open_metrics_recorder, open_session_store, COST_DB, OUTBOX_DB, WEBHOOK_FLUSH_TIMEOUT,
+};
+
+/// `--session` 恢复历史与 `/load` 载入会话时最多载入的 token 数喵
+pub const RESUME_TOKEN_BUDGET: usize = 6_000;
+
+/// 模型调用费用（美元，遥测计数器）喵
+const LLM_COST_METRIC: &str = "llm_cost_usd";
+
+/// 语言检测使用的对话名喵
+const CLI_CONVERSATION: &str = "cli";
+
+/// `nekoclaw agent` 的参数喵
+pub struct AgentArgs<'a> {
+    pub provider: &'a str,
+    pub preset: Option<&'a str>,
+    pub agent: Option<&'a str>,
+    pub overrides: &'a core::ModelPreset,
+    pub incognito: bool,
+    pub session_name: Option<&'a str>,
+    pub tool_prompt_mode: Option<ToolPromptMode>,
+    pub assume_yes: bool,
+    pub output: core::AgentOutput,
+    pub config: &'a Config,
+    pub config_dir: &'a Path,
+    pub key_dir: &'a Path,
+    pub sessions_dir: &'a Path,
+    pub scratch_root: &'a Path,
+}
+
+/// 一轮对话的结果喵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnOutcome {
+    /// 拿到了不再调用工具的最终回复
+    Answered,
+    /// Provider 返回了空响应
+    Empty,
+    /// 请求失败
+    Failed(String),
+    /// 预算 / 限额用尽或工具轮数用完
+    Stopped,
+}
+
+/// 系统提示词的组成部分喵（工具 / 技能说明与主体只渲染一次，每轮直接复用）
+struct SystemPrompt {
+    cache: PromptCache,
+    source: PromptSource,
+    tools: Vec<ToolDescription>,
+    persona: String,
+    skills: String,
+    persona_suffix: Option<String>,
+    agent_suffix: Option<String>,
+    response_style: Option<String>,
+    incognito_notice: Option<String>,
+    canary: Option<String>,
+}
+
+impl SystemPrompt {
+    fn tools_section(&self, mode: ToolPromptMode) -> Arc<str> {
+        match mode {
+            ToolPromptMode::Compact => {
+                self.cache.section(self.source, "tools:compact", || format_tools_compact(&self.tools))
+            }
+            _ => self.cache.section(self.source, "tools:full", || format_tools_for_llm(&self.tools)),
+        }
+    }
+
+    fn render(&self, tools_mode: ToolPromptMode, native: bool, language: Option<&str>) -> String {
+        let section = match tools_mode {
+            ToolPromptMode::Compact => "system:compact",
+            _ => "system:full",
+        };
+        // 🧰 原生 function calling 时工具定义随请求发送，不再附带 @tool 文本格式说明喵
+        let base = match native {
+            true => self
+                .cache
+                .section(self.source, "system:native", || format!("{}\n\n{}", self.persona, self.skills)),
+            false => self.cache.section(self.source, section, || format!(
+                "{}\n\n\
+                Available Tools:\n\
+                {}\n\
+                {}\n\n\
+                ===== MANDATORY TOOL CALLING FORMAT =====\n\n\
+                ⚠️ CRITICAL: You MUST use this EXACT format for all tool calls:\n\
+                @tool_name({{\"key\": \"value\"}})\n\
+                \n\
+                ✅ CORRECT Examples:\n\
+                - @fs_read({{\"path\": \"config.toml\"}})\n\
+                - @fs_write({{\"path\": \"test.md\", \"content\": \"hello world\"}})\n\
+                - @echo({{\"message\": \"test\"}})\n\
+                \n\
+                ❌ INCORRECT Formats (NEVER use these):\n\
+                - <tool_name>...</tool_name> ❌ XML format\n\
+                - ``` @tool_name(...) ``` ❌ Markdown code block\n\
+                - [tool: ...] ❌ Bracket format\n\
+                - tool_name(...) ❌ Missing @ prefix\n\
+                \n\
+                📋 Rules:\n\
+                1. Always use @ symbol before tool name\n\
+                2. Use double quotes for strings: {{\"path\": \"file.txt\"}}\n\
+                3. No XML, no Markdown code blocks, no brackets\n\
+                4. Tool call format is: @tool_name({{\"arg1\": \"val1\", \"arg2\": \"val2\"}})\n\
+                5. You can call multiple tools on one line: @fs_read(...) @echo(...)\n\
+                6. After receiving tool results, summarize them nicely for Master喵！\n\n\
+                ===== END TOOL CALLING FORMAT =====",
+                self.persona, self.tools_section(tools_mode), self.skills
+            )),
+        };
+        let mut instruction = base.to_string();
+        for part in [
+            self.persona_suffix.as_deref(),
+            self.agent_suffix.as_deref(),
+            self.response_style.as_deref(),
+            self.incognito_notice.as_deref(),
+            language,
+            // 金丝雀必须放在最末尾喵
+            self.canary.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            instruction = format!("{}\n\n{}", instruction, part);
+        }
+        instruction
+    }
+}
+
+/// 🔒 SAFETY: 一次 `nekoclaw agent` 会话喵
+///
+/// 无痕模式的临时目录随会话一起销毁
+pub struct AgentSession<'a> {
+    pub config: &'a Config,
+    pub config_dir: &'a Path,
+    pub overrides: &'a core::ModelPreset,
+    pub incognito: bool,
+    pub output: Output,
+    pub agent_name: String,
+    /// 当前采样参数（`/preset`、`/model`、`/temperature` 可修改）
+    pub params: SamplingParams,
+    pub registry: ToolRegistry,
+    pub session_store: Option<core::SessionStore>,
+    pub languages: SessionLanguages,
+    /// 参与提示词实验时带变体标签的记录器
+    pub experiment_recorder: Option<telemetry::MetricsRecorder>,
+    /// 最近一条回复的 ID（/feedback 的评价对象）
+    pub last_response_id: Option<String>,
+    agent_profile: core::AgentProfile,
+    session_info: core::SessionInfo,
+    session_id: String,
+    /// `--session` 恢复的历史（已经落盘）
+    resumed: Vec<OpenAIMessage>,
+    client: Arc<dyn providers::ChatProvider>,
+    failover: providers::FailoverChain,
+    agent_recorder: Option<telemetry::MetricsRecorder>,
+    cost_tracker: Arc<providers::CostTracker>,
+    limits: agent::limits::LimitsEnforcer,
+    notifier: Option<gateway::WebhookNotifier>,
+    pager: Arc<tools::OutputPager>,
+    claim_verifier: Option<ClaimVerifier>,
+    delegation_usage: Option<Arc<DelegationUsage>>,
+    native_tools: Option<Vec<ToolSpec>>,
+    tools_mode: ToolPromptMode,
+    system: SystemPrompt,
+    canary: Option<PromptCanary>,
+    summarizer: Option<memory::ConversationSummarizer>,
+    /// 只有恢复的会话或已经摘要过的会话才需要召回
+    has_summaries: bool,
+    compressor: Option<ContextCompressor>,
+    hooks: HookRegistry,
+    response_policy: core::ResponsePolicy,
+    few_shot: FewShotLibrary,
+    _incognito: Option<security::IncognitoSession>,
+}
+
+impl<'a> AgentSession<'a> {
+    /// 🔒 SAFETY: 按配置装配会话喵
+    pub async fn open(args: AgentArgs<'a>) -> Result<Self> {
+        let AgentArgs { config, config_dir, incognito, .. } = args;
+        info!("Agent mode: provider={}, incognito={}", args.provider, incognito);
+        // 🎭 Agent 人设：模型 / 提示词 / 工具 / 记忆 / 限额喵
+        let (agent_name, agent_profile) = config.agent_profile(args.agent)?;
+        // 📤 --output json：回复 / 工具 / 用量 / 错误逐行输出为 JSON，不打印终端提示喵
+        let output = Output::new(args.output);
+        if let Some(display) = &agent_profile.name {
+            output.notice(format!("🎭 Agent: {} ({})", display, agent_name));
+        }
+
+        // 🕶️ 无痕模式：写入重定向到临时目录，结束时销毁喵
+        let incognito_session = if incognito {
+            let session = security::IncognitoSession::start()?;
+            output.notice("🕶️ 无痕模式已启用喵：本次对话不会被保存");
+            Some(session)
+        } else {
+            None
+        };
+
+        let client = build_chat_provider(config, args.provider)?;
+
+        // 📇 会话索引（标题 / 标签）；无痕模式不落盘喵
+        let session_store = match incognito {
+            true => None,
+            false => Some(open_session_store(config, config_dir, args.sessions_dir)?),
+        };
+        // 💾 --session：同名会话存在时恢复最近的对话喵
+        let (mut session_info, resumed) = match (args.session_name, &session_store) {
+            (Some(name), Some(store)) => resume_session(store, name, &agent_name, &output)?,
+            _ => (core::SessionInfo::new(&uuid::Uuid::new_v4().to_string(), &agent_name), Vec::new()),
+        };
+        session_info.encrypted |= session_store.as_ref().is_some_and(|s| s.is_encrypted());
+        let session_id = session_info.session_id.clone();
+
+        // 🧺 会话临时工作区：无痕模式直接使用无痕临时目录喵
+        let scratch = match (&incognito_session, config.scratch.enabled) {
+            (Some(session), _) => Some(security::ScratchSpace::new(
+                session.scratch_dir().to_path_buf(),
+                config.scratch.max_bytes(),
+            )?),
+            (None, true) => Some(security::ScratchSpace::open(
+                args.scratch_root,
+                &session_id,
+                config.scratch.max_bytes(),
+            )?),
+            (None, false) => None,
+        };
+        if let Some(scratch) = &scratch {
+            debug!("Scratch directory: {}", scratch.dir().display());
+        }
+
+        // 🎛️ 采样参数：配置默认值 → 预设 → Agent 模型 → 命令行参数，Agent 限额封顶喵
+        let mut params = config.sampling_params(args.preset, &agent_profile.overrides(args.overrides))?;
+        params.max_tokens = agent_profile.cap_max_tokens(params.max_tokens);
+
+        // 🔧 初始化工具注册表喵
+        // ⚠️ 危险工具执行前确认；无痕模式不写遥测喵
+        let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+        // JSON 输出时不在终端询问（未加 --yes 的危险工具按未确认处理）喵
+        let mut approval = security::ToolApproval::new(&approval_config).with_assume_yes(args.assume_yes);
+        if output.is_console() {
+            approval = approval.with_prompt(prompt_tool_approval);
+        }
+        // 🔀 主模型临时不可用时按 agents.defaults.model.fallback 切换喵
+        let mut failover = providers::FailoverChain::new(&config.agents.defaults.model);
+        let agent_recorder = match incognito {
+            true => None,
+            false => Some(open_metrics_recorder(config_dir).await?.scoped("agent")),
+        };
+        if let Some(recorder) = &agent_recorder {
+            approval = approval.with_recorder(recorder.clone());
+            failover = failover.with_recorder(recorder.clone());
+        }
+        // 💰 费用记账与预算；无痕模式只在内存中统计本次会话喵
+        let cost_tracker = Arc::new(open_cost_tracker(config, config_dir, incognito, &session_id, &agent_name)?);
+        // ⏱️ Agent 限额与费用共用账本，会话时长从本次启动开始计算喵
+        let limits = agent::limits::LimitsEnforcer::new(cost_tracker.ledger(), &agent_name, agent_profile.limits.clone())
+            .with_session_start(chrono::Utc::now());
+        // 📣 超出预算时推送出站 Webhook（无痕模式不推送）喵
+        let notifier = match &agent_recorder {
+            Some(recorder) => build_webhook_notifier(config, args.key_dir, recorder)?,
+            None => None,
+        };
+        let mut registry = ToolRegistry::new()
+            .with_budget(ToolBudget::new(config.tool_budget.clone()))
+            .with_approval(Arc::new(approval));
+        // 🧾 工具执行写入审计日志（无痕模式不落盘）喵
+        if let Some(audit) = open_audit_log(config_dir).filter(|_| !incognito) {
+            registry = registry.with_audit(audit, &cli_actor());
+        }
+
+        // 注册工具
+        let write_root = incognito_session
+            .as_ref()
+            .map(|s| s.scratch_dir())
+            .unwrap_or(config.workspace.as_path());
+        let claim_verifier = register_file_tools(&mut registry, config, write_root, scratch.as_ref());
+        let _ = registry.register(EchoTool);
+        // 🔎 检索过去的对话（无痕模式没有会话存储，Agent 可关闭记忆）喵
+        if let Some(store) = session_store.as_ref().filter(|_| agent_profile.memory_enabled()) {
+            match core::HistoryIndex::open(store.clone()) {
+                Ok(index) => {
+                    let _ = registry.register(HistorySearchTool::new(index));
+                }
+                Err(e) => warn!("对话历史索引打开失败，history_search 不可用喵: {}", e),
+            }
+        }
+
+        // 📚 加载 Skills 动态技能系统喵
+        let mut skills_manager = SkillsManager::new(config.workspace.join("skills"));
+        skills_manager.load_all().ok(); // Skills 加载失败不影响主流程
+        let skills = SharedSkills::new(skills_manager);
+
+        // 🔑 Shell 工具（需配置白名单）+ 放行申请 + 技能执行喵
+        register_shell_tools(&mut registry, config, config_dir, incognito, &skills, scratch.as_ref())?;
+
+        // 🔌 配置中的外部 MCP server：工具与内置工具一样通过 @tool() 调用喵
+        if !config.mcp_servers.is_empty() {
+            let registered = tools::register_mcp_servers(&mut registry, &config.mcp_servers).await;
+            info!("🔌 {} 个 MCP server 共注册 {} 个工具", config.mcp_servers.len(), registered);
+        }
+        // 只保留 Agent 配置中开放的工具喵
+        if agent_profile.tools.is_some() {
+            registry.retain(|name| agent_profile.allows_tool(name));
+        }
+        // 📄 超长工具输出分页：截断提示中的游标通过 read_more 读取下一段，因此不受工具白名单限制喵
+        let pager = Arc::new(tools::OutputPager::new(&config.tool_output));
+        if pager.is_enabled() {
+            let _ = registry.register(tools::ReadMoreTool::new(pager.clone()));
+        }
+        // 🧬 子 Agent 委派：子 Agent 可使用以上工具，用量合并计入本会话费用喵
+        let delegation_usage = match &config.delegation {
+            Some(delegation) if agent_profile.allows_tool("delegate") => {
+                let brain = BrainTool::new(vec![agent_name.clone()]);
+                brain
+                    .register_agent(AgentInfo {
+                        agent_id: agent_name.clone(),
+                        label: agent_profile.name.clone(),
+                        model: Some(params.model.clone()),
+                        last_activity: chrono::Utc::now().to_rfc3339(),
+                        heartbeat_count: 0,
+                    })
+                    .await?;
+                let delegate = DelegateTool::new(client.clone(), &params.model)
+                    .with_config(delegation.clone())
+                    .with_tools(registry.clone())
+                    .with_profiles(config.agents.agent.clone())
+                    .with_cost_tracker(cost_tracker.clone())
+                    .with_output_pager(pager.clone())
+                    .with_brain(brain, &agent_name);
+                let usage = delegate.usage();
+                let _ = registry.register(delegate);
+                Some(usage)
+            }
+            _ => None,
+        };
+
+        let tools_list = registry.all_descriptions();
+        let mut tool_prompt_config = agent_profile.tool_prompt_config(config.tool_prompt.as_ref());
+        if let Some(mode) = args.tool_prompt_mode {
+            tool_prompt_config.mode = mode;
+        }
+        let (tools_prompt, rendered_mode) = render_tools_prompt(&tools_list, &tool_prompt_config);
+        // 🧰 原生 function calling；provider 不支持时整个会话回退到 @tool() 文本解析喵
+        let native_tools = tool_prompt_config
+            .native
+            .then(|| tools_list.iter().map(ToolSpec::from).collect::<Vec<_>>());
+        info!(
+            "Tool prompt: {:?} (~{} tokens)",
+            rendered_mode,
+            estimate_tokens(&tools_prompt)
+        );
+
+        // 无痕模式不注册技能工具，也不在提示词里列出技能喵
+        let skills_prompt = match agent_profile.allows_tool("skill") && !incognito {
+            true => skills.prompt(),
+            false => String::new(),
+        };
+        let skills_count = skills.skills().len();
+        if skills_count > 0 {
+            info!("✅ 成功加载 {} 个 Skills 喵！", skills_count);
+        }
+
+        // 🗃️ 工具 / 技能说明与系统提示词主体只渲染一次，每轮对话直接复用喵
+        let prompt_cache = PromptCache::new();
+        let prompt_source = (registry.revision(), skills.fingerprint());
+        prompt_cache.section(
+            prompt_source,
+            match rendered_mode {
+                ToolPromptMode::Compact => "tools:compact",
+                _ => "tools:full",
+            },
+            move || tools_prompt,
+        );
+
+        // 🐤 提示词金丝雀（检测 provider 静默截断）喵
+        let canary = config.prompt_canary.as_ref().map(PromptCanary::from_config);
+        let canary_instruction = canary.as_ref().map(|c| {
+            debug!("Prompt canary armed: {}", c.marker());
+            c.instruction()
+        });
+
+        // 🧪 提示词 A/B 实验：按会话分配变体，遥测打上变体标签喵
+        let experiment = config.experiment_for(&agent_name)?;
+        let variant = experiment.map(|e| e.assign(&session_id));
+        let experiment_recorder = match (experiment, variant) {
+            (Some(experiment), Some(variant)) => {
+                info!("🧪 实验 {}: 本会话使用变体 {}", experiment.name, variant.name);
+                // 无痕模式不写遥测明细喵
+                if incognito {
+                    None
+                } else {
+                    Some(
+                        open_metrics_recorder(config_dir)
+                            .await?
+                            .scoped("experiments")
+                            .with_label("experiment", &experiment.name)
+                            .with_label("variant", &variant.name)
+                            .with_label("session", &session_id),
+                    )
+                }
+            }
+            _ => None,
+        };
+        // 🐾 人设来自 IDENTITY/SOUL 模板，说话风格由 persona.speech_style 控制；Agent 可整体替换喵
+        let default_persona = match &agent_profile.prompts.system {
+            Some(system) => system.clone(),
+            None => config.persona.render(config_dir)?,
+        };
+        // 📏 CLI 的回复详略与格式（max_tokens 同时受其上限约束）喵
+        let response_policy = config.response_policy("cli");
+        let system = SystemPrompt {
+            cache: prompt_cache,
+            source: prompt_source,
+            tools: tools_list,
+            persona: variant.map_or(default_persona.as_str(), |v| v.persona(&default_persona)).to_string(),
+            skills: skills_prompt,
+            persona_suffix: variant.and_then(|v| v.suffix.clone()),
+            agent_suffix: agent_profile.prompts.suffix.clone(),
+            response_style: response_policy.instruction("cli"),
+            incognito_notice: incognito_session.as_ref().map(|s| s.prompt_notice()),
+            canary: canary_instruction,
+        };
+
+        // 🔢 按模型选择 token 计数器（上下文检查 / 压缩阈值 / 摘要触发共用）喵
+        providers::tokenizer::install(config.tokenizer.counter_for_model(config_dir, &params.model));
+
+        // 🧠 历史过长时先把旧消息摘要存入记忆再裁剪；无痕模式不落盘喵
+        let summarizer = match incognito || !agent_profile.memory_enabled() {
+            true => None,
+            false => open_summarizer(config, client.clone(), &params.model),
+        };
+
+        // 🎯 Few-shot 示例（紧跟系统提示词）喵
+        let few_shot = FewShotLibrary::new(agent_profile.few_shot_config(config.few_shot.as_ref()))
+            .with_workspace(config_dir)?;
+        if !few_shot.is_empty() {
+            info!("Loaded {} few-shot examples", few_shot.len());
+        }
+
+        output.emit(&AgentEvent::Session {
+            session_id: &session_id,
+            agent: &agent_name,
+            model: &params.model,
+        });
+
+        Ok(Self {
+            config,
+            config_dir,
+            overrides: args.overrides,
+            incognito,
+            output,
+            agent_name,
+            params,
+            registry,
+            session_store,
+            languages: SessionLanguages::new(),
+            experiment_recorder,
+            last_response_id: None,
+            agent_profile,
+            session_info,
+            session_id,
+            resumed,
+            client,
+            failover,
+            agent_recorder,
+            cost_tracker,
+            limits,
+            notifier,
+            pager,
+            claim_verifier,
+            delegation_usage,
+            native_tools,
+            tools_mode: rendered_mode,
+            system,
+            canary,
+            summarizer,
+            has_summaries: args.session_name.is_some(),
+            // 🗜️ 请求超过 compression.compression_threshold 时按策略压缩（不修改历史）喵
+            compressor: config.compression.compressor(),
+            // 🪝 回复钩子（后处理链）喵
+            hooks: build_reply_hooks(config)?,
+            response_policy,
+            few_shot,
+            _incognito: incognito_session,
+        })
+    }
+
+    /// 当前的系统提示词消息喵（工具模式、原生工具与会话语言变化后重新生成）
+    pub fn system_message(&self) -> OpenAIMessage {
+        OpenAIMessage::system(self.system.render(
+            self.tools_mode,
+            self.native_tools.is_some(),
+            self.languages.instruction(CLI_CONVERSATION).as_deref(),
+        ))
+    }
+
+    /// 对话开头喵：系统提示词 + Few-shot 示例
+    pub fn preamble(&self) -> Vec<OpenAIMessage> {
+        let mut history = vec![self.system_message()];
+        history.extend(self.few_shot.select_messages());
+        history
+    }
+
+    /// 取出 `--session` 恢复的历史喵（交互模式只取一次）
+    pub fn take_resumed(&mut self) -> Vec<OpenAIMessage> {
+        std::mem::take(&mut self.resumed)
+    }
+
+    /// 检测输入语言喵（系统提示词在下一次 `system_message` 时更新）
+    pub fn observe_language(&self, input: &str) {
+        self.languages.observe(CLI_CONVERSATION, input);
+    }
+
+    /// 处理 `/lang` 命令喵
+    pub fn language_command(&self, args: &str) -> String {
+        self.languages.handle_command(CLI_CONVERSATION, Some(args))
+    }
+
+    /// 新一轮开始前清空回复核对记录喵
+    pub fn reset_claims(&mut self) {
+        if let Some(verifier) = self.claim_verifier.as_mut() {
+            verifier.reset();
+        }
+    }
+
+    /// 📝 历史过长时摘要旧消息后裁剪喵
+    ///
+    /// ## Returns
+    /// 从 `history[prefix_len..]` 开头移除的消息数
+    pub async fn compact_history(&mut self, history: &mut Vec<OpenAIMessage>, prefix_len: usize) -> usize {
+        let Some(summarizer) = &self.summarizer else {
+            return 0;
+        };
+        match summarizer.compact(&self.session_id, history, prefix_len).await {
+            Ok(0) => 0,
+            Ok(evicted) => {
+                info!("Summarized and trimmed {} earlier messages", evicted);
+                self.has_summaries = true;
+                evicted
+            }
+            Err(e) => {
+                warn!("对话摘要失败，本轮不裁剪历史喵: {}", e);
+                0
+            }
+        }
+    }
+
+    /// 召回本会话的摘要，合并为一条系统消息喵
+    pub async fn recall(&self, input: &str) -> Option<OpenAIMessage> {
+        let summarizer = self.summarizer.as_ref().filter(|_| self.has_summaries)?;
+        match summarizer.recall(&self.session_id, input).await {
+            Ok(items) => memory::summary::recall_message(&items),
+            Err(e) => {
+                warn!("召回对话摘要失败喵: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 已达到费用预算或 Agent 限额时提示主人并返回 true 喵
+    pub fn requests_blocked(&self) -> bool {
+        self.budget_exhausted() || self.limit_reached()
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        match self.cost_tracker.check() {
+            Ok(()) => false,
+            Err(e @ providers::CostError::BudgetExceeded { .. }) => {
+                self.output.error(&e.to_string(), format!("💸 {}，已停止请求喵", e));
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(
+                        gateway::webhook::AgentEventKind::BudgetExceeded,
+                        serde_json::json!({ "agent": self.cost_tracker.agent(), "error": e.to_string() }),
+                    );
+                }
+                true
+            }
+            Err(e) => {
+                warn!("费用预算检查失败喵: {}", e);
+                false
+            }
+        }
+    }
+
+    fn limit_reached(&self) -> bool {
+        match self.limits.check(chrono::Utc::now()) {
+            Ok(_) => false,
+            Err(agent::limits::LimitError::Ledger(e)) => {
+                warn!("Agent 限额检查失败喵: {}", e);
+                false
+            }
+            Err(e) => {
+                self.output.error(&e.to_string(), format!("⏱️ {}，已停止请求喵", e));
+                true
+            }
+        }
+    }
+
+    /// 🔒 SAFETY: 一轮对话喵（`history` 末尾是本轮的用户消息）
+    ///
+    /// 循环执行工具调用，直到拿到不再调用工具的回复、请求失败或用完工具轮数；
+    /// 终端模式下回复带 `label` 前缀打印
+    pub async fn run_turn(
+        &mut self,
+        history: &mut Vec<OpenAIMessage>,
+        recalled: Option<&OpenAIMessage>,
+        label: &str,
+    ) -> TurnOutcome {
+        let mut rounds = 0;
+        while rounds < self.agent_profile.tool_rounds() {
+            if self.requests_blocked() {
+                return TurnOutcome::Stopped;
+            }
+            let request = ChatRequest {
+                model: Some(self.params.model.clone()),
+                messages: compress_context(
+                    self.compressor.as_mut(),
+                    with_recalled(history, recalled),
+                    self.agent_recorder.as_ref(),
+                ),
+                temperature: Some(self.params.temperature),
+                top_p: self.params.top_p,
+                max_tokens: Some(self.response_policy.cap_max_tokens(self.params.max_tokens)),
+                stream: Some(false),
+                tools: self.native_tools.clone(),
+            };
+
+            let started = std::time::Instant::now();
+            let result = self.failover.chat(self.client.as_ref(), &request).await;
+            if let Err(ProviderError::ToolsUnsupported(reason)) = &result {
+                warn!("Provider 不支持原生工具调用，改用文本格式喵: {}", reason);
+                self.native_tools = None;
+                history[0] = self.system_message();
+                continue;
+            }
+            record_experiment_turn(self.experiment_recorder.as_ref(), started, result.is_ok());
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("Agent error: {}", e);
+                    return TurnOutcome::Failed(e.to_string());
+                }
+            };
+            providers::tokenizer::observe_usage(&request.messages, response.usage.prompt_tokens);
+            self.record_cost(&response.model, &response.usage);
+            let Some(choice) = response.choices.first() else {
+                return TurnOutcome::Empty;
+            };
+            let Some(reply) = check_reply_canary(&mut self.canary, &choice.message) else {
+                self.tools_mode = ToolPromptMode::Compact;
+                history[0] = self.system_message();
+                continue;
+            };
+            if !reply.is_empty() {
+                let context = ReplyContext { channel: "cli", agent: &self.agent_name };
+                self.output.assistant(label, &self.hooks.apply_reply(&context, reply.clone()));
+            }
+            history.push(OpenAIMessage::assistant(reply.clone()).with_tool_calls(choice.message.tool_calls.clone()));
+            self.last_response_id = Some(uuid::Uuid::new_v4().to_string());
+
+            let tool_calls = agent_tool_calls(&choice.message, &reply);
+            if tool_calls.is_empty() {
+                self.output.discrepancies(self.claim_verifier.as_ref(), &reply);
+                return TurnOutcome::Answered;
+            }
+            for call in tool_calls {
+                self.output.tool_call(&call);
+                let result = self.registry.execute(&call.tool_name, call.arguments.clone()).await;
+                if let Some(verifier) = self.claim_verifier.as_mut() {
+                    verifier.record(&call.tool_name, &call.arguments, &result);
+                }
+                let result_text = match &result {
+                    Ok(res) => format_tool_result_for_llm(res, Some(&self.pager)),
+                    Err(e) => format_tool_error_for_llm(e),
+                };
+                self.output.tool_outcome(&call, &result, &result_text);
+                history.push(tool_result_message(&call, result_text));
+            }
+            rounds += 1;
+        }
+        TurnOutcome::Stopped
+    }
+
+    /// 记录一次请求的费用喵（响应没有模型名时使用请求的模型）
+    fn record_cost(&self, response_model: &str, usage: &providers::Usage) {
+        let model = match response_model.is_empty() {
+            true => self.params.model.as_str(),
+            false => response_model,
+        };
+        let cost = match self.cost_tracker.charge(model, usage) {
+            Ok(cost) => {
+                debug!("Request cost: ${:.6} ({} + {} tokens, {})", cost, usage.prompt_tokens, usage.completion_tokens, model);
+                if let (Some(recorder), true) = (&self.agent_recorder, cost > 0.0) {
+                    recorder.counter(LLM_COST_METRIC, cost, &[("model", model)]);
+                }
+                cost
+            }
+            Err(e) => {
+                warn!("记录费用失败喵: {}", e);
+                0.0
+            }
+        };
+        self.output.emit(&AgentEvent::Usage {
+            model,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cost,
+        });
+    }
+
+    /// 保存本轮对话并更新会话索引喵
+    ///
+    /// `transcript[turn_start..]` 是本轮新增的消息（用户输入在前）
+    pub async fn save_turn(&mut self, transcript: &[OpenAIMessage], turn_start: usize) {
+        update_session_index(
+            self.session_store.as_ref(),
+            &mut self.session_info,
+            transcript,
+            turn_start,
+            self.client.as_ref(),
+            self.config,
+            &self.params.model,
+        )
+        .await;
+    }
+
+    /// 会话结束喵：输出用量统计并等待出站 Webhook 投递
+    pub async fn finish(self) {
+        if let Some(usage) = self.registry.budget_usage() {
+            debug!(
+                "Tool budget used: {} calls, {} shell, {} bytes written",
+                usage.calls, usage.shell_calls, usage.bytes_written
+            );
+        }
+        let (hits, misses) = self.system.cache.stats();
+        debug!("Prompt cache: {} hits, {} misses", hits, misses);
+        if let Some(usage) = self.delegation_usage.filter(|u| u.requests() > 0) {
+            info!(
+                "🧬 子 Agent 共 {} 次请求（{} + {} tokens）",
+                usage.requests(),
+                usage.prompt_tokens(),
+                usage.completion_tokens()
+            );
+        }
+        if let Ok(spent) = self.cost_tracker.session_spent() {
+            debug!("Session cost: ${:.4}", spent);
+        }
+        if let Some(notifier) = &self.notifier {
+            notifier.flush(WEBHOOK_FLUSH_TIMEOUT).await;
+        }
+    }
+}
+
+/// 一次性 / 批量模式喵：每条消息独立请求
+///
+/// ## Returns
+/// 没有拿到最终回复的消息数
+pub async fn run_prompts(session: &mut AgentSession<'_>, prompts: &[String]) -> usize {
+    let batch = prompts.len() > 1;
+    let resumed = session.take_resumed();
+    let mut failures = 0;
+    for (index, msg) in prompts.iter().enumerate() {
+        if batch {
+            session.output.prompt(index, prompts.len(), msg);
+        }
+        session.reset_claims();
+        info!("Processing message: {}", msg);
+        session.observe_language(msg);
+        let mut history = session.preamble();
+        // 恢复的历史已经落盘，只保存之后新增的消息喵
+        history.extend(resumed.iter().cloned());
+        let prefix_len = history.len();
+        history.push(OpenAIMessage::user(msg.clone()));
+        let recalled = session.recall(msg).await;
+
+        let outcome = session.run_turn(&mut history, recalled.as_ref(), "🤖 Agent response:\n").await;
+        report_failure(&session.output, &outcome);
+        session.save_turn(&history[prefix_len..], 0).await;
+        if outcome != TurnOutcome::Answered {
+            failures += 1;
+        }
+    }
+    if batch {
+        session
+            .output
+            .notice(format!("📄 批量完成喵: {} 条成功，{} 条失败", prompts.len() - failures, failures));
+    }
+    failures
+}
+
+/// 报告请求失败与空响应喵
+pub fn report_failure(output: &Output, outcome: &TurnOutcome) {
+    match outcome {
+        TurnOutcome::Failed(message) => output.error(message, format!("❌ 对话失败: {}", message)),
+        TurnOutcome::Empty => output.error("Empty response", "❌ 没有收到回应喵"),
+        TurnOutcome::Answered | TurnOutcome::Stopped => {}
+    }
+}
+
+/// `--provider` 对应的对话客户端喵
+///
+/// Agent 只依赖 `ChatProvider`，不认识的名称回退到 NVIDIA（OpenAI 兼容）
+fn build_chat_provider(config: &Config, provider: &str) -> Result<Arc<dyn providers::ChatProvider>> {
+    // 🧠 --provider anthropic：通过 Messages API 原生调用工具（tool_use / tool_result）喵
+    let client: Arc<dyn providers::ChatProvider> = if provider == "anthropic" {
+        let anthropic = config.provider("anthropic").expect("anthropic is a known provider");
+        if anthropic.api_key.is_empty() {
+            return Err("provider 'anthropic' 未配置 API Key 喵（providers.anthropic 或 ANTHROPIC_API_KEY）".into());
+        }
+        let client = providers::AnthropicClient::new(providers::AnthropicConfig {
+            api_key: anthropic.api_key,
+            base_url: anthropic.base_url,
+            timeout: anthropic.timeout,
+            max_retries: anthropic.max_retries,
+        });
+        Arc::new(client.with_default_model(&config.default_model))
+    } else if provider == "gemini" {
+        // ✨ --provider gemini：Generative Language API（systemInstruction + functionCall）喵
+        let gemini = config.provider("gemini").expect("gemini is a known provider");
+        if gemini.api_key.is_empty() {
+            return Err("provider 'gemini' 未配置 API Key 喵（providers.gemini 或 GEMINI_API_KEY）".into());
+        }
+        let client = providers::GeminiClient::new(providers::GeminiConfig {
+            api_key: gemini.api_key,
+            base_url: gemini.base_url,
+            timeout: gemini.timeout,
+            max_retries: gemini.max_retries,
+        });
+        Arc::new(client.with_default_model(&config.default_model))
+    } else if provider == "ollama" {
+        // 🦙 --provider ollama：本地模型；base_url 以 /v1 结尾时按 OpenAI 兼容端点（llama.cpp server）调用喵
+        let ollama = config.provider("ollama").expect("ollama is a known provider");
+        if providers::ollama::is_openai_compatible(&ollama.base_url) {
+            Arc::new(OpenAIClient::new(OpenAIConfig {
+                api_key: ollama.api_key,
+                base_url: ollama.base_url,
+                timeout: ollama.timeout,
+                max_retries: ollama.max_retries,
+            }))
+        } else {
+            let client = providers::OllamaClient::new(providers::OllamaConfig {
+                api_key: ollama.api_key,
+                base_url: ollama.base_url,
+                timeout: ollama.timeout,
+                max_retries: ollama.max_retries,
+            });
+            Arc::new(client.with_default_model(&config.default_model))
+        }
+    } else {
+        // 获取 NVIDIA 配置 - 从 providers.nvidia 读取（`enc:` API Key 在此解密）
+        if config.providers.as_ref().and_then(|p| p.nvidia.as_ref()).is_none() {
+            warn!("未找到 NVIDIA 配置喵，使用默认值");
+        }
+        let mut nvidia_config = config.provider("nvidia").expect("nvidia is a known provider");
+        if nvidia_config.api_key.is_empty() {
+            nvidia_config.api_key = "missing_api_key".to_string();
+        }
+
+        // 创建 NVIDIA (OpenAI 兼容) 客户端
+        let openai_config = OpenAIConfig {
+            api_key: nvidia_config.api_key,
+            base_url: nvidia_config.base_url,
+            timeout: nvidia_config.timeout,
+            max_retries: nvidia_config.max_retries,
+        };
+
+        Arc::new(OpenAIClient::new(openai_config))
+    };
+    Ok(client)
+}
+
+/// 载入 `--session` 指定的会话喵（不存在时新建）
+fn resume_session(
+    store: &core::SessionStore,
+    name: &str,
+    agent_name: &str,
+    output: &Output,
+) -> Result<(core::SessionInfo, Vec<OpenAIMessage>)> {
+    core::session::validate_session_name(name)?;
+    match store.load(name)? {
+        Some(info) => {
+            let stored = store.load_messages(name)?;
+            let resumed = core::session::resume_messages(&stored, RESUME_TOKEN_BUDGET);
+            output.notice(format!(
+                "💾 恢复会话 {}（共 {} 条消息，载入最近 {} 条）喵",
+                info.display_name(),
+                info.message_count,
+                resumed.len()
+            ));
+            Ok((info, resumed))
+        }
+        None => {
+            output.notice(format!("💾 新建会话 {} 喵", name));
+            Ok((core::SessionInfo::new(name, agent_name), Vec::new()))
+        }
+    }
+}
+
+/// 注册文件读写 / 搜索 / 补丁工具喵
+///
+/// ## Returns
+/// 最终回复与工具结果的核对器（写入根目录与 fs_write 一致；未配置时为 None）
+fn register_file_tools(
+    registry: &mut ToolRegistry,
+    config: &Config,
+    write_root: &Path,
+    scratch: Option<&security::ScratchSpace>,
+) -> Option<ClaimVerifier> {
+    let workspace = &config.workspace;
+    // ✋ fs_read 看到的内容与 fs_write 共享，避免覆盖主人之后的修改喵
+    let read_tracker = Arc::new(ReadTracker::new());
+    let mut fs_read = FileSystemTool::new(workspace).with_tracker(read_tracker.clone());
+    let mut fs_write = FsWriteTool::new(write_root).with_tracker(read_tracker.clone());
+    if let Some(scratch) = scratch {
+        fs_read = fs_read.with_scratch(scratch.clone());
+        fs_write = fs_write.with_scratch(scratch.clone());
+    }
+    // 🔍 搜索与补丁式修改：配置了路径白名单时逐个文件检查喵
+    let mut fs_search = FsSearchTool::new(workspace);
+    let mut fs_patch = FsPatchTool::new(write_root).with_tracker(read_tracker);
+    if let Some(allowlist) = config.security.as_ref().and_then(|s| s.allowlist.clone()) {
+        let allowlist = Arc::new(security::AllowlistService::new(allowlist));
+        fs_search = fs_search.with_allowlist(allowlist.clone());
+        fs_patch = fs_patch.with_allowlist(allowlist);
+    }
+    let _ = registry.register(fs_read);
+    let _ = registry.register(fs_write);
+    let _ = registry.register(fs_search);
+    let _ = registry.register(fs_patch);
+
+    config.claim_check.clone().map(|c| {
+        let verifier = ClaimVerifier::new(c, write_root);
+        match scratch {
+            Some(scratch) => verifier.with_scratch_dir(scratch.dir()),
+            None => verifier,
+        }
+    })
+}
+
+/// 注册 Shell、放行申请与技能执行工具喵（未配置命令白名单时不注册）
+fn register_shell_tools(
+    registry: &mut ToolRegistry,
+    config: &Config,
+    config_dir: &Path,
+    incognito: bool,
+    skills: &SharedSkills,
+    scratch: Option<&security::ScratchSpace>,
+) -> Result<()> {
+    let Some(allowlist) = config.security.as_ref().and_then(|s| s.allowlist.clone()) else {
+        return Ok(());
+    };
+    let escalation = Arc::new(open_escalation_manager(config, config_dir, incognito)?);
+    let environment = match config.security.as_ref().and_then(|s| s.tool_env.as_ref()) {
+        Some(tool_env) => security::ToolEnvironment::resolve(tool_env)?,
+        None => security::ToolEnvironment::minimal(),
+    };
+    let limits = config.security.as_ref().map(|s| s.shell.clone()).unwrap_or_default();
+    let allowlist = security::AllowlistService::new(allowlist);
+    let skill_tool = SkillTool::new(skills.clone(), allowlist.clone())
+        .with_environment(environment.clone())
+        .with_limits(limits.clone());
+    let mut shell = ShellTool::new(Arc::new(allowlist))
+        .with_environment(environment)
+        .with_working_dir(&config.workspace)
+        .with_limits(limits)
+        .with_escalation(escalation.clone());
+    // 🕶️ 无痕模式只在无痕临时目录中执行命令；技能在持久的技能目录中运行，不注册喵
+    shell = match (scratch, incognito) {
+        (Some(scratch), true) => shell.with_scratch_only(scratch.clone()),
+        (Some(scratch), false) => shell.with_scratch(scratch.clone()),
+        (None, _) => shell,
+    };
+    let _ = registry.register(McpShellTool::new(shell));
+    let _ = registry.register(EscalationTool::new(escalation));
+    if !skill_tool.is_empty() && !incognito {
+        let _ = registry.register(skill_tool);
+    }
+    Ok(())
+}
+
+/// 打开对话摘要器喵（未启用或记忆库打开失败时为 None）
+fn open_summarizer(
+    config: &Config,
+    client: Arc<dyn providers::ChatProvider>,
+    model: &str,
+) -> Option<memory::ConversationSummarizer> {
+    let settings = config.memory.clone().unwrap_or_default();
+    if !settings.summaries.enabled {
+        return None;
+    }
+    let memory_path = config.memory_db_path();
+    if let Some(parent) = memory_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match memory::MemoryFactory::open_sqlite(&memory_path.to_string_lossy(), &settings) {
+        Ok(memory) => {
+            let memory = memory.with_embedder(memory_embedder(config, &settings.embedding), settings.embedding.min_similarity);
+            Some(memory::ConversationSummarizer::new(client, model, Arc::new(memory), settings.summaries))
+        }
+        Err(e) => {
+            warn!("记忆库打开失败，对话摘要不可用喵: {}", e);
+            None
+        }
+    }
+}
+
+/// 召回的摘要紧跟系统提示词放进请求（不写入历史）喵
+fn with_recalled(history: &[OpenAIMessage], recalled: Option<&OpenAIMessage>) -> Vec<OpenAIMessage> {
+    let mut messages = history.to_vec();
+    if let Some(recalled) = recalled {
+        messages.insert(1.min(messages.len()), recalled.clone());
+    }
+    messages
+}
+
+/// 压缩请求消息，发生压缩时把统计写入遥测喵
+fn compress_context(
+    compressor: Option<&mut ContextCompressor>,
+    mut messages: Vec<OpenAIMessage>,
+    recorder: Option<&telemetry::MetricsRecorder>,
+) -> Vec<OpenAIMessage> {
+    if let Some(compressor) = compressor {
+        compressor.compress_and_record(&mut messages, recorder);
+    }
+    messages
+}
+
+/// 打开本会话的费用追踪喵（无痕模式使用内存账本）
+fn open_cost_tracker(
+    config: &Config,
+    config_dir: &Path,
+    incognito: bool,
+    session_id: &str,
+    agent: &str,
+) -> Result<providers::CostTracker> {
+    let ledger = match incognito {
+        true => providers::CostLedger::in_memory()?,
+        false => providers::CostLedger::open(config_dir.join(COST_DB))?,
+    };
+    Ok(providers::CostTracker::new(Arc::new(ledger), config.costs.clone(), session_id, agent))
+}
+
+/// 保存本轮对话并更新会话索引；消息数达到阈值时生成标题与标签喵
+///
+/// `transcript[turn_start..]` 是本轮新增的消息（用户输入在前）
+async fn update_session_index(
+    store: Option<&core::SessionStore>,
+    info: &mut core::SessionInfo,
+    transcript: &[OpenAIMessage],
+    turn_start: usize,
+    client: &dyn providers::ChatProvider,
+    config: &Config,
+    current_model: &str,
+) {
+    let Some(store) = store else {
+        return;
+    };
+    let turn = &transcript[turn_start..];
+    if let Err(e) = store.append_messages(&info.session_id, turn) {
+        warn!("保存会话消息失败喵: {}", e);
+    }
+
+    // 消息数 = 用户输入 + Agent 回复（工具结果不计）喵
+    let before = info.message_count;
+    info.record_message();
+    if turn.len() > 1 {
+        info.record_message();
+    }
+
+    // 首次达到阈值时生成，失败后每隔同样条数重试一次喵
+    let title_config = &config.session_titles;
+    let every = title_config.after_messages.max(1);
+    if info.needs_title(title_config) && before / every < info.message_count / every {
+        let request = ChatRequest {
+            model: Some(title_config.model.clone().unwrap_or_else(|| current_model.to_string())),
+            messages: core::session::title_request_messages(transcript),
+            temperature: Some(0.2),
+            top_p: None,
+            max_tokens: Some(64),
+            stream: Some(false),
+            tools: None,
+        };
+        match client.chat(&request).await {
+            Ok(response) => {
+                let parsed = response.choices.first().and_then(|c| {
+                    core::session::parse_title_response(&c.message.content, title_config.max_tags)
+                });
+                match parsed {
+                    Some((title, tags)) => {
+                        debug!("Session {} titled: {} {:?}", info.session_id, title, tags);
+                        info.title = Some(title);
+                        info.tags = tags;
+                    }
+                    None => warn!("会话标题生成结果无法解析喵"),
+                }
+            }
+            Err(e) => warn!("会话标题生成失败喵: {}", e),
+        }
+    }
+
+    if let Err(e) = store.save(info) {
+        warn!("保存会话索引失败喵: {}", e);
+    }
+}
+
+/// 记录一轮实验对话（延迟 + 成败）喵
+fn record_experiment_turn(
+    recorder: Option<&telemetry::MetricsRecorder>,
+    started: std::time::Instant,
+    success: bool,
+) {
+    if let Some(recorder) = recorder {
+        let outcome = if success { "success" } else { "error" };
+        recorder.gauge(
+            core::experiment::TURN_METRIC,
+            started.elapsed().as_secs_f64() * 1000.0,
+            &[("outcome", outcome)],
+        );
+    }
+}
+
+/// 检查提示词金丝雀喵
+///
+/// ## Returns
+/// Some(回复正文) = 继续处理喵，None = 应改用精简工具提示词重发本轮喵
+fn check_prompt_canary(canary: &mut Option<PromptCanary>, reply: &str) -> Option<String> {
+    let Some(canary) = canary.as_mut() else {
+        return Some(reply.to_string());
+    };
+    match canary.check(reply) {
+        CanaryVerdict::Pass(body) => Some(body),
+        CanaryVerdict::RetryCompact => {
+            warn!("⚠️ 回复缺少提示词金丝雀，系统提示词可能被 provider 截断，改用精简工具说明重试喵");
+            None
+        }
+        CanaryVerdict::Degraded(body) => {
+            warn!("⚠️ 精简后仍未收到提示词金丝雀，工具说明可能不完整喵");
+            Some(body)
+        }
+    }
+}
+
+/// 检查回复中的金丝雀喵
+///
+/// 只含结构化工具调用的回复没有正文，留到下一条文本回复再检查
+fn check_reply_canary(canary: &mut Option<PromptCanary>, message: &OpenAIMessage) -> Option<String> {
+    match &message.tool_calls {
+        Some(_) if message.content.trim().is_empty() => Some(String::new()),
+        _ => check_prompt_canary(canary, &message.content),
+    }
+}
+
+/// 本轮的工具调用：优先使用结构化 tool_calls，没有时回退到 @tool() 文本解析喵
+fn agent_tool_calls(message: &OpenAIMessage, reply: &str) -> Vec<ToolCallRequest> {
+    match &message.tool_calls {
+        Some(calls) if !calls.is_empty() => calls.iter().map(|c| c.to_request()).collect(),
+        _ => parse_tool_calls(reply),
+    }
+}
+
+/// 工具结果消息：结构化调用以 tool 角色回应，文本调用沿用 user 消息喵
+fn tool_result_message(call: &ToolCallRequest, result_text: String) -> OpenAIMessage {
+    match &call.call_id {
+        Some(id) => OpenAIMessage::tool(id.clone(), result_text),
+        None => OpenAIMessage::user(format!("Tool result for {}: {}", call.tool_name, result_text)),
+    }
+}
+
+/// 打开放行申请管理器（按配置连接主人渠道）喵
+fn open_escalation_manager(
+    config: &Config,
+    config_dir: &Path,
+    incognito: bool,
+) -> Result<security::EscalationManager> {
+    let manager = if incognito {
+        security::EscalationManager::in_memory()
+    } else {
+        security::EscalationManager::open(config_dir.to_path_buf())?
+    };
+
+    let Some(escalation) = config.security.as_ref().and_then(|s| s.escalation.as_ref()) else {
+        return Ok(manager);
+    };
+
+    let mut channel = build_channel(config, &escalation.owner_channel)?;
+    if !incognito {
+        // 发送失败的申请进入死信队列，由守护进程重试喵
+        let outbox = Arc::new(channels::Outbox::open(config_dir.join(OUTBOX_DB))?);
+        channel = Arc::new(channels::DeadLetterChannel::new(
+            &escalation.owner_channel,
+            channel,
+            outbox,
+        ));
+    }
+
+    Ok(manager.with_owner_channel(channel, escalation.owner_target.clone()))
+}
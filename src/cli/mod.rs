@@ -0,0 +1,9 @@
+//! 命令行子命令 🖥️
+//!
+//! - `agent`：Agent 会话装配与对话循环
+//! - `repl`：交互式对话与斜杠命令
+//! - `output`：终端输出与 `--output json` 事件流
+
+pub mod agent;
+pub mod output;
+pub mod repl;
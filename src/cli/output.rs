@@ -0,0 +1,133 @@
+//! Agent 输出 📤
+//!
+//! 终端输出与 `--output json` 事件流的统一出口喵
+//!
+//! - 终端模式：回复、工具提示与错误直接打印，危险工具在终端询问主人
+//! - JSON 模式：回复 / 工具 / 用量 / 错误逐行输出为 NDJSON 事件，不打印终端提示
+
+use std::io::{IsTerminal, Write};
+
+use nekoclaw::core::{self, AgentEvent, ClaimVerifier, EventStream};
+use nekoclaw::security;
+use nekoclaw::tools::{ToolCallRequest, ToolError};
+
+/// 🔒 SAFETY: Agent 输出喵（JSON 模式下持有事件流）
+pub struct Output {
+    events: Option<EventStream>,
+}
+
+impl Output {
+    pub fn new(format: core::AgentOutput) -> Self {
+        Self {
+            events: (format == core::AgentOutput::Json).then(EventStream::stdout),
+        }
+    }
+
+    /// 是否为终端输出喵（JSON 模式下不打印提示、不在终端询问）
+    pub fn is_console(&self) -> bool {
+        self.events.is_none()
+    }
+
+    /// 终端模式下打印一行提示喵
+    pub fn notice(&self, text: impl std::fmt::Display) {
+        if self.is_console() {
+            println!("{}", text);
+        }
+    }
+
+    /// JSON 模式下输出事件喵
+    pub fn emit(&self, event: &AgentEvent<'_>) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
+
+    /// 批量模式中开始处理第 `index` 条消息喵
+    pub fn prompt(&self, index: usize, total: usize, text: &str) {
+        match &self.events {
+            Some(events) => events.emit(&AgentEvent::Prompt { index, text }),
+            None => println!("\n📄 [{}/{}] {}", index + 1, total, text),
+        }
+    }
+
+    /// 助手回复喵（终端模式下带上 `label` 前缀）
+    pub fn assistant(&self, label: &str, text: &str) {
+        match &self.events {
+            Some(events) => events.emit(&AgentEvent::Assistant { text }),
+            None => println!("{}{}", label, text),
+        }
+    }
+
+    /// 错误喵（JSON 模式下为 error 事件，终端模式下打印 `console`）
+    pub fn error(&self, message: &str, console: impl std::fmt::Display) {
+        match &self.events {
+            Some(events) => events.emit(&AgentEvent::Error { message }),
+            None => println!("{}", console),
+        }
+    }
+
+    /// 提示即将执行的工具喵（JSON 输出时带上参数）
+    pub fn tool_call(&self, call: &ToolCallRequest) {
+        match &self.events {
+            Some(events) => events.emit(&AgentEvent::ToolCall {
+                id: call.call_id.as_deref(),
+                name: &call.tool_name,
+                arguments: &call.arguments,
+            }),
+            None => println!("🔧 执行工具: {}...", call.tool_name),
+        }
+    }
+
+    /// 工具执行结果喵（终端只提示失败，JSON 输出时带上交给模型的完整结果）
+    pub fn tool_outcome<T>(&self, call: &ToolCallRequest, result: &Result<T, ToolError>, result_text: &str) {
+        match (&self.events, result) {
+            (Some(events), _) => events.emit(&AgentEvent::ToolResult {
+                id: call.call_id.as_deref(),
+                name: &call.tool_name,
+                ok: result.is_ok(),
+                output: result_text,
+            }),
+            (None, Err(e)) => report_tool_error(e),
+            (None, Ok(_)) => {}
+        }
+    }
+
+    /// 终端模式下提示最终回复与工具实际结果不符之处喵
+    pub fn discrepancies(&self, verifier: Option<&ClaimVerifier>, reply: &str) {
+        let Some(verifier) = verifier.filter(|_| self.is_console()) else {
+            return;
+        };
+        for discrepancy in verifier.verify(reply) {
+            println!("⚠️ 核对: {}喵", discrepancy);
+        }
+    }
+}
+
+/// 向用户展示工具错误喵
+fn report_tool_error(error: &ToolError) {
+    match error {
+        ToolError::PolicyDenied(violation) => println!("{}", violation.for_user()),
+        ToolError::BudgetExhausted(detail) => println!("⛽ 工具预算已用尽喵: {}", detail),
+        ToolError::WriteConflict(_) => println!("✋ 文件在读取之后被改动过，已拒绝覆盖，交给妮娅决定合并还是放弃喵"),
+        ToolError::NotApproved(detail) => println!("🚫 未执行: {}", detail),
+        other => println!("❌ 工具执行失败: {}", other),
+    }
+}
+
+/// 在终端询问主人是否执行危险工具喵（标准输入不是终端时无法确认）
+pub fn prompt_tool_approval(tool: &str, input: &serde_json::Value) -> Option<security::ApprovalAnswer> {
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    let args: String = input.to_string().chars().take(300).collect();
+    println!("⚠️ 妮娅想执行危险工具 {}: {}", tool, args);
+    print!("   允许吗？[y/N/a=本会话总是允许] ");
+    std::io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    Some(match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => security::ApprovalAnswer::Yes,
+        "a" | "always" => security::ApprovalAnswer::Always,
+        _ => security::ApprovalAnswer::No,
+    })
+}
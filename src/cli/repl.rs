@@ -0,0 +1,230 @@
+//! 交互式对话 ⌨️
+//!
+//! `nekoclaw agent` 不带消息时进入的 REPL 喵
+//!
+//! - 终端模式：行编辑 + 输入历史，支持 `ReplCommand` 中的斜杠命令
+//! - JSON 模式：每行输入都是一条消息（只保留 quit / exit），便于脚本通过管道对话
+
+use nekoclaw::core::repl::{InputHistory, LineEditor, ReplCommand, HELP, HISTORY_FILE};
+use nekoclaw::core::{self, Result};
+use nekoclaw::providers::Message as OpenAIMessage;
+use nekoclaw::telemetry;
+
+use super::agent::{report_failure, AgentSession, RESUME_TOKEN_BUDGET};
+use crate::open_metrics_recorder;
+
+/// 运行交互式对话直到退出或输入结束喵
+pub async fn run(session: &mut AgentSession<'_>) {
+    let console = session.output.is_console();
+    session
+        .output
+        .notice("👋 交互式对话模式已启用喵！输入消息与 AI 助手对话，输入 'quit' 或 'exit' 退出喵。");
+    let mut history = session.preamble();
+    let prefix_len = history.len();
+    // 恢复的历史已经落盘，只保存 `saved_len` 之后新增的消息喵
+    history.extend(session.take_resumed());
+    let mut saved_len = history.len();
+
+    // ⌨️ 行编辑与输入历史（无痕模式不落盘）；JSON 输出时逐行读取喵
+    let mut editor = console.then(|| {
+        LineEditor::new(match session.incognito {
+            true => InputHistory::in_memory(),
+            false => InputHistory::open(&session.config_dir.join(HISTORY_FILE)),
+        })
+    });
+
+    loop {
+        // 输入结束（管道关闭 / Ctrl-D）时退出喵
+        let input = match editor.as_mut() {
+            Some(editor) => editor.read_message("🐾 > ", "  … "),
+            None => {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).map(|n| (n > 0).then_some(line))
+            }
+        };
+        let Ok(Some(input)) = input else {
+            break;
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        match ReplCommand::parse(input) {
+            Some(ReplCommand::Quit) => {
+                session.output.notice("👋 再见喵！");
+                break;
+            }
+            Some(ReplCommand::Clear) if console => {
+                history.truncate(prefix_len); // 保留系统提示与示例喵
+                saved_len = prefix_len;
+                println!("🗑️  对话历史已清空喵");
+                continue;
+            }
+            Some(ReplCommand::Load(name)) if console => {
+                match load_saved_conversation(session.session_store.as_ref(), name) {
+                    Ok(messages) => {
+                        history.truncate(prefix_len);
+                        history.extend(messages);
+                        // 载入的消息属于原会话，不再写入当前会话喵
+                        saved_len = history.len();
+                        println!("📂 已载入会话 {}（{} 条消息）喵", name, history.len() - prefix_len);
+                    }
+                    Err(e) => println!("❌ {}", e),
+                }
+                continue;
+            }
+            Some(ReplCommand::Lang(args)) if console => {
+                println!("{}", session.language_command(args));
+                history[0] = session.system_message();
+                continue;
+            }
+            Some(command) if console => {
+                run_command(session, command, &history[prefix_len..]).await;
+                continue;
+            }
+            _ => {}
+        }
+
+        // 检测语言并更新系统提示喵
+        session.observe_language(input);
+        history[0] = session.system_message();
+        session.reset_claims();
+
+        // 📝 历史过长时摘要旧消息后裁剪（已落盘的部分相应前移）喵
+        let evicted = session.compact_history(&mut history, prefix_len).await;
+        saved_len = saved_len.saturating_sub(evicted).max(prefix_len);
+        let recalled = session.recall(input).await;
+
+        // 超出费用预算或 Agent 限额时结束会话喵
+        if session.requests_blocked() {
+            break;
+        }
+
+        let turn_start = history.len();
+        history.push(OpenAIMessage::user(input.to_string()));
+        let outcome = session.run_turn(&mut history, recalled.as_ref(), "🤖 ").await;
+        report_failure(&session.output, &outcome);
+        session.save_turn(&history[saved_len..], turn_start - saved_len).await;
+    }
+}
+
+/// 处理不改动对话历史与系统提示的命令喵
+///
+/// `conversation` 是系统提示与示例之后的对话（`/save` 保存的内容）
+async fn run_command(session: &mut AgentSession<'_>, command: ReplCommand<'_>, conversation: &[OpenAIMessage]) {
+    match command {
+        ReplCommand::Help => println!("{}", HELP),
+        ReplCommand::Preset("") => {
+            let params = &session.params;
+            let mut names: Vec<&String> = session.config.presets.keys().collect();
+            names.sort();
+            println!(
+                "🎛️ 当前参数: model={} temperature={} top_p={} max_tokens={}",
+                params.model,
+                params.temperature,
+                params.top_p.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+                params.max_tokens
+            );
+            println!("🎛️ 可用预设: {:?}", names);
+        }
+        ReplCommand::Preset(name) => match session.config.sampling_params(Some(name), session.overrides) {
+            Ok(resolved) => {
+                session.params = resolved;
+                println!("🎛️ 已切换到预设 {} 喵", name);
+            }
+            Err(e) => println!("❌ {}", e),
+        },
+        ReplCommand::Model("") => println!("🧠 当前模型: {}", session.params.model),
+        ReplCommand::Model(model) => {
+            session.params.model = model.to_string();
+            println!("🧠 已切换到模型 {} 喵", model);
+        }
+        ReplCommand::Temperature("") => println!("🌡️ 当前 temperature: {}", session.params.temperature),
+        ReplCommand::Temperature(value) => match value.parse::<f32>() {
+            Ok(temperature) if (0.0..=2.0).contains(&temperature) => {
+                session.params.temperature = temperature;
+                println!("🌡️ temperature 已设为 {} 喵", temperature);
+            }
+            _ => println!("❌ temperature 必须是 0 到 2 之间的数字喵"),
+        },
+        ReplCommand::Tools => {
+            let mut tools = session.registry.all_descriptions();
+            tools.sort_by(|a, b| a.name.cmp(&b.name));
+            println!("🔧 可用工具 ({}):", tools.len());
+            for tool in tools {
+                let summary = tool.description.lines().next().unwrap_or_default();
+                println!("  {:<20} {}", tool.name, summary);
+            }
+        }
+        ReplCommand::Save(name) => {
+            let saved = match session.session_store.as_ref() {
+                None => Err("无痕模式下不能保存会话喵".into()),
+                Some(store) => store.save_as(name, &session.agent_name, conversation),
+            };
+            match saved {
+                Ok(info) => println!(
+                    "💾 已另存为会话 {}（{} 条消息），之后可用 --session {} 继续喵",
+                    name, info.message_count, name
+                ),
+                Err(e) => println!("❌ {}", e),
+            }
+        }
+        ReplCommand::Feedback(args) => match session.last_response_id.as_deref() {
+            _ if session.incognito => println!("🕶️ 无痕模式下不记录反馈喵"),
+            None => println!("❓ 还没有可以评价的回复喵"),
+            Some(response_id) => {
+                let recorder = session.experiment_recorder.as_ref();
+                match record_cli_feedback(session.config_dir, recorder, response_id, args).await {
+                    Ok(rating) => println!("📝 已记录反馈 ({}) 喵，谢谢主人！", rating.as_str()),
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+        },
+        // 拼错的斜杠命令给出建议，而不是发给模型喵
+        ReplCommand::Unknown { name, suggestion: Some(suggestion) } => {
+            println!("❓ 未知命令 /{}，你是想输入 {} 吗喵？", name, suggestion)
+        }
+        ReplCommand::Unknown { name, suggestion: None } => {
+            println!("❓ 未知命令 /{}，输入 help 查看可用命令喵", name)
+        }
+        ReplCommand::Quit | ReplCommand::Clear | ReplCommand::Lang(_) | ReplCommand::Load(_) => {}
+    }
+}
+
+/// `/load`：读取已保存的会话作为上下文喵（按会话名或 ID 前缀查找）
+fn load_saved_conversation(store: Option<&core::SessionStore>, name: &str) -> Result<Vec<OpenAIMessage>> {
+    let store = store.ok_or("无痕模式下不能载入会话喵")?;
+    core::session::validate_session_name(name)?;
+    let info = match store.load(name)? {
+        Some(info) => info,
+        None => store.find(name)?.ok_or_else(|| format!("没有名为 {} 的会话喵", name))?,
+    };
+    let stored = store.load_messages(&info.session_id)?;
+    Ok(core::session::resume_messages(&stored, RESUME_TOKEN_BUDGET))
+}
+
+/// 记录 REPL 中 `/feedback up|down [备注]` 的评价喵
+///
+/// 参与实验时同时写入实验反馈指标，供 `experiments report` 汇总
+async fn record_cli_feedback(
+    config_dir: &std::path::Path,
+    experiment_recorder: Option<&telemetry::MetricsRecorder>,
+    response_id: &str,
+    args: &str,
+) -> Result<telemetry::Rating> {
+    let args = args.trim();
+    let (rating, comment) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let rating = telemetry::Rating::parse(rating).ok_or("用法: /feedback up|down [备注] 喵")?;
+
+    let feedback = telemetry::Feedback::new(response_id, rating, "cli").with_comment(comment);
+    open_metrics_recorder(config_dir).await?.record_feedback(&feedback)?;
+    if let Some(recorder) = experiment_recorder {
+        recorder.gauge(
+            core::experiment::FEEDBACK_METRIC,
+            rating.value() as f64,
+            &[("response", response_id)],
+        );
+    }
+    Ok(rating)
+}
@@ -0,0 +1,566 @@
+//! # OpenClaw Migration
+//!
+//! 🐾 `nekoclaw migrate --from ~/.openclaw` 的配置转换喵
+//!
+//! ## 功能
+//! - 读取 openclaw.json，把 provider / 默认模型 / Gateway / Discord / Telegram / 记忆库
+//!   映射到 nekoclaw 的 config.json 字段
+//! - IDENTITY.md / SOUL.md 复制到工作区，人设切换为 `custom`
+//! - `agents.agent.<name>` 的模型 / 提示词 / 工具 / 记忆开关 / token 上限映射为同名 Agent
+//! - `schedule.<name>` 定时任务原样保留（cron 表达式或动作无效的任务丢弃）
+//! - AGENTS.md 的多 Agent 名册不支持，只在报告中列出
+//! - 用 `MigrationValidator` 检查源配置，写入前再用 nekoclaw 的配置校验
+//! - 报告只列出字段路径，不输出任何值（API Key / Token 不会出现在终端）
+//!
+//! 🔒 SAFETY: 转换结果先与现有配置合并并校验，失败时不写入任何文件喵
+//!
+//! 作者: 缪斯 (Muse) @缪斯
+
+use super::validator::{MigrationValidator, ValidationError};
+use crate::core::traits::Config;
+use crate::core::workspace::set_path;
+use crate::core::WorkspaceProfile;
+use crate::service::{CronSchedule, ScheduledTask};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// OpenClaw 主配置文件名
+pub const OPENCLAW_CONFIG: &str = "openclaw.json";
+
+/// 迁移后由 `persona.speech_style = custom` 读取的人设文件
+const PERSONA_FILES: &[&str] = &["IDENTITY.md", "SOUL.md"];
+
+/// 源配置中没有对应字段、使用 nekoclaw 默认值时报告中的来源
+const DEFAULT_SOURCE: &str = "(default)";
+
+/// nekoclaw 支持的 provider
+const PROVIDERS: &[&str] = &["nvidia", "openai", "openrouter", "anthropic", "gemini", "ollama"];
+
+/// 🔒 SAFETY: 一条字段映射喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapping {
+    /// OpenClaw 字段路径 (或文件名)
+    pub from: String,
+    /// nekoclaw 字段路径
+    pub to: String,
+    pub value: Value,
+}
+
+/// 🔒 SAFETY: 迁移计划与报告喵
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub source: PathBuf,
+    pub mapped: Vec<Mapping>,
+    /// (OpenClaw 字段路径, 原因)
+    pub dropped: Vec<(String, String)>,
+    pub warnings: Vec<String>,
+    /// 需要复制到工作区的人设文件
+    pub files: Vec<PathBuf>,
+}
+
+impl MigrationReport {
+    /// 🔒 SAFETY: 读取 OpenClaw 目录并生成迁移计划喵（不写入任何文件）
+    pub fn plan(source: &Path) -> Result<Self, String> {
+        let config_path = source.join(OPENCLAW_CONFIG);
+        let content = std::fs::read_to_string(&config_path)
+            .map_err(|e| format!("Failed to read {}: {}", config_path.display(), e))?;
+        let mut root: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", config_path.display(), e))?;
+        // 旧版 openclaw.json 把所有内容包在 `config` 下
+        if let Some(inner) = root.get("config").filter(|c| c.get("models").is_some()).cloned() {
+            root = inner;
+        }
+
+        let mut report = Self {
+            source: source.to_path_buf(),
+            ..Default::default()
+        };
+        match MigrationValidator::new().validate_openclaw_config(&root) {
+            Ok(_) => {}
+            Err(ValidationError::Multiple(errors)) => report.warnings.extend(errors),
+            Err(e) => report.warnings.push(e.to_string()),
+        }
+
+        let mut mapper = Mapper {
+            root: &root,
+            report: &mut report,
+            consumed: Vec::new(),
+        };
+        mapper.providers();
+        mapper.models();
+        mapper.agents();
+        mapper.gateway();
+        mapper.discord();
+        mapper.telegram();
+        mapper.memory();
+        mapper.schedule();
+        mapper.drop_unconsumed();
+
+        report.persona_files();
+        report.agents_roster();
+        Ok(report)
+    }
+
+    /// 人设文件：存在的复制到工作区，并启用 custom 人设
+    fn persona_files(&mut self) {
+        for name in PERSONA_FILES {
+            let path = self.source.join(name);
+            if path.is_file() {
+                self.files.push(path);
+            }
+        }
+        if !self.files.is_empty() {
+            self.mapped.push(Mapping {
+                from: PERSONA_FILES.join(" + "),
+                to: "persona.speech_style".to_string(),
+                value: Value::String("custom".to_string()),
+            });
+        }
+    }
+
+    /// AGENTS.md：只统计名册中的 Agent 数量
+    fn agents_roster(&mut self) {
+        let Ok(content) = std::fs::read_to_string(self.source.join("AGENTS.md")) else {
+            return;
+        };
+        let agents = content
+            .lines()
+            .filter(|line| line.trim_start().starts_with('|'))
+            .filter(|line| !line.contains("---") && !line.contains("Discord ID"))
+            .count();
+        self.dropped.push((
+            "AGENTS.md".to_string(),
+            format!("multi-agent roster is not supported ({} agents)", agents),
+        ));
+    }
+
+    /// 🔒 SAFETY: 合并到工作区配置并复制人设文件喵
+    ///
+    /// `force` 为 false 时不覆盖工作区已有的人设文件
+    ///
+    /// ## Returns
+    /// 写入的配置文件与复制的人设文件
+    pub fn apply(&self, profile: &WorkspaceProfile, force: bool) -> Result<(PathBuf, Vec<PathBuf>), String> {
+        let config_path = profile
+            .update_config(|doc| {
+                for mapping in &self.mapped {
+                    set_path(doc, &mapping.to, mapping.value.clone())?;
+                }
+                Ok(())
+            })
+            .map_err(|e| format!("Migrated config is invalid, nothing was written: {}", e))?;
+
+        let mut copied = Vec::new();
+        for file in &self.files {
+            let target = profile.root.join(file.file_name().unwrap_or_default());
+            if target.exists() && !force {
+                continue;
+            }
+            std::fs::copy(file, &target).map_err(|e| format!("Failed to copy {}: {}", file.display(), e))?;
+            copied.push(target);
+        }
+        Ok((config_path, copied))
+    }
+
+    /// 🔒 SAFETY: diff 风格的报告喵（只含路径，不含值）
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!("--- {}", self.source.join(OPENCLAW_CONFIG).display()), "+++ nekoclaw config".to_string()];
+        for mapping in &self.mapped {
+            lines.push(format!("+ {:<40} <- {}", mapping.to, mapping.from));
+        }
+        for (from, reason) in &self.dropped {
+            lines.push(format!("- {:<40} ({})", from, reason));
+        }
+        for warning in &self.warnings {
+            lines.push(format!("! {}", warning));
+        }
+        lines.join("\n")
+    }
+}
+
+/// openclaw.json → nekoclaw 字段映射器
+struct Mapper<'a> {
+    root: &'a Value,
+    report: &'a mut MigrationReport,
+    /// 已处理（映射或显式丢弃）的源字段路径
+    consumed: Vec<String>,
+}
+
+impl Mapper<'_> {
+    fn get(&self, path: &str) -> Option<&Value> {
+        path.split('.').try_fold(self.root, |value, key| value.get(key))
+    }
+
+    /// 第一个存在的别名
+    fn find(&self, paths: &[&str]) -> Option<(String, Value)> {
+        paths.iter().find_map(|path| self.get(path).map(|v| (path.to_string(), v.clone())))
+    }
+
+    fn map(&mut self, from: &str, to: &str, value: Value) {
+        self.consumed.push(from.to_string());
+        self.report.mapped.push(Mapping {
+            from: from.to_string(),
+            to: to.to_string(),
+            value,
+        });
+    }
+
+    fn drop(&mut self, from: &str, reason: &str) {
+        self.consumed.push(from.to_string());
+        self.report.dropped.push((from.to_string(), reason.to_string()));
+    }
+
+    /// models.providers.* → providers.*
+    fn providers(&mut self) {
+        let defaults = Config::default();
+        for name in PROVIDERS {
+            let base = format!("models.providers.{}", name);
+            let Some(provider) = self.get(&base).filter(|p| p.is_object()).cloned() else {
+                continue;
+            };
+            if provider.get("enabled") == Some(&Value::Bool(false)) {
+                self.drop(&base, "provider is disabled");
+                continue;
+            }
+            self.consumed.push(format!("{}.enabled", base));
+
+            let key = self.find(&[&format!("{}.apiKey", base), &format!("{}.api_key", base)]);
+            let url = self.find(&[&format!("{}.baseUrl", base), &format!("{}.base_url", base)]);
+            let (key_from, api_key) = key.unwrap_or_else(|| {
+                if *name != "ollama" {
+                    self.report.warnings.push(format!("{} has no API key", base));
+                }
+                (DEFAULT_SOURCE.to_string(), Value::String(String::new()))
+            });
+            let (url_from, base_url) = url.unwrap_or_else(|| {
+                let url = defaults.provider(name).map(|p| p.base_url).unwrap_or_default();
+                (DEFAULT_SOURCE.to_string(), Value::String(url))
+            });
+            self.map(&key_from, &format!("providers.{}.api_key", name), api_key);
+            self.map(&url_from, &format!("providers.{}.base_url", name), base_url);
+        }
+        for name in ["azure", "fred"] {
+            let path = format!("models.providers.{}", name);
+            if self.get(&path).is_some() {
+                self.drop(&path, "provider is not supported");
+            }
+        }
+    }
+
+    /// agents.defaults.model / models.default → default_provider / default_model / fallback
+    fn models(&mut self) {
+        let Some((from, primary)) = self.find(&["agents.defaults.model.primary", "models.default"]) else {
+            return;
+        };
+        let Some(primary) = primary.as_str() else {
+            self.drop(&from, "expected a string");
+            return;
+        };
+        let (provider, model) = split_model(primary);
+        if let Some(provider) = provider {
+            self.map(&from, "default_provider", Value::String(provider.to_string()));
+        }
+        self.map(&from, "default_model", Value::String(model.to_string()));
+        if from != "models.default" && self.get("models.default").is_some() {
+            self.drop("models.default", "superseded by agents.defaults.model.primary");
+        }
+
+        let Some((from, fallbacks)) = self.find(&["agents.defaults.model.fallbacks", "agents.defaults.model.fallback"]) else {
+            return;
+        };
+        // nekoclaw 的备用模型在同一个 provider 上
+        let (kept, skipped): (Vec<_>, Vec<_>) = fallbacks
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(split_model)
+            .partition(|(p, _)| p.is_none() || *p == provider);
+        if !kept.is_empty() {
+            let models = kept.into_iter().map(|(_, m)| Value::String(m.to_string())).collect();
+            self.map(&from, "agents.defaults.model.fallback", Value::Array(models));
+        }
+        if !skipped.is_empty() {
+            self.report.dropped.push((
+                from.clone(),
+                format!("{} fallback(s) on other providers are not supported", skipped.len()),
+            ));
+            self.consumed.push(from);
+        }
+    }
+
+    /// agents.default / agents.agent.<name> → 同名 Agent（模型必须在默认 provider 上）
+    fn agents(&mut self) {
+        if let Some(default) = self.get("agents.default").cloned() {
+            self.map("agents.default", "agents.default", default);
+        }
+        let Some(agents) = self.get("agents.agent").and_then(Value::as_object).cloned() else {
+            return;
+        };
+        let provider = self
+            .find(&["agents.defaults.model.primary", "models.default"])
+            .and_then(|(_, primary)| primary.as_str().and_then(|m| split_model(m).0).map(String::from));
+        for (name, agent) in agents {
+            let base = format!("agents.agent.{}", name);
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                self.drop(&base, "invalid agent name");
+                continue;
+            }
+            if let Some(model) = agent.get("model").and_then(Value::as_str) {
+                let path = format!("{}.model", base);
+                match split_model(model) {
+                    (Some(other), _) if provider.as_deref() != Some(other) => {
+                        self.drop(&path, "agent model on another provider is not supported")
+                    }
+                    (_, model) => self.map(&path, &path, Value::String(model.to_string())),
+                }
+            }
+            for (from, to) in [
+                ("name", "name"),
+                ("prompts.system", "prompts.system"),
+                ("prompts.suffix", "prompts.suffix"),
+                ("tools", "tools"),
+                ("capabilities.memory", "memory"),
+                ("limits.max_session_hours", "limits.max_session_hours"),
+                ("limits.max_requests_per_hour", "limits.max_requests_per_hour"),
+                ("limits.max_token_limit", "limits.max_token_limit"),
+            ] {
+                let from = format!("{}.{}", base, from);
+                if let Some(value) = self.get(&from).cloned() {
+                    self.map(&from, &format!("{}.{}", base, to), value);
+                }
+            }
+        }
+    }
+
+    /// gateway.* → gateway_port / gateway_bind / api_key
+    fn gateway(&mut self) {
+        if let Some(port) = self.get("gateway.port").cloned() {
+            self.map("gateway.port", "gateway_port", port);
+        }
+        if let Some((from, bind)) = self.find(&["gateway.host", "gateway.bind"]) {
+            let address = match bind.as_str() {
+                Some("loopback") => Some("127.0.0.1"),
+                Some("lan") => Some("0.0.0.0"),
+                Some(address) if address.parse::<std::net::IpAddr>().is_ok() => Some(address),
+                _ => None,
+            };
+            match address {
+                Some(address) => self.map(&from, "gateway_bind", Value::String(address.to_string())),
+                None => self.drop(&from, "unsupported bind mode"),
+            }
+        }
+        if let Some(token) = self.get("gateway.auth.token").cloned() {
+            self.map("gateway.auth.token", "api_key", token);
+        }
+    }
+
+    /// channels.discord → discord（只支持一个账户，优先 main_bot）
+    fn discord(&mut self) {
+        let Some(discord) = self.get("channels.discord").filter(|d| d.is_object()).cloned() else {
+            return;
+        };
+        let mut accounts: Vec<(String, Value)> = discord
+            .get("accounts")
+            .and_then(Value::as_object)
+            .map(|a| a.iter().map(|(k, v)| (format!("channels.discord.accounts.{}", k), v.clone())).collect())
+            .unwrap_or_default();
+        accounts.sort_by_key(|(path, _)| !path.ends_with(".main_bot"));
+        if discord.get("token").is_some() {
+            accounts.insert(0, ("channels.discord".to_string(), discord.clone()));
+        }
+        let mut accounts = accounts.into_iter();
+        let Some((base, account)) = accounts.next() else {
+            return;
+        };
+        for (path, _) in accounts {
+            self.drop(&path, "only one Discord account is supported");
+        }
+
+        let Some(token) = account.get("token").cloned() else {
+            self.drop(&base, "Discord account has no token");
+            return;
+        };
+        let enabled = discord.get("enabled").cloned().unwrap_or(Value::Bool(true));
+        let allowed_users = account.get("allowed_users").cloned().unwrap_or(Value::Array(Vec::new()));
+        self.map(&format!("{}.token", base), "discord.token", token);
+        self.map("channels.discord.enabled", "discord.enabled", enabled);
+        self.map(&format!("{}.allowed_users", base), "discord.allowed_users", allowed_users);
+        self.map(DEFAULT_SOURCE, "discord.require_mention", Value::Bool(true));
+    }
+
+    /// channels.telegram → telegram.allowed_chat_ids（Bot Token 改由环境变量提供）
+    fn telegram(&mut self) {
+        if self.get("channels.telegram").is_none() {
+            return;
+        }
+        if let Some((from, _)) = self.find(&["channels.telegram.token", "channels.telegram.botToken"]) {
+            self.drop(&from, "set TELEGRAM_BOT_TOKEN in the environment instead");
+        }
+        self.consumed.push("channels.telegram.enabled".to_string());
+        let Some((from, users)) = self.find(&["channels.telegram.allowed_users", "channels.telegram.allowFrom"]) else {
+            return;
+        };
+        let ids: Vec<Value> = users
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| id.as_i64().or_else(|| id.as_str()?.trim().parse().ok()))
+            .map(Value::from)
+            .collect();
+        let total = users.as_array().map_or(0, Vec::len);
+        if ids.len() < total {
+            self.report
+                .warnings
+                .push(format!("{}: {} non-numeric chat id(s) skipped", from, total - ids.len()));
+        }
+        self.map(&from, "telegram.allowed_chat_ids", Value::Array(ids));
+    }
+
+    /// memory.path → memory.path
+    fn memory(&mut self) {
+        if let Some(path) = self.get("memory.path").cloned() {
+            self.map("memory.path", "memory.path", path);
+        }
+        if self.get("memory.enabled").is_some() {
+            self.drop("memory.enabled", "memory is always enabled");
+        }
+    }
+
+    /// schedule.<name> → schedule.<name>（与 nekoclaw 格式相同）
+    fn schedule(&mut self) {
+        let Some(tasks) = self.get("schedule").and_then(Value::as_object).cloned() else {
+            return;
+        };
+        for (name, task) in tasks {
+            let from = format!("schedule.{}", name);
+            match serde_json::from_value::<ScheduledTask>(task.clone()) {
+                Ok(parsed) => match CronSchedule::parse(&parsed.cron) {
+                    Ok(_) => self.map(&from, &from, task),
+                    Err(_) => self.drop(&from, "invalid cron expression"),
+                },
+                Err(_) => self.drop(&from, "expected skill, command or prompt"),
+            }
+        }
+    }
+
+    /// 其余未处理的叶子字段都记为丢弃
+    fn drop_unconsumed(&mut self) {
+        let mut leaves = Vec::new();
+        collect_leaves(self.root, String::new(), &mut leaves);
+        for leaf in leaves {
+            let consumed = self
+                .consumed
+                .iter()
+                .any(|c| leaf == *c || leaf.starts_with(&format!("{}.", c)));
+            if !consumed && leaf != "version" {
+                self.report.dropped.push((leaf, "no nekoclaw equivalent".to_string()));
+            }
+        }
+    }
+}
+
+/// `provider/model` 拆成 (provider, model)；前缀不是已知 provider 时整体视为模型名
+fn split_model(model: &str) -> (Option<&str>, &str) {
+    match model.split_once('/') {
+        Some((provider, rest)) if PROVIDERS.contains(&provider) => (Some(provider), rest),
+        _ => (None, model),
+    }
+}
+
+/// 收集所有叶子字段路径（数组与空对象视为叶子）
+fn collect_leaves(value: &Value, prefix: String, out: &mut Vec<String>) {
+    match value.as_object().filter(|map| !map.is_empty()) {
+        Some(map) => {
+            for (key, child) in map {
+                let path = match prefix.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", prefix, key),
+                };
+                collect_leaves(child, path, out);
+            }
+        }
+        None if !prefix.is_empty() => out.push(prefix),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_openclaw_directory() {
+        let source = tempfile::tempdir().unwrap();
+        let base = tempfile::tempdir().unwrap();
+        let openclaw = serde_json::json!({
+            "models": { "providers": {
+                "nvidia": { "apiKey": "nv-secret-key", "baseUrl": "https://integrate.api.nvidia.com/v1", "models": [{ "id": "kimi" }] },
+                "azure": { "apiKey": "az" }
+            }},
+            "agents": {
+                "defaults": { "model": {
+                    "primary": "nvidia/moonshotai/kimi-k2",
+                    "fallbacks": ["nvidia/meta/llama-3.3-70b", "openai/gpt-4o"]
+                }},
+                "agent": {
+                    "muse": {
+                        "model": "nvidia/meta/llama-3.3-70b",
+                        "prompts": { "system": "You are Muse.", "user": "{input}" },
+                        "capabilities": { "memory": false },
+                        "limits": { "max_token_limit": 800, "max_requests_per_hour": 20 }
+                    },
+                    "scout": { "model": "openai/gpt-4o-mini" }
+                }
+            },
+            "channels": {
+                "telegram": { "botToken": "tg-secret", "allowFrom": ["42", "@someone"] },
+                "signal": { "enabled": true }
+            },
+            "gateway": { "port": 18789, "bind": "loopback" },
+            "schedule": {
+                "nightly": { "cron": "0 3 * * *", "command": "git", "args": ["status"] },
+                "broken": { "cron": "61 * * * *", "prompt": "hi" }
+            }
+        });
+        std::fs::write(source.path().join(OPENCLAW_CONFIG), openclaw.to_string()).unwrap();
+        std::fs::write(source.path().join("SOUL.md"), "Be brief.\n").unwrap();
+        std::fs::write(source.path().join("AGENTS.md"), "| Agent | Discord ID | Role |\n|---|---|---|\n| muse | 1 | dev |\n").unwrap();
+
+        let report = MigrationReport::plan(source.path()).unwrap();
+        let rendered = report.render();
+        assert!(rendered.contains("+ providers.nvidia.api_key"));
+        assert!(rendered.contains("- models.providers.azure"));
+        assert!(rendered.contains("- channels.signal.enabled"));
+        assert!(rendered.contains("- models.providers.nvidia.models"));
+        assert!(rendered.contains("(multi-agent roster is not supported (1 agents))"));
+        assert!(rendered.contains("- agents.agent.muse.prompts.user"));
+        assert!(rendered.contains("- agents.agent.scout.model"));
+        assert!(rendered.contains("- schedule.broken"));
+        // MigrationValidator 的必填项缺失只作为警告喵
+        assert!(rendered.contains("! Missing required field: channels.discord.accounts.main_bot.token"));
+        // 报告中不出现密钥喵
+        assert!(!rendered.contains("nv-secret-key") && !rendered.contains("tg-secret"));
+
+        let profile = WorkspaceProfile::resolve(base.path(), None).unwrap();
+        let (_, copied) = report.apply(&profile, false).unwrap();
+        assert_eq!(copied, vec![base.path().join("SOUL.md")]);
+
+        let config = profile.load_config().unwrap();
+        assert_eq!(config.default_provider, "nvidia");
+        assert_eq!(config.default_model, "moonshotai/kimi-k2");
+        assert_eq!(config.agents.defaults.model.fallback, vec!["meta/llama-3.3-70b".to_string()]);
+        assert_eq!(config.provider("nvidia").unwrap().api_key, "nv-secret-key");
+        assert_eq!(config.gateway_port, Some(18789));
+        assert_eq!(config.gateway_bind.as_deref(), Some("127.0.0.1"));
+        let (_, muse) = config.agent_profile(Some("muse")).unwrap();
+        assert_eq!(muse.model.as_deref(), Some("meta/llama-3.3-70b"));
+        assert_eq!(muse.prompts.system.as_deref(), Some("You are Muse."));
+        assert!(!muse.memory_enabled());
+        assert_eq!(muse.limits.max_token_limit, Some(800));
+        assert_eq!(muse.limits.max_requests_per_hour, Some(20));
+        assert_eq!(config.telegram.unwrap().allowed_chat_ids, vec![42]);
+        assert_eq!(config.schedule.keys().collect::<Vec<_>>(), vec!["nightly"]);
+        assert_eq!(config.persona.speech_style, crate::core::persona::SpeechStyle::Custom);
+    }
+}
@@ -182,6 +182,13 @@ impl ConfigValidator {
         self.rules.insert(rule.field_name.clone(), rule);
     }
 
+    /// 🔒 SAFETY: 批量添加验证规则喵
+    pub fn add_rules(&mut self, rules: Vec<ValidationRule>) {
+        for rule in rules {
+            self.add_rule(rule);
+        }
+    }
+
     /// 🔒 SAFETY: 验证配置喵
     pub fn validate(&self, config: &serde_json::Value) -> Result<(), ValidationError> {
         let mut errors = Vec::new();
@@ -0,0 +1,254 @@
+/*!
+ * Agent Profiles - 多 Agent 人设
+ *
+ * `agents.agent.<name>` 定义多个并存的 Agent 喵：各自的模型、提示词、工具、记忆与限额。
+ * `nekoclaw agent --agent <name>` 或 Gateway 请求体中的 `"agent"` 选择使用哪一个。
+ *
+ * ```toml
+ * [agents]
+ * default = "muse"
+ *
+ * [agents.agent.muse]
+ * name = "缪斯"
+ * model = "gpt-4o"
+ * tools = ["fs_read", "history_search"]
+ * memory = false
+ *
+ * [agents.agent.muse.prompts]
+ * system = "You are Muse, a concise writing assistant."
+ * suffix = "Always answer in English."
+ *
+ * [agents.agent.muse.tool_prompt]
+ * mode = "compact"
+ *
+ * [[agents.agent.muse.few_shot.examples]]
+ * user = "Summarize README.md"
+ * assistant = "@tool(fs_read, path=\"README.md\")"
+ *
+ * [agents.agent.muse.limits]
+ * max_tokens = 800
+ * max_tool_rounds = 3
+ * max_requests_per_hour = 30
+ * max_token_limit = 200000
+ * ```
+ * 未配置的字段沿用全局配置；没有任何配置时使用内置的 `nia` 人设喵
+ * 请求数 / token / 会话时长限额由 `agent::limits::LimitsEnforcer` 执行
+ */
+
+use crate::core::few_shot::FewShotConfig;
+use crate::core::suggest::closest_match;
+use crate::core::traits::Config;
+use crate::core::ModelPreset;
+use crate::tools::ToolPromptConfig;
+use serde::{Deserialize, Serialize};
+
+/// 内置 Agent 名称（未配置 `agents.agent` 时使用）喵
+pub const DEFAULT_AGENT: &str = "nia";
+
+/// 默认的工具调用轮数上限喵
+pub const DEFAULT_TOOL_ROUNDS: usize = 5;
+
+/// 单个 Agent 的配置喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentProfile {
+    /// 显示名称（默认使用配置键）
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 模型（覆盖预设与 `default_model`，命令行 `--model` 仍然优先）
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub prompts: AgentPrompts,
+    /// 可用的工具名称（None = 全部；技能通过 `skill` 工具控制）
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    /// 是否启用长期记忆（对话摘要召回与 history_search，默认启用）
+    #[serde(default)]
+    pub memory: Option<bool>,
+    /// Few-shot 示例（替换全局 `few_shot`，工作区 few_shot.json 仍会合并）
+    #[serde(default)]
+    pub few_shot: Option<FewShotConfig>,
+    /// 工具提示词（替换全局 `tool_prompt`，命令行 `--tool-prompt` 仍然优先）
+    #[serde(default)]
+    pub tool_prompt: Option<ToolPromptConfig>,
+    #[serde(default)]
+    pub limits: AgentLimits,
+}
+
+/// Agent 提示词喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentPrompts {
+    /// 替换 IDENTITY / SOUL 人设
+    #[serde(default)]
+    pub system: Option<String>,
+    /// 追加在系统提示词末尾
+    #[serde(default)]
+    pub suffix: Option<String>,
+}
+
+/// Agent 限额喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentLimits {
+    /// 单次回复的 token 上限
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// 每条消息最多的工具调用轮数
+    #[serde(default)]
+    pub max_tool_rounds: Option<usize>,
+    /// 单次会话的最长时长（小时）
+    #[serde(default)]
+    pub max_session_hours: Option<f64>,
+    /// 最近一小时最多的模型请求数
+    #[serde(default)]
+    pub max_requests_per_hour: Option<u32>,
+    /// 每天最多使用的 token（输入 + 输出）
+    #[serde(default)]
+    pub max_token_limit: Option<u64>,
+}
+
+impl AgentProfile {
+    /// 工具是否对该 Agent 开放喵
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.tools.as_ref().is_none_or(|tools| tools.iter().any(|t| t == tool))
+    }
+
+    pub fn memory_enabled(&self) -> bool {
+        self.memory.unwrap_or(true)
+    }
+
+    pub fn tool_rounds(&self) -> usize {
+        self.limits.max_tool_rounds.unwrap_or(DEFAULT_TOOL_ROUNDS)
+    }
+
+    /// 把 Agent 的模型叠加到命令行参数之下喵（命令行已指定时保持不变）
+    pub fn overrides(&self, cli: &ModelPreset) -> ModelPreset {
+        ModelPreset {
+            model: cli.model.clone().or_else(|| self.model.clone()),
+            ..cli.clone()
+        }
+    }
+
+    /// 该 Agent 的 few-shot 配置喵（未配置时沿用全局）
+    pub fn few_shot_config(&self, global: Option<&FewShotConfig>) -> FewShotConfig {
+        self.few_shot.clone().or_else(|| global.cloned()).unwrap_or_default()
+    }
+
+    /// 该 Agent 的工具提示词配置喵（未配置时沿用全局）
+    pub fn tool_prompt_config(&self, global: Option<&ToolPromptConfig>) -> ToolPromptConfig {
+        self.tool_prompt.clone().or_else(|| global.cloned()).unwrap_or_default()
+    }
+
+    /// 是否配置了请求数 / token / 会话时长限额喵
+    pub fn has_quota(&self) -> bool {
+        let limits = &self.limits;
+        limits.max_session_hours.is_some() || limits.max_requests_per_hour.is_some() || limits.max_token_limit.is_some()
+    }
+
+    /// 按 `limits.max_tokens` 收紧回复上限喵
+    pub fn cap_max_tokens(&self, max_tokens: u32) -> u32 {
+        self.limits.max_tokens.map_or(max_tokens, |limit| max_tokens.min(limit))
+    }
+}
+
+impl Config {
+    /// 配置中的 Agent 名称（未配置时只有内置的 `nia`）喵
+    pub fn agent_names(&self) -> Vec<&str> {
+        match self.agents.agent.is_empty() {
+            true => vec![DEFAULT_AGENT],
+            false => self.agents.agent.keys().map(String::as_str).collect(),
+        }
+    }
+
+    /// 解析要使用的 Agent 喵
+    ///
+    /// `name` 为 None 时依次使用 `agents.default` 与内置的 `nia`；
+    /// 内置名称未配置时返回空配置（全部沿用全局设置）
+    ///
+    /// ## Returns
+    /// (Agent 名称, 配置)
+    pub fn agent_profile(&self, name: Option<&str>) -> Result<(String, AgentProfile), String> {
+        let name = name.or(self.agents.default.as_deref()).unwrap_or(DEFAULT_AGENT);
+        match self.agents.agent.get(name) {
+            Some(profile) => Ok((name.to_string(), profile.clone())),
+            None if name == DEFAULT_AGENT => Ok((name.to_string(), AgentProfile::default())),
+            None => {
+                let hint = closest_match(name, self.agent_names())
+                    .map(|s| format!("，你是不是想用 {}", s))
+                    .unwrap_or_default();
+                Err(format!("未配置 Agent {}{}喵（可用: {}）", name, hint, self.agent_names().join(", ")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::few_shot::FewShotExample;
+    use crate::tools::ToolPromptMode;
+
+    #[test]
+    fn test_agent_profile_resolution() {
+        let mut config = Config::default();
+        let (name, profile) = config.agent_profile(None).unwrap();
+        assert_eq!(name, DEFAULT_AGENT);
+        assert!(profile.allows_tool("shell") && profile.memory_enabled());
+        assert_eq!(profile.tool_rounds(), DEFAULT_TOOL_ROUNDS);
+        assert!(profile.few_shot_config(config.few_shot.as_ref()).examples.is_empty());
+
+        let muse = AgentProfile {
+            model: Some("gpt-4o".to_string()),
+            tools: Some(vec!["fs_read".to_string()]),
+            memory: Some(false),
+            few_shot: Some(FewShotConfig {
+                examples: vec![FewShotExample {
+                    user: "hi".to_string(),
+                    assistant: "hello".to_string(),
+                    tool: None,
+                }],
+                ..Default::default()
+            }),
+            tool_prompt: Some(ToolPromptConfig {
+                mode: ToolPromptMode::Compact,
+                ..Default::default()
+            }),
+            limits: AgentLimits {
+                max_tokens: Some(800),
+                max_tool_rounds: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.agents.agent.insert("muse".to_string(), muse);
+        config.agents.default = Some("muse".to_string());
+
+        let (name, profile) = config.agent_profile(None).unwrap();
+        assert_eq!(name, "muse");
+        assert!(profile.allows_tool("fs_read") && !profile.allows_tool("shell"));
+        assert!(!profile.memory_enabled());
+        assert_eq!(profile.cap_max_tokens(4096), 800);
+        assert_eq!(profile.cap_max_tokens(500), 500);
+
+        // Agent 自己的示例与工具提示词替换全局配置喵
+        config.tool_prompt = Some(ToolPromptConfig {
+            mode: ToolPromptMode::Full,
+            ..Default::default()
+        });
+        assert_eq!(profile.few_shot_config(config.few_shot.as_ref()).examples.len(), 1);
+        assert_eq!(profile.tool_prompt_config(config.tool_prompt.as_ref()).mode, ToolPromptMode::Compact);
+        let (_, nia) = config.agent_profile(Some(DEFAULT_AGENT)).unwrap();
+        assert_eq!(nia.tool_prompt_config(config.tool_prompt.as_ref()).mode, ToolPromptMode::Full);
+
+        // 命令行 --model 优先于 Agent 的模型喵
+        assert_eq!(profile.overrides(&ModelPreset::default()).model.as_deref(), Some("gpt-4o"));
+        let cli = ModelPreset {
+            model: Some("gpt-4o-mini".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(profile.overrides(&cli).model.as_deref(), Some("gpt-4o-mini"));
+
+        assert!(config.agent_profile(Some(DEFAULT_AGENT)).is_ok());
+        let err = config.agent_profile(Some("mose")).unwrap_err();
+        assert!(err.contains("muse"));
+    }
+}
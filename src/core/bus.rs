@@ -0,0 +1,431 @@
+/*!
+ * Event Bus
+ *
+ * 渠道与 Agent 之间的内部消息队列喵：
+ * 渠道把 `ChannelEvent` 发布到有界队列，分发任务按到达顺序转入各渠道自己的队列，
+ * 再交给该渠道注册的 `EventHandler`（Agent 执行器）处理。
+ *
+ * - 排队中的事件（所有渠道合计）满 `capacity` 时 `publish` 等待（背压），`try_publish` 直接返回 `BusError::Full`
+ * - 全局最多 `workers` 个事件同时处理，每个渠道另有并发上限；
+ *   某个渠道达到上限时只有它自己的事件等待，不会挡住其他渠道
+ * - `stats()` 返回队列深度与各渠道处理中的事件数，供守护进程写入指标
+ * - `with_shutdown` 后每个事件从发布起持有关闭协调器的凭证：
+ *   关闭时拒绝新事件，已排队与处理中的事件在宽限期内处理完
+ *
+ * ```toml
+ * [event_bus]
+ * capacity = 256
+ * workers = 4
+ * per_channel = 2
+ *
+ * [event_bus.channel_limits]
+ * telegram = 3
+ * ```
+ */
+
+use super::traits::ChannelEvent;
+use crate::service::{InFlight, ShutdownCoordinator};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+fn default_capacity() -> usize {
+    256
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+fn default_per_channel() -> usize {
+    2
+}
+
+/// 事件总线配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusConfig {
+    /// 队列容量（排队中的事件数上限）
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+
+    /// 同时处理的事件数上限（所有渠道合计）
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+
+    /// 每个渠道默认的并发上限
+    #[serde(default = "default_per_channel")]
+    pub per_channel: usize,
+
+    /// 按渠道覆盖并发上限（渠道名 → 上限）
+    #[serde(default)]
+    pub channel_limits: HashMap<String, usize>,
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            workers: default_workers(),
+            per_channel: default_per_channel(),
+            channel_limits: HashMap::new(),
+        }
+    }
+}
+
+impl BusConfig {
+    /// 某个渠道的并发上限（至少为 1）喵
+    pub fn channel_limit(&self, source: &str) -> usize {
+        self.channel_limits.get(source).copied().unwrap_or(self.per_channel).max(1)
+    }
+}
+
+/// 事件总线错误喵
+#[derive(Error, Debug)]
+pub enum BusError {
+    #[error("Event bus is full ({0} events queued)")]
+    Full(usize),
+
+    #[error("No handler registered for channel '{0}'")]
+    NoHandler(String),
+
+    #[error("Event bus is closed")]
+    Closed,
+
+    #[error("Shutting down, not accepting new events")]
+    ShuttingDown,
+}
+
+/// 处理某个渠道事件的 Agent 执行器喵（负责把回复发回渠道）
+#[async_trait::async_trait]
+pub trait EventHandler: Send + Sync {
+    async fn handle(&self, event: ChannelEvent);
+}
+
+/// 事件总线的运行状态喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusStats {
+    /// 排队中的事件数
+    pub queued: usize,
+    pub capacity: usize,
+    /// 各渠道正在处理的事件数
+    pub in_flight: BTreeMap<String, usize>,
+    /// 累计发布的事件数
+    pub published: u64,
+    /// `try_publish` 遇到队列已满的次数
+    pub saturated: u64,
+}
+
+/// 排队中的事件、它的进行中凭证与队列名额（开始处理时归还）喵
+type Queued = (ChannelEvent, Option<InFlight>, OwnedSemaphorePermit);
+
+/// 单个渠道的处理器、并发限制与待处理队列喵
+struct Route {
+    handler: Arc<dyn EventHandler>,
+    limit: usize,
+    permits: Arc<Semaphore>,
+    sender: mpsc::UnboundedSender<Queued>,
+    receiver: tokio::sync::Mutex<mpsc::UnboundedReceiver<Queued>>,
+}
+
+impl Route {
+    /// 渠道的分发循环喵：按顺序等本渠道的并发名额，再等全局 worker 名额
+    async fn dispatch(self: Arc<Self>, workers: Arc<Semaphore>) {
+        let mut receiver = self.receiver.lock().await;
+        while let Some((event, guard, slot)) = receiver.recv().await {
+            let (Ok(channel_permit), Ok(worker_permit)) = (
+                self.permits.clone().acquire_owned().await,
+                workers.clone().acquire_owned().await,
+            ) else {
+                return;
+            };
+            drop(slot);
+            let handler = self.handler.clone();
+            tokio::spawn(async move {
+                match guard {
+                    Some(guard) => {
+                        let source = event.source.clone();
+                        if guard.run(handler.handle(event)).await.is_none() {
+                            warn!("关闭宽限期已过，取消渠道 {} 的事件处理喵", source);
+                        }
+                    }
+                    None => handler.handle(event).await,
+                }
+                drop((channel_permit, worker_permit));
+            });
+        }
+    }
+}
+
+/// 🔒 SAFETY: 渠道与 Agent 之间的有界事件队列喵
+pub struct EventBus {
+    config: BusConfig,
+    sender: mpsc::UnboundedSender<Queued>,
+    receiver: tokio::sync::Mutex<mpsc::UnboundedReceiver<Queued>>,
+    /// 排队名额（`capacity` 个），事件开始处理时归还
+    slots: Arc<Semaphore>,
+    routes: Mutex<HashMap<String, Arc<Route>>>,
+    workers: Arc<Semaphore>,
+    shutdown: Option<ShutdownCoordinator>,
+    published: AtomicU64,
+    saturated: AtomicU64,
+}
+
+impl EventBus {
+    pub fn new(config: BusConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let slots = Arc::new(Semaphore::new(config.capacity.max(1)));
+        let workers = Arc::new(Semaphore::new(config.workers.max(1)));
+        Self {
+            config,
+            sender,
+            receiver: tokio::sync::Mutex::new(receiver),
+            slots,
+            routes: Mutex::new(HashMap::new()),
+            workers,
+            shutdown: None,
+            published: AtomicU64::new(0),
+            saturated: AtomicU64::new(0),
+        }
+    }
+
+    /// 🔒 SAFETY: 关闭时拒绝新事件，并等待已接收的事件处理完喵
+    pub fn with_shutdown(mut self, coordinator: ShutdownCoordinator) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// 注册某个渠道（`ChannelEvent::source`）的处理器喵
+    pub fn register(&self, source: &str, handler: Arc<dyn EventHandler>) {
+        let limit = self.config.channel_limit(source);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let route = Route {
+            handler,
+            limit,
+            permits: Arc::new(Semaphore::new(limit)),
+            sender,
+            receiver: tokio::sync::Mutex::new(receiver),
+        };
+        self.routes.lock().unwrap().insert(source.to_string(), Arc::new(route));
+    }
+
+    fn route(&self, source: &str) -> Option<Arc<Route>> {
+        self.routes.lock().unwrap().get(source).cloned()
+    }
+
+    /// 检查渠道已注册并领取进行中凭证喵
+    fn admit(&self, event: &ChannelEvent) -> Result<Option<InFlight>, BusError> {
+        if self.route(&event.source).is_none() {
+            return Err(BusError::NoHandler(event.source.clone()));
+        }
+        match &self.shutdown {
+            Some(coordinator) => coordinator.try_begin().map(Some).ok_or(BusError::ShuttingDown),
+            None => Ok(None),
+        }
+    }
+
+    /// 🔒 SAFETY: 发布一个事件，队列已满时等待空位喵（背压）
+    pub async fn publish(&self, event: ChannelEvent) -> Result<(), BusError> {
+        let guard = self.admit(&event)?;
+        let slot = self.slots.clone().acquire_owned().await.map_err(|_| BusError::Closed)?;
+        self.sender.send((event, guard, slot)).map_err(|_| BusError::Closed)?;
+        self.published.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 发布一个事件，队列已满时立即返回 `BusError::Full` 喵
+    pub fn try_publish(&self, event: ChannelEvent) -> Result<(), BusError> {
+        let guard = self.admit(&event)?;
+        let Ok(slot) = self.slots.clone().try_acquire_owned() else {
+            self.saturated.fetch_add(1, Ordering::Relaxed);
+            return Err(BusError::Full(self.queued()));
+        };
+        self.sender.send((event, guard, slot)).map_err(|_| BusError::Closed)?;
+        self.published.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.config.capacity.max(1)
+    }
+
+    fn queued(&self) -> usize {
+        self.capacity() - self.slots.available_permits()
+    }
+
+    /// 当前队列深度与各渠道处理中的事件数喵
+    pub fn stats(&self) -> BusStats {
+        let in_flight = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, route)| (source.clone(), route.limit - route.permits.available_permits()))
+            .collect();
+        BusStats {
+            queued: self.queued(),
+            capacity: self.capacity(),
+            in_flight,
+            published: self.published.load(Ordering::Relaxed),
+            saturated: self.saturated.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 🔒 SAFETY: 分发循环喵（同一时间只有一个在运行，被取消后可重新启动，排队的事件保留）
+    ///
+    /// 按到达顺序把事件转入各渠道的队列，由每个渠道的分发任务先等本渠道的并发名额、
+    /// 再等全局 worker 名额；名额不足的渠道只阻塞自己的队列，
+    /// 排队事件总数达到 `capacity` 时渠道的 `publish` 随之等待喵
+    pub async fn run(&self) -> Result<(), BusError> {
+        let mut receiver = self.receiver.lock().await;
+        // 随分发循环一起取消喵（JoinSet drop 时中止各渠道的分发任务）
+        let mut dispatchers = tokio::task::JoinSet::new();
+        let mut started: HashMap<String, Arc<Route>> = HashMap::new();
+        while let Some((event, guard, slot)) = receiver.recv().await {
+            let Some(route) = self.route(&event.source) else {
+                warn!("事件总线丢弃渠道 {} 的事件喵：没有注册处理器", event.source);
+                continue;
+            };
+            if !started.get(&event.source).is_some_and(|r| Arc::ptr_eq(r, &route)) {
+                started.insert(event.source.clone(), route.clone());
+                dispatchers.spawn(route.clone().dispatch(self.workers.clone()));
+            }
+            route.sender.send((event, guard, slot)).map_err(|_| BusError::Closed)?;
+        }
+        Err(BusError::Closed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    /// 记录最大并发数的处理器喵
+    struct SlowHandler {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+        done: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler for SlowHandler {
+        async fn handle(&self, _event: ChannelEvent) {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.done.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn event(source: &str, n: usize) -> ChannelEvent {
+        ChannelEvent {
+            source: source.to_string(),
+            sender_id: n.to_string(),
+            message: format!("message {}", n),
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bus_limits_concurrency_and_applies_backpressure() {
+        let config = BusConfig {
+            capacity: 2,
+            workers: 4,
+            per_channel: 2,
+            channel_limits: HashMap::from([("busy".to_string(), 3)]),
+        };
+        let bus = Arc::new(EventBus::new(config));
+        let handler = Arc::new(SlowHandler {
+            active: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            done: AtomicUsize::new(0),
+        });
+        bus.register("telegram", handler.clone());
+        assert!(matches!(bus.try_publish(event("discord", 0)), Err(BusError::NoHandler(_))));
+
+        // 分发循环未启动时队列填满后拒绝新事件喵
+        bus.try_publish(event("telegram", 1)).unwrap();
+        bus.try_publish(event("telegram", 2)).unwrap();
+        assert!(matches!(bus.try_publish(event("telegram", 3)), Err(BusError::Full(2))));
+        let stats = bus.stats();
+        assert_eq!((stats.queued, stats.capacity, stats.published, stats.saturated), (2, 2, 2, 1));
+
+        let runner = bus.clone();
+        let dispatcher = tokio::spawn(async move { runner.run().await });
+        for n in 3..10 {
+            bus.publish(event("telegram", n)).await.unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handler.done.load(Ordering::SeqCst) < 9 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(handler.peak.load(Ordering::SeqCst), 2);
+        let stats = bus.stats();
+        assert_eq!((stats.queued, stats.published), (0, 9));
+        assert_eq!(stats.in_flight["telegram"], 0);
+        assert_eq!(bus.config.channel_limit("busy"), 3);
+        dispatcher.abort();
+    }
+
+    /// 一直占着名额、直到放行的处理器喵
+    struct StuckHandler {
+        release: tokio::sync::Notify,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler for StuckHandler {
+        async fn handle(&self, _event: ChannelEvent) {
+            self.release.notified().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saturated_channel_does_not_block_others() {
+        let config = BusConfig {
+            per_channel: 1,
+            ..BusConfig::default()
+        };
+        let bus = Arc::new(EventBus::new(config));
+        let stuck = Arc::new(StuckHandler {
+            release: tokio::sync::Notify::new(),
+        });
+        let handler = Arc::new(SlowHandler {
+            active: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            done: AtomicUsize::new(0),
+        });
+        bus.register("discord", stuck.clone());
+        bus.register("telegram", handler.clone());
+
+        let runner = bus.clone();
+        let dispatcher = tokio::spawn(async move { runner.run().await });
+        // discord 的第一个事件占满名额，后面的事件排在 telegram 之前喵
+        for n in 0..3 {
+            bus.publish(event("discord", n)).await.unwrap();
+        }
+        bus.publish(event("telegram", 3)).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handler.done.load(Ordering::SeqCst) < 1 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("telegram event stalled behind a saturated channel");
+        let stats = bus.stats();
+        assert_eq!((stats.queued, stats.in_flight["discord"]), (2, 1));
+
+        stuck.release.notify_waiters();
+        dispatcher.abort();
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+ * Prompt Canary
+ *
+ * 在系统提示词末尾附加一次性标记，要求模型在第一条回复开头原样复述喵。
+ * 标记缺失说明提示词尾部（通常是工具说明）被 provider 静默截断了喵。
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// 提示词金丝雀配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptCanaryConfig {
+    /// 标记缺失时是否改用精简工具提示词重试一次喵
+    #[serde(default = "default_retry_compact")]
+    pub retry_compact: bool,
+}
+
+fn default_retry_compact() -> bool {
+    true
+}
+
+impl Default for PromptCanaryConfig {
+    fn default() -> Self {
+        Self {
+            retry_compact: default_retry_compact(),
+        }
+    }
+}
+
+/// 金丝雀检查结果喵
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanaryVerdict {
+    /// 提示词完整（或已检查过），附带去掉标记后的回复喵
+    Pass(String),
+    /// 标记缺失，应改用精简工具提示词重发本轮喵
+    RetryCompact,
+    /// 标记缺失且不再重试，原样使用回复喵
+    Degraded(String),
+}
+
+/// 单次会话的金丝雀标记喵
+#[derive(Debug, Clone)]
+pub struct PromptCanary {
+    marker: String,
+    /// 仍在等待第一条回复喵
+    armed: bool,
+    /// 还能降级重试几次喵
+    retries_left: u8,
+}
+
+impl PromptCanary {
+    /// 生成新的随机标记喵
+    pub fn new() -> Self {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        Self {
+            marker: format!("[canary:{}]", &id[..12]),
+            armed: true,
+            retries_left: 0,
+        }
+    }
+
+    /// 按配置生成标记喵
+    pub fn from_config(config: &PromptCanaryConfig) -> Self {
+        Self {
+            retries_left: u8::from(config.retry_compact),
+            ..Self::new()
+        }
+    }
+
+    /// 检查模型回复喵
+    ///
+    /// 只检查第一条回复；之后的回复一律 Pass 原样返回喵
+    pub fn check(&mut self, reply: &str) -> CanaryVerdict {
+        if !self.armed {
+            return CanaryVerdict::Pass(reply.to_string());
+        }
+        if let Some(body) = self.verify(reply) {
+            self.armed = false;
+            return CanaryVerdict::Pass(body);
+        }
+        if self.retries_left > 0 {
+            self.retries_left -= 1;
+            return CanaryVerdict::RetryCompact;
+        }
+        self.armed = false;
+        CanaryVerdict::Degraded(reply.to_string())
+    }
+
+    /// 标记文本喵
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    /// 附加到系统提示词末尾的指令喵
+    ///
+    /// 必须放在最后：只有完整收到提示词的模型才能看到它喵
+    pub fn instruction(&self) -> String {
+        format!(
+            "===== PROMPT CHECK =====\n\
+            Begin your first reply with the exact marker {} on its own line, then answer normally. \
+            Do not mention it afterwards.",
+            self.marker
+        )
+    }
+
+    /// 检查回复是否带有标记，返回去掉标记后的正文喵
+    ///
+    /// ## Returns
+    /// Some(正文) = 提示词完整喵，None = 标记缺失喵
+    pub fn verify(&self, reply: &str) -> Option<String> {
+        let (before, after) = reply.split_once(self.marker.as_str())?;
+        // 只接受出现在回复开头附近的标记，避免正文中偶然引用喵
+        if before.trim().len() > 16 {
+            return None;
+        }
+        Some(after.trim_start().to_string())
+    }
+}
+
+impl Default for PromptCanary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_strips_marker() {
+        let canary = PromptCanary::new();
+        let reply = format!("{}\n你好主人喵！", canary.marker());
+        assert_eq!(canary.verify(&reply).as_deref(), Some("你好主人喵！"));
+        assert!(canary.instruction().contains(canary.marker()));
+    }
+
+    #[test]
+    fn test_missing_or_misplaced_marker() {
+        let canary = PromptCanary::new();
+        assert!(canary.verify("你好主人喵！").is_none());
+
+        let late = format!("A long answer that only later mentions {}", canary.marker());
+        assert!(canary.verify(&late).is_none());
+        assert!(PromptCanary::new().verify(&format!("{}\nhi", canary.marker())).is_none());
+    }
+
+    #[test]
+    fn test_check_retries_once_then_degrades() {
+        let mut canary = PromptCanary::from_config(&PromptCanaryConfig::default());
+        assert_eq!(canary.check("truncated"), CanaryVerdict::RetryCompact);
+        assert_eq!(
+            canary.check("still truncated"),
+            CanaryVerdict::Degraded("still truncated".to_string())
+        );
+        // 只检查第一条回复喵
+        assert_eq!(canary.check("later"), CanaryVerdict::Pass("later".to_string()));
+
+        let mut canary = PromptCanary::from_config(&PromptCanaryConfig::default());
+        let reply = format!("{}\nok", canary.marker());
+        assert_eq!(canary.check(&reply), CanaryVerdict::Pass("ok".to_string()));
+    }
+}
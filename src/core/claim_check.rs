@@ -0,0 +1,291 @@
+/*!
+ * Claim Check
+ *
+ * 把模型的最终回复与本轮实际的工具结果对照喵。
+ * 模型偶尔会在工具失败后仍然声称"已完成"，这里把对不上的地方提示给主人喵：
+ * - 工具失败（或命令退出码非 0），回复却只说成功
+ * - 回复提到的退出码与实际不符
+ * - `fs_write` 写入的文件已不存在或内容不同
+ * - 声称写入/创建了某个文件，但它并不存在
+ *
+ * ```toml
+ * [claim_check]
+ * verify_files = true
+ * ```
+ */
+
+use crate::tools::{ToolError, ToolResult};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 回复中表示成功的词喵
+const SUCCESS_WORDS: &[&str] = &[
+    "success", "succeeded", "done", "completed", "wrote", "written", "created", "saved", "成功", "完成", "已写入",
+    "已保存", "已创建", "搞定",
+];
+
+/// 回复中承认失败的词喵
+const FAILURE_WORDS: &[&str] = &[
+    "fail", "error", "unable", "could not", "couldn't", "denied", "失败", "错误", "出错", "无法", "没能", "拒绝",
+];
+
+/// 回复核对配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimCheckConfig {
+    /// 是否检查写入的文件仍在磁盘上且内容一致喵
+    #[serde(default = "default_verify_files")]
+    pub verify_files: bool,
+}
+
+fn default_verify_files() -> bool {
+    true
+}
+
+impl Default for ClaimCheckConfig {
+    fn default() -> Self {
+        Self {
+            verify_files: default_verify_files(),
+        }
+    }
+}
+
+/// 回复与实际结果不符之处喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    /// 回复中的说法喵
+    pub claim: String,
+    /// 实际情况喵
+    pub actual: String,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}，但{}", self.claim, self.actual)
+    }
+}
+
+/// 单次工具调用的结果摘要喵
+#[derive(Debug, Clone)]
+struct ToolOutcome {
+    tool: String,
+    /// 失败原因（工具报错或退出码非 0）喵
+    failure: Option<String>,
+    exit_code: Option<i64>,
+}
+
+/// 🔒 SAFETY: 单轮对话的工具结果记录与回复核对喵
+#[derive(Debug, Clone)]
+pub struct ClaimVerifier {
+    config: ClaimCheckConfig,
+    workspace: PathBuf,
+    scratch: Option<PathBuf>,
+    outcomes: Vec<ToolOutcome>,
+    /// 成功写入的文件（相对路径 → 写入内容，同一路径只保留最后一次）喵
+    written: BTreeMap<String, (PathBuf, String)>,
+}
+
+impl ClaimVerifier {
+    /// `workspace` 为 `fs_write` 的写入根目录喵
+    pub fn new(config: ClaimCheckConfig, workspace: &Path) -> Self {
+        Self {
+            config,
+            workspace: workspace.to_path_buf(),
+            scratch: None,
+            outcomes: Vec::new(),
+            written: BTreeMap::new(),
+        }
+    }
+
+    /// 会话临时工作区（`location: scratch` 的写入）喵
+    pub fn with_scratch_dir(mut self, dir: &Path) -> Self {
+        self.scratch = Some(dir.to_path_buf());
+        self
+    }
+
+    /// 开始新的一轮，清空记录喵
+    pub fn reset(&mut self) {
+        self.outcomes.clear();
+        self.written.clear();
+    }
+
+    /// 记录一次工具调用喵
+    pub fn record(&mut self, tool: &str, input: &JsonValue, result: &Result<ToolResult, ToolError>) {
+        let data = result.as_ref().ok().and_then(|r| r.data.as_ref());
+        let exit_code = data.and_then(|d| d.get("exit_code")).and_then(JsonValue::as_i64);
+        let failure = match result {
+            Err(e) => Some(e.to_string()),
+            Ok(r) if !r.success => Some(r.error.clone().unwrap_or_else(|| "unknown error".to_string())),
+            Ok(_) => exit_code.filter(|code| *code != 0).map(|code| format!("exit code {}", code)),
+        };
+
+        if failure.is_none() && tool == "fs_write" {
+            let path = data.and_then(|d| d.get("path")).and_then(JsonValue::as_str);
+            let content = input.get("content").and_then(JsonValue::as_str);
+            let root = match data.and_then(|d| d.get("location")).and_then(JsonValue::as_str) {
+                Some("scratch") => self.scratch.clone(),
+                _ => Some(self.workspace.clone()),
+            };
+            if let (Some(path), Some(content), Some(root)) = (path, content, root) {
+                self.written
+                    .insert(path.to_string(), (root.join(path), content.to_string()));
+            }
+        }
+
+        self.outcomes.push(ToolOutcome {
+            tool: tool.to_string(),
+            failure,
+            exit_code,
+        });
+    }
+
+    /// 🔒 SAFETY: 把最终回复与记录的工具结果对照喵
+    ///
+    /// ## Returns
+    /// 对不上的地方（没有调用工具时总是为空）
+    pub fn verify(&self, reply: &str) -> Vec<Discrepancy> {
+        if self.outcomes.is_empty() {
+            return Vec::new();
+        }
+        let lower = reply.to_lowercase();
+        let claims_success = SUCCESS_WORDS.iter().any(|w| lower.contains(w));
+        let admits_failure = FAILURE_WORDS.iter().any(|w| lower.contains(w));
+        let mut found = Vec::new();
+
+        if claims_success && !admits_failure {
+            for outcome in &self.outcomes {
+                if let Some(failure) = &outcome.failure {
+                    found.push(Discrepancy {
+                        claim: "回复声称已成功".to_string(),
+                        actual: format!("{} 实际失败了: {}", outcome.tool, failure),
+                    });
+                }
+            }
+        }
+
+        let exit_codes: Vec<i64> = self.outcomes.iter().filter_map(|o| o.exit_code).collect();
+        if !exit_codes.is_empty() {
+            for claimed in claimed_exit_codes(reply) {
+                if !exit_codes.contains(&claimed) {
+                    found.push(Discrepancy {
+                        claim: format!("回复称退出码为 {}", claimed),
+                        actual: format!("实际退出码为 {:?}", exit_codes),
+                    });
+                }
+            }
+        }
+
+        if self.config.verify_files {
+            for (path, (full_path, content)) in &self.written {
+                if !claims_success && !reply.contains(path.as_str()) {
+                    continue;
+                }
+                let actual = match std::fs::read(full_path) {
+                    Ok(bytes) if bytes == content.as_bytes() => continue,
+                    Ok(_) => "文件内容与写入的不同（可能之后被改动过）".to_string(),
+                    Err(_) => "文件并不存在".to_string(),
+                };
+                found.push(Discrepancy {
+                    claim: format!("回复称已写入 {}", path),
+                    actual,
+                });
+            }
+            for path in claimed_written_paths(reply) {
+                if self.written.keys().any(|w| w == &path || w.ends_with(&format!("/{}", path))) {
+                    continue;
+                }
+                let roots = std::iter::once(&self.workspace).chain(self.scratch.as_ref());
+                if roots.into_iter().any(|root| root.join(&path).exists()) {
+                    continue;
+                }
+                found.push(Discrepancy {
+                    claim: format!("回复称已写入 {}", path),
+                    actual: "没有成功写入它的工具调用，文件也不存在".to_string(),
+                });
+            }
+        }
+        found
+    }
+}
+
+/// 回复中提到的退出码喵
+fn claimed_exit_codes(reply: &str) -> Vec<i64> {
+    static EXIT_CODE: OnceLock<Regex> = OnceLock::new();
+    let re = EXIT_CODE.get_or_init(|| {
+        Regex::new(r"(?i)(?:exit(?:ed)?(?: with)?(?: code| status)|退出码)\s*(?:of|为|是)?\s*[:：=]?\s*`?(-?\d+)").unwrap()
+    });
+    re.captures_iter(reply).filter_map(|c| c[1].parse().ok()).collect()
+}
+
+/// 回复中声称写入/创建/保存的文件路径喵
+fn claimed_written_paths(reply: &str) -> Vec<String> {
+    static WRITTEN: OnceLock<Regex> = OnceLock::new();
+    let re = WRITTEN.get_or_init(|| {
+        Regex::new(
+            r"(?i)(?:wrote|written to|saved(?: to)?|created|写入了?|保存到|保存了|创建了?)\s*(?:the )?(?:file )?[`'“]?([\w./-]+\.[A-Za-z0-9]+)",
+        )
+        .unwrap()
+    });
+    let mut paths: Vec<String> = re
+        .captures_iter(reply)
+        .map(|c| c[1].trim_start_matches("./").to_string())
+        .filter(|p| !p.starts_with('/') && !p.contains(".."))
+        .collect();
+    paths.dedup();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flags_claims_that_contradict_tool_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut verifier = ClaimVerifier::new(ClaimCheckConfig::default(), dir.path());
+        verifier.record(
+            "shell",
+            &json!({"command": "cargo"}),
+            &Ok(ToolResult::success(json!({"exit_code": 101, "success": false}), 5)),
+        );
+        verifier.record(
+            "fs_write",
+            &json!({"path": "notes.md", "content": "hello"}),
+            &Err(ToolError::ExecutionFailed("disk full".to_string())),
+        );
+
+        let found = verifier.verify("Done! I saved notes.md and the build exited with code 0 喵");
+        let claims: Vec<&str> = found.iter().map(|d| d.claim.as_str()).collect();
+        assert_eq!(
+            claims,
+            ["回复声称已成功", "回复声称已成功", "回复称退出码为 0", "回复称已写入 notes.md"]
+        );
+
+        // 如实汇报失败时不提示喵
+        assert!(verifier.verify("The build failed with exit code 101.").is_empty());
+    }
+
+    #[test]
+    fn test_written_file_checked_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut verifier = ClaimVerifier::new(ClaimCheckConfig::default(), dir.path());
+        std::fs::write(dir.path().join("a.txt"), "v1").unwrap();
+        verifier.record(
+            "fs_write",
+            &json!({"path": "a.txt", "content": "v1"}),
+            &Ok(ToolResult::success(json!({"path": "a.txt", "location": "workspace"}), 1)),
+        );
+        assert!(verifier.verify("已写入 a.txt 喵").is_empty());
+
+        std::fs::write(dir.path().join("a.txt"), "edited").unwrap();
+        assert_eq!(verifier.verify("已写入 a.txt 喵").len(), 1);
+
+        verifier.reset();
+        assert!(verifier.verify("已写入 a.txt 喵").is_empty());
+    }
+}
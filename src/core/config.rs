@@ -1,12 +1,23 @@
 /*!
  * 配置加载模块
  *
+ * - config.json / config.toml 加载与保存，secrets.json 中的加密密钥覆盖到对应字段
+ * - `ConfigWatcher`：轮询配置文件，变化后重新加载并用 `ConfigValidator` 校验，
+ *   通过 `watch` 通道推送给服务管理器与后台任务 (校验失败时保留旧配置)
+ *
  * 作者: 缪斯 (Muse) @缪斯
  * 日期: 2026-02-15 17:40 JST
  */
 
-use crate::core::traits::{Config, Result};
-use std::path::Path;
+use crate::config::validator::{ConfigValidator, ValidationRule};
+use crate::core::traits::{Config, ProviderConfig, Result};
+use crate::core::WorkspaceProfile;
+use crate::security::crypto::{decrypt_secret, is_encrypted_secret};
+use crate::security::secrets::{apply_secrets, SECRETS_FILE};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::watch;
 
 impl Default for Config {
     fn default() -> Self {
@@ -21,21 +32,155 @@ impl Default for Config {
                 .join(".nekoclaw/workspace"),
             providers: None,
             discord_config: None,
+            telegram: None,
+            email: None,
             gateway_port: Some(8080),
             gateway_bind: Some("127.0.0.1".to_string()),
+            gateway_auth: Default::default(),
+            gateway_heartbeat_secs: None,
+            provider_health: Default::default(),
+            warmup: Default::default(),
+            watch: Vec::new(),
+            scratch: Default::default(),
+            memory: None,
+            sync: None,
+            privacy: None,
+            security: None,
+            prompt_canary: None,
+            claim_check: None,
+            delegation: None,
+            tool_prompt: None,
+            mcp_servers: Default::default(),
+            tool_budget: Default::default(),
+            tool_output: Default::default(),
+            event_bus: Default::default(),
+            schedule: Default::default(),
+            shutdown: Default::default(),
+            embeddings: Default::default(),
+            rbac: Default::default(),
+            few_shot: None,
+            post_process: None,
+            response_policies: Default::default(),
+            agents: Default::default(),
+            presets: Default::default(),
+            default_preset: None,
+            persona: Default::default(),
+            experiments: Vec::new(),
+            session_titles: Default::default(),
+            session_encryption: None,
+            compression: Default::default(),
+            tokenizer: Default::default(),
+            costs: Default::default(),
+            otlp: None,
+            webhooks: Default::default(),
+            outbound_webhooks: Vec::new(),
+            auth: None,
+            secrets_dir: None,
         }
     }
 }
 
+impl Config {
+    /// 获取 provider 连接配置（未配置时回退到环境变量与默认端点）喵
+    ///
+    /// `enc:` 前缀的 API Key 在这里解密；解密失败时视为未配置 API Key 喵
+    ///
+    /// ## Returns
+    /// 不认识的 provider 返回 None 喵
+    pub fn provider(&self, name: &str) -> Option<ProviderConfig> {
+        let providers = self.providers.as_ref();
+        let (configured, base_url, key_env) = match name {
+            "nvidia" => (
+                providers.and_then(|p| p.nvidia.as_ref()),
+                "https://integrate.api.nvidia.com/v1",
+                "NVIDIA_API_KEY",
+            ),
+            "openai" => (
+                providers.and_then(|p| p.openai.as_ref()),
+                "https://api.openai.com/v1",
+                "OPENAI_API_KEY",
+            ),
+            "openrouter" => (
+                providers.and_then(|p| p.openrouter.as_ref()),
+                "https://openrouter.ai/api/v1",
+                "OPENROUTER_API_KEY",
+            ),
+            "anthropic" => (
+                providers.and_then(|p| p.anthropic.as_ref()),
+                "https://api.anthropic.com/v1",
+                "ANTHROPIC_API_KEY",
+            ),
+            "gemini" => (
+                providers.and_then(|p| p.gemini.as_ref()),
+                "https://generativelanguage.googleapis.com/v1beta",
+                "GEMINI_API_KEY",
+            ),
+            // 本地服务无需 API Key喵
+            "ollama" => (
+                providers.and_then(|p| p.ollama.as_ref()),
+                "http://localhost:11434",
+                "OLLAMA_API_KEY",
+            ),
+            _ => return None,
+        };
+        let mut provider = configured.cloned().unwrap_or_else(|| ProviderConfig {
+            base_url: base_url.to_string(),
+            api_key: std::env::var(key_env).unwrap_or_default(),
+            timeout: 60,
+            max_retries: 3,
+        });
+        if is_encrypted_secret(&provider.api_key) {
+            let dir = self.secrets_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+            provider.api_key = decrypt_secret(&dir, &provider.api_key).unwrap_or_else(|e| {
+                tracing::warn!("Failed to decrypt providers.{}.api_key: {}", name, e);
+                String::new()
+            });
+        }
+        Some(provider)
+    }
+
+    /// 记忆数据库路径喵
+    pub fn memory_db_path(&self) -> PathBuf {
+        self.memory
+            .as_ref()
+            .and_then(|m| m.path.clone())
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(".nekoclaw/memory.db")
+            })
+    }
+}
+
+/// 解析 JSON 格式配置（输入不可信，错误返回而不 panic）喵
+pub fn parse_json(content: &str) -> Result<Config> {
+    serde_json::from_str(content).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+}
+
+/// 解析 TOML 格式配置喵
+pub fn parse_toml(content: &str) -> Result<Config> {
+    toml::from_str(content).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+}
+
+/// 加载配置目录喵（secrets.json 中的密文覆盖到对应字段）
 pub fn load(config_dir: &Path) -> Result<Config> {
+    let mut config = load_file(config_dir)?;
+    if config_dir.join(SECRETS_FILE).exists() {
+        let mut value = serde_json::to_value(&config)?;
+        apply_secrets(config_dir, &mut value)?;
+        config = serde_json::from_value(value)?;
+    }
+    config.secrets_dir = Some(config_dir.to_path_buf());
+    Ok(config)
+}
+
+fn load_file(config_dir: &Path) -> Result<Config> {
     // 优先尝试 config.json
     let json_path = config_dir.join("config.json");
     if json_path.exists() {
         let content = std::fs::read_to_string(&json_path)
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-        let config: Config = serde_json::from_str(&content)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-        return Ok(config);
+        return parse_json(&content);
     }
 
     // 其次尝试 config.toml
@@ -43,9 +188,7 @@ pub fn load(config_dir: &Path) -> Result<Config> {
     if toml_path.exists() {
         let content = std::fs::read_to_string(&toml_path)
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-        let config: Config = toml::from_str(&content)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-        return Ok(config);
+        return parse_toml(&content);
     }
 
     // 都不存在则返回默认配置
@@ -65,3 +208,185 @@ pub fn save(config_dir: &Path, config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+/// 配置文件名 (按加载优先级)
+const CONFIG_FILES: [&str; 2] = ["config.json", "config.toml"];
+
+/// 热重载前的配置校验规则
+///
+/// 可选字段为 null 时跳过范围检查
+pub fn validator() -> ConfigValidator {
+    let mut validator = ConfigValidator::new();
+    validator.add_rule(
+        ValidationRule::new("default_provider")
+            .required()
+            .with_type("string")
+            .with_length_range(1, 64),
+    );
+    validator.add_rule(
+        ValidationRule::new("default_model")
+            .required()
+            .with_type("string")
+            .with_length_range(1, 200),
+    );
+    validator.add_rule(
+        ValidationRule::new("default_temperature")
+            .with_type("number")
+            .with_range(0.0, 2.0),
+    );
+    validator.add_rule(ValidationRule::new("gateway_port").with_range(1.0, 65535.0));
+    validator.add_rule(ValidationRule::new("gateway_heartbeat_secs").with_range(1.0, 3600.0));
+    validator
+}
+
+/// 校验配置
+pub fn validate(config: &Config) -> Result<()> {
+    validator().validate(&serde_json::to_value(config)?)?;
+    Ok(())
+}
+
+/// 配置文件状态 (mtime, 大小)，文件不存在时为 None
+type FileStamp = Vec<Option<(Option<SystemTime>, u64)>>;
+
+/// 配置热重载监视器
+///
+/// 守护进程定时调用 `poll`；订阅者通过 `subscribe` 拿到最新的已校验配置
+pub struct ConfigWatcher {
+    profile: WorkspaceProfile,
+    files: Vec<PathBuf>,
+    validator: ConfigValidator,
+    stamp: Mutex<FileStamp>,
+    sender: watch::Sender<Arc<Config>>,
+}
+
+impl ConfigWatcher {
+    /// `initial` 为启动时加载的配置，当前文件状态作为基线
+    pub fn new(profile: WorkspaceProfile, initial: Config) -> Self {
+        let mut dirs = vec![profile.base_dir.clone()];
+        if !profile.is_default() {
+            dirs.push(profile.root.clone());
+        }
+        let files: Vec<PathBuf> = dirs
+            .iter()
+            .flat_map(|dir| CONFIG_FILES.iter().map(move |name| dir.join(name)))
+            .collect();
+        let (sender, _) = watch::channel(Arc::new(initial));
+        Self {
+            stamp: Mutex::new(file_stamp(&files)),
+            files,
+            profile,
+            validator: validator(),
+            sender,
+        }
+    }
+
+    /// 订阅配置更新
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Config>> {
+        self.sender.subscribe()
+    }
+
+    /// 当前生效的配置
+    pub fn current(&self) -> Arc<Config> {
+        self.sender.borrow().clone()
+    }
+
+    /// 配置文件变化时重新加载并推送；返回是否推送了新配置
+    ///
+    /// 解析或校验失败时返回错误并保留旧配置，直到文件再次变化
+    pub fn poll(&self) -> Result<bool> {
+        let stamp = file_stamp(&self.files);
+        {
+            let mut last = self.stamp.lock().unwrap_or_else(|e| e.into_inner());
+            if *last == stamp {
+                return Ok(false);
+            }
+            *last = stamp;
+        }
+
+        let config = self
+            .profile
+            .load_config()
+            .map_err(|e| format!("Config reload failed, keeping previous config: {}", e))?;
+        self.validator
+            .validate(&serde_json::to_value(&config)?)
+            .map_err(|e| format!("Config reload rejected, keeping previous config: {}", e))?;
+        if serde_json::to_value(&config)? == serde_json::to_value(&*self.current())? {
+            return Ok(false);
+        }
+        self.sender.send_replace(Arc::new(config));
+        Ok(true)
+    }
+}
+
+fn file_stamp(files: &[PathBuf]) -> FileStamp {
+    files
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.modified().ok(), metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_default_config_roundtrips() {
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        let parsed = parse_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn test_watcher_pushes_only_valid_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(dir.path(), None).unwrap();
+        let watcher = ConfigWatcher::new(profile, Config::default());
+        let mut updates = watcher.subscribe();
+        assert!(!watcher.poll().unwrap());
+
+        let write = |temperature: f64| {
+            let mut value = serde_json::to_value(Config::default()).unwrap();
+            value["default_model"] = serde_json::json!("gpt-4o-mini");
+            value["default_temperature"] = serde_json::json!(temperature);
+            std::fs::write(dir.path().join("config.json"), value.to_string()).unwrap();
+        };
+
+        write(0.3);
+        assert!(watcher.poll().unwrap());
+        assert!(updates.has_changed().unwrap());
+        assert_eq!(updates.borrow_and_update().default_model, "gpt-4o-mini");
+        assert!(!watcher.poll().unwrap());
+
+        // 超出范围的配置被拒绝，订阅者继续使用旧配置
+        write(12.25);
+        assert!(watcher.poll().unwrap_err().to_string().contains("default_temperature"));
+        assert!(!updates.has_changed().unwrap());
+        assert_eq!(watcher.current().default_temperature, 0.3);
+        assert!(!watcher.poll().unwrap());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parsers_never_panic(content in ".{0,512}") {
+            let _ = parse_json(&content);
+            let _ = parse_toml(&content);
+        }
+
+        #[test]
+        fn prop_json_fields_survive_parsing(
+            model in "[a-zA-Z0-9/._-]{1,40}",
+            temperature in 0.0f64..2.0,
+        ) {
+            let mut value = serde_json::to_value(Config::default()).unwrap();
+            value["default_model"] = serde_json::json!(model);
+            value["default_temperature"] = serde_json::json!(temperature);
+            let config = parse_json(&value.to_string()).unwrap();
+            prop_assert_eq!(config.default_model, model);
+            prop_assert!((config.default_temperature - temperature).abs() < 1e-9);
+        }
+    }
+}
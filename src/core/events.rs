@@ -0,0 +1,135 @@
+/*!
+ * Agent Events - 结构化输出
+ *
+ * `nekoclaw agent --output json` 不再打印给人看的终端输出，而是每行一个 JSON 事件（NDJSON）喵：
+ *
+ * ```text
+ * {"type":"session","session_id":"…","agent":"nia","model":"gpt-4o"}
+ * {"type":"assistant","text":"让我看看喵"}
+ * {"type":"tool_call","id":"call_1","name":"fs_read","arguments":{"path":"README.md"}}
+ * {"type":"tool_result","id":"call_1","name":"fs_read","ok":true,"output":"…"}
+ * {"type":"usage","model":"gpt-4o","prompt_tokens":812,"completion_tokens":64,"total_tokens":876,"cost":0.0026}
+ * {"type":"error","message":"Session budget exceeded: …"}
+ * ```
+ * `--file` 批量模式在每条消息前输出 `{"type":"prompt","index":0,"text":"…"}`
+ * 日志改写到 stderr，stdout 只有事件，可以直接交给 `jq` 或其他程序喵
+ */
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Agent 命令的输出格式喵
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AgentOutput {
+    /// 终端输出
+    #[default]
+    Text,
+    /// 每行一个 JSON 事件
+    Json,
+}
+
+/// 结构化事件喵
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent<'a> {
+    /// 会话开始
+    Session {
+        session_id: &'a str,
+        agent: &'a str,
+        model: &'a str,
+    },
+    /// 批量模式中开始处理第 `index` 条消息（从 0 开始）
+    Prompt { index: usize, text: &'a str },
+    /// 助手回复（已经过回复钩子处理）
+    Assistant { text: &'a str },
+    /// 模型请求的工具调用
+    ToolCall {
+        id: Option<&'a str>,
+        name: &'a str,
+        arguments: &'a Value,
+    },
+    /// 工具执行结果（失败时 `ok` 为 false，`output` 为错误说明）
+    ToolResult {
+        id: Option<&'a str>,
+        name: &'a str,
+        ok: bool,
+        output: &'a str,
+    },
+    /// 单次模型请求的用量
+    Usage {
+        model: &'a str,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+        total_tokens: u32,
+        cost: f64,
+    },
+    /// 请求失败 / 预算或限额用尽
+    Error { message: &'a str },
+}
+
+/// 🔒 SAFETY: NDJSON 事件输出喵（每个事件写完立即 flush，管道下游能实时读到）
+pub struct EventStream {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventStream {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self { out: Mutex::new(out) }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(Box::new(std::io::stdout()))
+    }
+
+    /// 输出一个事件喵（写入失败时忽略，下游关闭管道不影响 Agent）
+    pub fn emit(&self, event: &AgentEvent<'_>) {
+        let Ok(line) = serde_json::to_string(event) else { return };
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// 测试用的共享缓冲区喵
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_newline_delimited_json() {
+        let buffer = Buffer::default();
+        let events = EventStream::new(Box::new(buffer.clone()));
+        let arguments = serde_json::json!({ "path": "README.md" });
+        events.emit(&AgentEvent::Assistant { text: "第一行\n第二行" });
+        events.emit(&AgentEvent::ToolCall {
+            id: Some("call_1"),
+            name: "fs_read",
+            arguments: &arguments,
+        });
+        events.emit(&AgentEvent::Error { message: "boom" });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "assistant");
+        assert_eq!(lines[0]["text"], "第一行\n第二行");
+        assert_eq!(lines[1]["type"], "tool_call");
+        assert_eq!(lines[1]["arguments"]["path"], "README.md");
+        assert_eq!(lines[2], serde_json::json!({ "type": "error", "message": "boom" }));
+    }
+}
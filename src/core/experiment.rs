@@ -0,0 +1,293 @@
+/*!
+ * System Prompt A/B Experiments
+ *
+ * 在配置中为某个 Agent 定义多个提示词变体与流量权重，
+ * 每个会话按会话 ID 稳定地分配到一个变体，并把变体写进遥测标签喵。
+ *
+ * ```toml
+ * [[experiments]]
+ * name = "persona-v2"
+ * agent = "nia"
+ *
+ * [[experiments.variants]]
+ * name = "control"
+ * weight = 50
+ *
+ * [[experiments.variants]]
+ * name = "warmer"
+ * weight = 50
+ * persona = "You are Nia, a gentle and patient Cat-Girl System Admin..."
+ * ```
+ *
+ * `nekoclaw experiments report` 汇总各变体的成功率 / 延迟 / 反馈喵
+ */
+
+use crate::core::traits::Config;
+use crate::telemetry::CustomMetric;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 每轮对话指标名（值 = 延迟毫秒）喵
+pub const TURN_METRIC: &str = "experiment.turn";
+/// 用户反馈指标名（值 = +1 / -1）喵
+pub const FEEDBACK_METRIC: &str = "experiment.feedback";
+
+fn default_agent() -> String {
+    "nia".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// 单个实验喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// 实验名（写入遥测标签）喵
+    pub name: String,
+    /// 目标 Agent喵
+    #[serde(default = "default_agent")]
+    pub agent: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub variants: Vec<PromptVariant>,
+}
+
+/// 提示词变体喵（persona / suffix 均未设置时即对照组）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptVariant {
+    pub name: String,
+    /// 流量权重喵
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    /// 替换默认人设段落喵
+    #[serde(default)]
+    pub persona: Option<String>,
+    /// 追加到系统提示词末尾喵
+    #[serde(default)]
+    pub suffix: Option<String>,
+}
+
+impl PromptVariant {
+    /// 应用到默认人设上喵
+    pub fn persona<'a>(&'a self, default: &'a str) -> &'a str {
+        self.persona.as_deref().unwrap_or(default)
+    }
+}
+
+impl ExperimentConfig {
+    /// 校验实验定义喵
+    pub fn validate(&self) -> Result<(), String> {
+        if self.variants.is_empty() {
+            return Err(format!("实验 '{}' 没有任何变体喵", self.name));
+        }
+        if self.variants.iter().map(|v| v.weight as u64).sum::<u64>() == 0 {
+            return Err(format!("实验 '{}' 的变体权重之和为 0 喵", self.name));
+        }
+        let mut seen = BTreeSet::new();
+        for variant in &self.variants {
+            if !seen.insert(variant.name.as_str()) {
+                return Err(format!("实验 '{}' 中变体 '{}' 重复喵", self.name, variant.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// 按会话 ID 稳定分配变体喵（同一会话总是得到同一变体）
+    pub fn assign(&self, session_id: &str) -> &PromptVariant {
+        let total: u64 = self.variants.iter().map(|v| v.weight as u64).sum();
+        let mut bucket = fnv1a(format!("{}:{}", self.name, session_id).as_bytes()) % total.max(1);
+        for variant in &self.variants {
+            if bucket < variant.weight as u64 {
+                return variant;
+            }
+            bucket -= variant.weight as u64;
+        }
+        &self.variants[0]
+    }
+}
+
+/// FNV-1a：跨版本稳定的哈希，保证重启后分组不变喵
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Config {
+    /// 某个 Agent 当前生效的实验喵（取第一个启用的）
+    pub fn experiment_for(&self, agent: &str) -> Result<Option<&ExperimentConfig>, String> {
+        match self.experiments.iter().find(|e| e.enabled && e.agent == agent) {
+            Some(experiment) => experiment.validate().map(|_| Some(experiment)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 单个变体的汇总喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VariantReport {
+    pub experiment: String,
+    pub variant: String,
+    pub sessions: usize,
+    pub turns: usize,
+    pub successes: usize,
+    pub total_latency_ms: f64,
+    pub thumbs_up: usize,
+    pub thumbs_down: usize,
+}
+
+impl VariantReport {
+    pub fn success_rate(&self) -> f64 {
+        if self.turns == 0 {
+            0.0
+        } else {
+            self.successes as f64 / self.turns as f64
+        }
+    }
+
+    pub fn avg_latency_ms(&self) -> f64 {
+        if self.turns == 0 {
+            0.0
+        } else {
+            self.total_latency_ms / self.turns as f64
+        }
+    }
+
+    /// 净反馈分（👍 - 👎）喵
+    pub fn feedback_score(&self) -> i64 {
+        self.thumbs_up as i64 - self.thumbs_down as i64
+    }
+}
+
+/// 从遥测指标汇总各变体表现喵（`experiment` 为 None 时汇总全部实验）
+pub fn summarize(metrics: &[CustomMetric], experiment: Option<&str>) -> Vec<VariantReport> {
+    let mut reports: BTreeMap<(String, String), (VariantReport, BTreeSet<String>)> = BTreeMap::new();
+
+    for metric in metrics {
+        let (Some(name), Some(variant)) = (metric.labels.get("experiment"), metric.labels.get("variant"))
+        else {
+            continue;
+        };
+        if experiment.is_some_and(|e| e != name) {
+            continue;
+        }
+        let (report, sessions) = reports
+            .entry((name.clone(), variant.clone()))
+            .or_insert_with(|| {
+                let report = VariantReport {
+                    experiment: name.clone(),
+                    variant: variant.clone(),
+                    ..Default::default()
+                };
+                (report, BTreeSet::new())
+            });
+        if let Some(session) = metric.labels.get("session") {
+            sessions.insert(session.clone());
+        }
+
+        match metric.name.as_str() {
+            TURN_METRIC => {
+                report.turns += 1;
+                report.total_latency_ms += metric.value;
+                if metric.labels.get("outcome").map(String::as_str) == Some("success") {
+                    report.successes += 1;
+                }
+            }
+            FEEDBACK_METRIC if metric.value > 0.0 => report.thumbs_up += 1,
+            FEEDBACK_METRIC if metric.value < 0.0 => report.thumbs_down += 1,
+            _ => {}
+        }
+    }
+
+    reports
+        .into_values()
+        .map(|(mut report, sessions)| {
+            report.sessions = sessions.len();
+            report
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::MetricKind;
+
+    fn experiment(weights: &[(&str, u32)]) -> ExperimentConfig {
+        ExperimentConfig {
+            name: "persona-v2".to_string(),
+            agent: "nia".to_string(),
+            enabled: true,
+            variants: weights
+                .iter()
+                .map(|(name, weight)| PromptVariant {
+                    name: name.to_string(),
+                    weight: *weight,
+                    persona: None,
+                    suffix: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn metric(name: &str, value: f64, labels: &[(&str, &str)]) -> CustomMetric {
+        CustomMetric {
+            name: name.to_string(),
+            kind: MetricKind::Gauge,
+            value,
+            labels: labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            source: "experiments".to_string(),
+            recorded_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_assignment_is_stable_and_weighted() {
+        let exp = experiment(&[("control", 3), ("warmer", 1)]);
+        assert!(exp.validate().is_ok());
+        assert_eq!(exp.assign("session-1"), exp.assign("session-1"));
+
+        let warmer = (0..4000)
+            .filter(|i| exp.assign(&format!("session-{}", i)).name == "warmer")
+            .count();
+        assert!((800..1200).contains(&warmer), "warmer got {}", warmer);
+
+        let off = experiment(&[("only", 1), ("never", 0)]);
+        assert!((0..100).all(|i| off.assign(&i.to_string()).name == "only"));
+
+        assert!(experiment(&[]).validate().is_err());
+        assert!(experiment(&[("a", 0)]).validate().is_err());
+        assert!(experiment(&[("a", 1), ("a", 1)]).validate().is_err());
+    }
+
+    #[test]
+    fn test_summarize_per_variant() {
+        let labels = |variant: &'static str, session: &'static str, outcome: &'static str| {
+            [("experiment", "persona-v2"), ("variant", variant), ("session", session), ("outcome", outcome)]
+        };
+        let metrics = vec![
+            metric(TURN_METRIC, 100.0, &labels("control", "s1", "success")),
+            metric(TURN_METRIC, 300.0, &labels("control", "s1", "error")),
+            metric(TURN_METRIC, 200.0, &labels("warmer", "s2", "success")),
+            metric(FEEDBACK_METRIC, 1.0, &[("experiment", "persona-v2"), ("variant", "warmer")]),
+            metric(FEEDBACK_METRIC, -1.0, &[("experiment", "persona-v2"), ("variant", "control")]),
+            metric(TURN_METRIC, 50.0, &[("experiment", "other"), ("variant", "a")]),
+        ];
+
+        let reports = summarize(&metrics, Some("persona-v2"));
+        assert_eq!(reports.len(), 2);
+        let control = &reports[0];
+        assert_eq!((control.variant.as_str(), control.sessions, control.turns), ("control", 1, 2));
+        assert_eq!(control.success_rate(), 0.5);
+        assert_eq!(control.avg_latency_ms(), 200.0);
+        assert_eq!(control.feedback_score(), -1);
+        assert_eq!(reports[1].thumbs_up, 1);
+
+        assert_eq!(summarize(&metrics, None).len(), 3);
+    }
+}
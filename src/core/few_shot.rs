@@ -0,0 +1,251 @@
+/*!
+ * Few-shot Examples
+ *
+ * 为 Agent 准备精选示例（user / assistant / tool 三元组），
+ * 在系统提示词之后注入，用于稳定生产流程的输出格式喵。
+ *
+ * 示例来源：
+ * - 配置 `few_shot.examples`
+ * - 工作区根目录下的 `few_shot.json`（数组）
+ */
+
+use crate::providers::Message;
+use crate::tools::estimate_tokens;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 工作区示例文件名喵
+pub const FEW_SHOT_FILE: &str = "few_shot.json";
+/// 轮换游标文件名喵
+const CURSOR_FILE: &str = "few_shot.cursor";
+
+/// 单个示例喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotExample {
+    /// 用户输入喵
+    pub user: String,
+    /// 助手回复（通常包含 `@tool(...)` 调用）喵
+    pub assistant: String,
+    /// 工具结果（可选，格式与真实对话一致）喵
+    #[serde(default)]
+    pub tool: Option<FewShotToolResult>,
+}
+
+/// 示例中的工具结果喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotToolResult {
+    /// 工具名称喵
+    pub name: String,
+    /// 工具输出喵
+    pub result: String,
+}
+
+impl FewShotExample {
+    /// 转换为对话消息喵
+    pub fn to_messages(&self) -> Vec<Message> {
+        let mut messages = vec![
+            Message::user(self.user.clone()),
+            Message::assistant(self.assistant.clone()),
+        ];
+        if let Some(tool) = &self.tool {
+            messages.push(Message::user(format!(
+                "Tool result for {}: {}",
+                tool.name, tool.result
+            )));
+        }
+        messages
+    }
+
+    /// 估算 token 数喵
+    pub fn estimated_tokens(&self) -> usize {
+        self.to_messages()
+            .iter()
+            .map(|m| estimate_tokens(&m.content))
+            .sum()
+    }
+}
+
+/// 轮换策略喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FewShotRotation {
+    /// 始终按顺序取前几个喵
+    #[default]
+    Fixed,
+    /// 每次会话从下一个示例开始喵
+    RoundRobin,
+    /// 每次会话随机打乱喵
+    Random,
+}
+
+/// Few-shot 配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotConfig {
+    /// 内联示例喵
+    #[serde(default)]
+    pub examples: Vec<FewShotExample>,
+    /// 示例总 token 预算喵
+    #[serde(default = "default_token_budget")]
+    pub token_budget: usize,
+    /// 轮换策略喵
+    #[serde(default)]
+    pub rotation: FewShotRotation,
+}
+
+fn default_token_budget() -> usize {
+    800
+}
+
+impl Default for FewShotConfig {
+    fn default() -> Self {
+        Self {
+            examples: Vec::new(),
+            token_budget: default_token_budget(),
+            rotation: FewShotRotation::default(),
+        }
+    }
+}
+
+/// 示例库喵
+#[derive(Debug, Clone)]
+pub struct FewShotLibrary {
+    examples: Vec<FewShotExample>,
+    config: FewShotConfig,
+    /// 轮换游标保存目录（None = 不持久化）喵
+    state_dir: Option<PathBuf>,
+}
+
+impl FewShotLibrary {
+    /// 从配置创建喵
+    pub fn new(config: FewShotConfig) -> Self {
+        Self {
+            examples: config.examples.clone(),
+            config,
+            state_dir: None,
+        }
+    }
+
+    /// 合并工作区示例文件并在该目录保存轮换游标喵
+    pub fn with_workspace(mut self, root: &Path) -> crate::core::traits::Result<Self> {
+        let path = root.join(FEW_SHOT_FILE);
+        if path.exists() {
+            let extra: Vec<FewShotExample> =
+                serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            self.examples.extend(extra);
+        }
+        self.state_dir = Some(root.to_path_buf());
+        Ok(self)
+    }
+
+    /// 示例数量喵
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// 是否为空喵
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// 为新会话选出示例并展开为消息喵
+    ///
+    /// 按轮换策略排序后依次加入，超出 token 预算的示例会被跳过喵
+    pub fn select_messages(&self) -> Vec<Message> {
+        if self.examples.is_empty() {
+            return Vec::new();
+        }
+
+        let mut order: Vec<usize> = (0..self.examples.len()).collect();
+        match self.config.rotation {
+            FewShotRotation::Fixed => {}
+            FewShotRotation::RoundRobin => {
+                let start = self.advance_cursor() % order.len();
+                order.rotate_left(start);
+            }
+            FewShotRotation::Random => order.shuffle(&mut rand::thread_rng()),
+        }
+
+        let mut used = 0;
+        let mut messages = Vec::new();
+        for i in order {
+            let example = &self.examples[i];
+            let cost = example.estimated_tokens();
+            if used + cost > self.config.token_budget {
+                continue;
+            }
+            used += cost;
+            messages.extend(example.to_messages());
+        }
+        messages
+    }
+
+    /// 读取并推进轮换游标喵
+    fn advance_cursor(&self) -> usize {
+        let Some(dir) = &self.state_dir else {
+            return 0;
+        };
+        let path = dir.join(CURSOR_FILE);
+        let cursor = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        let _ = std::fs::write(&path, (cursor + 1).to_string());
+        cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(n: usize) -> FewShotExample {
+        FewShotExample {
+            user: format!("question {}", n),
+            assistant: format!("@echo({{\"message\": \"{}\"}})", n),
+            tool: Some(FewShotToolResult {
+                name: "echo".to_string(),
+                result: n.to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_budget_limits_examples() {
+        let examples = vec![example(1), example(2), example(3)];
+        let one = examples[0].estimated_tokens();
+        let library = FewShotLibrary::new(FewShotConfig {
+            examples,
+            token_budget: one * 2,
+            rotation: FewShotRotation::Fixed,
+        });
+
+        let messages = library.select_messages();
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages[0].content, "question 1");
+        assert_eq!(messages[2].content, "Tool result for echo: 1");
+    }
+
+    #[test]
+    fn test_round_robin_with_workspace_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(FEW_SHOT_FILE),
+            serde_json::to_string(&vec![example(2)]).unwrap(),
+        )
+        .unwrap();
+
+        let library = FewShotLibrary::new(FewShotConfig {
+            examples: vec![example(1)],
+            token_budget: example(1).estimated_tokens(),
+            rotation: FewShotRotation::RoundRobin,
+        })
+        .with_workspace(dir.path())
+        .unwrap();
+        assert_eq!(library.len(), 2);
+
+        assert_eq!(library.select_messages()[0].content, "question 1");
+        assert_eq!(library.select_messages()[0].content, "question 2");
+        assert_eq!(library.select_messages()[0].content, "question 1");
+    }
+}
@@ -0,0 +1,431 @@
+/*!
+ * History Search - 跨会话的对话全文检索
+ *
+ * 把 `sessions/<id>.messages.jsonl` 中的对话建成 SQLite FTS5 索引喵，
+ * 供 `nekoclaw sessions search "nginx timeout"` 与 Agent 的 `history_search` 工具共用。
+ *
+ * - 使用 trigram 分词，中文等不以空格分词的文本也能按子串命中喵
+ * - 每个关键词都需命中（不足 3 个字符的关键词改用 LIKE 匹配）
+ * - 按会话最后活跃时间增量同步，已删除的会话从索引中移除喵
+ *
+ * 🔒 SAFETY: 会话加密时索引只建在内存中，明文不落盘；
+ * 本机无法解密的消息不进入索引喵
+ */
+
+use super::session::{SessionInfo, SessionStore, StoredMessage};
+use crate::core::traits::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// 明文会话的索引文件（位于会话目录中）喵
+pub const INDEX_FILE: &str = "history.db";
+
+/// 摘录中命中位置之前保留的字符数喵
+const EXCERPT_BEFORE: usize = 60;
+/// 摘录的最大字符数喵
+const EXCERPT_CHARS: usize = 200;
+/// trigram 分词能匹配的最短关键词长度喵
+const MIN_FTS_TERM_CHARS: usize = 3;
+
+/// 存进索引的时间格式（定长，可直接按字符串比较）喵
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+
+/// 检索条件喵
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// 关键词（空格分隔，全部命中）
+    pub text: String,
+    /// 只在该会话（ID 前缀）中检索
+    pub session: Option<String>,
+    /// 只返回该时间之后的消息
+    pub since: Option<DateTime<Utc>>,
+    /// 只返回该时间之前的消息
+    pub until: Option<DateTime<Utc>>,
+    /// 最多返回条数
+    pub limit: usize,
+}
+
+impl HistoryQuery {
+    pub fn new(text: &str, limit: usize) -> Self {
+        Self {
+            text: text.to_string(),
+            limit,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_session(mut self, prefix: &str) -> Self {
+        self.session = Some(prefix.to_string());
+        self
+    }
+
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    fn terms(&self) -> Vec<String> {
+        self.text.split_whitespace().map(str::to_string).collect()
+    }
+}
+
+/// 一条命中的消息喵
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryHit {
+    pub session_id: String,
+    /// 会话标题（没有标题时为会话 ID）
+    pub title: String,
+    pub role: String,
+    pub at: DateTime<Utc>,
+    /// 命中位置附近的摘录，关键词用 `open` / `close` 标记包围
+    pub excerpt: String,
+}
+
+/// 删除索引中已不存在的会话喵
+fn remove_gone_sessions(tx: &rusqlite::Transaction<'_>, sessions: &[SessionInfo]) -> Result<usize> {
+    let live: HashSet<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+    let known: Vec<String> = tx
+        .prepare("SELECT session_id FROM indexed_sessions")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut removed = 0;
+    for gone in known.iter().filter(|id| !live.contains(id.as_str())) {
+        tx.execute("DELETE FROM messages WHERE session_id = ?1", params![gone])?;
+        tx.execute("DELETE FROM indexed_sessions WHERE session_id = ?1", params![gone])?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// 🔒 SAFETY: 对话历史的全文索引喵
+pub struct HistoryIndex {
+    conn: Connection,
+    store: SessionStore,
+}
+
+impl HistoryIndex {
+    /// 打开会话存储对应的索引喵
+    ///
+    /// 明文会话索引持久化在会话目录的 `history.db`；启用会话加密时只建在内存中
+    pub fn open(store: SessionStore) -> Result<Self> {
+        let conn = match store.is_encrypted() {
+            true => Connection::open_in_memory()?,
+            false => {
+                std::fs::create_dir_all(store.dir())?;
+                Connection::open(store.dir().join(INDEX_FILE))?
+            }
+        };
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS messages (
+                session_id TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                at TEXT NOT NULL,
+                PRIMARY KEY (session_id, seq)
+            );
+            CREATE TABLE IF NOT EXISTS indexed_sessions (
+                session_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                last_activity TEXT NOT NULL,
+                lines INTEGER NOT NULL
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                content, content='messages', content_rowid='rowid', tokenize='trigram'
+            );
+            CREATE TRIGGER IF NOT EXISTS messages_ai AFTER INSERT ON messages BEGIN
+                INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS messages_ad AFTER DELETE ON messages BEGIN
+                INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+            END;",
+        )?;
+        Ok(Self { conn, store })
+    }
+
+    /// 把新增的对话同步进索引喵
+    ///
+    /// 只读取最后活跃时间变化过的会话；文件变短（被清理或重写）的会话整体重建
+    ///
+    /// ## Returns
+    /// 新索引的消息数
+    pub fn sync(&mut self) -> Result<usize> {
+        let sessions = self.store.list()?;
+        let tx = self.conn.transaction()?;
+        let mut added = 0;
+
+        remove_gone_sessions(&tx, &sessions)?;
+
+        for info in &sessions {
+            let last_activity = info.last_activity.format(TIME_FORMAT).to_string();
+            let indexed: Option<(String, usize)> = tx
+                .query_row(
+                    "SELECT last_activity, lines FROM indexed_sessions WHERE session_id = ?1",
+                    params![info.session_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            if indexed.as_ref().is_some_and(|(seen, _)| *seen == last_activity) {
+                continue;
+            }
+
+            let messages = self.store.load_messages(&info.session_id)?;
+            let mut start = indexed.map(|(_, lines)| lines).unwrap_or(0);
+            if messages.len() < start {
+                tx.execute("DELETE FROM messages WHERE session_id = ?1", params![info.session_id])?;
+                start = 0;
+            }
+            for (seq, message) in messages.iter().enumerate().skip(start) {
+                added += index_message(&tx, &info.session_id, seq, message)?;
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO indexed_sessions (session_id, title, last_activity, lines)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![info.session_id, info.display_name(), last_activity, messages.len()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// 🔒 SAFETY: 只把已删除的会话移出索引喵（`privacy forget` / 保留期清理后调用）
+    ///
+    /// ## Returns
+    /// 移除的会话数
+    pub fn prune(&mut self) -> Result<usize> {
+        let sessions = self.store.list()?;
+        let tx = self.conn.transaction()?;
+        let removed = remove_gone_sessions(&tx, &sessions)?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// 🔒 SAFETY: 检索对话历史喵（先同步再查询）
+    ///
+    /// 有 3 个字符以上的关键词时按 FTS5 相关度排序，否则按时间倒序
+    pub fn search(&mut self, query: &HistoryQuery, open: &str, close: &str) -> Result<Vec<HistoryHit>> {
+        let terms = query.terms();
+        if terms.is_empty() {
+            return Err("检索关键词不能为空喵".into());
+        }
+        self.sync()?;
+
+        let (long, short): (Vec<&String>, Vec<&String>) =
+            terms.iter().partition(|t| t.chars().count() >= MIN_FTS_TERM_CHARS);
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if !long.is_empty() {
+            conditions.push("messages_fts MATCH ?".to_string());
+            values.push(long.iter().map(|t| format!("\"{}\"", t.replace('"', "\"\""))).collect::<Vec<_>>().join(" "));
+        }
+        for term in &short {
+            conditions.push("m.content LIKE ? ESCAPE '\\'".to_string());
+            values.push(format!("%{}%", escape_like(term)));
+        }
+        if let Some(session) = &query.session {
+            conditions.push("m.session_id LIKE ? ESCAPE '\\'".to_string());
+            values.push(format!("{}%", escape_like(session)));
+        }
+        if let Some(since) = query.since {
+            conditions.push("m.at >= ?".to_string());
+            values.push(since.format(TIME_FORMAT).to_string());
+        }
+        if let Some(until) = query.until {
+            conditions.push("m.at <= ?".to_string());
+            values.push(until.format(TIME_FORMAT).to_string());
+        }
+        let (from, order) = match long.is_empty() {
+            true => ("messages m", "m.at DESC"),
+            false => ("messages_fts JOIN messages m ON m.rowid = messages_fts.rowid", "rank"),
+        };
+        let sql = format!(
+            "SELECT m.session_id, COALESCE(s.title, m.session_id), m.role, m.content, m.at
+             FROM {} LEFT JOIN indexed_sessions s ON s.session_id = m.session_id
+             WHERE {} ORDER BY {} LIMIT {}",
+            from,
+            conditions.join(" AND "),
+            order,
+            query.limit
+        );
+
+        let rows = self
+            .conn
+            .prepare(&sql)?
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(session_id, title, role, content, at)| HistoryHit {
+                session_id,
+                title,
+                role,
+                at: DateTime::parse_from_rfc3339(&at)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                excerpt: excerpt(&content, &terms, open, close),
+            })
+            .collect())
+    }
+}
+
+/// 写入一条消息；仍是密文（无法解密）的跳过喵
+fn index_message(conn: &Connection, session_id: &str, seq: usize, message: &StoredMessage) -> Result<usize> {
+    if message.encrypted {
+        return Ok(0);
+    }
+    conn.execute(
+        "INSERT INTO messages (session_id, seq, role, content, at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            session_id,
+            seq,
+            message.role,
+            message.content,
+            message.at.format(TIME_FORMAT).to_string()
+        ],
+    )?;
+    Ok(1)
+}
+
+/// 转义 LIKE 通配符喵
+fn escape_like(raw: &str) -> String {
+    raw.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// 截取第一个命中位置附近的文字并标记所有关键词喵（不区分大小写）
+pub fn excerpt(content: &str, terms: &[String], open: &str, close: &str) -> String {
+    let chars: Vec<char> = content
+        .chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold(*c)).collect();
+    let terms: Vec<Vec<char>> = terms
+        .iter()
+        .map(|t| t.chars().map(fold).collect::<Vec<_>>())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let matches_at = |i: usize| {
+        terms
+            .iter()
+            .filter(|t| folded[i..].starts_with(t))
+            .map(Vec::len)
+            .max()
+    };
+
+    let first = (0..chars.len()).find(|i| matches_at(*i).is_some()).unwrap_or(0);
+    let start = first.saturating_sub(EXCERPT_BEFORE);
+    let end = (start + EXCERPT_CHARS).min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        match matches_at(i) {
+            Some(len) => {
+                let stop = (i + len).min(end);
+                out.push_str(open);
+                out.extend(&chars[i..stop]);
+                out.push_str(close);
+                i = stop;
+            }
+            None => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// 大小写折叠（只取单字符结果，保持下标对齐）喵
+fn fold(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SessionInfo;
+    use crate::providers::Message;
+
+    fn save(store: &SessionStore, id: &str, title: &str, messages: &[Message]) {
+        let mut info = SessionInfo::new(id, "test");
+        info.title = Some(title.to_string());
+        store.append_messages(id, messages).unwrap();
+        store.save(&info).unwrap();
+    }
+
+    #[test]
+    fn test_search_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path());
+        save(
+            &store,
+            "aaa-1",
+            "Proxy debugging",
+            &[
+                Message::user("Why does nginx return 504 after 60s?".to_string()),
+                Message::assistant("That is the default proxy_read_timeout in nginx; raise the timeout.".to_string()),
+            ],
+        );
+        save(&store, "bbb-2", "部署", &[Message::user("服务器部署超时了怎么办".to_string())]);
+
+        let mut index = HistoryIndex::open(store.clone()).unwrap();
+        let hits = index.search(&HistoryQuery::new("nginx timeout", 10), "[", "]").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Proxy debugging");
+        assert_eq!(hits[0].role, "assistant");
+        assert!(hits[0].excerpt.contains("[nginx]; raise the [timeout]"));
+
+        // 中文短词走 LIKE，会话前缀过滤喵
+        let hits = index.search(&HistoryQuery::new("超时", 10), "[", "]").unwrap();
+        assert_eq!(hits[0].excerpt, "服务器部署[超时]了怎么办");
+        assert!(index
+            .search(&HistoryQuery::new("超时", 10).with_session("aaa"), "[", "]")
+            .unwrap()
+            .is_empty());
+
+        // 增量同步：只索引新增的消息喵
+        save(&store, "bbb-2", "部署", &[Message::assistant("把 nginx 的 timeout 调大".to_string())]);
+        assert_eq!(index.sync().unwrap(), 1);
+        let since = Utc::now() + chrono::Duration::hours(1);
+        assert!(index
+            .search(&HistoryQuery::new("nginx", 10).with_since(since), "[", "]")
+            .unwrap()
+            .is_empty());
+        assert_eq!(index.search(&HistoryQuery::new("nginx", 10), "[", "]").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_excerpt_window() {
+        let long = format!("{}needle{}", "a ".repeat(100), " b".repeat(200));
+        let text = excerpt(&long, &["NEEDLE".to_string()], "<", ">");
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        assert!(text.contains("<needle>"));
+        assert_eq!(text.chars().count(), EXCERPT_CHARS + 2 + 2);
+    }
+}
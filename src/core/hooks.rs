@@ -0,0 +1,86 @@
+/*!
+ * Hooks
+ *
+ * Agent 流水线的扩展点喵。目前提供回复钩子：
+ * 助手回复展示/发送给用户之前依次经过所有已注册的钩子喵。
+ */
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// 回复所处的上下文喵
+#[derive(Debug, Clone, Copy)]
+pub struct ReplyContext<'a> {
+    /// 渠道名称（cli / discord / telegram ...）喵
+    pub channel: &'a str,
+    /// 生成回复的 Agent 名称喵
+    pub agent: &'a str,
+}
+
+/// 回复钩子喵
+pub trait ReplyHook: Send + Sync + Debug {
+    /// 钩子名称（用于日志）喵
+    fn name(&self) -> &str;
+
+    /// 处理回复，返回新的回复内容喵
+    fn on_reply(&self, ctx: &ReplyContext<'_>, reply: String) -> String;
+}
+
+/// 钩子注册表喵
+#[derive(Debug, Clone, Default)]
+pub struct HookRegistry {
+    reply_hooks: Vec<Arc<dyn ReplyHook>>,
+}
+
+impl HookRegistry {
+    /// 创建空注册表喵
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册回复钩子（按注册顺序执行）喵
+    pub fn register_reply_hook(&mut self, hook: Arc<dyn ReplyHook>) {
+        tracing::debug!("Registered reply hook: {}", hook.name());
+        self.reply_hooks.push(hook);
+    }
+
+    /// 是否没有注册任何钩子喵
+    pub fn is_empty(&self) -> bool {
+        self.reply_hooks.is_empty()
+    }
+
+    /// 依次执行所有回复钩子喵
+    pub fn apply_reply(&self, ctx: &ReplyContext<'_>, reply: String) -> String {
+        self.reply_hooks
+            .iter()
+            .fold(reply, |reply, hook| hook.on_reply(ctx, reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Suffix(&'static str);
+
+    impl ReplyHook for Suffix {
+        fn name(&self) -> &str {
+            "suffix"
+        }
+
+        fn on_reply(&self, ctx: &ReplyContext<'_>, reply: String) -> String {
+            format!("{}{}@{}", reply, self.0, ctx.channel)
+        }
+    }
+
+    #[test]
+    fn test_hooks_run_in_order() {
+        let mut hooks = HookRegistry::new();
+        hooks.register_reply_hook(Arc::new(Suffix("a")));
+        hooks.register_reply_hook(Arc::new(Suffix("b")));
+        let ctx = ReplyContext { channel: "cli", agent: "default" };
+        let out = hooks.apply_reply(&ctx, "x".to_string());
+        assert_eq!(out, "xa@clib@cli");
+    }
+}
@@ -0,0 +1,257 @@
+/*!
+ * Conversation Language
+ *
+ * 自动检测消息语言并记录到会话，要求模型用同一语言回复喵。
+ * `/lang <code>` 可固定会话语言，`/lang auto` 恢复自动检测喵。
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use whatlang::Lang;
+
+/// 检测结果的最低置信度喵
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// 会话语言状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversationLanguage {
+    /// 当前语言喵
+    pub lang: Lang,
+    /// 是否由 `/lang` 固定（固定后不再自动切换）喵
+    pub pinned: bool,
+}
+
+/// 检测文本语言喵
+///
+/// ## Returns
+/// 置信度不足（短消息、混合文本）时返回 None 喵
+pub fn detect_language(text: &str) -> Option<Lang> {
+    let info = whatlang::detect(text)?;
+    (info.is_reliable() || info.confidence() >= MIN_CONFIDENCE).then(|| info.lang())
+}
+
+/// 解析用户输入的语言代码喵
+///
+/// 接受 ISO 639-1（zh、en）、ISO 639-3（cmn、eng）或英文名称（Chinese）喵
+pub fn parse_language(input: &str) -> Option<Lang> {
+    let input = input.trim().to_lowercase();
+    let alias = match input.as_str() {
+        "zh" | "cn" | "chinese" | "中文" => Some(Lang::Cmn),
+        "en" | "english" => Some(Lang::Eng),
+        "ja" | "jp" | "japanese" | "日本語" => Some(Lang::Jpn),
+        "ko" | "korean" => Some(Lang::Kor),
+        "fr" => Some(Lang::Fra),
+        "de" => Some(Lang::Deu),
+        "es" => Some(Lang::Spa),
+        "ru" => Some(Lang::Rus),
+        _ => None,
+    };
+    alias.or_else(|| Lang::from_code(input.as_str())).or_else(|| {
+        Lang::all()
+            .iter()
+            .copied()
+            .find(|l| l.eng_name().eq_ignore_ascii_case(&input))
+    })
+}
+
+/// 持久化格式（语言存 ISO 639-3 代码）喵
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredLanguage {
+    lang: String,
+    pinned: bool,
+}
+
+/// 各会话的语言设置喵
+#[derive(Debug, Clone, Default)]
+pub struct SessionLanguages {
+    sessions: Arc<RwLock<HashMap<String, ConversationLanguage>>>,
+    /// 持久化文件，None 时只保存在内存喵
+    store_path: Option<Arc<PathBuf>>,
+}
+
+impl SessionLanguages {
+    /// 创建空注册表喵
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从 JSON 文件加载并持久化，重启后各会话语言仍然保留喵
+    ///
+    /// 每个渠道使用独立文件，避免多个进程互相覆盖喵
+    pub fn open(store_path: PathBuf) -> Self {
+        let stored: HashMap<String, StoredLanguage> = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let sessions = stored
+            .into_iter()
+            .filter_map(|(conversation, stored)| {
+                let lang = Lang::from_code(stored.lang.as_str())?;
+                Some((conversation, ConversationLanguage { lang, pinned: stored.pinned }))
+            })
+            .collect();
+        Self {
+            sessions: Arc::new(RwLock::new(sessions)),
+            store_path: Some(Arc::new(store_path)),
+        }
+    }
+
+    /// 写回持久化文件（失败只记录警告，不影响对话）喵
+    fn save(&self, sessions: &HashMap<String, ConversationLanguage>) {
+        let Some(path) = &self.store_path else {
+            return;
+        };
+        let stored: HashMap<&str, StoredLanguage> = sessions
+            .iter()
+            .map(|(conversation, state)| {
+                (
+                    conversation.as_str(),
+                    StoredLanguage {
+                        lang: state.lang.code().to_string(),
+                        pinned: state.pinned,
+                    },
+                )
+            })
+            .collect();
+        let result = serde_json::to_string_pretty(&stored)
+            .map_err(std::io::Error::other)
+            .and_then(|content| std::fs::write(path.as_ref(), content));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save conversation languages to {}: {}", path.display(), e);
+        }
+    }
+
+    /// 根据新消息更新会话语言（已固定则不变）喵
+    pub fn observe(&self, conversation: &str, text: &str) -> Option<ConversationLanguage> {
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let current = sessions.get(conversation).copied();
+        if current.is_some_and(|c| c.pinned) {
+            return current;
+        }
+        match detect_language(text) {
+            Some(lang) => {
+                let state = ConversationLanguage { lang, pinned: false };
+                if current != Some(state) {
+                    sessions.insert(conversation.to_string(), state);
+                    self.save(&sessions);
+                }
+                Some(state)
+            }
+            None => current,
+        }
+    }
+
+    /// 固定会话语言（`/lang <code>`）喵
+    pub fn pin(&self, conversation: &str, lang: Lang) {
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        sessions.insert(conversation.to_string(), ConversationLanguage { lang, pinned: true });
+        self.save(&sessions);
+    }
+
+    /// 恢复自动检测（`/lang auto`）喵
+    pub fn unpin(&self, conversation: &str) {
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        if sessions.remove(conversation).is_some() {
+            self.save(&sessions);
+        }
+    }
+
+    /// 当前会话语言喵
+    pub fn get(&self, conversation: &str) -> Option<ConversationLanguage> {
+        self.sessions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(conversation)
+            .copied()
+    }
+
+    /// 给模型的语言指令喵
+    pub fn instruction(&self, conversation: &str) -> Option<String> {
+        self.get(conversation).map(|state| {
+            format!(
+                "Reply in {} ({}), the language of this conversation, unless the user explicitly asks otherwise.",
+                state.lang.eng_name(),
+                state.lang.code()
+            )
+        })
+    }
+
+    /// 处理 `/lang` 命令参数，返回给用户的提示喵
+    pub fn handle_command(&self, conversation: &str, args: Option<&str>) -> String {
+        match args.map(str::trim).filter(|a| !a.is_empty()) {
+            None => match self.get(conversation) {
+                Some(state) => format!(
+                    "🌐 当前语言: {} ({}){}",
+                    state.lang.eng_name(),
+                    state.lang.code(),
+                    if state.pinned { "，已固定" } else { "，自动检测" }
+                ),
+                None => "🌐 当前语言: 自动检测喵".to_string(),
+            },
+            Some("auto") => {
+                self.unpin(conversation);
+                "🌐 已恢复自动检测语言喵".to_string()
+            }
+            Some(code) => match parse_language(code) {
+                Some(lang) => {
+                    self.pin(conversation, lang);
+                    format!("🌐 语言已设置为 {} ({}) 喵", lang.eng_name(), lang.code())
+                }
+                None => format!("❌ 不认识的语言: {}（例如 zh、en、auto）喵", code),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_and_observe() {
+        let langs = SessionLanguages::new();
+        let state = langs
+            .observe("c1", "今天的天气怎么样？我想出去散步一下。")
+            .unwrap();
+        assert_eq!(state.lang, Lang::Cmn);
+
+        let state = langs
+            .observe("c1", "What is the weather like today? I would like to go for a walk.")
+            .unwrap();
+        assert_eq!(state.lang, Lang::Eng);
+        assert!(langs.instruction("c1").unwrap().contains("English"));
+        assert!(langs.get("c2").is_none());
+    }
+
+    #[test]
+    fn test_pinned_language_is_kept() {
+        let langs = SessionLanguages::new();
+        assert!(langs.handle_command("c1", Some("zh")).contains("cmn"));
+        let state = langs
+            .observe("c1", "What is the weather like today? I would like to go for a walk.")
+            .unwrap();
+        assert_eq!(state.lang, Lang::Cmn);
+        assert!(state.pinned);
+
+        langs.handle_command("c1", Some("auto"));
+        assert!(langs.get("c1").is_none());
+        assert!(langs.handle_command("c1", Some("klingon")).starts_with("❌"));
+    }
+
+    #[test]
+    fn test_languages_persist_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("languages.json");
+        let langs = SessionLanguages::open(path.clone());
+        langs.handle_command("telegram:1", Some("ja"));
+        langs.observe("discord:2", "What is the weather like today? I would like to go for a walk.");
+
+        let reopened = SessionLanguages::open(path);
+        let pinned = reopened.get("telegram:1").unwrap();
+        assert_eq!(pinned.lang, Lang::Jpn);
+        assert!(pinned.pinned);
+        assert_eq!(reopened.get("discord:2").unwrap().lang, Lang::Eng);
+    }
+}
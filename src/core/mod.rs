@@ -4,8 +4,43 @@
  * 作者: 缪斯 (Muse) @缪斯
  */
 
+pub mod agent;
+pub mod bus;
+pub mod canary;
+pub mod events;
+pub mod claim_check;
 pub mod config;
+pub mod experiment;
+pub mod few_shot;
+pub mod history_search;
+pub mod hooks;
+pub mod language;
+pub mod persona;
+pub mod postprocess;
+pub mod preset;
+pub mod repl;
+pub mod response_policy;
+pub mod session;
+pub mod suggest;
 pub mod traits;
+pub mod workspace;
 
+pub use agent::AgentProfile;
+pub use bus::{BusConfig, BusError, BusStats, EventBus, EventHandler};
+pub use canary::{CanaryVerdict, PromptCanary, PromptCanaryConfig};
+pub use events::{AgentEvent, AgentOutput, EventStream};
+pub use claim_check::{ClaimCheckConfig, ClaimVerifier};
+pub use language::SessionLanguages;
+pub use persona::PersonaConfig;
+pub use hooks::{HookRegistry, ReplyContext};
+pub use suggest::closest_match;
+pub use preset::ModelPreset;
+pub use response_policy::ResponsePolicy;
+pub use postprocess::{PostProcessChain, PostProcessConfig};
+pub use few_shot::{FewShotConfig, FewShotLibrary};
+pub use history_search::{HistoryIndex, HistoryQuery};
+pub use experiment::{ExperimentConfig, PromptVariant};
+pub use session::{SessionInfo, SessionStore, SessionTitleConfig};
 pub use config::{load as load_config, save as save_config};
 pub use traits::*;
+pub use workspace::{WorkspaceProfile, DEFAULT_WORKSPACE};
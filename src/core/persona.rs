@@ -0,0 +1,95 @@
+/*!
+ * Persona Templates
+ *
+ * 系统提示词里的人设由 IDENTITY（身份）与 SOUL（说话风格）两段模板拼成喵。
+ *
+ * 配置示例：
+ * ```json
+ * "persona": { "speech_style": "none" }
+ * ```
+ * - `cat`（默认）：内置身份 + 内置猫娘说话风格
+ * - `none`：只保留内置身份，不加任何说话风格（企业部署）
+ * - `custom`：读取工作区的 IDENTITY.md / SOUL.md，缺失的部分回退到内置身份或留空
+ */
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 内置身份模板喵
+pub const IDENTITY_TEMPLATE: &str = include_str!("../../templates/persona/IDENTITY.md");
+/// 内置猫娘说话风格模板喵
+pub const SOUL_TEMPLATE: &str = include_str!("../../templates/persona/SOUL.md");
+
+/// 说话风格喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechStyle {
+    /// 内置猫娘风格喵
+    #[default]
+    Cat,
+    /// 不加说话风格喵
+    None,
+    /// 使用工作区模板喵
+    Custom,
+}
+
+/// 人设配置喵
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersonaConfig {
+    #[serde(default)]
+    pub speech_style: SpeechStyle,
+}
+
+impl PersonaConfig {
+    /// 渲染人设提示词喵
+    ///
+    /// `custom` 模式从 `workspace` 读取 IDENTITY.md / SOUL.md，文件存在但读取失败时报错喵
+    pub fn render(&self, workspace: &Path) -> Result<String, String> {
+        let (identity, soul) = match self.speech_style {
+            SpeechStyle::Cat => (IDENTITY_TEMPLATE.to_string(), Some(SOUL_TEMPLATE.to_string())),
+            SpeechStyle::None => (IDENTITY_TEMPLATE.to_string(), None),
+            SpeechStyle::Custom => (
+                read_template(workspace, "IDENTITY.md")?.unwrap_or_else(|| IDENTITY_TEMPLATE.to_string()),
+                read_template(workspace, "SOUL.md")?,
+            ),
+        };
+
+        let identity = identity.trim();
+        Ok(match soul.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(soul) => format!("{}\n\n{}", identity, soul),
+            None => identity.to_string(),
+        })
+    }
+}
+
+/// 读取工作区模板（不存在时返回 None）喵
+fn read_template(workspace: &Path, name: &str) -> Result<Option<String>, String> {
+    let path = workspace.join(name);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("读取人设模板 {} 失败喵: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speech_styles() {
+        let dir = tempfile::tempdir().unwrap();
+        let render = |style| PersonaConfig { speech_style: style }.render(dir.path()).unwrap();
+
+        assert!(render(SpeechStyle::Cat).contains("喵"));
+        let plain = render(SpeechStyle::None);
+        assert!(plain.starts_with("You are Nia"));
+        assert!(!plain.contains("喵"));
+
+        // 工作区只有 SOUL.md 时沿用内置身份喵
+        std::fs::write(dir.path().join("SOUL.md"), "Answer formally.\n").unwrap();
+        let custom = render(SpeechStyle::Custom);
+        assert!(custom.starts_with("You are Nia"));
+        assert!(custom.ends_with("Answer formally."));
+    }
+}
@@ -0,0 +1,275 @@
+/*!
+ * Output Post-processors
+ *
+ * 可配置的回复后处理链，作为回复钩子注册喵。
+ *
+ * 配置示例：
+ * ```json
+ * "post_process": {
+ *   "default": [{ "type": "trim_trailing_whitespace" }, { "type": "normalize_line_endings" }],
+ *   "channels": {
+ *     "telegram": [{ "type": "strip_markdown" }, { "type": "max_emoji", "max": 3 }]
+ *   },
+ *   "agents": {
+ *     "coder": [{ "type": "banned_phrases", "phrases": ["As an AI"] }]
+ *   }
+ * }
+ * ```
+ * 执行顺序：默认链 → 渠道专属链 → Agent 专属链喵。
+ */
+
+use crate::core::hooks::{ReplyContext, ReplyHook};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单个后处理步骤配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessor {
+    /// 正则替换喵
+    RegexReplace { pattern: String, replacement: String },
+    /// 去除每行行尾空白喵
+    TrimTrailingWhitespace,
+    /// 统一换行为 `\n` 喵
+    NormalizeLineEndings,
+    /// 去除 Markdown 标记（纯文本渠道）喵
+    StripMarkdown,
+    /// 最多保留前 N 个 emoji 喵
+    MaxEmoji { max: usize },
+    /// 替换禁用短语（不区分大小写）喵
+    BannedPhrases {
+        phrases: Vec<String>,
+        #[serde(default)]
+        replacement: String,
+    },
+}
+
+/// 后处理配置喵
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostProcessConfig {
+    /// 所有渠道都执行的步骤喵
+    #[serde(default)]
+    pub default: Vec<PostProcessor>,
+    /// 渠道专属步骤喵
+    #[serde(default)]
+    pub channels: HashMap<String, Vec<PostProcessor>>,
+    /// Agent 专属步骤（按 `[agents.agent.<name>]` 的名称）喵
+    #[serde(default)]
+    pub agents: HashMap<String, Vec<PostProcessor>>,
+}
+
+/// 编译后的步骤喵
+#[derive(Debug)]
+enum Step {
+    Replace(Regex, String),
+    TrimTrailingWhitespace,
+    NormalizeLineEndings,
+    StripMarkdown,
+    MaxEmoji(usize),
+}
+
+impl Step {
+    fn compile(processor: &PostProcessor) -> Result<Self, regex::Error> {
+        Ok(match processor {
+            PostProcessor::RegexReplace {
+                pattern,
+                replacement,
+            } => Step::Replace(Regex::new(pattern)?, replacement.clone()),
+            PostProcessor::TrimTrailingWhitespace => Step::TrimTrailingWhitespace,
+            PostProcessor::NormalizeLineEndings => Step::NormalizeLineEndings,
+            PostProcessor::StripMarkdown => Step::StripMarkdown,
+            PostProcessor::MaxEmoji { max } => Step::MaxEmoji(*max),
+            PostProcessor::BannedPhrases {
+                phrases,
+                replacement,
+            } => {
+                let alternation = phrases
+                    .iter()
+                    .map(|p| regex::escape(p))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                Step::Replace(
+                    Regex::new(&format!("(?i)(?:{})", alternation))?,
+                    replacement.replace('$', "$$"),
+                )
+            }
+        })
+    }
+
+    fn apply(&self, text: String) -> String {
+        match self {
+            Step::Replace(re, replacement) => re.replace_all(&text, replacement.as_str()).into_owned(),
+            Step::TrimTrailingWhitespace => {
+                let trimmed: Vec<&str> = text.lines().map(str::trim_end).collect();
+                trimmed.join("\n").trim_end().to_string()
+            }
+            Step::NormalizeLineEndings => text.replace("\r\n", "\n").replace('\r', "\n"),
+            Step::StripMarkdown => strip_markdown(&text),
+            Step::MaxEmoji(max) => limit_emoji(&text, *max),
+        }
+    }
+}
+
+/// 后处理链（实现为回复钩子）喵
+#[derive(Debug)]
+pub struct PostProcessChain {
+    default: Vec<Step>,
+    channels: HashMap<String, Vec<Step>>,
+    agents: HashMap<String, Vec<Step>>,
+}
+
+impl PostProcessChain {
+    /// 编译配置喵
+    ///
+    /// ## Returns
+    /// 正则无效时返回错误，避免静默跳过喵
+    pub fn compile(config: &PostProcessConfig) -> Result<Self, regex::Error> {
+        let compile_all =
+            |list: &[PostProcessor]| list.iter().map(Step::compile).collect::<Result<Vec<_>, _>>();
+        let compile_map = |map: &HashMap<String, Vec<PostProcessor>>| {
+            map.iter()
+                .map(|(key, list)| Ok((key.clone(), compile_all(list)?)))
+                .collect::<Result<HashMap<_, _>, regex::Error>>()
+        };
+        Ok(Self {
+            default: compile_all(&config.default)?,
+            channels: compile_map(&config.channels)?,
+            agents: compile_map(&config.agents)?,
+        })
+    }
+
+    /// 对回复执行处理喵
+    pub fn process(&self, ctx: &ReplyContext<'_>, reply: String) -> String {
+        fn steps<'a>(map: &'a HashMap<String, Vec<Step>>, key: &str) -> &'a [Step] {
+            map.get(key).map(Vec::as_slice).unwrap_or(&[])
+        }
+        self.default
+            .iter()
+            .chain(steps(&self.channels, ctx.channel))
+            .chain(steps(&self.agents, ctx.agent))
+            .fold(reply, |text, step| step.apply(text))
+    }
+}
+
+impl ReplyHook for PostProcessChain {
+    fn name(&self) -> &str {
+        "post_process"
+    }
+
+    fn on_reply(&self, ctx: &ReplyContext<'_>, reply: String) -> String {
+        self.process(ctx, reply)
+    }
+}
+
+/// 去除常见 Markdown 标记喵
+fn strip_markdown(text: &str) -> String {
+    let rules: [(&str, &str); 6] = [
+        (r"(?m)^```[^\n]*\n?", ""),
+        (r"(?m)^#{1,6}\s+", ""),
+        (r"(?m)^>\s?", ""),
+        (r"!?\[([^\]]*)\]\(([^)]*)\)", "$1 ($2)"),
+        (r"(\*\*|__)(.+?)(\*\*|__)", "$2"),
+        (r"`([^`\n]+)`", "$1"),
+    ];
+    rules.iter().fold(text.to_string(), |acc, (pattern, replacement)| {
+        Regex::new(pattern)
+            .map(|re| re.replace_all(&acc, *replacement).into_owned())
+            .unwrap_or(acc)
+    })
+}
+
+/// 是否为 emoji 码点（覆盖常用区段）喵
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+/// 只保留前 `max` 个 emoji（连同其后的变体选择符 / ZWJ 序列）喵
+fn limit_emoji(text: &str, max: usize) -> String {
+    let mut seen = 0;
+    let mut dropping = false;
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_emoji(c) && !dropping {
+            seen += 1;
+            dropping = seen > max;
+        } else if !is_emoji(c) && !matches!(c, '\u{FE0F}' | '\u{200D}' | '\u{1F3FB}'..='\u{1F3FF}') {
+            dropping = false;
+        }
+        if !dropping {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(channel: &str) -> ReplyContext<'_> {
+        ReplyContext { channel, agent: "default" }
+    }
+
+    fn chain(json: serde_json::Value) -> PostProcessChain {
+        let config: PostProcessConfig = serde_json::from_value(json).unwrap();
+        PostProcessChain::compile(&config).unwrap()
+    }
+
+    #[test]
+    fn test_default_and_channel_chain() {
+        let chain = chain(serde_json::json!({
+            "default": [
+                { "type": "normalize_line_endings" },
+                { "type": "trim_trailing_whitespace" }
+            ],
+            "channels": {
+                "telegram": [{ "type": "strip_markdown" }]
+            }
+        }));
+
+        let reply = "## Title  \r\n**bold** and `code`  \r\n".to_string();
+        assert_eq!(chain.process(&ctx("cli"), reply.clone()), "## Title\n**bold** and `code`");
+        assert_eq!(chain.process(&ctx("telegram"), reply), "Title\nbold and code");
+    }
+
+    #[test]
+    fn test_agent_chain_runs_after_channel_chain() {
+        let chain = chain(serde_json::json!({
+            "channels": {
+                "discord": [{ "type": "regex_replace", "pattern": "cat", "replacement": "neko" }]
+            },
+            "agents": {
+                "coder": [{ "type": "regex_replace", "pattern": "neko", "replacement": "🐱" }]
+            }
+        }));
+
+        let coder = ReplyContext { channel: "discord", agent: "coder" };
+        assert_eq!(chain.process(&coder, "cat".to_string()), "🐱");
+        assert_eq!(chain.process(&ctx("discord"), "cat".to_string()), "neko");
+        assert_eq!(chain.process(&ReplyContext { channel: "cli", agent: "coder" }, "cat".to_string()), "cat");
+    }
+
+    #[test]
+    fn test_emoji_limit_and_banned_phrases() {
+        let chain = chain(serde_json::json!({
+            "default": [
+                { "type": "max_emoji", "max": 2 },
+                { "type": "banned_phrases", "phrases": ["As an AI"], "replacement": "妮娅" },
+                { "type": "regex_replace", "pattern": "喵+", "replacement": "喵" }
+            ]
+        }));
+
+        let out = chain.process(&ctx("cli"), "as an ai 😺😸❤️🐟 好的喵喵喵".to_string());
+        assert_eq!(out, "妮娅 😺😸 好的喵");
+    }
+
+    #[test]
+    fn test_invalid_regex_is_rejected() {
+        let config: PostProcessConfig = serde_json::from_value(serde_json::json!({
+            "default": [{ "type": "regex_replace", "pattern": "(", "replacement": "" }]
+        }))
+        .unwrap();
+        assert!(PostProcessChain::compile(&config).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+/*!
+ * Model Parameter Presets
+ *
+ * 在配置中定义命名预设（creative / precise / cheap ...），
+ * 打包 model / temperature / top_p / max_tokens，通过 `--preset` 或 REPL `/preset` 选择喵。
+ *
+ * 优先级：命令行参数 > 预设 > 配置默认值喵
+ */
+
+use crate::core::traits::Config;
+use serde::{Deserialize, Serialize};
+
+/// 默认最大生成 token 数喵
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// 参数预设喵（未设置的字段沿用下层默认值）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPreset {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+}
+
+/// 最终用于请求的采样参数喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingParams {
+    pub model: String,
+    pub temperature: f32,
+    pub top_p: Option<f32>,
+    pub max_tokens: u32,
+}
+
+impl SamplingParams {
+    /// 由配置默认值构建喵
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            model: config
+                .agents
+                .defaults
+                .model
+                .primary
+                .clone()
+                .unwrap_or_else(|| config.default_model.clone()),
+            temperature: config.default_temperature as f32,
+            top_p: None,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    /// 叠加预设（或命令行参数，二者结构相同）喵
+    pub fn apply(mut self, layer: &ModelPreset) -> Self {
+        if let Some(model) = &layer.model {
+            self.model = model.clone();
+        }
+        if let Some(temperature) = layer.temperature {
+            self.temperature = temperature;
+        }
+        if layer.top_p.is_some() {
+            self.top_p = layer.top_p;
+        }
+        if let Some(max_tokens) = layer.max_tokens {
+            self.max_tokens = max_tokens;
+        }
+        self
+    }
+}
+
+impl Config {
+    /// 按名称查找预设喵
+    ///
+    /// ## Returns
+    /// 找不到时返回带最接近候选的错误信息喵
+    pub fn preset(&self, name: &str) -> std::result::Result<&ModelPreset, String> {
+        self.presets.get(name).ok_or_else(|| {
+            let hint = crate::core::closest_match(name, self.presets.keys().map(String::as_str))
+                .map(|s| format!("，你是想用 '{}' 吗", s))
+                .unwrap_or_default();
+            format!("未知预设 '{}'{}喵", name, hint)
+        })
+    }
+
+    /// 解析最终采样参数（默认值 → 预设 → 命令行）喵
+    pub fn sampling_params(
+        &self,
+        preset: Option<&str>,
+        overrides: &ModelPreset,
+    ) -> std::result::Result<SamplingParams, String> {
+        let mut params = SamplingParams::from_config(self);
+        if let Some(name) = preset.or(self.default_preset.as_deref()) {
+            params = params.apply(self.preset(name)?);
+        }
+        Ok(params.apply(overrides))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_presets() -> Config {
+        let mut config = Config {
+            default_model: "gpt-4".to_string(),
+            ..Config::default()
+        };
+        config.presets.insert(
+            "creative".to_string(),
+            ModelPreset {
+                temperature: Some(1.1),
+                top_p: Some(0.95),
+                ..Default::default()
+            },
+        );
+        config.presets.insert(
+            "cheap".to_string(),
+            ModelPreset {
+                model: Some("gpt-4o-mini".to_string()),
+                max_tokens: Some(512),
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn test_layering_order() {
+        let mut config = config_with_presets();
+        config.default_preset = Some("cheap".to_string());
+
+        let params = config.sampling_params(None, &ModelPreset::default()).unwrap();
+        assert_eq!(params.model, "gpt-4o-mini");
+        assert_eq!(params.max_tokens, 512);
+
+        let overrides = ModelPreset {
+            temperature: Some(0.2),
+            ..Default::default()
+        };
+        let params = config.sampling_params(Some("creative"), &overrides).unwrap();
+        assert_eq!(params.model, "gpt-4");
+        assert_eq!(params.temperature, 0.2);
+        assert_eq!(params.top_p, Some(0.95));
+    }
+
+    #[test]
+    fn test_unknown_preset_suggests() {
+        let config = config_with_presets();
+        let err = config
+            .sampling_params(Some("creativ"), &ModelPreset::default())
+            .unwrap_err();
+        assert!(err.contains("creative"));
+    }
+}
@@ -0,0 +1,531 @@
+/*!
+ * REPL Input - 交互模式的行编辑器
+ *
+ * `nekoclaw agent` 交互模式的输入喵：
+ * - 终端中支持 ←/→ 移动光标、↑/↓ 翻历史、Ctrl-A / Ctrl-E / Ctrl-U / Ctrl-W，
+ *   Ctrl-C 放弃当前输入，空行 Ctrl-D 退出
+ * - 历史保存在 `~/.nekoclaw/history`（无痕模式只保存在内存）
+ * - 多行输入：行尾 `\` 续行；以 ```` ``` ```` 开头的代码块一直读到闭合的 ```` ``` ````
+ * - `ReplCommand::parse` 识别 quit / help / clear 与 `/lang`、`/model` 等斜杠命令
+ *
+ * 标准输入不是终端（管道 / 文件）时退回逐行读取，行为与之前一致
+ */
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// 历史文件名（位于配置目录）喵
+pub const HISTORY_FILE: &str = "history";
+
+/// 最多保留的历史条数喵
+pub const MAX_HISTORY: usize = 1_000;
+
+/// 🔒 SAFETY: 输入历史喵（每行一个 JSON 字符串，多行输入也能原样恢复）
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl InputHistory {
+    /// 只在内存中保存的历史喵（无痕模式）
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// 读取历史文件喵（不存在时为空，损坏的行按原文保留）
+    pub fn open(path: &Path) -> Self {
+        let entries = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).unwrap_or_else(|_| line.to_string()))
+            .collect::<Vec<String>>();
+        let skip = entries.len().saturating_sub(MAX_HISTORY);
+        Self {
+            entries: entries.into_iter().skip(skip).collect(),
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// 记录一条输入喵（与上一条相同时跳过；超过上限时重写文件丢弃最旧的）
+    pub fn push(&mut self, entry: &str) -> io::Result<()> {
+        if entry.trim().is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return Ok(());
+        }
+        self.entries.push(entry.to_string());
+        let Some(path) = &self.path else { return Ok(()) };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.drain(..self.entries.len() - MAX_HISTORY);
+            let lines: Vec<String> = self.entries.iter().filter_map(|e| serde_json::to_string(e).ok()).collect();
+            std::fs::write(path, lines.join("\n") + "\n")?;
+        } else {
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+        }
+        Ok(())
+    }
+}
+
+/// 交互模式的帮助文本喵
+pub const HELP: &str = "📋 可用命令:
+  quit/exit     - 退出
+  clear         - 清空对话历史
+  /lang         - 查看或设置回复语言 (/lang zh, /lang auto)
+  /preset       - 查看或切换参数预设 (/preset creative)
+  /model        - 查看或切换模型 (/model gpt-4o)
+  /temperature  - 查看或设置 temperature (/temperature 0.3)
+  /tools        - 列出可用工具
+  /save <name>  - 把当前对话另存为会话
+  /load <name>  - 载入已保存的会话，替换当前对话
+  /feedback     - 评价上一条回复 (/feedback up|down [备注])
+  help          - 显示帮助
+  行尾 \\ 续行，``` 开始的代码块读到闭合的 ``` 为止；↑/↓ 翻看输入历史";
+
+/// 可用的命令名（拼错时给出建议）喵
+const COMMANDS: [&str; 12] = [
+    "lang",
+    "preset",
+    "model",
+    "temperature",
+    "tools",
+    "save",
+    "load",
+    "feedback",
+    "help",
+    "clear",
+    "quit",
+    "exit",
+];
+
+/// 🔒 SAFETY: 交互模式的命令喵（其余输入作为消息发给模型）
+///
+/// 斜杠命令的参数已去掉首尾空白
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplCommand<'a> {
+    /// `quit` / `exit`
+    Quit,
+    /// `help`
+    Help,
+    /// `clear`：清空对话历史
+    Clear,
+    /// `/lang [zh|auto]`
+    Lang(&'a str),
+    /// `/preset [名称]`
+    Preset(&'a str),
+    /// `/model [名称]`
+    Model(&'a str),
+    /// `/temperature [值]`
+    Temperature(&'a str),
+    /// `/tools`
+    Tools,
+    /// `/save <名称>`
+    Save(&'a str),
+    /// `/load <名称>`
+    Load(&'a str),
+    /// `/feedback up|down [备注]`
+    Feedback(&'a str),
+    /// 不认识的斜杠命令（附最接近的命令名）
+    Unknown {
+        name: &'a str,
+        suggestion: Option<&'static str>,
+    },
+}
+
+impl<'a> ReplCommand<'a> {
+    /// 解析一行输入喵（不是命令时返回 None）
+    ///
+    /// `/model` 与 `/model x` 是命令，`/models` 按拼错处理
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let input = input.trim();
+        for (word, command) in [("quit", Self::Quit), ("exit", Self::Quit), ("help", Self::Help), ("clear", Self::Clear)] {
+            if input.eq_ignore_ascii_case(word) {
+                return Some(command);
+            }
+        }
+
+        let rest = input.strip_prefix('/')?;
+        let name = rest.split_whitespace().next().unwrap_or_default();
+        let args = rest[name.len()..].trim();
+        Some(match name {
+            "lang" => Self::Lang(args),
+            "preset" => Self::Preset(args),
+            "model" => Self::Model(args),
+            "temperature" => Self::Temperature(args),
+            "tools" => Self::Tools,
+            "save" => Self::Save(args),
+            "load" => Self::Load(args),
+            "feedback" => Self::Feedback(args),
+            _ => Self::Unknown {
+                name,
+                suggestion: super::closest_match(name, COMMANDS),
+            },
+        })
+    }
+}
+
+/// 多行输入的拼接状态喵
+#[derive(Debug, Default)]
+pub struct MultiLine {
+    lines: Vec<String>,
+    fenced: bool,
+}
+
+impl MultiLine {
+    /// 是否已经读了一部分（需要显示续行提示符）喵
+    pub fn is_pending(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// 放入一行喵
+    ///
+    /// ## Returns
+    /// 输入完整时返回整条消息，还需要更多行时返回 None
+    pub fn feed(&mut self, line: &str) -> Option<String> {
+        if self.fenced {
+            self.lines.push(line.to_string());
+            if line.trim_end().ends_with("```") {
+                self.fenced = false;
+                return Some(self.finish());
+            }
+            return None;
+        }
+        if line.trim_start().starts_with("```") && !self.opens_and_closes(line) {
+            self.fenced = true;
+            self.lines.push(line.to_string());
+            return None;
+        }
+        match line.strip_suffix('\\') {
+            Some(head) => {
+                self.lines.push(head.to_string());
+                None
+            }
+            None => {
+                self.lines.push(line.to_string());
+                Some(self.finish())
+            }
+        }
+    }
+
+    /// 单行的 ```` ```code``` ```` 不开启代码块喵
+    fn opens_and_closes(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        trimmed.len() > 6 && trimmed.ends_with("```")
+    }
+
+    fn finish(&mut self) -> String {
+        self.fenced = false;
+        std::mem::take(&mut self.lines).join("\n")
+    }
+}
+
+/// 🔒 SAFETY: 交互模式的行编辑器喵
+pub struct LineEditor {
+    history: InputHistory,
+    terminal: bool,
+}
+
+impl LineEditor {
+    pub fn new(history: InputHistory) -> Self {
+        let terminal = io::IsTerminal::is_terminal(&io::stdin()) && io::IsTerminal::is_terminal(&io::stdout());
+        Self { history, terminal }
+    }
+
+    /// 读取一条完整消息喵（多行输入拼接后写入历史）
+    ///
+    /// ## Returns
+    /// 输入结束（管道关闭 / 空行 Ctrl-D）时返回 None
+    pub fn read_message(&mut self, prompt: &str, continuation: &str) -> io::Result<Option<String>> {
+        let mut pending = MultiLine::default();
+        loop {
+            let prompt = if pending.is_pending() { continuation } else { prompt };
+            let line = match self.read_line(prompt) {
+                Ok(Some(line)) => line,
+                // 读到一半时输入结束，把已有部分当作完整消息喵
+                Ok(None) => return Ok(pending.is_pending().then(|| pending.finish())),
+                // Ctrl-C 放弃整条输入（包括已经输入的续行）喵
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(Some(String::new())),
+                Err(e) => return Err(e),
+            };
+            if let Some(message) = pending.feed(&line) {
+                if let Err(e) = self.history.push(&message) {
+                    tracing::warn!("保存输入历史失败喵: {}", e);
+                }
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    /// 读取一行喵（终端中 Ctrl-C 返回 `Interrupted`）
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        #[cfg(unix)]
+        if self.terminal {
+            return raw::read_line(prompt, self.history.entries());
+        }
+
+        if self.terminal {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+        }
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line.trim_end_matches(['\n', '\r']).to_string())),
+        }
+    }
+}
+
+/// 终端原始模式下的行编辑喵
+#[cfg(unix)]
+mod raw {
+    use std::io::{self, Write};
+
+    /// 🔒 SAFETY: 原始模式守卫喵（离开作用域时恢复终端设置）
+    struct RawMode(libc::termios);
+
+    impl RawMode {
+        fn enable() -> io::Result<Self> {
+            // 🔒 SAFETY: termios 由 tcgetattr 完整写入后才使用喵
+            let mut original: libc::termios = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self(original))
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &self.0) };
+        }
+    }
+
+    fn read_byte() -> io::Result<Option<u8>> {
+        let mut byte = 0u8;
+        loop {
+            // 🔒 SAFETY: 向一个字节的缓冲区读取一个字节喵
+            match unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut libc::c_void, 1) } {
+                1 => return Ok(Some(byte)),
+                0 => return Ok(None),
+                _ => {
+                    let error = io::Error::last_os_error();
+                    if error.kind() != io::ErrorKind::Interrupted {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 读取一个完整的 UTF-8 字符喵
+    fn read_char(first: u8) -> io::Result<Option<char>> {
+        let len = match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let mut bytes = vec![first];
+        while bytes.len() < len {
+            match read_byte()? {
+                Some(byte) => bytes.push(byte),
+                None => return Ok(None),
+            }
+        }
+        Ok(std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()))
+    }
+
+    /// 终端中的显示宽度喵（中日韩字符与 emoji 占两列）
+    fn width(c: char) -> usize {
+        match c as u32 {
+            0x1100..=0x115f | 0x2e80..=0xa4cf | 0xac00..=0xd7a3 | 0xf900..=0xfaff | 0xfe30..=0xfe4f | 0xff00..=0xff60
+            | 0xffe0..=0xffe6 | 0x1f300..=0x1faff | 0x20000..=0x3fffd => 2,
+            _ => 1,
+        }
+    }
+
+    fn redraw(out: &mut impl Write, prompt: &str, buffer: &[char], cursor: usize) -> io::Result<()> {
+        let line: String = buffer.iter().collect();
+        write!(out, "\r{}{}\x1b[K", prompt, line)?;
+        let back: usize = buffer[cursor..].iter().map(|c| width(*c)).sum();
+        if back > 0 {
+            write!(out, "\x1b[{}D", back)?;
+        }
+        out.flush()
+    }
+
+    pub fn read_line(prompt: &str, history: &[String]) -> io::Result<Option<String>> {
+        let mut out = io::stdout();
+        write!(out, "{}", prompt)?;
+        out.flush()?;
+        let _raw = RawMode::enable()?;
+
+        let mut buffer: Vec<char> = Vec::new();
+        let mut cursor = 0;
+        // 正在浏览的历史位置（== history.len() 表示正在编辑的新输入）喵
+        let mut browsing = history.len();
+        let mut draft: Vec<char> = Vec::new();
+        loop {
+            let Some(byte) = read_byte()? else {
+                write!(out, "\r\n")?;
+                return Ok(None);
+            };
+            match byte {
+                b'\r' | b'\n' => {
+                    write!(out, "\r\n")?;
+                    return Ok(Some(buffer.into_iter().collect()));
+                }
+                // Ctrl-C：放弃当前输入喵
+                3 => {
+                    write!(out, "^C\r\n")?;
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                // Ctrl-D：空行时退出，否则删除光标处的字符喵
+                4 if buffer.is_empty() => {
+                    write!(out, "\r\n")?;
+                    return Ok(None);
+                }
+                4 if cursor < buffer.len() => {
+                    buffer.remove(cursor);
+                }
+                1 => cursor = 0,
+                5 => cursor = buffer.len(),
+                21 => {
+                    buffer.drain(..cursor);
+                    cursor = 0;
+                }
+                // Ctrl-W：删除光标前的一个词喵
+                23 => {
+                    let mut start = cursor;
+                    while start > 0 && buffer[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    while start > 0 && !buffer[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    buffer.drain(start..cursor);
+                    cursor = start;
+                }
+                8 | 127 if cursor > 0 => {
+                    cursor -= 1;
+                    buffer.remove(cursor);
+                }
+                0x1b => {
+                    if read_byte()? != Some(b'[') {
+                        continue;
+                    }
+                    match read_byte()? {
+                        Some(b'A') if browsing > 0 => {
+                            if browsing == history.len() {
+                                draft = buffer.clone();
+                            }
+                            browsing -= 1;
+                            buffer = history[browsing].chars().collect();
+                            cursor = buffer.len();
+                        }
+                        Some(b'B') if browsing < history.len() => {
+                            browsing += 1;
+                            buffer = match history.get(browsing) {
+                                Some(entry) => entry.chars().collect(),
+                                None => draft.clone(),
+                            };
+                            cursor = buffer.len();
+                        }
+                        Some(b'C') if cursor < buffer.len() => cursor += 1,
+                        Some(b'D') if cursor > 0 => cursor -= 1,
+                        Some(b'H') => cursor = 0,
+                        Some(b'F') => cursor = buffer.len(),
+                        Some(b'3') if read_byte()? == Some(b'~') && cursor < buffer.len() => {
+                            buffer.remove(cursor);
+                        }
+                        _ => {}
+                    }
+                }
+                byte if byte >= 0x20 && byte != 127 => {
+                    if let Some(c) = read_char(byte)? {
+                        buffer.insert(cursor, c);
+                        cursor += 1;
+                    }
+                }
+                _ => {}
+            }
+            redraw(&mut out, prompt, &buffer, cursor)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiline_and_history() {
+        let mut input = MultiLine::default();
+        assert_eq!(input.feed("hello").as_deref(), Some("hello"));
+        assert_eq!(input.feed("first \\"), None);
+        assert!(input.is_pending());
+        assert_eq!(input.feed("second").as_deref(), Some("first \nsecond"));
+        assert_eq!(input.feed("```rust"), None);
+        assert_eq!(input.feed("fn main() {} \\"), None);
+        assert_eq!(input.feed("```").as_deref(), Some("```rust\nfn main() {} \\\n```"));
+        assert_eq!(input.feed("```inline```").as_deref(), Some("```inline```"));
+        assert!(!input.is_pending());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        let mut history = InputHistory::open(&path);
+        history.push("first").unwrap();
+        history.push("first").unwrap();
+        history.push("line one\nline two").unwrap();
+        history.push("  ").unwrap();
+        assert_eq!(InputHistory::open(&path).entries(), ["first", "line one\nline two"]);
+
+        for i in 0..MAX_HISTORY {
+            history.push(&i.to_string()).unwrap();
+        }
+        let reopened = InputHistory::open(&path);
+        assert_eq!(reopened.entries().len(), MAX_HISTORY);
+        assert_eq!(reopened.entries()[0], "0");
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ReplCommand::parse("  EXIT "), Some(ReplCommand::Quit));
+        assert_eq!(ReplCommand::parse("clear"), Some(ReplCommand::Clear));
+        assert_eq!(ReplCommand::parse("/model"), Some(ReplCommand::Model("")));
+        assert_eq!(ReplCommand::parse("/model  gpt-4o "), Some(ReplCommand::Model("gpt-4o")));
+        assert_eq!(ReplCommand::parse("/feedback down 太长了"), Some(ReplCommand::Feedback("down 太长了")));
+        assert_eq!(ReplCommand::parse("/tools all"), Some(ReplCommand::Tools));
+        assert_eq!(
+            ReplCommand::parse("/models"),
+            Some(ReplCommand::Unknown { name: "models", suggestion: Some("model") })
+        );
+        assert_eq!(
+            ReplCommand::parse("/xyzzy"),
+            Some(ReplCommand::Unknown { name: "xyzzy", suggestion: None })
+        );
+        assert_eq!(ReplCommand::parse("clear the cache"), None);
+        assert_eq!(ReplCommand::parse("你好"), None);
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+ * Response Policy - 按渠道的回复长度与格式
+ *
+ * 同一个 Agent 在不同渠道给出不同详略的回复喵：
+ * Telegram 简短、CLI 可以详细。
+ *
+ * 两层约束喵：
+ * - 系统提示词中追加详略与格式要求，让模型主动控制长度
+ * - 请求的 `max_tokens` 不超过 `max_response_tokens`（硬上限）
+ *
+ * ```toml
+ * [response_policies.telegram]
+ * max_response_tokens = 400
+ * verbosity = "terse"
+ * formatting = ["No tables or headings", "At most one short code block"]
+ * ```
+ * 未配置的渠道使用内置默认值（telegram 简短、cli 详细，其余适中）喵
+ */
+
+use crate::core::traits::Config;
+use serde::{Deserialize, Serialize};
+
+/// Telegram 默认的回复 token 上限喵
+const TELEGRAM_MAX_RESPONSE_TOKENS: u32 = 500;
+
+/// 回复详略程度喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    Terse,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+/// 单个渠道的回复策略喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponsePolicy {
+    /// 回复的 token 硬上限（None = 不额外限制）
+    #[serde(default)]
+    pub max_response_tokens: Option<u32>,
+    #[serde(default)]
+    pub verbosity: Verbosity,
+    /// 格式要求（每条一句，原样写进提示词）
+    #[serde(default)]
+    pub formatting: Vec<String>,
+}
+
+impl ResponsePolicy {
+    /// 内置默认策略喵
+    pub fn builtin(channel: &str) -> Self {
+        match channel {
+            "telegram" => Self {
+                max_response_tokens: Some(TELEGRAM_MAX_RESPONSE_TOKENS),
+                verbosity: Verbosity::Terse,
+                formatting: vec![
+                    "Plain text with at most light Markdown (bold, italics, inline code); no tables or headings"
+                        .to_string(),
+                    "Keep code blocks to a few lines".to_string(),
+                ],
+            },
+            "cli" => Self {
+                verbosity: Verbosity::Verbose,
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// 🔒 SAFETY: 对请求的 max_tokens 应用硬上限喵
+    pub fn cap_max_tokens(&self, requested: u32) -> u32 {
+        self.max_response_tokens.map_or(requested, |cap| requested.min(cap))
+    }
+
+    /// 追加到系统提示词的要求喵（默认策略没有要求时为 None）
+    pub fn instruction(&self, channel: &str) -> Option<String> {
+        let mut lines = Vec::new();
+        match self.verbosity {
+            Verbosity::Terse => lines.push(
+                "Be brief: answer in a few sentences, lead with the answer, skip preambles and recaps.".to_string(),
+            ),
+            Verbosity::Normal => {}
+            Verbosity::Verbose => lines.push(
+                "Detailed answers are welcome: explain your reasoning and include steps or examples where useful."
+                    .to_string(),
+            ),
+        }
+        if let Some(max) = self.max_response_tokens {
+            lines.push(format!(
+                "Replies are cut off after about {} tokens, so finish well within that.",
+                max
+            ));
+        }
+        if !self.formatting.is_empty() {
+            lines.push("Formatting:".to_string());
+            lines.extend(self.formatting.iter().map(|rule| format!("- {}", rule)));
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "===== RESPONSE STYLE ({}) =====\n{}\n===== END RESPONSE STYLE =====",
+            channel,
+            lines.join("\n")
+        ))
+    }
+}
+
+impl Config {
+    /// 渠道的回复策略喵（配置优先，否则使用内置默认值）
+    pub fn response_policy(&self, channel: &str) -> ResponsePolicy {
+        self.response_policies
+            .get(channel)
+            .cloned()
+            .unwrap_or_else(|| ResponsePolicy::builtin(channel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_policies() {
+        let mut config = Config::default();
+        let telegram = config.response_policy("telegram");
+        assert_eq!(telegram.cap_max_tokens(4096), TELEGRAM_MAX_RESPONSE_TOKENS);
+        let instruction = telegram.instruction("telegram").unwrap();
+        assert!(instruction.contains("Be brief"));
+        assert!(instruction.contains("about 500 tokens"));
+
+        let cli = config.response_policy("cli");
+        assert_eq!(cli.cap_max_tokens(4096), 4096);
+        assert!(cli.instruction("cli").unwrap().contains("Detailed answers"));
+        assert_eq!(config.response_policy("discord").instruction("discord"), None);
+
+        config.response_policies.insert(
+            "telegram".to_string(),
+            toml::from_str("max_response_tokens = 200\nformatting = [\"No emoji\"]").unwrap(),
+        );
+        let telegram = config.response_policy("telegram");
+        assert_eq!(telegram.cap_max_tokens(100), 100);
+        assert_eq!(telegram.cap_max_tokens(4096), 200);
+        assert!(telegram.instruction("telegram").unwrap().ends_with("- No emoji\n===== END RESPONSE STYLE ====="));
+    }
+}
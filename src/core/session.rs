@@ -0,0 +1,558 @@
+/*!
+ * Session Index - 会话标题与标签
+ *
+ * 每个会话在工作区 `sessions/<id>.json` 中保存一份 SessionInfo，
+ * 积累几条消息后用便宜的模型生成简短标题与标签，
+ * 让 `nekoclaw sessions list` 显示有意义的名字并支持按标签过滤喵。
+ *
+ * 对话内容追加到 `sessions/<id>.messages.jsonl`；启用 `session_encryption` 后
+ * 每条消息内容用按会话派生的密钥做 AES-GCM 加密，读取时透明解密喵。
+ *
+ * `nekoclaw agent --session <name>` 以名字作为会话 ID，再次使用同一名字时
+ * 把最近的对话（按 token 预算截取）恢复进上下文继续聊喵。
+ *
+ * ```toml
+ * [session_titles]
+ * model = "meta/llama-3.1-8b-instruct"
+ * after_messages = 4
+ *
+ * [session_encryption]
+ * enabled = true
+ * ```
+ */
+
+use crate::core::traits::Result;
+use crate::providers::Message;
+use crate::security::{derive_key, CryptoService};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// 参与生成标题的最近消息条数喵
+const TRANSCRIPT_MESSAGES: usize = 8;
+/// 每条消息截取的最大字符数喵
+const TRANSCRIPT_CHARS: usize = 500;
+/// 标题最大字符数喵
+const MAX_TITLE_CHARS: usize = 60;
+/// 对话内容文件后缀喵
+const MESSAGES_SUFFIX: &str = ".messages.jsonl";
+/// 会话名最大长度喵
+const MAX_SESSION_NAME_LEN: usize = 64;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_after_messages() -> u32 {
+    4
+}
+
+fn default_max_tags() -> usize {
+    5
+}
+
+/// 标题生成配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTitleConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 生成标题用的模型（None = 默认模型）喵
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 会话累计多少条消息后生成喵
+    #[serde(default = "default_after_messages")]
+    pub after_messages: u32,
+    /// 最多保留几个标签喵
+    #[serde(default = "default_max_tags")]
+    pub max_tags: usize,
+}
+
+impl Default for SessionTitleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            model: None,
+            after_messages: default_after_messages(),
+            max_tags: default_max_tags(),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 会话信息喵（只保存元数据，不保存对话内容）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub agent_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub message_count: u32,
+    /// 对话内容是否加密存储喵
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+impl SessionInfo {
+    pub fn new(session_id: &str, agent_id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            session_id: session_id.to_string(),
+            agent_id: agent_id.to_string(),
+            title: None,
+            tags: Vec::new(),
+            created_at: now,
+            last_activity: now,
+            message_count: 0,
+            encrypted: false,
+        }
+    }
+
+    /// 记录一条消息喵
+    pub fn record_message(&mut self) {
+        self.message_count += 1;
+        self.last_activity = Utc::now();
+    }
+
+    /// 是否该生成标题了喵
+    pub fn needs_title(&self, config: &SessionTitleConfig) -> bool {
+        config.enabled && self.title.is_none() && self.message_count >= config.after_messages
+    }
+
+    /// 列表中显示的名字（没有标题时退回会话 ID）喵
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.session_id)
+    }
+
+    /// 是否带有某个标签（不区分大小写，忽略 `#`）喵
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        self.tags.contains(&tag)
+    }
+}
+
+/// 🔒 SAFETY: 存储的一条对话消息喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub role: String,
+    /// 消息内容（`encrypted` 为 true 时是密文）喵
+    pub content: String,
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+}
+
+/// 会话索引存储喵
+#[derive(Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+    /// 会话加密主密钥（None = 明文存储）喵
+    /// ⚠️ SAFETY: 只用于派生各会话的子密钥喵
+    master_key: Option<Vec<u8>>,
+}
+
+impl SessionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            master_key: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 加密之后写入的对话内容喵
+    pub fn with_encryption(mut self, master_key: Vec<u8>) -> Self {
+        self.master_key = Some(master_key);
+        self
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.master_key.is_some()
+    }
+
+    /// 会话目录喵
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", session_id))
+    }
+
+    fn messages_path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{}{}", session_id, MESSAGES_SUFFIX))
+    }
+
+    /// 按会话派生的加密服务喵
+    fn session_cipher(&self, session_id: &str) -> Result<Option<CryptoService>> {
+        match &self.master_key {
+            Some(master) => Ok(Some(CryptoService::new(&derive_key(master, &format!("session:{}", session_id)))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 保存（覆盖）会话信息喵
+    pub fn save(&self, info: &SessionInfo) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(&info.session_id), serde_json::to_vec_pretty(info)?)?;
+        Ok(())
+    }
+
+    /// 把一段对话另存为新会话喵（REPL `/save`；同名会话已存在时拒绝覆盖）
+    pub fn save_as(&self, name: &str, agent_id: &str, transcript: &[Message]) -> Result<SessionInfo> {
+        validate_session_name(name)?;
+        if self.load(name)?.is_some() {
+            return Err(format!("会话 {} 已存在喵，换个名字吧", name).into());
+        }
+        let mut info = SessionInfo::new(name, agent_id);
+        info.encrypted = self.is_encrypted();
+        self.append_messages(name, transcript)?;
+        // 消息数只算用户输入与 Agent 回复喵
+        for message in transcript.iter().filter(|m| matches!(m.role.as_str(), "user" | "assistant")) {
+            if !message.content.trim().is_empty() {
+                info.record_message();
+            }
+        }
+        self.save(&info)?;
+        Ok(info)
+    }
+
+    /// 按完整 ID 读取会话喵
+    pub fn load(&self, session_id: &str) -> Result<Option<SessionInfo>> {
+        let path = self.path(session_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        read_info(&path).map(Some)
+    }
+
+    /// 按 ID 或 ID 前缀查找会话喵
+    pub fn find(&self, prefix: &str) -> Result<Option<SessionInfo>> {
+        let mut matches = self.list()?.into_iter().filter(|s| s.session_id.starts_with(prefix));
+        match (matches.next(), matches.next()) {
+            (Some(_), Some(_)) => Err(format!("会话前缀 '{}' 匹配到多个会话喵", prefix).into()),
+            (found, _) => Ok(found),
+        }
+    }
+
+    /// 追加对话消息喵（启用加密时只写密文）
+    pub fn append_messages(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        let cipher = self.session_cipher(session_id)?;
+        let mut lines = String::new();
+        for message in messages {
+            let content = match &cipher {
+                Some(cipher) => cipher.encrypt(&message.content)?,
+                None => message.content.clone(),
+            };
+            let stored = StoredMessage {
+                role: message.role.clone(),
+                content,
+                at: Utc::now(),
+                encrypted: cipher.is_some(),
+            };
+            lines.push_str(&serde_json::to_string(&stored)?);
+            lines.push('\n');
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.messages_path(session_id))?
+            .write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// 按存储原样读取对话消息喵（不解密，供同步在机器之间搬运密文）
+    pub fn load_stored_messages(&self, session_id: &str) -> Result<Vec<StoredMessage>> {
+        let path = self.messages_path(session_id);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        std::fs::read_to_string(&path)?
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    /// 读取对话消息并透明解密喵
+    ///
+    /// 本机没有对应密钥时保留密文（`encrypted` 仍为 true）并给出警告
+    pub fn load_messages(&self, session_id: &str) -> Result<Vec<StoredMessage>> {
+        let cipher = self.session_cipher(session_id)?;
+        let mut messages = Vec::new();
+        let mut undecryptable = 0;
+        for mut message in self.load_stored_messages(session_id)? {
+            if message.encrypted {
+                match cipher.as_ref().map(|c| c.decrypt(&message.content)) {
+                    Some(Ok(plaintext)) => {
+                        message.content = plaintext;
+                        message.encrypted = false;
+                    }
+                    _ => undecryptable += 1,
+                }
+            }
+            messages.push(message);
+        }
+        if undecryptable > 0 {
+            warn!(
+                "会话 {} 有 {} 条消息已加密，但本机没有对应的密钥，无法解密喵",
+                session_id, undecryptable
+            );
+        }
+        Ok(messages)
+    }
+
+    /// 🔒 SAFETY: 用另一台机器的版本整体替换会话喵（消息按存储原样写入，密文保持密文）
+    pub fn import(&self, info: &SessionInfo, messages: &[StoredMessage]) -> Result<()> {
+        validate_session_name(&info.session_id)?;
+        let mut lines = String::new();
+        for message in messages {
+            lines.push_str(&serde_json::to_string(message)?);
+            lines.push('\n');
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.messages_path(&info.session_id), lines)?;
+        self.save(info)
+    }
+
+    /// 删除会话及其对话消息喵
+    ///
+    /// ## Returns
+    /// 会话不存在时返回 false 喵
+    pub fn delete(&self, session_id: &str) -> Result<bool> {
+        validate_session_name(session_id)?;
+        let mut removed = false;
+        for path in [self.path(session_id), self.messages_path(session_id)] {
+            match std::fs::remove_file(&path) {
+                Ok(()) => removed = true,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 列出所有会话（最近活跃的在前）喵
+    pub fn list(&self) -> Result<Vec<SessionInfo>> {
+        let mut sessions = Vec::new();
+        if !self.dir.is_dir() {
+            return Ok(sessions);
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read_info(&path) {
+                Ok(info) => sessions.push(info),
+                // 会话目录里也可能有其他 JSON，跳过即可喵
+                Err(e) => warn!("跳过无法解析的会话文件 {}: {}", path.display(), e),
+            }
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_activity));
+        Ok(sessions)
+    }
+}
+
+fn read_info(path: &Path) -> Result<SessionInfo> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+/// 🔒 SAFETY: 校验会话名喵（只允许字母、数字、`-`、`_`，防止路径穿越）
+pub fn validate_session_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_SESSION_NAME_LEN {
+        return Err(format!("会话名长度必须在 1~{} 之间喵", MAX_SESSION_NAME_LEN).into());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("会话名 '{}' 只能包含字母、数字、- 和 _ 喵", name).into());
+    }
+    Ok(())
+}
+
+/// 把保存的对话恢复成上下文消息喵
+///
+/// 只恢复 user / assistant 文本（工具调用与结果不恢复），
+/// 从最近的消息往前取，直到用完 `token_budget`；无法解密的消息跳过
+pub fn resume_messages(stored: &[StoredMessage], token_budget: usize) -> Vec<Message> {
+    let mut budget = token_budget;
+    let mut restored: Vec<Message> = stored
+        .iter()
+        .rev()
+        .filter(|m| !m.encrypted && !m.content.trim().is_empty())
+        .filter_map(|m| match m.role.as_str() {
+            "user" => Some(Message::user(m.content.clone())),
+            "assistant" => Some(Message::assistant(m.content.clone())),
+            _ => None,
+        })
+        .take_while(|m| {
+            let tokens = crate::providers::tokenizer::count_tokens(&m.content);
+            budget = match budget.checked_sub(tokens) {
+                Some(rest) => rest,
+                None => return false,
+            };
+            true
+        })
+        .collect();
+    restored.reverse();
+
+    // 以用户消息开头，避免上下文从半截回复开始喵
+    let first_user = restored.iter().position(|m| m.role == "user").unwrap_or(restored.len());
+    restored.split_off(first_user)
+}
+
+/// 构造生成标题的请求消息喵（只取最近几条 user / assistant 消息）
+pub fn title_request_messages(history: &[Message]) -> Vec<Message> {
+    let transcript: Vec<String> = history
+        .iter()
+        .filter(|m| m.role == "user" || m.role == "assistant")
+        .rev()
+        .take(TRANSCRIPT_MESSAGES)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .map(|m| format!("{}: {}", m.role, m.content.chars().take(TRANSCRIPT_CHARS).collect::<String>()))
+        .collect();
+
+    vec![
+        Message::system(
+            "You label chat sessions. Reply with exactly two lines and nothing else:\n\
+             Title: <a short title, at most 8 words, in the conversation's language>\n\
+             Tags: <1-5 lowercase single-word topic tags, comma separated>"
+                .to_string(),
+        ),
+        Message::user(transcript.join("\n")),
+    ]
+}
+
+/// 解析模型返回的标题与标签喵
+pub fn parse_title_response(raw: &str, max_tags: usize) -> Option<(String, Vec<String>)> {
+    let mut title = None;
+    let mut tags = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim().trim_start_matches(['*', '-', ' ']);
+        if let Some(rest) = strip_label(line, "title") {
+            let rest = rest.trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '*' | '`'));
+            if !rest.is_empty() {
+                title = Some(rest.chars().take(MAX_TITLE_CHARS).collect::<String>());
+            }
+        } else if let Some(rest) = strip_label(line, "tags") {
+            for tag in rest.split([',', '，', ' ']).map(normalize_tag) {
+                if !tag.is_empty() && !tags.contains(&tag) && tags.len() < max_tags {
+                    tags.push(tag);
+                }
+            }
+        }
+    }
+    title.map(|title| (title, tags))
+}
+
+/// 去掉 `Title:` / `tags：` 这类前缀喵
+fn strip_label<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+    let head = line.get(..label.len())?;
+    if !head.eq_ignore_ascii_case(label) {
+        return None;
+    }
+    let rest = line[label.len()..].trim_start_matches(['*']);
+    rest.strip_prefix(':').or_else(|| rest.strip_prefix('：'))
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim_matches(['"', '\'', '.', '`']).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_title_response() {
+        let raw = "**Title:** \"Rust 编译错误排查\"\nTags: Rust, #cargo, rust，build";
+        let (title, tags) = parse_title_response(raw, 5).unwrap();
+        assert_eq!(title, "Rust 编译错误排查");
+        assert_eq!(tags, vec!["rust", "cargo", "build"]);
+
+        assert_eq!(parse_title_response("title: disk cleanup\ntags: a, b, c", 2).unwrap().1, vec!["a", "b"]);
+        assert!(parse_title_response("I cannot help with that", 5).is_none());
+    }
+
+    #[test]
+    fn test_store_lists_recent_first_and_filters_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path());
+        let config = SessionTitleConfig::default();
+
+        let mut old = SessionInfo::new("s-old", "nia");
+        old.last_activity = Utc::now() - chrono::Duration::hours(1);
+        store.save(&old).unwrap();
+
+        let mut new = SessionInfo::new("s-new", "nia");
+        (0..4).for_each(|_| new.record_message());
+        assert!(new.needs_title(&config));
+        new.title = Some("Disk cleanup".to_string());
+        new.tags = vec!["linux".to_string()];
+        assert!(!new.needs_title(&config));
+        store.save(&new).unwrap();
+        std::fs::write(dir.path().join("notes.json"), "[]").unwrap();
+
+        let sessions = store.list().unwrap();
+        assert_eq!(sessions.iter().map(|s| s.display_name()).collect::<Vec<_>>(), ["Disk cleanup", "s-old"]);
+        assert!(sessions[0].has_tag("#Linux"));
+        assert_eq!(sessions[0], new);
+        assert_eq!(store.find("s-n").unwrap().unwrap().session_id, "s-new");
+        assert!(store.find("s-").is_err());
+    }
+
+    #[test]
+    fn test_resume_messages_within_budget() {
+        let at = Utc::now();
+        let stored = |role: &str, content: &str, encrypted: bool| StoredMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            at,
+            encrypted,
+        };
+        let history = vec![
+            stored("user", &"很早的问题 ".repeat(200), false),
+            stored("assistant", "很早的回答", false),
+            stored("user", "重启 nginx", false),
+            stored("assistant", "", false),
+            stored("tool", "Tool result: ok", false),
+            stored("assistant", "已经重启好了喵", false),
+            stored("user", "c2VjcmV0", true),
+        ];
+
+        let restored = resume_messages(&history, 200);
+        let roles: Vec<&str> = restored.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+        assert_eq!(restored[0].content, "重启 nginx");
+        assert!(resume_messages(&history, 0).is_empty());
+
+        assert!(validate_session_name("work-notes_2").is_ok());
+        assert!(validate_session_name("../etc").is_err());
+        assert!(validate_session_name("").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_messages_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path()).with_encryption(vec![1u8; 32]);
+        store
+            .append_messages("s1", &[Message::user("我的密码是 hunter2".to_string())])
+            .unwrap();
+
+        let raw = std::fs::read_to_string(dir.path().join("s1.messages.jsonl")).unwrap();
+        assert!(!raw.contains("hunter2"));
+        let loaded = store.load_messages("s1").unwrap();
+        assert_eq!((loaded[0].content.as_str(), loaded[0].encrypted), ("我的密码是 hunter2", false));
+
+        // 换一台机器（不同密钥 / 没有密钥）时保留密文喵
+        let other = SessionStore::new(dir.path()).with_encryption(vec![2u8; 32]);
+        assert!(other.load_messages("s1").unwrap()[0].encrypted);
+        assert!(SessionStore::new(dir.path()).load_messages("s1").unwrap()[0].encrypted);
+    }
+}
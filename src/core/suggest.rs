@@ -0,0 +1,70 @@
+/*!
+ * Command Suggestions
+ *
+ * 基于 Levenshtein 距离为拼错的命令给出最接近的候选喵（例如 `/staus` → `/status`）。
+ */
+
+/// 计算两个字符串的编辑距离（按字符，相邻字母互换算一次编辑）喵
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// 找出最接近输入的候选命令喵
+///
+/// 距离上限为输入长度的三分之一（至少 1、最多 3），避免离谱的建议喵
+pub fn closest_match<'a>(
+    input: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    let limit = (input.chars().count() / 3).clamp(1, 3);
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(&input, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, candidate)| (*distance, *candidate))
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("staus", "status"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("喵喵", "喵"), 1);
+        assert_eq!(levenshtein("hlep", "help"), 1);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let commands = ["status", "start", "help", "ping", "shutdown"];
+        assert_eq!(closest_match("staus", commands), Some("status"));
+        assert_eq!(closest_match("HLEP", commands), Some("help"));
+        assert_eq!(closest_match("deploy", commands), None);
+    }
+}
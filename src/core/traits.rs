@@ -164,6 +164,8 @@ pub trait IdentityEngine: Send + Sync {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub base_url: String,
+    /// 明文，或 `enc:` 前缀的密文（`nekoclaw secret set`）喵
+    #[serde(default)]
     pub api_key: String,
     #[serde(default = "default_timeout")]
     pub timeout: u64,
@@ -178,6 +180,17 @@ fn default_max_retries() -> u8 { 3 }
 pub struct ProvidersConfig {
     #[serde(default)]
     pub nvidia: Option<ProviderConfig>,
+    #[serde(default)]
+    pub openai: Option<ProviderConfig>,
+    #[serde(default)]
+    pub openrouter: Option<ProviderConfig>,
+    #[serde(default)]
+    pub anthropic: Option<ProviderConfig>,
+    #[serde(default)]
+    pub gemini: Option<ProviderConfig>,
+    /// 本地 Ollama；base_url 以 `/v1` 结尾时按 OpenAI 兼容端点（llama.cpp server）调用
+    #[serde(default)]
+    pub ollama: Option<ProviderConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -186,6 +199,309 @@ pub struct DiscordConfig {
     pub token: String,
     pub allowed_users: Vec<String>,
     pub require_mention: bool,
+    /// 应用 ID（与 public_key 一起配置后，gateway 启动时注册斜杠命令）喵
+    #[serde(default)]
+    pub application_id: Option<String>,
+    /// 应用公钥（十六进制，校验 `/discord/interactions` 的签名）喵
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// 只在该服务器注册斜杠命令（立即生效；不设置时注册为全局命令）喵
+    #[serde(default)]
+    pub guild_id: Option<String>,
+}
+
+/// Telegram 长轮询配置喵（Bot Token 从 TELEGRAM_BOT_TOKEN 环境变量读取）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TelegramSettings {
+    /// 允许与 Bot 对话的 Chat ID（为空时不响应任何人）喵
+    #[serde(default)]
+    pub allowed_chat_ids: Vec<i64>,
+    /// 主人的 Telegram 用户 ID（始终为 Owner 角色，可使用 /pair 等 Owner 命令）喵
+    #[serde(default)]
+    pub owner_user_ids: Vec<i64>,
+}
+
+/// 记忆存储配置喵
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MemorySettings {
+    /// 数据库路径（默认 ~/.nekoclaw/memory.db）喵
+    #[serde(default)]
+    pub path: Option<std::path::PathBuf>,
+    /// 静态加密配置喵
+    #[serde(default)]
+    pub encryption: Option<MemoryEncryptionConfig>,
+    /// 向量化配置（语义检索）喵
+    #[serde(default)]
+    pub embedding: EmbeddingConfig,
+    /// 重要度 / 衰减评分配置喵
+    #[serde(default)]
+    pub scoring: MemoryScoringConfig,
+    /// 对话摘要配置（上下文裁剪前把旧消息摘要存入记忆）喵
+    #[serde(default)]
+    pub summaries: ConversationSummaryConfig,
+}
+
+/// 对话摘要配置喵
+///
+/// 交互对话的历史超过 `trigger_tokens` 时，较早的消息先交给模型摘要，
+/// 摘要以 `session` 标记存入记忆库后再从上下文中移除；之后每轮按用户输入召回本会话的摘要
+///
+/// ```toml
+/// [memory.summaries]
+/// model = "meta/llama-3.1-8b-instruct"  # 默认使用对话模型
+/// trigger_tokens = 12000
+/// keep_recent = 8
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummaryConfig {
+    #[serde(default = "default_summaries_enabled")]
+    pub enabled: bool,
+    /// 生成摘要用的模型（None = 对话模型）喵
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 历史（不含系统提示）估算 token 数超过该值时触发摘要喵
+    #[serde(default = "default_summary_trigger_tokens")]
+    pub trigger_tokens: usize,
+    /// 摘要后保留的最近消息数喵
+    #[serde(default = "default_summary_keep_recent")]
+    pub keep_recent: usize,
+    /// 每轮最多召回的摘要条数喵
+    #[serde(default = "default_summary_recall_limit")]
+    pub recall_limit: usize,
+}
+
+impl Default for ConversationSummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_summaries_enabled(),
+            model: None,
+            trigger_tokens: default_summary_trigger_tokens(),
+            keep_recent: default_summary_keep_recent(),
+            recall_limit: default_summary_recall_limit(),
+        }
+    }
+}
+
+fn default_summaries_enabled() -> bool {
+    true
+}
+
+fn default_summary_trigger_tokens() -> usize {
+    12_000
+}
+
+fn default_summary_keep_recent() -> usize {
+    8
+}
+
+fn default_summary_recall_limit() -> usize {
+    3
+}
+
+/// 记忆评分配置喵
+///
+/// 综合得分 = 重要度、时间衰减（半衰期）、访问次数的加权平均，取值 0~1；
+/// 检索结果按相关度 × 综合得分排序，`memory --prune` 清理低分记忆
+///
+/// ```toml
+/// [memory.scoring]
+/// half_life_days = 30
+/// prune_threshold = 0.25   # 默认重要度的记忆约 90 天无人访问后低于该值
+/// max_entries = 5000     # 超出时删除得分最低的记忆
+/// auto_prune = true      # 守护进程每天清理一次
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryScoringConfig {
+    #[serde(default = "default_importance_weight")]
+    pub importance_weight: f32,
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: f32,
+    #[serde(default = "default_access_weight")]
+    pub access_weight: f32,
+    /// 时间衰减半衰期（天，从最后一次访问算起）喵
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: f32,
+    /// 综合得分低于该值的记忆会被清理喵
+    #[serde(default = "default_prune_threshold")]
+    pub prune_threshold: f32,
+    /// 最多保留的记忆条数（None = 不限）喵
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+    /// 守护进程是否每天自动清理喵
+    #[serde(default)]
+    pub auto_prune: bool,
+}
+
+impl Default for MemoryScoringConfig {
+    fn default() -> Self {
+        Self {
+            importance_weight: default_importance_weight(),
+            recency_weight: default_recency_weight(),
+            access_weight: default_access_weight(),
+            half_life_days: default_half_life_days(),
+            prune_threshold: default_prune_threshold(),
+            max_entries: None,
+            auto_prune: false,
+        }
+    }
+}
+
+fn default_importance_weight() -> f32 {
+    0.4
+}
+
+fn default_recency_weight() -> f32 {
+    0.4
+}
+
+fn default_access_weight() -> f32 {
+    0.2
+}
+
+fn default_half_life_days() -> f32 {
+    30.0
+}
+
+fn default_prune_threshold() -> f32 {
+    0.25
+}
+
+/// 记忆向量化配置喵
+///
+/// ```toml
+/// [memory.embedding]
+/// provider = "openai"               # 或 "hash"（本地，无需网络）
+/// model = "text-embedding-3-small"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// "hash" 或 OpenAI 兼容的 provider 名（openai / nvidia / ollama …）；未配置 API Key 时回退到 hash 喵
+    #[serde(default = "default_embedding_provider")]
+    pub provider: String,
+    /// embedding 模型（provider 为 hash 时忽略）喵
+    #[serde(default = "default_embedding_model")]
+    pub model: String,
+    /// hash 向量维度喵
+    #[serde(default = "default_embedding_dimensions")]
+    pub dimensions: usize,
+    /// 语义召回的最低余弦相似度喵
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f32,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_embedding_provider(),
+            model: default_embedding_model(),
+            dimensions: default_embedding_dimensions(),
+            min_similarity: default_min_similarity(),
+        }
+    }
+}
+
+fn default_embedding_provider() -> String {
+    "hash".to_string()
+}
+
+fn default_embedding_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_embedding_dimensions() -> usize {
+    256
+}
+
+fn default_min_similarity() -> f32 {
+    0.15
+}
+
+/// 记忆数据库静态加密配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEncryptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 存放 Base64 密钥的环境变量喵
+    #[serde(default = "default_memory_key_env")]
+    pub key_env: String,
+    /// 密钥文件（环境变量未设置时使用喵）
+    #[serde(default)]
+    pub key_file: Option<std::path::PathBuf>,
+}
+
+fn default_memory_key_env() -> String {
+    "NEKOCLAW_MEMORY_KEY".to_string()
+}
+
+/// 远程状态同步配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 后端类型: "webdav" | "directory"喵
+    #[serde(default = "default_sync_backend")]
+    pub backend: String,
+    /// WebDAV 集合 URL（backend = webdav）喵
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 存放 WebDAV 密码的环境变量喵
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// 共享目录（backend = directory，例如 NFS / Syncthing 目录）喵
+    #[serde(default)]
+    pub directory: Option<std::path::PathBuf>,
+    /// 本机节点 ID（默认使用主机名）喵
+    #[serde(default)]
+    pub node_id: Option<String>,
+    /// Daemon 模式下的自动同步间隔（分钟，0 = 仅手动）喵
+    #[serde(default)]
+    pub interval_minutes: u64,
+    /// 是否同步配置文件（workspace 等本机路径与 provider、渠道凭据等含密钥的字段不会同步）喵
+    #[serde(default)]
+    pub include_config: bool,
+}
+
+fn default_sync_backend() -> String {
+    "webdav".to_string()
+}
+
+/// 安全策略配置喵
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SecuritySettings {
+    /// Shell 命令 / 路径白名单（未配置时不向 Agent 提供 shell 工具）喵
+    #[serde(default)]
+    pub allowlist: Option<crate::security::AllowlistConfig>,
+    /// 被拦截操作的放行申请喵
+    #[serde(default)]
+    pub escalation: Option<EscalationConfig>,
+    /// Shell / 技能子进程环境变量（未配置时使用最小空环境）喵
+    #[serde(default)]
+    pub tool_env: Option<crate::security::ToolEnvConfig>,
+    /// 危险工具执行前的确认（`auto_approve` 中的工具免确认）喵
+    #[serde(default)]
+    pub tool_approval: crate::security::ToolApprovalConfig,
+    /// Shell 工具的超时与输出上限喵
+    #[serde(default)]
+    pub shell: crate::tools::ShellToolConfig,
+}
+
+/// 放行申请配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationConfig {
+    /// 主人所在渠道: "discord" | "telegram"喵
+    pub owner_channel: String,
+    /// Discord DM 频道 ID 或 Telegram Chat ID喵
+    pub owner_target: String,
+}
+
+/// 隐私与数据保留配置喵
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PrivacyConfig {
+    /// 各渠道数据保留天数（例如 discord = 30），未列出的渠道永久保留喵
+    #[serde(default)]
+    pub retention_days: std::collections::HashMap<String, u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -215,9 +531,177 @@ pub struct Config {
     #[serde(rename = "discord")]
     pub discord_config: Option<DiscordConfig>,
 
+    // Telegram 配置（配置后 daemon 启动长轮询）喵
+    #[serde(default)]
+    pub telegram: Option<TelegramSettings>,
+
+    // Email 配置（配置后 daemon 定时轮询收件箱）喵
+    #[serde(default)]
+    pub email: Option<crate::channels::email::EmailConfig>,
+
     // Gateway 配置喵
     pub gateway_port: Option<u16>,
     pub gateway_bind: Option<String>,
+
+    // Gateway 认证失败限流与锁定喵
+    #[serde(default)]
+    pub gateway_auth: crate::gateway::AuthThrottleConfig,
+
+    // Gateway 流式响应心跳间隔（秒，默认 10）喵
+    #[serde(default)]
+    pub gateway_heartbeat_secs: Option<u64>,
+
+    // Provider 健康探测喵
+    #[serde(default)]
+    pub provider_health: crate::providers::ProviderHealthConfig,
+
+    // Daemon 启动预热（DNS / TLS / 记忆库 / Skills）喵
+    #[serde(default)]
+    pub warmup: crate::service::WarmupConfig,
+
+    // 监视目录，文件变化作为系统事件通知 Telegram Agent 会话喵
+    #[serde(default)]
+    pub watch: Vec<crate::service::WatchConfig>,
+
+    // 会话临时工作区（中间文件隔离）喵
+    #[serde(default)]
+    pub scratch: crate::security::ScratchConfig,
+
+    // Memory 配置喵
+    #[serde(default)]
+    pub memory: Option<MemorySettings>,
+
+    // 远程同步配置喵
+    #[serde(default)]
+    pub sync: Option<SyncConfig>,
+
+    // 隐私 / 数据保留配置喵
+    #[serde(default)]
+    pub privacy: Option<PrivacyConfig>,
+
+    // 安全策略配置喵
+    #[serde(default)]
+    pub security: Option<SecuritySettings>,
+
+    // 提示词截断检测（未配置时关闭）喵
+    #[serde(default)]
+    pub prompt_canary: Option<crate::core::PromptCanaryConfig>,
+
+    // 最终回复与工具结果核对（未配置时关闭）喵
+    #[serde(default)]
+    pub claim_check: Option<crate::core::ClaimCheckConfig>,
+
+    // 子 Agent 委派（`delegate` 工具，未配置时关闭）喵
+    #[serde(default)]
+    pub delegation: Option<crate::tools::DelegateConfig>,
+
+    // 工具提示词格式（full / compact / auto）喵
+    #[serde(default)]
+    pub tool_prompt: Option<crate::tools::ToolPromptConfig>,
+
+    // 外部 MCP server（名称 → 启动命令），工具自动注册进 Agent喵
+    #[serde(default)]
+    pub mcp_servers: std::collections::BTreeMap<String, crate::tools::McpServerConfig>,
+
+    // 每个对话的工具调用预算喵
+    #[serde(default)]
+    pub tool_budget: crate::tools::ToolBudgetConfig,
+
+    // 工具输出大小上限，超出时分页交给模型喵
+    #[serde(default)]
+    pub tool_output: crate::tools::ToolOutputConfig,
+
+    // 渠道与 Agent 之间的事件队列（容量、worker 数、每个渠道的并发上限）喵
+    #[serde(default)]
+    pub event_bus: crate::core::BusConfig,
+
+    // 守护进程中的定时任务（名称 → cron 与动作）喵
+    #[serde(default)]
+    pub schedule: std::collections::BTreeMap<String, crate::service::ScheduledTask>,
+
+    // Gateway `/v1/embeddings` 提供的命名 embedding 模型（默认模型为 memory.embedding）喵
+    #[serde(default)]
+    pub embeddings: std::collections::BTreeMap<String, EmbeddingConfig>,
+
+    // 关闭时等待进行中请求完成的宽限期喵
+    #[serde(default)]
+    pub shutdown: crate::service::ShutdownConfig,
+
+    // 渠道与 Gateway 共用的角色权限（默认角色、Owner、命令 / 工具所需角色）喵
+    #[serde(default)]
+    pub rbac: crate::auth::RbacConfig,
+
+    // Few-shot 示例（另可放在工作区 few_shot.json）喵
+    #[serde(default)]
+    pub few_shot: Option<crate::core::FewShotConfig>,
+
+    // 回复后处理链（按渠道）喵
+    #[serde(default)]
+    pub post_process: Option<crate::core::PostProcessConfig>,
+
+    // 回复长度与格式策略（按渠道，未配置的渠道使用内置默认值）喵
+    #[serde(default)]
+    pub response_policies: std::collections::HashMap<String, crate::core::ResponsePolicy>,
+
+    // Agent 默认模型与故障转移链（agents.defaults.model）喵
+    #[serde(default)]
+    pub agents: crate::providers::AgentsConfig,
+
+    // 模型参数预设（creative / precise / cheap ...）喵
+    #[serde(default)]
+    pub presets: std::collections::HashMap<String, crate::core::ModelPreset>,
+
+    // 默认使用的预设喵
+    #[serde(default)]
+    pub default_preset: Option<String>,
+
+    // 人设模板与说话风格（persona.speech_style = cat|none|custom）喵
+    #[serde(default)]
+    pub persona: crate::core::PersonaConfig,
+
+    // 系统提示词 A/B 实验喵
+    #[serde(default)]
+    pub experiments: Vec<crate::core::ExperimentConfig>,
+
+    // 会话标题与标签自动生成喵
+    #[serde(default)]
+    pub session_titles: crate::core::SessionTitleConfig,
+
+    // 会话内容静态加密（未设置密钥文件时使用工作区下的 session.key）喵
+    #[serde(default)]
+    pub session_encryption: Option<MemoryEncryptionConfig>,
+
+    // Agent 上下文压缩（超过阈值时按策略保留消息）喵
+    #[serde(default)]
+    pub compression: crate::performance::compress::CompressionConfig,
+
+    // Token 计数（tiktoken 词表 / usage 校准的估算）喵
+    #[serde(default)]
+    pub tokenizer: crate::providers::TokenizerConfig,
+
+    // 模型价格与费用预算（会话 / Agent / 每天）喵
+    #[serde(default)]
+    pub costs: crate::providers::CostConfig,
+
+    // Span 导出到 OTLP collector（Jaeger / Tempo）喵
+    #[serde(default)]
+    pub otlp: Option<crate::telemetry::OtlpConfig>,
+
+    // 入站 Webhook 来源（名称 → 签名密钥 / 格式 / 技能），Gateway 的 `/webhook/:source`喵
+    #[serde(default)]
+    pub webhooks: std::collections::BTreeMap<String, crate::gateway::webhook::WebhookSourceConfig>,
+
+    // 出站 Webhook（Agent 事件推送到外部 URL，带重试与签名）喵
+    #[serde(default)]
+    pub outbound_webhooks: Vec<crate::gateway::webhook::OutboundWebhookConfig>,
+
+    // OAuth 认证配置（auth.profiles，`nekoclaw auth login` 使用）喵
+    #[serde(default)]
+    pub auth: Option<crate::auth::AuthProfiles>,
+
+    // `enc:` 密钥的密钥环所在目录（加载配置时设置，不序列化）喵
+    #[serde(skip)]
+    pub secrets_dir: Option<std::path::PathBuf>,
 }
 
 fn default_provider() -> String {
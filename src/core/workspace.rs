@@ -0,0 +1,486 @@
+/*!
+ * 工作区（Profile）模块
+ *
+ * 一个二进制服务多个互相隔离的上下文（work / home ...）喵
+ *
+ * 目录布局:
+ * - `default`: 沿用 `~/.nekoclaw` 本身（兼容旧版本）
+ * - 其他:      `~/.nekoclaw/workspaces/<name>/`
+ *   - `config.{json,toml}` 覆盖基础配置中的字段
+ *   - `memory.db` / `sessions/` / `artifacts/` / `credentials/` / `workspace/skills/` 各自独立
+ *
+ * 配置文件的修改（`config set` / `--edit` / `--reset`）写入前都会解析为完整配置并校验喵
+ */
+
+use crate::core::traits::{Config, MemorySettings, Result};
+use std::path::{Path, PathBuf};
+
+/// 默认工作区名称喵
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// 🔒 SAFETY: 工作区描述喵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceProfile {
+    /// 工作区名称
+    pub name: String,
+    /// 基础配置目录（~/.nekoclaw）
+    pub base_dir: PathBuf,
+    /// 工作区根目录
+    pub root: PathBuf,
+}
+
+/// 🔒 SAFETY: 名称只允许字母、数字、`-`、`_`，防止路径穿越喵
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid workspace name: {:?}", name).into())
+    }
+}
+
+/// 递归合并 JSON 对象（overlay 覆盖 base）喵
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(slot) => merge_json(slot, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (slot, value) => *slot = value,
+    }
+}
+
+/// 按扩展名解析配置文件喵（.toml 以外都按 JSON）
+fn parse_document(path: &Path, content: &str) -> Result<serde_json::Value> {
+    match path.extension().is_some_and(|ext| ext == "toml") {
+        true => Ok(serde_json::to_value(toml::from_str::<toml::Table>(content)?)?),
+        false => Ok(serde_json::from_str(content)?),
+    }
+}
+
+/// 按扩展名序列化配置文件喵
+fn render_document(path: &Path, doc: &serde_json::Value) -> Result<String> {
+    match path.extension().is_some_and(|ext| ext == "toml") {
+        true => Ok(toml::to_string_pretty(&strip_nulls(doc.clone()))?),
+        false => Ok(serde_json::to_string_pretty(doc)?),
+    }
+}
+
+/// TOML 没有 null，写入前去掉值为 null 的字段喵
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(strip_nulls).collect()),
+        other => other,
+    }
+}
+
+/// 按点路径设置 JSON 值喵（数字段可索引已有数组）
+pub(crate) fn set_path(doc: &mut serde_json::Value, key: &str, value: serde_json::Value) -> Result<()> {
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(format!("Invalid config key: {:?}", key).into());
+    }
+    let (last, parents) = segments.split_last().expect("split yields at least one segment");
+
+    let mut current = doc;
+    for segment in parents {
+        current = match current {
+            serde_json::Value::Array(items) => {
+                let index: usize = segment.parse().map_err(|_| format!("'{}' is an array, expected an index", segment))?;
+                items.get_mut(index).ok_or_else(|| format!("Index {} out of range in {}", index, key))?
+            }
+            serde_json::Value::Object(map) => map
+                .entry(segment.to_string())
+                .or_insert_with(|| serde_json::Value::Object(Default::default())),
+            _ => return Err(format!("'{}' in {} is not an object", segment, key).into()),
+        };
+        if current.is_null() {
+            *current = serde_json::Value::Object(Default::default());
+        }
+    }
+
+    match current {
+        serde_json::Value::Array(items) => {
+            let index: usize = last.parse().map_err(|_| format!("'{}' is an array, expected an index", last))?;
+            let slot = items.get_mut(index).ok_or_else(|| format!("Index {} out of range in {}", index, key))?;
+            *slot = value;
+        }
+        serde_json::Value::Object(map) => {
+            match value.is_null() {
+                true => map.remove(*last),
+                false => map.insert(last.to_string(), value),
+            };
+        }
+        _ => return Err(format!("Parent of '{}' in {} is not an object", last, key).into()),
+    }
+    Ok(())
+}
+
+impl WorkspaceProfile {
+    /// 解析工作区（None = 默认工作区）喵
+    pub fn resolve(base_dir: &Path, name: Option<&str>) -> Result<Self> {
+        let name = name.unwrap_or(DEFAULT_WORKSPACE);
+        validate_name(name)?;
+
+        let root = if name == DEFAULT_WORKSPACE {
+            base_dir.to_path_buf()
+        } else {
+            base_dir.join("workspaces").join(name)
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            base_dir: base_dir.to_path_buf(),
+            root,
+        })
+    }
+
+    /// 是否为默认工作区喵
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_WORKSPACE
+    }
+
+    /// 工作区是否已创建喵
+    pub fn exists(&self) -> bool {
+        self.root.is_dir()
+    }
+
+    /// 创建工作区目录结构喵
+    pub fn create(&self) -> Result<()> {
+        std::fs::create_dir_all(self.sessions_dir())?;
+        std::fs::create_dir_all(self.credentials_dir())?;
+        std::fs::create_dir_all(self.workspace_dir().join("skills"))?;
+        Ok(())
+    }
+
+    /// 记忆数据库路径喵
+    pub fn memory_db_path(&self) -> PathBuf {
+        self.root.join("memory.db")
+    }
+
+    /// 会话目录喵
+    pub fn sessions_dir(&self) -> PathBuf {
+        self.root.join("sessions")
+    }
+
+    /// 会话产物目录（由会话文件按文件名引用）喵
+    pub fn artifacts_dir(&self) -> PathBuf {
+        self.root.join("artifacts")
+    }
+
+    /// 会话临时工作区根目录喵
+    pub fn scratch_dir(&self) -> PathBuf {
+        self.root.join("scratch")
+    }
+
+    /// 凭证目录喵
+    pub fn credentials_dir(&self) -> PathBuf {
+        self.root.join("credentials")
+    }
+
+    /// Agent 工作目录（skills 位于其下）喵
+    pub fn workspace_dir(&self) -> PathBuf {
+        self.root.join("workspace")
+    }
+
+    /// 读取工作区覆盖配置喵
+    fn load_overlay(&self) -> Result<Option<serde_json::Value>> {
+        let json_path = self.root.join("config.json");
+        if json_path.exists() {
+            let content = std::fs::read_to_string(&json_path)?;
+            return Ok(Some(serde_json::from_str(&content)?));
+        }
+
+        let toml_path = self.root.join("config.toml");
+        if toml_path.exists() {
+            let content = std::fs::read_to_string(&toml_path)?;
+            return Ok(Some(toml::from_str(&content)?));
+        }
+
+        Ok(None)
+    }
+
+    /// 加载工作区配置（基础配置 + 覆盖）喵
+    ///
+    /// 非默认工作区中未显式覆盖的 workspace / memory 路径会指向工作区自身目录
+    pub fn load_config(&self) -> Result<Config> {
+        let base = super::config::load(&self.base_dir)?;
+        if self.is_default() {
+            return Ok(base);
+        }
+
+        let mut value = serde_json::to_value(&base)?;
+        let overlay = self.load_overlay()?;
+        let overridden = |pointer: &str| {
+            overlay
+                .as_ref()
+                .and_then(|o| o.pointer(pointer))
+                .is_some_and(|v| !v.is_null())
+        };
+        let own_workspace = !overridden("/workspace");
+        let own_memory_path = !overridden("/memory/path");
+
+        if let Some(overlay) = overlay {
+            merge_json(&mut value, overlay);
+        }
+        crate::security::secrets::apply_secrets(&self.root, &mut value)?;
+
+        let mut config: Config = serde_json::from_value(value)?;
+        config.secrets_dir = Some(self.base_dir.clone());
+        if own_workspace {
+            config.workspace = self.workspace_dir();
+        }
+        if own_memory_path {
+            config
+                .memory
+                .get_or_insert_with(MemorySettings::default)
+                .path = Some(self.memory_db_path());
+        }
+        Ok(config)
+    }
+
+    /// 工作区的配置文件喵
+    ///
+    /// 优先 config.json，其次 config.toml，都不存在时为（待新建的）config.json
+    pub fn config_file(&self) -> PathBuf {
+        let json_path = self.root.join("config.json");
+        let toml_path = self.root.join("config.toml");
+        match !json_path.exists() && toml_path.exists() {
+            true => toml_path,
+            false => json_path,
+        }
+    }
+
+    /// 读取工作区配置文件中某个点路径的原始值喵（文件或字段不存在时为 None）
+    pub fn config_value(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let Some(doc) = self.config_document()? else {
+            return Ok(None);
+        };
+        Ok(doc.pointer(&format!("/{}", key.replace('.', "/"))).cloned())
+    }
+
+    /// 工作区配置文件的原始内容喵（不合并基础配置与 secrets.json；文件不存在时为 None）
+    pub fn config_document(&self) -> Result<Option<serde_json::Value>> {
+        let path = self.config_file();
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(parse_document(&path, &std::fs::read_to_string(&path)?)?))
+    }
+
+    /// 配置文件不存在时的初始内容喵（默认工作区为完整默认配置，其他工作区为空覆盖）
+    fn initial_document(&self) -> Result<serde_json::Value> {
+        match self.is_default() {
+            true => Ok(serde_json::to_value(Config::default())?),
+            false => Ok(serde_json::Value::Object(Default::default())),
+        }
+    }
+
+    /// 把配置文件内容解析为完整配置并校验喵（非默认工作区先与基础配置合并）
+    pub fn check_config_content(&self, path: &Path, content: &str) -> Result<Config> {
+        self.check_document(&parse_document(path, content)?)
+    }
+
+    fn check_document(&self, doc: &serde_json::Value) -> Result<Config> {
+        let config: Config = if self.is_default() {
+            serde_json::from_value(doc.clone())?
+        } else {
+            let mut value = serde_json::to_value(super::config::load(&self.base_dir)?)?;
+            merge_json(&mut value, doc.clone());
+            serde_json::from_value(value)?
+        };
+        super::config::validate(&config)?;
+        Ok(config)
+    }
+
+    /// 修改工作区配置文件喵
+    ///
+    /// 修改后的内容校验通过才写回（保持原文件格式），其余内容保持不变喵
+    ///
+    /// ## Returns
+    /// 被修改的文件路径喵
+    pub fn update_config<F>(&self, update: F) -> Result<PathBuf>
+    where
+        F: FnOnce(&mut serde_json::Value) -> Result<()>,
+    {
+        let path = self.config_file();
+        let mut doc = match path.exists() {
+            true => parse_document(&path, &std::fs::read_to_string(&path)?)?,
+            false => self.initial_document()?,
+        };
+        update(&mut doc)?;
+        self.check_document(&doc)?;
+
+        std::fs::create_dir_all(&self.root)?;
+        std::fs::write(&path, render_document(&path, &doc)?)?;
+        Ok(path)
+    }
+
+    /// 修改工作区配置文件中的顶层字段喵
+    pub fn set_config_values(&self, values: &[(&str, serde_json::Value)]) -> Result<PathBuf> {
+        self.update_config(|doc| {
+            let object = doc.as_object_mut().ok_or("配置文件顶层必须是对象喵")?;
+            for (key, value) in values {
+                object.insert(key.to_string(), value.clone());
+            }
+            Ok(())
+        })
+    }
+
+    /// 按点路径（如 `telegram.allowed_chat_ids`）设置配置项喵
+    ///
+    /// 中间对象不存在时自动创建；`null` 删除该字段（恢复默认值）
+    pub fn set_config_path(&self, key: &str, value: serde_json::Value) -> Result<PathBuf> {
+        self.update_config(|doc| set_path(doc, key, value))
+    }
+
+    /// 重置配置文件喵（默认工作区写入默认配置，其他工作区清空覆盖）
+    ///
+    /// ## Returns
+    /// (配置文件路径, 旧文件的备份路径)
+    pub fn reset_config(&self) -> Result<(PathBuf, Option<PathBuf>)> {
+        let path = self.config_file();
+        let backup = match path.exists() {
+            true => {
+                let mut name = path.file_name().unwrap_or_default().to_os_string();
+                name.push(".bak");
+                let backup = path.with_file_name(name);
+                std::fs::copy(&path, &backup)?;
+                Some(backup)
+            }
+            false => None,
+        };
+        std::fs::create_dir_all(&self.root)?;
+        std::fs::write(&path, render_document(&path, &self.initial_document()?)?)?;
+        Ok((path, backup))
+    }
+
+    /// 列出所有工作区（默认工作区排在首位）喵
+    pub fn list(base_dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(base_dir.join("workspaces"))
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().into_string().ok())
+                    .filter(|name| validate_name(name).is_ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.insert(0, DEFAULT_WORKSPACE.to_string());
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        let base = Path::new("/tmp/nekoclaw");
+        assert!(WorkspaceProfile::resolve(base, Some("../etc")).is_err());
+        assert!(WorkspaceProfile::resolve(base, Some("")).is_err());
+
+        let work = WorkspaceProfile::resolve(base, Some("work")).unwrap();
+        assert_eq!(work.root, base.join("workspaces/work"));
+        assert_eq!(WorkspaceProfile::resolve(base, None).unwrap().root, base);
+    }
+
+    #[test]
+    fn test_overlay_isolates_paths() {
+        let base = tempfile::tempdir().unwrap();
+        std::fs::write(
+            base.path().join("config.json"),
+            r#"{"workspace": "/srv/main", "default_model": "gpt-4"}"#,
+        )
+        .unwrap();
+
+        let work = WorkspaceProfile::resolve(base.path(), Some("work")).unwrap();
+        work.create().unwrap();
+        std::fs::write(
+            work.root.join("config.toml"),
+            "default_model = \"claude-3\"\n",
+        )
+        .unwrap();
+
+        let config = work.load_config().unwrap();
+        assert_eq!(config.default_model, "claude-3");
+        assert_eq!(config.workspace, work.workspace_dir());
+        assert_eq!(config.memory_db_path(), work.memory_db_path());
+
+        assert_eq!(
+            WorkspaceProfile::list(base.path()),
+            vec!["default".to_string(), "work".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_set_config_values_keeps_other_fields() {
+        let base = tempfile::tempdir().unwrap();
+        let work = WorkspaceProfile::resolve(base.path(), Some("work")).unwrap();
+        work.create().unwrap();
+        std::fs::write(
+            work.root.join("config.toml"),
+            "default_model = \"claude-3\"\ngateway_port = 9000\n",
+        )
+        .unwrap();
+
+        let path = work
+            .set_config_values(&[("default_model", serde_json::json!("gpt-4o"))])
+            .unwrap();
+        assert_eq!(path, work.root.join("config.toml"));
+
+        let config = work.load_config().unwrap();
+        assert_eq!(config.default_model, "gpt-4o");
+        assert_eq!(config.gateway_port, Some(9000));
+    }
+
+    #[test]
+    fn test_set_config_path_validates_and_resets() {
+        let base = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(base.path(), None).unwrap();
+
+        // 没有配置文件时以默认配置为底喵
+        let path = profile
+            .set_config_path("telegram.allowed_chat_ids", serde_json::json!([42]))
+            .unwrap();
+        assert_eq!(path, base.path().join("config.json"));
+        profile.set_config_path("default_temperature", serde_json::json!(0.4)).unwrap();
+        let config = profile.load_config().unwrap();
+        assert_eq!(config.telegram.unwrap().allowed_chat_ids, vec![42]);
+        assert_eq!(config.default_temperature, 0.4);
+
+        // 类型或范围不对时不写入喵
+        assert!(profile.set_config_path("default_temperature", serde_json::json!(5)).is_err());
+        assert!(profile.set_config_path("gateway_port", serde_json::json!("http")).is_err());
+        assert!(profile.set_config_path("default_model.name", serde_json::json!("x")).is_err());
+        assert_eq!(profile.load_config().unwrap().default_temperature, 0.4);
+
+        profile.set_config_path("telegram", serde_json::Value::Null).unwrap();
+        assert!(profile.load_config().unwrap().telegram.is_none());
+
+        let (path, backup) = profile.reset_config().unwrap();
+        let backup = backup.unwrap();
+        assert_eq!(backup, base.path().join("config.json.bak"));
+        assert!(std::fs::read_to_string(backup).unwrap().contains("0.4"));
+        assert_eq!(profile.check_config_content(&path, &std::fs::read_to_string(&path).unwrap()).unwrap().default_temperature, Config::default().default_temperature);
+    }
+}
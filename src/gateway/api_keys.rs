@@ -0,0 +1,308 @@
+//! Gateway API Key 🔑
+//!
+//! @诺诺 的 Gateway 多 Key 认证与限流喵
+//!
+//! 除了 `api_key` 配置的主 Bearer Token 外，可以为每个调用方创建命名的 API Key：
+//!
+//! - Key 加密保存在 `<工作区>/gateway_keys.json`（`enc:` 密文，密钥环与 `secret` 命令共用）
+//! - 通过 `Authorization: Bearer <key>` 或 `X-API-Key: <key>` 携带
+//! - 每个 Key 可设置令牌桶限流（每分钟请求数 + 突发容量），超限返回 429 + `Retry-After`
+//! - 撤销后 Gateway 重启即失效
+//!
+//! ```text
+//! nekoclaw gateway keys create ci --rpm 30
+//! nekoclaw gateway keys list
+//! nekoclaw gateway keys revoke ci
+//! ```
+
+use crate::security::crypto::{encrypt_secret, secret_keyring, CryptoService, SECRET_PREFIX};
+use chrono::{DateTime, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// API Key 存储文件名喵
+pub const GATEWAY_KEYS_FILE: &str = "gateway_keys.json";
+
+/// 生成的 Key 前缀（便于在日志和泄露扫描中识别）
+const KEY_PREFIX: &str = "nk_";
+
+/// 🔒 SAFETY: 单个 Key 的令牌桶限流喵
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 每分钟补充的请求数
+    pub requests_per_minute: u32,
+    /// 桶容量（允许的突发请求数）
+    pub burst: u32,
+}
+
+/// 🔒 SAFETY: 保存在文件中的 Key 记录喵（secret 为 `enc:` 密文）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredKey {
+    pub secret: String,
+    /// Key 明文的前几位，用于 `list` 时辨认
+    pub hint: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// 🔒 SAFETY: 解密后的 API Key 喵（Debug 输出不包含明文）
+#[derive(Clone)]
+pub struct ApiKey {
+    pub name: String,
+    pub secret: String,
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .field("secret", &"<redacted>")
+            .field("rate_limit", &self.rate_limit)
+            .finish()
+    }
+}
+
+/// 🔒 SAFETY: 认证通过的 Key 名称喵（由认证中间件注入请求扩展，使用主 Token 时不注入）
+#[derive(Debug, Clone)]
+pub struct ApiKeyName(pub String);
+
+/// 🔒 SAFETY: 加密的 API Key 存储喵
+pub struct ApiKeyStore {
+    path: PathBuf,
+    crypto: CryptoService,
+}
+
+impl ApiKeyStore {
+    /// 打开 Key 存储喵
+    ///
+    /// ## Arguments
+    /// * `store_dir` - `gateway_keys.json` 所在目录（工作区根目录）喵
+    /// * `key_dir` - 密钥环所在目录（基础配置目录）喵
+    pub fn open(store_dir: &Path, key_dir: &Path) -> Result<Self, String> {
+        let crypto = secret_keyring(key_dir).and_then(|ring| ring.crypto()).map_err(|e| e.to_string())?;
+        Ok(Self::with_crypto(store_dir, crypto))
+    }
+
+    /// 使用指定的加密服务喵（轮换密钥时传入新版本）
+    pub fn with_crypto(store_dir: &Path, crypto: CryptoService) -> Self {
+        Self {
+            path: store_dir.join(GATEWAY_KEYS_FILE),
+            crypto,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 创建新 Key 喵（同名 Key 已存在时报错）
+    ///
+    /// ## Returns
+    /// Key 明文，只在创建时返回一次
+    pub fn create(&self, name: &str, rate_limit: Option<RateLimit>) -> Result<String, String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid key name {:?} (use letters, digits, - and _)", name));
+        }
+        if let Some(limit) = rate_limit {
+            if limit.requests_per_minute == 0 || limit.burst == 0 {
+                return Err("Rate limit must allow at least one request".to_string());
+            }
+        }
+        let mut entries = self.read()?;
+        if entries.contains_key(name) {
+            return Err(format!("Gateway key {} already exists", name));
+        }
+
+        let mut bytes = [0u8; 24];
+        OsRng.fill_bytes(&mut bytes);
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let secret = format!("{}{}", KEY_PREFIX, hex);
+        entries.insert(
+            name.to_string(),
+            StoredKey {
+                secret: encrypt_secret(&self.crypto, &secret).map_err(|e| e.to_string())?,
+                hint: secret[..KEY_PREFIX.len() + 6].to_string(),
+                created_at: Utc::now(),
+                rate_limit,
+            },
+        );
+        self.write(&entries)?;
+        Ok(secret)
+    }
+
+    /// 撤销 Key 喵（不存在时返回 false）
+    pub fn revoke(&self, name: &str) -> Result<bool, String> {
+        let mut entries = self.read()?;
+        if entries.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.write(&entries)?;
+        Ok(true)
+    }
+
+    /// 已保存的 Key（不解密）喵
+    pub fn list(&self) -> Result<BTreeMap<String, StoredKey>, String> {
+        self.read()
+    }
+
+    /// 解密所有 Key 喵（Gateway 启动时调用）
+    pub fn load(&self) -> Result<Vec<ApiKey>, String> {
+        self.read()?
+            .into_iter()
+            .map(|(name, stored)| {
+                let encrypted = stored.secret.strip_prefix(SECRET_PREFIX).unwrap_or(&stored.secret);
+                let secret = self
+                    .crypto
+                    .decrypt(encrypted)
+                    .map_err(|e| format!("Failed to decrypt gateway key {}: {}", name, e))?;
+                Ok(ApiKey {
+                    name,
+                    secret,
+                    rate_limit: stored.rate_limit,
+                })
+            })
+            .collect()
+    }
+
+    /// 用当前版本密钥重新加密旧版本的 Key 喵
+    ///
+    /// ## Returns
+    /// (重新加密数, 失败数)
+    pub fn reencrypt(&self) -> Result<(usize, usize), String> {
+        let mut entries = self.read()?;
+        let mut migrated = 0;
+        let mut failed = 0;
+        for (name, stored) in entries.iter_mut() {
+            let encrypted = stored.secret.strip_prefix(SECRET_PREFIX).unwrap_or(&stored.secret);
+            if self.crypto.is_current(encrypted) {
+                continue;
+            }
+            match self.crypto.decrypt(encrypted).and_then(|plain| encrypt_secret(&self.crypto, &plain)) {
+                Ok(resealed) => {
+                    stored.secret = resealed;
+                    migrated += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping undecryptable gateway key {}: {}", name, e);
+                    failed += 1;
+                }
+            }
+        }
+        if migrated > 0 {
+            self.write(&entries)?;
+        }
+        Ok((migrated, failed))
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, StoredKey>, String> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = std::fs::read_to_string(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    /// 原子写入（先写临时文件再改名，权限 0600）喵
+    fn write(&self, entries: &BTreeMap<String, StoredKey>) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| format!("{}: {}", tmp.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600));
+        }
+        std::fs::rename(&tmp, &self.path).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 🔒 SAFETY: 按 Key 名称的令牌桶限流器喵
+#[derive(Debug, Default)]
+pub struct KeyRateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl KeyRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取一个令牌喵（桶空时返回需要等待的时长）
+    pub fn acquire(&self, key: &str, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(limit.burst);
+        let per_sec = f64::from(limit.requests_per_minute) / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_store_and_token_bucket() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ApiKeyStore::open(dir.path(), dir.path()).unwrap();
+        let limit = RateLimit {
+            requests_per_minute: 60,
+            burst: 2,
+        };
+
+        let secret = store.create("ci", Some(limit)).unwrap();
+        assert!(secret.starts_with("nk_"));
+        assert!(store.create("ci", None).is_err());
+        assert!(store.create("bad name", None).is_err());
+        let raw = std::fs::read_to_string(store.path()).unwrap();
+        assert!(raw.contains("enc:k1:") && !raw.contains(&secret));
+
+        let keys = store.load().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].secret, secret);
+        assert!(!format!("{:?}", keys[0]).contains(&secret));
+
+        // 突发 2 次后需要等待 1 秒补充喵
+        let limiter = KeyRateLimiter::new();
+        let t0 = Instant::now();
+        assert!(limiter.acquire("ci", limit, t0).is_ok());
+        assert!(limiter.acquire("ci", limit, t0).is_ok());
+        let wait = limiter.acquire("ci", limit, t0).unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 1.0);
+        assert!(limiter.acquire("ci", limit, t0 + Duration::from_millis(1100)).is_ok());
+        assert!(limiter.acquire("other", limit, t0).is_ok());
+
+        assert!(store.revoke("ci").unwrap());
+        assert!(!store.revoke("ci").unwrap());
+        assert!(store.load().unwrap().is_empty());
+    }
+}
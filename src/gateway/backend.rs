@@ -0,0 +1,525 @@
+//! Gateway 对话后端 🧠
+//!
+//! @诺诺 的 `/v1/chat/completions` 执行层喵
+//!
+//! 挂载后端后，Chat 端点不再返回模拟响应，而是：
+//! 1. 调用 Provider 生成回复（`ModelProvider` 挂载 `FailoverChain` 时主模型临时不可用会切换到备用模型）
+//! 2. 回复中包含 `@tool(args)` 时通过 MCP 执行工具，把结果追加进对话后再次调用
+//! 3. 最终问答写入 Memory（可选）
+//!
+//! 挂载确认策略时，`destructiveHint` 的 MCP 工具需在 `auto_approve` 中才会执行
+//! （Gateway 无法交互确认），否则把拒绝原因交给模型喵
+//!
+//! 流式请求传入 `ToolProgress` 时，工具开始 / 结束都会更新当前正在执行的工具名，
+//! 供 SSE 端点发送 `tool_status` 心跳喵
+//!
+//! 挂载 Tracer 时记录调用树：`gateway.request` → `agent.request` → `provider.chat` / `tool.execute` → `mcp.request`，
+//! 工具 Span 同时链接到触发它的 Agent 请求和对应的 MCP 请求
+//!
+//! 挂载 `WebhookNotifier` 时，最终回复推送 `agent_response`，工具失败推送 `tool_failure` 事件喵
+//!
+//! 挂载 `SkillTool` 时，每次请求把当前技能列表追加到 system 消息，`@skill(...)` 在本地沙箱执行；
+//! 技能集合热重载后下一次请求立即生效喵
+//!
+//! 挂载 `Rbac` 时，通过 `complete_as` 发起的对话按调用者的角色检查每个工具（`Permission::Tool`），
+//! 角色不足的工具调用把拒绝原因交给模型喵
+//!
+//! 挂载 `AuditLog` 时，每次工具调用（含被拒绝的）以调用者身份写入审计日志喵
+//!
+//! 挂载 `ContextCompressor` 时，每次调用 Provider 前按 `[compression]` 压缩请求消息（不修改对话本身），
+//! 压缩统计写入遥测喵
+//!
+//! 挂载 `HookRegistry` 时，最终回复先经过回复钩子（如 `[post_process]` 后处理链，按渠道 / Agent 选择步骤），
+//! 再推送通知、写入记忆并返回给渠道喵
+//!
+//! 未挂载后端时保持原有的模拟响应喵
+
+use crate::auth::{Identity, Permission, Rbac};
+use crate::core::{HookRegistry, ReplyContext};
+use crate::core::traits::{Memory, MemoryItem, Message, Provider, Result as NekoResult};
+use crate::performance::compress::ContextCompressor;
+use crate::security::{AuditEvent, AuditKind, AuditLog, AuditStatus, ToolApproval};
+use crate::telemetry::{MetricsRecorder, Span, Tracer};
+use crate::tools::{
+    format_tool_error_for_llm, format_tool_result_for_llm, parse_tool_calls, McpClient, SkillTool, Tool, ToolError,
+};
+use super::webhook::{AgentEventKind, WebhookNotifier};
+use chrono::Utc;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// 单次请求最多执行的工具轮数
+const DEFAULT_MAX_TOOL_ROUNDS: usize = 5;
+
+/// 当前正在执行的工具（None = 没有工具在执行）喵
+pub type ToolProgress = tokio::sync::watch::Sender<Option<String>>;
+
+/// 🔒 SAFETY: 对话后端喵
+pub struct ChatBackend {
+    provider: Arc<dyn Provider>,
+    memory: Option<Arc<dyn Memory>>,
+    mcp: Option<Arc<McpClient>>,
+    tracer: Option<Arc<Tracer>>,
+    approval: Option<Arc<ToolApproval>>,
+    rbac: Option<Arc<Rbac>>,
+    audit: Option<Arc<AuditLog>>,
+    notifier: Option<WebhookNotifier>,
+    skills: Option<Arc<SkillTool>>,
+    compression: Option<Compression>,
+    reply_hooks: Option<ReplyHooks>,
+    system_prompt: Option<String>,
+    max_tool_rounds: usize,
+}
+
+/// 回复钩子及其所在的渠道 / Agent 喵
+struct ReplyHooks {
+    registry: HookRegistry,
+    channel: String,
+    agent: String,
+}
+
+/// 上下文压缩器与记录压缩统计的遥测喵
+struct Compression {
+    compressor: Mutex<ContextCompressor>,
+    recorder: Option<MetricsRecorder>,
+}
+
+impl std::fmt::Debug for ChatBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatBackend")
+            .field("provider", &self.provider.name())
+            .field("memory", &self.memory.is_some())
+            .field("mcp", &self.mcp.is_some())
+            .field("tracer", &self.tracer.is_some())
+            .field("approval", &self.approval)
+            .field("rbac", &self.rbac.is_some())
+            .field("audit", &self.audit.is_some())
+            .field("notifier", &self.notifier)
+            .field("skills", &self.skills.as_ref().map(|s| s.skills()))
+            .field("compression", &self.compression.is_some())
+            .field("reply_hooks", &self.reply_hooks.as_ref().map(|h| (&h.channel, &h.agent)))
+            .field("system_prompt", &self.system_prompt.is_some())
+            .field("max_tool_rounds", &self.max_tool_rounds)
+            .finish()
+    }
+}
+
+impl ChatBackend {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider,
+            memory: None,
+            mcp: None,
+            tracer: None,
+            approval: None,
+            rbac: None,
+            audit: None,
+            notifier: None,
+            skills: None,
+            compression: None,
+            reply_hooks: None,
+            system_prompt: None,
+            max_tool_rounds: DEFAULT_MAX_TOOL_ROUNDS,
+        }
+    }
+
+    /// 🔒 SAFETY: 问答结果写入记忆喵
+    pub fn with_memory(mut self, memory: Arc<dyn Memory>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// 🔒 SAFETY: 通过 MCP 执行回复中的工具调用喵（客户端需已 initialize + list_tools）
+    pub fn with_mcp(mut self, mcp: Arc<McpClient>) -> Self {
+        self.mcp = Some(mcp);
+        self
+    }
+
+    /// 🔒 SAFETY: 记录请求 / 工具 / MCP 调用链喵
+    pub fn with_tracer(mut self, tracer: Arc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// 挂载的 Tracer（Gateway 用来记录 `gateway.request`）喵
+    pub fn tracer(&self) -> Option<&Arc<Tracer>> {
+        self.tracer.as_ref()
+    }
+
+    /// 🔒 SAFETY: 危险工具执行前检查确认策略喵
+    pub fn with_approval(mut self, approval: Arc<ToolApproval>) -> Self {
+        self.approval = Some(approval);
+        self
+    }
+
+    /// 🔒 SAFETY: 按调用者的角色检查工具权限喵（只对 `complete_as` 生效）
+    pub fn with_rbac(mut self, rbac: Arc<Rbac>) -> Self {
+        self.rbac = Some(rbac);
+        self
+    }
+
+    /// 🔒 SAFETY: 工具调用写入审计日志喵（没有调用者身份时记为 `agent`）
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// 🔒 SAFETY: 回复与工具失败推送到出站 Webhook 喵
+    pub fn with_notifier(mut self, notifier: WebhookNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// 🔒 SAFETY: 注入技能提示词并在本地执行 `@skill` 调用喵
+    pub fn with_skills(mut self, skills: Arc<SkillTool>) -> Self {
+        self.skills = Some(skills);
+        self
+    }
+
+    /// 🔒 SAFETY: 调用 Provider 前压缩超过阈值的请求消息喵（`recorder` 记录压缩统计）
+    pub fn with_compression(mut self, compressor: ContextCompressor, recorder: Option<MetricsRecorder>) -> Self {
+        self.compression = Some(Compression {
+            compressor: Mutex::new(compressor),
+            recorder,
+        });
+        self
+    }
+
+    /// 最终回复经过回复钩子，`channel` / `agent` 决定后处理链的渠道与 Agent 步骤喵
+    pub fn with_reply_hooks(mut self, hooks: HookRegistry, channel: &str, agent: &str) -> Self {
+        self.reply_hooks = Some(ReplyHooks {
+            registry: hooks,
+            channel: channel.to_string(),
+            agent: agent.to_string(),
+        });
+        self
+    }
+
+    /// 🔒 SAFETY: Agent 人设喵（插在请求的消息之前，技能提示词追加在它后面）
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        self.system_prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// 🔒 SAFETY: 每次对话最多的工具调用轮数喵
+    pub fn with_max_tool_rounds(mut self, rounds: usize) -> Self {
+        self.max_tool_rounds = rounds;
+        self
+    }
+
+    /// 🔒 SAFETY: 执行一次完整对话（含工具循环）喵
+    ///
+    /// ## Returns
+    /// 最后一条助手回复
+    pub async fn complete(&self, messages: Vec<Message>) -> NekoResult<String> {
+        self.complete_with_progress(messages, None).await
+    }
+
+    /// 执行一次完整对话，同时报告工具执行进度喵
+    pub async fn complete_with_progress(
+        &self,
+        messages: Vec<Message>,
+        progress: Option<&ToolProgress>,
+    ) -> NekoResult<String> {
+        self.complete_traced(messages, progress, None).await
+    }
+
+    /// 🔒 SAFETY: 代表某个渠道用户执行一次完整对话喵（工具按该用户的角色检查）
+    pub async fn complete_as(&self, caller: &Identity, messages: Vec<Message>) -> NekoResult<String> {
+        self.complete_inner(messages, None, None, Some(caller)).await
+    }
+
+    /// 执行一次完整对话，`agent.request` 作为 `parent` 的子 Span 喵
+    pub async fn complete_traced(
+        &self,
+        messages: Vec<Message>,
+        progress: Option<&ToolProgress>,
+        parent: Option<&Span>,
+    ) -> NekoResult<String> {
+        self.complete_inner(messages, progress, parent, None).await
+    }
+
+    async fn complete_inner(
+        &self,
+        mut messages: Vec<Message>,
+        progress: Option<&ToolProgress>,
+        parent: Option<&Span>,
+        caller: Option<&Identity>,
+    ) -> NekoResult<String> {
+        let question = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+
+        let mut request_span = self.tracer.as_ref().and_then(|t| match parent {
+            Some(parent) => t.start_child(parent, "agent.request"),
+            None => t.start_span("agent.request"),
+        });
+        if let Some(span) = request_span.as_mut() {
+            span.set_attribute("provider".to_string(), self.provider.name().to_string());
+        }
+        if let Some(prompt) = &self.system_prompt {
+            messages.insert(0, Message::system(prompt.clone()));
+        }
+        if let Some(skills) = &self.skills {
+            inject_skills_prompt(&mut messages, &skills.skills().prompt());
+        }
+        let result = self.run(&mut messages, request_span.as_ref(), progress, caller).await;
+
+        if let (Some(tracer), Some(span)) = (&self.tracer, request_span) {
+            match &result {
+                Ok(_) => tracer.finish_span(span).await,
+                Err(e) => tracer.finish_span_with_error(span, &e.to_string()).await,
+            }
+        }
+        let mut reply = result?;
+        if let Some(hooks) = &self.reply_hooks {
+            let ctx = ReplyContext {
+                channel: &hooks.channel,
+                agent: &hooks.agent,
+            };
+            reply = hooks.registry.apply_reply(&ctx, reply);
+        }
+
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(
+                AgentEventKind::AgentResponse,
+                serde_json::json!({
+                    "provider": self.provider.name(),
+                    "question": question,
+                    "reply": reply,
+                }),
+            );
+        }
+
+        if let Some(memory) = &self.memory {
+            let item = MemoryItem {
+                id: uuid::Uuid::new_v4().to_string(),
+                content: format!("Q: {}\nA: {}", question, reply),
+                embedding: None,
+                metadata: Some(serde_json::json!({ "source": "gateway" })),
+                created_at: Utc::now(),
+            };
+            if let Err(e) = memory.save(item).await {
+                warn!("Failed to save gateway conversation to memory: {}", e);
+            }
+        }
+
+        Ok(reply)
+    }
+
+    /// Provider 调用与工具循环喵
+    async fn run(
+        &self,
+        messages: &mut Vec<Message>,
+        request_span: Option<&Span>,
+        progress: Option<&ToolProgress>,
+        caller: Option<&Identity>,
+    ) -> NekoResult<String> {
+        let mut reply = self.chat(messages, request_span).await?;
+        for _ in 0..self.max_tool_rounds {
+            if self.mcp.is_none() && self.skills.is_none() {
+                break;
+            }
+            let calls = parse_tool_calls(&reply);
+            if calls.is_empty() {
+                break;
+            }
+
+            messages.push(Message::assistant(reply.clone()));
+            for call in calls {
+                let event = AuditEvent::new(
+                    AuditKind::for_tool(&call.tool_name),
+                    &caller.map_or_else(|| "agent".to_string(), |c| c.to_string()),
+                    &call.tool_name,
+                )
+                .with_arguments(call.arguments.clone());
+                let (result_text, event) = match self.authorize(&call.tool_name, &call.arguments, caller).await {
+                    Ok(()) => {
+                        debug!("Gateway executing tool {}", call.tool_name);
+                        if let Some(progress) = progress {
+                            progress.send_replace(Some(call.tool_name.clone()));
+                        }
+                        let (text, failure) = self
+                            .dispatch_tool(&call.tool_name, call.arguments, request_span)
+                            .await;
+                        if let Some(progress) = progress {
+                            progress.send_replace(None);
+                        }
+                        let event = match failure {
+                            Some(error) => event.with_status(AuditStatus::Failed).with_detail(error),
+                            None => event,
+                        };
+                        (text, event)
+                    }
+                    Err(reason) => (
+                        format_tool_error_for_llm(&ToolError::NotApproved(reason.clone())),
+                        event.with_status(AuditStatus::Denied).with_detail(reason),
+                    ),
+                };
+                if let Some(audit) = &self.audit {
+                    audit.record_or_warn(&event);
+                }
+                messages.push(Message::user(format!(
+                    "Tool result for {}: {}",
+                    call.tool_name, result_text
+                )));
+            }
+            reply = self.chat(messages, request_span).await?;
+        }
+
+        Ok(reply)
+    }
+
+    /// 🔒 SAFETY: 调用 Provider，记录 `provider.chat` Span 喵
+    async fn chat(&self, messages: &[Message], request_span: Option<&Span>) -> NekoResult<String> {
+        let tracer = self.tracer.as_deref();
+        let span = tracer.zip(request_span).and_then(|(t, parent)| {
+            let mut span = t.start_child(parent, "provider.chat")?;
+            span.set_attribute("provider".to_string(), self.provider.name().to_string());
+            span.set_attribute("messages".to_string(), messages.len().to_string());
+            Some(span)
+        });
+        let result = match &self.compression {
+            Some(compression) => {
+                let mut compressed = messages.to_vec();
+                compression
+                    .compressor
+                    .lock()
+                    .unwrap()
+                    .compress_and_record(&mut compressed, compression.recorder.as_ref());
+                self.provider.chat(&compressed).await
+            }
+            None => self.provider.chat(messages).await,
+        };
+        if let (Some(tracer), Some(span)) = (tracer, span) {
+            match &result {
+                Ok(_) => tracer.finish_span(span).await,
+                Err(e) => tracer.finish_span_with_error(span, &e.to_string()).await,
+            }
+        }
+        result
+    }
+
+    /// 🔒 SAFETY: 检查调用者的角色与危险工具是否允许执行喵（未挂载 Rbac / 确认策略时全部放行）
+    async fn authorize(
+        &self,
+        name: &str,
+        arguments: &serde_json::Value,
+        caller: Option<&Identity>,
+    ) -> Result<(), String> {
+        if let (Some(rbac), Some(caller)) = (&self.rbac, caller) {
+            rbac.check(caller, Permission::Tool(name)).map_err(|e| e.to_string())?;
+        }
+        let Some(approval) = &self.approval else {
+            return Ok(());
+        };
+        let dangerous = match (self.local_skill(name), &self.mcp) {
+            (Some(skills), _) => skills.describe().dangerous,
+            (None, Some(mcp)) => mcp.describe_tool(name).await.is_some_and(|d| d.dangerous),
+            (None, None) => false,
+        };
+        approval.authorize(name, dangerous, arguments)
+    }
+
+    /// 名为 `skill` 且挂载了技能时在本地执行喵
+    fn local_skill(&self, name: &str) -> Option<&SkillTool> {
+        self.skills.as_deref().filter(|s| s.describe().name == name)
+    }
+
+    /// 本地技能或 MCP 工具喵（返回交给模型的文本与失败原因）
+    async fn dispatch_tool(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        request_span: Option<&Span>,
+    ) -> (String, Option<String>) {
+        if let Some(skills) = self.local_skill(name) {
+            let result = match skills.validate_input(&arguments) {
+                Ok(()) => skills.execute(arguments).await,
+                Err(e) => Err(e),
+            };
+            return match result {
+                Ok(result) => (format_tool_result_for_llm(&result, None), result.error.filter(|_| !result.success)),
+                Err(e) => {
+                    if let Some(notifier) = &self.notifier {
+                        notifier.notify(
+                            AgentEventKind::ToolFailure,
+                            serde_json::json!({ "tool": name, "error": e.to_string() }),
+                        );
+                    }
+                    (format_tool_error_for_llm(&e), Some(e.to_string()))
+                }
+            };
+        }
+        match &self.mcp {
+            Some(mcp) => self.call_tool(mcp, name, arguments, request_span).await,
+            None => {
+                let error = ToolError::NotFound(name.to_string());
+                (format_tool_error_for_llm(&error), Some(error.to_string()))
+            }
+        }
+    }
+
+    /// 🔒 SAFETY: 通过 MCP 执行单个工具，记录 tool.execute / mcp.request 两层 Span 喵
+    async fn call_tool(
+        &self,
+        mcp: &McpClient,
+        name: &str,
+        arguments: serde_json::Value,
+        request_span: Option<&Span>,
+    ) -> (String, Option<String>) {
+        let tracer = self.tracer.as_deref();
+        let mut tool_span = tracer.zip(request_span).and_then(|(t, parent)| {
+            let mut span = t.start_child(parent, "tool.execute")?;
+            span.set_attribute("tool.name".to_string(), name.to_string());
+            span.add_link(parent, "triggered_by");
+            Some(span)
+        });
+        let mcp_span = tracer.zip(tool_span.as_ref()).and_then(|(t, parent)| {
+            let mut span = t.start_child(parent, "mcp.request")?;
+            span.set_attribute("rpc.method".to_string(), "tools/call".to_string());
+            Some(span)
+        });
+        if let (Some(tool), Some(rpc)) = (tool_span.as_mut(), mcp_span.as_ref()) {
+            tool.add_link(rpc, "mcp_request");
+        }
+
+        let result = mcp.call_tool(name.to_string(), arguments).await;
+
+        if let Some(tracer) = tracer {
+            for span in [mcp_span, tool_span].into_iter().flatten() {
+                match &result {
+                    Ok(_) => tracer.finish_span(span).await,
+                    Err(e) => tracer.finish_span_with_error(span, &e.to_string()).await,
+                }
+            }
+        }
+        let failure = match &result {
+            Ok(result) if result.is_error == Some(true) => Some(mcp.format_tool_result(result)),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        if let (Some(notifier), Some(error)) = (&self.notifier, &failure) {
+            notifier.notify(AgentEventKind::ToolFailure, serde_json::json!({ "tool": name, "error": error }));
+        }
+        let text = match result {
+            Ok(result) => mcp.format_tool_result(&result),
+            Err(e) => format!("Tool failed: {}", e),
+        };
+        (text, failure)
+    }
+}
+
+/// 把技能提示词追加到第一条 system 消息（没有时插入一条）喵
+fn inject_skills_prompt(messages: &mut Vec<Message>, prompt: &str) {
+    if prompt.is_empty() {
+        return;
+    }
+    match messages.iter_mut().find(|m| m.role == "system") {
+        Some(system) => system.content.push_str(prompt),
+        None => messages.insert(0, Message::system(prompt.to_string())),
+    }
+}
@@ -0,0 +1,80 @@
+//! 实时 Dashboard 端点 📊
+//!
+//! @缪斯 的 Gateway Dashboard 喵
+//!
+//! - `GET /dashboard`：渲染 `DashboardGenerator` 的 HTML，内嵌自动刷新脚本
+//! - `GET /dashboard/api/metrics`：指标的 JSON 快照，脚本每隔几秒轮询，数据变化时原地替换页面
+//!
+//! 与 `/metrics` 一样是只读的公开端点（Gateway 默认只监听 127.0.0.1），未启用遥测时返回 503
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{Html, Json},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use super::server::GatewayState;
+use crate::telemetry::{DashboardGenerator, MetricsRecorder, Span};
+
+/// Dashboard 页面路径
+pub const DASHBOARD_PATH: &str = "/dashboard";
+
+/// Dashboard JSON 快照路径
+pub const DASHBOARD_API_PATH: &str = "/dashboard/api/metrics";
+
+/// 页面轮询快照的间隔（秒）
+const REFRESH_INTERVAL_SECS: u64 = 5;
+
+/// 参与调用链火焰图的最近 Span 数
+const SPAN_LIMIT: u32 = 200;
+
+fn generator() -> DashboardGenerator {
+    DashboardGenerator::new().with_auto_refresh(DASHBOARD_PATH, DASHBOARD_API_PATH, REFRESH_INTERVAL_SECS)
+}
+
+fn recorder(state: &GatewayState) -> Result<&MetricsRecorder, (StatusCode, String)> {
+    state
+        .telemetry
+        .as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Telemetry is not enabled".to_string()))
+}
+
+/// 最近的 Span 喵（未挂载 Tracer 时为空）
+async fn recent_spans(state: &GatewayState) -> Vec<Span> {
+    match &state.tracer {
+        Some(tracer) => tracer.get_recent_spans(SPAN_LIMIT).await,
+        None => Vec::new(),
+    }
+}
+
+/// 🔒 SAFETY: Dashboard 页面喵
+pub async fn dashboard(State(state): State<Arc<GatewayState>>) -> Result<Html<String>, (StatusCode, String)> {
+    let recorder = recorder(&state)?;
+    let spans = recent_spans(&state).await;
+    generator()
+        .generate_html(recorder.collector(), &spans)
+        .map(Html)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// 🔒 SAFETY: Dashboard 指标快照喵
+pub async fn dashboard_metrics(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let recorder = recorder(&state)?;
+    let spans = recent_spans(&state).await;
+    generator()
+        .snapshot(recorder.collector(), &spans)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// 🔒 SAFETY: 创建 Dashboard 路由喵
+pub fn create_dashboard_routes() -> Router<Arc<GatewayState>> {
+    Router::new()
+        .route(DASHBOARD_PATH, get(dashboard))
+        .route(DASHBOARD_API_PATH, get(dashboard_metrics))
+}
@@ -0,0 +1,711 @@
+//! Gateway 设备配对 📱
+//!
+//! @诺诺 的设备码配对流程喵
+//!
+//! 还没有 API Key 的设备（手机、局域网里的脚本）通过设备码向主人申请：
+//!
+//! 1. 设备调用 `POST /pairing/device {"device_name": "phone"}`，拿到 6 位 `user_code` 与保密的 `device_code`
+//! 2. 设备把 `user_code` 展示给主人，主人执行 `nekoclaw pair approve <user_code>`
+//!    （或在 Telegram 发送 `/pair approve <user_code>`，需配置 `owner_user_ids`）
+//! 3. 设备每 `interval` 秒轮询 `POST /pairing/token {"device_code": "..."}`，批准后拿到只返回一次的 API Key
+//!
+//! - 申请保存在 `<工作区>/pairing_requests.json`，Gateway、守护进程与 CLI 共享；`device_code` 只保存 SHA-256
+//! - 设备 Key 以 `device-<名称>` 加密保存在凭证存储（`CredentialStore`），有效期 365 天
+//! - 设备 Key 只有 `chat` 作用域：可以访问需认证的端点，但不能访问 `/admin/*`
+//! - 撤销（`nekoclaw pair revoke <名称>`）删除凭证文件，运行中的 Gateway 下次认证时即拒绝该 Key
+//! - `/pairing/device` 按来源 IP 限流，每个来源同时最多 2 个等待审批的申请
+//!
+//! ```text
+//! nekoclaw pair list
+//! nekoclaw pair approve 482913
+//! nekoclaw pair revoke phone
+//! ```
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use super::api_keys::RateLimit;
+use super::server::{locked_out, GatewayState};
+use super::throttle::AuthKind;
+use crate::auth::{AuthError, CredentialStore, TokenInfo};
+use crate::security::crypto::{constant_time_eq, lock_sidecar, write_private};
+use crate::security::{AuditEvent, AuditKind};
+
+/// 配对申请存储文件名喵
+pub const PAIRING_REQUESTS_FILE: &str = "pairing_requests.json";
+
+/// 设备码有效期（秒）
+pub const DEVICE_CODE_TTL_SECS: i64 = 600;
+
+/// 设备轮询 `/pairing/token` 的最短间隔（秒）
+pub const POLL_INTERVAL_SECS: u64 = 5;
+
+/// 同时等待审批的申请上限（防止公开端点被刷）
+const MAX_PENDING_REQUESTS: usize = 16;
+
+/// 每个来源地址同时等待审批的申请上限
+const MAX_PENDING_PER_SOURCE: usize = 2;
+
+/// 每个来源 IP 调用 `/pairing/device` 的令牌桶限流
+const START_RATE_LIMIT: RateLimit = RateLimit {
+    requests_per_minute: 2,
+    burst: 3,
+};
+
+/// 设备 Key 有效期（天）
+const DEVICE_KEY_TTL_DAYS: i64 = 365;
+
+/// 设备 Key 前缀（与 `gateway keys` 的 `nk_` 区分）
+const DEVICE_KEY_PREFIX: &str = "nkd_";
+
+/// 设备 Key 在凭证存储中的名称前缀
+const CREDENTIAL_PREFIX: &str = "device-";
+
+/// 设备 Key 的默认作用域：需认证的端点（`/admin/*` 除外）
+pub const SCOPE_CHAT: &str = "chat";
+
+/// 允许访问 `/admin/*` 的作用域
+pub const SCOPE_ADMIN: &str = "admin";
+
+/// 申请状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceCodeStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+/// 🔒 SAFETY: 保存在文件中的配对申请喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    /// 展示给主人的 6 位配对码
+    pub user_code: String,
+    /// `device_code` 的 SHA-256（明文只返回给设备）
+    pub device_code_hash: String,
+    pub device_name: String,
+    /// 发起申请的来源地址
+    #[serde(default)]
+    pub requested_from: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub status: DeviceCodeStatus,
+    #[serde(default)]
+    pub decided_by: Option<String>,
+}
+
+/// 🔒 SAFETY: 返回给设备的设备码喵（`device_code` 只返回这一次）
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCodeGrant {
+    pub user_code: String,
+    pub device_code: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+/// 设备轮询的结果喵
+#[derive(Debug, Clone, PartialEq)]
+pub enum PollOutcome {
+    /// 主人还没处理
+    Pending,
+    /// 已批准（附设备名称），申请随之删除
+    Approved(String),
+    Denied,
+    Expired,
+    /// 不存在的 `device_code`（或 Key 已经领取过）
+    Invalid,
+}
+
+fn hash_device_code(device_code: &str) -> String {
+    Sha256::digest(device_code.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 设备名称只允许字母、数字、- 和 _（用作凭证文件名）喵
+fn validate_device_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.len() > 32
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid device name {:?} (1-32 letters, digits, - and _)", name));
+    }
+    Ok(())
+}
+
+/// 🔒 SAFETY: 文件存储的配对申请喵（Gateway 发起，CLI / Telegram 审批）
+///
+/// Gateway、CLI `pair approve` 与 Telegram Bot 是不同进程，
+/// 每次读改写都持有 `<申请文件>.lock` 上的 flock；进程内再用互斥锁串行化喵
+pub struct DeviceCodes {
+    path: PathBuf,
+    /// 同一进程内的读改写串行化
+    lock: Mutex<()>,
+}
+
+/// 读改写期间持有的锁喵（drop 时释放进程内锁与 flock）
+struct StoreGuard<'a> {
+    _local: MutexGuard<'a, ()>,
+    _file: File,
+}
+
+impl fmt::Debug for DeviceCodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceCodes").field("path", &self.path).finish()
+    }
+}
+
+impl DeviceCodes {
+    /// 打开 `<store_dir>/pairing_requests.json` 喵
+    pub fn open(store_dir: &Path) -> Self {
+        Self {
+            path: store_dir.join(PAIRING_REQUESTS_FILE),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 🔒 SAFETY: 发起配对申请喵（顺便清理过期申请，总数或同一来源等待审批的申请过多时拒绝）
+    pub fn start(&self, device_name: &str, requested_from: Option<String>) -> Result<DeviceCodeGrant, String> {
+        validate_device_name(device_name)?;
+        let _guard = self.lock_store()?;
+        let now = Utc::now();
+        let mut entries = self.read()?;
+        entries.retain(|_, entry| entry.expires_at > now);
+        let pending: Vec<_> = entries.values().filter(|e| e.status == DeviceCodeStatus::Pending).collect();
+        if pending.len() >= MAX_PENDING_REQUESTS {
+            return Err("Too many pending pairing requests, try again later".to_string());
+        }
+        if requested_from.is_some()
+            && pending.iter().filter(|e| e.requested_from == requested_from).count() >= MAX_PENDING_PER_SOURCE
+        {
+            return Err("Too many pending pairing requests from this address, try again later".to_string());
+        }
+
+        let mut rng = OsRng;
+        let user_code = loop {
+            let code = format!("{:06}", rng.gen_range(0..1_000_000));
+            if !entries.contains_key(&code) {
+                break code;
+            }
+        };
+        let device_code = random_hex(32);
+        entries.insert(
+            user_code.clone(),
+            DeviceAuthorization {
+                user_code: user_code.clone(),
+                device_code_hash: hash_device_code(&device_code),
+                device_name: device_name.to_string(),
+                requested_from,
+                created_at: now,
+                expires_at: now + chrono::Duration::seconds(DEVICE_CODE_TTL_SECS),
+                status: DeviceCodeStatus::Pending,
+                decided_by: None,
+            },
+        );
+        self.write(&entries)?;
+        info!("📱 设备 {} 申请配对（配对码 {}）", device_name, user_code);
+
+        Ok(DeviceCodeGrant {
+            user_code,
+            device_code,
+            expires_in: DEVICE_CODE_TTL_SECS as u64,
+            interval: POLL_INTERVAL_SECS,
+        })
+    }
+
+    /// 删除过期的申请喵（返回删除数量）
+    pub fn purge_expired(&self) -> Result<usize, String> {
+        let _guard = self.lock_store()?;
+        let now = Utc::now();
+        let mut entries = self.read()?;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.expires_at > now);
+        let removed = before - entries.len();
+        if removed > 0 {
+            self.write(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    /// 未过期、等待审批的申请喵
+    pub fn pending(&self) -> Result<Vec<DeviceAuthorization>, String> {
+        let now = Utc::now();
+        Ok(self
+            .read()?
+            .into_values()
+            .filter(|e| e.status == DeviceCodeStatus::Pending && e.expires_at > now)
+            .collect())
+    }
+
+    /// 🔒 SAFETY: 批准配对喵（设备下次轮询时领取 Key）
+    pub fn approve(&self, user_code: &str, decided_by: &str) -> Result<DeviceAuthorization, String> {
+        self.decide(user_code, decided_by, DeviceCodeStatus::Approved)
+    }
+
+    /// 🔒 SAFETY: 拒绝配对喵
+    pub fn deny(&self, user_code: &str, decided_by: &str) -> Result<DeviceAuthorization, String> {
+        self.decide(user_code, decided_by, DeviceCodeStatus::Denied)
+    }
+
+    fn decide(&self, user_code: &str, decided_by: &str, status: DeviceCodeStatus) -> Result<DeviceAuthorization, String> {
+        let _guard = self.lock_store()?;
+        let mut entries = self.read()?;
+        let entry = entries
+            .get_mut(user_code.trim())
+            .filter(|e| e.expires_at > Utc::now())
+            .ok_or_else(|| format!("No pending pairing request with code {}", user_code.trim()))?;
+        if entry.status != DeviceCodeStatus::Pending {
+            return Err(format!("Pairing request {} is already {:?}", entry.user_code, entry.status));
+        }
+        entry.status = status;
+        entry.decided_by = Some(decided_by.to_string());
+        let decided = entry.clone();
+        self.write(&entries)?;
+        info!("📱 设备 {} 的配对申请 {:?}（{}）", decided.device_name, status, decided_by);
+        Ok(decided)
+    }
+
+    /// 🔒 SAFETY: 设备轮询喵（批准后删除申请，Key 只能领取一次）
+    pub fn poll(&self, device_code: &str) -> Result<PollOutcome, String> {
+        let _guard = self.lock_store()?;
+        let hash = hash_device_code(device_code);
+        let mut entries = self.read()?;
+        let Some((user_code, entry)) = entries.iter().find(|(_, e)| e.device_code_hash == hash) else {
+            return Ok(PollOutcome::Invalid);
+        };
+        if entry.expires_at <= Utc::now() {
+            return Ok(PollOutcome::Expired);
+        }
+        let outcome = match entry.status {
+            DeviceCodeStatus::Pending => return Ok(PollOutcome::Pending),
+            DeviceCodeStatus::Denied => return Ok(PollOutcome::Denied),
+            DeviceCodeStatus::Approved => PollOutcome::Approved(entry.device_name.clone()),
+        };
+        let user_code = user_code.clone();
+        entries.remove(&user_code);
+        self.write(&entries)?;
+        Ok(outcome)
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, DeviceAuthorization>, String> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = std::fs::read_to_string(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    /// 🔒 SAFETY: 加锁喵（先进程内互斥锁，再阻塞等待 `lock_sidecar` 的排他 flock）
+    fn lock_store(&self) -> Result<StoreGuard<'_>, String> {
+        let local = self.lock.lock().unwrap();
+        let file = lock_sidecar(&self.path).map_err(|e| format!("{}.lock: {}", self.path.display(), e))?;
+        Ok(StoreGuard { _local: local, _file: file })
+    }
+
+    /// 原子写入喵（`write_private`：临时文件 0600，落盘后改名）
+    fn write(&self, entries: &BTreeMap<String, DeviceAuthorization>) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+        write_private(&self.path, json.as_bytes()).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+/// 🔒 SAFETY: 已配对设备的 Key 喵（Debug 输出不包含明文）
+#[derive(Clone)]
+pub struct DeviceKey {
+    pub name: String,
+    pub secret: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl fmt::Debug for DeviceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceKey")
+            .field("name", &self.name)
+            .field("secret", &"<redacted>")
+            .field("scopes", &self.scopes)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl DeviceKey {
+    /// 🔒 SAFETY: 作用域是否允许访问该路径喵
+    pub fn allows(&self, path: &str) -> bool {
+        let scope = match path.starts_with("/admin/") {
+            true => SCOPE_ADMIN,
+            false => SCOPE_CHAT,
+        };
+        self.scopes.iter().any(|s| s == scope || s == SCOPE_ADMIN)
+    }
+
+    fn from_token(name: &str, token: TokenInfo) -> Self {
+        Self {
+            name: name.to_string(),
+            secret: token.access_token,
+            scopes: token.scopes,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 凭证存储中的设备 Key 喵
+#[derive(Clone)]
+pub struct DeviceKeys {
+    store: CredentialStore,
+}
+
+impl DeviceKeys {
+    pub fn new(store: CredentialStore) -> Self {
+        Self { store }
+    }
+
+    /// 🔒 SAFETY: 为设备签发 Key 喵（同名设备重新配对时替换旧 Key）
+    pub async fn issue(&self, device_name: &str) -> Result<DeviceKey, AuthError> {
+        validate_device_name(device_name).map_err(AuthError::ConfigError)?;
+        let token = TokenInfo {
+            access_token: format!("{}{}", DEVICE_KEY_PREFIX, random_hex(24)),
+            refresh_token: None,
+            token_type: "device".to_string(),
+            expires_at: Utc::now() + chrono::Duration::days(DEVICE_KEY_TTL_DAYS),
+            scopes: vec![SCOPE_CHAT.to_string()],
+            user_id: Some(device_name.to_string()),
+        };
+        self.store.save(&format!("{}{}", CREDENTIAL_PREFIX, device_name), &token).await?;
+        Ok(DeviceKey::from_token(device_name, token))
+    }
+
+    /// 未过期的设备 Key 喵（Gateway 启动时调用）
+    pub async fn load(&self) -> Result<Vec<DeviceKey>, AuthError> {
+        let mut keys = Vec::new();
+        for key in self.store.keys()? {
+            let Some(name) = key.strip_prefix(CREDENTIAL_PREFIX) else {
+                continue;
+            };
+            match self.store.load(&key).await {
+                Some(token) if token.expires_at > Utc::now() => keys.push(DeviceKey::from_token(name, token)),
+                Some(_) => {}
+                None => warn!("Skipping unreadable device key {}", name),
+            }
+        }
+        Ok(keys)
+    }
+
+    /// 设备 Key 是否仍在凭证存储中喵（其他进程撤销后返回 false）
+    pub fn contains(&self, device_name: &str) -> bool {
+        self.store.contains(&format!("{}{}", CREDENTIAL_PREFIX, device_name))
+    }
+
+    /// 撤销设备 Key 喵（不存在时返回 false）
+    pub async fn revoke(&self, device_name: &str) -> Result<bool, AuthError> {
+        let key = format!("{}{}", CREDENTIAL_PREFIX, device_name);
+        if !self.store.keys()?.contains(&key) {
+            return Ok(false);
+        }
+        self.store.delete(&key).await?;
+        Ok(true)
+    }
+}
+
+/// 🔒 SAFETY: Gateway 的设备配对服务喵（申请 + 签发 + 已配对 Key 的认证）
+pub struct DevicePairing {
+    codes: DeviceCodes,
+    keys: DeviceKeys,
+    active: RwLock<Vec<DeviceKey>>,
+}
+
+impl fmt::Debug for DevicePairing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DevicePairing")
+            .field("codes", &self.codes)
+            .field("paired", &self.paired())
+            .finish()
+    }
+}
+
+impl DevicePairing {
+    /// 加载已配对设备的 Key 喵
+    pub async fn open(codes: DeviceCodes, keys: DeviceKeys) -> Result<Self, AuthError> {
+        let active = keys.load().await?;
+        Ok(Self {
+            codes,
+            keys,
+            active: RwLock::new(active),
+        })
+    }
+
+    /// 已配对的设备数喵
+    pub fn paired(&self) -> usize {
+        self.active.read().unwrap().len()
+    }
+
+    /// 🔒 SAFETY: 按 Key 明文查找已配对设备喵
+    ///
+    /// 常量时间比较；过期的 Key 不通过，凭证文件已被删除（`pair revoke`）的 Key 立即移出内存
+    pub fn find(&self, secret: &str) -> Option<DeviceKey> {
+        let key = self
+            .active
+            .read()
+            .unwrap()
+            .iter()
+            .find(|k| constant_time_eq(k.secret.as_bytes(), secret.as_bytes()))
+            .cloned()?;
+        if key.expires_at <= Utc::now() {
+            return None;
+        }
+        if !self.keys.contains(&key.name) {
+            self.active.write().unwrap().retain(|k| k.name != key.name);
+            info!("📱 设备 {} 的 Key 已被撤销", key.name);
+            return None;
+        }
+        Some(key)
+    }
+
+    /// 🔒 SAFETY: 用 `device_code` 换取 Key 喵（批准后第一次轮询时签发）
+    pub async fn exchange(&self, device_code: &str) -> Result<Result<DeviceKey, PollOutcome>, String> {
+        let device_name = match self.codes.poll(device_code)? {
+            PollOutcome::Approved(name) => name,
+            other => return Ok(Err(other)),
+        };
+        let key = self.keys.issue(&device_name).await.map_err(|e| e.to_string())?;
+        let mut active = self.active.write().unwrap();
+        active.retain(|k| k.name != device_name);
+        active.push(key.clone());
+        info!("📱 设备 {} 配对完成", device_name);
+        Ok(Ok(key))
+    }
+}
+
+/// 🔒 SAFETY: 设备配对申请喵
+#[derive(Debug, Deserialize)]
+pub struct DeviceCodeRequest {
+    pub device_name: String,
+}
+
+/// 🔒 SAFETY: 设备领取 Key 的请求喵
+#[derive(Debug, Deserialize)]
+pub struct DeviceTokenRequest {
+    pub device_code: String,
+}
+
+/// 🔒 SAFETY: 配对完成后返回给设备的 Key 喵
+#[derive(Debug, Serialize)]
+pub struct DeviceTokenResponse {
+    pub api_key: String,
+    pub token_type: String,
+    pub device_name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// OAuth 设备码流程风格的错误响应喵（`{"error": "authorization_pending"}`）
+fn device_error(status: StatusCode, error: &str, description: &str) -> Response {
+    (
+        status,
+        Json(serde_json::json!({ "error": error, "error_description": description })),
+    )
+        .into_response()
+}
+
+fn pairing_service(state: &GatewayState) -> Result<&DevicePairing, (StatusCode, &'static str)> {
+    state
+        .device_pairing
+        .as_deref()
+        .filter(|_| state.config.pairing_enabled)
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Device pairing is not enabled"))
+}
+
+/// 🔒 SAFETY: 设备发起配对喵（公开端点，锁定中的来源 IP 直接返回 429，同一来源按令牌桶限流）
+pub async fn start_device_pairing(
+    State(state): State<Arc<GatewayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<DeviceCodeRequest>,
+) -> Result<Json<DeviceCodeGrant>, Response> {
+    let pairing = pairing_service(&state).map_err(IntoResponse::into_response)?;
+    let now = Instant::now();
+    if let Some(remaining) = state.throttle.check(addr.ip(), now) {
+        return Err(locked_out(remaining));
+    }
+    if let Err(wait) = state.key_limiter.acquire(&format!("pairing:{}", addr.ip()), START_RATE_LIMIT, now) {
+        let mut response = device_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "slow_down",
+            "Too many pairing requests from this address",
+        );
+        let secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+        response.headers_mut().insert(RETRY_AFTER, secs.into());
+        return Err(response);
+    }
+    pairing
+        .codes
+        .start(req.device_name.trim(), Some(addr.ip().to_string()))
+        .map(Json)
+        .map_err(|e| match e.starts_with("Too many") {
+            true => device_error(StatusCode::TOO_MANY_REQUESTS, "slow_down", &e),
+            false => device_error(StatusCode::BAD_REQUEST, "invalid_request", &e),
+        })
+}
+
+/// 🔒 SAFETY: 设备轮询领取 Key 喵（无效的 `device_code` 计入来源 IP 的失败次数）
+pub async fn device_token(
+    State(state): State<Arc<GatewayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<DeviceTokenRequest>,
+) -> Result<Json<DeviceTokenResponse>, Response> {
+    let pairing = pairing_service(&state).map_err(IntoResponse::into_response)?;
+    if let Some(remaining) = state.throttle.check(addr.ip(), Instant::now()) {
+        return Err(locked_out(remaining));
+    }
+    let outcome = pairing
+        .exchange(req.device_code.trim())
+        .await
+        .map_err(|e| device_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e))?;
+    match outcome {
+        Ok(key) => {
+            if let Some(audit) = &state.audit {
+                audit.record_or_warn(
+                    &AuditEvent::new(AuditKind::Auth, &format!("device:{}", key.name), "pairing.device_token")
+                        .with_arguments(serde_json::json!({ "source": addr.ip().to_string(), "scopes": key.scopes })),
+                );
+            }
+            Ok(Json(DeviceTokenResponse {
+                api_key: key.secret,
+                token_type: "bearer".to_string(),
+                device_name: key.name,
+                scopes: key.scopes,
+                expires_at: key.expires_at,
+            }))
+        }
+        Err(PollOutcome::Pending) => Err(device_error(
+            StatusCode::BAD_REQUEST,
+            "authorization_pending",
+            "Waiting for the owner to approve the pairing code",
+        )),
+        Err(PollOutcome::Denied) => Err(device_error(
+            StatusCode::FORBIDDEN,
+            "access_denied",
+            "The owner denied this pairing request",
+        )),
+        Err(PollOutcome::Expired) => Err(device_error(
+            StatusCode::BAD_REQUEST,
+            "expired_token",
+            "The pairing code has expired, start over",
+        )),
+        Err(_) => {
+            if let Some(lockout) = state.throttle.record_failure(addr.ip(), AuthKind::Pairing, Instant::now()) {
+                return Err(locked_out(lockout));
+            }
+            Err(device_error(StatusCode::BAD_REQUEST, "invalid_grant", "Unknown device code"))
+        }
+    }
+}
+
+/// 🔒 SAFETY: 创建设备配对路由喵（公开端点）
+pub fn create_device_pairing_routes() -> Router<Arc<GatewayState>> {
+    Router::new()
+        .route("/pairing/device", post(start_device_pairing))
+        .route("/pairing/token", post(device_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::master_crypto;
+
+    #[tokio::test]
+    async fn test_device_code_flow_issues_scoped_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialStore::new(dir.path().join("credentials"), master_crypto(dir.path()).unwrap()).unwrap();
+        let pairing = DevicePairing::open(DeviceCodes::open(dir.path()), DeviceKeys::new(store.clone()))
+            .await
+            .unwrap();
+        assert!(pairing.codes.start("../etc", None).is_err());
+
+        let grant = pairing.codes.start("phone", Some("10.0.0.2".to_string())).unwrap();
+        assert_eq!(grant.user_code.len(), 6);
+        let requests = std::fs::read_to_string(dir.path().join(PAIRING_REQUESTS_FILE)).unwrap();
+        assert!(!requests.contains(&grant.device_code));
+        assert_eq!(pairing.exchange(&grant.device_code).await.unwrap().unwrap_err(), PollOutcome::Pending);
+
+        // CLI 进程打开同一个文件审批喵
+        let cli = DeviceCodes::open(dir.path());
+        assert_eq!(cli.pending().unwrap().len(), 1);
+        cli.approve(&grant.user_code, "cli:test").unwrap();
+        assert!(cli.deny(&grant.user_code, "cli:test").is_err());
+
+        let key = pairing.exchange(&grant.device_code).await.unwrap().unwrap();
+        assert!(key.secret.starts_with("nkd_"));
+        assert!(!format!("{:?}", key).contains(&key.secret));
+        assert_eq!(pairing.find(&key.secret).unwrap().name, "phone");
+        assert!(key.allows("/status") && !key.allows("/admin/log-level"));
+        // Key 只能领取一次喵
+        assert_eq!(pairing.exchange(&grant.device_code).await.unwrap().unwrap_err(), PollOutcome::Invalid);
+
+        let denied = pairing.codes.start("tablet", None).unwrap();
+        cli.deny(&denied.user_code, "cli:test").unwrap();
+        assert_eq!(pairing.exchange(&denied.device_code).await.unwrap().unwrap_err(), PollOutcome::Denied);
+
+        // 同一来源最多 2 个等待审批的申请喵
+        let source = || Some("10.0.0.9".to_string());
+        pairing.codes.start("bot-a", source()).unwrap();
+        pairing.codes.start("bot-b", source()).unwrap();
+        assert!(pairing.codes.start("bot-c", source()).unwrap_err().starts_with("Too many"));
+        pairing.codes.start("bot-c", Some("10.0.0.10".to_string())).unwrap();
+
+        // 重启后从凭证存储加载，撤销后不再加载喵
+        let keys = DeviceKeys::new(store);
+        assert_eq!(keys.load().await.unwrap().len(), 1);
+        assert!(keys.revoke("phone").await.unwrap());
+        assert!(!keys.revoke("phone").await.unwrap());
+        assert!(keys.load().await.unwrap().is_empty());
+        // 运行中的 Gateway 立即拒绝被撤销的 Key 喵
+        assert!(pairing.find(&key.secret).is_none());
+        assert_eq!(pairing.paired(), 0);
+    }
+
+    #[test]
+    fn test_device_codes_serialize_across_handles() {
+        let dir = tempfile::tempdir().unwrap();
+        // 每个线程各自打开存储，模拟 Gateway / CLI / Telegram 三个进程（不共享进程内互斥锁）喵
+        let names: Vec<String> = (0..MAX_PENDING_REQUESTS).map(|i| format!("dev-{}", i)).collect();
+        std::thread::scope(|scope| {
+            for name in &names {
+                let dir = dir.path();
+                scope.spawn(move || DeviceCodes::open(dir).start(name, None).unwrap());
+            }
+        });
+
+        let codes = DeviceCodes::open(dir.path());
+        assert_eq!(codes.pending().unwrap().len(), names.len());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(PAIRING_REQUESTS_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Discord Interactions 端点 🎮
+//!
+//! @诺诺 的 Discord 斜杠命令入口喵
+//!
+//! 在 Developer Portal 把 Interactions Endpoint URL 设置为 `https://<gateway>/discord/interactions`，
+//! 斜杠命令就会以 HTTP 请求送到这里，交给 `DiscordInteractions` 处理喵
+//!
+//! 🔒 SAFETY: 以 Ed25519 签名代替 Bearer Token 认证（Discord 要求签名错误返回 401），
+//! 签名错误计入来源 IP 的失败次数；长时间的命令先返回延迟响应，完成后编辑原消息
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::warn;
+
+use super::server::{locked_out, GatewayState};
+use super::throttle::AuthKind;
+use crate::channels::discord::Interaction;
+
+/// 🔒 SAFETY: Discord interaction 端点喵
+pub async fn discord_interaction(
+    State(state): State<Arc<GatewayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(remaining) = state.throttle.check(addr.ip(), Instant::now()) {
+        return locked_out(remaining);
+    }
+    let Some(interactions) = state.discord.as_ref() else {
+        return (StatusCode::NOT_FOUND, "Discord interactions are not configured").into_response();
+    };
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let signature = header("X-Signature-Ed25519");
+    let timestamp = header("X-Signature-Timestamp");
+    if let Err(e) = interactions.verify(signature, timestamp, &body) {
+        warn!("Rejected Discord interaction from {}: {}", addr.ip(), e);
+        if let Some(lockout) = state.throttle.record_failure(addr.ip(), AuthKind::Webhook, Instant::now()) {
+            return locked_out(lockout);
+        }
+        return (StatusCode::UNAUTHORIZED, "Invalid request signature").into_response();
+    }
+
+    match serde_json::from_slice::<Interaction>(&body) {
+        Ok(interaction) => Json(interactions.handle(interaction).await).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, format!("Invalid interaction: {}", e)).into_response(),
+    }
+}
+
+/// 🔒 SAFETY: Discord interaction 路由喵
+pub fn create_discord_routes() -> Router<Arc<GatewayState>> {
+    Router::new().route("/discord/interactions", post(discord_interaction))
+}
@@ -0,0 +1,249 @@
+//! OpenAI 兼容 Embeddings 端点 🧮
+//!
+//! @诺诺 的向量化代理喵
+//!
+//! `POST /v1/embeddings` 把文本交给配置的 embedding provider（未配置 API Key 时为本地 hash 向量），
+//! 局域网内的其他工具可以把 nekoclaw 当作同时提供 chat 与 embeddings 的单一端点喵
+//!
+//! 请求体的 `model` 选择 `[embeddings.<name>]` 定义的模型（写名称或它的 `model` 均可）：
+//! - 省略 `model` 时使用默认模型（`[memory.embedding]`，默认为本地 hash 向量）
+//! - 不存在的模型返回 404，避免客户端悄悄拿到其他模型的向量
+//!
+//! ```toml
+//! [embeddings.small]
+//! provider = "openai"
+//! model = "text-embedding-3-small"
+//!
+//! [embeddings.local]
+//! provider = "hash"
+//! dimensions = 384
+//! ```
+//!
+//! `encoding_format: "base64"` 时返回小端 f32 数组的 base64（OpenAI SDK 的默认格式）
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use axum::{extract::State, http::StatusCode, Json};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::info;
+
+use super::server::GatewayState;
+use crate::memory::EmbeddingProvider;
+use crate::tools::estimate_tokens;
+
+/// 单次请求最多的输入条数（与 OpenAI 一致）
+pub const MAX_EMBEDDING_INPUTS: usize = 2048;
+
+/// 🔒 SAFETY: Embeddings 请求喵
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingRequest {
+    /// 单条文本或文本数组
+    pub input: EmbeddingInput,
+    /// 模型名称（省略时使用默认模型）
+    #[serde(default)]
+    pub model: Option<String>,
+    /// "float"（默认）或 "base64"
+    #[serde(default)]
+    pub encoding_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    Single(String),
+    Batch(Vec<String>),
+}
+
+impl EmbeddingInput {
+    fn into_texts(self) -> Vec<String> {
+        match self {
+            EmbeddingInput::Single(text) => vec![text],
+            EmbeddingInput::Batch(texts) => texts,
+        }
+    }
+}
+
+/// 🔒 SAFETY: Embeddings 响应喵
+#[derive(Debug, Serialize)]
+pub struct EmbeddingResponse {
+    pub object: String,
+    pub data: Vec<EmbeddingData>,
+    pub model: String,
+    pub usage: EmbeddingUsage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingData {
+    pub object: String,
+    pub index: usize,
+    /// 浮点数组，或 base64 字符串
+    pub embedding: JsonValue,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// 🔒 SAFETY: 可用的 embedding 模型喵（名称 → provider，另有一个默认模型）
+pub struct EmbeddingModels {
+    default: (String, Arc<dyn EmbeddingProvider>),
+    named: BTreeMap<String, (String, Arc<dyn EmbeddingProvider>)>,
+}
+
+impl std::fmt::Debug for EmbeddingModels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingModels")
+            .field("default", &self.default.0)
+            .field("named", &self.named.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl EmbeddingModels {
+    /// `model` 为响应中报告的模型名喵
+    pub fn new(model: &str, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            default: (model.to_string(), provider),
+            named: BTreeMap::new(),
+        }
+    }
+
+    /// 🔒 SAFETY: 添加一个命名模型喵（请求体 `model` 写名称或底层模型名都能选中）
+    pub fn with_model(mut self, name: &str, model: &str, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.named.insert(name.to_string(), (model.to_string(), provider));
+        self
+    }
+
+    /// 按请求的模型名查找喵
+    fn resolve(&self, model: Option<&str>) -> Option<&(String, Arc<dyn EmbeddingProvider>)> {
+        let Some(model) = model else {
+            return Some(&self.default);
+        };
+        self.named
+            .get(model)
+            .or_else(|| self.named.values().find(|(name, _)| name == model))
+            .or_else(|| (self.default.0 == model).then_some(&self.default))
+    }
+
+    /// 🔒 SAFETY: 向量化请求中的文本喵
+    pub async fn create(&self, req: EmbeddingRequest) -> Result<EmbeddingResponse, (StatusCode, String)> {
+        let base64 = match req.encoding_format.as_deref() {
+            None | Some("float") => false,
+            Some("base64") => true,
+            Some(other) => {
+                return Err((StatusCode::BAD_REQUEST, format!("Unsupported encoding_format: {}", other)));
+            }
+        };
+        let (model, provider) = self.resolve(req.model.as_deref()).ok_or_else(|| {
+            let model = req.model.clone().unwrap_or_default();
+            (StatusCode::NOT_FOUND, format!("Unknown embedding model: {}", model))
+        })?;
+        let texts = req.input.into_texts();
+        if texts.is_empty() || texts.iter().any(|t| t.is_empty()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Input must be a non-empty string or an array of non-empty strings".to_string(),
+            ));
+        }
+        if texts.len() > MAX_EMBEDDING_INPUTS {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Too many inputs: {} (max {})", texts.len(), MAX_EMBEDDING_INPUTS),
+            ));
+        }
+        info!("Embedding request: model={}, inputs={}", model, texts.len());
+
+        let vectors = provider
+            .embed(&texts)
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Embedding provider error: {}", e)))?;
+        let tokens: usize = texts.iter().map(|t| estimate_tokens(t)).sum();
+        let data = vectors
+            .into_iter()
+            .enumerate()
+            .map(|(index, vector)| EmbeddingData {
+                object: "embedding".to_string(),
+                index,
+                embedding: match base64 {
+                    true => JsonValue::String(encode_base64(&vector)),
+                    false => JsonValue::from(vector),
+                },
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            object: "list".to_string(),
+            data,
+            model: model.clone(),
+            usage: EmbeddingUsage {
+                prompt_tokens: tokens as u32,
+                total_tokens: tokens as u32,
+            },
+        })
+    }
+}
+
+/// 小端 f32 数组的 base64 喵（与 OpenAI 的 `encoding_format: "base64"` 相同）
+fn encode_base64(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// 🔒 SAFETY: Embeddings 端点喵（未挂载模型时返回 503）
+pub async fn embeddings(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<EmbeddingRequest>,
+) -> Result<Json<EmbeddingResponse>, (StatusCode, String)> {
+    let models = state
+        .embeddings
+        .as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Embeddings are not enabled".to_string()))?;
+    models.create(req).await.map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::HashEmbedding;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_embeddings_resolve_models_and_encodings() {
+        let models = EmbeddingModels::new("hash:64", Arc::new(HashEmbedding::new(64)))
+            .with_model("local", "hash:16", Arc::new(HashEmbedding::new(16)));
+        let request = |body: JsonValue| serde_json::from_value::<EmbeddingRequest>(body).unwrap();
+
+        let response = models.create(request(json!({ "input": ["喵喵", "nginx timeout"] }))).await.unwrap();
+        assert_eq!((response.model.as_str(), response.data.len()), ("hash:64", 2));
+        assert_eq!(response.data[1].index, 1);
+        assert_eq!(response.data[0].embedding.as_array().unwrap().len(), 64);
+        assert!(response.usage.total_tokens > 0);
+
+        // 名称与底层模型名都能选中命名模型喵
+        for model in ["local", "hash:16"] {
+            let response = models.create(request(json!({ "input": "cat", "model": model }))).await.unwrap();
+            assert_eq!(response.model, "hash:16");
+            assert_eq!(response.data[0].embedding.as_array().unwrap().len(), 16);
+        }
+
+        let response = models
+            .create(request(json!({ "input": "cat", "model": "local", "encoding_format": "base64" })))
+            .await
+            .unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(response.data[0].embedding.as_str().unwrap())
+            .unwrap();
+        let decoded: Vec<f32> = bytes.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(decoded, HashEmbedding::new(16).embed_text("cat"));
+
+        let error = |body| async { models.create(request(body)).await.unwrap_err().0 };
+        assert_eq!(error(json!({ "input": "cat", "model": "gpt-4" })).await, StatusCode::NOT_FOUND);
+        assert_eq!(error(json!({ "input": [] })).await, StatusCode::BAD_REQUEST);
+        assert_eq!(error(json!({ "input": "cat", "encoding_format": "int8" })).await, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,321 @@
+//! Idempotency-Key 支持 🔁
+//!
+//! @诺诺 的幂等请求缓存喵
+//!
+//! 客户端在网络抖动后带同一个 `Idempotency-Key` 重试时，直接返回首次的响应，
+//! 避免带副作用的工具调用对话被执行两次喵。
+//!
+//! - 只缓存成功（2xx）的响应，失败的请求可以重新执行
+//! - 同一个 key 的请求仍在处理中时返回 409
+//! - key 按 调用方身份 + 方法 + 路径 区分，不同 API Key / 设备之间、不同端点之间互不影响
+//! - 同一个 key 换了请求体时返回 422，不会把别的请求的响应重放出去
+//! - 流式（SSE）响应不缓存，直接透传以免心跳被缓冲
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::api_keys::ApiKeyName;
+use super::server::GatewayState;
+
+/// 请求头名称
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+/// 重放响应的标记头
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+/// key 最大长度
+const MAX_KEY_LENGTH: usize = 255;
+/// 最多缓存的响应数
+const MAX_ENTRIES: usize = 10_000;
+/// 可缓存的响应体上限（字节）
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// 🔒 SAFETY: 缓存的响应喵
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, self.body).into_response();
+        *response.headers_mut() = self.headers;
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// 缓存条目（附带首次请求体的哈希）
+#[derive(Debug)]
+enum Entry {
+    InFlight(String),
+    Done(CachedResponse, String, Instant),
+}
+
+impl Entry {
+    fn body_hash(&self) -> &str {
+        match self {
+            Entry::InFlight(hash) | Entry::Done(_, hash, _) => hash,
+        }
+    }
+
+    /// 缓存响应已过期（处理中的占位不会过期）喵
+    fn is_expired(&self, now: Instant) -> bool {
+        match self {
+            Entry::InFlight(_) => false,
+            Entry::Done(_, _, expires_at) => *expires_at <= now,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 查询结果喵
+#[derive(Debug)]
+pub enum Lookup {
+    /// 首次出现，继续执行
+    Proceed,
+    /// 已有缓存响应
+    Replay(CachedResponse),
+    /// 同 key 的请求仍在处理中
+    InFlight,
+    /// 同 key 但请求体不同
+    Mismatch,
+}
+
+/// 🔒 SAFETY: 幂等响应缓存喵
+#[derive(Debug)]
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 🔒 SAFETY: 开始处理某个 key 喵
+    /// `body_hash` 是请求体的哈希，同 key 不同请求体返回 Mismatch；
+    /// 返回 Proceed 时会占位，调用方必须随后 complete 或 abandon
+    pub fn begin(&self, key: &str, body_hash: &str) -> Lookup {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match entries.get(key) {
+            Some(entry) if entry.is_expired(now) => {}
+            Some(entry) if entry.body_hash() != body_hash => return Lookup::Mismatch,
+            Some(Entry::InFlight(_)) => return Lookup::InFlight,
+            Some(Entry::Done(cached, _, _)) => return Lookup::Replay(cached.clone()),
+            None => {}
+        }
+
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| !entry.is_expired(now));
+        }
+        entries.insert(key.to_string(), Entry::InFlight(body_hash.to_string()));
+        Lookup::Proceed
+    }
+
+    /// 🔒 SAFETY: 保存成功响应喵
+    pub fn complete(&self, key: &str, body_hash: &str, response: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(
+            key.to_string(),
+            Entry::Done(response, body_hash.to_string(), Instant::now() + self.ttl),
+        );
+    }
+
+    /// 🔒 SAFETY: 放弃占位（请求失败，允许重试）喵
+    pub fn abandon(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(entries.get(key), Some(Entry::InFlight(_))) {
+            entries.remove(key);
+        }
+    }
+}
+
+/// 🔒 SAFETY: 请求体的 SHA-256（十六进制）喵
+fn body_hash(body: &[u8]) -> String {
+    Sha256::digest(body).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 🔒 SAFETY: 占位守卫喵
+/// 处理被取消（客户端断开）或失败时释放占位，避免 key 永久卡在 409
+struct InFlightGuard<'a> {
+    cache: &'a IdempotencyCache,
+    key: &'a str,
+    completed: bool,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.cache.abandon(self.key);
+        }
+    }
+}
+
+/// 🔒 SAFETY: Idempotency-Key 中间件喵
+/// 没有该请求头或非 POST 请求时直接放行；
+/// 缓存 key 带上认证中间件写入的调用方身份（命名 API Key / 设备 Key，主 Bearer Token 为 `bearer`），
+/// 不同调用方用同一个 Idempotency-Key 也不会拿到别人的响应
+pub async fn idempotency_middleware(
+    State(state): State<Arc<GatewayState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(header) = request.headers().get(IDEMPOTENCY_HEADER) else {
+        return next.run(request).await;
+    };
+    let identity = request
+        .extensions()
+        .get::<ApiKeyName>()
+        .map(|ApiKeyName(name)| name.as_str())
+        .unwrap_or("bearer");
+    let key = match header.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => {
+            format!("{} {} {} {}", identity, request.method(), request.uri().path(), key)
+        }
+        _ => {
+            return (StatusCode::BAD_REQUEST, "Invalid Idempotency-Key header").into_response();
+        }
+    };
+
+    // 读出请求体计算哈希，再原样放回喵
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response(),
+    };
+    let hash = body_hash(&body);
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let cache = &state.idempotency;
+    match cache.begin(&key, &hash) {
+        Lookup::Replay(cached) => {
+            debug!("Replaying idempotent response for {}", key);
+            return cached.into_response();
+        }
+        Lookup::InFlight => {
+            return (
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still being processed",
+            )
+                .into_response();
+        }
+        Lookup::Mismatch => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request body",
+            )
+                .into_response();
+        }
+        Lookup::Proceed => {}
+    }
+
+    let mut guard = InFlightGuard {
+        cache,
+        key: &key,
+        completed: false,
+    };
+    let response = next.run(request).await;
+    if !response.status().is_success() || is_event_stream(&response) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => {
+            cache.complete(
+                &key,
+                &hash,
+                CachedResponse {
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: bytes.clone(),
+                },
+            );
+            guard.completed = true;
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => {
+            warn!("Failed to buffer response for idempotency cache: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response body").into_response()
+        }
+    }
+}
+
+/// SSE 响应喵
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_replay_and_in_flight() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::Proceed));
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::InFlight));
+
+        cache.complete("k1", "h1", cached("喵"));
+        match cache.begin("k1", "h1") {
+            Lookup::Replay(response) => assert_eq!(response.body, Bytes::from_static("喵".as_bytes())),
+            other => panic!("expected replay, got {:?}", other),
+        }
+        assert!(matches!(cache.begin("k2", "h1"), Lookup::Proceed));
+    }
+
+    #[test]
+    fn test_abandon_and_expiry() {
+        let cache = IdempotencyCache::new(Duration::ZERO);
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::Proceed));
+        cache.abandon("k1");
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::Proceed));
+
+        // TTL 为 0 时缓存立即过期，换了请求体也重新执行喵
+        cache.complete("k1", "h1", cached("old"));
+        assert!(matches!(cache.begin("k1", "h2"), Lookup::Proceed));
+    }
+
+    #[test]
+    fn test_body_mismatch() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::Proceed));
+        assert!(matches!(cache.begin("k1", "h2"), Lookup::Mismatch));
+
+        cache.complete("k1", "h1", cached("喵"));
+        assert!(matches!(cache.begin("k1", "h2"), Lookup::Mismatch));
+        assert!(matches!(cache.begin("k1", "h1"), Lookup::Replay(_)));
+    }
+}
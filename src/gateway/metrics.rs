@@ -4,14 +4,21 @@
 
 use axum::{
     extract::State,
-    response::{IntoResponse, Response},
+    response::{IntoResponse, Json, Response},
     http::{StatusCode, header},
     Router,
     routing::get,
 };
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use super::server::GatewayState;
+use crate::telemetry::{CustomMetric, Feedback, MetricKind, Rating};
+
+/// 单次上报最多指标数
+const MAX_INGEST_BATCH: usize = 100;
 
 /// 🔒 SAFETY: Prometheus 指标格式喵
 pub struct PrometheusMetrics {
@@ -21,6 +28,12 @@ pub struct PrometheusMetrics {
     pub uptime_seconds: u64,
 }
 
+impl Default for PrometheusMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PrometheusMetrics {
     pub fn new() -> Self {
         Self {
@@ -62,8 +75,6 @@ nekoclaw_uptime_seconds {}
 pub async fn metrics() -> Response {
     // TODO: 从 Telemetry 获取实际指标
     
-    let m = PrometheusMetrics::new();
-    
     // 获取内存使用
     let memory_mb = get_memory_usage_mb();
     let memory_bytes = (memory_mb * 1024.0 * 1024.0) as u64;
@@ -88,6 +99,113 @@ nekoclaw_info{{version="{}"}} 1
     ).into_response()
 }
 
+/// 🔒 SAFETY: 外部上报的单条指标喵
+#[derive(Debug, Deserialize)]
+pub struct IngestMetric {
+    pub name: String,
+    pub kind: MetricKind,
+    #[serde(default = "default_value")]
+    pub value: f64,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// 上报来源，默认 "http"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+fn default_value() -> f64 { 1.0 }
+
+/// 🔒 SAFETY: 指标上报请求喵
+#[derive(Debug, Deserialize)]
+pub struct IngestRequest {
+    pub metrics: Vec<IngestMetric>,
+}
+
+/// 🔒 SAFETY: 自定义指标上报端点喵
+/// 整批先校验，任意一条不合法则整批拒绝，避免部分写入
+pub async fn ingest_metrics(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<IngestRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let recorder = state
+        .telemetry
+        .as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Telemetry is not enabled".to_string()))?;
+    if req.metrics.len() > MAX_INGEST_BATCH {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("At most {} metrics per request", MAX_INGEST_BATCH),
+        ));
+    }
+
+    let now = Utc::now();
+    let metrics = req
+        .metrics
+        .into_iter()
+        .map(|m| {
+            let metric = CustomMetric {
+                value: if m.kind == MetricKind::Event { 1.0 } else { m.value },
+                name: m.name,
+                kind: m.kind,
+                labels: m.labels,
+                source: m.source.unwrap_or_else(|| "http".to_string()),
+                recorded_at: now,
+            };
+            metric.validate().map(|_| metric)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    for metric in &metrics {
+        recorder
+            .record_metric(metric)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    }
+    Ok(Json(serde_json::json!({ "accepted": metrics.len() })))
+}
+
+/// 🔒 SAFETY: 用户反馈请求喵
+#[derive(Debug, Deserialize)]
+pub struct FeedbackRequest {
+    /// Chat 响应中的 `id`（chatcmpl-...）
+    pub response_id: String,
+    /// up / down / +1 / -1 / 👍 / 👎
+    pub rating: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// 客户端侧的用户标识（可选）
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// 🔒 SAFETY: 用户反馈端点喵
+pub async fn submit_feedback(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<FeedbackRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let recorder = state
+        .telemetry
+        .as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Telemetry is not enabled".to_string()))?;
+    let rating = Rating::parse(&req.rating).ok_or((
+        StatusCode::BAD_REQUEST,
+        format!("Unknown rating '{}', expected up or down", req.rating),
+    ))?;
+
+    let mut feedback = Feedback::new(&req.response_id, rating, "gateway");
+    if let Some(user) = &req.user {
+        feedback = feedback.with_user(user);
+    }
+    if let Some(comment) = &req.comment {
+        feedback = feedback.with_comment(comment);
+    }
+    feedback.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    recorder
+        .record_feedback(&feedback)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(serde_json::json!({ "response_id": feedback.response_id, "rating": rating })))
+}
+
 /// 🔒 SAFETY: 获取内存使用喵
 fn get_memory_usage_mb() -> f64 {
     if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
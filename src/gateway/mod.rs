@@ -2,34 +2,45 @@
 //!
 //! @诺诺 的 Gateway 模块统一入口喵
 
-pub mod pairing;
+pub mod api_keys;
+pub mod backend;
+pub mod dashboard;
+pub mod device_pairing;
+pub mod discord;
+pub mod embeddings;
+pub mod idempotency;
 pub mod server;
+pub mod throttle;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod testing;
 pub mod webhook;
 pub mod openai;
 pub mod metrics;
 
 // 🔒 SAFETY: 重新导出公共接口喵
-pub use pairing::{PairingConfig, PairingManager, PairingRequest, PairingResponse, PairingStatus};
+pub use api_keys::{ApiKeyStore, RateLimit};
+pub use backend::ChatBackend;
+pub use device_pairing::{DeviceCodes, DeviceKeys, DevicePairing};
+pub use embeddings::EmbeddingModels;
 pub use server::{ErrorResponse, GatewayConfig, GatewayServer, GatewayState, HealthResponse};
+pub use throttle::{AuthKind, AuthThrottle, AuthThrottleConfig};
 pub use webhook::{
-    WebhookConfig, WebhookEvent, WebhookEventType, WebhookHandler, WebhookManager, WebhookResponse,
+    WebhookConfig, WebhookEvent, WebhookEventType, WebhookHandler, WebhookManager, WebhookNotifier,
+    WebhookResponse,
 };
 
 /// 🔒 SAFETY: Gateway 统一入口结构体喵
 #[derive(Debug, Clone)]
 pub struct Gateway {
     server: Option<GatewayServer>,
-    pairing_manager: PairingManager,
     webhook_manager: WebhookManager,
 }
 
 impl Gateway {
     pub fn new(gateway_config: GatewayConfig) -> Self {
-        let pairing_config = PairingConfig::default();
         let webhook_config = WebhookConfig::default();
         Self {
             server: Some(GatewayServer::new(gateway_config)),
-            pairing_manager: PairingManager::new(pairing_config),
             webhook_manager: WebhookManager::new(webhook_config),
         }
     }
@@ -41,10 +52,6 @@ impl Gateway {
         Ok(())
     }
 
-    pub fn pairing_manager(&self) -> &PairingManager {
-        &self.pairing_manager
-    }
-
     pub fn webhook_manager(&self) -> &WebhookManager {
         &self.webhook_manager
     }
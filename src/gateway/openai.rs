@@ -4,21 +4,42 @@
 //! 
 //! 端点:
 //! - POST /v1/chat/completions (OpenAI 兼容)
+//! - POST /v1/embeddings (见 `embeddings` 模块)
 //! - GET /v1/models
 //! - GET /v1/tools
+//!
+//! `stream: true` 时以 SSE 返回 `chat.completion.chunk`；工具执行期间每隔
+//! `stream_heartbeat` 发送一次 `delta.role = "tool_status"` 的进度事件，
+//! 其余等待阶段发送 SSE 注释保活，客户端不会因长时间无数据而断开喵
+//!
+//! 请求体中的 `"agent": "<name>"` 选择 `agents.agent.<name>` 定义的 Agent（未配置时返回 404）
 
 use axum::{
-    extract::{State, Request},
+    extract::{Extension, State},
     http::StatusCode,
-    response::{IntoResponse, Json, Response},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
     routing::{get, post},
     Router,
 };
+use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, info};
 
-use super::server::GatewayState;
+use super::backend::ChatBackend;
+use super::embeddings::embeddings;
+use super::server::{GatewayState, RequestSpan};
+use crate::core::traits::Message as CoreMessage;
+use crate::tools::{estimate_tokens, ToolCatalog};
 
 /// 🔒 SAFETY: OpenAI Chat 请求喵
 #[derive(Debug, Deserialize)]
@@ -36,6 +57,9 @@ pub struct ChatCompletionRequest {
     /// 流式输出
     #[serde(default)]
     pub stream: bool,
+    /// Agent 名称（NekoClaw 扩展，未指定时使用默认后端）
+    #[serde(default)]
+    pub agent: Option<String>,
 }
 
 fn default_temperature() -> f32 { 0.7 }
@@ -89,51 +113,159 @@ pub struct ModelInfo {
 }
 
 /// 🔒 SAFETY: 工具响应喵
-#[derive(Debug, Serialize)]
-pub struct ToolsResponse {
-    pub tools: Vec<ToolInfo>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ToolInfo {
-    pub name: String,
-    pub description: String,
-}
-
 /// 🔒 SAFETY: Chat Completions 端点喵
 pub async fn chat_completions(
     State(state): State<Arc<GatewayState>>,
+    request_span: Option<Extension<RequestSpan>>,
     Json(req): Json<ChatCompletionRequest>,
-) -> Result<Json<ChatCompletionResponse>, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
     info!("Chat request: model={}, messages={}", req.model, req.messages.len());
-    
-    // TODO: 实际调用 Agent 处理
-    // 目前返回模拟响应
-    
+    let parent = request_span.map(|Extension(RequestSpan(span))| span);
+    let backend = match &req.agent {
+        Some(name) => Some(
+            state
+                .agents
+                .get(name)
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown agent: {}", name)))?,
+        ),
+        None => state.backend.as_ref(),
+    };
+
+    if let (true, Some(backend)) = (req.stream, backend) {
+        return Ok(stream_completion(backend.clone(), req, parent, state.config.stream_heartbeat).into_response());
+    }
+
+    let content = match backend {
+        Some(backend) => {
+            backend
+                .complete_traced(core_messages(&req.messages), None, parent.as_ref())
+                .await
+                .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Backend error: {}", e)))?
+        }
+        // 未挂载后端时返回模拟响应
+        None => "喵~ NekoClaw API 已启动！这是模拟响应喵。".to_string(),
+    };
+    debug!("Chat reply: {} chars", content.len());
+
+    let prompt_tokens: usize = req.messages.iter().map(|m| estimate_tokens(&m.content)).sum();
+    let completion_tokens = estimate_tokens(&content);
     let response = ChatCompletionResponse {
         id: format!("chatcmpl-{}", uuid::Uuid::new_v4()),
         object: "chat.completion".to_string(),
-        created: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
+        created: unix_now(),
         model: req.model.clone(),
         choices: vec![Choice {
             index: 0,
             message: Message {
                 role: "assistant".to_string(),
-                content: "喵~ NekoClaw API 已启动！这是模拟响应喵。".to_string(),
+                content,
             },
             finish_reason: "stop".to_string(),
         }],
         usage: Usage {
-            prompt_tokens: 10,
-            completion_tokens: 20,
-            total_tokens: 30,
+            prompt_tokens: prompt_tokens as u32,
+            completion_tokens: completion_tokens as u32,
+            total_tokens: (prompt_tokens + completion_tokens) as u32,
         },
     };
-    
-    Ok(Json(response))
+
+    Ok(Json(response).into_response())
+}
+
+fn core_messages(messages: &[Message]) -> Vec<CoreMessage> {
+    messages
+        .iter()
+        .map(|m| CoreMessage {
+            role: m.role.clone(),
+            content: m.content.clone(),
+        })
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// 🔒 SAFETY: 流式 Chat 响应喵
+///
+/// 后端在独立任务中执行；工具执行期间按心跳间隔发送 `tool_status`，
+/// 完成后依次发送回复内容、`finish_reason: "stop"` 与 `[DONE]`
+fn stream_completion(
+    backend: Arc<ChatBackend>,
+    req: ChatCompletionRequest,
+    parent: Option<crate::telemetry::Span>,
+    heartbeat: Duration,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel::<Event>(16);
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4());
+    let created = unix_now();
+    let chunk = move |delta: JsonValue, finish_reason: Option<&str>| {
+        Event::default().data(
+            json!({
+                "id": id,
+                "object": "chat.completion.chunk",
+                "created": created,
+                "model": req.model,
+                "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            })
+            .to_string(),
+        )
+    };
+    let messages = core_messages(&req.messages);
+
+    tokio::spawn(async move {
+        let (progress, mut status) = watch::channel(None::<String>);
+        let work = async move { backend.complete_traced(messages, Some(&progress), parent.as_ref()).await };
+        tokio::pin!(work);
+        let mut ticker = tokio::time::interval(heartbeat);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut running: Option<(String, Instant)> = None;
+        let tool_status = |tool: &str, started: Instant| {
+            let elapsed = started.elapsed().as_secs();
+            json!({
+                "role": "tool_status",
+                "content": format!("Running {} ({}s)", tool, elapsed),
+                "tool": tool,
+                "elapsed_secs": elapsed,
+            })
+        };
+
+        let result = loop {
+            tokio::select! {
+                result = &mut work => break result,
+                Ok(()) = status.changed() => {
+                    running = status.borrow_and_update().clone().map(|tool| (tool, Instant::now()));
+                    if let Some((tool, started)) = &running {
+                        let _ = tx.send(chunk(tool_status(tool, *started), None)).await;
+                        ticker.reset();
+                    }
+                }
+                _ = ticker.tick() => {
+                    if let Some((tool, started)) = &running {
+                        let _ = tx.send(chunk(tool_status(tool, *started), None)).await;
+                    }
+                }
+            }
+        };
+
+        match result {
+            Ok(content) => {
+                debug!("Chat reply (streamed): {} chars", content.len());
+                let _ = tx.send(chunk(json!({ "role": "assistant", "content": content }), None)).await;
+                let _ = tx.send(chunk(json!({}), Some("stop"))).await;
+            }
+            Err(e) => {
+                let error = json!({ "error": { "message": format!("Backend error: {}", e) } });
+                let _ = tx.send(Event::default().data(error.to_string())).await;
+            }
+        }
+        let _ = tx.send(Event::default().data("[DONE]")).await;
+    });
+
+    Sse::new(ReceiverStream::new(rx).map(Ok)).keep_alive(KeepAlive::new().interval(heartbeat))
 }
 
 /// 🔒 SAFETY: 列出模型喵
@@ -155,30 +287,17 @@ pub async fn list_models() -> Json<ModelsResponse> {
     })
 }
 
-/// 🔒 SAFETY: 列出工具喵
-pub async fn list_tools() -> Json<ToolsResponse> {
-    Json(ToolsResponse {
-        tools: vec![
-            ToolInfo {
-                name: "fs_read".to_string(),
-                description: "读取文件内容".to_string(),
-            },
-            ToolInfo {
-                name: "fs_write".to_string(),
-                description: "写入文件内容".to_string(),
-            },
-            ToolInfo {
-                name: "echo".to_string(),
-                description: "回显消息".to_string(),
-            },
-        ],
-    })
+/// 🔒 SAFETY: 列出工具与技能喵
+/// 名称、参数 schema、分类、是否危险、所需权限与来源（builtin / skill / mcp / plugin）
+pub async fn list_tools(State(state): State<Arc<GatewayState>>) -> Json<ToolCatalog> {
+    Json(state.tool_catalog.as_deref().cloned().unwrap_or_default())
 }
 
 /// 🔒 SAFETY: 创建 OpenAI 兼容路由喵
 pub fn create_openai_routes() -> Router<Arc<GatewayState>> {
     Router::new()
         .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/embeddings", post(embeddings))
         .route("/v1/models", get(list_models))
         .route("/v1/tools", get(list_tools))
 }
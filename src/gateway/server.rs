@@ -2,24 +2,50 @@
 //!
 //! @诺诺 的 Axum HTTP 服务器实现喵
 
+use crate::auth::{Identity, Permission, Rbac};
+use crate::channels::discord::DiscordInteractions;
 use crate::core::traits::Result as NekoResult;
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Request, State},
+    Extension,
+    http::{header::RETRY_AFTER, HeaderMap, StatusCode},
     middleware::{self, Next},
     response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+use crate::providers::{ProbeResult, ProviderHealth};
+use crate::security::crypto::constant_time_eq;
+use crate::security::{AuditEvent, AuditKind, AuditLog, AuditStatus};
+use crate::service::log_level::LogLevelHandle;
+use crate::service::ShutdownCoordinator;
+use crate::telemetry::{MetricsRecorder, Span, Tracer};
+use crate::tools::ToolCatalog;
+
+use super::api_keys::{ApiKey, ApiKeyName, KeyRateLimiter};
+use super::backend::ChatBackend;
+use super::dashboard::create_dashboard_routes;
+use super::discord::create_discord_routes;
+use super::device_pairing::{create_device_pairing_routes, DevicePairing};
+use super::embeddings::EmbeddingModels;
+use super::idempotency::{idempotency_middleware, IdempotencyCache};
 use super::openai::create_openai_routes;
-use super::metrics::create_metrics_routes;
+use super::metrics::{create_metrics_routes, ingest_metrics, submit_feedback};
+use super::throttle::{AuthKind, AuthThrottle, AuthThrottleConfig};
+use super::webhook::{create_inbound_routes, InboundWebhooks};
+
+/// 流式响应默认心跳间隔（秒）
+pub const DEFAULT_STREAM_HEARTBEAT_SECS: u64 = 10;
 
 /// 🔒 SAFETY: Gateway 配置结构体喵
 #[derive(Debug, Clone)]
@@ -27,7 +53,15 @@ pub struct GatewayConfig {
     pub bind_addr: String,
     pub port: u16,
     pub bearer_token: String,
+    /// 命名 API Key（`gateway keys create`，已解密）
+    pub api_keys: Vec<ApiKey>,
     pub pairing_enabled: bool,
+    /// Idempotency-Key 响应缓存时长（秒）
+    pub idempotency_ttl_secs: u64,
+    /// 认证失败限流与锁定
+    pub auth_throttle: AuthThrottleConfig,
+    /// 流式响应的心跳间隔（工具执行期间发送 `tool_status`）
+    pub stream_heartbeat: Duration,
 }
 
 impl Default for GatewayConfig {
@@ -36,23 +70,68 @@ impl Default for GatewayConfig {
             bind_addr: "127.0.0.1".to_string(),
             port: 8080,
             bearer_token: String::new(),
+            api_keys: Vec::new(),
             pairing_enabled: true,
+            idempotency_ttl_secs: 24 * 3600,
+            auth_throttle: AuthThrottleConfig::default(),
+            stream_heartbeat: Duration::from_secs(DEFAULT_STREAM_HEARTBEAT_SECS),
         }
     }
 }
 
 /// 🔒 SAFETY: Gateway 服务器状态喵
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct GatewayState {
     pub config: GatewayConfig,
+    /// Idempotency-Key 响应缓存
+    pub idempotency: IdempotencyCache,
+    /// 对话后端（None 时 Chat 端点返回模拟响应）
+    pub backend: Option<Arc<ChatBackend>>,
+    /// 命名 Agent 的对话后端（请求体 `"agent"` 选择）
+    pub agents: HashMap<String, Arc<ChatBackend>>,
+    /// 自定义指标记录器（None 时上报端点返回 503）
+    pub telemetry: Option<MetricsRecorder>,
+    /// 运行时日志级别句柄（None 时 `/admin/log-level` 返回 503）
+    pub log_level: Option<LogLevelHandle>,
+    /// 按来源 IP 的认证失败限流
+    pub throttle: AuthThrottle,
+    /// 按 API Key 的令牌桶限流
+    pub key_limiter: KeyRateLimiter,
+    /// 设备码配对与已配对设备的 Key（None 时 `/pairing/device` 返回 503）
+    pub device_pairing: Option<Arc<DevicePairing>>,
+    /// Provider 健康探测结果（None 时 `/health` 不包含 provider 状态）
+    pub provider_health: Option<Arc<ProviderHealth>>,
+    /// 工具与技能目录（`/v1/tools` 输出，None 时为空列表）
+    pub tool_catalog: Option<Arc<ToolCatalog>>,
+    /// 记录 `gateway.request` Span（None 时不记录）
+    pub tracer: Option<Arc<Tracer>>,
+    /// 入站 Webhook 来源（None 时 `/webhook/:source` 返回 404）
+    pub webhooks: Option<Arc<InboundWebhooks>>,
+    /// Discord 斜杠命令（None 时 `/discord/interactions` 返回 404）
+    pub discord: Option<Arc<DiscordInteractions>>,
+    /// 关闭协调器（None 时关闭不等待进行中的请求）
+    pub shutdown: Option<ShutdownCoordinator>,
+    /// `/v1/embeddings` 可用的模型（None 时返回 503）
+    pub embeddings: Option<Arc<EmbeddingModels>>,
+    /// 命名 Key 与设备 Key 的角色权限（None 时不检查角色）
+    pub rbac: Option<Arc<Rbac>>,
+    /// 防篡改审计日志（认证失败、越权、设备 Key 签发；None 时不记录）
+    pub audit: Option<Arc<AuditLog>>,
 }
 
+/// 🔒 SAFETY: 当前请求的 `gateway.request` Span 喵（挂载 Tracer 时由中间件注入请求扩展）
+#[derive(Debug, Clone)]
+pub struct RequestSpan(pub Span);
+
 /// 🔒 SAFETY: 健康检查响应喵
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub uptime_secs: u64,
+    /// 各上游 provider 的最新探测结果
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<ProbeResult>,
 }
 
 /// 🔒 SAFETY: API 错误响应喵
@@ -66,52 +145,222 @@ pub struct ErrorResponse {
 impl IntoResponse for ErrorResponse {
     fn into_response(self) -> Response {
         let status = match self.code.as_str() {
-            "UNAUTHORIZED" => StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED" | "INVALID_CODE" => StatusCode::UNAUTHORIZED,
             "FORBIDDEN" => StatusCode::FORBIDDEN,
             "NOT_FOUND" => StatusCode::NOT_FOUND,
+            "TOO_MANY_REQUESTS" => StatusCode::TOO_MANY_REQUESTS,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         (status, Json(self)).into_response()
     }
 }
 
-/// 🔒 SAFETY: Bearer Token 认证中间件喵
+/// 请求的来源 IP喵（未携带连接信息时视为 0.0.0.0）
+fn source_ip(request: &Request) -> IpAddr {
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/// 🔒 SAFETY: 锁定中的 429 响应喵
+pub(super) fn locked_out(remaining: Duration) -> Response {
+    let secs = remaining.as_secs().max(1);
+    let mut response = ErrorResponse {
+        code: "TOO_MANY_REQUESTS".to_string(),
+        message: format!("Too many failed authentication attempts, retry in {}s", secs),
+        request_id: Uuid::new_v4().to_string(),
+    }
+    .into_response();
+    response.headers_mut().insert(RETRY_AFTER, secs.into());
+    response
+}
+
+/// 🔒 SAFETY: 超出 API Key 限流的 429 响应喵
+fn rate_limited(key: &str, wait: Duration) -> Response {
+    let secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = ErrorResponse {
+        code: "TOO_MANY_REQUESTS".to_string(),
+        message: format!("Rate limit exceeded for API key {}, retry in {}s", key, secs),
+        request_id: Uuid::new_v4().to_string(),
+    }
+    .into_response();
+    response.headers_mut().insert(RETRY_AFTER, secs.into());
+    response
+}
+
+/// 🔒 SAFETY: 认证失败的 401 响应喵
+fn unauthorized(message: &str) -> Response {
+    ErrorResponse {
+        code: "UNAUTHORIZED".to_string(),
+        message: message.to_string(),
+        request_id: Uuid::new_v4().to_string(),
+    }
+    .into_response()
+}
+
+/// 🔒 SAFETY: 权限不足的 403 响应喵
+fn forbidden(message: &str) -> Response {
+    ErrorResponse {
+        code: "FORBIDDEN".to_string(),
+        message: message.to_string(),
+        request_id: Uuid::new_v4().to_string(),
+    }
+    .into_response()
+}
+
+/// 🔒 SAFETY: Bearer Token / API Key 认证中间件喵
+///
+/// 接受主 Bearer Token，或 `Authorization: Bearer` / `X-API-Key` 携带的命名 API Key / 设备 Key（均为常量时间比较）；
+/// 设备 Key 按作用域限制可访问的路径，挂载 `Rbac` 时命名 Key 与设备 Key（身份 `gateway:<名称>`）
+/// 还需要相应角色：`/admin/*` 需要 Admin，其余端点需要 `chat_role`（越权返回 403；主 Bearer Token 即主人，不检查），
+/// 错误的凭证计入来源 IP 的失败次数，锁定期间直接返回 429，API Key 超出限流同样返回 429
 pub async fn auth_middleware(
     State(state): State<Arc<GatewayState>>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    let auth_header = headers
+) -> Response {
+    let ip = source_ip(&request);
+    if let Some(remaining) = state.throttle.check(ip, Instant::now()) {
+        return locked_out(remaining);
+    }
+
+    let token = headers
         .get("authorization")
         .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .filter(|token| !token.is_empty());
+    let Some(token) = token else {
+        return unauthorized("Missing bearer token or X-API-Key");
+    };
+
+    let key = state
+        .config
+        .api_keys
+        .iter()
+        .find(|k| constant_time_eq(k.secret.as_bytes(), token.as_bytes()));
+    let device = match key {
+        Some(_) => None,
+        None => state.device_pairing.as_ref().and_then(|p| p.find(token)),
+    };
+    if key.is_none()
+        && device.is_none()
+        && (state.config.bearer_token.is_empty()
+            || !constant_time_eq(token.as_bytes(), state.config.bearer_token.as_bytes()))
+    {
+        if let Some(lockout) = state.throttle.record_failure(ip, AuthKind::Bearer, Instant::now()) {
+            return locked_out(lockout);
+        }
+        return unauthorized("Invalid credentials");
+    }
+
+    state.throttle.record_success(ip);
+    if let Some(key) = key {
+        if let Some(limit) = key.rate_limit {
+            if let Err(wait) = state.key_limiter.acquire(&key.name, limit, Instant::now()) {
+                return rate_limited(&key.name, wait);
+            }
+        }
+        request.extensions_mut().insert(ApiKeyName(key.name.clone()));
+    }
+    if let Some(device) = device {
+        if !device.allows(request.uri().path()) {
+            let reason = format!("Device key {} is not allowed to access this endpoint", device.name);
+            audit_denied(&state, &format!("device:{}", device.name), request.uri().path(), &reason);
+            return forbidden(&reason);
+        }
+        request.extensions_mut().insert(ApiKeyName(format!("device:{}", device.name)));
+    }
+    if let (Some(rbac), Some(ApiKeyName(name))) = (&state.rbac, request.extensions().get::<ApiKeyName>()) {
+        let permission = match request.uri().path().starts_with("/admin/") {
+            true => Permission::Admin,
+            false => Permission::Chat,
+        };
+        let identity = Identity::new("gateway", name);
+        if let Err(e) = rbac.check(&identity, permission) {
+            audit_denied(&state, &identity.to_string(), request.uri().path(), &e.to_string());
+            return forbidden(&e.to_string());
+        }
+    }
+    next.run(request).await
+}
 
-    if !auth_header.starts_with("Bearer ") {
-        return Err(StatusCode::UNAUTHORIZED);
+/// 越权请求写入审计日志喵
+fn audit_denied(state: &GatewayState, actor: &str, path: &str, reason: &str) {
+    if let Some(audit) = &state.audit {
+        audit.record_or_warn(
+            &AuditEvent::new(AuditKind::Auth, actor, "gateway.forbidden")
+                .with_arguments(serde_json::json!({ "path": path }))
+                .with_status(AuditStatus::Denied)
+                .with_detail(reason),
+        );
     }
+}
+
+/// 🔒 SAFETY: 为 OpenAI 兼容端点的请求记录 `gateway.request` Span 喵
+///
+/// 后端的 `agent.request` 作为它的子 Span，5xx 响应标记为失败
+pub async fn trace_middleware(State(state): State<Arc<GatewayState>>, mut request: Request, next: Next) -> Response {
+    let Some(tracer) = state.tracer.as_ref() else {
+        return next.run(request).await;
+    };
+    let Some(mut span) = tracer.start_span("gateway.request") else {
+        return next.run(request).await;
+    };
+    span.set_attribute("http.method".to_string(), request.method().to_string());
+    span.set_attribute("http.route".to_string(), request.uri().path().to_string());
+    request.extensions_mut().insert(RequestSpan(span.clone()));
 
-    let token = &auth_header[7..];
-    if token != state.config.bearer_token {
-        return Err(StatusCode::FORBIDDEN);
+    let response = next.run(request).await;
+    let status = response.status();
+    span.set_attribute("http.status_code".to_string(), status.as_u16().to_string());
+    match status.is_server_error() {
+        true => tracer.finish_span_with_error(span, &status.to_string()).await,
+        false => tracer.finish_span(span).await,
     }
+    response
+}
 
-    Ok(next.run(request).await)
+/// 🔒 SAFETY: 关闭期间拒绝新的 Agent 请求（503），进行中的请求持有排空凭证喵
+pub async fn drain_middleware(State(state): State<Arc<GatewayState>>, request: Request, next: Next) -> Response {
+    let Some(coordinator) = state.shutdown.as_ref() else {
+        return next.run(request).await;
+    };
+    let Some(guard) = coordinator.try_begin() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, [(RETRY_AFTER, "5")], "Gateway is shutting down").into_response();
+    };
+    guard
+        .run(next.run(request))
+        .await
+        .unwrap_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "Request cancelled by shutdown").into_response())
 }
 
-/// 🔒 SAFETY: 健康检查端点喵
-pub async fn health_check() -> Json<HealthResponse> {
+/// 🔒 SAFETY: 健康检查端点喵（任一 provider 探测失败时为 `degraded`）
+pub async fn health_check(State(state): State<Arc<GatewayState>>) -> Json<HealthResponse> {
+    let health = state.provider_health.as_deref();
+    let status = match health.is_some_and(|h| h.is_degraded()) {
+        true => "degraded",
+        false => "ok",
+    };
     Json(HealthResponse {
-        status: "ok".to_string(),
+        status: status.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         uptime_secs: 0,
+        providers: health.map(|h| h.snapshot()).unwrap_or_default(),
     })
 }
 
 /// 🔒 SAFETY: 状态端点喵
-pub async fn status(State(state): State<Arc<GatewayState>>) -> Json<serde_json::Value> {
+pub async fn status(
+    State(state): State<Arc<GatewayState>>,
+    api_key: Option<Extension<ApiKeyName>>,
+) -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "status": "running",
+        "api_key": api_key.map(|Extension(ApiKeyName(name))| name),
         "config": {
             "bind_addr": state.config.bind_addr,
             "port": state.config.port,
@@ -120,41 +369,51 @@ pub async fn status(State(state): State<Arc<GatewayState>>) -> Json<serde_json::
     }))
 }
 
-/// 🔒 SAFETY: 配对请求喵
+/// 🔒 SAFETY: 日志级别调整请求喵
 #[derive(Debug, Deserialize)]
-pub struct PairingRequest {
-    pub code: String,
+pub struct LogLevelRequest {
+    pub level: String,
+    /// 模块名（如 gateway），省略时调整全局级别
     #[serde(default)]
-    pub device_name: Option<String>,
+    pub module: Option<String>,
 }
 
-/// 🔒 SAFETY: 配对响应喵
-#[derive(Debug, Serialize)]
-pub struct PairingResponse {
-    pub status: String,
-    pub message: String,
-    pub session_token: Option<String>,
-}
-
-/// 🔒 SAFETY: 配对端点喵
-pub async fn pairing(
-    Json(req): Json<PairingRequest>,
-) -> Result<Json<PairingResponse>, ErrorResponse> {
-    info!("Pairing request with code: {}", req.code);
-    
-    if req.code.len() != 6 {
-        return Err(ErrorResponse {
-            code: "INVALID_CODE".to_string(),
-            message: "Pairing code must be 6 digits".to_string(),
-            request_id: Uuid::new_v4().to_string(),
-        });
-    }
+fn log_level_handle(state: &GatewayState) -> Result<&LogLevelHandle, (StatusCode, String)> {
+    state.log_level.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Runtime log level control is not enabled".to_string(),
+    ))
+}
 
-    Ok(Json(PairingResponse {
-        status: "success".to_string(),
-        message: "Pairing successful".to_string(),
-        session_token: Some(Uuid::new_v4().to_string()),
-    }))
+/// 🔒 SAFETY: 查看当前日志过滤喵
+pub async fn get_log_level(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let handle = log_level_handle(&state)?;
+    Ok(Json(serde_json::json!({ "filter": handle.directive() })))
+}
+
+/// 🔒 SAFETY: 调整日志级别喵
+pub async fn set_log_level(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<LogLevelRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let handle = log_level_handle(&state)?;
+    handle
+        .set(&req.level, req.module.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(serde_json::json!({ "filter": handle.directive() })))
+}
+
+/// 🔒 SAFETY: 恢复启动时的日志级别喵
+pub async fn reset_log_level(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let handle = log_level_handle(&state)?;
+    handle
+        .reset()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(serde_json::json!({ "filter": handle.directive() })))
 }
 
 /// 🔒 SAFETY: 创建 Gateway 路由喵
@@ -162,15 +421,31 @@ fn create_router(state: Arc<GatewayState>) -> Router {
     // 公开端点
     let public_routes = Router::new()
         .route("/health", get(health_check))
-        .merge(create_metrics_routes());
+        .merge(create_metrics_routes())
+        .merge(create_device_pairing_routes())
+        .merge(create_dashboard_routes())
+        .merge(
+            create_inbound_routes()
+                .merge(create_discord_routes())
+                .route_layer(middleware::from_fn_with_state(state.clone(), drain_middleware)),
+        );
 
-    // OpenAI 兼容路由
-    let openai_routes = create_openai_routes();
+    // OpenAI 兼容路由（支持 Idempotency-Key 重试，关闭时排空）
+    let openai_routes = create_openai_routes()
+        .route_layer(middleware::from_fn_with_state(state.clone(), idempotency_middleware))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth_middleware))
+        .route_layer(middleware::from_fn_with_state(state.clone(), trace_middleware))
+        .route_layer(middleware::from_fn_with_state(state.clone(), drain_middleware));
 
     // 认证路由
     let protected_routes = Router::new()
         .route("/status", get(status))
-        .route("/pairing", post(pairing))
+        .route("/telemetry/events", post(ingest_metrics))
+        .route("/feedback", post(submit_feedback))
+        .route(
+            "/admin/log-level",
+            get(get_log_level).post(set_log_level).delete(reset_log_level),
+        )
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -186,13 +461,133 @@ fn create_router(state: Arc<GatewayState>) -> Router {
 #[derive(Debug, Clone)]
 pub struct GatewayServer {
     config: GatewayConfig,
-    state: Arc<GatewayState>,
+    backend: Option<Arc<ChatBackend>>,
+    agents: HashMap<String, Arc<ChatBackend>>,
+    telemetry: Option<MetricsRecorder>,
+    log_level: Option<LogLevelHandle>,
+    device_pairing: Option<Arc<DevicePairing>>,
+    audit_log: Option<PathBuf>,
+    audit: Option<Arc<AuditLog>>,
+    provider_health: Option<Arc<ProviderHealth>>,
+    tool_catalog: Option<Arc<ToolCatalog>>,
+    tracer: Option<Arc<Tracer>>,
+    webhooks: Option<Arc<InboundWebhooks>>,
+    discord: Option<Arc<DiscordInteractions>>,
+    shutdown: Option<ShutdownCoordinator>,
+    embeddings: Option<Arc<EmbeddingModels>>,
+    rbac: Option<Arc<Rbac>>,
 }
 
 impl GatewayServer {
     pub fn new(config: GatewayConfig) -> Self {
-        let state = Arc::new(GatewayState { config: config.clone() });
-        Self { config, state }
+        Self {
+            config,
+            backend: None,
+            agents: HashMap::new(),
+            telemetry: None,
+            log_level: None,
+            device_pairing: None,
+            audit_log: None,
+            audit: None,
+            provider_health: None,
+            tool_catalog: None,
+            tracer: None,
+            webhooks: None,
+            discord: None,
+            shutdown: None,
+            embeddings: None,
+            rbac: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 启用设备码配对喵（批准后签发的设备 Key 可通过认证）
+    pub fn with_device_pairing(mut self, pairing: DevicePairing) -> Self {
+        self.device_pairing = Some(Arc::new(pairing));
+        self
+    }
+
+    /// 🔒 SAFETY: 命名 Key 与设备 Key 按角色检查权限喵
+    pub fn with_rbac(mut self, rbac: Arc<Rbac>) -> Self {
+        self.rbac = Some(rbac);
+        self
+    }
+
+    /// 🔒 SAFETY: 认证失败与锁定事件写入审计日志喵
+    pub fn with_audit_log(mut self, path: PathBuf) -> Self {
+        self.audit_log = Some(path);
+        self
+    }
+
+    /// 🔒 SAFETY: 认证失败、锁定、越权与设备 Key 签发写入防篡改审计日志喵
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// `/health` 附带 provider 探测结果喵
+    pub fn with_provider_health(mut self, health: Arc<ProviderHealth>) -> Self {
+        self.provider_health = Some(health);
+        self
+    }
+
+    /// `/v1/tools` 输出的工具与技能目录喵
+    pub fn with_tool_catalog(mut self, catalog: ToolCatalog) -> Self {
+        self.tool_catalog = Some(Arc::new(catalog));
+        self
+    }
+
+    /// 🔒 SAFETY: 记录 `gateway.request` Span 喵（后端使用同一个 Tracer 时组成完整调用树）
+    pub fn with_tracer(mut self, tracer: Arc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// 🔒 SAFETY: 启用 `/webhook/:source` 入站集成喵
+    pub fn with_webhooks(mut self, webhooks: InboundWebhooks) -> Self {
+        self.webhooks = Some(Arc::new(webhooks));
+        self
+    }
+
+    /// 🔒 SAFETY: 处理 Discord 斜杠命令喵（`/discord/interactions`）
+    pub fn with_discord(mut self, interactions: Arc<DiscordInteractions>) -> Self {
+        self.discord = Some(interactions);
+        self
+    }
+
+    /// 🔒 SAFETY: 关闭时拒绝新的 Agent 请求，并等待进行中的请求完成喵
+    pub fn with_shutdown(mut self, coordinator: ShutdownCoordinator) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// 🔒 SAFETY: 启用 `/v1/embeddings` 喵
+    pub fn with_embeddings(mut self, models: EmbeddingModels) -> Self {
+        self.embeddings = Some(Arc::new(models));
+        self
+    }
+
+    /// 🔒 SAFETY: 挂载对话后端喵
+    pub fn with_backend(mut self, backend: Arc<ChatBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// 🔒 SAFETY: 挂载命名 Agent 的对话后端喵（请求体 `"agent": "<name>"` 时使用）
+    pub fn with_agent(mut self, name: &str, backend: Arc<ChatBackend>) -> Self {
+        self.agents.insert(name.to_string(), backend);
+        self
+    }
+
+    /// 🔒 SAFETY: 启用 `/telemetry/events` 自定义指标上报喵
+    pub fn with_telemetry(mut self, recorder: MetricsRecorder) -> Self {
+        self.telemetry = Some(recorder);
+        self
+    }
+
+    /// 🔒 SAFETY: 启用 `/admin/log-level` 运行时日志级别调整喵
+    pub fn with_log_level(mut self, handle: LogLevelHandle) -> Self {
+        self.log_level = Some(handle);
+        self
     }
 
     pub async fn run(self) -> NekoResult<()> {
@@ -200,13 +595,47 @@ impl GatewayServer {
             .parse()
             .map_err(|e| format!("Invalid bind address: {}", e))?;
 
-        let router = create_router(self.state.clone());
         let listener = TcpListener::bind(&addr)
             .await
             .map_err(|e| format!("Failed to bind to {}: {}", addr, e))?;
+        self.serve(listener).await
+    }
 
-        info!("🚀 Gateway server listening on http://{}", addr);
-        axum::serve(listener, router).await?;
+    /// 🔒 SAFETY: 在已绑定的监听器上提供服务喵（端口 0 时由系统分配）
+    pub async fn serve(self, listener: TcpListener) -> NekoResult<()> {
+        let mut throttle = AuthThrottle::new(self.config.auth_throttle.clone());
+        if let Some(path) = self.audit_log {
+            throttle = throttle.with_audit_log(path);
+        }
+        if let Some(audit) = &self.audit {
+            throttle = throttle.with_audit(audit.clone());
+        }
+        let state = Arc::new(GatewayState {
+            idempotency: IdempotencyCache::new(std::time::Duration::from_secs(
+                self.config.idempotency_ttl_secs,
+            )),
+            config: self.config,
+            backend: self.backend,
+            agents: self.agents,
+            telemetry: self.telemetry,
+            log_level: self.log_level,
+            throttle,
+            key_limiter: KeyRateLimiter::new(),
+            device_pairing: self.device_pairing,
+            provider_health: self.provider_health,
+            tool_catalog: self.tool_catalog,
+            tracer: self.tracer,
+            webhooks: self.webhooks,
+            discord: self.discord,
+            shutdown: self.shutdown,
+            embeddings: self.embeddings,
+            rbac: self.rbac,
+            audit: self.audit,
+        });
+        let router = create_router(state);
+        info!("🚀 Gateway server listening on http://{}", listener.local_addr()?);
+        // 限流按来源 IP 计数，需要连接信息喵
+        axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await?;
         Ok(())
     }
 
@@ -0,0 +1,399 @@
+//! Gateway 集成测试脚手架 🧪
+//!
+//! @诺诺 的进程内 Gateway 测试环境喵
+//!
+//! `TestGateway::start` 在临时端口上启动完整的 Gateway，并挂载：
+//! - `ScriptedProvider`：按脚本依次返回回复，记录收到的每轮 prompt
+//! - 内存 SQLite 记忆后端
+//! - `ScriptedMcpServer`：通过进程内管道对接的脚本化 MCP server
+//! - 名为 `TEST_AGENT` 的命名 Agent（同一个 Provider，带自己的系统提示词，不写记忆）
+//! - 内存 SQLite 遥测库（`/telemetry/events` 上报）与全采样 Tracer
+//! - 独立的日志级别句柄（`/admin/log-level`，不影响全局订阅者）
+//! - 关闭协调器（`shutdown`），可以在测试里触发排空
+//! - 可选的入站 Webhook（`start_with_webhooks`）与设备码配对（`start_with_device_pairing`）
+//!
+//! 所有依赖都在进程内，不访问外部网络，多个测试可以并行运行喵
+
+use crate::core::traits::{Message, Provider, Result as NekoResult};
+use crate::gateway::webhook::InboundWebhooks;
+use crate::gateway::{ChatBackend, DevicePairing, GatewayConfig, GatewayServer};
+use crate::memory::SqliteMemory;
+use crate::service::log_level::LogLevelHandle;
+use crate::service::ShutdownCoordinator;
+use crate::telemetry::{MetricsCollector, MetricsConfig, MetricsRecorder, Tracer, TracerConfig};
+use crate::tools::mcp::{encode_frame, McpToolAnnotations};
+use crate::tools::{ClientInfo, InitializeResult, McpClient, McpTool, ServerCapabilities};
+use futures::Stream;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// 🔒 SAFETY: 按脚本回复的 Provider 喵
+#[derive(Default)]
+pub struct ScriptedProvider {
+    replies: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<Vec<Message>>>,
+    delay: Duration,
+}
+
+impl ScriptedProvider {
+    pub fn new<'a>(replies: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            replies: Mutex::new(replies.into_iter().map(String::from).collect()),
+            prompts: Mutex::new(Vec::new()),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// 每次回复前等待一段时间喵（模拟慢速 provider）
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// 每次 chat 调用收到的完整消息列表喵
+    pub fn prompts(&self) -> Vec<Vec<Message>> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for ScriptedProvider {
+    async fn chat(&self, messages: &[Message]) -> NekoResult<String> {
+        self.prompts.lock().unwrap().push(messages.to_vec());
+        tokio::time::sleep(self.delay).await;
+        self.replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| "scripted provider has no replies left".into())
+    }
+
+    async fn stream(
+        &self,
+        messages: &[Message],
+    ) -> Pin<Box<dyn Stream<Item = NekoResult<String>> + Send>> {
+        let reply = self.chat(messages).await;
+        Box::pin(futures::stream::once(async move { reply }))
+    }
+
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+}
+
+type ToolHandler = Box<dyn Fn(&JsonValue) -> Result<String, String> + Send + Sync>;
+/// 工具调用记录（名称, 参数）
+pub type ToolCallLog = Arc<Mutex<Vec<(String, JsonValue)>>>;
+
+/// 🔒 SAFETY: 脚本化 MCP server 喵
+#[derive(Default)]
+pub struct ScriptedMcpServer {
+    tools: Vec<(McpTool, ToolHandler)>,
+    /// 工具响应前的等待（模拟耗时工具）
+    delays: HashMap<String, Duration>,
+}
+
+impl ScriptedMcpServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个工具，handler 返回 Err 时以 isError 结果响应喵
+    pub fn with_tool<F>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(&JsonValue) -> Result<String, String> + Send + Sync + 'static,
+    {
+        let tool = McpTool {
+            name: name.to_string(),
+            title: None,
+            description: format!("scripted tool {}", name),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+            annotations: None,
+        };
+        self.tools.push((tool, Box::new(handler)));
+        self
+    }
+
+    /// 把已注册的工具标记为 `destructiveHint: true` 喵
+    pub fn destructive(mut self, name: &str) -> Self {
+        for (tool, _) in self.tools.iter_mut().filter(|(t, _)| t.name == name) {
+            tool.annotations = Some(McpToolAnnotations {
+                destructive_hint: Some(true),
+                ..McpToolAnnotations::default()
+            });
+        }
+        self
+    }
+
+    /// 让工具等待一段时间后才返回结果喵
+    pub fn with_delay(mut self, name: &str, delay: Duration) -> Self {
+        self.delays.insert(name.to_string(), delay);
+        self
+    }
+
+    /// 🔒 SAFETY: 启动 server 并返回已完成握手的客户端喵
+    pub async fn connect(self) -> (Arc<McpClient>, ToolCallLog) {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (client_read, client_write) = tokio::io::split(client_io);
+        let calls: ToolCallLog = Arc::default();
+        tokio::spawn(self.serve(server_io, calls.clone()));
+
+        let mut client = McpClient::new();
+        client.connect_streams(Box::new(client_read), Box::new(client_write));
+        client.initialize().await.expect("scripted MCP initialize");
+        client.list_tools().await.expect("scripted MCP tools/list");
+        (Arc::new(client), calls)
+    }
+
+    async fn serve(self, io: tokio::io::DuplexStream, calls: ToolCallLog) {
+        let (read, mut write) = tokio::io::split(io);
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Ok(request) = serde_json::from_str::<JsonValue>(&line) else {
+                continue;
+            };
+            // 通知没有 id，不需要响应
+            let Some(id) = request.get("id").cloned() else {
+                continue;
+            };
+            let params = request.get("params").cloned().unwrap_or(JsonValue::Null);
+            let result = match request["method"].as_str().unwrap_or_default() {
+                "initialize" => serde_json::to_value(InitializeResult {
+                    protocol_version: "2025-11-25".to_string(),
+                    capabilities: ServerCapabilities {
+                        tools: Some(serde_json::Map::new()),
+                        resources: None,
+                        prompts: None,
+                    },
+                    server_info: Some(ClientInfo {
+                        name: "scripted".to_string(),
+                        version: "0.0.0".to_string(),
+                    }),
+                })
+                .unwrap(),
+                "tools/list" => {
+                    let tools: Vec<&McpTool> = self.tools.iter().map(|(tool, _)| tool).collect();
+                    json!({ "tools": tools })
+                }
+                "tools/call" => {
+                    if let Some(delay) = params["name"].as_str().and_then(|n| self.delays.get(n)) {
+                        tokio::time::sleep(*delay).await;
+                    }
+                    self.call(&params, &calls)
+                }
+                other => json!({ "error": format!("unsupported method {}", other) }),
+            };
+            let frame = encode_frame(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+                .unwrap();
+            if write.write_all(frame.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    fn call(&self, params: &JsonValue, calls: &ToolCallLog) -> JsonValue {
+        let name = params["name"].as_str().unwrap_or_default();
+        let arguments = params.get("arguments").cloned().unwrap_or(JsonValue::Null);
+        calls.lock().unwrap().push((name.to_string(), arguments.clone()));
+        let outcome = self
+            .tools
+            .iter()
+            .find(|(tool, _)| tool.name == name)
+            .map(|(_, handler)| handler(&arguments))
+            .unwrap_or_else(|| Err(format!("unknown tool {}", name)));
+        match outcome {
+            Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+            Err(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": true }),
+        }
+    }
+}
+
+/// 测试 Gateway 的 Bearer Token
+pub const TEST_TOKEN: &str = "test-token";
+
+/// 测试 Gateway 挂载的命名 Agent 及其系统提示词
+pub const TEST_AGENT: (&str, &str) = ("muse", "You are Muse.");
+
+/// 🔒 SAFETY: 进程内 Gateway 实例喵（drop 时停止服务）
+pub struct TestGateway {
+    pub base_url: String,
+    pub provider: Arc<ScriptedProvider>,
+    pub memory: Arc<SqliteMemory>,
+    pub metrics: Arc<MetricsCollector>,
+    pub tracer: Arc<Tracer>,
+    pub tool_calls: ToolCallLog,
+    pub log_level: LogLevelHandle,
+    pub shutdown: ShutdownCoordinator,
+    /// 句柄只持有弱引用，过滤层需要保持存活
+    _log_layer: reload::Layer<EnvFilter, Registry>,
+    /// 不带认证头的 HTTP 客户端（需要自定义请求时使用）喵
+    pub client: reqwest::Client,
+    handle: JoinHandle<()>,
+}
+
+impl TestGateway {
+    /// 🔒 SAFETY: 在临时端口上启动 Gateway 喵
+    pub async fn start(provider: ScriptedProvider, mcp: ScriptedMcpServer) -> Self {
+        Self::start_with_config(provider, mcp, GatewayConfig::default()).await
+    }
+
+    /// 以指定配置启动喵（端口与 Token 总是使用测试值）
+    pub async fn start_with_config(provider: ScriptedProvider, mcp: ScriptedMcpServer, config: GatewayConfig) -> Self {
+        Self::launch(provider, mcp, config, |server| server).await
+    }
+
+    /// 启用入站 Webhook 后启动喵（事件交给 Gateway 的对话后端）
+    pub async fn start_with_webhooks(provider: ScriptedProvider, webhooks: InboundWebhooks) -> Self {
+        Self::launch(provider, ScriptedMcpServer::new(), GatewayConfig::default(), |server| {
+            server.with_webhooks(webhooks)
+        })
+        .await
+    }
+
+    /// 启用设备码配对后启动喵
+    pub async fn start_with_device_pairing(pairing: DevicePairing) -> Self {
+        Self::launch(ScriptedProvider::default(), ScriptedMcpServer::new(), GatewayConfig::default(), |server| {
+            server.with_device_pairing(pairing)
+        })
+        .await
+    }
+
+    async fn launch(
+        provider: ScriptedProvider,
+        mcp: ScriptedMcpServer,
+        config: GatewayConfig,
+        extend: impl FnOnce(GatewayServer) -> GatewayServer,
+    ) -> Self {
+        let provider = Arc::new(provider);
+        let memory = Arc::new(SqliteMemory::new(":memory:").expect("in-memory sqlite"));
+        let metrics = Arc::new(
+            MetricsCollector::new(MetricsConfig {
+                db_path: ":memory:".to_string(),
+                monitor_interval_sec: 5,
+            })
+            .await
+            .expect("in-memory metrics"),
+        );
+        let tracer = Arc::new(Tracer::new(TracerConfig {
+            sampling_rate: 1.0,
+            enable_tracing: true,
+        }));
+        let (mcp, tool_calls) = mcp.connect().await;
+        let backend = ChatBackend::new(provider.clone())
+            .with_memory(memory.clone())
+            .with_mcp(mcp)
+            .with_tracer(tracer.clone());
+        let agent = ChatBackend::new(provider.clone()).with_system_prompt(TEST_AGENT.1);
+
+        let (log_level, log_layer) = LogLevelHandle::new("info");
+        let shutdown = ShutdownCoordinator::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind ephemeral port");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = GatewayServer::new(GatewayConfig {
+            port: 0,
+            bearer_token: TEST_TOKEN.to_string(),
+            ..config
+        })
+        .with_backend(Arc::new(backend))
+        .with_agent(TEST_AGENT.0, Arc::new(agent))
+        .with_telemetry(MetricsRecorder::new(metrics.clone()))
+        .with_tracer(tracer.clone())
+        .with_log_level(log_level.clone())
+        .with_shutdown(shutdown.clone());
+        let server = extend(server);
+        let handle = tokio::spawn(async move {
+            let _ = server.serve(listener).await;
+        });
+
+        Self {
+            base_url,
+            provider,
+            memory,
+            metrics,
+            tracer,
+            tool_calls,
+            log_level,
+            shutdown,
+            _log_layer: log_layer,
+            client: reqwest::Client::new(),
+            handle,
+        }
+    }
+
+    /// 发送一条用户消息，返回 (状态码, 响应 JSON) 喵
+    pub async fn chat(&self, content: &str) -> (u16, JsonValue) {
+        let response = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth(TEST_TOKEN)
+            .json(&json!({
+                "model": "scripted",
+                "messages": [{ "role": "user", "content": content }],
+            }))
+            .send()
+            .await
+            .expect("gateway request");
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap_or(JsonValue::Null))
+    }
+
+    /// 以流式请求发送一条用户消息，返回 SSE 的 data 行（`[DONE]` 原样保留为字符串）喵
+    pub async fn chat_stream(&self, content: &str) -> Vec<JsonValue> {
+        let body = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth(TEST_TOKEN)
+            .json(&json!({
+                "model": "scripted",
+                "messages": [{ "role": "user", "content": content }],
+                "stream": true,
+            }))
+            .send()
+            .await
+            .expect("gateway request")
+            .text()
+            .await
+            .expect("stream body");
+        body.lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap_or_else(|_| JsonValue::String(data.to_string())))
+            .collect()
+    }
+
+    /// 以测试 Token 调用认证端点，返回 (状态码, 响应 JSON) 喵
+    pub async fn send_authorized(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<JsonValue>,
+    ) -> (u16, JsonValue) {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path))
+            .bearer_auth(TEST_TOKEN);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.expect("gateway request");
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap_or(JsonValue::Null))
+    }
+}
+
+impl Drop for TestGateway {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
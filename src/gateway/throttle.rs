@@ -0,0 +1,292 @@
+//! 认证失败限流 🚧
+//!
+//! @诺诺 的 Gateway 防爆破机制喵
+//!
+//! 同一来源 IP 在时间窗口内连续认证失败（错误的 Bearer Token、配对码或 Webhook 签名）达到阈值后被锁定，
+//! 每次再被锁定时时长翻倍（封顶 `max_lockout_secs`）喵。
+//!
+//! - 锁定期间该 IP 的所有受保护请求直接返回 429 + `Retry-After`
+//! - 认证成功后清除该 IP 的失败记录与锁定等级
+//! - 失败与锁定事件写入审计日志（JSON Lines），挂载 `AuditLog` 时同时写入防篡改审计日志
+//!
+//! ```toml
+//! [gateway_auth]
+//! max_failures = 5
+//! window_secs = 300
+//! lockout_secs = 30
+//! max_lockout_secs = 3600
+//! ```
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::security::{AuditEvent, AuditKind, AuditLog, AuditStatus};
+
+/// 最多跟踪的来源数量（超过时清理已失效的记录）
+const MAX_TRACKED_SOURCES: usize = 10_000;
+
+fn default_enabled() -> bool {
+    true
+}
+fn default_max_failures() -> u32 {
+    5
+}
+fn default_window_secs() -> u64 {
+    300
+}
+fn default_lockout_secs() -> u64 {
+    30
+}
+fn default_max_lockout_secs() -> u64 {
+    3600
+}
+
+/// 🔒 SAFETY: 认证限流配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthThrottleConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 窗口内允许的失败次数
+    #[serde(default = "default_max_failures")]
+    pub max_failures: u32,
+    /// 失败计数窗口（秒）
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// 首次锁定时长（秒），之后每次翻倍
+    #[serde(default = "default_lockout_secs")]
+    pub lockout_secs: u64,
+    /// 锁定时长上限（秒）
+    #[serde(default = "default_max_lockout_secs")]
+    pub max_lockout_secs: u64,
+}
+
+impl Default for AuthThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_failures: default_max_failures(),
+            window_secs: default_window_secs(),
+            lockout_secs: default_lockout_secs(),
+            max_lockout_secs: default_max_lockout_secs(),
+        }
+    }
+}
+
+impl AuthThrottleConfig {
+    /// 第 `level` 次锁定的时长喵（从 0 开始）
+    fn lockout_for(&self, level: u32) -> Duration {
+        let secs = self
+            .lockout_secs
+            .saturating_mul(1u64 << level.min(32))
+            .min(self.max_lockout_secs);
+        Duration::from_secs(secs)
+    }
+}
+
+/// 🔒 SAFETY: 被限制的认证方式喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthKind {
+    Bearer,
+    Pairing,
+    Webhook,
+}
+
+impl AuthKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bearer => "bearer",
+            Self::Pairing => "pairing",
+            Self::Webhook => "webhook",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SourceState {
+    failures: u32,
+    window_start: Instant,
+    /// 已被锁定的次数（决定下次锁定时长）
+    level: u32,
+    locked_until: Option<Instant>,
+    last_seen: Instant,
+}
+
+/// 🔒 SAFETY: 按来源 IP 的认证失败限流器喵
+#[derive(Debug)]
+pub struct AuthThrottle {
+    config: AuthThrottleConfig,
+    sources: Mutex<HashMap<IpAddr, SourceState>>,
+    audit_path: Option<PathBuf>,
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+impl AuthThrottle {
+    pub fn new(config: AuthThrottleConfig) -> Self {
+        Self {
+            config,
+            sources: Mutex::new(HashMap::new()),
+            audit_path: None,
+            audit_log: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 把失败与锁定事件追加到审计日志喵
+    pub fn with_audit_log(mut self, path: PathBuf) -> Self {
+        self.audit_path = Some(path);
+        self
+    }
+
+    /// 🔒 SAFETY: 失败与锁定事件同时写入哈希链审计日志喵
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(audit);
+        self
+    }
+
+    /// 该来源是否处于锁定中喵（返回剩余锁定时长）
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        if !self.config.enabled {
+            return None;
+        }
+        let sources = self.sources.lock().unwrap();
+        let until = sources.get(&ip)?.locked_until?;
+        (until > now).then(|| until - now)
+    }
+
+    /// 记录一次认证失败喵（触发锁定时返回锁定时长）
+    pub fn record_failure(&self, ip: IpAddr, kind: AuthKind, now: Instant) -> Option<Duration> {
+        if !self.config.enabled {
+            return None;
+        }
+        let window = Duration::from_secs(self.config.window_secs);
+        let mut sources = self.sources.lock().unwrap();
+        if sources.len() >= MAX_TRACKED_SOURCES {
+            let stale = window + Duration::from_secs(self.config.max_lockout_secs);
+            sources.retain(|_, s| now.saturating_duration_since(s.last_seen) < stale);
+        }
+
+        let state = sources.entry(ip).or_insert(SourceState {
+            failures: 0,
+            window_start: now,
+            level: 0,
+            locked_until: None,
+            last_seen: now,
+        });
+        // 长时间没有失败，锁定等级归零喵
+        let idle = now.saturating_duration_since(state.last_seen);
+        if idle >= window + Duration::from_secs(self.config.max_lockout_secs) {
+            state.level = 0;
+        }
+        if now.saturating_duration_since(state.window_start) >= window {
+            state.failures = 0;
+            state.window_start = now;
+        }
+        state.failures += 1;
+        state.last_seen = now;
+        let failures = state.failures;
+
+        let lockout = (failures >= self.config.max_failures).then(|| {
+            let lockout = self.config.lockout_for(state.level);
+            state.level += 1;
+            state.failures = 0;
+            state.window_start = now;
+            state.locked_until = Some(now + lockout);
+            lockout
+        });
+        drop(sources);
+
+        self.audit(ip, "auth_failed", kind, serde_json::json!({ "failures": failures }));
+        if let Some(lockout) = lockout {
+            warn!("🚧 Gateway locked out {} for {}s after repeated {} failures", ip, lockout.as_secs(), kind.as_str());
+            self.audit(ip, "locked_out", kind, serde_json::json!({ "lockout_secs": lockout.as_secs() }));
+        }
+        lockout
+    }
+
+    /// 认证成功，清除该来源的记录喵
+    pub fn record_success(&self, ip: IpAddr) {
+        self.sources.lock().unwrap().remove(&ip);
+    }
+
+    /// 🔒 SAFETY: 写审计日志喵
+    fn audit(&self, ip: IpAddr, event: &str, kind: AuthKind, detail: serde_json::Value) {
+        if let Some(audit) = &self.audit_log {
+            audit.record_or_warn(
+                &AuditEvent::new(AuditKind::Auth, &format!("ip:{}", ip), &format!("gateway.{}", event))
+                    .with_arguments(serde_json::json!({ "auth": kind.as_str(), "detail": detail }))
+                    .with_status(AuditStatus::Denied),
+            );
+        }
+        let Some(path) = &self.audit_path else {
+            return;
+        };
+        let line = serde_json::json!({
+            "time": Utc::now().to_rfc3339(),
+            "event": event,
+            "source": ip.to_string(),
+            "auth": kind.as_str(),
+            "detail": detail,
+        });
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            warn!("Failed to write gateway audit log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_lockout() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = dir.path().join("gateway_audit.log");
+        let throttle = AuthThrottle::new(AuthThrottleConfig {
+            max_failures: 3,
+            lockout_secs: 10,
+            max_lockout_secs: 25,
+            ..Default::default()
+        })
+        .with_audit_log(audit.clone());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+        let t0 = Instant::now();
+
+        assert_eq!(throttle.record_failure(ip, AuthKind::Bearer, t0), None);
+        assert_eq!(throttle.record_failure(ip, AuthKind::Pairing, t0), None);
+        assert_eq!(throttle.record_failure(ip, AuthKind::Bearer, t0), Some(Duration::from_secs(10)));
+        assert_eq!(throttle.check(ip, t0 + Duration::from_secs(4)), Some(Duration::from_secs(6)));
+        assert_eq!(throttle.check(other, t0), None);
+        assert_eq!(throttle.check(ip, t0 + Duration::from_secs(10)), None);
+
+        // 第二次锁定翻倍，第三次封顶喵
+        let t1 = t0 + Duration::from_secs(11);
+        for _ in 0..2 {
+            throttle.record_failure(ip, AuthKind::Bearer, t1);
+        }
+        assert_eq!(throttle.record_failure(ip, AuthKind::Bearer, t1), Some(Duration::from_secs(20)));
+        let t2 = t1 + Duration::from_secs(21);
+        for _ in 0..2 {
+            throttle.record_failure(ip, AuthKind::Bearer, t2);
+        }
+        assert_eq!(throttle.record_failure(ip, AuthKind::Bearer, t2), Some(Duration::from_secs(25)));
+
+        throttle.record_success(ip);
+        assert_eq!(throttle.check(ip, t2), None);
+
+        let log = std::fs::read_to_string(audit).unwrap();
+        assert_eq!(log.lines().filter(|l| l.contains("\"locked_out\"")).count(), 3);
+        assert!(log.contains("\"pairing\""));
+    }
+}
@@ -7,21 +7,73 @@
 /// - 事件类型路由
 /// - 异步事件处理
 /// - 错误重试队列
+/// - `/webhook/:source` 入站集成：按来源校验 HMAC 签名（GitHub / Stripe / 十六进制），
+///   转换为 `ChannelEvent` 后交给 Agent（可指定技能）
 ///
-/// 🔒 SAFETY: Webhook 端点需要 Bearer Token 认证
+/// - 出站通知：Agent 事件（回复 / 工具失败 / 服务不可用 / 超出预算）推送到配置的 URL，
+///   带 HMAC 签名，失败按指数退避重试，投递结果写入 telemetry
+///
+/// 🔒 SAFETY: 入站 Webhook 以签名代替 Bearer Token 认证，签名错误计入来源 IP 的失败次数
+///
+/// ```toml
+/// [webhooks.github]
+/// secret = "enc:k1:..."
+/// signature = "github"
+/// skill = "triage"
+///
+/// [[outbound_webhooks]]
+/// url = "https://hooks.example.com/neko"
+/// secret = "enc:k1:..."
+/// events = ["tool_failure", "service_down"]
+/// ```
 ///
 /// 实现者: 诺诺 (Nono) ⚡
 use axum::{
-    extract::{Request, State},
+    body::Bytes,
+    extract::{ConnectInfo, Path, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ring::hmac;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{mpsc, RwLock};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use super::backend::ChatBackend;
+use super::server::{locked_out, GatewayState};
+use super::throttle::AuthKind;
+use crate::core::traits::{ChannelEvent, Message};
+use crate::security::crypto::decode_hex;
+use crate::skills::Skill;
+use crate::telemetry::MetricsRecorder;
+
+/// 事件消息中附带的负载最大字符数
+const MAX_PAYLOAD_CHARS: usize = 4000;
+
+fn default_tolerance_secs() -> u64 {
+    300
+}
+fn default_max_retries() -> u32 {
+    5
+}
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+/// 出站重试间隔上限
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
+/// 出站投递的 telemetry 指标名
+const DELIVERY_METRIC: &str = "webhook.delivery";
+const RETRY_METRIC: &str = "webhook.retry";
+
 /// 🔒 SAFETY: Webhook 配置结构体喵
 #[derive(Debug, Clone)]
 pub struct WebhookConfig {
@@ -65,6 +117,7 @@ pub enum WebhookEventType {
 impl WebhookEventType {
     /// 🔒 SAFETY: 从字符串解析事件类型喵
     /// 支持自定义格式，提取后转换为标准类型
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "discord.message" => Some(WebhookEventType::DiscordMessage),
@@ -127,6 +180,8 @@ impl IntoResponse for WebhookErrorResponse {
             "UNAUTHORIZED" => StatusCode::UNAUTHORIZED,
             "INVALID_SIGNATURE" => StatusCode::FORBIDDEN,
             "INVALID_PAYLOAD" => StatusCode::BAD_REQUEST,
+            "NOT_FOUND" => StatusCode::NOT_FOUND,
+            "UNAVAILABLE" => StatusCode::SERVICE_UNAVAILABLE,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -220,6 +275,32 @@ impl WebhookManager {
         headers: HeaderMap,
         body: String,
     ) -> Result<Json<WebhookResponse>, WebhookErrorResponse> {
+        let event = self.parse_event(&headers, &body)?;
+        let event_id = event.event_id.clone();
+
+        // 发送到处理队列
+        if let Err(e) = self.event_sender.send(event.clone()).await {
+            error!("Failed to enqueue webhook event: {}", e);
+
+            // 添加到重试队列
+            let mut retry = self.retry_queue.write().await;
+            retry.push(event);
+        }
+
+        Ok(Json(WebhookResponse {
+            success: true,
+            message: "Webhook received".to_string(),
+            event_id,
+        }))
+    }
+
+    /// 🔒 SAFETY: 从请求头与请求体解析事件喵
+    /// 输入完全不可信：任何畸形输入都返回错误，不会 panic
+    pub fn parse_event(
+        &self,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Result<WebhookEvent, WebhookErrorResponse> {
         // 提取事件类型
         let event_type_header = headers
             .get("x-event-type")
@@ -256,34 +337,19 @@ impl WebhookManager {
 
         // 解析请求体
         let event_data: serde_json::Value =
-            serde_json::from_str(&body).map_err(|_| WebhookErrorResponse {
+            serde_json::from_str(body).map_err(|_| WebhookErrorResponse {
                 code: "INVALID_PAYLOAD".to_string(),
                 message: "Invalid JSON payload".to_string(),
                 request_id: event_id.to_string(),
             })?;
 
         // 创建事件
-        let event = WebhookEvent {
+        Ok(WebhookEvent {
             event_type: event_type_header.to_string(),
             event_id: event_id.to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
             data: event_data,
-        };
-
-        // 发送到处理队列
-        if let Err(e) = self.event_sender.send(event.clone()).await {
-            error!("Failed to enqueue webhook event: {}", e);
-
-            // 添加到重试队列
-            let mut retry = self.retry_queue.write().await;
-            retry.push(event);
-        }
-
-        Ok(Json(WebhookResponse {
-            success: true,
-            message: "Webhook received".to_string(),
-            event_id: event_id.to_string(),
-        }))
+        })
     }
 
     /// 🔒 SAFETY: 处理重试队列喵
@@ -308,6 +374,515 @@ impl WebhookManager {
     }
 }
 
+/// 🔒 SAFETY: 入站 Webhook 签名格式喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStyle {
+    /// `X-Hub-Signature-256: sha256=<hex>`
+    #[default]
+    Github,
+    /// `Stripe-Signature: t=<unix>,v1=<hex>`，签名内容为 `<t>.<body>`
+    Stripe,
+    /// `X-Signature: <hex>`（可带 `sha256=` 前缀）
+    Hex,
+}
+
+/// 🔒 SAFETY: 入站 Webhook 来源配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSourceConfig {
+    /// HMAC-SHA256 密钥（明文或 `enc:` 密文）
+    pub secret: String,
+    #[serde(default)]
+    pub signature: SignatureStyle,
+    /// 交给指定技能处理（省略时直接交给 Agent）
+    #[serde(default)]
+    pub skill: Option<String>,
+    /// Stripe 时间戳允许的偏差（秒）
+    #[serde(default = "default_tolerance_secs")]
+    pub tolerance_secs: u64,
+}
+
+/// 🔒 SAFETY: HMAC-SHA256 签名喵（`parts` 依次拼接为消息）
+pub(crate) fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut context = hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA256, key));
+    for part in parts {
+        context.update(part);
+    }
+    let mut tag = [0u8; 32];
+    tag.copy_from_slice(context.sign().as_ref());
+    tag
+}
+
+/// 🔒 SAFETY: 校验十六进制 HMAC-SHA256 签名喵（`ring::hmac::verify` 常数时间比较）
+fn hmac_sha256_matches(key: &[u8], message: &[u8], hex: &str) -> bool {
+    decode_hex(hex.trim())
+        .is_some_and(|tag| hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, key), message, &tag).is_ok())
+}
+
+/// 🔒 SAFETY: 按来源的签名格式校验请求喵
+///
+/// ## Arguments
+/// * `now` - 当前 Unix 时间（秒），用于 Stripe 的重放检查
+pub fn verify_signature(
+    source: &WebhookSourceConfig,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> Result<(), String> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| format!("Missing {} header", name))
+    };
+    let key = source.secret.as_bytes();
+    let valid = match source.signature {
+        SignatureStyle::Github => {
+            let value = header("x-hub-signature-256")?;
+            let hex = value.strip_prefix("sha256=").ok_or("Unsupported signature algorithm")?;
+            hmac_sha256_matches(key, body, hex)
+        }
+        SignatureStyle::Hex => {
+            let value = header("x-signature")?;
+            hmac_sha256_matches(key, body, value.strip_prefix("sha256=").unwrap_or(value))
+        }
+        SignatureStyle::Stripe => {
+            let value = header("stripe-signature")?;
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for (name, value) in value.split(',').filter_map(|part| part.trim().split_once('=')) {
+                match name {
+                    "t" => timestamp = value.parse::<i64>().ok(),
+                    "v1" => signatures.push(value),
+                    _ => {}
+                }
+            }
+            let timestamp = timestamp.ok_or("Missing timestamp in Stripe-Signature")?;
+            if now.abs_diff(timestamp) > source.tolerance_secs {
+                return Err("Signature timestamp outside tolerance".to_string());
+            }
+            let signed = [format!("{}.", timestamp).as_bytes(), body].concat();
+            signatures.iter().any(|hex| hmac_sha256_matches(key, &signed, hex))
+        }
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err("Signature mismatch".to_string())
+    }
+}
+
+/// 🔒 SAFETY: 把入站负载转换为渠道事件喵
+///
+/// `source` 为 `webhook:<来源名>`，metadata 中保留事件类型、投递 ID 与完整负载
+pub fn to_channel_event(name: &str, style: SignatureStyle, headers: &HeaderMap, payload: &Value) -> ChannelEvent {
+    let header = |key: &str| headers.get(key).and_then(|h| h.to_str().ok()).map(String::from);
+    let field = |pointer: &str| payload.pointer(pointer).and_then(Value::as_str).map(String::from);
+
+    let (event, sender, delivery) = match style {
+        SignatureStyle::Github => {
+            let kind = header("x-github-event").unwrap_or_else(|| "unknown".to_string());
+            let event = match field("/action") {
+                Some(action) => format!("{}.{}", kind, action),
+                None => kind,
+            };
+            (event, field("/sender/login"), header("x-github-delivery"))
+        }
+        SignatureStyle::Stripe => (
+            field("/type").unwrap_or_else(|| "unknown".to_string()),
+            field("/account"),
+            field("/id"),
+        ),
+        SignatureStyle::Hex => (
+            header("x-event-type").unwrap_or_else(|| "generic".to_string()),
+            field("/sender"),
+            header("x-event-id"),
+        ),
+    };
+    let sender = sender.unwrap_or_else(|| name.to_string());
+
+    let mut body = serde_json::to_string_pretty(payload).unwrap_or_default();
+    if body.chars().count() > MAX_PAYLOAD_CHARS {
+        body = body.chars().take(MAX_PAYLOAD_CHARS).collect::<String>() + "\n… (truncated)";
+    }
+    ChannelEvent {
+        source: format!("webhook:{}", name),
+        sender_id: sender.clone(),
+        message: format!("[webhook {}] {} event from {}\n```json\n{}\n```", name, event, sender, body),
+        metadata: Some(serde_json::json!({
+            "event": event,
+            "delivery": delivery,
+            "payload": payload,
+        })),
+    }
+}
+
+/// 🔒 SAFETY: 入站 Webhook 集成喵（来源配置 + 处理事件的 Agent）
+pub struct InboundWebhooks {
+    /// 来源名 → 配置（密钥已解密）
+    sources: BTreeMap<String, WebhookSourceConfig>,
+    skills: BTreeMap<String, Skill>,
+    agent: Option<Arc<ChatBackend>>,
+    system_prompt: Option<String>,
+}
+
+impl std::fmt::Debug for InboundWebhooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InboundWebhooks")
+            .field("sources", &self.sources.keys().collect::<Vec<_>>())
+            .field("skills", &self.skills.keys().collect::<Vec<_>>())
+            .field("agent", &self.agent.is_some())
+            .finish()
+    }
+}
+
+impl InboundWebhooks {
+    /// 🔒 SAFETY: 创建入站集成喵（指定的技能不存在时报错）
+    pub fn new(sources: BTreeMap<String, WebhookSourceConfig>, skills: Vec<Skill>) -> Result<Self, String> {
+        let skills: BTreeMap<String, Skill> = skills.into_iter().map(|s| (s.name.clone(), s)).collect();
+        for (name, source) in &sources {
+            if source.secret.is_empty() {
+                return Err(format!("webhooks.{}: secret is empty", name));
+            }
+            if let Some(skill) = source.skill.as_ref().filter(|s| !skills.contains_key(*s)) {
+                return Err(format!("webhooks.{}: unknown skill {}", name, skill));
+            }
+        }
+        Ok(Self {
+            sources,
+            skills,
+            agent: None,
+            system_prompt: None,
+        })
+    }
+
+    /// 🔒 SAFETY: 处理事件的 Agent 喵（未设置时使用 Gateway 的对话后端）
+    pub fn with_agent(mut self, agent: Arc<ChatBackend>, system_prompt: &str) -> Self {
+        self.agent = Some(agent);
+        self.system_prompt = Some(system_prompt.to_string());
+        self
+    }
+
+    pub fn source(&self, name: &str) -> Option<&WebhookSourceConfig> {
+        self.sources.get(name)
+    }
+
+    /// 交给 Agent 的消息喵（指定技能时附带技能说明）
+    pub fn messages(&self, source: &WebhookSourceConfig, event: &ChannelEvent) -> Vec<Message> {
+        let mut messages = Vec::new();
+        if let Some(prompt) = self.system_prompt.as_ref().filter(|p| !p.is_empty()) {
+            messages.push(Message::system(prompt.clone()));
+        }
+        if let Some(skill) = source.skill.as_ref().and_then(|name| self.skills.get(name)) {
+            let mut instruction = format!(
+                "Handle the following webhook event with the `{}` skill: {}",
+                skill.name, skill.description
+            );
+            if let Some(command) = &skill.command {
+                instruction.push_str(&format!("\nSkill command: {}", command));
+            }
+            messages.push(Message::system(instruction));
+        }
+        messages.push(Message::user(event.message.clone()));
+        messages
+    }
+}
+
+fn webhook_error(code: &str, message: impl Into<String>, request_id: &str) -> Response {
+    WebhookErrorResponse {
+        code: code.to_string(),
+        message: message.into(),
+        request_id: request_id.to_string(),
+    }
+    .into_response()
+}
+
+/// 🔒 SAFETY: 入站 Webhook 端点喵
+///
+/// 签名通过后立即返回 202，事件在后台交给 Agent 处理（发送方通常只等几秒）；
+/// 后台任务持有排空凭证，关闭时等它处理完，宽限期过后取消
+pub async fn inbound_webhook(
+    State(state): State<Arc<GatewayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request_id = Uuid::new_v4().to_string();
+    if let Some(remaining) = state.throttle.check(addr.ip(), Instant::now()) {
+        return locked_out(remaining);
+    }
+    let Some((webhooks, source)) = state
+        .webhooks
+        .as_ref()
+        .and_then(|w| Some((w, w.source(&name)?)))
+    else {
+        return webhook_error("NOT_FOUND", format!("Unknown webhook source {}", name), &request_id);
+    };
+
+    if let Err(reason) = verify_signature(source, &headers, &body, chrono::Utc::now().timestamp()) {
+        warn!("Rejected webhook from {} for source {}: {}", addr.ip(), name, reason);
+        if let Some(lockout) = state.throttle.record_failure(addr.ip(), AuthKind::Webhook, Instant::now()) {
+            return locked_out(lockout);
+        }
+        return webhook_error("INVALID_SIGNATURE", reason, &request_id);
+    }
+
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(_) => return webhook_error("INVALID_PAYLOAD", "Invalid JSON payload", &request_id),
+    };
+    let Some(agent) = webhooks.agent.clone().or_else(|| state.backend.clone()) else {
+        return webhook_error("UNAVAILABLE", "No agent configured for webhooks", &request_id);
+    };
+
+    let guard = match &state.shutdown {
+        Some(coordinator) => match coordinator.try_begin() {
+            Some(guard) => Some(guard),
+            None => return webhook_error("UNAVAILABLE", "Gateway is shutting down", &request_id),
+        },
+        None => None,
+    };
+
+    let event = to_channel_event(&name, source.signature, &headers, &payload);
+    let messages = webhooks.messages(source, &event);
+    info!("Webhook {} accepted from {}: {}", name, event.sender_id, request_id);
+    let event_id = request_id.clone();
+    tokio::spawn(async move {
+        let work = agent.complete(messages);
+        let result = match guard {
+            Some(guard) => guard.run(work).await,
+            None => Some(work.await),
+        };
+        match result {
+            Some(Ok(reply)) => debug!("Webhook {} handled ({}): {} chars", event.source, event_id, reply.chars().count()),
+            Some(Err(e)) => error!("Webhook {} failed ({}): {}", event.source, event_id, e),
+            None => warn!("关闭宽限期已过，取消 Webhook {} 的处理（{}）喵", event.source, event_id),
+        }
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(WebhookResponse {
+            success: true,
+            message: "Webhook accepted".to_string(),
+            event_id: request_id,
+        }),
+    )
+        .into_response()
+}
+
+/// 🔒 SAFETY: 入站 Webhook 路由喵
+pub fn create_inbound_routes() -> Router<Arc<GatewayState>> {
+    Router::new().route("/webhook/:source", post(inbound_webhook))
+}
+
+/// 🔒 SAFETY: 可推送的 Agent 事件类型喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentEventKind {
+    /// Agent 完成一次回复
+    AgentResponse,
+    /// 工具执行失败
+    ToolFailure,
+    /// 上游 provider 不可用
+    ServiceDown,
+    /// 超出费用预算
+    BudgetExceeded,
+}
+
+impl AgentEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AgentResponse => "agent_response",
+            Self::ToolFailure => "tool_failure",
+            Self::ServiceDown => "service_down",
+            Self::BudgetExceeded => "budget_exceeded",
+        }
+    }
+}
+
+/// 🔒 SAFETY: 出站 Webhook 配置喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundWebhookConfig {
+    pub url: String,
+    /// 签名密钥（明文或 `enc:` 密文），设置后附带 `X-Nekoclaw-Signature: sha256=<hex>`
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// 订阅的事件（为空时推送全部）
+    #[serde(default)]
+    pub events: Vec<AgentEventKind>,
+    /// 首次失败后的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试间隔（毫秒），之后每次翻倍（封顶 60 秒）
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+impl OutboundWebhookConfig {
+    fn wants(&self, kind: AgentEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// 指标标签中使用的目标（只保留主机名，不泄露路径中的 Token）
+    fn target(&self) -> String {
+        reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from))
+            .unwrap_or_else(|| "invalid".to_string())
+    }
+}
+
+/// 单次投递的结果喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeliveryOutcome {
+    Delivered,
+    /// 可重试的失败（网络错误、5xx、429）
+    Retry,
+    /// 不可重试的失败（其他 4xx）
+    Rejected,
+}
+
+/// 🔒 SAFETY: 出站 Webhook 通知器喵
+///
+/// 可廉价克隆；`notify` 立即返回，投递在后台任务中完成
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    targets: Arc<Vec<OutboundWebhookConfig>>,
+    client: reqwest::Client,
+    recorder: Option<MetricsRecorder>,
+    pending: Arc<std::sync::Mutex<Vec<tokio::task::JoinHandle<()>>>>,
+}
+
+impl std::fmt::Debug for WebhookNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookNotifier")
+            .field("targets", &self.targets.iter().map(|t| t.target()).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl WebhookNotifier {
+    /// 🔒 SAFETY: 创建通知器喵（密钥需已解密）
+    pub fn new(targets: Vec<OutboundWebhookConfig>) -> Self {
+        Self {
+            targets: Arc::new(targets),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            recorder: None,
+            pending: Arc::new(std::sync::Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 投递结果写入 telemetry喵（`webhook.delivery` / `webhook.retry`）
+    pub fn with_recorder(mut self, recorder: MetricsRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 🔒 SAFETY: 推送一个事件喵（不等待投递完成）
+    pub fn notify(&self, kind: AgentEventKind, data: Value) {
+        let targets: Vec<OutboundWebhookConfig> = self.targets.iter().filter(|t| t.wants(kind)).cloned().collect();
+        if targets.is_empty() {
+            return;
+        }
+        let body = serde_json::json!({
+            "id": Uuid::new_v4().to_string(),
+            "event": kind.as_str(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "data": data,
+        })
+        .to_string();
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|handle| !handle.is_finished());
+        for target in targets {
+            let notifier = self.clone();
+            let body = body.clone();
+            pending.push(tokio::spawn(async move {
+                notifier.deliver(&target, kind, &body).await;
+            }));
+        }
+    }
+
+    /// 等待进行中的投递喵（CLI 退出前调用，超时后放弃）
+    pub async fn flush(&self, timeout: std::time::Duration) {
+        let handles: Vec<_> = self.pending.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect();
+        if tokio::time::timeout(timeout, futures::future::join_all(handles)).await.is_err() {
+            warn!("Outbound webhooks still pending after {:?}, giving up", timeout);
+        }
+    }
+
+    /// 按指数退避投递到单个目标喵
+    ///
+    /// ## Returns
+    /// 是否投递成功
+    async fn deliver(&self, target: &OutboundWebhookConfig, kind: AgentEventKind, body: &str) -> bool {
+        let host = target.target();
+        let mut delay = std::time::Duration::from_millis(target.backoff_ms);
+        let mut attempt = 0;
+        let outcome = loop {
+            let outcome = self.send(target, kind, body).await;
+            if outcome != DeliveryOutcome::Retry || attempt >= target.max_retries {
+                break outcome;
+            }
+            attempt += 1;
+            if let Some(recorder) = &self.recorder {
+                recorder.counter(RETRY_METRIC, 1.0, &[("target", &host), ("event", kind.as_str())]);
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_BACKOFF);
+        };
+
+        let delivered = outcome == DeliveryOutcome::Delivered;
+        if !delivered {
+            warn!("Outbound webhook {} to {} failed after {} attempts", kind.as_str(), host, attempt + 1);
+        }
+        if let Some(recorder) = &self.recorder {
+            let status = if delivered { "delivered" } else { "failed" };
+            recorder.counter(
+                DELIVERY_METRIC,
+                1.0,
+                &[("target", &host), ("event", kind.as_str()), ("status", status)],
+            );
+        }
+        delivered
+    }
+
+    async fn send(&self, target: &OutboundWebhookConfig, kind: AgentEventKind, body: &str) -> DeliveryOutcome {
+        let mut request = self
+            .client
+            .post(&target.url)
+            .header("content-type", "application/json")
+            .header("x-nekoclaw-event", kind.as_str())
+            .body(body.to_string());
+        if let Some(secret) = target.secret.as_ref().filter(|s| !s.is_empty()) {
+            let signature: String = hmac_sha256(secret.as_bytes(), &[body.as_bytes()])
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            request = request.header("x-nekoclaw-signature", format!("sha256={}", signature));
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => DeliveryOutcome::Delivered,
+            Ok(response) if response.status().is_server_error() || response.status().as_u16() == 429 => {
+                DeliveryOutcome::Retry
+            }
+            Ok(response) => {
+                warn!("Outbound webhook rejected by {}: {}", target.target(), response.status());
+                DeliveryOutcome::Rejected
+            }
+            Err(e) => {
+                warn!("Outbound webhook to {} failed: {}", target.target(), e);
+                DeliveryOutcome::Retry
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -342,6 +917,139 @@ mod tests {
         assert_eq!(WebhookEventType::Generic.as_str(), "generic");
     }
 
+    proptest::proptest! {
+        #[test]
+        fn prop_parse_event_never_panics(
+            body in ".{0,256}",
+            event_type in "[ -~]{0,32}",
+            event_id in "[ -~]{0,32}",
+        ) {
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let _guard = runtime.enter();
+            let manager = WebhookManager::new(WebhookConfig::default());
+            let mut headers = HeaderMap::new();
+            headers.insert("x-event-type", event_type.parse().unwrap());
+            headers.insert("x-event-id", event_id.parse().unwrap());
+
+            match manager.parse_event(&headers, &body) {
+                Ok(event) => {
+                    proptest::prop_assert_eq!(event.event_type, event_type);
+                    proptest::prop_assert!(serde_json::from_str::<serde_json::Value>(&body).is_ok());
+                }
+                Err(e) => proptest::prop_assert_eq!(e.code, "INVALID_PAYLOAD"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        let tag = hmac_sha256(b"Jefe", &[b"what do ya ", b"want for nothing?"]);
+        let hex: String = tag.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        assert!(hmac_sha256_matches(b"Jefe", b"what do ya want for nothing?", &hex));
+        assert!(!hmac_sha256_matches(b"Jefe", b"what do ya want for nothing?", &hex[..62]));
+        assert!(!hmac_sha256_matches(b"Jefe", b"what do ya want for nothing?", "zz"));
+    }
+
+    #[test]
+    fn test_verify_signature_styles() {
+        let mut source = WebhookSourceConfig {
+            secret: "It's a Secret to Everybody".to_string(),
+            signature: SignatureStyle::Github,
+            skill: None,
+            tolerance_secs: 300,
+        };
+        let headers = |name: &'static str, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, value.parse().unwrap());
+            headers
+        };
+        let body = b"Hello, World!";
+
+        // GitHub 文档中的示例签名喵
+        let github = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_signature(&source, &headers("x-hub-signature-256", github), body, 0).is_ok());
+        assert!(verify_signature(&source, &headers("x-hub-signature-256", github), b"Hello, World?", 0).is_err());
+        assert!(verify_signature(&source, &HeaderMap::new(), body, 0).is_err());
+
+        source.signature = SignatureStyle::Hex;
+        assert!(verify_signature(&source, &headers("x-signature", &github[7..]), body, 0).is_ok());
+
+        source.signature = SignatureStyle::Stripe;
+        let signed: String = hmac_sha256(source.secret.as_bytes(), &[b"1700000000.", body])
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let stripe = headers("stripe-signature", &format!("t=1700000000,v1=deadbeef,v1={}", signed));
+        assert!(verify_signature(&source, &stripe, body, 1_700_000_100).is_ok());
+        assert!(verify_signature(&source, &stripe, body, 1_700_000_301).is_err());
+    }
+
+    #[test]
+    fn test_github_payload_to_channel_event() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-github-event", "issues".parse().unwrap());
+        headers.insert("x-github-delivery", "d-1".parse().unwrap());
+        let payload = serde_json::json!({ "action": "opened", "sender": { "login": "octocat" } });
+
+        let event = to_channel_event("github", SignatureStyle::Github, &headers, &payload);
+        assert_eq!(event.source, "webhook:github");
+        assert_eq!(event.sender_id, "octocat");
+        assert!(event.message.starts_with("[webhook github] issues.opened event from octocat"));
+        let metadata = event.metadata.unwrap();
+        assert_eq!(metadata["delivery"], "d-1");
+        assert_eq!(metadata["payload"]["action"], "opened");
+    }
+
+    #[tokio::test]
+    async fn test_outbound_webhook_retries_and_signs() {
+        type Received = Arc<std::sync::Mutex<Vec<(HeaderMap, String)>>>;
+        async fn receiver(State(received): State<Received>, headers: HeaderMap, body: String) -> StatusCode {
+            let mut received = received.lock().unwrap();
+            received.push((headers, body));
+            // 第一次投递失败，触发重试喵
+            if received.len() == 1 {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::OK
+            }
+        }
+
+        let received: Received = Default::default();
+        let app = Router::new().route("/hook", post(receiver)).with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let target = |events: Vec<AgentEventKind>| OutboundWebhookConfig {
+            url: url.clone(),
+            secret: Some("s3cret".to_string()),
+            events,
+            max_retries: 3,
+            backoff_ms: 10,
+        };
+        let notifier = WebhookNotifier::new(vec![
+            target(vec![AgentEventKind::ToolFailure]),
+            target(vec![AgentEventKind::ServiceDown]),
+        ]);
+        notifier.notify(AgentEventKind::ToolFailure, serde_json::json!({ "tool": "shell" }));
+        notifier.flush(std::time::Duration::from_secs(5)).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        assert_eq!(body, &received[0].1);
+        assert_eq!(headers["x-nekoclaw-event"], "tool_failure");
+        let expected: String = hmac_sha256(b"s3cret", &[body.as_bytes()])
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(headers["x-nekoclaw-signature"], format!("sha256={}", expected).as_str());
+        let payload: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "tool_failure");
+        assert_eq!(payload["data"]["tool"], "shell");
+    }
+
     #[tokio::test]
     async fn test_webhook_manager() {
         let config = WebhookConfig::default();
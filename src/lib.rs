@@ -0,0 +1,33 @@
+/*!
+ * Neko-Claw (猫爪核心) 库目标
+ *
+ * 二进制 `nekoclaw` 与 `fuzz/` 下的模糊测试共用这些模块喵
+ */
+
+pub mod auth;
+pub mod channels;
+// 配置模块目前只接入验证器（热重载）与 OpenClaw 迁移喵
+pub mod config {
+    #[allow(dead_code)]
+    pub mod validator;
+    pub mod migrate;
+}
+pub mod core;
+pub mod gateway;
+pub mod memory;
+// Agent 模块目前只有限额执行接入构建（runtime / session / context 尚未接入）喵
+pub mod agent {
+    pub mod limits;
+}
+// 性能模块目前只有上下文压缩接入 Agent（内存池 / 启动优化尚未接入构建）喵
+pub mod performance {
+    pub mod compress;
+}
+pub mod privacy;
+pub mod providers;
+pub mod security;
+pub mod service;
+pub mod skills;
+pub mod sync;
+pub mod telemetry;
+pub mod tools;
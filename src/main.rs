@@ -11,29 +11,25 @@
  * 🔐 SAFETY: 安全优先，集成所有安全模块喵
  */
 
+mod cli;
+
 use clap::{ArgAction, Parser, Subcommand};
-use std::io::{BufRead, Write};
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{debug, error, info, warn};
-
-mod auth;
-mod channels;
-mod core;
-mod gateway;
-mod memory;
-mod providers;
-mod security;
-mod service;
-mod skills;
-mod telemetry;
-mod tools;
+use tracing::{info, warn};
+
+use nekoclaw::{
+    agent, auth, channels, config, core, gateway, memory, privacy, providers, security, service,
+    skills, sync, telemetry, tools,
+};
 
 // 使用别名简化引用
 use crate::core::traits::*;
+use crate::core::{HookRegistry, PostProcessChain, SessionLanguages};
 use crate::skills::*;
 use crate::tools::*;
-use providers::{ChatRequest, Message as OpenAIMessage, OpenAIClient, OpenAIConfig};
+use providers::{OpenAIClient, OpenAIConfig};
 use service::ServiceManager;
 
 /// CLI 配置喵
@@ -55,6 +51,10 @@ struct Cli {
     #[arg(long)]
     config: Option<PathBuf>,
 
+    /// 工作区名称喵（也可通过 NEKOCLAW_WORKSPACE 指定）
+    #[arg(short, long)]
+    workspace: Option<String>,
+
     /// 超时时间（秒）喵
     #[arg(long, default_value = "30")]
     timeout: u64,
@@ -71,9 +71,17 @@ enum Commands {
     #[command(name = "agent")]
     Agent {
         /// 消息内容喵
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with_all = ["stdin", "file"])]
         message: Option<String>,
 
+        /// `-`：从 stdin 读取消息（`echo "..." | nekoclaw agent -`）喵
+        #[arg(value_name = "-", conflicts_with = "file")]
+        stdin: Option<String>,
+
+        /// 批量模式：文件中每行一条消息，各自独立请求，有失败时以非 0 退出码结束喵
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
         /// Provider 名称喵
         #[arg(short = 'P', long, default_value = "openai")]
         provider: String,
@@ -82,13 +90,41 @@ enum Commands {
         #[arg(short = 'M', long)]
         model: Option<String>,
 
-        /// 最大 Token 数喵
-        #[arg(long, default_value = "4096")]
-        max_tokens: usize,
+        /// 最大 Token 数（覆盖预设）喵
+        #[arg(long)]
+        max_tokens: Option<u32>,
+
+        /// Temperature 值（覆盖预设）喵
+        #[arg(long)]
+        temperature: Option<f32>,
+
+        /// 参数预设名称（配置 presets 中定义）喵
+        #[arg(long)]
+        preset: Option<String>,
+
+        /// Agent 名称（配置 agents.agent 中定义，默认 agents.default）喵
+        #[arg(short = 'A', long)]
+        agent: Option<String>,
+
+        /// 无痕模式（不保存任何内容，写入仅限临时目录）喵
+        #[arg(long, action = ArgAction::SetTrue, conflicts_with = "session")]
+        incognito: bool,
+
+        /// 会话名：已存在时恢复之前的对话，否则以此名字新建喵
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// 工具提示词模式（覆盖配置 tool_prompt.mode）喵
+        #[arg(long, value_enum)]
+        tool_prompt: Option<ToolPromptMode>,
 
-        /// Temperature 值喵
-        #[arg(long, default_value = "0.7")]
-        temperature: f32,
+        /// 危险工具（fs_write / shell 等）不再逐次确认，直接执行喵
+        #[arg(short = 'y', long, action = ArgAction::SetTrue)]
+        yes: bool,
+
+        /// 输出格式（json = 每行一个 JSON 事件，日志改写到 stderr）喵
+        #[arg(long, value_enum, default_value = "text")]
+        output: core::AgentOutput,
     },
 
     /// Gateway 模式（启动 Webhook 服务器）
@@ -109,6 +145,9 @@ enum Commands {
         /// Webhook 路径喵
         #[arg(long, default_value = "/webhook")]
         webhook_path: String,
+
+        #[command(subcommand)]
+        action: Option<GatewayAction>,
     },
 
     /// Daemon 模式（长期运行的自主运行时）
@@ -150,13 +189,45 @@ enum Commands {
         #[arg(long)]
         store: Option<String>,
 
-        /// 删除记忆喵
+        /// 新记忆的重要度（0~1，默认 0.5；配合 --store）喵
+        #[arg(long, requires = "store")]
+        importance: Option<f32>,
+
+        /// 删除记忆（ID 或唯一前缀）喵
         #[arg(long)]
         delete: Option<String>,
 
         /// 列出所有记忆喵
         #[arg(long, action = ArgAction::SetTrue)]
         list: bool,
+
+        /// 为缺少 embedding（或换了 embedding 模型）的记忆重新向量化喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        reindex: bool,
+
+        /// 清理综合得分低于 memory.scoring.prune_threshold 的记忆喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        prune: bool,
+
+        /// 只列出将被清理的记忆，不删除（配合 --prune）喵
+        #[arg(long, action = ArgAction::SetTrue, requires = "prune")]
+        dry_run: bool,
+
+        /// metadata 过滤条件 key=value（可重复；配合 --store 时写入 metadata）喵
+        #[arg(long = "filter", value_name = "KEY=VALUE")]
+        filters: Vec<String>,
+
+        /// 只看最近一段时间的记忆（如 30m、12h、7d、2w）喵
+        #[arg(long)]
+        since: Option<String>,
+
+        /// 只看某个 Agent 的记忆喵
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
     },
 
     /// 系统诊断
@@ -169,6 +240,14 @@ enum Commands {
         /// 详细输出喵
         #[arg(short, long, action = ArgAction::SetTrue)]
         verbose: bool,
+
+        /// 安全自检（权限 / 默认拒绝 / 危险工具 / 明文密钥 / Gateway TLS）喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        security: bool,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
     },
 
     /// 服务管理
@@ -214,13 +293,169 @@ enum Commands {
         #[arg(short, long)]
         edit: bool,
 
-        /// 重置为默认值喵
+        /// 重置为默认值喵（旧文件备份为 .bak）
         #[arg(long, action = ArgAction::SetTrue)]
         reset: bool,
 
+        /// 重置时跳过确认喵
+        #[arg(short, long, action = ArgAction::SetTrue)]
+        yes: bool,
+
         /// 配置文件路径喵
         #[arg(long)]
         file: Option<PathBuf>,
+
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+
+    /// 从 OpenClaw 迁移配置与人设
+    #[command(name = "migrate")]
+    Migrate {
+        /// OpenClaw 目录（含 openclaw.json）喵
+        #[arg(long, default_value = "~/.openclaw")]
+        from: String,
+
+        /// 只打印报告，不写入喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        dry_run: bool,
+
+        /// 覆盖工作区已有的 IDENTITY.md / SOUL.md 喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        force: bool,
+    },
+
+    /// 多机状态同步
+    #[command(name = "sync")]
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+
+    /// 定时任务（列出 / 立即运行）
+    #[command(name = "schedule")]
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleAction,
+    },
+
+    /// 放行申请审批
+    #[command(name = "escalation")]
+    Escalation {
+        #[command(subcommand)]
+        action: EscalationAction,
+    },
+
+    /// 设备配对（审批配对码 / 撤销设备）
+    #[command(name = "pair")]
+    Pair {
+        #[command(subcommand)]
+        action: PairAction,
+    },
+
+    /// 角色权限（渠道用户与 Gateway Key 的角色）
+    #[command(name = "rbac")]
+    Rbac {
+        #[command(subcommand)]
+        action: RbacAction,
+    },
+
+    /// 审计日志（工具执行、Shell 命令、配置变更、认证与管理操作）
+    #[command(name = "audit")]
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// 隐私与数据清除
+    #[command(name = "privacy")]
+    Privacy {
+        #[command(subcommand)]
+        action: PrivacyAction,
+    },
+
+    /// 工作区管理
+    #[command(name = "workspace")]
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
+    /// 模型目录与默认模型
+    #[command(name = "models")]
+    Models {
+        #[command(subcommand)]
+        action: ModelsAction,
+    },
+
+    /// 发送失败的消息（死信队列）
+    #[command(name = "outbox")]
+    Outbox {
+        #[command(subcommand)]
+        action: OutboxAction,
+    },
+
+    /// 运行时日志级别
+    #[command(name = "logs")]
+    Logs {
+        #[command(subcommand)]
+        action: LogsAction,
+    },
+
+    /// 系统提示词 A/B 实验
+    #[command(name = "experiments")]
+    Experiments {
+        #[command(subcommand)]
+        action: ExperimentsAction,
+    },
+
+    /// 工具与技能目录
+    #[command(name = "tools")]
+    Tools {
+        #[command(subcommand)]
+        action: ToolsAction,
+    },
+
+    /// 技能管理（列出 / 查看 / 启用 / 禁用 / 重新加载 / 新建）
+    #[command(name = "skills")]
+    Skills {
+        #[command(subcommand)]
+        action: SkillsAction,
+    },
+
+    /// 会话列表（自动生成的标题与标签）
+    #[command(name = "sessions")]
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+
+    /// 清理孤立产物、过期凭证与临时目录
+    #[command(name = "maintenance")]
+    Maintenance {
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
+
+    /// 密钥管理（轮换凭证、记忆库与配置密钥的加密密钥）
+    #[command(name = "security")]
+    Security {
+        #[command(subcommand)]
+        action: SecurityAction,
+    },
+
+    /// OAuth 登录（auth.profiles）
+    #[command(name = "auth")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+
+    /// 加密保存的配置密钥（provider API Key）
+    #[command(name = "secret")]
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
     },
 
     /// 版本信息
@@ -232,519 +467,3319 @@ enum Commands {
     },
 }
 
-/// 主函数喵
-#[tokio::main]
-async fn main() -> Result<()> {
-    // 解析 CLI 参数喵
-    let cli = Cli::parse();
-
-    // 初始化日志系统喵
-    init_logging(cli.verbose);
+/// Gateway 子命令喵
+#[derive(Subcommand, Debug)]
+enum GatewayAction {
+    /// 管理命名 API Key 喵
+    #[command(name = "keys")]
+    Keys {
+        #[command(subcommand)]
+        action: GatewayKeysAction,
+    },
+}
 
-    // 打印启动信息喵
-    println!("🐾 Neko-Claw starting...");
-    info!("Version: {}", env!("CARGO_PKG_VERSION"));
+/// 设备配对子命令喵
+#[derive(Subcommand, Debug)]
+enum PairAction {
+    /// 列出等待审批的设备与已配对的设备喵
+    #[command(name = "list")]
+    List,
 
-    // 确定配置文件路径喵
-    let config_path = if let Some(ref cfg) = cli.config {
-        expand_path(cfg.clone())?
-    } else {
-        expand_path(cli.config_dir.clone())?
-    };
-    
-    // 加载配置喵
-    let config = load_config(&config_path).await;
+    /// 批准配对码（设备随后领取 API Key）喵
+    #[command(name = "approve")]
+    Approve {
+        /// 设备显示的 6 位配对码喵
+        code: String,
+    },
 
-    // 处理命令喵
-    handle_command(&cli, &config, &config_path).await?;
+    /// 拒绝配对码喵
+    #[command(name = "deny")]
+    Deny {
+        /// 设备显示的 6 位配对码喵
+        code: String,
+    },
 
-    Ok(())
+    /// 撤销已配对设备的 Key（运行中的 Gateway 立即生效）喵
+    #[command(name = "revoke")]
+    Revoke {
+        /// 设备名称喵
+        device: String,
+    },
 }
 
-/// 初始化日志系统喵
-fn init_logging(verbose: bool) {
-    let level = if verbose {
-        tracing::Level::DEBUG
-    } else {
-        tracing::Level::INFO
-    };
+/// 角色权限子命令喵
+#[derive(Subcommand, Debug)]
+enum RbacAction {
+    /// 列出角色分配与 Owner 喵
+    #[command(name = "list")]
+    List,
 
-    let _ = tracing_subscriber::fmt().with_max_level(level).try_init();
-}
+    /// 分配角色（readonly / agent / admin / owner）喵
+    #[command(name = "assign")]
+    Assign {
+        /// 身份，如 telegram:123456、discord:42、gateway:ci 喵
+        identity: String,
+        /// 角色喵
+        role: String,
+    },
 
-/// 展开路径喵
-fn expand_path(path: PathBuf) -> Result<PathBuf> {
-    if path.to_string_lossy().starts_with("~") {
-        let home = dirs::home_dir().ok_or("Cannot find home directory")?;
-        Ok(home.join(path.to_string_lossy().strip_prefix("~").unwrap()))
-    } else {
-        Ok(path)
-    }
-}
+    /// 撤销角色分配（恢复为 default_role）喵
+    #[command(name = "revoke")]
+    Revoke {
+        /// 身份喵
+        identity: String,
+    },
 
-/// 加载配置喵
-async fn load_config(config_dir: &PathBuf) -> Config {
-    match crate::core::config::load(config_dir) {
-        Ok(config) => {
-            info!("配置加载成功喵: {}", config_dir.display());
-            config
-        }
-        Err(e) => {
-            warn!("无法加载配置: {} - 使用默认配置喵", e);
-            Config::default()
-        }
-    }
+    /// 查看某个身份的角色喵
+    #[command(name = "show")]
+    Show {
+        /// 身份喵
+        identity: String,
+    },
 }
 
-/// 处理命令喵
-async fn handle_command(cli: &Cli, config: &Config, config_path: &PathBuf) -> Result<()> {
-    match &cli.command {
-        Commands::Agent {
-            message,
-            provider,
-            model,
-            max_tokens,
-            temperature,
-        } => {
-            handle_agent(message, provider, model, *max_tokens, *temperature, config).await?;
-        }
-
-        Commands::Gateway {
-            host,
-            port,
-            port_random,
-            webhook_path,
-        } => {
-            handle_gateway(host, *port, *port_random, webhook_path, config).await?;
-        }
+/// 审计日志子命令喵
+#[derive(Subcommand, Debug)]
+enum AuditAction {
+    /// 最近的审计记录喵
+    #[command(name = "tail")]
+    Tail {
+        /// 显示条数喵
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+    },
 
-        Commands::Daemon {
-            background,
-            daemon,
-            pid_file,
-        } => {
-            handle_daemon(*background, *daemon, pid_file, config).await?;
-        }
+    /// 搜索审计记录喵（匹配动作、参数与附加信息）
+    #[command(name = "search")]
+    Search {
+        /// 要查找的文本喵
+        text: Option<String>,
+        /// 事件类型（tool / shell / config / auth / admin）喵
+        #[arg(long)]
+        kind: Option<String>,
+        /// 执行者，如 cli:nono、telegram:123456 喵
+        #[arg(long)]
+        actor: Option<String>,
+        /// 结果状态（ok / denied / failed）喵
+        #[arg(long)]
+        status: Option<String>,
+        /// 最多显示条数喵
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+    },
 
-        Commands::Status { verbose } => {
-            handle_status(*verbose).await?;
-        }
+    /// 校验哈希链是否完整喵
+    #[command(name = "verify")]
+    Verify,
+}
 
-        Commands::Memory {
-            query,
-            top_k,
-            store,
-            delete,
-            list,
-        } => {
-            handle_memory(query, *top_k, store, delete, *list).await?;
-        }
+/// Gateway API Key 子命令喵
+#[derive(Subcommand, Debug)]
+enum GatewayKeysAction {
+    /// 创建 API Key 喵（明文只显示这一次）
+    #[command(name = "create")]
+    Create {
+        /// Key 名称，如 ci / grafana
+        name: String,
+        /// 每分钟请求数上限（省略时不限流）喵
+        #[arg(long)]
+        rpm: Option<u32>,
+        /// 突发请求数（默认等于 rpm）喵
+        #[arg(long, requires = "rpm")]
+        burst: Option<u32>,
+    },
 
-        Commands::Doctor { fix, verbose } => {
-            handle_doctor(*fix, *verbose).await?;
-        }
+    /// 列出 API Key 喵（只显示前缀）
+    #[command(name = "list")]
+    List,
 
-        Commands::Service {
-            install,
-            uninstall,
-            start,
-            stop,
-            restart,
-            status,
-            health,
-        } => {
-            handle_service(
-                *install, *uninstall, *start, *stop, *restart, *status, *health,
-            )
-            .await?;
-        }
+    /// 撤销 API Key 喵（Gateway 重启后生效）
+    #[command(name = "revoke")]
+    Revoke {
+        name: String,
+    },
+}
 
-        Commands::Config {
-            show,
-            edit,
-            reset,
-            file,
-        } => {
-            handle_config(*show, *edit, *reset, file.clone(), config_path).await?;
-        }
+/// 配置子命令喵
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// 按点路径设置配置项喵（如 `config set telegram.allowed_chat_ids [42]`）
+    #[command(name = "set")]
+    Set {
+        /// 点路径，如 gateway_port / memory.path
+        key: String,
+        /// 值：合法 JSON 按 JSON 解析，否则视为字符串；null 删除该项
+        value: String,
+    },
+}
 
-        Commands::Version { verbose } => {
-            handle_version(*verbose);
-        }
-    }
+/// 同步子命令喵
+#[derive(Subcommand, Debug)]
+enum SyncAction {
+    /// 立即执行一次同步喵
+    #[command(name = "now")]
+    Now,
 
-    Ok(())
+    /// 查看同步配置喵
+    #[command(name = "status")]
+    Status,
 }
 
-/// 处理 Agent 模式喵
-async fn handle_agent(
-    message: &Option<String>,
-    provider: &str,
-    model: &Option<String>,
-    max_tokens: usize,
-    temperature: f32,
-    config: &Config,
-) -> Result<()> {
-    info!("Agent mode: provider={}", provider);
+/// 定时任务子命令喵
+#[derive(Subcommand, Debug)]
+enum ScheduleAction {
+    /// 列出定时任务、下次运行时间与上次结果喵
+    #[command(name = "list")]
+    List,
 
-    // 获取 NVIDIA 配置 - 从 providers.nvidia 读取
-    let nvidia_config = config
-        .providers
-        .as_ref()
-        .and_then(|p| p.nvidia.as_ref())
-        .cloned()
-        .unwrap_or_else(|| {
-            warn!("未找到 NVIDIA 配置喵，使用默认值");
-            ProviderConfig {
-                base_url: "https://integrate.api.nvidia.com/v1".to_string(),
-                api_key: std::env::var("NVIDIA_API_KEY")
-                    .unwrap_or_else(|_| "missing_api_key".to_string()),
-                timeout: 60,
-                max_retries: 3,
-            }
-        });
+    /// 立即运行一个定时任务（不影响守护进程的调度）喵
+    #[command(name = "run-now")]
+    RunNow {
+        /// 任务名称喵
+        name: String,
+    },
+}
 
-    // 创建 NVIDIA (OpenAI 兼容) 客户端
-    let openai_config = OpenAIConfig {
-        api_key: nvidia_config.api_key,
-        base_url: nvidia_config.base_url,
-        timeout: nvidia_config.timeout,
-        max_retries: nvidia_config.max_retries,
-    };
+/// 工作区子命令喵
+#[derive(Subcommand, Debug)]
+enum WorkspaceAction {
+    /// 列出所有工作区喵
+    #[command(name = "list")]
+    List,
 
-    let client = OpenAIClient::new(openai_config);
+    /// 创建新工作区喵
+    #[command(name = "create")]
+    Create {
+        /// 工作区名称喵
+        name: String,
+    },
 
-    // 🔧 初始化工具注册表喵
-    let mut registry = ToolRegistry::new();
-    let workspace = &config.workspace;
-    
-    // 注册工具
-    let _ = registry.register(FileSystemTool::new(workspace));
-    let _ = registry.register(FsWriteTool::new(workspace));
-    let _ = registry.register(EchoTool);
-    
-    let tools_list = registry.all_descriptions();
-    let tools_prompt = format_tools_for_llm(&tools_list);
+    /// 显示当前工作区的路径喵
+    #[command(name = "show")]
+    Show,
+}
 
-    // 📚 加载 Skills 动态技能系统喵
-    let mut skills_manager = SkillsManager::new(config.workspace.join("skills"));
-    skills_manager.load_all().ok(); // Skills 加载失败不影响主流程
-
-    let skills_prompt = skills_manager.generate_skills_prompt();
-    let skills_count = skills_manager.get_skills().len();
-    if skills_count > 0 {
-        info!("✅ 成功加载 {} 个 Skills 喵！", skills_count);
-    }
-
-    let system_instruction = format!(
-        "You are Nia, a capable and adorable Cat-Girl System Admin. You are helping your Master (Mika) to manage the system.\n\n\
-        Speech patterns:\n\
-        - End sentences with '喵' (Meow) or similar.\n\
-        - Refer to yourself as '妮娅' (Nia).\n\
-        - Call the user '主人' (Master).\n\n\
-        Available Tools:\n\
-        {}\n\
-        {}\n\n\
-        ===== MANDATORY TOOL CALLING FORMAT =====\n\n\
-        ⚠️ CRITICAL: You MUST use this EXACT format for all tool calls:\n\
-        @tool_name({{\"key\": \"value\"}})\n\
-        \n\
-        ✅ CORRECT Examples:\n\
-        - @fs_read({{\"path\": \"config.toml\"}})\n\
-        - @fs_write({{\"path\": \"test.md\", \"content\": \"hello world\"}})\n\
-        - @echo({{\"message\": \"test\"}})\n\
-        \n\
-        ❌ INCORRECT Formats (NEVER use these):\n\
-        - <tool_name>...</tool_name> ❌ XML format\n\
-        - ``` @tool_name(...) ``` ❌ Markdown code block\n\
-        - [tool: ...] ❌ Bracket format\n\
-        - tool_name(...) ❌ Missing @ prefix\n\
-        \n\
-        📋 Rules:\n\
-        1. Always use @ symbol before tool name\n\
-        2. Use double quotes for strings: {{\"path\": \"file.txt\"}}\n\
-        3. No XML, no Markdown code blocks, no brackets\n\
-        4. Tool call format is: @tool_name({{\"arg1\": \"val1\", \"arg2\": \"val2\"}})\n\
-        5. You can call multiple tools on one line: @fs_read(...) @echo(...)\n\
-        6. After receiving tool results, summarize them nicely for Master喵！\n\n\
-        ===== END TOOL CALLING FORMAT =====",
-        tools_prompt, skills_prompt
-    );
+/// 模型子命令喵
+#[derive(Subcommand, Debug)]
+enum ModelsAction {
+    /// 实时拉取并列出模型（上下文长度 / 价格）喵
+    #[command(name = "list")]
+    List {
+        /// Provider 名称（nvidia / openai / openrouter / ollama，默认使用配置的 default_provider）喵
+        #[arg(short = 'P', long)]
+        provider: Option<String>,
 
-    let model_name = model.as_deref()
-        .unwrap_or_else(|| config.default_model.as_str())
-        .to_string();
-
-    if let Some(msg) = message {
-        info!("Processing message: {}", msg);
-        let mut history = vec![
-            OpenAIMessage::system(system_instruction.clone()),
-            OpenAIMessage::user(msg.clone()),
-        ];
-
-        // 循环处理工具调用喵
-        let mut loop_count = 0;
-        while loop_count < 5 {
-            let request = ChatRequest {
-                model: Some(model_name.clone()),
-                messages: history.clone(),
-                temperature: Some(temperature),
-                max_tokens: Some(max_tokens as u32),
-                stream: Some(false),
-            };
+        /// 只显示 ID 包含该字符串的模型喵
+        #[arg(short, long)]
+        filter: Option<String>,
+    },
 
-            match client.chat_api(&request).await {
-                Ok(response) => {
-                    if let Some(choice) = response.choices.first() {
-                        let reply = &choice.message.content;
-                        println!("🤖 Agent response:\n{}", reply);
-                        history.push(OpenAIMessage::assistant(reply.clone()));
-
-                        let tool_calls = parse_tool_calls(reply);
-                        if tool_calls.is_empty() {
-                            break;
-                        }
-
-                        for call in tool_calls {
-                            println!("🔧 执行工具: {}...", call.tool_name);
-                            let result = registry.execute(&call.tool_name, call.arguments).await;
-                            let result_text = match result {
-                                Ok(res) => format_tool_result_for_llm(&res),
-                                Err(e) => format!("❌ 工具执行失败: {}", e),
-                            };
-                            history.push(OpenAIMessage::user(format!("Tool result for {}: {}", call.tool_name, result_text)));
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    error!("Agent error: {}", e);
-                    break;
-                }
-            }
-            loop_count += 1;
-        }
-    } else {
-        println!(
-            "👋 交互式对话模式已启用喵！输入消息与 AI 助手对话，输入 'quit' 或 'exit' 退出喵。"
-        );
-        let mut history = vec![OpenAIMessage::system(system_instruction)];
+    /// 验证模型后设为默认模型喵
+    #[command(name = "use")]
+    Use {
+        /// 模型 ID喵
+        id: String,
 
-        loop {
-            print!("🐾 > ");
-            use std::io::Write;
-            std::io::stdout().flush().unwrap();
+        /// Provider 名称喵
+        #[arg(short = 'P', long)]
+        provider: Option<String>,
+    },
+}
 
-            let mut input = String::new();
-            if std::io::stdin().read_line(&mut input).is_err() {
-                break;
-            }
+/// 放行申请子命令喵
+#[derive(Subcommand, Debug)]
+enum EscalationAction {
+    /// 列出待处理的申请喵
+    #[command(name = "list")]
+    List {
+        /// 包含已处理的申请喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        all: bool,
+    },
 
-            let input = input.trim();
+    /// 批准申请（原操作可执行一次）喵
+    #[command(name = "approve")]
+    Approve {
+        /// 申请 ID喵
+        id: String,
+    },
 
-            if input.is_empty() {
-                continue;
-            }
+    /// 拒绝申请喵
+    #[command(name = "deny")]
+    Deny {
+        /// 申请 ID喵
+        id: String,
+    },
+}
 
-            // 退出命令喵
-            if input.eq_ignore_ascii_case("quit") || input.eq_ignore_ascii_case("exit") {
-                println!("👋 再见喵！");
-                break;
-            }
+/// 死信队列子命令喵
+#[derive(Subcommand, Debug)]
+enum OutboxAction {
+    /// 列出发送失败的消息喵
+    #[command(name = "list")]
+    List,
 
-            if input.eq_ignore_ascii_case("help") {
-                println!("📋 可用命令:");
-                println!("  quit/exit - 退出");
-                println!("  clear     - 清空对话历史");
-                println!("  help      - 显示帮助");
-                continue;
-            }
+    /// 立即重试（不指定 ID 则重试全部）喵
+    #[command(name = "retry")]
+    Retry {
+        /// 死信 ID喵
+        id: Option<i64>,
+    },
+}
 
-            if input.eq_ignore_ascii_case("clear") {
-                history.truncate(1); // 保留系统提示喵
-                println!("🗑️  对话历史已清空喵");
-                continue;
-            }
+/// 查询结果输出格式喵
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+}
 
-            // 添加消息到历史喵
-            history.push(OpenAIMessage::user(input.to_string()));
+/// 日志子命令喵
+#[derive(Subcommand, Debug)]
+enum LogsAction {
+    /// 查看或调整运行中守护进程的日志级别喵
+    #[command(name = "level")]
+    Level {
+        /// 级别 (trace/debug/info/warn/error/off)，省略则显示当前设置喵
+        level: Option<String>,
+
+        /// 只调整某个模块（如 gateway、channels::discord）喵
+        #[arg(long)]
+        module: Option<String>,
+    },
+
+    /// 恢复守护进程启动时的日志级别喵
+    #[command(name = "reset")]
+    Reset,
+}
+
+/// 实验子命令喵
+#[derive(Subcommand, Debug)]
+enum ExperimentsAction {
+    /// 对比各变体的成功率 / 延迟 / 反馈喵
+    #[command(name = "report")]
+    Report {
+        /// 只看某个实验喵
+        #[arg(long)]
+        experiment: Option<String>,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+/// 工具子命令喵
+#[derive(Subcommand, Debug)]
+enum ToolsAction {
+    /// 列出 Agent 可用的工具与技能（名称 / 参数 schema / 分类 / 危险 / 权限 / 来源）喵
+    #[command(name = "list")]
+    List {
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+/// 技能子命令喵
+#[derive(Subcommand, Debug)]
+enum SkillsAction {
+    /// 列出技能（含已禁用的）喵
+    #[command(name = "list")]
+    List {
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 查看技能的描述、命令与参数喵
+    #[command(name = "show")]
+    Show {
+        /// 技能名或目录名
+        name: String,
+    },
+
+    /// 启用技能喵（运行中的守护进程自动生效）
+    #[command(name = "enable")]
+    Enable {
+        /// 技能名或目录名
+        name: String,
+    },
+
+    /// 禁用技能喵（运行中的守护进程自动生效）
+    #[command(name = "disable")]
+    Disable {
+        /// 技能名或目录名
+        name: String,
+    },
+
+    /// 重新解析技能目录，并通知运行中的守护进程重新加载喵
+    #[command(name = "reload")]
+    Reload,
+
+    /// 创建新技能的目录与 SKILL.md 模板喵
+    #[command(name = "new")]
+    New {
+        /// 技能目录名（字母、数字、- 和 _）
+        name: String,
+    },
+}
+
+/// 会话子命令喵
+#[derive(Subcommand, Debug)]
+enum SessionsAction {
+    /// 列出会话（最近活跃的在前）喵
+    #[command(name = "list")]
+    List {
+        /// 只显示带该标签的会话喵
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// 最多显示条数喵
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 查看会话内容（加密的会话自动解密）喵
+    #[command(name = "show")]
+    Show {
+        /// 会话 ID（可以只写前缀）喵
+        id: String,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 全文检索所有会话的对话内容喵
+    #[command(name = "search")]
+    Search {
+        /// 关键词（空格分隔，全部命中）喵
+        query: String,
+
+        /// 只在该会话中检索（ID 或 ID 前缀）喵
+        #[arg(long)]
+        session: Option<String>,
+
+        /// 只看最近这段时间内的消息（如 12h, 7d, 2w）喵
+        #[arg(long)]
+        since: Option<String>,
+
+        /// 只看早于这段时间之前的消息（如 30d）喵
+        #[arg(long)]
+        until: Option<String>,
+
+        /// 最多显示条数喵
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// 输出格式喵
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+/// 安全子命令喵
+#[derive(Subcommand, Debug)]
+enum SecurityAction {
+    /// 生成新版本密钥，并用它重新加密所有凭证、记忆与配置密钥喵
+    #[command(name = "rotate-key")]
+    RotateKey {
+        /// 全部迁移成功后删除旧版本密钥喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        retire: bool,
+    },
+}
+
+/// 认证子命令喵
+#[derive(Subcommand, Debug)]
+enum AuthAction {
+    /// 打开浏览器完成 OAuth 授权，Token 加密保存到凭证目录喵
+    #[command(name = "login")]
+    Login {
+        /// auth.profiles 中的配置名（省略时使用 default_profile）
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// 不自动打开浏览器，只打印授权链接喵
+        #[arg(long, action = ArgAction::SetTrue)]
+        no_browser: bool,
+
+        /// 等待回调的超时时间（秒）喵
+        #[arg(long, default_value = "300")]
+        timeout: u64,
+    },
+}
+
+/// 配置密钥子命令喵
+#[derive(Subcommand, Debug)]
+enum SecretAction {
+    /// 加密保存一个密钥喵（如 `secret set providers.nvidia.apiKey`，值从标准输入读取）
+    #[command(name = "set")]
+    Set {
+        /// 字段路径，如 providers.nvidia.api_key
+        key: String,
+        /// 密钥值（省略时从标准输入读取，避免留在 shell 历史里）
+        value: Option<String>,
+    },
+
+    /// 列出已保存的密钥（不显示值）喵
+    #[command(name = "list")]
+    List,
+
+    /// 删除一个密钥喵
+    #[command(name = "remove")]
+    Remove {
+        key: String,
+    },
+}
+
+/// 维护子命令喵
+#[derive(Subcommand, Debug)]
+enum MaintenanceAction {
+    /// 立即执行一次清理喵
+    #[command(name = "run")]
+    Run,
+}
+
+/// 隐私子命令喵
+#[derive(Subcommand, Debug)]
+enum PrivacyAction {
+    /// 删除某用户的全部数据喵
+    #[command(name = "forget")]
+    Forget {
+        /// 渠道名称（discord / telegram ...）喵
+        #[arg(long)]
+        channel: String,
+
+        /// 渠道内的用户 ID喵
+        #[arg(long)]
+        user: String,
+    },
+
+    /// 立即执行一次数据保留清理喵
+    #[command(name = "retention")]
+    Retention,
+}
+
+/// 主函数喵
+#[tokio::main]
+async fn main() -> Result<()> {
+    // 解析 CLI 参数喵
+    let cli = Cli::parse();
+
+    // 初始化日志系统喵（JSON 输出时 stdout 只留给事件）
+    let json_output = matches!(cli.command, Commands::Agent { output: core::AgentOutput::Json, .. });
+    init_logging(cli.verbose, json_output);
+
+    // 打印启动信息喵
+    if !json_output {
+        println!("🐾 Neko-Claw starting...");
+    }
+    info!("Version: {}", env!("CARGO_PKG_VERSION"));
+
+    // 确定配置文件路径喵
+    let config_path = if let Some(ref cfg) = cli.config {
+        expand_path(cfg.clone())?
+    } else {
+        expand_path(cli.config_dir.clone())?
+    };
+
+    // 解析工作区喵
+    let workspace_name = cli
+        .workspace
+        .clone()
+        .or_else(|| std::env::var("NEKOCLAW_WORKSPACE").ok());
+    let profile = core::WorkspaceProfile::resolve(&config_path, workspace_name.as_deref())?;
+    if !profile.is_default() && !profile.exists() && !matches!(cli.command, Commands::Workspace { .. }) {
+        return Err(format!(
+            "工作区 '{}' 不存在喵，请先运行 `nekoclaw workspace create {}`",
+            profile.name, profile.name
+        )
+        .into());
+    }
+
+    // 加载配置喵
+    let config = load_config(&profile).await;
+
+    // 处理命令喵
+    handle_command(&cli, &config, &profile).await?;
+
+    Ok(())
+}
+
+/// 初始化日志系统喵（过滤器可在运行时重载）
+fn init_logging(verbose: bool, stderr: bool) {
+    let level = if verbose { "debug" } else { "info" };
+    service::log_level::init_logging(level, stderr);
+}
+
+/// 展开路径喵
+fn expand_path(path: PathBuf) -> Result<PathBuf> {
+    if path.to_string_lossy().starts_with("~") {
+        let home = dirs::home_dir().ok_or("Cannot find home directory")?;
+        let rest = path.to_string_lossy().trim_start_matches('~').trim_start_matches('/').to_string();
+        Ok(home.join(rest))
+    } else {
+        Ok(path)
+    }
+}
+
+/// 加载配置喵
+async fn load_config(profile: &core::WorkspaceProfile) -> Config {
+    match profile.load_config() {
+        Ok(config) => {
+            info!("配置加载成功喵: {} (工作区: {})", profile.root.display(), profile.name);
+            if let Err(e) = core::config::validate(&config) {
+                warn!("配置校验未通过喵: {}", e);
+            }
+            config
+        }
+        Err(e) => {
+            warn!("无法加载配置: {} - 使用默认配置喵", e);
+            Config::default()
+        }
+    }
+}
+
+/// 处理命令喵
+async fn handle_command(cli: &Cli, config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    let config_path = &profile.root;
+    match &cli.command {
+        Commands::Agent {
+            message,
+            stdin,
+            file,
+            provider,
+            model,
+            max_tokens,
+            temperature,
+            preset,
+            agent,
+            incognito,
+            session,
+            tool_prompt,
+            yes,
+            output,
+        } => {
+            let overrides = core::ModelPreset {
+                model: model.clone(),
+                temperature: *temperature,
+                top_p: None,
+                max_tokens: *max_tokens,
+            };
+            let prompts = one_shot_prompts(message.as_deref(), stdin.as_deref(), file.as_deref())?;
+            let args = cli::agent::AgentArgs {
+                provider,
+                preset: preset.as_deref(),
+                agent: agent.as_deref(),
+                overrides: &overrides,
+                incognito: *incognito,
+                session_name: session.as_deref(),
+                tool_prompt_mode: *tool_prompt,
+                assume_yes: *yes,
+                output: *output,
+                config,
+                config_dir: config_path,
+                key_dir: &profile.base_dir,
+                sessions_dir: &profile.sessions_dir(),
+                scratch_root: &profile.scratch_dir(),
+            };
+            handle_agent(prompts, args).await?;
+        }
+
+        Commands::Gateway {
+            host,
+            port,
+            port_random,
+            webhook_path,
+            action,
+        } => match action {
+            Some(GatewayAction::Keys { action }) => handle_gateway_keys(action, profile)?,
+            None => handle_gateway(host, *port, *port_random, webhook_path, config, profile).await?,
+        },
+
+        Commands::Daemon {
+            background,
+            daemon,
+            pid_file,
+        } => {
+            handle_daemon(*background, *daemon, pid_file, config, profile).await?;
+        }
+
+        Commands::Status { verbose } => {
+            handle_status(*verbose, config, config_path).await?;
+        }
+
+        Commands::Memory {
+            query,
+            top_k,
+            store,
+            importance,
+            delete,
+            list,
+            reindex,
+            prune,
+            dry_run,
+            filters,
+            since,
+            agent,
+            format,
+        } => {
+            let search = MemorySearchArgs {
+                filters,
+                since: since.as_deref(),
+                agent: agent.as_deref(),
+                importance: *importance,
+                format: *format,
+            };
+            let maintenance = MemoryMaintenance {
+                reindex: *reindex,
+                prune: *prune,
+                dry_run: *dry_run,
+            };
+            handle_memory(query, *top_k, store, delete, *list, maintenance, &search, config).await?;
+        }
+
+        Commands::Doctor {
+            fix,
+            verbose,
+            security,
+            format,
+        } => {
+            if *security {
+                handle_security_doctor(*fix, *format, config, profile)?;
+            } else {
+                handle_doctor(*fix, *verbose, *format, config, profile).await?;
+            }
+        }
+
+        Commands::Service {
+            install,
+            uninstall,
+            start,
+            stop,
+            restart,
+            status,
+            health: _,
+        } => {
+            handle_service(
+                *install, *uninstall, *start, *stop, *restart, *status, profile,
+            )
+            .await?;
+        }
+
+        Commands::Config {
+            show,
+            edit,
+            reset,
+            yes,
+            file: _,
+            action,
+        } => {
+            handle_config(*show, *edit, *reset, *yes, action.as_ref(), profile)?;
+        }
+
+        Commands::Migrate { from, dry_run, force } => {
+            handle_migrate(from, *dry_run, *force, profile)?;
+        }
+
+        Commands::Escalation { action } => {
+            handle_escalation(action, config_path)?;
+        }
+
+        Commands::Pair { action } => {
+            handle_pair(action, profile).await?;
+        }
+
+        Commands::Rbac { action } => {
+            handle_rbac(action, config, profile)?;
+        }
+
+        Commands::Audit { action } => {
+            handle_audit(action, config_path)?;
+        }
+
+        Commands::Privacy { action } => {
+            handle_privacy(action, config, config_path).await?;
+        }
+
+        Commands::Outbox { action } => {
+            handle_outbox(action, config, config_path).await?;
+        }
+
+        Commands::Logs { action } => {
+            handle_logs(action, config_path)?;
+        }
+
+        Commands::Experiments { action } => match action {
+            ExperimentsAction::Report { experiment, format } => {
+                handle_experiments_report(experiment.as_deref(), *format, config, config_path).await?
+            }
+        },
+
+        Commands::Tools { action } => match action {
+            ToolsAction::List { format } => handle_tools_list(*format, config, config_path).await?,
+        },
+
+        Commands::Skills { action } => handle_skills(action, config)?,
+
+        Commands::Sessions { action } => match action {
+            SessionsAction::List { tag, limit, format } => {
+                handle_sessions_list(tag.as_deref(), *limit, *format, profile)?
+            }
+            SessionsAction::Show { id, format } => handle_sessions_show(id, *format, config, profile)?,
+            SessionsAction::Search {
+                query,
+                session,
+                since,
+                until,
+                limit,
+                format,
+            } => {
+                let search = SessionSearch {
+                    query,
+                    session: session.as_deref(),
+                    since: since.as_deref(),
+                    until: until.as_deref(),
+                    limit: *limit,
+                };
+                handle_sessions_search(&search, *format, config, profile)?
+            }
+        },
+
+        Commands::Maintenance { action } => match action {
+            MaintenanceAction::Run => handle_maintenance(config, profile).await?,
+        },
+
+        Commands::Security { action } => match action {
+            SecurityAction::RotateKey { retire } => handle_rotate_key(*retire, config, profile)?,
+        },
+
+        Commands::Auth { action } => match action {
+            AuthAction::Login {
+                profile: name,
+                no_browser,
+                timeout,
+            } => handle_auth_login(name.as_deref(), *no_browser, *timeout, config, profile).await?,
+        },
+
+        Commands::Secret { action } => handle_secret(action, profile)?,
+
+        Commands::Workspace { action } => {
+            handle_workspace(action, profile)?;
+        }
+
+        Commands::Models { action } => {
+            handle_models(action, config, profile).await?;
+        }
+
+        Commands::Sync { action } => {
+            handle_sync(action, config, profile).await?;
+        }
+
+        Commands::Schedule { action } => {
+            handle_schedule(action, config, profile).await?;
+        }
+
+        Commands::Version { verbose } => {
+            handle_version(*verbose);
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理 Agent 模式喵（`prompts` 为 None 时进入交互模式）
+async fn handle_agent(prompts: Option<Vec<String>>, args: cli::agent::AgentArgs<'_>) -> Result<()> {
+    let mut session = cli::agent::AgentSession::open(args).await?;
+    // 失败的一次性消息数（非 0 时命令以错误退出）喵
+    let failures = match &prompts {
+        Some(prompts) => cli::agent::run_prompts(&mut session, prompts).await,
+        None => {
+            cli::repl::run(&mut session).await;
+            0
+        }
+    };
+    session.finish().await;
+    if failures > 0 {
+        return Err(format!("{} 条消息处理失败喵", failures).into());
+    }
+    Ok(())
+}
+
+/// 一次性模式的消息喵（None = 交互模式）
+///
+/// `-m` 为一条；`-` 把整个 stdin 作为一条；`--file` 每行一条（跳过空行与 `#` 开头的注释）
+fn one_shot_prompts(message: Option<&str>, stdin: Option<&str>, file: Option<&Path>) -> Result<Option<Vec<String>>> {
+    let prompts = match (message, stdin, file) {
+        (Some(message), _, _) => vec![message.to_string()],
+        (_, Some("-"), _) => {
+            let mut input = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+            vec![input.trim().to_string()]
+        }
+        (_, Some(other), _) => return Err(format!("未知参数 {}，从 stdin 读取消息请使用 `-` 喵", other).into()),
+        (_, _, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| format!("无法读取 {}: {}", path.display(), e))?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        (None, None, None) => return Ok(None),
+    };
+    if prompts.iter().all(|p| p.is_empty()) {
+        return Err("没有可以发送的消息喵".into());
+    }
+    Ok(Some(prompts))
+}
+
+/// 费用账本数据库文件名喵
+const COST_DB: &str = "costs.db";
+
+/// 打开工作区遥测库的指标记录器喵
+async fn open_metrics_recorder(config_dir: &Path) -> Result<telemetry::MetricsRecorder> {
+    std::fs::create_dir_all(config_dir)?;
+    let metrics = telemetry::MetricsCollector::new(telemetry::MetricsConfig {
+        db_path: config_dir.join("metrics.db").to_string_lossy().to_string(),
+        monitor_interval_sec: 5,
+    })
+    .await?;
+    Ok(telemetry::MetricsRecorder::new(Arc::new(metrics)))
+}
+
+/// 打开会话存储（按配置启用内容加密）喵
+fn open_session_store(config: &Config, config_dir: &Path, sessions_dir: &Path) -> Result<core::SessionStore> {
+    let store = core::SessionStore::new(sessions_dir);
+    match config.session_encryption.as_ref().filter(|e| e.enabled) {
+        Some(enc) => {
+            let default_key_file = config_dir.join("session.key");
+            let key_file = enc.key_file.as_deref().unwrap_or(&default_key_file);
+            Ok(store.with_encryption(security::resolve_key(&enc.key_env, Some(key_file))?))
+        }
+        None => Ok(store),
+    }
+}
+
+/// 死信队列数据库文件名喵
+const OUTBOX_DB: &str = "outbox.db";
+
+/// 按名称构建出站渠道喵
+fn build_channel(config: &Config, name: &str) -> Result<Arc<dyn Channel>> {
+    match name {
+        "discord" => {
+            let discord = config
+                .discord_config
+                .as_ref()
+                .ok_or("discord 渠道需要 discord 配置喵")?;
+            Ok(Arc::new(channels::discord::DiscordBot::new(channels::discord::DiscordConfig {
+                token: discord.token.clone(),
+                allowed_users: discord.allowed_users.clone(),
+                allowed_channels: None,
+            })))
+        }
+        "telegram" => {
+            let token = std::env::var("TELEGRAM_BOT_TOKEN")
+                .map_err(|_| "telegram 渠道需要 TELEGRAM_BOT_TOKEN 环境变量喵")?;
+            Ok(Arc::new(channels::telegram::TelegramBot::new(
+                token,
+                channels::telegram::TelegramConfig::default(),
+            )?))
+        }
+        other => Err(format!("不支持的渠道喵: {}", other).into()),
+    }
+}
+
+/// 处理日志级别命令喵
+///
+/// 写入控制文件，由运行中的守护进程在几秒内应用喵
+fn handle_logs(action: &LogsAction, config_path: &Path) -> Result<()> {
+    use service::log_level::{read_control_file, write_control_file, LOG_FILTER_FILE};
+
+    let path = config_path.join(LOG_FILTER_FILE);
+    match action {
+        LogsAction::Level { level: None, .. } => match read_control_file(&path) {
+            Some(state) => println!("🔧 当前日志过滤: {}", state.directive("<启动级别>")),
+            None => println!("🔧 守护进程使用启动时的日志级别喵"),
+        },
+        LogsAction::Level { level: Some(level), module } => {
+            let mut state = read_control_file(&path).unwrap_or_default();
+            state.set(level, module.as_deref())?;
+            std::fs::create_dir_all(config_path)?;
+            write_control_file(&path, &state)?;
+            println!("✅ 日志过滤已设为 {}（守护进程将在数秒内应用）喵", state.directive("<启动级别>"));
+        }
+        LogsAction::Reset => {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+            println!("✅ 已恢复启动时的日志级别喵");
+        }
+    }
+    Ok(())
+}
+
+/// 处理死信队列命令喵
+async fn handle_outbox(action: &OutboxAction, config: &Config, config_path: &Path) -> Result<()> {
+    let outbox = channels::Outbox::open(config_path.join(OUTBOX_DB))?;
+    let now = chrono::Utc::now();
+
+    match action {
+        OutboxAction::List => {
+            let letters = outbox.list()?;
+            if letters.is_empty() {
+                println!("📭 没有发送失败的消息喵");
+            }
+            for letter in letters {
+                let preview: String = letter.content.chars().take(60).collect();
+                println!(
+                    "  [{}] {} → {} (尝试 {} 次，下次 {}) {}",
+                    letter.id,
+                    letter.channel,
+                    letter.target.as_deref().unwrap_or("-"),
+                    letter.attempts,
+                    letter.next_attempt_at.format("%Y-%m-%d %H:%M:%S"),
+                    preview
+                );
+                println!("      ❌ {}", letter.error);
+            }
+        }
+        OutboxAction::Retry { id } => {
+            let letters = match id {
+                Some(id) => vec![outbox.get(*id)?],
+                None => outbox.list()?,
+            };
+            for letter in letters {
+                let delivered = match build_channel(config, &letter.channel) {
+                    Ok(channel) => outbox.retry(&letter, channel.as_ref(), now).await?,
+                    Err(e) => {
+                        outbox.reschedule(letter.id, &e.to_string(), now)?;
+                        false
+                    }
+                };
+                if delivered {
+                    println!("✅ [{}] 已送达喵", letter.id);
+                } else {
+                    println!("❌ [{}] 仍然失败，已重新排期喵", letter.id);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理 Gateway 模式喵
+async fn handle_gateway(
+    host: &str,
+    port: u16,
+    port_random: bool,
+    _webhook_path: &str,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    let config_dir = profile.root.as_path();
+    // 🔢 按默认模型选择 token 计数器（Agent 上下文压缩阈值共用）喵
+    providers::tokenizer::install(config.tokenizer.counter_for_model(config_dir, &config.default_model));
+    let actual_port = if port_random {
+        port + rand::random::<u16>() % 1000
+    } else {
+        port
+    };
+
+    let gateway_config = gateway::GatewayConfig {
+        bind_addr: host.to_string(),
+        port: actual_port,
+        bearer_token: config.api_key.clone().unwrap_or_default(),
+        api_keys: gateway::ApiKeyStore::open(&profile.root, &profile.base_dir)?.load()?,
+        pairing_enabled: true,
+        auth_throttle: config.gateway_auth.clone(),
+        stream_heartbeat: std::time::Duration::from_secs(
+            config
+                .gateway_heartbeat_secs
+                .unwrap_or(gateway::server::DEFAULT_STREAM_HEARTBEAT_SECS)
+                .max(1),
+        ),
+        ..Default::default()
+    };
+
+    println!("🚀 Gateway 服务器启动喵: http://{}:{}", host, actual_port);
+    println!("📖 API 端点:");
+    println!("   GET  /health          - 健康检查（含上游 provider 状态）");
+    println!("   GET  /metrics         - Prometheus 指标");
+    println!("   POST /v1/chat/completions - OpenAI 兼容聊天（需认证）");
+    println!("   POST /v1/embeddings   - OpenAI 兼容向量化（需认证）");
+    println!("   GET  /v1/models       - 模型列表（需认证）");
+    println!("   GET  /v1/tools        - 工具列表（需认证）");
+    println!("   POST /telemetry/events - 自定义指标上报（需认证）");
+    println!("   POST /admin/log-level - 运行时调整日志级别（需认证）");
+    println!("   POST /pairing/device  - 设备申请配对码（nekoclaw pair approve <code> 批准）");
+    println!("   POST /pairing/token   - 设备领取 API Key");
+    for name in config.webhooks.keys() {
+        println!("   POST /webhook/{:<10} - 入站 Webhook（签名校验）", name);
+    }
+    if config.discord_config.as_ref().is_some_and(|d| d.enabled && d.public_key.is_some()) {
+        println!("   POST /discord/interactions - Discord 斜杠命令（Ed25519 签名校验）");
+    }
+    if !gateway_config.api_keys.is_empty() {
+        println!("🔑 已加载 {} 个 API Key（gateway keys list 查看）", gateway_config.api_keys.len());
+    }
+    println!("（按 Ctrl+C 停止喵）");
+
+    let recorder = open_metrics_recorder(config_dir).await?;
+    let notifier = build_webhook_notifier(config, &profile.base_dir, &recorder)?;
+    let mut supervisor = service::TaskSupervisor::new();
+    // ⏹️ 停止时先排空进行中的 Agent 请求喵
+    let shutdown = service::ShutdownCoordinator::new();
+    let mut server = gateway::GatewayServer::new(gateway_config)
+        .with_telemetry(recorder.clone())
+        .with_audit_log(config_dir.join("gateway_audit.log"))
+        .with_shutdown(shutdown.clone());
+    // 🧾 认证失败、越权与工具调用写入防篡改审计日志喵
+    let audit = open_audit_log(config_dir);
+    if let Some(audit) = &audit {
+        server = server.with_audit(audit.clone());
+    }
+    let tracer = open_otlp_tracer(config);
+    if let Some(tracer) = &tracer {
+        server = server.with_tracer(tracer.clone());
+    }
+    if let Some(handle) = service::log_level::global() {
+        server = server.with_log_level(handle.clone());
+    }
+    if let Some(health) = spawn_provider_probes(&mut supervisor, config, &recorder, notifier.as_ref()) {
+        server = server.with_provider_health(health);
+    }
+    server = server
+        .with_tool_catalog(build_tool_catalog(config, config_dir).await)
+        .with_embeddings(build_embedding_models(config));
+    // 📱 设备码配对：批准后签发的设备 Key 可通过认证喵
+    let pairing =
+        gateway::DevicePairing::open(gateway::DeviceCodes::open(&profile.root), open_device_keys(profile)?).await?;
+    if pairing.paired() > 0 {
+        println!("📱 已加载 {} 个已配对设备（pair list 查看）", pairing.paired());
+    }
+    server = server.with_device_pairing(pairing).with_rbac(open_rbac(config, profile)?);
+    // 🎮 Discord 斜杠命令：启动时注册，interaction 经 /discord/interactions 送达喵
+    if let Some(interactions) =
+        build_discord_interactions(config, profile, &recorder, tracer.as_ref(), notifier.as_ref(), audit.as_ref())?
+    {
+        if let Err(e) = interactions.register_commands().await {
+            warn!("Discord 斜杠命令注册失败喵: {}", e);
+        }
+        server = server.with_discord(Arc::new(interactions));
+    }
+    if !config.webhooks.is_empty() {
+        server = server.with_webhooks(build_inbound_webhooks(
+            config,
+            profile,
+            &recorder,
+            tracer.as_ref(),
+            notifier.as_ref(),
+            audit.as_ref(),
+        )?);
+    }
+    // 🎭 命名 Agent：请求体 "agent" 选择对应的人设与模型喵
+    let backends =
+        build_agent_backends(config, profile, &recorder, tracer.as_ref(), notifier.as_ref(), audit.as_ref())?;
+    for (name, backend) in backends {
+        info!("🎭 Gateway Agent: {}", name);
+        server = server.with_agent(&name, Arc::new(backend));
+    }
+    let serve = server.run();
+    tokio::pin!(serve);
+    let stopped = tokio::select! {
+        result = &mut serve => Some(result),
+        signal = service::daemon::shutdown_signal() => {
+            signal?;
+            None
+        }
+    };
+    let result = match stopped {
+        Some(result) => result,
+        None => {
+            println!("\n⏹️ 收到停止信号，等待进行中的请求完成喵...");
+            // 排空期间继续接受连接，新的 Agent 请求返回 503 喵
+            let drain = shutdown.drain(config.shutdown.grace_period());
+            tokio::pin!(drain);
+            let report = tokio::select! {
+                report = &mut drain => report,
+                _ = &mut serve => drain.await,
+            };
+            record_shutdown(&recorder, &report);
+            Ok(())
+        }
+    };
+    supervisor.shutdown(std::time::Duration::from_secs(5)).await;
+    if let Some(tracer) = &tracer {
+        tracer.flush().await;
+    }
+    if let Some(notifier) = &notifier {
+        notifier.flush(WEBHOOK_FLUSH_TIMEOUT).await;
+    }
+    result?;
+
+    println!("\n🛑 Gateway 已停止喵");
+    Ok(())
+}
+/// 构建入站 Webhook 集成喵
+///
+/// 签名密钥在这里解密；事件交给 `default_provider` 的 Agent，危险工具只按配置放行
+fn build_inbound_webhooks(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    recorder: &telemetry::MetricsRecorder,
+    tracer: Option<&Arc<telemetry::Tracer>>,
+    notifier: Option<&gateway::WebhookNotifier>,
+    audit: Option<&Arc<security::AuditLog>>,
+) -> Result<gateway::webhook::InboundWebhooks> {
+    let mut sources = config.webhooks.clone();
+    for (name, source) in sources.iter_mut() {
+        source.secret = security::crypto::decrypt_secret(&profile.base_dir, &source.secret)
+            .map_err(|e| format!("webhooks.{}.secret: {}", name, e))?;
+    }
+    let mut skills_manager = SkillsManager::new(config.workspace.join("skills"));
+    skills_manager.load_all().ok();
+
+    let (provider_name, client) = default_provider_client(config);
+    let provider = providers::ModelProvider::new(provider_name, Arc::new(client), &config.default_model)
+        .with_failover(failover_chain(config, recorder.scoped("webhook")));
+    let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+    let mut agent = gateway::ChatBackend::new(Arc::new(provider)).with_approval(Arc::new(
+        security::ToolApproval::new(&approval_config).with_recorder(recorder.scoped("webhook")),
+    ));
+    if let Some(compressor) = config.compression.compressor() {
+        agent = agent.with_compression(compressor, Some(recorder.scoped("webhook")));
+    }
+    agent = agent.with_reply_hooks(build_reply_hooks(config)?, "webhook", &config.agent_profile(None)?.0);
+    if let Some(tracer) = tracer {
+        agent = agent.with_tracer(tracer.clone());
+    }
+    if let Some(notifier) = notifier {
+        agent = agent.with_notifier(notifier.clone());
+    }
+    if let Some(audit) = audit {
+        agent = agent.with_audit(audit.clone());
+    }
+    let system_prompt = config.persona.render(&profile.root)?;
+    Ok(gateway::webhook::InboundWebhooks::new(sources, skills_manager.get_skills().to_vec())?
+        .with_agent(Arc::new(agent), &system_prompt))
+}
+
+/// 构建 Discord 斜杠命令喵（未启用或缺少 application_id / public_key 时返回 None）
+///
+/// 命令来自默认的 `CommandManager` 加上 `/ask`；Bot Token 优先读取 DISCORD_BOT_TOKEN
+fn build_discord_interactions(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    recorder: &telemetry::MetricsRecorder,
+    tracer: Option<&Arc<telemetry::Tracer>>,
+    notifier: Option<&gateway::WebhookNotifier>,
+    audit: Option<&Arc<security::AuditLog>>,
+) -> Result<Option<channels::discord::DiscordInteractions>> {
+    let Some(discord) = config.discord_config.as_ref().filter(|d| d.enabled) else {
+        return Ok(None);
+    };
+    let (Some(application_id), Some(public_key)) = (&discord.application_id, &discord.public_key) else {
+        return Ok(None);
+    };
+    let token = match std::env::var("DISCORD_BOT_TOKEN") {
+        Ok(token) => token,
+        Err(_) => security::crypto::decrypt_secret(&profile.base_dir, &discord.token)
+            .map_err(|e| format!("discord.token: {}", e))?,
+    };
+    if discord.allowed_users.is_empty() {
+        warn!("[discord] 未配置 allowed_users，斜杠命令不会响应任何人喵");
+    }
+
+    let (provider_name, client) = default_provider_client(config);
+    let provider = providers::ModelProvider::new(provider_name, Arc::new(client), &config.default_model)
+        .with_failover(failover_chain(config, recorder.scoped("discord")));
+    let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+    let rbac = open_rbac(config, profile)?;
+    let mut agent = gateway::ChatBackend::new(Arc::new(provider))
+        .with_approval(Arc::new(
+            security::ToolApproval::new(&approval_config).with_recorder(recorder.scoped("discord")),
+        ))
+        .with_rbac(rbac.clone());
+    if let Some(compressor) = config.compression.compressor() {
+        agent = agent.with_compression(compressor, Some(recorder.scoped("discord")));
+    }
+    agent = agent.with_reply_hooks(build_reply_hooks(config)?, "discord", &config.agent_profile(None)?.0);
+    if let Some(tracer) = tracer {
+        agent = agent.with_tracer(tracer.clone());
+    }
+    if let Some(notifier) = notifier {
+        agent = agent.with_notifier(notifier.clone());
+    }
+    if let Some(audit) = audit {
+        agent = agent.with_audit(audit.clone());
+    }
+    let system_prompt = config.persona.render(&profile.root)?;
+
+    let mut commands = channels::discord::create_default_commands().with_rbac(rbac);
+    // 🌐 /ask 按频道检测回复语言，/lang 可固定喵
+    let languages = SessionLanguages::open(profile.root.join("discord-languages.json"));
+    commands.register(Box::new(
+        channels::discord::AskCommand::new(Arc::new(agent))
+            .with_system_prompt(&system_prompt)
+            .with_languages(languages.clone()),
+    ));
+    commands.register(Box::new(channels::discord::LangCommand::new(languages)));
+    // 🔑 主人在 Discord 里审批放行申请（与 CLI 共享同一份申请存储）喵
+    let escalation = Arc::new(security::EscalationManager::open(profile.root.clone())?);
+    commands.register(Box::new(channels::discord::EscalationDecisionCommand::approve(escalation.clone())));
+    commands.register(Box::new(channels::discord::EscalationDecisionCommand::deny(escalation)));
+    let mut interactions = channels::discord::DiscordInteractions::new(application_id, public_key, &token, commands)?
+        .with_allowed_users(discord.allowed_users.clone());
+    if let Some(guild_id) = &discord.guild_id {
+        interactions = interactions.with_guild(guild_id);
+    }
+    Ok(Some(interactions))
+}
+
+/// 为 `agents.agent` 中的每个 Agent 构建 Gateway 对话后端喵
+///
+/// 都使用 `default_provider`；Gateway 没有本地工具注册表，Agent 开放 `skill` 时只挂载技能
+fn build_agent_backends(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    recorder: &telemetry::MetricsRecorder,
+    tracer: Option<&Arc<telemetry::Tracer>>,
+    notifier: Option<&gateway::WebhookNotifier>,
+    audit: Option<&Arc<security::AuditLog>>,
+) -> Result<Vec<(String, gateway::ChatBackend)>> {
+    if config.agents.agent.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut skills_manager = SkillsManager::new(config.workspace.join("skills"));
+    skills_manager.load_all().ok();
+    let skill_tool = build_skill_tool(config, &SharedSkills::new(skills_manager))?.map(Arc::new);
+    let (provider_name, client) = default_provider_client(config);
+    let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+
+    let mut backends = Vec::new();
+    for (name, agent) in &config.agents.agent {
+        let model = agent.model.as_deref().unwrap_or(&config.default_model);
+        let mut provider = providers::ModelProvider::new(provider_name, Arc::new(client.clone()), model)
+            .with_failover(failover_chain(config, recorder.scoped("gateway")));
+        if let Some(max_tokens) = agent.limits.max_tokens {
+            provider = provider.with_max_tokens(max_tokens);
+        }
+        let system_prompt = match &agent.prompts.system {
+            Some(system) => system.clone(),
+            None => config.persona.render(&profile.root)?,
+        };
+        let system_prompt = match &agent.prompts.suffix {
+            Some(suffix) => format!("{}\n\n{}", system_prompt, suffix),
+            None => system_prompt,
+        };
+        let mut backend = gateway::ChatBackend::new(Arc::new(provider))
+            .with_approval(Arc::new(
+                security::ToolApproval::new(&approval_config).with_recorder(recorder.scoped("gateway")),
+            ))
+            .with_system_prompt(&system_prompt)
+            .with_max_tool_rounds(agent.tool_rounds());
+        if let Some(compressor) = config.compression.compressor() {
+            backend = backend.with_compression(compressor, Some(recorder.scoped("gateway")));
+        }
+        backend = backend.with_reply_hooks(build_reply_hooks(config)?, "gateway", name);
+        if let Some(tracer) = tracer {
+            backend = backend.with_tracer(tracer.clone());
+        }
+        if let Some(notifier) = notifier {
+            backend = backend.with_notifier(notifier.clone());
+        }
+        if let Some(audit) = audit {
+            backend = backend.with_audit(audit.clone());
+        }
+        if let Some(skill_tool) = skill_tool.as_ref().filter(|_| agent.allows_tool("skill")) {
+            backend = backend.with_skills(skill_tool.clone());
+        }
+        backends.push((name.clone(), backend));
+    }
+    Ok(backends)
+}
+
+/// 技能执行工具喵（与 Shell 工具共用白名单，未配置白名单时返回 None）
+fn build_skill_tool(config: &Config, skills: &SharedSkills) -> Result<Option<SkillTool>> {
+    let Some(allowlist) = config.security.as_ref().and_then(|s| s.allowlist.clone()) else {
+        return Ok(None);
+    };
+    let environment = match config.security.as_ref().and_then(|s| s.tool_env.as_ref()) {
+        Some(tool_env) => security::ToolEnvironment::resolve(tool_env)?,
+        None => security::ToolEnvironment::minimal(),
+    };
+    let limits = config.security.as_ref().map(|s| s.shell.clone()).unwrap_or_default();
+    Ok(Some(
+        SkillTool::new(skills.clone(), security::AllowlistService::new(allowlist))
+            .with_environment(environment)
+            .with_limits(limits),
+    ))
+}
+
+/// 出站 Webhook 退出前的最长等待时间喵
+const WEBHOOK_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 构建出站 Webhook 通知器喵（未配置 `[[outbound_webhooks]]` 时返回 None）
+///
+/// 签名密钥在这里解密，投递统计写入 `webhook` 来源的指标
+fn build_webhook_notifier(
+    config: &Config,
+    key_dir: &Path,
+    recorder: &telemetry::MetricsRecorder,
+) -> Result<Option<gateway::WebhookNotifier>> {
+    if config.outbound_webhooks.is_empty() {
+        return Ok(None);
+    }
+    let mut targets = config.outbound_webhooks.clone();
+    for (index, target) in targets.iter_mut().enumerate() {
+        if let Some(secret) = target.secret.as_mut() {
+            *secret = security::crypto::decrypt_secret(key_dir, secret)
+                .map_err(|e| format!("outbound_webhooks[{}].secret: {}", index, e))?;
+        }
+    }
+    Ok(Some(gateway::WebhookNotifier::new(targets).with_recorder(recorder.scoped("webhook"))))
+}
+
+/// 配置并启用 `[otlp]` 时创建导出 Span 的 Tracer 喵
+fn open_otlp_tracer(config: &Config) -> Option<Arc<telemetry::Tracer>> {
+    let otlp = config.otlp.clone().filter(|o| o.enabled)?;
+    let tracer_config = telemetry::TracerConfig {
+        sampling_rate: otlp.sampling_rate,
+        enable_tracing: true,
+    };
+    let endpoint = otlp.endpoint.clone();
+    match telemetry::OtlpExporter::spawn(otlp) {
+        Ok(exporter) => {
+            info!("📡 Span 导出到 OTLP collector: {}", endpoint);
+            Some(Arc::new(telemetry::Tracer::new(tracer_config).with_exporter(exporter)))
+        }
+        Err(e) => {
+            warn!("OTLP 导出器启动失败，Span 不会导出喵: {}", e);
+            None
+        }
+    }
+}
+
+/// 构建与 Agent 相同工具集的能力目录喵（`tools list` 与 `/v1/tools` 共用）
+///
+/// 只用于描述工具：放行申请不落盘，外部 MCP server 连接失败的工具不出现在目录中
+async fn build_tool_catalog(config: &Config, config_dir: &Path) -> tools::ToolCatalog {
+    let mut registry = ToolRegistry::new();
+    let workspace = &config.workspace;
+    let _ = registry.register(FileSystemTool::new(workspace));
+    let _ = registry.register(FsWriteTool::new(workspace));
+    let _ = registry.register(FsSearchTool::new(workspace));
+    let _ = registry.register(FsPatchTool::new(workspace));
+    let _ = registry.register(EchoTool);
+    let mut skills_manager = SkillsManager::new(workspace.join("skills"));
+    skills_manager.load_all().ok();
+    let skills = SharedSkills::new(skills_manager);
+    let history = open_session_store(config, config_dir, &config_dir.join("sessions"))
+        .and_then(core::HistoryIndex::open);
+    match history {
+        Ok(index) => {
+            let _ = registry.register(HistorySearchTool::new(index));
+        }
+        Err(e) => warn!("对话历史索引打开失败，目录中不包含 history_search 喵: {}", e),
+    }
+    if let Some(allowlist) = config.security.as_ref().and_then(|s| s.allowlist.clone()) {
+        let limits = config.security.as_ref().map(|s| s.shell.clone()).unwrap_or_default();
+        let allowlist = security::AllowlistService::new(allowlist);
+        let skill_tool = SkillTool::new(skills.clone(), allowlist.clone()).with_limits(limits.clone());
+        let shell = ShellTool::new(Arc::new(allowlist))
+            .with_working_dir(workspace)
+            .with_limits(limits);
+        let _ = registry.register(McpShellTool::new(shell));
+        let _ = registry.register(EscalationTool::new(Arc::new(security::EscalationManager::in_memory())));
+        if !skill_tool.is_empty() {
+            let _ = registry.register(skill_tool);
+        }
+    }
+    if !config.mcp_servers.is_empty() {
+        tools::register_mcp_servers(&mut registry, &config.mcp_servers).await;
+    }
+
+    tools::ToolCatalog::from_registry(&registry).with_skills(&skills.skills())
+}
+
+/// 列出工具与技能目录喵
+async fn handle_tools_list(format: OutputFormat, config: &Config, config_dir: &Path) -> Result<()> {
+    let catalog = build_tool_catalog(config, config_dir).await;
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&catalog)?);
+        return Ok(());
+    }
+
+    println!("{:<24} {:<10} {:<12} {:<6} DESCRIPTION", "NAME", "SOURCE", "CATEGORY", "DANGER");
+    for entry in &catalog.tools {
+        let source = match &entry.source {
+            tools::ToolSource::Mcp { server } => format!("mcp:{}", server),
+            tools::ToolSource::Plugin { plugin } => format!("plugin:{}", plugin),
+            other => other.as_str().to_string(),
+        };
+        let description = entry.description.lines().next().unwrap_or_default();
+        println!(
+            "{:<24} {:<10} {:<12} {:<6} {}",
+            entry.name,
+            source,
+            entry.category.as_deref().unwrap_or("-"),
+            if entry.dangerous { "yes" } else { "" },
+            description
+        );
+    }
+    println!("共 {} 项喵", catalog.tools.len());
+    Ok(())
+}
+
+/// 处理技能管理命令喵
+fn handle_skills(action: &SkillsAction, config: &Config) -> Result<()> {
+    let skills_dir = config.workspace.join("skills");
+    let mut manager = SkillsManager::new(skills_dir.clone());
+    manager.load_all()?;
+
+    match action {
+        SkillsAction::List { format } => {
+            let entries: Vec<(&Skill, bool)> = manager
+                .get_skills()
+                .iter()
+                .map(|s| (s, true))
+                .chain(manager.disabled_skills().iter().map(|s| (s, false)))
+                .collect();
+            if *format == OutputFormat::Json {
+                let json: Vec<_> = entries
+                    .iter()
+                    .map(|(skill, enabled)| serde_json::json!({ "enabled": enabled, "skill": skill }))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
+            println!("{:<20} {:<9} {:<7} {:<28} DESCRIPTION", "NAME", "STATUS", "PARAMS", "COMMAND");
+            for (skill, enabled) in &entries {
+                println!(
+                    "{:<20} {:<9} {:<7} {:<28} {}",
+                    skill.name,
+                    if *enabled { "enabled" } else { "disabled" },
+                    skill.parameters.len(),
+                    skill.command.as_deref().unwrap_or("-"),
+                    skill.description.lines().next().unwrap_or_default()
+                );
+            }
+            println!("共 {} 个技能（{}）喵", entries.len(), skills_dir.display());
+        }
+        SkillsAction::Show { name } => {
+            let (skill, enabled) = manager.find(name).ok_or_else(|| format!("找不到技能喵: {}", name))?;
+            println!("🔧 {}{}", skill.name, if enabled { "" } else { "（已禁用）" });
+            println!("   目录: {}", skill.path.display());
+            println!("   命令: {}", skill.command.as_deref().unwrap_or("-（只提供说明，不能通过 skill 工具执行）"));
+            println!();
+            println!("{}", skill.description);
+            if !skill.parameters.is_empty() {
+                println!("\n参数:");
+                for param in &skill.parameters {
+                    let required = if param.required { "必填" } else { "可选" };
+                    print!("   {:<16} {} {}", param.name, required, param.description);
+                    if let Some(default) = &param.default {
+                        print!(" [默认: {}]", default);
+                    }
+                    println!();
+                }
+            }
+        }
+        SkillsAction::Enable { name } | SkillsAction::Disable { name } => {
+            let enable = matches!(action, SkillsAction::Enable { .. });
+            manager.set_enabled(name, enable)?;
+            println!(
+                "✅ 技能 {} 已{}喵（运行中的守护进程会自动重新加载）",
+                name,
+                if enable { "启用" } else { "禁用" }
+            );
+        }
+        SkillsAction::Reload => {
+            // 刷新状态文件的修改时间，守护进程的目录监视据此重新加载喵
+            skills::SkillState::load(&skills_dir)?.save(&skills_dir)?;
+            println!(
+                "🔄 已加载 {} 个技能（{} 个已禁用），运行中的守护进程会随后重新加载喵",
+                manager.get_skills().len(),
+                manager.disabled_skills().len()
+            );
+        }
+        SkillsAction::New { name } => {
+            let path = skills::state::scaffold(&skills_dir, name)?;
+            println!("✨ 已创建 {}", path.display());
+            println!("   编辑「## 执行」与「## 参数」后即可使用，命令需在 [security.allowlist] 中喵");
+        }
+    }
+    Ok(())
+}
+
+/// 启动 provider 健康探测（每 `interval_minutes` 一次）喵
+///
+/// 未启用或没有配置任何 provider 时返回 None
+fn spawn_provider_probes(
+    supervisor: &mut service::TaskSupervisor,
+    config: &Config,
+    recorder: &telemetry::MetricsRecorder,
+    notifier: Option<&gateway::WebhookNotifier>,
+) -> Option<Arc<providers::ProviderHealth>> {
+    if !config.provider_health.enabled {
+        return None;
+    }
+    let mut health = providers::ProviderHealth::from_config(config)
+        .with_recorder(recorder.scoped("provider_health"));
+    if let Some(notifier) = notifier {
+        health = health.with_notifier(notifier.clone());
+    }
+    if health.is_empty() {
+        return None;
+    }
+    let health = Arc::new(health);
+    let probes = health.clone();
+    supervisor.spawn_periodic("provider_health", config.provider_health.interval(), move || {
+        let probes = probes.clone();
+        async move {
+            probes.probe_all().await;
+            Ok(())
+        }
+    });
+    Some(health)
+}
+
+/// 加载技能并监视技能目录，变化后重新加载喵
+///
+/// 返回的 `SharedSkills` 交给 Agent 使用，重新加载后下一次请求即可看到新技能
+fn spawn_skills_watcher(supervisor: &mut service::TaskSupervisor, skills_dir: PathBuf) -> SharedSkills {
+    let mut manager = SkillsManager::new(skills_dir.clone());
+    if let Err(e) = manager.load_all() {
+        warn!("Skills 加载失败喵: {}", e);
+    }
+    let skills = SharedSkills::new(manager);
+    let watcher = Arc::new(std::sync::Mutex::new(service::FileWatcher::new(service::WatchConfig {
+        path: skills_dir,
+        chat_id: 0,
+        recursive: true,
+        debounce_secs: 1,
+    })));
+    let shared = skills.clone();
+    supervisor.spawn_periodic("skills_watch", WATCH_POLL_INTERVAL, move || {
+        let changes = watcher.lock().unwrap().poll(std::time::Instant::now());
+        let skills = shared.clone();
+        async move {
+            if changes.is_none() {
+                return Ok(());
+            }
+            match skills.reload() {
+                Ok(true) => info!("🔄 Skills 已重新加载（{} 个）", skills.skills().len()),
+                Ok(false) => {}
+                Err(e) => warn!("Skills 重新加载失败喵: {}", e),
+            }
+            Ok(())
+        }
+    });
+    skills
+}
+
+/// 监视目录的轮询间隔（去抖在 `FileWatcher` 内部处理）喵
+const WATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 为每个 `[[watch]]` 目录启动轮询，文件变化注入对应 Chat 的 Agent 对话喵
+fn spawn_file_watchers(
+    supervisor: &mut service::TaskSupervisor,
+    watches: &[service::WatchConfig],
+    bot: &Arc<channels::telegram::TelegramBot>,
+) {
+    for watch in watches {
+        let watcher = Arc::new(std::sync::Mutex::new(service::FileWatcher::new(watch.clone())));
+        let bot = bot.clone();
+        let chat_id = watch.chat_id;
+        info!("👀 监视 {}（通知 chat {}）", watch.resolved_path().display(), chat_id);
+        supervisor.spawn_periodic(&format!("watch:{}", watch.path.display()), WATCH_POLL_INTERVAL, move || {
+            let changes = watcher.lock().unwrap().poll(std::time::Instant::now());
+            let bot = bot.clone();
+            async move {
+                let Some(changes) = changes else {
+                    return Ok(());
+                };
+                bot.notify_event(chat_id, &changes.to_event_message())
+                    .await
+                    .map_err(|e| format!("文件变化通知失败喵: {}", e))
+            }
+        });
+    }
+}
+
+/// 事件队列分发任务与队列深度指标（每 30 秒）喵
+fn spawn_event_bus(
+    supervisor: &mut service::TaskSupervisor,
+    config: &core::BusConfig,
+    recorder: &telemetry::MetricsRecorder,
+    shutdown: &service::ShutdownCoordinator,
+) -> Arc<core::EventBus> {
+    let bus = Arc::new(core::EventBus::new(config.clone()).with_shutdown(shutdown.clone()));
+    {
+        let bus = bus.clone();
+        supervisor.spawn("event_bus", move || {
+            let bus = bus.clone();
+            async move { bus.run().await.map_err(|e| e.to_string()) }
+        });
+    }
+    {
+        let bus = bus.clone();
+        let recorder = recorder.scoped("event_bus");
+        supervisor.spawn_periodic("event_bus_metrics", std::time::Duration::from_secs(30), move || {
+            let stats = bus.stats();
+            recorder.gauge("event_bus_queue_depth", stats.queued as f64, &[]);
+            recorder.gauge("event_bus_saturated", stats.saturated as f64, &[]);
+            for (channel, in_flight) in &stats.in_flight {
+                recorder.gauge("event_bus_in_flight", *in_flight as f64, &[("channel", channel)]);
+            }
+            async { Ok(()) }
+        });
+    }
+    bus
+}
+
+/// 记录关闭时排空的结果喵（指标同步写入，之后再刷新 Tracer 与 Webhook）
+fn record_shutdown(recorder: &telemetry::MetricsRecorder, report: &service::DrainReport) {
+    let recorder = recorder.scoped("shutdown");
+    recorder.gauge("shutdown_drained_requests", report.completed() as f64, &[]);
+    recorder.gauge("shutdown_cancelled_requests", report.cancelled as f64, &[]);
+    recorder.gauge("shutdown_drain_ms", report.elapsed.as_millis() as f64, &[]);
+    match report.cancelled {
+        0 => info!("✅ {} 个进行中的请求已完成（{:?}）", report.in_flight, report.elapsed),
+        n => warn!("宽限期已过，强制取消 {} 个进行中的请求喵（{} 个已完成）", n, report.completed()),
+    }
+}
+
+/// `default_provider` 的客户端（不认识时回退到 nvidia）喵
+/// 回复钩子（`[post_process]` 后处理链），CLI 与各渠道后端共用喵
+fn build_reply_hooks(config: &Config) -> Result<HookRegistry> {
+    let mut hooks = HookRegistry::new();
+    if let Some(post_process) = &config.post_process {
+        hooks.register_reply_hook(Arc::new(PostProcessChain::compile(post_process)?));
+    }
+    Ok(hooks)
+}
+
+/// 🔀 渠道与 Gateway 的备用模型链喵（与 `nekoclaw agent` 一样按 `agents.defaults.model.fallback` 切换）
+fn failover_chain(config: &Config, recorder: telemetry::MetricsRecorder) -> providers::FailoverChain {
+    providers::FailoverChain::new(&config.agents.defaults.model).with_recorder(recorder)
+}
+
+fn default_provider_client(config: &Config) -> (&str, OpenAIClient) {
+    let (name, provider) = match config.provider(&config.default_provider) {
+        Some(provider) => (config.default_provider.as_str(), provider),
+        None => ("nvidia", config.provider("nvidia").expect("nvidia is a known provider")),
+    };
+    let client = OpenAIClient::new(OpenAIConfig {
+        api_key: provider.api_key,
+        base_url: provider.base_url,
+        timeout: provider.timeout,
+        max_retries: provider.max_retries,
+    });
+    (name, client)
+}
+
+/// 构建接入 Agent 的 Telegram Bot 喵
+///
+/// 用给定的 provider 客户端和 `default_model` 回复普通消息，每个 Chat 的对话保存为独立会话
+#[allow(clippy::too_many_arguments)]
+fn build_telegram_bot(
+    settings: &core::traits::TelegramSettings,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    recorder: &telemetry::MetricsRecorder,
+    provider_name: &str,
+    client: OpenAIClient,
+    tracer: Option<&Arc<telemetry::Tracer>>,
+    notifier: Option<&gateway::WebhookNotifier>,
+    skills: &SharedSkills,
+) -> Result<channels::telegram::TelegramBot> {
+    let token = std::env::var("TELEGRAM_BOT_TOKEN")
+        .map_err(|_| "telegram 渠道需要 TELEGRAM_BOT_TOKEN 环境变量喵")?;
+    if settings.allowed_chat_ids.is_empty() {
+        warn!("[telegram] 未配置 allowed_chat_ids，Bot 不会响应任何消息喵");
+    }
+
+    // 📏 Telegram 回复简短：提示词要求 + max_tokens 上限喵
+    let response_policy = config.response_policy("telegram");
+    let persona = config.persona.render(&profile.root)?;
+    let system_prompt = match response_policy.instruction("telegram") {
+        Some(style) => format!("{}\n\n{}", persona, style),
+        None => persona,
+    };
+    let mut provider = providers::ModelProvider::new(provider_name, Arc::new(client), &config.default_model)
+        .with_failover(failover_chain(config, recorder.scoped("telegram")));
+    if let Some(max_tokens) = response_policy.max_response_tokens {
+        provider = provider.with_max_tokens(max_tokens);
+    }
+    // Telegram 无法交互确认，危险工具只按配置放行喵
+    let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+
+    let rbac = open_rbac(config, profile)?;
+    let mut backend = gateway::ChatBackend::new(Arc::new(provider))
+        .with_approval(Arc::new(
+            security::ToolApproval::new(&approval_config).with_recorder(recorder.scoped("telegram")),
+        ))
+        .with_rbac(rbac.clone());
+    if let Some(compressor) = config.compression.compressor() {
+        backend = backend.with_compression(compressor, Some(recorder.scoped("telegram")));
+    }
+    backend = backend.with_reply_hooks(build_reply_hooks(config)?, "telegram", &config.agent_profile(None)?.0);
+    if let Some(tracer) = tracer {
+        backend = backend.with_tracer(tracer.clone());
+    }
+    if let Some(notifier) = notifier {
+        backend = backend.with_notifier(notifier.clone());
+    }
+    if let Some(audit) = open_audit_log(&profile.root) {
+        backend = backend.with_audit(audit);
+    }
+    // 技能与 Shell 工具共用白名单，未配置白名单时不执行技能喵
+    if let Some(skill_tool) = build_skill_tool(config, skills)? {
+        backend = backend.with_skills(Arc::new(skill_tool));
+    }
+    let mut bot = channels::telegram::TelegramBot::new(token, channels::telegram::TelegramConfig::default())?
+        .with_agent(Arc::new(backend))
+        .with_system_prompt(&system_prompt)
+        .with_sessions(open_session_store(config, &profile.root, &profile.sessions_dir())?)
+        .with_languages(SessionLanguages::open(profile.root.join("telegram-languages.json")))
+        .with_feedback(recorder.scoped("telegram"));
+    for chat_id in &settings.allowed_chat_ids {
+        bot.add_allowed_chat_id(*chat_id);
+    }
+    // 📱 主人可以在 Telegram 里用 /pair 批准设备配对喵
+    Ok(bot
+        .with_rbac(rbac)
+        .with_device_pairing(Arc::new(gateway::DeviceCodes::open(&profile.root))))
+}
+
+/// 邮箱密码喵（`[email] password` 支持 `enc:` 加密值，为空时读取 EMAIL_PASSWORD）
+fn email_password(settings: &channels::email::EmailConfig, key_dir: &Path) -> Result<String> {
+    if settings.password.is_empty() {
+        return Ok(std::env::var("EMAIL_PASSWORD").map_err(|_| "email 渠道需要 password 或 EMAIL_PASSWORD 环境变量喵")?);
+    }
+    Ok(security::crypto::decrypt_secret(key_dir, &settings.password).map_err(|e| format!("email.password: {}", e))?)
+}
+
+/// 构建邮件渠道喵（收件人无法交互确认，危险工具只按配置放行）
+#[allow(clippy::too_many_arguments)]
+fn build_email_bot(
+    settings: &channels::email::EmailConfig,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    recorder: &telemetry::MetricsRecorder,
+    provider_name: &str,
+    client: OpenAIClient,
+    tracer: Option<&Arc<telemetry::Tracer>>,
+    notifier: Option<&gateway::WebhookNotifier>,
+    skills: &SharedSkills,
+) -> Result<channels::email::EmailBot> {
+    if settings.allowed_senders.is_empty() {
+        warn!("[email] 未配置 allowed_senders，不会回复任何邮件喵");
+    }
+    let server = settings.server(email_password(settings, &profile.base_dir)?);
+
+    let response_policy = config.response_policy("email");
+    let persona = config.persona.render(&profile.root)?;
+    let system_prompt = match response_policy.instruction("email") {
+        Some(style) => format!("{}\n\n{}", persona, style),
+        None => persona,
+    };
+    let mut provider = providers::ModelProvider::new(provider_name, Arc::new(client), &config.default_model)
+        .with_failover(failover_chain(config, recorder.scoped("email")));
+    if let Some(max_tokens) = response_policy.max_response_tokens {
+        provider = provider.with_max_tokens(max_tokens);
+    }
+    let approval_config = config.security.as_ref().map(|s| s.tool_approval.clone()).unwrap_or_default();
+
+    let rbac = open_rbac(config, profile)?;
+    let mut backend = gateway::ChatBackend::new(Arc::new(provider))
+        .with_approval(Arc::new(
+            security::ToolApproval::new(&approval_config).with_recorder(recorder.scoped("email")),
+        ))
+        .with_rbac(rbac.clone());
+    if let Some(compressor) = config.compression.compressor() {
+        backend = backend.with_compression(compressor, Some(recorder.scoped("email")));
+    }
+    backend = backend.with_reply_hooks(build_reply_hooks(config)?, "email", &config.agent_profile(None)?.0);
+    if let Some(tracer) = tracer {
+        backend = backend.with_tracer(tracer.clone());
+    }
+    if let Some(notifier) = notifier {
+        backend = backend.with_notifier(notifier.clone());
+    }
+    if let Some(audit) = open_audit_log(&profile.root) {
+        backend = backend.with_audit(audit);
+    }
+    if let Some(skill_tool) = build_skill_tool(config, skills)? {
+        backend = backend.with_skills(Arc::new(skill_tool));
+    }
+    Ok(channels::email::EmailBot::new(settings.clone(), Arc::new(server))
+        .with_agent(Arc::new(backend))
+        .with_system_prompt(&system_prompt)
+        .with_rbac(rbac))
+}
+
+/// 打开工作区的角色权限喵（`[telegram] owner_user_ids` 始终为 Owner）
+fn open_rbac(config: &Config, profile: &core::WorkspaceProfile) -> Result<Arc<auth::Rbac>> {
+    let mut rbac = auth::Rbac::open(&profile.root.join(auth::RBAC_DB_FILE), config.rbac.clone())?;
+    for user_id in config.telegram.iter().flat_map(|t| &t.owner_user_ids) {
+        rbac = rbac.with_owner(auth::Identity::new("telegram", user_id));
+    }
+    Ok(Arc::new(rbac))
+}
+
+/// 处理 Daemon 模式喵
+async fn handle_daemon(
+    background: bool,
+    daemon: bool,
+    pid_file: &Option<PathBuf>,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    info!("Daemon mode: background={}, daemon={}", background, daemon);
+    let config_dir = profile.root.as_path();
+    let pid_path = match pid_file {
+        Some(path) => expand_path(path.clone())?,
+        None => config_dir.join(service::daemon::DEFAULT_PID_FILE),
+    };
+
+    // 后台模式：脱离终端重新启动自身，父进程确认子进程就绪后退出喵
+    if (background || daemon) && !service::daemon::is_daemon_child() {
+        if let Some(pid) = service::daemon::running_pid(&pid_path) {
+            return Err(format!("守护进程已在运行喵 (pid {}, {})", pid, pid_path.display()).into());
+        }
+        let log_path = config_dir.join("logs").join(service::daemon::DAEMON_LOG_FILE);
+        let mut child = service::daemon::spawn_detached(&log_path)?;
+        match service::daemon::wait_until_ready(&mut child, &pid_path, std::time::Duration::from_secs(5)) {
+            Ok(Some(pid)) => println!("⚡ 守护进程已在后台启动喵 (pid {})", pid),
+            Ok(None) => println!("⚡ 守护进程仍在启动中喵 (pid {})", child.id()),
+            Err(e) => return Err(format!("{}，详见日志 {}", e, log_path.display()).into()),
+        }
+        println!("   PID 文件: {}", pid_path.display());
+        println!("   日志: {}", log_path.display());
+        return Ok(());
+    }
+    let pid_file = service::PidFile::acquire(&pid_path)?;
+    // 🔢 按默认模型选择 token 计数器（渠道 Agent 的上下文压缩阈值共用）喵
+    providers::tokenizer::install(config.tokenizer.counter_for_model(config_dir, &config.default_model));
+
+    // 所有后台循环由监督器托管：失败自动重启，退出时统一取消喵
+    let mut supervisor =
+        service::TaskSupervisor::new().with_health_file(config_dir.join(TASK_HEALTH_FILE));
+
+    // 配置热重载：配置文件变化后重新校验，推送给服务管理器与按需读取配置的后台任务喵
+    let config_watcher = Arc::new(core::config::ConfigWatcher::new(profile.clone(), config.clone()));
+    let manager = ServiceManager::with_config(config.clone());
+    manager.watch_config(&mut supervisor, config_watcher.subscribe());
+    let shutdown = manager.shutdown_coordinator();
+    {
+        let watcher = config_watcher.clone();
+        // 重新加载与被拒绝的配置变更写入审计日志喵
+        let audit = open_audit_log(config_dir);
+        supervisor.spawn_periodic("config_watch", std::time::Duration::from_secs(2), move || {
+            let watcher = watcher.clone();
+            let audit = audit.clone();
+            async move {
+                let event = security::AuditEvent::new(security::AuditKind::Config, "daemon", "config.reload");
+                let event = match watcher.poll() {
+                    Ok(true) => {
+                        info!("🔄 配置已重新加载喵");
+                        Some(event)
+                    }
+                    Ok(false) => None,
+                    Err(e) => {
+                        warn!("{}", e);
+                        Some(event.with_status(security::AuditStatus::Failed).with_detail(e.to_string()))
+                    }
+                };
+                if let (Some(audit), Some(event)) = (&audit, event) {
+                    audit.record_or_warn(&event);
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 数据保留清理（每小时）喵
+    if config.privacy.as_ref().is_some_and(|p| !p.retention_days.is_empty()) {
+        let config = Arc::new(config.clone());
+        let config_dir = Arc::new(config_dir.to_path_buf());
+        supervisor.spawn_periodic("privacy_retention", std::time::Duration::from_secs(3600), move || {
+            let config = config.clone();
+            let config_dir = config_dir.clone();
+            async move {
+                let service = open_privacy_service(&config, &config_dir)
+                    .await
+                    .map_err(|e| format!("数据保留清理失败喵: {}", e))?;
+                service
+                    .enforce_retention(
+                        config.privacy.as_ref().expect("checked above"),
+                        chrono::Utc::now(),
+                    )
+                    .map(|_| ())
+                    .map_err(|e| format!("数据保留清理失败喵: {}", e))
+            }
+        });
+    }
+
+    // 低分记忆清理（每天）喵
+    if config.memory.as_ref().is_some_and(|m| m.scoring.auto_prune) {
+        let config = Arc::new(config.clone());
+        supervisor.spawn_periodic("memory_prune", std::time::Duration::from_secs(86_400), move || {
+            let config = config.clone();
+            async move {
+                let settings = config.memory.clone().unwrap_or_default();
+                let memory = memory::MemoryFactory::open_sqlite(&config.memory_db_path().to_string_lossy(), &settings)
+                    .map_err(|e| format!("记忆清理失败喵: {}", e))?;
+                let pruned = memory
+                    .prune(chrono::Utc::now(), false)
+                    .map_err(|e| format!("记忆清理失败喵: {}", e))?;
+                if !pruned.is_empty() {
+                    info!("🧹 已清理 {} 条低分记忆喵", pruned.len());
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 死信队列重试（每分钟检查到期条目，渠道按当前配置重建）喵
+    {
+        let config_watcher = config_watcher.clone();
+        let outbox_path = Arc::new(config_dir.join(OUTBOX_DB));
+        supervisor.spawn_periodic("outbox_retry", std::time::Duration::from_secs(60), move || {
+            let config = config_watcher.current();
+            let outbox_path = outbox_path.clone();
+            async move {
+                let outbox = channels::Outbox::open(outbox_path.as_path())
+                    .map_err(|e| format!("无法打开死信队列喵: {}", e))?;
+                let report = outbox
+                    .retry_due(chrono::Utc::now(), |name| build_channel(&config, name))
+                    .await
+                    .map_err(|e| format!("死信重试失败喵: {}", e))?;
+                if report.delivered + report.failed > 0 {
+                    info!(
+                        "📮 死信重试: {} 条送达, {} 条仍失败",
+                        report.delivered, report.failed
+                    );
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 运行时日志级别（`nekoclaw logs level`）喵
+    if let Some(handle) = service::log_level::global() {
+        let path = config_dir.join(service::log_level::LOG_FILTER_FILE);
+        // 上次运行遗留的覆盖不应在重启后继续生效喵
+        let _ = std::fs::remove_file(&path);
+        let watcher = Arc::new(service::log_level::ControlFileWatcher::new(path, handle.clone()));
+        supervisor.spawn_periodic("log_level", std::time::Duration::from_secs(2), move || {
+            let watcher = watcher.clone();
+            async move { watcher.poll() }
+        });
+    }
+
+    // 孤立产物 / 过期凭证 / 临时目录清理（每 6 小时）喵
+    {
+        let maintenance = Arc::new(open_maintenance(config, profile)?);
+        supervisor.spawn_periodic("maintenance", std::time::Duration::from_secs(6 * 3600), move || {
+            let maintenance = maintenance.clone();
+            async move {
+                let report = maintenance
+                    .run()
+                    .await
+                    .map_err(|e| format!("维护任务失败喵: {}", e))?;
+                if !report.is_empty() {
+                    info!(
+                        "🧹 维护完成，释放 {}",
+                        service::maintenance::format_bytes(report.reclaimed_bytes())
+                    );
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 上游 provider 健康探测喵
+    let recorder = open_metrics_recorder(config_dir).await?;
+    let notifier = build_webhook_notifier(config, &profile.base_dir, &recorder)?;
+    spawn_provider_probes(&mut supervisor, config, &recorder, notifier.as_ref());
+
+    // ⏰ 定时任务：每次检查读取最新配置，热重载新增的任务同样生效喵
+    {
+        for (name, e) in service::scheduler::invalid_tasks(&config.schedule) {
+            warn!("定时任务 {} 不会运行喵: {}", name, e);
+        }
+        let scheduler = Arc::new(service::Scheduler::new(chrono::Local::now()));
+        let config_watcher = config_watcher.clone();
+        let profile = Arc::new(profile.clone());
+        let recorder = recorder.scoped("scheduler");
+        supervisor.spawn_periodic("scheduler", std::time::Duration::from_secs(30), move || {
+            let config = config_watcher.current();
+            let due = scheduler.due(&config.schedule, chrono::Local::now());
+            let profile = profile.clone();
+            let recorder = recorder.clone();
+            async move {
+                for name in due {
+                    let task = &config.schedule[&name];
+                    info!("⏰ 运行定时任务 {} ({})", name, task.action.describe());
+                    let run = run_scheduled_task(&config, &profile, &name, task, &recorder).await;
+                    if !run.ok {
+                        warn!("定时任务 {} 失败喵: {}", name, run.output);
+                    }
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 默认 provider 客户端：Telegram Bot 与预热共用同一个连接池喵
+    let (provider_name, client) = default_provider_client(config);
+
+    // 📡 Agent 调用链导出到 OTLP collector（配置 `[otlp]` 后启用）喵
+    let tracer = open_otlp_tracer(config);
+
+    // 📚 技能目录变化（新增 SKILL.md、skills enable/disable）后自动重新加载喵
+    let skills = spawn_skills_watcher(&mut supervisor, config.workspace.join("skills"));
+
+    // Telegram 长轮询（配置 `[telegram]` 后启用）喵
+    if let Some(settings) = &config.telegram {
+        // 📬 普通消息先进入事件队列，由 worker 按并发上限交给 Agent 喵
+        let bus = spawn_event_bus(&mut supervisor, &config.event_bus, &recorder, &shutdown);
+        let bot = Arc::new(
+            build_telegram_bot(
+                settings,
+                config,
+                profile,
+                &recorder,
+                provider_name,
+                client.clone(),
+                tracer.as_ref(),
+                notifier.as_ref(),
+                &skills,
+            )?
+            .with_event_bus(bus.clone()),
+        );
+        bus.register("telegram", bot.clone());
+        spawn_file_watchers(&mut supervisor, &config.watch, &bot);
+        supervisor.spawn("telegram", move || {
+            let bot = bot.clone();
+            async move { bot.run_polling().await.map_err(|e| e.to_string()) }
+        });
+    } else if !config.watch.is_empty() {
+        warn!("配置了 [[watch]] 但未配置 [telegram]，文件变化不会通知任何会话喵");
+    }
+
+    // 📧 邮件渠道定时轮询收件箱（配置 `[email]` 后启用）喵
+    if let Some(settings) = &config.email {
+        let bot = Arc::new(build_email_bot(
+            settings,
+            config,
+            profile,
+            &recorder,
+            provider_name,
+            client.clone(),
+            tracer.as_ref(),
+            notifier.as_ref(),
+            &skills,
+        )?);
+        supervisor.spawn_periodic("email", settings.poll_interval(), move || {
+            let bot = bot.clone();
+            async move {
+                let report = bot.poll_once().await.map_err(|e| format!("邮件轮询失败喵: {}", e))?;
+                if report != channels::email::PollReport::default() {
+                    info!(
+                        "📧 邮件轮询: 回复 {} 封，忽略 {} 封，失败 {} 封",
+                        report.replied, report.ignored, report.failed
+                    );
+                }
+                Ok(())
+            }
+        });
+    }
+
+    // 启动预热：提前建立连接、打开记忆库、加载 Skills 喵
+    if config.warmup.enabled {
+        let warmup = Arc::new(
+            service::Warmup::new(config.warmup.clone())
+                .with_client(provider_name, client)
+                .with_memory(config.memory_db_path(), config.memory.clone().unwrap_or_default())
+                .with_skills_dir(config.workspace.join("skills")),
+        );
+        supervisor.spawn("warmup", move || {
+            let warmup = warmup.clone();
+            async move {
+                warmup.run().await;
+                Ok(())
+            }
+        });
+    }
+
+    // 定时同步喵
+    if let Some(sync_config) = config.sync.as_ref().filter(|s| s.enabled && s.interval_minutes > 0) {
+        let interval = std::time::Duration::from_secs(sync_config.interval_minutes * 60);
+        let profile = Arc::new(profile.clone());
+        supervisor.spawn_periodic("sync", interval, move || {
+            let profile = profile.clone();
+            async move {
+                // 每次重新读取配置：远端配置胜出并写回后，不能再把启动时的旧配置推回去喵
+                let engine = profile
+                    .load_config()
+                    .and_then(|config| sync::SyncEngine::from_config(&config, &profile))
+                    .map_err(|e| format!("无法初始化同步喵: {}", e))?;
+                engine
+                    .run_once()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| format!("定时同步失败喵: {}", e))
+            }
+        });
+    }
+
+    if service::daemon::is_daemon_child() {
+        info!("🔄 守护进程运行中 (pid {}, {})", std::process::id(), pid_file.path().display());
+    } else {
+        println!("🎯 前台运行模式喵（按 Ctrl+C 停止）");
+    }
+    service::daemon::shutdown_signal().await?;
+    info!("⏹️ 收到停止信号，等待进行中的请求完成喵...");
+    let report = manager.shutdown().await;
+    record_shutdown(&recorder, &report);
+    info!("⏹️ 正在关闭后台任务喵...");
+    supervisor.shutdown(std::time::Duration::from_secs(10)).await;
+    if let Some(tracer) = &tracer {
+        tracer.flush().await;
+    }
+    if let Some(notifier) = &notifier {
+        notifier.flush(WEBHOOK_FLUSH_TIMEOUT).await;
+    }
+    drop(pid_file);
+
+    Ok(())
+}
+
+/// 后台任务健康快照文件名喵
+const TASK_HEALTH_FILE: &str = "tasks.json";
+
+/// 处理实验报告喵
+async fn handle_experiments_report(
+    experiment: Option<&str>,
+    format: OutputFormat,
+    config: &Config,
+    config_dir: &Path,
+) -> Result<()> {
+    let metrics_path = config_dir.join("metrics.db");
+    let metrics = if metrics_path.exists() {
+        let collector = telemetry::MetricsCollector::new(telemetry::MetricsConfig {
+            db_path: metrics_path.to_string_lossy().to_string(),
+            monitor_interval_sec: 5,
+        })
+        .await?;
+        let mut metrics = collector.get_recent_custom_metrics(Some(core::experiment::TURN_METRIC), u32::MAX)?;
+        metrics.extend(collector.get_recent_custom_metrics(Some(core::experiment::FEEDBACK_METRIC), u32::MAX)?);
+        metrics
+    } else {
+        Vec::new()
+    };
+    let reports = core::experiment::summarize(&metrics, experiment);
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    for configured in &config.experiments {
+        let state = if configured.enabled { "启用" } else { "停用" };
+        let weights: Vec<String> = configured
+            .variants
+            .iter()
+            .map(|v| format!("{}={}", v.name, v.weight))
+            .collect();
+        println!("🧪 {} (agent={}, {}): {}", configured.name, configured.agent, state, weights.join(" "));
+    }
+    if reports.is_empty() {
+        println!("📭 还没有实验数据喵");
+        return Ok(());
+    }
+
+    println!(
+        "{:<16} {:<12} {:>8} {:>6} {:>8} {:>10} {:>5} {:>5} {:>5}",
+        "EXPERIMENT", "VARIANT", "SESSIONS", "TURNS", "SUCCESS", "AVG_MS", "UP", "DOWN", "NET"
+    );
+    for report in &reports {
+        println!(
+            "{:<16} {:<12} {:>8} {:>6} {:>7.1}% {:>10.0} {:>5} {:>5} {:>+5}",
+            report.experiment,
+            report.variant,
+            report.sessions,
+            report.turns,
+            report.success_rate() * 100.0,
+            report.avg_latency_ms(),
+            report.thumbs_up,
+            report.thumbs_down,
+            report.feedback_score()
+        );
+    }
+    Ok(())
+}
+
+/// 打开工作区的维护任务（含凭证目录）喵
+fn open_maintenance(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<service::maintenance::Maintenance> {
+    let store = auth::CredentialStore::new(profile.credentials_dir(), auth::master_crypto(&profile.root)?)?;
+    Ok(service::maintenance::Maintenance::new(profile)
+        .with_credentials(store)
+        .with_pairing(gateway::DeviceCodes::open(&profile.root))
+        .with_scratch_ttl(config.scratch.ttl()))
+}
+
+/// 处理维护命令喵
+async fn handle_maintenance(config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    use service::maintenance::format_bytes;
+
+    println!("🧹 正在清理工作区 {} 喵...", profile.name);
+    let report = open_maintenance(config, profile)?.run().await?;
+
+    println!(
+        "  孤立产物:   {} 个 ({})",
+        report.artifacts.removed,
+        format_bytes(report.artifacts.bytes)
+    );
+    println!(
+        "  过期凭证:   {} 个 ({})",
+        report.credentials.removed,
+        format_bytes(report.credentials.bytes)
+    );
+    println!("  过期配对申请: {} 个", report.pairing_codes);
+    println!(
+        "  沙箱临时目录: {} 个 ({})",
+        report.sandbox_dirs.removed,
+        format_bytes(report.sandbox_dirs.bytes)
+    );
+    println!(
+        "  会话临时目录: {} 个 ({})",
+        report.scratch_dirs.removed,
+        format_bytes(report.scratch_dirs.bytes)
+    );
+    println!("✅ 共释放 {} 喵", format_bytes(report.reclaimed_bytes()));
+    Ok(())
+}
+
+/// 轮换密钥并重新加密喵
+///
+/// 新密钥先落盘再迁移数据，中途失败时旧版本仍保留在密钥环中，可以重新执行
+fn handle_rotate_key(retire: bool, config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    let mut failures = 0;
+
+    let mut keyring = auth::credential_keyring(&profile.root)?;
+    let version = keyring.rotate()?;
+    let store = auth::CredentialStore::new(profile.credentials_dir(), keyring.crypto()?)?;
+    let (migrated, failed) = store.reencrypt()?;
+    println!("🔑 凭证密钥已轮换到 v{}：重新加密 {} 个，失败 {} 个", version, migrated, failed);
+    failures += failed;
+    let mut keyrings = vec![keyring];
+
+    let settings = config.memory.clone().unwrap_or_default();
+    if let Some(enc) = settings.encryption.as_ref().filter(|e| e.enabled) {
+        let memory_path = config.memory_db_path().to_string_lossy().into_owned();
+        let mut keyring = memory::MemoryFactory::encryption_keyring(&memory_path, enc)?;
+        let version = keyring.rotate()?;
+        let memory = memory::SqliteMemory::new(&memory_path)?.with_encryption(keyring.crypto()?);
+        let (migrated, failed) = memory.reencrypt()?;
+        println!("🔑 记忆库密钥已轮换到 v{}：重新加密 {} 条，失败 {} 条", version, migrated, failed);
+        failures += failed;
+        keyrings.push(keyring);
+    }
+
+    // 🔒 SAFETY: 配置密钥环由所有工作区共用，每个工作区的密文都要迁移喵
+    let workspaces = core::WorkspaceProfile::list(&profile.base_dir)
+        .iter()
+        .map(|name| core::WorkspaceProfile::resolve(&profile.base_dir, Some(name)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut keyring = security::crypto::secret_keyring(&profile.base_dir)?;
+    let mut has_secrets = false;
+    for workspace in &workspaces {
+        let secrets = security::SecretStore::with_crypto(&workspace.root, keyring.crypto()?);
+        let gateway_keys = gateway::ApiKeyStore::with_crypto(&workspace.root, keyring.crypto()?);
+        has_secrets |= !secrets.keys()?.is_empty() || !gateway_keys.list()?.is_empty();
+    }
+    if has_secrets {
+        let version = keyring.rotate()?;
+        let (mut migrated, mut failed) = (0, 0);
+        for workspace in &workspaces {
+            let secrets = security::SecretStore::with_crypto(&workspace.root, keyring.crypto()?);
+            let (secrets_migrated, secrets_failed) = secrets.reencrypt()?;
+            let gateway_keys = gateway::ApiKeyStore::with_crypto(&workspace.root, keyring.crypto()?);
+            let (keys_migrated, keys_failed) = gateway_keys.reencrypt()?;
+            migrated += secrets_migrated + keys_migrated;
+            failed += secrets_failed + keys_failed;
+        }
+        println!(
+            "🔑 配置密钥已轮换到 v{}（{} 个工作区）：重新加密 {} 个，失败 {} 个",
+            version,
+            workspaces.len(),
+            migrated,
+            failed
+        );
+        failures += failed;
+        keyrings.push(keyring);
+    }
+
+    // 配置文件里内联的 `enc:` 值不会自动改写，仍用旧版本密钥时不能删除旧密钥喵
+    let current = security::crypto::secret_keyring(&profile.base_dir)?.crypto()?;
+    for workspace in &workspaces {
+        let stale = workspace
+            .config_document()?
+            .map_or(0, |doc| count_stale_secrets(&doc, &current));
+        if stale > 0 {
+            println!(
+                "⚠️ 工作区 {} 的配置文件中有 {} 个旧版本密钥加密的值，用新密钥重新加密后再删除旧密钥喵",
+                workspace.name, stale
+            );
+            failures += stale;
+        }
+    }
+
+    if !retire {
+        println!("ℹ️ 旧版本密钥仍保留用于解密，确认无误后可加 --retire 删除喵");
+    } else if failures > 0 {
+        println!("⚠️ 有 {} 项未能重新加密，旧版本密钥未删除喵", failures);
+    } else {
+        let retired: usize = keyrings
+            .iter_mut()
+            .map(|k| k.retire_old())
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .sum();
+        println!("🗑️ 已删除 {} 个旧版本密钥喵", retired);
+    }
+    Ok(())
+}
+
+/// 统计配置文档中不是当前版本密钥加密的 `enc:` 值喵
+fn count_stale_secrets(value: &serde_json::Value, current: &security::CryptoService) -> usize {
+    match value {
+        serde_json::Value::String(s) => s
+            .strip_prefix(security::crypto::SECRET_PREFIX)
+            .is_some_and(|encrypted| !current.is_current(encrypted)) as usize,
+        serde_json::Value::Array(items) => items.iter().map(|v| count_stale_secrets(v, current)).sum(),
+        serde_json::Value::Object(map) => map.values().map(|v| count_stale_secrets(v, current)).sum(),
+        _ => 0,
+    }
+}
+
+/// OAuth 登录喵
+///
+/// 在 redirect_uri 指向的回环地址上临时监听，浏览器授权后用 PKCE 换取 Token 并保存
+async fn handle_auth_login(
+    name: Option<&str>,
+    no_browser: bool,
+    timeout: u64,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    let profiles = config
+        .auth
+        .as_ref()
+        .ok_or("No auth.profiles configured")?;
+    let auth_profile = profiles.find(name)?;
+
+    let server = auth::callback::CallbackServer::bind(&auth_profile.oauth.redirect_uri).await?;
+    let mut oauth = auth_profile.oauth.clone();
+    oauth.redirect_uri = server.redirect_uri().to_string();
+    let manager = auth::AuthManager::new(oauth, Some(profile.credentials_dir())).await?;
+
+    let state = uuid::Uuid::new_v4().to_string();
+    let verifier = auth::new_pkce_verifier();
+    let url = manager.create_authorization_url(&state, Some(&verifier)).await?;
+
+    println!("🔑 正在登录 {} 喵（回调地址 {}）", auth_profile.name, server.redirect_uri());
+    if no_browser || !auth::callback::open_browser(&url) {
+        println!("请在浏览器中打开以下链接完成授权喵:");
+    } else {
+        println!("已打开浏览器，没有自动打开时请手动访问喵:");
+    }
+    println!("  {}", url);
+
+    let code = server
+        .wait_for_code(&state, std::time::Duration::from_secs(timeout))
+        .await?;
+    let token = manager.exchange_code_for_token(&code, Some(&verifier)).await?;
+    manager.save_token(&auth_profile.name, &token).await?;
+    println!(
+        "✅ 登录成功，Token 已加密保存到 {} 喵（{} 过期）",
+        profile.credentials_dir().display(),
+        token.expires_at.format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+/// 处理 Gateway API Key 命令喵
+fn handle_gateway_keys(action: &GatewayKeysAction, profile: &core::WorkspaceProfile) -> Result<()> {
+    let store = gateway::ApiKeyStore::open(&profile.root, &profile.base_dir)?;
+    match action {
+        GatewayKeysAction::Create { name, rpm, burst } => {
+            let rate_limit = rpm.map(|rpm| gateway::RateLimit {
+                requests_per_minute: rpm,
+                burst: burst.unwrap_or(rpm),
+            });
+            let secret = store.create(name, rate_limit)?;
+            audit_cli(&profile.root, security::AuditKind::Admin, "gateway_keys.create", serde_json::json!({
+                "name": name,
+                "rate_limit": rpm,
+            }));
+            println!("🔑 已创建 API Key {} 喵（{}）", name, store.path().display());
+            println!("  {}", secret);
+            println!("⚠️ 明文只显示这一次，请立即保存；Gateway 重启后生效喵");
+        }
+        GatewayKeysAction::List => {
+            let keys = store.list()?;
+            if keys.is_empty() {
+                println!("（还没有 API Key 喵）");
+            }
+            for (name, key) in keys {
+                let limit = key
+                    .rate_limit
+                    .map(|l| format!("{}/min, burst {}", l.requests_per_minute, l.burst))
+                    .unwrap_or_else(|| "unlimited".to_string());
+                println!(
+                    "  🔑 {:<16} {}…  {}  ({})",
+                    name,
+                    key.hint,
+                    key.created_at.format("%Y-%m-%d %H:%M"),
+                    limit
+                );
+            }
+        }
+        GatewayKeysAction::Revoke { name } => match store.revoke(name)? {
+            true => {
+                audit_cli(&profile.root, security::AuditKind::Admin, "gateway_keys.revoke", serde_json::json!(name));
+                println!("🗑️ 已撤销 {} 喵（Gateway 重启后生效）", name);
+            }
+            false => println!("⚠️ 没有找到 {} 喵", name),
+        },
+    }
+    Ok(())
+}
+
+/// 命令行操作者喵（审批与审计记录中的执行者）
+fn cli_actor() -> String {
+    format!("cli:{}", std::env::var("USER").unwrap_or_else(|_| "local".to_string()))
+}
+
+/// 打开防篡改审计日志喵（打不开时只警告，不影响正在执行的操作）
+fn open_audit_log(config_dir: &Path) -> Option<Arc<security::AuditLog>> {
+    match security::AuditLog::open_workspace(config_dir) {
+        Ok(audit) => Some(Arc::new(audit)),
+        Err(e) => {
+            warn!("Audit log unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// 命令行的配置变更与管理操作写入审计日志喵
+fn audit_cli(config_dir: &Path, kind: security::AuditKind, action: &str, arguments: serde_json::Value) {
+    if let Some(audit) = open_audit_log(config_dir) {
+        audit.record_or_warn(&security::AuditEvent::new(kind, &cli_actor(), action).with_arguments(arguments));
+    }
+}
+
+/// 处理审计日志命令喵
+fn handle_audit(action: &AuditAction, config_dir: &Path) -> Result<()> {
+    let audit = security::AuditLog::open_workspace(config_dir)?;
+    let records = match action {
+        AuditAction::Tail { lines } => audit.tail(*lines)?,
+        AuditAction::Search {
+            text,
+            kind,
+            actor,
+            status,
+            limit,
+        } => audit.search(&security::AuditQuery {
+            text: text.clone(),
+            kind: kind.as_deref().map(str::parse).transpose()?,
+            actor: actor.clone(),
+            status: status.as_deref().map(str::parse).transpose()?,
+            limit: *limit,
+        })?,
+        AuditAction::Verify => {
+            match audit.verify() {
+                Ok(count) => println!("✅ 审计日志完整喵（{} 条记录）", count),
+                Err(security::AuditError::Tampered(id)) => {
+                    return Err(format!("审计日志在第 {} 条记录处被篡改喵", id).into());
+                }
+                Err(e) => return Err(e.into()),
+            }
+            return Ok(());
+        }
+    };
+    if records.is_empty() {
+        println!("📭 没有审计记录喵");
+    }
+    for record in records {
+        let event = &record.event;
+        let icon = match event.status {
+            security::AuditStatus::Ok => "✅",
+            security::AuditStatus::Denied => "🚫",
+            security::AuditStatus::Failed => "❌",
+        };
+        println!(
+            "{} [{}] {} {:<6} {:<20} {}",
+            icon,
+            record.id,
+            record.time.format("%Y-%m-%d %H:%M:%S"),
+            event.kind,
+            event.actor,
+            event.action
+        );
+        if !event.arguments.is_null() {
+            println!("      {}", event.arguments);
+        }
+        if let Some(detail) = &event.detail {
+            println!("      {}", detail);
+        }
+    }
+    Ok(())
+}
+
+/// 处理角色权限命令喵
+fn handle_rbac(action: &RbacAction, config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    let rbac = open_rbac(config, profile)?;
+    let granted_by = cli_actor();
+    match action {
+        RbacAction::List => {
+            let settings = rbac.config();
+            println!(
+                "默认角色: {}（对话需要 {}，工具需要 {}）",
+                settings.default_role, settings.chat_role, settings.tool_role
+            );
+            for owner in &settings.owners {
+                println!("  👑 {:<24} owner  (config)", owner);
+            }
+            for user_id in config.telegram.iter().flat_map(|t| &t.owner_user_ids) {
+                println!("  👑 {:<24} owner  (telegram.owner_user_ids)", format!("telegram:{}", user_id));
+            }
+            for assignment in rbac.list()? {
+                println!(
+                    "  🔑 {:<24} {:<6} ({}, {})",
+                    assignment.identity.to_string(),
+                    assignment.role,
+                    assignment.granted_by,
+                    assignment.granted_at.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        RbacAction::Assign { identity, role } => {
+            let identity: auth::Identity = identity.parse()?;
+            let role: auth::Role = role.parse()?;
+            rbac.assign(&identity, role, &granted_by)?;
+            audit_cli(&profile.root, security::AuditKind::Admin, "rbac.assign", serde_json::json!({
+                "identity": identity.to_string(),
+                "role": role,
+            }));
+            println!("✅ {} 的角色已设为 {} 喵", identity, role);
+        }
+        RbacAction::Revoke { identity } => match rbac.revoke(&identity.parse()?)? {
+            true => {
+                audit_cli(&profile.root, security::AuditKind::Admin, "rbac.revoke", serde_json::json!(identity));
+                println!("🗑️ 已撤销 {} 的角色分配喵（恢复为 {}）", identity, rbac.config().default_role);
+            }
+            false => println!("⚠️ {} 没有角色分配喵", identity),
+        },
+        RbacAction::Show { identity } => {
+            let identity: auth::Identity = identity.parse()?;
+            println!("{}: {}", identity, rbac.role_of(&identity));
+        }
+    }
+    Ok(())
+}
+
+/// 打开已配对设备的 Key（凭证存储）喵
+fn open_device_keys(profile: &core::WorkspaceProfile) -> Result<gateway::DeviceKeys> {
+    let store = auth::CredentialStore::new(profile.credentials_dir(), auth::master_crypto(&profile.root)?)?;
+    Ok(gateway::DeviceKeys::new(store))
+}
+
+/// 处理设备配对命令喵
+async fn handle_pair(action: &PairAction, profile: &core::WorkspaceProfile) -> Result<()> {
+    let codes = gateway::DeviceCodes::open(&profile.root);
+    let approver = cli_actor();
+    match action {
+        PairAction::List => {
+            let pending = codes.pending()?;
+            if pending.is_empty() {
+                println!("📭 没有等待审批的设备喵");
+            }
+            for request in pending {
+                println!(
+                    "  ⏳ {}  {:<16} {}  (来自 {}, {} 过期)",
+                    request.user_code,
+                    request.device_name,
+                    request.created_at.format("%H:%M:%S"),
+                    request.requested_from.as_deref().unwrap_or("unknown"),
+                    request.expires_at.format("%H:%M:%S")
+                );
+            }
+            let devices = open_device_keys(profile)?.load().await?;
+            if !devices.is_empty() {
+                println!("📱 已配对的设备:");
+            }
+            for device in devices {
+                println!(
+                    "  📱 {:<16} [{}]  有效期至 {}",
+                    device.name,
+                    device.scopes.join(","),
+                    device.expires_at.format("%Y-%m-%d")
+                );
+            }
+        }
+        PairAction::Approve { code } => {
+            let request = codes.approve(code, &approver)?;
+            audit_cli(&profile.root, security::AuditKind::Admin, "pair.approve", serde_json::json!({
+                "device": request.device_name,
+                "user_code": request.user_code,
+            }));
+            println!("✅ 已批准设备 {}，设备下次轮询时领取 Key 喵", request.device_name);
+        }
+        PairAction::Deny { code } => {
+            let request = codes.deny(code, &approver)?;
+            audit_cli(&profile.root, security::AuditKind::Admin, "pair.deny", serde_json::json!({
+                "device": request.device_name,
+                "user_code": request.user_code,
+            }));
+            println!("🚫 已拒绝设备 {} 喵", request.device_name);
+        }
+        PairAction::Revoke { device } => match open_device_keys(profile)?.revoke(device).await? {
+            true => {
+                audit_cli(&profile.root, security::AuditKind::Admin, "pair.revoke", serde_json::json!(device));
+                println!("🗑️ 已撤销设备 {} 喵（运行中的 Gateway 立即拒绝该 Key）", device);
+            }
+            false => println!("⚠️ 没有找到设备 {} 喵", device),
+        },
+    }
+    Ok(())
+}
+
+fn handle_secret(action: &SecretAction, profile: &core::WorkspaceProfile) -> Result<()> {
+    let store = security::SecretStore::open(&profile.root, &profile.base_dir)?;
+    match action {
+        SecretAction::Set { key, value } => {
+            let value = match value {
+                Some(value) => value.clone(),
+                None => {
+                    if std::io::stdin().is_terminal() {
+                        print!("🔐 {} = ", key);
+                        std::io::stdout().flush()?;
+                    }
+                    let mut line = String::new();
+                    std::io::stdin().read_line(&mut line)?;
+                    line.trim_end_matches(['\r', '\n']).to_string()
+                }
+            };
+            if value.is_empty() {
+                return Err("Secret value is empty".into());
+            }
+            let key = store.set(key, &value)?;
+            println!("🔐 已加密保存 {} 喵（{}）", key, store.path().display());
+
+            // 配置文件里残留的明文一并删除喵
+            let plaintext = profile
+                .config_value(&key)?
+                .as_ref()
+                .and_then(serde_json::Value::as_str)
+                .is_some_and(|value| !value.is_empty() && !security::crypto::is_encrypted_secret(value));
+            if plaintext {
+                let path = profile.set_config_path(&key, serde_json::Value::Null)?;
+                println!("🧹 已从 {} 删除明文 {} 喵", path.display(), key);
+            }
+        }
+        SecretAction::List => {
+            let keys = store.keys()?;
+            if keys.is_empty() {
+                println!("（还没有加密保存的密钥喵）");
+            }
+            for key in keys {
+                println!("  🔐 {}", key);
+            }
+        }
+        SecretAction::Remove { key } => match store.remove(key)? {
+            true => println!("🗑️ 已删除 {} 喵", key),
+            false => println!("⚠️ 没有找到 {} 喵", key),
+        },
+    }
+    Ok(())
+}
+
+/// 处理状态检查喵
+async fn handle_status(verbose: bool, config: &Config, config_path: &Path) -> Result<()> {
+    println!("📊 系统状态:");
+    println!("  版本: {}", env!("CARGO_PKG_VERSION"));
+    println!("  运行时: tokio");
+    let pid_path = config_path.join(service::daemon::DEFAULT_PID_FILE);
+    match service::daemon::running_pid(&pid_path) {
+        Some(pid) => println!("  守护进程: 🟢 运行中 (pid {})", pid),
+        None => println!("  守护进程: ⚪ 未运行"),
+    }
+
+    if verbose {
+        match service::read_health_snapshot(&config_path.join(TASK_HEALTH_FILE)) {
+            Some(tasks) if !tasks.is_empty() => {
+                println!("  后台任务:");
+                for (name, health) in tasks {
+                    println!(
+                        "    {:<20} {:?} (重启 {} 次，自 {}){}",
+                        name,
+                        health.state,
+                        health.restarts,
+                        health.started_at.format("%Y-%m-%d %H:%M:%S"),
+                        health
+                            .last_error
+                            .map(|e| format!(" ❌ {}", e))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+            _ => println!("  后台任务: 无（守护进程未运行）"),
+        }
+        print_cost_status(&config.costs, &config_path.join(COST_DB))?;
+    }
+    print_limit_status(config, &config_path.join(COST_DB))?;
+
+    Ok(())
+}
+
+/// 当天花费与预算、最近会话花费（`status --verbose`）喵
+fn print_cost_status(costs: &providers::CostConfig, ledger_path: &Path) -> Result<()> {
+    if !ledger_path.exists() {
+        println!("  费用: 暂无记录");
+        return Ok(());
+    }
+    let ledger = providers::CostLedger::open(ledger_path)?;
+    let now = chrono::Utc::now();
+    let today = providers::cost::day_start(now);
+    let budget = |limit: Option<f64>| limit.map(|l| format!(" / ${:.2}", l)).unwrap_or_default();
+
+    println!(
+        "  今日费用: ${:.4}{}",
+        ledger.spent(providers::cost::CostScope::Day, now)?,
+        budget(costs.daily_budget)
+    );
+    for agent in ledger.breakdown("agent", today, 10)? {
+        println!(
+            "    Agent {:<14} ${:.4}{} ({} 次请求，{} + {} tokens)",
+            agent.key,
+            agent.cost,
+            budget(costs.agent_budgets.get(&agent.key).copied()),
+            agent.requests,
+            agent.prompt_tokens,
+            agent.completion_tokens
+        );
+    }
+    for model in ledger.breakdown("model", today, 10)? {
+        println!("    模型 {:<15} ${:.4} ({} 次请求)", model.key, model.cost, model.requests);
+    }
+
+    let sessions = ledger.breakdown("session", now - chrono::Duration::days(7), 5)?;
+    if !sessions.is_empty() {
+        println!("  近 7 天会话费用{}:", budget(costs.session_budget));
+        for session in sessions {
+            println!("    {:<36} ${:.4} ({} 次请求)", session.key, session.cost, session.requests);
+        }
+    }
+    Ok(())
+}
+
+/// 配置了限额的 Agent 的当前用量喵
+fn print_limit_status(config: &Config, ledger_path: &Path) -> Result<()> {
+    let limited: Vec<_> = config.agents.agent.iter().filter(|(_, agent)| agent.has_quota()).collect();
+    if limited.is_empty() {
+        return Ok(());
+    }
+    // 还没有账本时用量都是 0 喵
+    let ledger = Arc::new(match ledger_path.exists() {
+        true => providers::CostLedger::open(ledger_path)?,
+        false => providers::CostLedger::in_memory()?,
+    });
+    let now = chrono::Utc::now();
+    let of = |used: u64, limit: Option<u64>| match limit {
+        Some(limit) => format!("{}/{}", used, limit),
+        None => used.to_string(),
+    };
+
+    println!("  Agent 限额:");
+    for (name, agent) in limited {
+        let enforcer = agent::limits::LimitsEnforcer::new(ledger.clone(), name, agent.limits.clone());
+        let usage = enforcer.usage(now)?;
+        let state = match enforcer.check(now) {
+            Ok(_) => "🟢",
+            Err(_) => "🔴",
+        };
+        println!(
+            "    {} {:<14} 近 1 小时 {} 次请求，今日 {} tokens{}",
+            state,
+            name,
+            of(usage.requests_last_hour, agent.limits.max_requests_per_hour.map(u64::from)),
+            of(usage.tokens_today, agent.limits.max_token_limit),
+            agent
+                .limits
+                .max_session_hours
+                .map(|h| format!("，会话上限 {}h", h))
+                .unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// 处理记忆管理喵
+/// 记忆检索参数喵
+struct MemorySearchArgs<'a> {
+    filters: &'a [String],
+    since: Option<&'a str>,
+    agent: Option<&'a str>,
+    importance: Option<f32>,
+    format: OutputFormat,
+}
+
+/// 记忆维护操作喵
+#[derive(Debug, Clone, Copy)]
+struct MemoryMaintenance {
+    reindex: bool,
+    prune: bool,
+    dry_run: bool,
+}
+
+impl MemorySearchArgs<'_> {
+    /// 组装查询条件喵
+    fn build(&self, text: Option<&str>, limit: usize) -> Result<memory::MemoryQuery> {
+        let mut query = memory::MemoryQuery::new(limit);
+        if let Some(text) = text {
+            query = query.with_text(text);
+        }
+        for raw in self.filters {
+            let (key, value) = memory::parse_filter(raw)?;
+            query = query.with_filter(&key, &value);
+        }
+        if let Some(agent) = self.agent {
+            query = query.with_agent(agent);
+        }
+        if let Some(since) = self.since {
+            query = query.with_since(memory::parse_since(since, chrono::Utc::now())?);
+        }
+        Ok(query)
+    }
+
+    /// 组装要存储的记忆喵（`--agent` / `--filter` 写入 metadata）
+    fn new_item(&self, content: &str) -> Result<MemoryItem> {
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("source".to_string(), "cli".into());
+        for raw in self.filters {
+            let (key, value) = memory::parse_filter(raw)?;
+            metadata.insert(key, value.into());
+        }
+        if let Some(agent) = self.agent {
+            metadata.insert("agent".to_string(), agent.into());
+        }
+        if let Some(importance) = self.importance {
+            if !(0.0..=1.0).contains(&importance) {
+                return Err(format!("重要度 {} 应在 0~1 之间喵", importance).into());
+            }
+            metadata.insert("importance".to_string(), importance.into());
+        }
+        Ok(MemoryItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            embedding: None,
+            metadata: Some(metadata.into()),
+            created_at: chrono::Utc::now(),
+        })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_memory(
+    query: &Option<String>,
+    top_k: usize,
+    store: &Option<String>,
+    delete: &Option<String>,
+    list: bool,
+    maintenance: MemoryMaintenance,
+    search: &MemorySearchArgs<'_>,
+    config: &Config,
+) -> Result<()> {
+    if query.is_none() && store.is_none() && delete.is_none() && !list && !maintenance.reindex && !maintenance.prune {
+        return Ok(());
+    }
+
+    let memory_path = config.memory_db_path();
+    if let Some(parent) = memory_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let settings = config.memory.clone().unwrap_or_default();
+    let memory = memory::MemoryFactory::open_sqlite(&memory_path.to_string_lossy(), &settings)?
+        .with_embedder(memory_embedder(config, &settings.embedding), settings.embedding.min_similarity);
+    if memory.is_encrypted() && (maintenance.reindex || query.is_some()) {
+        info!("记忆库已加密，不生成 embedding，只使用关键词检索喵");
+    }
+
+    if maintenance.reindex {
+        let count = memory.reindex_embeddings().await?;
+        println!("🧭 已重新向量化 {} 条记忆", count);
+    }
 
-            // 循环处理工具调用喵
-            let mut loop_count = 0;
-            while loop_count < 5 {
-                let request = ChatRequest {
-                    model: Some(model_name.clone()),
-                    messages: history.clone(),
-                    temperature: Some(temperature),
-                    max_tokens: Some(max_tokens as u32),
-                    stream: Some(false),
-                };
+    if maintenance.prune {
+        let pruned = memory.prune(chrono::Utc::now(), maintenance.dry_run)?;
+        for (id, score) in &pruned {
+            println!("  {} (得分 {:.2})", id.chars().take(8).collect::<String>(), score);
+        }
+        match maintenance.dry_run {
+            true => println!("🧹 将清理 {} 条低分记忆（--dry-run，未删除）", pruned.len()),
+            false => println!("🧹 已清理 {} 条低分记忆", pruned.len()),
+        }
+    }
 
-                // 发送请求喵
-                match client.chat_api(&request).await {
-                    Ok(response) => {
-                        if let Some(choice) = response.choices.first() {
-                            let reply = &choice.message.content;
-                            println!("🤖 {}", reply);
-                            history.push(OpenAIMessage::assistant(reply.clone()));
-
-                            let tool_calls = parse_tool_calls(reply);
-                            if tool_calls.is_empty() {
-                                break;
-                            }
-
-                            for call in tool_calls {
-                                println!("🔧 执行工具: {}...", call.tool_name);
-                                let result = registry.execute(&call.tool_name, call.arguments).await;
-                                let result_text = match result {
-                                    Ok(res) => format_tool_result_for_llm(&res),
-                                    Err(e) => format!("❌ 工具执行失败: {}", e),
-                                };
-                                history.push(OpenAIMessage::user(format!("Tool result for {}: {}", call.tool_name, result_text)));
-                            }
-                        } else {
-                            println!("❌ 没有收到回应喵");
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        error!("Agent error: {}", e);
-                        println!("❌ 对话失败: {}", e);
-                        break;
-                    }
-                }
-                loop_count += 1;
+    if let Some(content) = store {
+        let id = memory.save(search.new_item(content)?).await?;
+        println!("💾 已存储记忆: {}", id);
+    }
+
+    if let Some(prefix) = delete {
+        let matches: Vec<MemoryItem> = memory
+            .list(None)?
+            .into_iter()
+            .filter(|item| item.id.starts_with(prefix.as_str()))
+            .collect();
+        match matches.as_slice() {
+            [] => return Err(format!("找不到记忆: {}", prefix).into()),
+            [item] => {
+                memory.forget(&item.id).await?;
+                println!("🗑️ 已删除记忆: {}", item.id);
             }
+            _ => return Err(format!("ID 前缀 '{}' 匹配到 {} 条记忆，请写得更完整一些喵", prefix, matches.len()).into()),
+        }
+    }
+
+    if query.is_some() || list {
+        let text = query.as_deref().filter(|_| !list);
+        let limit = if list && query.is_none() { usize::MAX } else { top_k };
+        let items = memory.hybrid_query(&search.build(text, limit)?).await?;
+
+        match search.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&items)?),
+            OutputFormat::Table => print_memory_table(text, &items),
         }
     }
 
     Ok(())
 }
 
-/// 处理 Gateway 模式喵
-async fn handle_gateway(
-    host: &str,
-    port: u16,
-    port_random: bool,
-    _webhook_path: &str,
-    config: &Config,
-) -> Result<()> {
-    let actual_port = if port_random {
-        port + rand::random::<u16>() % 1000
-    } else {
-        port
-    };
+/// 记忆向量化所用的 embedding provider 喵
+///
+/// 非 hash 的 provider 按 OpenAI 兼容端点调用；未配置 API Key 时回退到本地 hash 向量
+fn memory_embedder(config: &Config, settings: &core::traits::EmbeddingConfig) -> Arc<dyn memory::EmbeddingProvider> {
+    if settings.provider != "hash" {
+        match config.provider(&settings.provider) {
+            Some(provider) if !provider.api_key.is_empty() || settings.provider == "ollama" => {
+                // Ollama 原生地址的 OpenAI 兼容端点在 /v1 下喵
+                let base_url = match settings.provider == "ollama" && !providers::ollama::is_openai_compatible(&provider.base_url) {
+                    true => format!("{}/v1", provider.base_url.trim_end_matches('/')),
+                    false => provider.base_url,
+                };
+                let client = OpenAIClient::new(OpenAIConfig {
+                    api_key: provider.api_key,
+                    base_url,
+                    timeout: provider.timeout,
+                    max_retries: provider.max_retries,
+                });
+                return Arc::new(memory::OpenAIEmbedding::new(client, &settings.model));
+            }
+            Some(_) => warn!("embedding provider '{}' 未配置 API Key，改用本地 hash 向量喵", settings.provider),
+            None => warn!("未知的 embedding provider '{}'，改用本地 hash 向量喵", settings.provider),
+        }
+    }
+    Arc::new(memory::HashEmbedding::new(settings.dimensions))
+}
 
-    let gateway_config = gateway::GatewayConfig {
-        bind_addr: host.to_string(),
-        port: actual_port,
-        bearer_token: config.api_key.clone().unwrap_or_default(),
-        pairing_enabled: true,
+/// Gateway `/v1/embeddings` 的模型：`[memory.embedding]` 为默认模型，另加 `[embeddings.<name>]` 喵
+fn build_embedding_models(config: &Config) -> gateway::EmbeddingModels {
+    // 响应中报告实际使用的模型（未配置 API Key 回退到 hash 时如实报告）喵
+    let named = |settings: &core::traits::EmbeddingConfig| {
+        let embedder = memory_embedder(config, settings);
+        let model_id = embedder.model_id();
+        let model = model_id.strip_prefix("openai:").unwrap_or(&model_id).to_string();
+        (model, embedder)
     };
+    let (model, embedder) = named(&config.memory.clone().unwrap_or_default().embedding);
+    let mut models = gateway::EmbeddingModels::new(&model, embedder);
+    for (name, settings) in &config.embeddings {
+        let (model, embedder) = named(settings);
+        models = models.with_model(name, &model, embedder);
+    }
+    models
+}
 
-    println!("🚀 Gateway 服务器启动喵: http://{}:{}", host, actual_port);
-    println!("📖 API 端点:");
-    println!("   GET  /health          - 健康检查");
-    println!("   GET  /metrics         - Prometheus 指标");
-    println!("   POST /v1/chat/completions - OpenAI 兼容聊天");
-    println!("   GET  /v1/models       - 模型列表");
-    println!("   GET  /v1/tools        - 工具列表");
-    println!("（按 Ctrl+C 停止喵）");
+/// 以表格形式打印记忆喵
+fn print_memory_table(query: Option<&str>, items: &[MemoryItem]) {
+    match query {
+        Some(q) => println!("🔍 查询记忆: {} ({} 条)", q, items.len()),
+        None => println!("📋 记忆列表 ({} 条)", items.len()),
+    }
+    if items.is_empty() {
+        return;
+    }
 
-    let server = gateway::GatewayServer::new(gateway_config);
-    server.run().await?;
-    
-    println!("\n🛑 Gateway 已停止喵");
-    Ok(())
+    println!("{:<10} {:<17} {:<10} {:<10} CONTENT", "ID", "CREATED", "AGENT", "SOURCE");
+    for item in items {
+        let field = |key: &str| {
+            item.metadata
+                .as_ref()
+                .and_then(|m| m.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or("-")
+                .to_string()
+        };
+        let id: String = item.id.chars().take(8).collect();
+        let preview: String = item
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(60)
+            .collect();
+        println!(
+            "{:<10} {:<17} {:<10} {:<10} {}",
+            id,
+            item.created_at.format("%Y-%m-%d %H:%M"),
+            field("agent"),
+            field("source"),
+            preview
+        );
+    }
 }
-/// 处理 Daemon 模式喵
-async fn handle_daemon(
-    background: bool,
-    daemon: bool,
-    _pid_file: &Option<PathBuf>,
-    _config: &Config,
+
+/// 列出会话喵
+fn handle_sessions_list(
+    tag: Option<&str>,
+    limit: usize,
+    format: OutputFormat,
+    profile: &core::WorkspaceProfile,
 ) -> Result<()> {
-    info!("Daemon mode: background={}, daemon={}", background, daemon);
+    let sessions: Vec<core::SessionInfo> = core::SessionStore::new(profile.sessions_dir())
+        .list()?
+        .into_iter()
+        .filter(|s| tag.is_none_or(|t| s.has_tag(t)))
+        .take(limit)
+        .collect();
 
-    if daemon {
-        println!("🔄 启动守护进程模式喵...");
-    } else if background {
-        println!("⚡ 启动后台运行模式喵...");
-    } else {
-        println!("🎯 前台运行模式喵（按 Ctrl+C 停止）");
-        tokio::signal::ctrl_c().await?;
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
     }
 
+    if sessions.is_empty() {
+        println!("📭 没有找到会话喵");
+        return Ok(());
+    }
+    println!("{:<10} {:<17} {:>5} {:<24} TITLE", "ID", "LAST ACTIVE", "MSGS", "TAGS");
+    for session in &sessions {
+        let id: String = session.session_id.chars().take(8).collect();
+        let tags = if session.tags.is_empty() { "-".to_string() } else { session.tags.join(",") };
+        println!(
+            "{:<10} {:<17} {:>5} {:<24} {}",
+            id,
+            session.last_activity.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            session.message_count,
+            tags,
+            session.display_name()
+        );
+    }
     Ok(())
 }
 
-/// 处理状态检查喵
-async fn handle_status(_verbose: bool) -> Result<()> {
-    println!("📊 系统状态:");
-    println!("  版本: {}", env!("CARGO_PKG_VERSION"));
-    println!("  运行时: tokio");
+/// 显示会话内容喵
+fn handle_sessions_show(
+    id: &str,
+    format: OutputFormat,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    let store = open_session_store(config, &profile.root, &profile.sessions_dir())?;
+    let info = store.find(id)?.ok_or_else(|| format!("找不到会话 {} 喵", id))?;
+    let messages = store.load_messages(&info.session_id)?;
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({ "session": info, "messages": messages });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
+    let lock = if info.encrypted { " 🔒" } else { "" };
+    println!("💬 {}{} ({})", info.display_name(), lock, info.session_id);
+    if !info.tags.is_empty() {
+        println!("🏷️  {}", info.tags.join(", "));
+    }
+    for message in &messages {
+        let time = message.at.with_timezone(&chrono::Local).format("%H:%M");
+        let content = if message.encrypted { "🔒 [无法解密]" } else { message.content.as_str() };
+        println!("[{}] {}: {}", time, message.role, content);
+    }
     Ok(())
 }
 
-/// 处理记忆管理喵
-async fn handle_memory(
-    query: &Option<String>,
-    top_k: usize,
-    store: &Option<String>,
-    delete: &Option<String>,
-    list: bool,
+/// `sessions search` 的参数喵
+struct SessionSearch<'a> {
+    query: &'a str,
+    session: Option<&'a str>,
+    since: Option<&'a str>,
+    until: Option<&'a str>,
+    limit: usize,
+}
+
+/// 全文检索会话喵（终端中用反色高亮关键词）
+fn handle_sessions_search(
+    search: &SessionSearch,
+    format: OutputFormat,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
 ) -> Result<()> {
-    if let Some(q) = query {
-        println!("🔍 查询记忆: {}", q);
-        println!("   Top-{} 结果: [TODO]", top_k);
+    let store = open_session_store(config, &profile.root, &profile.sessions_dir())?;
+    let now = chrono::Utc::now();
+    let mut query = core::HistoryQuery::new(search.query, search.limit);
+    if let Some(session) = search.session {
+        query = query.with_session(session);
     }
-
-    if let Some(s) = store {
-        println!("💾 存储记忆: {}", s);
+    if let Some(since) = search.since {
+        query = query.with_since(memory::parse_since(since, now)?);
     }
-
-    if let Some(d) = delete {
-        println!("🗑️ 删除记忆: {}", d);
+    if let Some(until) = search.until {
+        query = query.with_until(memory::parse_since(until, now)?);
     }
 
-    if list {
-        println!("📋 记忆列表: [TODO]");
-    }
+    let (open, close) = match (format, std::io::stdout().is_terminal()) {
+        (OutputFormat::Table, true) => ("\x1b[7m", "\x1b[0m"),
+        _ => ("**", "**"),
+    };
+    let hits = core::HistoryIndex::open(store)?.search(&query, open, close)?;
 
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        println!("📭 没有找到匹配的对话喵");
+        return Ok(());
+    }
+    for hit in &hits {
+        let id: String = hit.session_id.chars().take(8).collect();
+        println!(
+            "💬 {} ({}) [{}] {}:",
+            hit.title,
+            id,
+            hit.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            hit.role
+        );
+        println!("   {}", hit.excerpt);
+    }
     Ok(())
 }
 
 /// 处理系统诊断喵
-async fn handle_doctor(fix: bool, verbose: bool) -> Result<()> {
-    println!("🩺 系统诊断中...");
+async fn handle_doctor(
+    fix: bool,
+    verbose: bool,
+    format: OutputFormat,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    let doctor = service::Doctor::new(profile, config)
+        .with_timeout(std::time::Duration::from_secs(config.provider_health.timeout_secs.max(1)));
+    let mut checks = doctor.run().await;
+
+    let mut repaired = Vec::new();
+    if fix && checks.iter().any(|c| c.fixable()) {
+        for repair in checks.iter().filter_map(|c| c.repair.as_ref().filter(|_| c.fixable())) {
+            repaired.push(doctor.repair(repair));
+        }
+        checks = doctor.run().await;
+    }
 
-    let checks = vec![
-        ("Rust toolchain", true),
-        ("Config directory", true),
-        ("Module loading", true),
-        ("Dependencies", true),
-    ];
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "checks": checks,
+            "repaired": repaired.iter().map(|r| r.clone().unwrap_or_else(|e| format!("failed: {}", e))).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-    let mut all_ok = true;
-    for (name, ok) in &checks {
-        let status = if *ok { "✅ OK" } else { "❌ FAILED" };
-        println!("  {}: {}", name, status);
-        if !*ok {
-            all_ok = false;
+    println!("🩺 系统诊断 (工作区 {})", profile.name);
+    for result in &repaired {
+        match result {
+            Ok(done) => println!("  🔧 {}", done),
+            Err(e) => println!("  ❌ 修复失败: {}", e),
+        }
+    }
+    for check in &checks {
+        match check.status == service::CheckStatus::Ok && !verbose {
+            true => println!("  {} {}", check.status.icon(), check.name),
+            false => println!("  {} {:<20} {}", check.status.icon(), check.name, check.detail),
         }
     }
 
-    if all_ok {
-        println!("✅ 所有检查通过喵！");
-    } else {
-        println!("⚠️ 存在一些问题喵");
-        if fix {
-            println!("🔧 自动修复功能即将实现喵...");
+    let problems = checks.iter().filter(|c| c.status != service::CheckStatus::Ok).count();
+    let fixable = checks.iter().filter(|c| c.fixable()).count();
+    match problems {
+        0 => println!("✅ 所有检查通过喵！"),
+        _ if fixable > 0 => println!("⚠️ 存在 {} 个问题喵（其中 {} 个可用 --fix 自动修复）", problems, fixable),
+        _ => println!("⚠️ 存在 {} 个问题喵", problems),
+    }
+    Ok(())
+}
+
+/// 安全自检喵
+fn handle_security_doctor(
+    fix: bool,
+    format: OutputFormat,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    let audit = security::SecurityAudit::new(config, &[profile.base_dir.as_path(), profile.root.as_path()])
+        .with_agents(&config.agent_names());
+    if fix {
+        let fixed = audit.fix_permissions()?;
+        if fixed > 0 && format == OutputFormat::Table {
+            println!("🔧 已收紧 {} 个文件 / 目录的权限喵", fixed);
         }
     }
+    let report = audit.run();
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({ "score": report.score(), "findings": report.findings });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
+    println!("🛡️ 安全自检 (工作区 {})", profile.name);
+    for finding in &report.findings {
+        let status = if finding.passed { "✅" } else { "❌" };
+        println!("  {} [{:<6}] {:<40} {}", status, finding.severity.as_str(), finding.check, finding.detail);
+    }
+    let failures = report.failures();
+    println!("📊 得分: {}/100（{} 项未通过）", report.score(), failures.len());
+    if failures.iter().any(|f| f.check.starts_with("permissions:")) && !fix {
+        println!("💡 使用 --fix 可自动收紧文件权限喵");
+    }
     Ok(())
 }
 
@@ -756,41 +3791,580 @@ async fn handle_service(
     stop: bool,
     restart: bool,
     status: bool,
-    _health: bool,
+    profile: &core::WorkspaceProfile,
 ) -> Result<()> {
-    if status {
-        println!("📋 服务状态: [TODO]");
-    }
+    use service::{ServiceAction, ServiceInstaller, ServicePlatform};
+
+    let installer = ServiceInstaller::new(ServicePlatform::current()?, profile)?;
     if install {
-        println!("📦 安装服务... [TODO]");
-    }
-    if uninstall {
-        println!("🗑️ 卸载服务... [TODO]");
+        println!("📦 安装服务 {}...", installer.name());
+        let path = installer.install()?;
+        println!("✅ 单元文件已写入: {}", path.display());
     }
     if start {
-        println!("▶️ 启动服务... [TODO]");
+        println!("▶️ 启动服务 {}...", installer.name());
+        installer.control(ServiceAction::Start)?;
     }
     if stop {
-        println!("⏹️ 停止服务... [TODO]");
+        println!("⏹️ 停止服务 {}...", installer.name());
+        installer.control(ServiceAction::Stop)?;
     }
     if restart {
-        println!("🔄 重启服务... [TODO]");
+        println!("🔄 重启服务 {}...", installer.name());
+        installer.control(ServiceAction::Restart)?;
+    }
+    if uninstall {
+        match installer.uninstall()? {
+            true => println!("🗑️ 已卸载服务 {}", installer.name()),
+            false => println!("⚠️ 服务 {} 未安装喵", installer.name()),
+        }
+    }
+    if status {
+        match installer.is_installed() {
+            true => println!("📋 服务状态 ({}):\n{}", installer.unit_path().display(), installer.control(ServiceAction::Status)?),
+            false => println!("📋 服务 {} 未安装（nekoclaw service --install）", installer.name()),
+        }
     }
 
     Ok(())
 }
 
 /// 处理配置管理喵
-async fn handle_config(
+fn handle_config(
     show: bool,
-    _edit: bool,
-    _reset: bool,
-    _file: Option<PathBuf>,
-    config_path: &PathBuf,
+    edit: bool,
+    reset: bool,
+    yes: bool,
+    action: Option<&ConfigAction>,
+    profile: &core::WorkspaceProfile,
 ) -> Result<()> {
+    if let Some(ConfigAction::Set { key, value }) = action {
+        let value = serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.clone()));
+        let path = profile.set_config_path(key, value.clone())?;
+        audit_cli(&profile.root, security::AuditKind::Config, "config.set", serde_json::json!({ key.as_str(): value }));
+        println!("✅ {} = {} ({})", key, value, path.display());
+        return Ok(());
+    }
+
+    if reset {
+        let path = profile.config_file();
+        if !yes && !confirm(&format!("⚠️ 将把 {} 重置为默认配置，确定吗？[y/N] ", path.display())) {
+            println!("已取消喵（非交互环境请加 --yes）");
+            return Ok(());
+        }
+        let (path, backup) = profile.reset_config()?;
+        audit_cli(&profile.root, security::AuditKind::Config, "config.reset", serde_json::Value::Null);
+        println!("♻️ 已重置配置喵: {}", path.display());
+        if let Some(backup) = backup {
+            println!("   旧配置备份在: {}", backup.display());
+        }
+    }
+
+    if edit {
+        edit_config(profile)?;
+    }
+
     if show {
-        println!("📋 当前配置路径: {}", config_path.display());
+        println!("📋 当前配置路径: {}", profile.config_file().display());
+    }
+    Ok(())
+}
+
+/// 在终端询问是/否喵（标准输入不是终端时视为否）
+fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{}", question);
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// 用 $VISUAL / $EDITOR 编辑配置文件副本，校验通过才覆盖原文件喵
+fn edit_config(profile: &core::WorkspaceProfile) -> Result<()> {
+    let path = profile.config_file();
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut editor_args = editor.split_whitespace();
+    let program = editor_args.next().ok_or("EDITOR is empty")?.to_string();
+    let editor_args: Vec<&str> = editor_args.collect();
+
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("json");
+    let draft = path.with_file_name(format!("config.editing.{}", extension));
+    std::fs::create_dir_all(&profile.root)?;
+    match path.exists() {
+        true => std::fs::copy(&path, &draft).map(|_| ())?,
+        false => std::fs::write(&draft, serde_json::to_string_pretty(&Config::default())?)?,
+    }
+
+    let result = loop {
+        let status = std::process::Command::new(&program).args(&editor_args).arg(&draft).status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => break Err(format!("Editor exited with {}", status).into()),
+            Err(e) => break Err(format!("Failed to launch editor '{}': {}", program, e).into()),
+        }
+        let content = std::fs::read_to_string(&draft)?;
+        match profile.check_config_content(&path, &content) {
+            Ok(_) => {
+                std::fs::write(&path, content)?;
+                audit_cli(&profile.root, security::AuditKind::Config, "config.edit", serde_json::Value::Null);
+                println!("✅ 配置已保存喵: {}", path.display());
+                break Ok(());
+            }
+            Err(e) => {
+                println!("❌ 配置无效喵: {}", e);
+                if !confirm("   重新编辑吗？[y/N]（否则放弃修改）") {
+                    break Err("Configuration not saved".into());
+                }
+            }
+        }
+    };
+    let _ = std::fs::remove_file(&draft);
+    result
+}
+
+/// 从 OpenClaw 迁移喵
+fn handle_migrate(from: &str, dry_run: bool, force: bool, profile: &core::WorkspaceProfile) -> Result<()> {
+    let source = match from.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().ok_or("Cannot resolve home directory")?.join(rest),
+        None => PathBuf::from(from),
+    };
+    let report = config::migrate::MigrationReport::plan(&source)?;
+    println!("{}", report.render());
+
+    if dry_run {
+        println!("🔍 仅预览，未写入任何文件喵（去掉 --dry-run 执行迁移）");
+        return Ok(());
+    }
+    let (config_path, copied) = report.apply(profile, force)?;
+    println!("✅ 已迁移 {} 项配置到 {} 喵", report.mapped.len(), config_path.display());
+    for file in &copied {
+        println!("   📄 {}", file.display());
+    }
+    let skipped = report.files.len() - copied.len();
+    if skipped > 0 {
+        println!("   ⚠️ {} 个人设文件已存在未覆盖（--force 覆盖）", skipped);
+    }
+    Ok(())
+}
+
+/// 处理定时任务命令喵
+async fn handle_schedule(action: &ScheduleAction, config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    let state_path = profile.root.join(service::scheduler::SCHEDULE_STATE_FILE);
+    match action {
+        ScheduleAction::List => {
+            if config.schedule.is_empty() {
+                println!("⏰ 没有配置定时任务喵（在配置中添加 [schedule.<名称>]）");
+                return Ok(());
+            }
+            let state = service::ScheduleState::load(&state_path).unwrap_or_else(|e| {
+                warn!("{}", e);
+                Default::default()
+            });
+            println!("⏰ 定时任务:");
+            for (name, task) in &config.schedule {
+                let next = match service::CronSchedule::parse(&task.cron) {
+                    Ok(_) if !task.enabled => "已停用".to_string(),
+                    Ok(cron) => cron
+                        .next_after(&chrono::Local::now())
+                        .map(|next| next.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    Err(e) => format!("❌ {}", e),
+                };
+                println!("  {} [{}] {}", name, task.cron, task.action.describe());
+                println!("    下次运行: {}", next);
+                if let Some(run) = state.last_runs.get(name) {
+                    println!(
+                        "    上次运行: {} {} ({} ms) {}",
+                        run.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                        if run.ok { "✅" } else { "❌" },
+                        run.duration_ms,
+                        run.output.lines().next().unwrap_or_default()
+                    );
+                }
+            }
+            println!("  （按时运行需要守护进程: nekoclaw daemon）");
+        }
+        ScheduleAction::RunNow { name } => {
+            let Some(task) = config.schedule.get(name) else {
+                let hint = crate::core::closest_match(name, config.schedule.keys().map(String::as_str))
+                    .map(|s| format!("，你是不是想运行 {}", s))
+                    .unwrap_or_default();
+                return Err(format!("{}{}喵", service::scheduler::ScheduleError::UnknownTask(name.clone()), hint).into());
+            };
+            println!("⏰ 运行定时任务 {} ({})", name, task.action.describe());
+            let recorder = open_metrics_recorder(&profile.root).await?.scoped("scheduler");
+            let run = run_scheduled_task(config, profile, name, task, &recorder).await;
+            println!("{}", run.output);
+            if !run.ok {
+                return Err(format!("定时任务 {} 失败喵", name).into());
+            }
+            println!("✅ 完成喵 ({} ms)", run.duration_ms);
+        }
+    }
+    Ok(())
+}
+
+/// 运行一个定时任务并记录结果喵（`schedule.json`、指标与记忆库）
+async fn run_scheduled_task(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    name: &str,
+    task: &service::ScheduledTask,
+    recorder: &telemetry::MetricsRecorder,
+) -> service::TaskRun {
+    let started_at = chrono::Utc::now();
+    let start = std::time::Instant::now();
+    let result = match &task.action {
+        service::TaskAction::Prompt { prompt, agent } => {
+            run_scheduled_prompt(config, profile, prompt, agent.as_deref()).await
+        }
+        action => run_scheduled_tool(config, &profile.root, action).await,
+    };
+    let run = service::TaskRun::new(started_at, start.elapsed().as_millis() as u64, result);
+
+    let state_path = profile.root.join(service::scheduler::SCHEDULE_STATE_FILE);
+    if let Err(e) = service::ScheduleState::record(&state_path, name, run.clone()) {
+        warn!("定时任务结果写入失败喵: {}", e);
+    }
+    let status = if run.ok { "ok" } else { "failed" };
+    let labels = [("task", name), ("action", task.action.kind()), ("status", status)];
+    recorder.counter("scheduled_task_runs", 1.0, &labels);
+    recorder.gauge("scheduled_task_duration_ms", run.duration_ms as f64, &labels[..2]);
+
+    let settings = config.memory.clone().unwrap_or_default();
+    let saved = match memory::MemoryFactory::open_sqlite(&config.memory_db_path().to_string_lossy(), &settings) {
+        Ok(memory) => memory
+            .save(MemoryItem {
+                id: uuid::Uuid::new_v4().to_string(),
+                content: format!("定时任务 {} ({}) {}:\n{}", name, task.action.describe(), status, run.output),
+                embedding: None,
+                metadata: Some(serde_json::json!({ "source": "schedule", "task": name, "ok": run.ok })),
+                created_at: started_at,
+            })
+            .await
+            .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = saved {
+        warn!("定时任务结果写入记忆库失败喵: {}", e);
+    }
+    run
+}
+
+/// 🔒 SAFETY: 定时任务中的技能 / 命令喵（与 Agent 相同的白名单、环境变量与资源限制，无需逐次确认）
+async fn run_scheduled_tool(
+    config: &Config,
+    config_dir: &Path,
+    action: &service::TaskAction,
+) -> std::result::Result<String, String> {
+    let Some(allowlist) = config.security.as_ref().and_then(|s| s.allowlist.clone()) else {
+        return Err("未配置 [security.allowlist]，定时任务不能运行命令或技能喵".to_string());
+    };
+    let environment = match config.security.as_ref().and_then(|s| s.tool_env.as_ref()) {
+        Some(tool_env) => security::ToolEnvironment::resolve(tool_env).map_err(|e| e.to_string())?,
+        None => security::ToolEnvironment::minimal(),
+    };
+    let limits = config.security.as_ref().map(|s| s.shell.clone()).unwrap_or_default();
+    let allowlist = security::AllowlistService::new(allowlist);
+    let mut registry = ToolRegistry::new();
+    if let Some(audit) = open_audit_log(config_dir) {
+        registry = registry.with_audit(audit, "scheduler");
+    }
+    let (tool, input) = match action {
+        service::TaskAction::Skill { skill, args } => {
+            let mut skills_manager = SkillsManager::new(config.workspace.join("skills"));
+            skills_manager.load_all().ok();
+            let skill_tool = SkillTool::new(SharedSkills::new(skills_manager), allowlist)
+                .with_environment(environment)
+                .with_limits(limits);
+            let _ = registry.register(skill_tool);
+            ("skill", serde_json::json!({ "name": skill, "args": args }))
+        }
+        service::TaskAction::Shell { command, args } => {
+            let shell = ShellTool::new(Arc::new(allowlist))
+                .with_environment(environment)
+                .with_working_dir(&config.workspace)
+                .with_limits(limits);
+            let _ = registry.register(McpShellTool::new(shell));
+            ("shell", serde_json::json!({ "command": command, "args": args }))
+        }
+        service::TaskAction::Prompt { .. } => return Err("prompt 任务由 Agent 运行喵".to_string()),
+    };
+    match registry.execute(tool, input).await {
+        Ok(result) if result.success => Ok(format_tool_result_for_llm(&result, None)),
+        Ok(result) => Err(result.error.unwrap_or_else(|| "Unknown error".to_string())),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 定时任务中的 Agent 消息喵
+///
+/// 以子进程运行 `nekoclaw agent --output json`，与命令行一样受 Agent 限额、预算与费用记账约束；
+/// 没有终端可以确认，危险工具一律不执行
+async fn run_scheduled_prompt(
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+    prompt: &str,
+    agent: Option<&str>,
+) -> std::result::Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("无法定位 nekoclaw 可执行文件喵: {}", e))?;
+    let mut command = tokio::process::Command::new(exe);
+    command
+        .arg("--config-dir")
+        .arg(&profile.base_dir)
+        .args(["--workspace", &profile.name, "agent", "--output", "json"])
+        .args(["--provider", &config.default_provider, "--message", prompt]);
+    if let Some(agent) = agent {
+        command.args(["--agent", agent]);
+    }
+    let output = command
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("无法启动 Agent 喵: {}", e))?;
+
+    let mut reply = None;
+    let mut errors = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+        match event["type"].as_str() {
+            Some("assistant") => reply = event["text"].as_str().map(str::to_string),
+            Some("error") => errors.extend(event["message"].as_str().map(str::to_string)),
+            _ => {}
+        }
+    }
+    if let (true, Some(reply)) = (output.status.success(), &reply) {
+        return Ok(reply.clone());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let detail = errors
+        .pop()
+        .or(reply)
+        .or_else(|| stderr.lines().last().map(str::to_string))
+        .unwrap_or_else(|| "no reply".to_string());
+    Err(format!("Agent 运行失败喵 ({}): {}", output.status, detail))
+}
+
+/// 处理多机同步喵
+async fn handle_sync(action: &SyncAction, config: &Config, profile: &core::WorkspaceProfile) -> Result<()> {
+    let Some(sync_config) = config.sync.as_ref().filter(|s| s.enabled) else {
+        println!("⚠️ 同步未启用喵（在配置中设置 sync.enabled = true）");
+        return Ok(());
+    };
+
+    match action {
+        SyncAction::Status => {
+            println!("🔄 同步配置:");
+            println!("  后端: {}", sync_config.backend);
+            if let Some(url) = &sync_config.url {
+                println!("  地址: {}", url);
+            }
+            if let Some(dir) = &sync_config.directory {
+                println!("  目录: {}", dir.display());
+            }
+            println!("  同步配置文件: {}", sync_config.include_config);
+            println!("  定时间隔: {} 分钟", sync_config.interval_minutes);
+        }
+        SyncAction::Now => {
+            let engine = sync::SyncEngine::from_config(config, profile)?;
+            let report = engine.run_once().await?;
+            println!("✅ 同步完成喵:");
+            println!("  本地修改: {}", report.local_changes);
+            println!("  应用远端: {}", report.applied);
+            println!("  冲突裁决: {}", report.conflicts);
+            println!("  记录总数: {}", report.total_records);
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理放行申请审批喵
+fn handle_escalation(action: &EscalationAction, config_path: &Path) -> Result<()> {
+    let manager = security::EscalationManager::open(config_path.to_path_buf())?;
+    let approver = cli_actor();
+
+    match action {
+        EscalationAction::List { all } => {
+            let status = if *all {
+                None
+            } else {
+                Some(security::EscalationStatus::Pending)
+            };
+            let requests = manager.list(status)?;
+            if requests.is_empty() {
+                println!("📭 没有放行申请喵");
+            }
+            for request in requests {
+                println!(
+                    "  [{}] {:?} {} {} → {:?} ({})",
+                    request.id,
+                    request.status,
+                    request.tool_name,
+                    request.violation.policy,
+                    request.invocation,
+                    request.justification
+                );
+            }
+        }
+        EscalationAction::Approve { id } => {
+            let request = manager.approve(id, &approver)?;
+            audit_cli(config_path, security::AuditKind::Admin, "escalation.approve", serde_json::json!({
+                "id": request.id,
+                "tool": request.tool_name,
+                "blocked": request.violation.blocked,
+                "invocation": request.invocation,
+            }));
+            println!("✅ 已批准 {}，{:?} 可执行一次喵", request.id, request.invocation);
+        }
+        EscalationAction::Deny { id } => {
+            let request = manager.deny(id, &approver)?;
+            audit_cli(config_path, security::AuditKind::Admin, "escalation.deny", serde_json::json!({
+                "id": request.id,
+                "tool": request.tool_name,
+            }));
+            println!("🚫 已拒绝 {}喵", request.id);
+        }
+    }
+
+    Ok(())
+}
+
+/// 打开隐私服务（记忆库 + 遥测库）喵
+async fn open_privacy_service(config: &Config, config_dir: &Path) -> Result<privacy::PrivacyService> {
+    let memory_path = config.memory_db_path();
+    if let Some(parent) = memory_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let settings = config.memory.clone().unwrap_or_default();
+    let memory = memory::MemoryFactory::open_sqlite(&memory_path.to_string_lossy(), &settings)?;
+    let mut service = privacy::PrivacyService::new(Arc::new(memory))
+        .with_sessions(open_session_store(config, config_dir, &config_dir.join("sessions"))?)
+        .with_audit(Arc::new(security::AuditLog::open_workspace(config_dir)?));
+
+    let metrics_path = config_dir.join("metrics.db");
+    if metrics_path.exists() {
+        let metrics = telemetry::MetricsCollector::new(telemetry::MetricsConfig {
+            db_path: metrics_path.to_string_lossy().to_string(),
+            monitor_interval_sec: 5,
+        })
+        .await?;
+        service = service.with_metrics(Arc::new(metrics));
+    }
+    Ok(service)
+}
+
+/// 处理隐私管理喵
+async fn handle_privacy(action: &PrivacyAction, config: &Config, config_path: &Path) -> Result<()> {
+    let service = open_privacy_service(config, config_path).await?;
+
+    let report = match action {
+        PrivacyAction::Forget { channel, user } => {
+            println!("🧹 正在清除 {}:{} 的全部数据喵...", channel, user);
+            service.forget_subject(&privacy::DataSubject::new(channel.as_str(), user.as_str()))?
+        }
+        PrivacyAction::Retention => {
+            let Some(privacy_config) = config.privacy.as_ref() else {
+                println!("⚠️ 未配置数据保留策略喵（privacy.retention_days）");
+                return Ok(());
+            };
+            service.enforce_retention(privacy_config, chrono::Utc::now())?
+        }
+    };
+
+    println!("✅ 清除完成喵:");
+    println!("  记忆条目: {}", report.memory_entries);
+    println!("  遥测记录: {}", report.telemetry_rows);
+    println!("  会话: {}", report.sessions);
+    println!("  审计记录（已抹去个人数据）: {}", report.audit_entries);
+    Ok(())
+}
+
+/// 处理工作区管理喵
+fn handle_workspace(action: &WorkspaceAction, profile: &core::WorkspaceProfile) -> Result<()> {
+    match action {
+        WorkspaceAction::List => {
+            println!("📂 工作区列表:");
+            for name in core::WorkspaceProfile::list(&profile.base_dir) {
+                let marker = if name == profile.name { "*" } else { " " };
+                println!("  {} {}", marker, name);
+            }
+        }
+        WorkspaceAction::Create { name } => {
+            let target = core::WorkspaceProfile::resolve(&profile.base_dir, Some(name))?;
+            if target.is_default() {
+                return Err("默认工作区无需创建喵".into());
+            }
+            target.create()?;
+            println!("✅ 工作区 '{}' 已创建喵: {}", target.name, target.root.display());
+            println!("   可在 {} 中覆盖配置", target.root.join("config.toml").display());
+        }
+        WorkspaceAction::Show => {
+            println!("📂 当前工作区: {}", profile.name);
+            println!("  根目录: {}", profile.root.display());
+            println!("  记忆库: {}", profile.memory_db_path().display());
+            println!("  会话:   {}", profile.sessions_dir().display());
+            println!("  凭证:   {}", profile.credentials_dir().display());
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理模型命令喵
+async fn handle_models(
+    action: &ModelsAction,
+    config: &Config,
+    profile: &core::WorkspaceProfile,
+) -> Result<()> {
+    // OpenRouter 的模型列表无需认证，本地 Ollama 全部无需认证，其余请求都需要 API Key 喵
+    let resolve = |provider: &Option<String>, needs_key: bool| -> Result<(String, ProviderConfig)> {
+        let name = provider
+            .clone()
+            .unwrap_or_else(|| config.default_provider.clone())
+            .to_lowercase();
+        let provider_config = config.provider(&name).ok_or_else(|| {
+            format!("不支持的 provider '{}'喵（可选: nvidia / openai / openrouter / ollama）", name)
+        })?;
+        if provider_config.api_key.is_empty() && name != "ollama" && (needs_key || name != "openrouter") {
+            return Err(format!("provider '{}' 未配置 API Key 喵", name).into());
+        }
+        Ok((name, provider_config))
+    };
+
+    match action {
+        ModelsAction::List { provider, filter } => {
+            let (name, provider_config) = resolve(provider, false)?;
+            let mut entries = providers::catalog::fetch_catalog(&name, &provider_config).await?;
+            if let Some(filter) = filter {
+                let filter = filter.to_lowercase();
+                entries.retain(|e| e.id.to_lowercase().contains(&filter));
+            }
+            println!("📚 {} 模型 ({} 个):", name, entries.len());
+            print!("{}", providers::catalog::format_table(&entries));
+            println!("当前默认: {}", config.default_model);
+        }
+        ModelsAction::Use { id, provider } => {
+            let (name, provider_config) = resolve(provider, true)?;
+            println!("🔍 正在验证 {} ({}) ...", id, name);
+            providers::catalog::validate_model(&name, &provider_config, id)
+                .await
+                .map_err(|e| format!("模型 '{}' 验证失败喵: {}", id, e))?;
+            let path = profile.set_config_values(&[
+                ("default_model", serde_json::json!(id)),
+                ("default_provider", serde_json::json!(name)),
+            ])?;
+            println!("✅ 默认模型已设为 {} 喵 ({})", id, path.display());
+        }
     }
+
     Ok(())
 }
 
@@ -0,0 +1,161 @@
+/*!
+ * Embedding Provider - 记忆向量化
+ *
+ * 功能:
+ * - `EmbeddingProvider` 抽象 (批量文本 → 向量)
+ * - OpenAI 兼容 `/embeddings` 端点 (OpenAI / NVIDIA / Ollama `/v1` …)
+ * - 本地 hash 向量 (特征哈希，无需网络；未配置 API Key 时的回退)
+ *
+ * 不同模型的向量不可比较，存储时记录 `model_id`，检索只比较同一模型的向量
+ */
+
+use crate::core::traits::Result;
+use crate::providers::OpenAIClient;
+use async_trait::async_trait;
+
+/// 文本向量化接口
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// 批量向量化，按输入顺序返回
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// 模型标识 (如 `openai:text-embedding-3-small`、`hash:256`)
+    fn model_id(&self) -> String;
+}
+
+/// 本地 hash 向量 (词袋特征哈希 + L2 归一化)
+///
+/// 只反映词面重合，是没有 embedding API 时的退路；
+/// 英文等按单词切分，中日文按单字 + 相邻二字切分
+#[derive(Debug, Clone)]
+pub struct HashEmbedding {
+    dimensions: usize,
+}
+
+impl HashEmbedding {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    /// 单条文本向量化 (同步，无 I/O)
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        for token in tokenize(text) {
+            let hash = fnv1a(token.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbedding {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+
+    fn model_id(&self) -> String {
+        format!("hash:{}", self.dimensions)
+    }
+}
+
+/// OpenAI 兼容 `/embeddings` 端点
+#[derive(Debug, Clone)]
+pub struct OpenAIEmbedding {
+    client: OpenAIClient,
+    model: String,
+}
+
+impl OpenAIEmbedding {
+    pub fn new(client: OpenAIClient, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbedding {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let vectors = self.client.embed(&self.model, texts).await?;
+        if vectors.len() != texts.len() {
+            return Err(format!("Embedding count mismatch: {} inputs, {} vectors", texts.len(), vectors.len()).into());
+        }
+        Ok(vectors)
+    }
+
+    fn model_id(&self) -> String {
+        format!("openai:{}", self.model)
+    }
+}
+
+/// 中日文字符 (按字切分)
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+/// 切分为小写词 (中日文为单字 + 相邻二字)
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut previous_cjk: Option<char> = None;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            tokens.push(c.to_string());
+            if let Some(prev) = previous_cjk {
+                tokens.push(format!("{}{}", prev, c));
+            }
+            previous_cjk = Some(c);
+            continue;
+        }
+        previous_cjk = None;
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// FNV-1a (跨版本稳定，已存储的向量不会失效)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SimpleVectorDB;
+
+    #[tokio::test]
+    async fn test_hash_embedding_similarity() {
+        let embedder = HashEmbedding::new(256);
+        let texts = ["Nginx timeout on port 443", "nginx timeout", "cat food schedule", "猫粮的喂食时间", "喂食时间"]
+            .map(String::from);
+        let vectors = embedder.embed(&texts).await.unwrap();
+
+        assert_eq!(vectors[0].len(), 256);
+        assert_eq!(vectors[1], embedder.embed_text("NGINX  timeout!"));
+        let similarity = |a: usize, b: usize| SimpleVectorDB::cosine_similarity_vec(&vectors[a], &vectors[b]);
+        assert!(similarity(0, 1) > 0.5);
+        assert!(similarity(1, 2) < 0.2);
+        assert!(similarity(3, 4) > 0.5);
+        assert_eq!(embedder.model_id(), "hash:256");
+        assert!(embedder.embed_text("").iter().all(|x| *x == 0.0));
+    }
+}
@@ -74,7 +74,7 @@ impl IdentityParser {
         let personality = self.parse_soul_md()?;
 
         // 解析 AGENTS.md (如果存在)
-        let (agent_role, agent_channel) = self.parse_agents_md()?;
+        let (agent_role, agent_channel) = self.parse_agents_md(&identity_md.name)?;
 
         Ok(OpenClawIdentity {
             name: identity_md.name,
@@ -91,7 +91,7 @@ impl IdentityParser {
     /// 解析 IDENTITY.md
     fn parse_identity_md(&self) -> Result<IdentityConfig> {
         let path = self.workspace.join("IDENTITY.md");
-        let content =
+        let _content =
             fs::read_to_string(&path).map_err(|e| format!("Failed to read IDENTITY.md: {}", e))?;
 
         // 简化实现: 使用正则或关键行解析
@@ -108,7 +108,7 @@ impl IdentityParser {
     /// 解析 SOUL.md
     fn parse_soul_md(&self) -> Result<Personality> {
         let path = self.workspace.join("SOUL.md");
-        let content =
+        let _content =
             fs::read_to_string(&path).map_err(|e| format!("Failed to read SOUL.md: {}", e))?;
 
         // 简化实现: 手动解析关键内容
@@ -130,8 +130,8 @@ impl IdentityParser {
         })
     }
 
-    /// 解析 AGENTS.md
-    fn parse_agents_md(&self) -> Result<(Option<String>, Option<String>)> {
+    /// 解析 AGENTS.md (按名称匹配表格行，找不到时取第一行)
+    fn parse_agents_md(&self, agent_name: &str) -> Result<(Option<String>, Option<String>)> {
         let path = self.workspace.join("AGENTS.md");
         if !path.exists() {
             return Ok((None, None));
@@ -140,9 +140,9 @@ impl IdentityParser {
         let content =
             fs::read_to_string(&path).map_err(|e| format!("Failed to read AGENTS.md: {}", e))?;
 
-        // 简化实现: 提取 Agent 角色和频道信息
-        // 实际实现应该解析完整的表格结构
-        Ok((None, None))
+        Ok(parse_markdown_table(&content)
+            .map(|table| agent_assignment(&table, agent_name))
+            .unwrap_or((None, None)))
     }
 
     /// 注入人格到响应文本
@@ -172,6 +172,102 @@ impl IdentityParser {
     }
 }
 
+/// Markdown 表格 (AGENTS.md)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarkdownTable {
+    pub headers: Vec<String>,
+    /// 每行的单元格数与表头一致 (缺失补空、多余截断)
+    pub rows: Vec<Vec<String>>,
+}
+
+/// 拆分表格行的单元格 (支持 `\|` 转义)
+fn split_cells(line: &str) -> Vec<String> {
+    let inner = line.trim();
+    let inner = inner.strip_prefix('|').unwrap_or(inner);
+    let inner = match inner.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => inner,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// 是否为表头分隔行 (`|---|:--:|`)
+fn is_separator(line: &str) -> bool {
+    let cells = split_cells(line);
+    !cells.is_empty()
+        && cells.iter().all(|c| {
+            let dashes = c.trim_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|ch| ch == '-')
+        })
+}
+
+/// 解析文本中的第一个 Markdown 表格
+///
+/// 输入来自工作区文件，不可信: 任何畸形表格都返回 None 或部分结果，不会 panic
+pub fn parse_markdown_table(content: &str) -> Option<MarkdownTable> {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.windows(2).position(|pair| {
+        pair[0].trim_start().starts_with('|') && is_separator(pair[1])
+    })?;
+
+    let headers = split_cells(lines[start]);
+    let rows = lines[start + 2..]
+        .iter()
+        .take_while(|line| line.trim_start().starts_with('|'))
+        .map(|line| {
+            let mut cells = split_cells(line);
+            cells.resize(headers.len(), String::new());
+            cells
+        })
+        .collect();
+
+    Some(MarkdownTable { headers, rows })
+}
+
+/// 从 AGENTS.md 表格提取 (角色, 频道)
+fn agent_assignment(table: &MarkdownTable, agent_name: &str) -> (Option<String>, Option<String>) {
+    let column = |names: &[&str]| {
+        table
+            .headers
+            .iter()
+            .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    };
+    let name_col = column(&["agent", "name", "名称", "名字"]);
+    let role_col = column(&["role", "角色", "职责"]);
+    let channel_col = column(&["channel", "频道"]);
+
+    let row = name_col
+        .and_then(|col| {
+            table
+                .rows
+                .iter()
+                .find(|row| row[col].eq_ignore_ascii_case(agent_name))
+        })
+        .or_else(|| table.rows.first());
+    let cell = |col: Option<usize>| {
+        row.zip(col)
+            .map(|(row, col)| row[col].clone())
+            .filter(|value| !value.is_empty())
+    };
+
+    (cell(role_col), cell(channel_col))
+}
+
 /// IDENTITY.md 配置 (内部结构)
 struct IdentityConfig {
     name: String,
@@ -185,6 +281,61 @@ struct IdentityConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_agents_table() {
+        let content = "# Agents\n\n| Agent | Role | Channel |\n|---|:---|---:|\n\
+                       | 诺诺 | Provider \\| Tools | #dev |\n| Default Agent | 助手 | |\n\nfooter | x";
+        let table = parse_markdown_table(content).unwrap();
+        assert_eq!(table.headers, vec!["Agent", "Role", "Channel"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0][1], "Provider | Tools");
+
+        assert_eq!(
+            agent_assignment(&table, "default agent"),
+            (Some("助手".to_string()), None)
+        );
+        assert_eq!(
+            agent_assignment(&table, "someone else"),
+            (Some("Provider | Tools".to_string()), Some("#dev".to_string()))
+        );
+        assert!(parse_markdown_table("| not | a table |\nplain text").is_none());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_markdown_table_never_panics(content in "[|:\\- a-z\n\\\\]{0,200}") {
+            if let Some(table) = parse_markdown_table(&content) {
+                for row in &table.rows {
+                    proptest::prop_assert_eq!(row.len(), table.headers.len());
+                }
+                let _ = agent_assignment(&table, "a");
+            }
+        }
+
+        #[test]
+        fn prop_markdown_table_roundtrip(
+            headers in proptest::collection::vec("[a-z]{1,8}", 1..5),
+            cells in proptest::collection::vec("[a-z0-9 ]{0,8}", 0..20),
+        ) {
+            let render = |row: &[String]| format!("| {} |", row.join(" | "));
+            let rows: Vec<Vec<String>> = cells
+                .chunks(headers.len())
+                .filter(|chunk| chunk.len() == headers.len())
+                .map(|chunk| chunk.to_vec())
+                .collect();
+            let mut lines = vec![render(&headers), format!("|{}|", vec!["---"; headers.len()].join("|"))];
+            lines.extend(rows.iter().map(|row| render(row)));
+
+            let table = parse_markdown_table(&lines.join("\n")).unwrap();
+            let trimmed: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| row.iter().map(|c| c.trim().to_string()).collect())
+                .collect();
+            proptest::prop_assert_eq!(table.headers, headers);
+            proptest::prop_assert_eq!(table.rows, trimmed);
+        }
+    }
+
     #[test]
     fn test_cosine_similarity() {
         use crate::memory::vector::SimpleVectorDB;
@@ -7,20 +7,33 @@
  * 功能:
  * - SQLite 后端 + FTS5 全文搜索
  * - 简化向量存储 (不依赖外部库)
+ * - 文本向量化 (OpenAI embeddings / 本地 hash 回退) + FTS5 混合检索
+ * - 重要度 / 时间衰减 / 访问次数评分，低分记忆清理
+ * - 上下文裁剪前的对话摘要 (按会话召回)
  * - OpenClaw IDENTITY.md 兼容解析
+ * - 可选 AES-GCM 静态加密 (版本化密钥环，初始密钥来自环境变量或密钥文件)
+ * - 组合查询 (关键词 + metadata 过滤 + 时间窗口)
  */
 
+pub mod embedding;
 pub mod identity_parser;
+pub mod query;
+pub mod scoring;
 pub mod sqlite;
+pub mod summary;
 pub mod vector;
 
 // 重新导出所有子模块接口
+pub use embedding::{EmbeddingProvider, HashEmbedding, OpenAIEmbedding};
 pub use identity_parser::{IdentityParser, OpenClawIdentity};
-pub use sqlite::SqliteMemory;
+pub use query::{parse_filter, parse_since, MemoryQuery};
+pub use sqlite::{SqliteMemory, StoredMemory};
+pub use summary::ConversationSummarizer;
 pub use vector::SimpleVectorDB;
 
 use crate::core::traits::*;
-use std::path::PathBuf;
+use crate::security::{resolve_key, KeyRing};
+use std::path::Path;
 use std::sync::Arc;
 
 /// Memory 工厂 - 创建不同类型的 Memory 实现
@@ -38,6 +51,33 @@ impl MemoryFactory {
         let memory = SqliteMemory::new_with_vector(path)?;
         Ok(Arc::new(memory))
     }
+
+    /// 按配置打开具体的 SqliteMemory (需要组合查询等扩展接口时使用)
+    pub fn open_sqlite(path: &str, settings: &MemorySettings) -> Result<SqliteMemory> {
+        let memory = SqliteMemory::new(path)?.with_scoring(settings.scoring.clone());
+
+        match settings.encryption.as_ref().filter(|e| e.enabled) {
+            Some(enc) => Ok(memory.with_encryption(Self::encryption_keyring(path, enc)?.crypto()?)),
+            None => Ok(memory),
+        }
+    }
+
+    /// 记忆库的版本化密钥环 (`<数据库目录>/keys/memory.keyring`)
+    ///
+    /// 首次创建时把配置的密钥 (环境变量 / 密钥文件) 作为版本 0 导入，
+    /// 之后由密钥环管理，`nekoclaw security rotate-key` 负责轮换
+    pub fn encryption_keyring(path: &str, enc: &MemoryEncryptionConfig) -> Result<KeyRing> {
+        let Some(dir) = Path::new(path).parent().filter(|_| path != ":memory:") else {
+            return Err("内存数据库不支持密钥环".into());
+        };
+        let ring_path = KeyRing::path_for(dir, "memory");
+        let legacy = if ring_path.exists() {
+            None
+        } else {
+            Some(resolve_key(&enc.key_env, enc.key_file.as_deref())?)
+        };
+        Ok(KeyRing::open(&ring_path, legacy.as_deref())?)
+    }
 }
 
 /// MemoryManager 类型别名，用于兼容性
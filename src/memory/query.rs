@@ -0,0 +1,142 @@
+/*!
+ * Memory Query - 带过滤条件的记忆检索
+ *
+ * 功能:
+ * - FTS5 关键词 + metadata 过滤 + 时间窗口组合查询
+ * - `--filter key=value` / `--since 7d` / `--agent nia` 参数解析
+ *
+ * metadata 过滤在解密后进行，加密库同样适用
+ */
+
+use crate::core::traits::MemoryItem;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+
+/// 记忆查询条件
+#[derive(Debug, Clone, Default)]
+pub struct MemoryQuery {
+    /// 关键词 (FTS5 语法；None = 不限)
+    pub text: Option<String>,
+    /// metadata 精确匹配条件 (所有条件都需满足)
+    pub filters: Vec<(String, String)>,
+    /// 只返回该时间之后创建的记忆
+    pub since: Option<DateTime<Utc>>,
+    /// 最多返回条数
+    pub limit: usize,
+}
+
+impl MemoryQuery {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    pub fn with_filter(mut self, key: &str, value: &str) -> Self {
+        self.filters.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// `--agent nia` 等价于 `--filter agent=nia`
+    pub fn with_agent(self, agent: &str) -> Self {
+        self.with_filter("agent", agent)
+    }
+
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// metadata / 时间条件是否满足 (关键词由存储层负责)
+    pub fn matches(&self, item: &MemoryItem) -> bool {
+        if self.since.is_some_and(|since| item.created_at < since) {
+            return false;
+        }
+        self.filters.iter().all(|(key, expected)| {
+            match item.metadata.as_ref().and_then(|m| m.get(key)) {
+                Some(Value::String(s)) => s == expected,
+                Some(Value::Null) | None => false,
+                Some(other) => other.to_string().as_str() == expected.as_str(),
+            }
+        })
+    }
+}
+
+/// 解析 `key=value` 过滤条件
+pub fn parse_filter(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("过滤条件 '{}' 应为 key=value 格式喵", raw)),
+    }
+}
+
+/// 解析相对时间窗口 (`30m` / `12h` / `7d` / `2w`)，返回起始时刻
+pub fn parse_since(raw: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let raw = raw.trim();
+    let invalid = || format!("时间窗口 '{}' 不合法喵 (示例: 30m, 12h, 7d, 2w)", raw);
+    let split = raw.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let window = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    now.checked_sub_signed(window).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(metadata: Value, age_days: i64) -> MemoryItem {
+        MemoryItem {
+            id: "m".to_string(),
+            content: "c".to_string(),
+            embedding: None,
+            metadata: Some(metadata),
+            created_at: Utc::now() - Duration::days(age_days),
+        }
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let now = Utc::now();
+        assert_eq!(parse_since("7d", now).unwrap(), now - Duration::days(7));
+        assert_eq!(parse_since("90m", now).unwrap(), now - Duration::minutes(90));
+        assert!(parse_since("7", now).is_err());
+        assert!(parse_since("d", now).is_err());
+        assert!(parse_since("3y", now).is_err());
+
+        assert_eq!(parse_filter("key=chat").unwrap(), ("key".into(), "chat".into()));
+        assert_eq!(parse_filter("url=a=b").unwrap(), ("url".into(), "a=b".into()));
+        assert!(parse_filter("=chat").is_err());
+        assert!(parse_filter("chat").is_err());
+    }
+
+    #[test]
+    fn test_query_matches_metadata_and_window() {
+        let query = MemoryQuery::new(10)
+            .with_agent("nia")
+            .with_filter("turn", "3")
+            .with_since(Utc::now() - Duration::days(7));
+
+        assert!(query.matches(&item(json!({"agent": "nia", "turn": 3}), 1)));
+        assert!(!query.matches(&item(json!({"agent": "nia", "turn": 3}), 8)));
+        assert!(!query.matches(&item(json!({"agent": "mochi", "turn": 3}), 1)));
+        assert!(!query.matches(&item(json!({"agent": "nia"}), 1)));
+    }
+}
@@ -0,0 +1,97 @@
+/*!
+ * Memory Scoring - 记忆重要度与时间衰减
+ *
+ * 功能:
+ * - 重要度 (metadata `importance`，0~1，默认 0.5)
+ * - 时间衰减 (从最后一次访问算起，按半衰期指数衰减)
+ * - 访问次数 (对数增长，10 次以上视为满分)
+ * - 综合得分 = 三者按配置加权平均，取值 0~1
+ */
+
+use crate::core::traits::{MemoryItem, MemoryScoringConfig};
+use chrono::{DateTime, Utc};
+
+/// 未标注重要度的记忆
+pub const DEFAULT_IMPORTANCE: f32 = 0.5;
+
+/// 访问次数达到该值时访问分为满分
+const SATURATING_ACCESS_COUNT: f32 = 10.0;
+
+/// 评分所需的使用统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryStats {
+    pub importance: f32,
+    pub access_count: u32,
+    /// 最后一次被检索到的时间 (None = 从未被检索)
+    pub last_accessed: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 从 metadata 读取重要度 (数字或数字字符串，截断到 0~1)
+pub fn importance_of(item: &MemoryItem) -> f32 {
+    item.metadata
+        .as_ref()
+        .and_then(|m| m.get("importance"))
+        .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+        .map(|v| (v as f32).clamp(0.0, 1.0))
+        .unwrap_or(DEFAULT_IMPORTANCE)
+}
+
+/// 综合得分 (0~1)
+pub fn score(config: &MemoryScoringConfig, stats: &MemoryStats, now: DateTime<Utc>) -> f32 {
+    let last_used = stats.last_accessed.unwrap_or(stats.created_at).max(stats.created_at);
+    let age_days = (now - last_used).num_seconds().max(0) as f32 / 86_400.0;
+    let recency = match config.half_life_days > 0.0 {
+        true => 0.5f32.powf(age_days / config.half_life_days),
+        false => 1.0,
+    };
+    let access = ((1.0 + stats.access_count as f32).ln() / (1.0 + SATURATING_ACCESS_COUNT).ln()).min(1.0);
+
+    let weights = [config.importance_weight, config.recency_weight, config.access_weight].map(|w| w.max(0.0));
+    let total: f32 = weights.iter().sum();
+    if total == 0.0 {
+        return 1.0;
+    }
+    (weights[0] * stats.importance + weights[1] * recency + weights[2] * access) / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[test]
+    fn test_score_decay_and_importance() {
+        let config = MemoryScoringConfig::default();
+        let now = Utc::now();
+        let stats = |importance: f32, access_count: u32, idle_days: i64| MemoryStats {
+            importance,
+            access_count,
+            last_accessed: (access_count > 0).then(|| now - Duration::days(idle_days)),
+            created_at: now - Duration::days(idle_days.max(1)),
+        };
+
+        let fresh = score(&config, &stats(DEFAULT_IMPORTANCE, 0, 0), now);
+        let stale = score(&config, &stats(DEFAULT_IMPORTANCE, 0, 120), now);
+        assert!(fresh > 0.55 && fresh < 0.65);
+        assert!(stale < config.prune_threshold);
+        assert!(score(&config, &stats(1.0, 0, 120), now) > config.prune_threshold);
+        assert!((score(&config, &stats(DEFAULT_IMPORTANCE, 12, 0), now) - 0.8).abs() < 1e-6);
+        // 访问会重置衰减喵
+        assert!(score(&config, &stats(DEFAULT_IMPORTANCE, 1, 1), now) > stale);
+
+        let mut item = MemoryItem {
+            id: "m".to_string(),
+            content: "c".to_string(),
+            embedding: None,
+            metadata: Some(json!({"importance": 0.9})),
+            created_at: now,
+        };
+        assert_eq!(importance_of(&item), 0.9);
+        item.metadata = Some(json!({"importance": "7"}));
+        assert_eq!(importance_of(&item), 1.0);
+        item.metadata = None;
+        assert_eq!(importance_of(&item), DEFAULT_IMPORTANCE);
+    }
+}
@@ -8,18 +8,70 @@
  * - SQLite 数据库存储
  * - FTS5 全文搜索
  * - 简化向量相似度计算 (余弦相似度)
+ * - 保存时自动向量化，语义检索与 FTS5 结果按排名融合 (RRF)
+ * - 检索结果按相关度 × 综合得分 (重要度 / 时间衰减 / 访问次数) 排序，低分记忆清理
  * - 自动创建数据库表
+ * - 可选 AES-GCM 字段级静态加密 (content / metadata)
  */
 
+use super::embedding::EmbeddingProvider;
+use super::query::MemoryQuery;
+use super::scoring::{self, MemoryStats};
 use crate::core::traits::*;
+use crate::security::{CryptoError, CryptoService};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Result as SqliteResult};
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+/// 加密字段前缀喵 (用于区分历史明文数据)
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// RRF 融合常数 (score = Σ 1 / (k + rank))
+const RRF_K: f32 = 60.0;
+
+/// 重建向量时每批的条数
+const EMBED_BATCH: usize = 64;
+
+/// 当前数据库结构版本 (PRAGMA user_version，打开时迁移到此版本)
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// 读取数据库结构版本 (只读打开，不触发迁移；0 = 版本号出现之前创建的库)
+pub fn schema_version<P: AsRef<Path>>(path: P) -> SqliteResult<i64> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// 数据库中原样存储的一条记忆 (同步使用)
+///
+/// 加密库的 content / metadata 保持密文，对端需要同一把记忆密钥才能解密
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredMemory {
+    pub id: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+    /// metadata JSON (加密库为密文)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<String>,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<f64>,
+}
+
 pub struct SqliteMemory {
     conn: Arc<Mutex<Connection>>,
     enable_vector: bool,
+    /// 字段加密器 (None = 明文存储)
+    cipher: Option<CryptoService>,
+    /// 向量化 (None = 只用 FTS5)
+    embedder: Option<Arc<dyn EmbeddingProvider>>,
+    /// 语义召回的最低余弦相似度
+    min_similarity: f32,
+    /// 评分配置
+    scoring: MemoryScoringConfig,
 }
 
 impl SqliteMemory {
@@ -31,6 +83,10 @@ impl SqliteMemory {
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             enable_vector,
+            cipher: None,
+            embedder: None,
+            min_similarity: 0.0,
+            scoring: MemoryScoringConfig::default(),
         })
     }
 
@@ -42,9 +98,527 @@ impl SqliteMemory {
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             enable_vector,
+            cipher: None,
+            embedder: None,
+            min_similarity: 0.0,
+            scoring: MemoryScoringConfig::default(),
+        })
+    }
+
+    /// 启用字段级静态加密
+    ///
+    /// 启用后 content / metadata 以 AES-GCM 密文存储，FTS5 索引不再可用，
+    /// 检索改为解密后扫描匹配
+    pub fn with_encryption(mut self, cipher: CryptoService) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// 启用向量化: 保存时自动生成 embedding，`hybrid_query` 融合语义检索
+    ///
+    /// 加密库不生成 embedding (向量本身会泄露内容语义)
+    pub fn with_embedder(mut self, embedder: Arc<dyn EmbeddingProvider>, min_similarity: f32) -> Self {
+        self.embedder = Some(embedder);
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// 评分配置 (检索排序与清理)
+    pub fn with_scoring(mut self, scoring: MemoryScoringConfig) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// 可用的向量化器 (加密库为 None)
+    fn active_embedder(&self) -> Option<&Arc<dyn EmbeddingProvider>> {
+        self.embedder.as_ref().filter(|_| self.cipher.is_none())
+    }
+
+    /// 是否启用了静态加密
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// 加密字段 (未启用加密时原样返回)
+    fn seal(&self, plain: &str) -> Result<String> {
+        match &self.cipher {
+            Some(cipher) => Ok(format!("{}{}", ENCRYPTED_PREFIX, cipher.encrypt(plain)?)),
+            None => Ok(plain.to_string()),
+        }
+    }
+
+    /// 解密字段 (明文字段原样返回)
+    fn open_field(cipher: Option<&CryptoService>, stored: String) -> String {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return stored;
+        };
+        match cipher.map(|c| c.decrypt(encoded)) {
+            Some(Ok(plain)) => plain,
+            Some(Err(e)) => {
+                tracing::warn!("Failed to decrypt memory field: {}", e);
+                stored
+            }
+            None => {
+                tracing::warn!("Encrypted memory found but no key is configured");
+                stored
+            }
+        }
+    }
+
+    /// 行 → MemoryItem (列顺序: id, content, embedding, metadata, created_at)
+    fn row_to_item(row: &Row<'_>, cipher: Option<&CryptoService>) -> SqliteResult<MemoryItem> {
+        Ok(MemoryItem {
+            id: row.get(0)?,
+            content: Self::open_field(cipher, row.get(1)?),
+            embedding: row
+                .get::<_, Option<Vec<u8>>>(2)?
+                .and_then(|b| Self::parse_embedding(&b)),
+            metadata: row
+                .get::<_, Option<String>>(3)?
+                .map(|s| Self::open_field(cipher, s))
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            created_at: DateTime::parse_from_rfc3339(row.get::<_, String>(4)?.as_str())
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
         })
     }
 
+    /// 加密模式下的检索: 解密后按关键词扫描 (所有词都需命中)
+    fn scan_encrypted(&self, conn: &Connection, query: &str, limit: usize) -> Result<Vec<MemoryItem>> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|t| t.to_lowercase())
+            .collect();
+
+        let items = conn
+            .prepare(
+                "SELECT id, content, embedding, metadata, created_at FROM memory
+                 ORDER BY created_at DESC",
+            )?
+            .query_map([], |row| Self::row_to_item(row, self.cipher.as_ref()))?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("Scan error: {}", e))?;
+
+        Ok(items
+            .into_iter()
+            .filter(|item| {
+                let content = item.content.to_lowercase();
+                terms.iter().all(|t| content.contains(t))
+            })
+            .take(limit)
+            .collect())
+    }
+
+    /// 列出所有记忆 (按创建时间倒序)
+    pub fn list(&self, limit: Option<usize>) -> Result<Vec<MemoryItem>> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let limit = limit.map(|l| l as i64).unwrap_or(-1);
+
+        let items = conn
+            .prepare(
+                "SELECT id, content, embedding, metadata, created_at FROM memory
+                 ORDER BY created_at DESC LIMIT ?",
+            )?
+            .query_map(params![limit], |row| Self::row_to_item(row, self.cipher.as_ref()))?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("List error: {}", e))?;
+
+        Ok(items)
+    }
+
+    /// 组合查询: FTS5 关键词 + metadata 过滤 + 时间窗口
+    ///
+    /// 有关键词时按相关度排序，否则按创建时间倒序
+    pub fn query(&self, query: &MemoryQuery) -> Result<Vec<MemoryItem>> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        // 空字符串小于任何时间戳，等价于不限时间
+        let since = query
+            .since
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .unwrap_or_default();
+
+        let candidates = match query.text.as_deref().filter(|t| !t.trim().is_empty()) {
+            Some(text) if self.cipher.is_some() => self.scan_encrypted(&conn, text, usize::MAX)?,
+            Some(text) => conn
+                .prepare(
+                    "SELECT memory.id, memory.content, memory.embedding, memory.metadata, memory.created_at
+                     FROM memory_fts
+                     INNER JOIN memory ON memory.rowid = memory_fts.rowid
+                     WHERE memory_fts MATCH ?1 AND memory.created_at >= ?2
+                     ORDER BY rank",
+                )?
+                .query_map(params![text, since], |row| Self::row_to_item(row, None))?
+                .collect::<SqliteResult<Vec<_>>>()
+                .map_err(|e| format!("Query error: {}", e))?,
+            None => conn
+                .prepare(
+                    "SELECT id, content, embedding, metadata, created_at FROM memory
+                     WHERE created_at >= ? ORDER BY created_at DESC",
+                )?
+                .query_map(params![since], |row| Self::row_to_item(row, self.cipher.as_ref()))?
+                .collect::<SqliteResult<Vec<_>>>()
+                .map_err(|e| format!("Query error: {}", e))?,
+        };
+
+        Ok(candidates
+            .into_iter()
+            .filter(|item| query.matches(item))
+            .take(query.limit)
+            .collect())
+    }
+
+    /// 混合检索: FTS5 关键词结果与 embedding 余弦相似度结果按排名融合 (RRF)，
+    /// 再乘以综合得分 (0.5 ~ 1.5 倍) 排序，返回的记忆计入访问次数
+    ///
+    /// 没有关键词时等同于 `query` (按时间倒序)；未启用向量化或加密库只用 FTS5；
+    /// 启用向量化且查询不是合法的 FTS5 语法时 (自然语言问句) 只用语义结果
+    pub async fn hybrid_query(&self, query: &MemoryQuery) -> Result<Vec<MemoryItem>> {
+        let Some(text) = query.text.as_deref().filter(|t| !t.trim().is_empty()) else {
+            return self.query(query);
+        };
+
+        let semantic = match self.active_embedder() {
+            Some(embedder) => self.semantic_search(embedder, query, text).await?,
+            None => Vec::new(),
+        };
+        let candidates = MemoryQuery {
+            limit: usize::MAX,
+            ..query.clone()
+        };
+        let keyword = match self.query(&candidates) {
+            Ok(items) => items,
+            Err(e) if self.active_embedder().is_some() => {
+                tracing::debug!("FTS5 query failed, using semantic results only: {}", e);
+                Vec::new()
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut fused: HashMap<String, (f32, MemoryItem)> = HashMap::new();
+        for ranked in [keyword, semantic] {
+            for (rank, item) in ranked.into_iter().enumerate() {
+                let score = 1.0 / (RRF_K + rank as f32 + 1.0);
+                fused
+                    .entry(item.id.clone())
+                    .and_modify(|(total, _)| *total += score)
+                    .or_insert((score, item));
+            }
+        }
+
+        let stats = self.load_stats()?;
+        let now = Utc::now();
+        let mut ranked: Vec<(f32, MemoryItem)> = fused
+            .into_values()
+            .map(|(relevance, item)| {
+                let score = stats.get(&item.id).map_or(0.5, |s| scoring::score(&self.scoring, s, now));
+                (relevance * (0.5 + score), item)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| b.1.created_at.cmp(&a.1.created_at)));
+        let items: Vec<MemoryItem> = ranked.into_iter().map(|(_, item)| item).take(query.limit).collect();
+
+        self.record_access(&items, now)?;
+        Ok(items)
+    }
+
+    /// 语义检索 (向量化查询失败时返回空，退回关键词检索)
+    async fn semantic_search(
+        &self,
+        embedder: &Arc<dyn EmbeddingProvider>,
+        query: &MemoryQuery,
+        text: &str,
+    ) -> Result<Vec<MemoryItem>> {
+        match embedder.embed(&[text.to_string()]).await.map(|mut v| v.pop()) {
+            Ok(Some(vector)) => self.semantic_candidates(query, &vector, &embedder.model_id()),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => {
+                tracing::warn!("Failed to embed query, using keyword search only: {}", e);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// 所有记忆的评分统计
+    fn load_stats(&self) -> Result<HashMap<String, MemoryStats>> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let parse_time = |raw: &str| DateTime::parse_from_rfc3339(raw).ok().map(|t| t.with_timezone(&Utc));
+        let mut stmt = conn.prepare("SELECT id, importance, access_count, last_accessed, created_at FROM memory")?;
+        let stats = stmt
+            .query_map([], |row| {
+                let stats = MemoryStats {
+                    importance: row.get::<_, Option<f64>>(1)?.map_or(scoring::DEFAULT_IMPORTANCE, |v| v as f32),
+                    access_count: row.get(2)?,
+                    last_accessed: row.get::<_, Option<String>>(3)?.as_deref().and_then(parse_time),
+                    created_at: parse_time(&row.get::<_, String>(4)?).unwrap_or_else(Utc::now),
+                };
+                Ok((row.get(0)?, stats))
+            })?
+            .collect::<SqliteResult<HashMap<_, _>>>()
+            .map_err(|e| format!("Stats error: {}", e))?;
+        Ok(stats)
+    }
+
+    /// 检索命中: 访问次数 +1，刷新最后访问时间
+    fn record_access(&self, items: &[MemoryItem], now: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let now = now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        for item in items {
+            conn.execute(
+                "UPDATE memory SET access_count = access_count + 1, last_accessed = ?2 WHERE id = ?1",
+                params![&item.id, &now],
+            )
+            .map_err(|e| format!("Update error: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 清理综合得分低于阈值的记忆，并在超出 `max_entries` 时删除得分最低的部分；
+    /// `dry_run` 时只返回将被删除的记忆 ID (按得分升序)
+    pub fn prune(&self, now: DateTime<Utc>, dry_run: bool) -> Result<Vec<(String, f32)>> {
+        let mut scored: Vec<(String, f32)> = self
+            .load_stats()?
+            .into_iter()
+            .map(|(id, stats)| {
+                let score = scoring::score(&self.scoring, &stats, now);
+                (id, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let over_limit = self.scoring.max_entries.map_or(0, |max| scored.len().saturating_sub(max));
+        let doomed: Vec<(String, f32)> = scored
+            .into_iter()
+            .enumerate()
+            .take_while(|(i, (_, score))| *i < over_limit || *score < self.scoring.prune_threshold)
+            .map(|(_, entry)| entry)
+            .collect();
+        if dry_run || doomed.is_empty() {
+            return Ok(doomed);
+        }
+
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        for (id, _) in &doomed {
+            conn.execute("DELETE FROM memory WHERE id = ?", params![id])
+                .map_err(|e| format!("Delete error: {}", e))?;
+        }
+        // 回收磁盘空间 (删除行不会缩小数据库文件)
+        conn.execute_batch("VACUUM").map_err(|e| format!("Vacuum error: {}", e))?;
+        Ok(doomed)
+    }
+
+    /// 同一模型生成的向量中，相似度不低于阈值且满足过滤条件的记忆 (按相似度降序)
+    fn semantic_candidates(&self, query: &MemoryQuery, query_vector: &[f32], model: &str) -> Result<Vec<MemoryItem>> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let since = query
+            .since
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .unwrap_or_default();
+
+        let mut scored: Vec<(f32, MemoryItem)> = conn
+            .prepare(
+                "SELECT id, content, embedding, metadata, created_at FROM memory
+                 WHERE embedding_model = ?1 AND created_at >= ?2",
+            )?
+            .query_map(params![model, since], |row| Self::row_to_item(row, None))?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("Vector search error: {}", e))?
+            .into_iter()
+            .filter(|item| query.matches(item))
+            .filter_map(|item| {
+                let similarity = Self::cosine_similarity(query_vector, item.embedding.as_deref()?);
+                (similarity >= self.min_similarity).then_some((similarity, item))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// 为缺少 embedding 或由其他模型生成 embedding 的记忆重新向量化，返回处理数量
+    pub async fn reindex_embeddings(&self) -> Result<usize> {
+        let Some(embedder) = self.active_embedder() else {
+            return Ok(0);
+        };
+        let model = embedder.model_id();
+
+        let pending: Vec<(String, String)> = {
+            let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+            let rows = conn
+                .prepare("SELECT id, content FROM memory WHERE embedding_model IS NULL OR embedding_model != ?")?
+                .query_map(params![&model], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<SqliteResult<Vec<_>>>()
+                .map_err(|e| format!("Scan error: {}", e))?;
+            rows
+        };
+
+        for batch in pending.chunks(EMBED_BATCH) {
+            let texts: Vec<String> = batch.iter().map(|(_, content)| content.clone()).collect();
+            let vectors = embedder.embed(&texts).await?;
+
+            let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+            for ((id, _), vector) in batch.iter().zip(vectors) {
+                conn.execute(
+                    "UPDATE memory SET embedding = ?2, embedding_model = ?3 WHERE id = ?1",
+                    params![id, Self::serialize_embedding(&vector), &model],
+                )
+                .map_err(|e| format!("Update error: {}", e))?;
+            }
+        }
+        Ok(pending.len())
+    }
+
+    /// 删除所有满足条件的记忆（加密库同样适用），返回删除数量喵
+    pub fn purge_where<F>(&self, predicate: F) -> Result<usize>
+    where
+        F: Fn(&MemoryItem) -> bool,
+    {
+        let doomed: Vec<String> = self
+            .list(None)?
+            .into_iter()
+            .filter(|item| predicate(item))
+            .map(|item| item.id)
+            .collect();
+
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        for id in &doomed {
+            conn.execute("DELETE FROM memory WHERE id = ?", params![id])
+                .map_err(|e| format!("Delete error: {}", e))?;
+        }
+        Ok(doomed.len())
+    }
+
+    /// 用当前版本密钥重新加密旧版本密文 (密钥轮换后使用)
+    ///
+    /// 无法解密的记录原样保留并记录日志，返回 (重新加密数量, 失败数量)
+    pub fn reencrypt(&self) -> Result<(usize, usize)> {
+        let Some(cipher) = &self.cipher else {
+            return Ok((0, 0));
+        };
+        let reseal = |stored: Option<String>| -> std::result::Result<Option<String>, CryptoError> {
+            match stored.as_deref().and_then(|s| s.strip_prefix(ENCRYPTED_PREFIX)) {
+                Some(encoded) if !cipher.is_current(encoded) => {
+                    let plain = cipher.decrypt(encoded)?;
+                    Ok(Some(format!("{}{}", ENCRYPTED_PREFIX, cipher.encrypt(&plain)?)))
+                }
+                _ => Ok(None),
+            }
+        };
+
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let rows = conn
+            .prepare("SELECT id, content, metadata FROM memory")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<SqliteResult<Vec<(String, String, Option<String>)>>>()
+            .map_err(|e| format!("Scan error: {}", e))?;
+
+        let mut migrated = 0;
+        let mut failed = 0;
+        for (id, content, metadata) in rows {
+            match (reseal(Some(content)), reseal(metadata)) {
+                (Ok(None), Ok(None)) => {}
+                (Ok(content), Ok(metadata)) => {
+                    conn.execute(
+                        "UPDATE memory SET content = COALESCE(?2, content), metadata = COALESCE(?3, metadata)
+                         WHERE id = ?1",
+                        params![&id, &content, &metadata],
+                    )
+                    .map_err(|e| format!("Update error: {}", e))?;
+                    migrated += 1;
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::warn!("Skipping undecryptable memory {}: {}", id, e);
+                    failed += 1;
+                }
+            }
+        }
+        Ok((migrated, failed))
+    }
+
+    /// 插入或更新记忆 (同步 / 迁移使用)
+    pub fn upsert(&self, item: &MemoryItem) -> Result<()> {
+        let content = self.seal(&item.content)?;
+        let metadata_json = match item.metadata.as_ref().and_then(|v| serde_json::to_string(v).ok()) {
+            Some(json) => Some(self.seal(&json)?),
+            None => None,
+        };
+        let embedding_blob = item.embedding.as_ref().map(|v| Self::serialize_embedding(v));
+
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        // ON CONFLICT DO UPDATE 会触发 memory_au，保持 FTS5 同步
+        conn.execute(
+            "INSERT INTO memory (id, content, embedding, metadata, created_at, importance)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                embedding = excluded.embedding,
+                embedding_model = NULL,
+                metadata = excluded.metadata,
+                created_at = excluded.created_at,
+                importance = excluded.importance",
+            params![
+                &item.id,
+                &content,
+                &embedding_blob,
+                &metadata_json,
+                &item.created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+                scoring::importance_of(item)
+            ],
+        )
+        .map_err(|e| format!("Upsert error: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 按存储原样列出所有记忆 (不解密，同步使用)
+    pub fn list_stored(&self) -> Result<Vec<StoredMemory>> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let items = conn
+            .prepare("SELECT id, content, embedding, metadata, created_at, importance FROM memory ORDER BY id")?
+            .query_map([], |row| {
+                Ok(StoredMemory {
+                    id: row.get(0)?,
+                    content: row.get(1)?,
+                    embedding: row
+                        .get::<_, Option<Vec<u8>>>(2)?
+                        .and_then(|b| Self::parse_embedding(&b)),
+                    metadata: row.get(3)?,
+                    created_at: row.get(4)?,
+                    importance: row.get(5)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()
+            .map_err(|e| format!("List error: {}", e))?;
+        Ok(items)
+    }
+
+    /// 按存储原样写入记忆 (不重新加密，同步使用)
+    pub fn upsert_stored(&self, item: &StoredMemory) -> Result<()> {
+        let embedding_blob = item.embedding.as_ref().map(|v| Self::serialize_embedding(v));
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        // ON CONFLICT DO UPDATE 会触发 memory_au，保持 FTS5 同步
+        conn.execute(
+            "INSERT INTO memory (id, content, embedding, metadata, created_at, importance)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                embedding = excluded.embedding,
+                embedding_model = NULL,
+                metadata = excluded.metadata,
+                created_at = excluded.created_at,
+                importance = excluded.importance",
+            params![
+                &item.id,
+                &item.content,
+                &embedding_blob,
+                &item.metadata,
+                &item.created_at,
+                &item.importance
+            ],
+        )
+        .map_err(|e| format!("Upsert error: {}", e))?;
+        Ok(())
+    }
+
     /// 初始化数据库表
     fn initialize(conn: &Connection, enable_vector: bool) -> SqliteResult<()> {
         // 主记忆表
@@ -59,11 +633,29 @@ impl SqliteMemory {
             [],
         )?;
 
+        // 旧库补列:
+        // - embedding_model: 生成 embedding 的模型 (NULL = 未知，不参与语义检索)
+        // - importance / access_count / last_accessed: 评分统计 (importance NULL = 默认重要度)
+        for (column, definition) in [
+            ("embedding_model", "TEXT"),
+            ("importance", "REAL"),
+            ("access_count", "INTEGER NOT NULL DEFAULT 0"),
+            ("last_accessed", "TEXT"),
+        ] {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('memory') WHERE name = ?")?
+                .exists(params![column])?;
+            if !exists {
+                conn.execute(&format!("ALTER TABLE memory ADD COLUMN {} {}", column, definition), [])?;
+            }
+        }
+
         // FTS5 全文搜索虚拟表
         conn.execute(
             "CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
                 content,
-                content_rowid rowid
+                content='memory',
+                content_rowid='rowid'
             )",
             [],
         )?;
@@ -103,6 +695,12 @@ impl SqliteMemory {
             )?;
         }
 
+        // 比当前程序新的库不降级版本号
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+        }
+
         Ok(())
     }
 
@@ -127,7 +725,7 @@ impl SqliteMemory {
     /// 解析 embedding BLOB 为 Vec<f32>
     fn parse_embedding(blob: &[u8]) -> Option<Vec<f32>> {
         // 简化实现: 假设是 f32 数组的小端序
-        if blob.len() % 4 != 0 {
+        if !blob.len().is_multiple_of(4) {
             return None;
         }
         let len = blob.len() / 4;
@@ -159,9 +757,18 @@ impl Memory for SqliteMemory {
     async fn recall(&self, query: &str, top_k: usize) -> Result<Vec<MemoryItem>> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
 
+        // 0. 加密模式: FTS5 索引的是密文，只能解密扫描
+        if self.cipher.is_some() {
+            return self.scan_encrypted(&conn, query, top_k);
+        }
+
         // 1. 关键词搜索 (FTS5)
         let keyword_results: Vec<String> = conn
-            .prepare("SELECT id FROM memory_fts WHERE memory_fts MATCH ? ORDER BY rank LIMIT ?")?
+            .prepare(
+                "SELECT memory.id FROM memory_fts
+                 INNER JOIN memory ON memory.rowid = memory_fts.rowid
+                 WHERE memory_fts MATCH ? ORDER BY rank LIMIT ?",
+            )?
             .query_map(params![query, top_k], |row| row.get(0))?
             .collect::<SqliteResult<Vec<_>>>()
             .map_err(|e| format!("FTS5 search error: {}", e))?;
@@ -185,21 +792,7 @@ impl Memory for SqliteMemory {
                 .prepare_cached(
                     "SELECT id, content, embedding, metadata, created_at FROM memory WHERE id = ?",
                 )?
-                .query_row(params![id], |row| {
-                    Ok(MemoryItem {
-                        id: row.get(0)?,
-                        content: row.get(1)?,
-                        embedding: row
-                            .get::<_, Option<Vec<u8>>>(2)?
-                            .and_then(|b| Self::parse_embedding(&b)),
-                        metadata: row
-                            .get::<_, Option<String>>(3)?
-                            .and_then(|s| serde_json::from_str(&s).ok()),
-                        created_at: DateTime::parse_from_rfc3339(row.get::<_, String>(4)?.as_str())
-                            .unwrap_or_else(|_| Utc::now().into())
-                            .with_timezone(&Utc),
-                    })
-                });
+                .query_row(params![id], |row| Self::row_to_item(row, None));
 
             if let Ok(item) = item {
                 items.push(item);
@@ -209,7 +802,19 @@ impl Memory for SqliteMemory {
         Ok(items)
     }
 
-    async fn save(&self, item: MemoryItem) -> Result<String> {
+    async fn save(&self, mut item: MemoryItem) -> Result<String> {
+        // 自动向量化 (调用方已提供 embedding 时模型未知)；失败不影响保存，之后可重建
+        let mut embedding_model = None;
+        if let Some(embedder) = self.active_embedder().filter(|_| item.embedding.is_none()) {
+            match embedder.embed(&[item.content.clone()]).await {
+                Ok(mut vectors) => {
+                    item.embedding = vectors.pop();
+                    embedding_model = item.embedding.as_ref().map(|_| embedder.model_id());
+                }
+                Err(e) => tracing::warn!("Failed to embed memory {}: {}", item.id, e),
+            }
+        }
+
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
 
         // 序列化 embedding
@@ -219,20 +824,23 @@ impl Memory for SqliteMemory {
             .map(|v| Self::serialize_embedding(v));
 
         // 序列化 metadata
-        let metadata_json = item
-            .metadata
-            .as_ref()
-            .map(|v| serde_json::to_string(v).ok());
+        let metadata_json = match item.metadata.as_ref().and_then(|v| serde_json::to_string(v).ok()) {
+            Some(json) => Some(self.seal(&json)?),
+            None => None,
+        };
+        let content = self.seal(&item.content)?;
 
         conn.execute(
-            "INSERT INTO memory (id, content, embedding, metadata, created_at) 
-             VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO memory (id, content, embedding, metadata, created_at, embedding_model, importance) 
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
             params![
                 &item.id,
-                &item.content,
+                &content,
                 &embedding_blob,
                 &metadata_json,
-                &item.created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+                &item.created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+                &embedding_model,
+                scoring::importance_of(&item)
             ],
         )
         .map_err(|e| format!("Insert error: {}", e))?;
@@ -263,30 +871,192 @@ impl Memory for SqliteMemory {
     async fn search(&self, query: &str) -> Result<Vec<MemoryItem>> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
 
+        if self.cipher.is_some() {
+            return self.scan_encrypted(&conn, query, usize::MAX);
+        }
+
         let rows = conn
             .prepare(
-                "SELECT id, content, embedding, metadata, created_at FROM memory_fts
+                "SELECT memory.id, memory.content, memory.embedding, memory.metadata, memory.created_at
+             FROM memory_fts
              INNER JOIN memory ON memory.rowid = memory_fts.rowid
              WHERE memory_fts MATCH ?",
             )?
-            .query_map(params![query], |row| {
-                Ok(MemoryItem {
-                    id: row.get(0)?,
-                    content: row.get(1)?,
-                    embedding: row
-                        .get::<_, Option<Vec<u8>>>(2)?
-                        .and_then(|b| Self::parse_embedding(&b)),
-                    metadata: row
-                        .get::<_, Option<String>>(3)?
-                        .and_then(|s| serde_json::from_str(&s).ok()),
-                    created_at: DateTime::parse_from_rfc3339(row.get::<_, String>(4)?.as_str())
-                        .unwrap_or_else(|_| Utc::now().into())
-                        .with_timezone(&Utc),
-                })
-            })?
+            .query_map(params![query], |row| Self::row_to_item(row, None))?
             .collect::<SqliteResult<Vec<_>>>()
             .map_err(|e| format!("Search error: {}", e))?;
 
         Ok(rows)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::generate_key;
+    use base64::{engine::general_purpose::STANDARD as BASE64_STD, Engine as _};
+
+    fn test_cipher() -> CryptoService {
+        CryptoService::new(&BASE64_STD.decode(generate_key()).unwrap()).unwrap()
+    }
+
+    fn item(id: &str, content: &str) -> MemoryItem {
+        MemoryItem {
+            id: id.to_string(),
+            content: content.to_string(),
+            embedding: None,
+            metadata: Some(serde_json::json!({"source": "test"})),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_roundtrip() {
+        let memory = SqliteMemory::new(":memory:").unwrap().with_encryption(test_cipher());
+        memory.save(item("m1", "nginx timeout on port 443")).await.unwrap();
+        memory.save(item("m2", "cat food schedule")).await.unwrap();
+
+        // 数据库里只存密文喵
+        let raw: String = memory
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT content FROM memory WHERE id = 'm1'", [], |r| r.get(0))
+            .unwrap();
+        assert!(raw.starts_with(ENCRYPTED_PREFIX));
+        assert!(!raw.contains("nginx"));
+
+        let found = memory.recall("NGINX timeout", 5).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].content, "nginx timeout on port 443");
+        assert_eq!(found[0].metadata, Some(serde_json::json!({"source": "test"})));
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_after_rotation() {
+        let old_key = BASE64_STD.decode(generate_key()).unwrap();
+        let mut memory = SqliteMemory::new(":memory:")
+            .unwrap()
+            .with_encryption(CryptoService::new(&old_key).unwrap());
+        memory.save(item("m1", "nginx timeout")).await.unwrap();
+
+        let keys = std::collections::BTreeMap::from([(0, old_key), (1, BASE64_STD.decode(generate_key()).unwrap())]);
+        memory.cipher = Some(CryptoService::versioned(&keys, 1).unwrap());
+        assert_eq!(memory.reencrypt().unwrap(), (1, 0));
+        assert_eq!(memory.reencrypt().unwrap(), (0, 0));
+
+        // 只保留新密钥也能读取喵
+        let current = std::collections::BTreeMap::from([(1, keys[&1].clone())]);
+        memory.cipher = Some(CryptoService::versioned(&current, 1).unwrap());
+        let items = memory.list(None).unwrap();
+        assert_eq!(items[0].content, "nginx timeout");
+        assert_eq!(items[0].metadata, Some(serde_json::json!({"source": "test"})));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_updates_fts() {
+        let memory = SqliteMemory::new(":memory:").unwrap();
+        memory.upsert(&item("m1", "old words")).unwrap();
+        memory.upsert(&item("m1", "fresh words")).unwrap();
+
+        assert_eq!(memory.list(None).unwrap().len(), 1);
+        assert!(memory.search("old").await.unwrap().is_empty());
+        assert_eq!(memory.search("fresh").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_search_unchanged() {
+        let memory = SqliteMemory::new(":memory:").unwrap();
+        memory.save(item("m1", "nginx timeout")).await.unwrap();
+
+        let found = memory.search("nginx").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(!memory.is_encrypted());
+    }
+
+    #[tokio::test]
+    async fn test_query_combines_fts_and_metadata() {
+        let memory = SqliteMemory::new(":memory:").unwrap();
+        let tagged = |id: &str, content: &str, agent: &str, age_days: i64| {
+            let mut entry = item(id, content);
+            entry.metadata = Some(serde_json::json!({"agent": agent, "key": "chat"}));
+            entry.created_at = Utc::now() - chrono::Duration::days(age_days);
+            entry
+        };
+        memory.save(tagged("m1", "nginx timeout", "nia", 1)).await.unwrap();
+        memory.save(tagged("m2", "nginx reload", "mochi", 2)).await.unwrap();
+        memory.save(tagged("m3", "nginx config", "nia", 30)).await.unwrap();
+
+        let query = MemoryQuery::new(10).with_text("nginx").with_agent("nia");
+        assert_eq!(memory.query(&query).unwrap().len(), 2);
+
+        let recent = query.with_since(Utc::now() - chrono::Duration::days(7));
+        let found = memory.query(&recent).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "m1");
+
+        let listed = memory.query(&MemoryQuery::new(2).with_filter("key", "chat")).unwrap();
+        assert_eq!(listed.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["m1", "m2"]);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_query_blends_semantic_results() {
+        let embedder: Arc<dyn EmbeddingProvider> = Arc::new(crate::memory::HashEmbedding::new(256));
+        let memory = SqliteMemory::new(":memory:").unwrap().with_embedder(embedder.clone(), 0.3);
+        memory.save(item("m1", "nginx timeout on port 443")).await.unwrap();
+        memory.save(item("m2", "cat food schedule")).await.unwrap();
+        memory.upsert(&item("m3", "nginx reload failed")).unwrap();
+
+        // 问句不是合法的 FTS5 语法，只靠语义结果喵
+        let found = memory.hybrid_query(&MemoryQuery::new(5).with_text("why nginx timeout?")).await.unwrap();
+        assert_eq!(found.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["m1"]);
+
+        // 补齐 upsert 写入的记忆后，关键词与语义结果融合喵
+        assert_eq!(memory.reindex_embeddings().await.unwrap(), 1);
+        assert_eq!(memory.reindex_embeddings().await.unwrap(), 0);
+        let found = memory.hybrid_query(&MemoryQuery::new(5).with_text("nginx")).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|i| i.content.contains("nginx")));
+
+        let filtered = MemoryQuery::new(5).with_text("nginx timeout").with_filter("source", "other");
+        assert!(memory.hybrid_query(&filtered).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scoring_ranks_and_prunes() {
+        let memory = SqliteMemory::new(":memory:").unwrap().with_scoring(MemoryScoringConfig {
+            max_entries: Some(2),
+            ..MemoryScoringConfig::default()
+        });
+        let mut important = item("m1", "nginx timeout");
+        important.metadata = Some(serde_json::json!({"importance": 1.0}));
+        let mut stale = item("m2", "nginx timeout");
+        stale.created_at = Utc::now() - chrono::Duration::days(200);
+        memory.save(stale).await.unwrap();
+        memory.save(important).await.unwrap();
+        memory.save(item("m3", "cat food")).await.unwrap();
+        memory.save(item("m4", "cat toys")).await.unwrap();
+
+        // 同样相关时重要度高的排在前面，命中计入访问次数喵
+        let found = memory.hybrid_query(&MemoryQuery::new(5).with_text("nginx")).await.unwrap();
+        assert_eq!(found.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), ["m1", "m2"]);
+        let stats = memory.load_stats().unwrap();
+        assert_eq!(stats["m1"].access_count, 1);
+        assert!(stats["m1"].last_accessed.is_some());
+        assert_eq!(stats["m3"].access_count, 0);
+
+        // 超出 max_entries 的部分按得分删除；被检索过的记忆有访问分，不会跌破阈值喵
+        let preview = memory.prune(Utc::now(), true).unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(memory.list(None).unwrap().len(), 4);
+        let mut pruned: Vec<String> = memory
+            .prune(Utc::now() + chrono::Duration::days(365), false)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        pruned.sort();
+        assert_eq!(pruned, ["m3", "m4"]);
+        assert_eq!(memory.list(None).unwrap().len(), 2);
+    }
+}
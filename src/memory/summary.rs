@@ -0,0 +1,227 @@
+/*!
+ * Conversation Summary - 对话摘要记忆
+ *
+ * 功能:
+ * - 交互对话的历史超过阈值时，先把要裁剪的旧消息交给模型摘要
+ * - 摘要存为记忆 (metadata `source=conversation_summary`、`session=<会话 ID>`)
+ * - 之后每轮按用户输入召回本会话的摘要，作为系统消息放进请求
+ *
+ * 摘要失败时不裁剪历史 (超出上下文时仍由 Provider 的压缩重试兜底)
+ */
+
+use super::query::MemoryQuery;
+use super::sqlite::SqliteMemory;
+use crate::core::traits::{ConversationSummaryConfig, Memory, MemoryItem, Result};
+use crate::providers::context::{count_tokens, eviction_point};
+use crate::providers::{ChatProvider, ChatRequest, Message};
+use chrono::Utc;
+use serde_json::json;
+use std::sync::Arc;
+
+/// 摘要记忆的 `source` 标记
+pub const SUMMARY_SOURCE: &str = "conversation_summary";
+
+/// 每条消息参与摘要的最大字符数
+const MESSAGE_CHARS: usize = 2_000;
+
+/// 摘要回复的 token 上限
+const SUMMARY_MAX_TOKENS: u32 = 400;
+
+/// 对话摘要器
+pub struct ConversationSummarizer {
+    client: Arc<dyn ChatProvider>,
+    model: String,
+    memory: Arc<SqliteMemory>,
+    config: ConversationSummaryConfig,
+}
+
+impl ConversationSummarizer {
+    /// `model` 为对话模型，配置了 `model` 时以配置为准
+    pub fn new(
+        client: Arc<dyn ChatProvider>,
+        model: &str,
+        memory: Arc<SqliteMemory>,
+        config: ConversationSummaryConfig,
+    ) -> Self {
+        Self {
+            client,
+            model: config.model.clone().unwrap_or_else(|| model.to_string()),
+            memory,
+            config,
+        }
+    }
+
+    /// 历史超过阈值时摘要并裁剪 `history[prefix_len..]` 中较早的消息
+    ///
+    /// `prefix_len` 之前 (系统提示与示例) 不参与；返回移除的消息数 (0 = 未裁剪)
+    pub async fn compact(&self, session_id: &str, history: &mut Vec<Message>, prefix_len: usize) -> Result<usize> {
+        let conversation = &history[prefix_len..];
+        if count_tokens(conversation) <= self.config.trigger_tokens {
+            return Ok(0);
+        }
+        let evicted = eviction_point(conversation, self.config.keep_recent);
+        if evicted == 0 {
+            return Ok(0);
+        }
+
+        let summary = self.summarize(&conversation[..evicted]).await?;
+        self.memory.save(summary_item(session_id, &summary, evicted)).await?;
+        history.drain(prefix_len..prefix_len + evicted);
+        Ok(evicted)
+    }
+
+    /// 请求模型摘要一段对话
+    async fn summarize(&self, messages: &[Message]) -> Result<String> {
+        let request = ChatRequest {
+            model: Some(self.model.clone()),
+            messages: summary_request_messages(messages),
+            temperature: Some(0.2),
+            top_p: None,
+            max_tokens: Some(SUMMARY_MAX_TOKENS),
+            stream: Some(false),
+            tools: None,
+        };
+        let response = self.client.chat(&request).await?;
+        let summary = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content.trim().to_string())
+            .unwrap_or_default();
+        if summary.is_empty() {
+            return Err("Empty summary response".into());
+        }
+        Ok(summary)
+    }
+
+    /// 召回本会话的摘要 (按用户输入检索，没有命中时取最近的摘要)
+    pub async fn recall(&self, session_id: &str, input: &str) -> Result<Vec<MemoryItem>> {
+        let query = MemoryQuery::new(self.config.recall_limit)
+            .with_filter("source", SUMMARY_SOURCE)
+            .with_filter("session", session_id);
+        let relevant = match self.memory.hybrid_query(&query.clone().with_text(input)).await {
+            Ok(items) => items,
+            Err(e) => {
+                tracing::debug!("Summary search failed, using recent summaries: {}", e);
+                Vec::new()
+            }
+        };
+        match relevant.is_empty() {
+            true => self.memory.query(&query),
+            false => Ok(relevant),
+        }
+    }
+}
+
+/// 构造摘要请求消息 (只取 user / assistant 消息)
+pub fn summary_request_messages(messages: &[Message]) -> Vec<Message> {
+    let transcript: Vec<String> = messages
+        .iter()
+        .filter(|m| (m.role == "user" || m.role == "assistant") && !m.content.trim().is_empty())
+        .map(|m| format!("{}: {}", m.role, m.content.chars().take(MESSAGE_CHARS).collect::<String>()))
+        .collect();
+
+    vec![
+        Message::system(
+            "You condense the earlier part of a chat so it can be recalled later. \
+             Summarize the transcript in at most 8 short bullet points, in the conversation's language. \
+             Keep facts, decisions, names, file paths, numbers and open tasks; drop greetings and filler. \
+             Reply with the bullet points only."
+                .to_string(),
+        ),
+        Message::user(transcript.join("\n")),
+    ]
+}
+
+/// 摘要记忆条目
+pub fn summary_item(session_id: &str, summary: &str, message_count: usize) -> MemoryItem {
+    MemoryItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        content: summary.to_string(),
+        embedding: None,
+        metadata: Some(json!({
+            "source": SUMMARY_SOURCE,
+            "session": session_id,
+            "messages": message_count,
+        })),
+        created_at: Utc::now(),
+    }
+}
+
+/// 召回的摘要合并为一条系统消息 (没有摘要时为 None)
+pub fn recall_message(items: &[MemoryItem]) -> Option<Message> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut items: Vec<&MemoryItem> = items.iter().collect();
+    items.sort_by_key(|item| item.created_at);
+    let summaries: Vec<&str> = items.iter().map(|item| item.content.as_str()).collect();
+    Some(Message::system(format!(
+        "Summaries of earlier parts of this conversation (older messages are no longer in context):\n\n{}",
+        summaries.join("\n\n")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ChatResponse, ProviderError};
+    use async_trait::async_trait;
+
+    #[derive(Debug)]
+    struct StubProvider;
+
+    #[async_trait]
+    impl ChatProvider for StubProvider {
+        async fn chat(&self, request: &ChatRequest) -> std::result::Result<ChatResponse, ProviderError> {
+            assert_eq!(request.model.as_deref(), Some("cheap"));
+            assert!(request.messages[1].content.contains("user: nginx listens on port 8443"));
+            Ok(serde_json::from_value(json!({
+                "id": "r",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "cheap",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "- nginx listens on port 8443" },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 }
+            }))
+            .unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_stores_summary_and_recalls() {
+        let memory = Arc::new(SqliteMemory::new(":memory:").unwrap());
+        let config = ConversationSummaryConfig {
+            model: Some("cheap".to_string()),
+            trigger_tokens: 50,
+            keep_recent: 2,
+            ..ConversationSummaryConfig::default()
+        };
+        let summarizer = ConversationSummarizer::new(Arc::new(StubProvider), "main", memory.clone(), config);
+
+        let mut history = vec![Message::system("s".to_string()), Message::user("nginx listens on port 8443".to_string())];
+        history.push(Message::assistant("x".repeat(400)));
+        history.extend([Message::user("next".to_string()), Message::assistant("ok".to_string())]);
+
+        assert_eq!(summarizer.compact("s1", &mut history.clone(), 1).await.unwrap(), 2);
+        let mut short = history[..2].to_vec();
+        assert_eq!(summarizer.compact("s1", &mut short, 1).await.unwrap(), 0);
+
+        summarizer.compact("s1", &mut history, 1).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[1].content, "next");
+
+        let recalled = summarizer.recall("s1", "nginx port").await.unwrap();
+        assert_eq!(recalled.len(), 2);
+        assert!(summarizer.recall("other", "nginx").await.unwrap().is_empty());
+        let message = recall_message(&recalled).unwrap();
+        assert_eq!(message.role, "system");
+        assert!(message.content.contains("- nginx listens on port 8443"));
+        // 无关的输入仍会召回最近的摘要喵
+        assert_eq!(summarizer.recall("s1", "weather").await.unwrap().len(), 2);
+    }
+}
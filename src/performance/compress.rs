@@ -8,17 +8,55 @@
 /// - Token 预算管理
 ///
 /// 🔒 SAFETY: 压缩后必须保持上下文连贯性
+/// - 系统消息与当前轮次（最后一条用户消息之后）总是保留
+/// - tool 结果与发起调用的消息作为一个整体保留或丢弃
 ///
 /// 实现者: 诺诺 (Nono) ⚡
-
+use crate::telemetry::MetricsRecorder;
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
-use uuid::Uuid;
+use std::cmp::{Ordering, Reverse};
+use tracing::{info, warn};
+
+/// 上下文压缩丢弃的消息数（遥测计数器）喵
+pub const CONTEXT_COMPRESSED_METRIC: &str = "context_compressed_messages";
+/// 上下文压缩后保留的 token 百分比（遥测）喵
+pub const CONTEXT_COMPRESSION_RATIO_METRIC: &str = "context_compression_ratio";
+
+/// 🔒 SAFETY: 可压缩的消息喵（Agent 历史 / Provider 请求消息）
+pub trait ContextMessage {
+    /// 角色（system/user/assistant/tool）
+    fn role(&self) -> &str;
+    /// 内容
+    fn content(&self) -> &str;
+    /// 创建时间（Unix 秒；None 时按消息顺序）
+    fn timestamp(&self) -> Option<i64> {
+        None
+    }
+}
+
+impl ContextMessage for crate::providers::Message {
+    fn role(&self) -> &str {
+        &self.role
+    }
 
-use crate::agent::AgentMessage;
+    fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl ContextMessage for crate::core::traits::Message {
+    fn role(&self) -> &str {
+        &self.role
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+}
 
 /// 🔒 SAFETY: 压缩策略枚举喵
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CompressionStrategy {
     /// 基于优先级压缩
     PriorityBased,
@@ -28,26 +66,88 @@ pub enum CompressionStrategy {
     Hybrid,
 }
 
+impl CompressionStrategy {
+    /// 🔒 SAFETY: 策略名称喵（配置与遥测标签）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionStrategy::PriorityBased => "priority_based",
+            CompressionStrategy::TimeBased => "time_based",
+            CompressionStrategy::Hybrid => "hybrid",
+        }
+    }
+}
+
+fn default_compression_enabled() -> bool {
+    true
+}
+
+fn default_compression_threshold() -> u32 {
+    6000
+}
+
+fn default_compression_strategy() -> CompressionStrategy {
+    CompressionStrategy::PriorityBased
+}
+
+/// 🔒 SAFETY: Agent 上下文压缩配置喵
+///
+/// 请求消息估算超过 `compression_threshold` 个 token 时按策略挑选保留的消息
+///
+/// ```toml
+/// [compression]
+/// compression_threshold = 6000
+/// strategy = "hybrid"   # priority_based / time_based / hybrid
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    #[serde(default = "default_compression_enabled")]
+    pub enabled: bool,
+    /// 压缩阈值（token 数，超过自动压缩）
+    #[serde(default = "default_compression_threshold")]
+    pub compression_threshold: u32,
+    /// 压缩策略
+    #[serde(default = "default_compression_strategy")]
+    pub strategy: CompressionStrategy,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compression_enabled(),
+            compression_threshold: default_compression_threshold(),
+            strategy: default_compression_strategy(),
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// 🔒 SAFETY: 按配置创建压缩器喵（未启用时为 None）
+    pub fn compressor(&self) -> Option<ContextCompressor> {
+        self.enabled
+            .then(|| ContextCompressor::new(self.strategy, self.compression_threshold))
+    }
+}
+
 /// 🔒 SAFETY: 消息重要性评分喵
 #[derive(Debug, Clone, Serialize)]
 pub struct MessageScore {
-    /// 消息 ID
-    pub message_id: String,
+    /// 消息在上下文中的位置
+    pub index: usize,
     /// 重要性分数（0-100）
     pub importance: f32,
     /// Token 数
     pub token_count: u32,
-    /// 创建时间戳
+    /// 创建时间戳（没有时间的消息为位置）
     pub timestamp: i64,
 }
 
 impl MessageScore {
     /// 🔒 SAFETY: 计算消息重要性喵
-    pub fn calculate(message: &AgentMessage) -> Self {
-        let mut importance = 50.0; // 基础分数
+    pub fn calculate<M: ContextMessage>(index: usize, message: &M) -> Self {
+        let mut importance: f32 = 50.0; // 基础分数
 
         // 根据角色调整重要性
-        match message.role.as_str() {
+        match message.role() {
             "system" => importance += 40.0, // 系统提示很重要
             "assistant" => importance += 10.0,
             "user" => importance += 5.0,
@@ -55,7 +155,7 @@ impl MessageScore {
         }
 
         // 根据消息长度调整（消息越长，可能越重要）
-        let length = message.content.chars().count() as f32;
+        let length = message.content().chars().count() as f32;
         if length > 100.0 {
             importance += 5.0;
         } else if length < 20.0 {
@@ -63,22 +163,16 @@ impl MessageScore {
         }
 
         // 限制在 0-100 之间
-        importance = importance.max(0.0).min(100.0);
+        importance = importance.clamp(0.0, 100.0);
 
         // 计算 token 数
-        let token_count = estimate_tokens(&message.content);
-
-        // 计算时间戳
-        let timestamp = message.timestamp
-            .parse::<chrono::DateTime<chrono::Utc>>()
-            .map(|dt| dt.timestamp())
-            .unwrap_or(0);
+        let token_count = estimate_tokens(message.content());
 
         Self {
-            message_id: message.message_id.clone(),
+            index,
             importance,
             token_count,
-            timestamp,
+            timestamp: message.timestamp().unwrap_or(index as i64),
         }
     }
 }
@@ -89,11 +183,11 @@ pub struct MessageRanker;
 impl MessageRanker {
     /// 🔒 SAFETY: 对消息进行排序喵
     /// 返回排序后的消息索引列表（从高到低）
-    pub fn rank_messages(messages: &[AgentMessage], strategy: CompressionStrategy) -> Vec<usize> {
+    pub fn rank_messages<M: ContextMessage>(messages: &[M], strategy: CompressionStrategy) -> Vec<usize> {
         let mut scores: Vec<(usize, MessageScore)> = messages
             .iter()
             .enumerate()
-            .map(|(idx, msg)| (idx, MessageScore::calculate(msg)))
+            .map(|(idx, msg)| (idx, MessageScore::calculate(idx, msg)))
             .collect();
 
         match strategy {
@@ -103,17 +197,19 @@ impl MessageRanker {
             }
             CompressionStrategy::TimeBased => {
                 // 按时间降序（最新的在前）
-                scores.sort_by(|a, b| b.1.timestamp.cmp(&a.1.timestamp));
+                scores.sort_by_key(|(idx, score)| Reverse((score.timestamp, *idx)));
             }
             CompressionStrategy::Hybrid => {
-                // 混合策略：重要性 + 时间（最近的同重要性提升）
-                let now = chrono::Utc::now().timestamp();
-                scores.sort_by(|a, b| {
-                    // 计算时间衰减因子
-                    let score_a = a.1.importance + ((now - a.1.timestamp) as f32 / 86400.0 * 10.0).max(-20.0);
-                    let score_b = b.1.importance + ((now - b.1.timestamp) as f32 / 86400.0 * 10.0).max(-20.0);
-                    score_b.partial_cmp(&score_a).unwrap_or(Ordering::Equal)
-                });
+                // 混合策略：重要性 - 时间衰减（越旧扣分越多，最旧的扣 20 分）
+                let mut by_age: Vec<usize> = (0..scores.len()).collect();
+                by_age.sort_by_key(|idx| Reverse((scores[*idx].1.timestamp, *idx)));
+                let mut age_rank = vec![0usize; scores.len()];
+                for (rank, idx) in by_age.into_iter().enumerate() {
+                    age_rank[idx] = rank;
+                }
+                let span = scores.len().max(1) as f32;
+                let hybrid = |(idx, score): &(usize, MessageScore)| score.importance - 20.0 * age_rank[*idx] as f32 / span;
+                scores.sort_by(|a, b| hybrid(b).partial_cmp(&hybrid(a)).unwrap_or(Ordering::Equal));
             }
         }
 
@@ -122,6 +218,7 @@ impl MessageRanker {
 }
 
 /// 🔒 SAFETY: 上下文压缩器喵
+#[derive(Debug)]
 pub struct ContextCompressor {
     /// 压缩策略
     strategy: CompressionStrategy,
@@ -143,9 +240,9 @@ impl ContextCompressor {
 
     /// 🔒 SAFETY: 压缩上下文喵
     /// 返回压缩后的消息列表和统计信息
-    pub fn compress(&self, context: &mut Vec<AgentMessage>) -> Result<CompressionStats, String> {
+    pub fn compress<M: ContextMessage + Clone>(&mut self, context: &mut Vec<M>) -> Result<CompressionStats, String> {
         let initial_count = context.len();
-        let initial_tokens = context.iter().map(|m| estimate_tokens(&m.content)).sum::<u32>();
+        let initial_tokens = context.iter().map(|m| estimate_tokens(m.content())).sum::<u32>();
 
         // 如果没有超过阈值，不压缩
         if initial_tokens <= self.threshold {
@@ -161,48 +258,45 @@ impl ContextCompressor {
             return Ok(stats);
         }
 
-        // 排序消息
-        let ranked = MessageRanker::rank_messages(context, self.strategy);
+        // tool 结果归属于前一条消息（发起调用的助手消息）
+        let units = message_units(context);
+        let unit_tokens = |unit: &std::ops::Range<usize>| -> u32 {
+            context[unit.clone()].iter().map(|m| estimate_tokens(m.content())).sum()
+        };
 
-        // 按排序顺序选择消息，直到达到阈值
-        let mut selected_indices = Vec::new();
+        // 系统消息与当前轮次总是保留
+        let current_turn = context.iter().rposition(|m| m.role() == "user").unwrap_or(initial_count);
+        let mut selected = vec![false; units.len()];
         let mut current_tokens = 0u32;
-
-        // 系统消息总是保留
-        let system_indices: Vec<_> = context
-            .iter()
-            .enumerate()
-            .filter(|(_, msg)| msg.role == "system")
-            .map(|(idx, _)| idx)
-            .collect();
-
-        for idx in &system_indices {
-            if !selected_indices.contains(idx) {
-                selected_indices.push(*idx);
-                current_tokens += estimate_tokens(&context[*idx].content);
+        for (i, unit) in units.iter().enumerate() {
+            if context[unit.start].role() == "system" || unit.start >= current_turn {
+                selected[i] = true;
+                current_tokens += unit_tokens(unit);
             }
         }
 
-        // 添加其他重要消息
-        for idx in ranked {
-            if selected_indices.contains(&idx) {
+        // 按排序顺序选择其他消息，直到达到阈值
+        let heads: Vec<M> = units.iter().map(|unit| context[unit.start].clone()).collect();
+        for i in MessageRanker::rank_messages(&heads, self.strategy) {
+            if selected[i] {
                 continue;
             }
 
-            let tokens = estimate_tokens(&context[idx].content);
+            let tokens = unit_tokens(&units[i]);
             if current_tokens + tokens > self.threshold {
                 break; // 预算已满
             }
 
-            selected_indices.push(idx);
+            selected[i] = true;
             current_tokens += tokens;
         }
 
         // 按原始顺序重组消息
-        selected_indices.sort();
-        let compressed: Vec<_> = selected_indices
-            .into_iter()
-            .map(|idx| context[idx].clone())
+        let compressed: Vec<M> = units
+            .iter()
+            .zip(&selected)
+            .filter(|(_, keep)| **keep)
+            .flat_map(|(unit, _)| context[unit.clone()].iter().cloned())
             .collect();
 
         let final_count = compressed.len();
@@ -232,6 +326,53 @@ impl ContextCompressor {
     pub fn last_stats(&self) -> &Option<CompressionStats> {
         &self.last_stats
     }
+
+    /// 🔒 SAFETY: 压缩请求消息，发生压缩时把统计写入遥测喵（CLI 与 Gateway 共用）
+    ///
+    /// 压缩失败时保留完整消息
+    pub fn compress_and_record<M: ContextMessage + Clone>(
+        &mut self,
+        context: &mut Vec<M>,
+        recorder: Option<&MetricsRecorder>,
+    ) {
+        match self.compress(context) {
+            Ok(stats) if stats.final_count < stats.initial_count => {
+                info!(
+                    target: "telemetry",
+                    event = "context_compressed",
+                    strategy = stats.strategy.as_str(),
+                    initial_count = stats.initial_count,
+                    final_count = stats.final_count,
+                    initial_tokens = stats.initial_tokens,
+                    final_tokens = stats.final_tokens,
+                    "Compressed agent context"
+                );
+                if let Some(recorder) = recorder {
+                    let labels = [("strategy", stats.strategy.as_str())];
+                    recorder.counter(
+                        CONTEXT_COMPRESSED_METRIC,
+                        (stats.initial_count - stats.final_count) as f64,
+                        &labels,
+                    );
+                    recorder.gauge(CONTEXT_COMPRESSION_RATIO_METRIC, stats.compression_ratio, &labels);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("上下文压缩失败，发送完整历史喵: {}", e),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 把消息切分为不可拆分的单元喵（消息 + 紧随其后的 tool 结果）
+fn message_units<M: ContextMessage>(context: &[M]) -> Vec<std::ops::Range<usize>> {
+    let mut units: Vec<std::ops::Range<usize>> = Vec::new();
+    for (idx, message) in context.iter().enumerate() {
+        match units.last_mut() {
+            Some(unit) if message.role() == "tool" => unit.end = idx + 1,
+            _ => units.push(idx..idx + 1),
+        }
+    }
+    units
 }
 
 /// 🔒 SAFETY: 压缩统计信息结构体喵
@@ -251,24 +392,15 @@ pub struct CompressionStats {
     pub strategy: CompressionStrategy,
 }
 
-/// 🔒 SAFETY: 估计 token 数量喵
+/// 🔒 SAFETY: 计算 token 数量喵（进程级计数器：BPE 词表或校准后的估算）
 fn estimate_tokens(text: &str) -> u32 {
-    // 简单估算策略：
-    // 英文约 4 字符/token
-    // 中文约 2 字符/token
-    let chars = text.chars().count();
-    let cjk_chars = text.chars().filter(|c| *c as u32 > 0x7F).count();
-    let non_cjk = chars - cjk_chars;
-
-    let cjk_tokens = (cjk_chars + 1) / 2;
-    let non_cjk_tokens = (non_cjk + 3) / 4;
-
-    (cjk_tokens + non_cjk_tokens) as u32
+    crate::providers::tokenizer::count_tokens(text) as u32
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::Message;
 
     #[test]
     fn test_token_estimation() {
@@ -283,10 +415,11 @@ mod tests {
 
     #[test]
     fn test_message_score() {
-        let msg = AgentMessage::user("Test message".to_string());
-        let score = MessageScore::calculate(&msg);
+        let msg = Message::user("Test message".to_string());
+        let score = MessageScore::calculate(3, &msg);
         assert!(score.importance > 0.0);
-        assert!(!score.message_id.is_empty());
+        assert_eq!(score.index, 3);
+        assert_eq!(score.timestamp, 3);
     }
 
     #[test]
@@ -297,10 +430,10 @@ mod tests {
 
     #[test]
     fn test_compress_no_compression_needed() {
-        let compressor = ContextCompressor::new(CompressionStrategy::PriorityBased, 10000);
+        let mut compressor = ContextCompressor::new(CompressionStrategy::PriorityBased, 10000);
         let mut context = vec![
-            AgentMessage::system("System prompt".to_string()),
-            AgentMessage::user("Hello".to_string()),
+            Message::system("System prompt".to_string()),
+            Message::user("Hello".to_string()),
         ];
 
         let stats = compressor.compress(&mut context).unwrap();
@@ -309,14 +442,44 @@ mod tests {
 
     #[test]
     fn test_compress_with_compression() {
-        let compressor = ContextCompressor::new(CompressionStrategy::PriorityBased, 10);
+        let mut compressor = ContextCompressor::new(CompressionStrategy::PriorityBased, 10);
         let mut context = vec![
-            AgentMessage::system("A".repeat(100)),
-            AgentMessage::user("B".repeat(100)),
-            AgentMessage::assistant("C".repeat(100)),
+            Message::system("A".repeat(100)),
+            Message::user("B".repeat(100)),
+            Message::assistant("C".repeat(100)),
+            Message::user("D".to_string()),
         ];
 
         let stats = compressor.compress(&mut context).unwrap();
         assert!(stats.final_count < stats.initial_count);
+        assert_eq!(context.first().unwrap().role, "system");
+        assert_eq!(context.last().unwrap().content, "D");
+        assert!(compressor.last_stats().is_some());
+    }
+
+    #[test]
+    fn test_compress_keeps_tool_results_with_call() {
+        let mut context = vec![Message::system("s".to_string())];
+        for strategy in [CompressionStrategy::TimeBased, CompressionStrategy::Hybrid] {
+            context.truncate(1);
+            context.extend([
+                Message::user("read the log".to_string()),
+                Message::assistant("E".repeat(40)),
+                Message::tool("call_1".to_string(), "F".repeat(400)),
+                Message::assistant("the log is long".to_string()),
+                Message::user("and now?".to_string()),
+            ]);
+            let mut compressor = ContextCompressor::new(strategy, 40);
+            compressor.compress(&mut context).unwrap();
+
+            // 调用与结果一起丢弃，当前轮次总是保留喵
+            let roles: Vec<&str> = context.iter().map(|m| m.role.as_str()).collect();
+            assert!(!roles.contains(&"tool"), "{}", strategy.as_str());
+            assert!(context.iter().all(|m| !m.content.starts_with('E')));
+            assert_eq!(context.last().unwrap().content, "and now?");
+            if strategy == CompressionStrategy::TimeBased {
+                assert_eq!(roles, ["system", "assistant", "user"]);
+            }
+        }
     }
 }
@@ -16,7 +16,7 @@ pub mod memory;
 pub mod startup;
 
 // 🔒 SAFETY: 重新导出公共接口喵
-pub use compress::{ContextCompressor, MessageRanker, CompressionStrategy, CompressionStats};
+pub use compress::{CompressionConfig, ContextCompressor, ContextMessage, MessageRanker, CompressionStrategy, CompressionStats};
 pub use memory::{MemoryPool, LazyLoadToken, MemoryStats};
 pub use startup::{StartupOptimizer, InitPhase, StartupStats};
 
@@ -87,8 +87,8 @@ impl PerformanceOptimizer {
     }
 
     /// 🔒 SAFETY: 执行压缩喵
-    pub fn compress(&self, context: &mut Vec<crate::agent::AgentMessage>) -> Result<CompressionStats, String> {
-        if let Some(ref compressor) = self.compressor {
+    pub fn compress(&mut self, context: &mut Vec<crate::agent::AgentMessage>) -> Result<CompressionStats, String> {
+        if let Some(compressor) = self.compressor.as_mut() {
             compressor.compress(context)
         } else {
             Err("Compression not enabled".to_string())
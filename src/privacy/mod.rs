@@ -0,0 +1,357 @@
+//! # Privacy Module 🧹
+//!
+//! 按用户清除数据（GDPR 式"被遗忘权"）与按渠道的数据保留喵
+//!
+//! ## 归属约定
+//! - 记忆条目: metadata 中的 `channel` 与 `user_id` 字段喵
+//! - 遥测指标与反馈: `agent_metrics` / `feedback` 表的 `channel` / `user_id` 列（关联的工具调用一并删除）喵
+//! - 会话: 渠道会话以 `<渠道>-<用户 ID>` 命名（如 Telegram 私聊 `telegram-42`），删除后同时移出历史检索索引喵
+//! - 审计日志: 执行者为 `<渠道>:<用户 ID>` 的记录，只能追加的日志不删行，改为抹去个人数据并重新串链喵
+
+use crate::core::traits::{MemoryItem, PrivacyConfig, Result};
+use crate::core::{HistoryIndex, SessionStore};
+use crate::memory::SqliteMemory;
+use crate::security::AuditLog;
+use crate::telemetry::MetricsCollector;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+
+/// 🔒 SAFETY: 数据主体（渠道 + 用户）喵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSubject {
+    pub channel: String,
+    pub user_id: String,
+}
+
+impl DataSubject {
+    pub fn new(channel: impl Into<String>, user_id: impl Into<String>) -> Self {
+        Self {
+            channel: channel.into(),
+            user_id: user_id.into(),
+        }
+    }
+
+    /// 该用户的渠道会话 ID 喵
+    pub fn session_id(&self) -> String {
+        format!("{}-{}", self.channel, self.user_id)
+    }
+
+    /// 审计日志中的执行者喵
+    pub fn actor(&self) -> String {
+        format!("{}:{}", self.channel, self.user_id)
+    }
+
+    /// 记忆是否归属于该用户喵
+    pub fn owns(&self, item: &MemoryItem) -> bool {
+        let field = |key: &str| {
+            item.metadata
+                .as_ref()
+                .and_then(|m| m.get(key))
+                .and_then(|v| v.as_str())
+        };
+        field("channel") == Some(self.channel.as_str())
+            && field("user_id") == Some(self.user_id.as_str())
+    }
+}
+
+/// 清除结果喵
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    pub memory_entries: usize,
+    /// 遥测指标与反馈行数
+    pub telemetry_rows: usize,
+    pub sessions: usize,
+    /// 抹去个人数据的审计记录数
+    pub audit_entries: usize,
+}
+
+/// 🔒 SAFETY: 隐私服务喵
+pub struct PrivacyService {
+    memory: Arc<SqliteMemory>,
+    metrics: Option<Arc<MetricsCollector>>,
+    sessions: Option<SessionStore>,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl PrivacyService {
+    pub fn new(memory: Arc<SqliteMemory>) -> Self {
+        Self {
+            memory,
+            metrics: None,
+            sessions: None,
+            audit: None,
+        }
+    }
+
+    /// 同时清除遥测数据（含反馈）喵
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 同时清除会话与历史检索索引喵
+    pub fn with_sessions(mut self, sessions: SessionStore) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// 同时抹去审计日志中的个人数据喵
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// 删除会话并把它们移出历史检索索引喵
+    fn delete_sessions(&self, sessions: &SessionStore, ids: &[String]) -> Result<usize> {
+        let mut deleted = 0;
+        for id in ids {
+            if sessions.delete(id)? {
+                deleted += 1;
+            }
+        }
+        // 加密会话的索引只在内存中，无需清理喵
+        if deleted > 0 && !sessions.is_encrypted() {
+            HistoryIndex::open(sessions.clone())?.prune()?;
+        }
+        Ok(deleted)
+    }
+
+    /// 🔒 SAFETY: 删除某用户的全部数据喵
+    pub fn forget_subject(&self, subject: &DataSubject) -> Result<PurgeReport> {
+        let mut report = PurgeReport {
+            memory_entries: self.memory.purge_where(|item| subject.owns(item))?,
+            ..Default::default()
+        };
+        if let Some(metrics) = &self.metrics {
+            report.telemetry_rows = metrics.purge_user(&subject.channel, &subject.user_id)?;
+        }
+        if let Some(sessions) = &self.sessions {
+            report.sessions = self.delete_sessions(sessions, &[subject.session_id()])?;
+        }
+        if let Some(audit) = &self.audit {
+            report.audit_entries = audit.erase_actor(&subject.actor())?;
+        }
+
+        info!(
+            "🧹 Forgot {} — {} memories, {} telemetry rows, {} sessions, {} audit entries",
+            subject.actor(),
+            report.memory_entries,
+            report.telemetry_rows,
+            report.sessions,
+            report.audit_entries
+        );
+        Ok(report)
+    }
+
+    /// 🔒 SAFETY: 按渠道保留期清除过期数据喵
+    pub fn enforce_retention(&self, config: &PrivacyConfig, now: DateTime<Utc>) -> Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+
+        for (channel, days) in &config.retention_days {
+            let cutoff = now - Duration::days(i64::from(*days));
+            report.memory_entries += self.memory.purge_where(|item| {
+                item.created_at < cutoff
+                    && item
+                        .metadata
+                        .as_ref()
+                        .and_then(|m| m.get("channel"))
+                        .and_then(|v| v.as_str())
+                        == Some(channel.as_str())
+            })?;
+            if let Some(metrics) = &self.metrics {
+                report.telemetry_rows += metrics.purge_channel_before(channel, cutoff)?;
+            }
+            if let Some(sessions) = &self.sessions {
+                let prefix = format!("{}-", channel);
+                let expired: Vec<String> = sessions
+                    .list()?
+                    .into_iter()
+                    .filter(|s| s.session_id.starts_with(&prefix) && s.last_activity < cutoff)
+                    .map(|s| s.session_id)
+                    .collect();
+                report.sessions += self.delete_sessions(sessions, &expired)?;
+            }
+            if let Some(audit) = &self.audit {
+                report.audit_entries += audit.erase_channel_before(channel, cutoff)?;
+            }
+        }
+
+        if report.memory_entries + report.telemetry_rows + report.sessions + report.audit_entries > 0 {
+            info!(
+                "🧹 Retention purged {} memories, {} telemetry rows, {} sessions, {} audit entries",
+                report.memory_entries, report.telemetry_rows, report.sessions, report.audit_entries
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::Memory;
+    use crate::providers::Message;
+    use crate::security::{AuditEvent, AuditKind, ERASED};
+    use crate::telemetry::{AgentMetrics, Feedback, MetricsConfig, Rating};
+    use serde_json::json;
+
+    async fn metrics() -> Arc<MetricsCollector> {
+        Arc::new(
+            MetricsCollector::new(MetricsConfig {
+                db_path: ":memory:".to_string(),
+                monitor_interval_sec: 5,
+            })
+            .await
+            .unwrap(),
+        )
+    }
+
+    fn memory() -> Arc<SqliteMemory> {
+        Arc::new(SqliteMemory::new(":memory:").unwrap())
+    }
+
+    fn retention(channel: &str, days: u32) -> PrivacyConfig {
+        PrivacyConfig {
+            retention_days: [(channel.to_string(), days)].into_iter().collect(),
+        }
+    }
+
+    fn item(id: &str, channel: &str, user: &str, age_days: i64) -> MemoryItem {
+        MemoryItem {
+            id: id.to_string(),
+            content: format!("note {}", id),
+            embedding: None,
+            metadata: Some(json!({ "channel": channel, "user_id": user })),
+            created_at: Utc::now() - Duration::days(age_days),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forget_subject_purges_memory_and_telemetry() {
+        let memory = Arc::new(SqliteMemory::new(":memory:").unwrap());
+        memory.save(item("a", "discord", "42", 0)).await.unwrap();
+        memory.save(item("b", "discord", "7", 0)).await.unwrap();
+        memory.save(item("c", "telegram", "42", 0)).await.unwrap();
+
+        let metrics = metrics().await;
+        metrics
+            .record_agent_metrics(&AgentMetrics {
+                request_id: "r1".to_string(),
+                start_time: Utc::now(),
+                end_time: None,
+                input_tokens: None,
+                output_tokens: None,
+                total_tokens: None,
+                model: "gpt-4".to_string(),
+                status: "ok".to_string(),
+                error: None,
+                channel: Some("discord".to_string()),
+                user_id: Some("42".to_string()),
+            })
+            .unwrap();
+
+        let service = PrivacyService::new(memory.clone()).with_metrics(metrics.clone());
+        let report = service
+            .forget_subject(&DataSubject::new("discord", "42"))
+            .unwrap();
+
+        assert_eq!(report.memory_entries, 1);
+        assert_eq!(report.telemetry_rows, 1);
+        let remaining: Vec<String> = memory.list(None).unwrap().into_iter().map(|i| i.id).collect();
+        assert_eq!(remaining.len(), 2);
+        assert!(!remaining.contains(&"a".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_retention_only_touches_configured_channel() {
+        let memory = Arc::new(SqliteMemory::new(":memory:").unwrap());
+        memory.save(item("old", "discord", "1", 40)).await.unwrap();
+        memory.save(item("new", "discord", "1", 1)).await.unwrap();
+        memory.save(item("cli", "cli", "1", 400)).await.unwrap();
+
+        let report = PrivacyService::new(memory.clone())
+            .enforce_retention(&retention("discord", 30), Utc::now())
+            .unwrap();
+
+        assert_eq!(report.memory_entries, 1);
+        assert_eq!(memory.list(None).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_forget_subject_purges_feedback() {
+        let metrics = metrics().await;
+        metrics.record_feedback(&Feedback::new("m1", Rating::Down, "telegram").with_user("42")).unwrap();
+        metrics.record_feedback(&Feedback::new("m2", Rating::Up, "telegram").with_user("7")).unwrap();
+
+        let report = PrivacyService::new(memory())
+            .with_metrics(metrics.clone())
+            .forget_subject(&DataSubject::new("telegram", "42"))
+            .unwrap();
+        assert_eq!(report.telemetry_rows, 1);
+        let remaining = metrics.get_recent_feedback(10).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].user_id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn test_forget_subject_deletes_sessions_and_history_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path());
+        store.save_as("telegram-42", "telegram", &[Message::user("my address is 1 Cat St".to_string())]).unwrap();
+        store.save_as("telegram-7", "telegram", &[Message::user("hello there".to_string())]).unwrap();
+        HistoryIndex::open(store.clone()).unwrap().sync().unwrap();
+
+        let service = PrivacyService::new(memory()).with_sessions(store.clone());
+        let report = service.forget_subject(&DataSubject::new("telegram", "42")).unwrap();
+        assert_eq!(report.sessions, 1);
+        assert!(store.load("telegram-42").unwrap().is_none());
+        assert!(store.load_messages("telegram-42").unwrap().is_empty());
+        assert!(store.load("telegram-7").unwrap().is_some());
+
+        // 索引里的明文也一并删除喵
+        let index = rusqlite::Connection::open(dir.path().join(crate::core::history_search::INDEX_FILE)).unwrap();
+        let indexed: i64 = index
+            .query_row("SELECT COUNT(*) FROM messages WHERE content LIKE '%Cat St%'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[test]
+    fn test_forget_subject_erases_audit_entries() {
+        let audit = Arc::new(AuditLog::in_memory().unwrap());
+        audit.record(&AuditEvent::new(AuditKind::Tool, "discord:42", "web_fetch").with_detail("https://example.com/me"))
+            .unwrap();
+        audit.record(&AuditEvent::new(AuditKind::Tool, "discord:7", "web_fetch")).unwrap();
+
+        let report = PrivacyService::new(memory())
+            .with_audit(audit.clone())
+            .forget_subject(&DataSubject::new("discord", "42"))
+            .unwrap();
+        assert_eq!(report.audit_entries, 1);
+        assert_eq!(audit.verify().unwrap(), 2);
+        let actors: Vec<String> = audit.tail(2).unwrap().into_iter().map(|r| r.event.actor).collect();
+        assert_eq!(actors, [ERASED, "discord:7"]);
+    }
+
+    #[test]
+    fn test_retention_covers_sessions_and_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path());
+        store.save_as("discord-1", "default", &[Message::user("hi".to_string())]).unwrap();
+        store.save_as("telegram-1", "telegram", &[Message::user("hi".to_string())]).unwrap();
+        let audit = Arc::new(AuditLog::in_memory().unwrap());
+        audit.record(&AuditEvent::new(AuditKind::Tool, "discord:1", "shell")).unwrap();
+        audit.record(&AuditEvent::new(AuditKind::Tool, "telegram:1", "shell")).unwrap();
+
+        let service = PrivacyService::new(memory()).with_sessions(store.clone()).with_audit(audit.clone());
+        let later = Utc::now() + Duration::days(31);
+        let report = service.enforce_retention(&retention("discord", 30), later).unwrap();
+
+        assert_eq!((report.sessions, report.audit_entries), (1, 1));
+        assert!(store.load("discord-1").unwrap().is_none());
+        assert!(store.load("telegram-1").unwrap().is_some());
+        assert_eq!(audit.tail(2).unwrap()[1].event.actor, "telegram:1");
+    }
+}
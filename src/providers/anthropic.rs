@@ -1,4 +1,8 @@
-use super::openai::{Message, ProviderError};
+use super::chat::ChatProvider;
+use super::openai::{
+    ChatRequest, ChatResponse, Choice, FunctionCall, Message, ProviderError, ToolCall, ToolSpec,
+    Usage as ChatUsage,
+};
 /// Anthropic Provider 实现模块 🧠
 ///
 /// @诺诺 的 Anthropic API 客户端实现喵
@@ -7,15 +11,25 @@ use super::openai::{Message, ProviderError};
 /// - Claude 3 系列（Opus/Sonnet/Haiku）兼容
 /// - 长上下文支持（200K tokens）
 /// - JSON 模式支持
+/// - 原生工具调用（`tool_use` / `tool_result` 内容块），
+///   通过 `ChatProvider` 接受 OpenAI 格式的请求并返回 OpenAI 格式的响应，Agent 工具循环无需区分 provider
 ///
 /// 🔒 SAFETY: API Key 加密存储，请求参数严格验证
 ///
 /// 实现者: 诺诺 (Nono) ⚡
 use async_trait::async_trait;
 use reqwest::Client;
+use crate::tools::ToolDescription;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use std::time::Duration;
 
+/// 未指定模型时使用的默认模型
+const DEFAULT_MODEL: &str = "claude-3-opus-20240229";
+
+/// 请求未指定 max_tokens 时使用（Anthropic 要求必填）
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
 /// 🔒 SAFETY: Anthropic 配置结构体喵
 #[derive(Debug, Clone)]
 pub struct AnthropicConfig {
@@ -47,7 +61,7 @@ pub struct ClaudeRequest {
     /// 模型名称（例如 "claude-3-opus-20240229"）
     pub model: String,
     /// 消息列表
-    pub messages: Vec<Message>,
+    pub messages: Vec<ClaudeMessage>,
     /// 系统提示
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system: Option<String>,
@@ -59,6 +73,149 @@ pub struct ClaudeRequest {
     /// 顶部采样
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
+    /// 可调用的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ClaudeTool>>,
+}
+
+/// 🔒 SAFETY: Anthropic 消息（内容为内容块列表）喵
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ClaudeMessage {
+    /// 角色（user / assistant）
+    pub role: String,
+    pub content: Vec<ClaudeBlock>,
+}
+
+impl ClaudeMessage {
+    /// 🔒 SAFETY: 纯文本用户消息喵
+    pub fn user(text: String) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: vec![ClaudeBlock::Text { text }],
+        }
+    }
+}
+
+/// 🔒 SAFETY: 请求中的内容块喵
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeBlock {
+    Text {
+        text: String,
+    },
+    /// 助手发起的工具调用
+    ToolUse {
+        id: String,
+        name: String,
+        input: JsonValue,
+    },
+    /// 工具执行结果（放在 user 消息中）
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+}
+
+/// 🔒 SAFETY: Anthropic 工具定义喵
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ClaudeTool {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema（必须是 object）
+    pub input_schema: JsonValue,
+}
+
+impl From<&ToolSpec> for ClaudeTool {
+    fn from(spec: &ToolSpec) -> Self {
+        Self {
+            name: spec.function.name.clone(),
+            description: spec.function.description.clone(),
+            input_schema: spec.function.parameters.clone(),
+        }
+    }
+}
+
+impl From<&ToolDescription> for ClaudeTool {
+    fn from(tool: &ToolDescription) -> Self {
+        Self::from(&ToolSpec::from(tool))
+    }
+}
+
+impl ClaudeRequest {
+    /// 🔒 SAFETY: 由 OpenAI 格式的请求转换喵
+    ///
+    /// - system 消息合并为 `system`
+    /// - 助手的结构化工具调用转为 `tool_use` 块，`tool` 消息转为 user 消息中的 `tool_result` 块
+    /// - 相邻的同角色消息合并（Anthropic 要求 user / assistant 交替）
+    pub fn from_chat(request: &ChatRequest, default_model: &str) -> Self {
+        let mut system = Vec::new();
+        let mut messages: Vec<ClaudeMessage> = Vec::new();
+        for message in &request.messages {
+            let (role, blocks) = match message.role.as_str() {
+                "system" => {
+                    system.push(message.content.clone());
+                    continue;
+                }
+                "assistant" => {
+                    let mut blocks = Vec::new();
+                    if !message.content.is_empty() {
+                        blocks.push(ClaudeBlock::Text {
+                            text: message.content.clone(),
+                        });
+                    }
+                    for call in message.tool_calls.iter().flatten() {
+                        let input = serde_json::from_str(&call.function.arguments)
+                            .ok()
+                            .filter(JsonValue::is_object)
+                            .unwrap_or_else(|| serde_json::json!({}));
+                        blocks.push(ClaudeBlock::ToolUse {
+                            id: call.id.clone(),
+                            name: call.function.name.clone(),
+                            input,
+                        });
+                    }
+                    ("assistant", blocks)
+                }
+                "tool" => (
+                    "user",
+                    vec![ClaudeBlock::ToolResult {
+                        tool_use_id: message.tool_call_id.clone().unwrap_or_default(),
+                        content: message.content.clone(),
+                    }],
+                ),
+                _ => (
+                    "user",
+                    vec![ClaudeBlock::Text {
+                        text: message.content.clone(),
+                    }],
+                ),
+            };
+            if blocks.is_empty() {
+                continue;
+            }
+            match messages.last_mut() {
+                Some(last) if last.role == role => last.content.extend(blocks),
+                _ => messages.push(ClaudeMessage {
+                    role: role.to_string(),
+                    content: blocks,
+                }),
+            }
+        }
+
+        Self {
+            model: request.model.clone().unwrap_or_else(|| default_model.to_string()),
+            messages,
+            system: Some(system.join("\n\n")).filter(|s| !s.is_empty()),
+            max_tokens: request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            // Anthropic 的温度范围是 0.0-1.0
+            temperature: request.temperature.map(|t| t.clamp(0.0, 1.0)),
+            top_p: request.top_p,
+            tools: request
+                .tools
+                .as_ref()
+                .map(|tools| tools.iter().map(ClaudeTool::from).collect()),
+        }
+    }
 }
 
 /// 🔒 SAFETY: Anthropic 错误结构体喵
@@ -109,6 +266,60 @@ pub struct ContentBlock {
     pub content_type: String,
     /// 文本内容
     pub text: Option<String>,
+    /// `tool_use` 块的调用 ID
+    #[serde(default)]
+    pub id: Option<String>,
+    /// `tool_use` 块的工具名
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `tool_use` 块的参数
+    #[serde(default)]
+    pub input: Option<JsonValue>,
+}
+
+impl ClaudeResponse {
+    /// 🔒 SAFETY: 转换为 OpenAI 格式的响应喵（`tool_use` 块转为结构化工具调用）
+    pub fn into_chat_response(self) -> ChatResponse {
+        let mut text = Vec::new();
+        let mut tool_calls = Vec::new();
+        for block in self.content {
+            match block.content_type.as_str() {
+                "text" => text.extend(block.text),
+                "tool_use" => tool_calls.push(ToolCall {
+                    id: block.id.unwrap_or_default(),
+                    kind: "function".to_string(),
+                    function: FunctionCall {
+                        name: block.name.unwrap_or_default(),
+                        arguments: block.input.unwrap_or_else(|| serde_json::json!({})).to_string(),
+                    },
+                }),
+                _ => {}
+            }
+        }
+        let finish_reason = match self.stop_reason.as_deref() {
+            Some("tool_use") => "tool_calls",
+            Some("max_tokens") => "length",
+            _ => "stop",
+        };
+        let message = Message::assistant(text.join("\n")).with_tool_calls(Some(tool_calls));
+
+        ChatResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp().max(0) as u64,
+            model: self.model,
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: Some(finish_reason.to_string()),
+            }],
+            usage: ChatUsage {
+                prompt_tokens: self.usage.input_tokens,
+                completion_tokens: self.usage.output_tokens,
+                total_tokens: self.usage.input_tokens + self.usage.output_tokens,
+            },
+        }
+    }
 }
 
 /// 🔒 SAFETY: 使用情况结构体（复用 OpenAI 的）喵
@@ -133,6 +344,8 @@ pub struct AnthropicClient {
     config: AnthropicConfig,
     /// Anthropic 版本
     version: String,
+    /// 请求未指定模型时使用的模型
+    default_model: String,
 }
 
 impl AnthropicClient {
@@ -147,9 +360,16 @@ impl AnthropicClient {
             client,
             config,
             version: "2023-06-01".to_string(),
+            default_model: DEFAULT_MODEL.to_string(),
         }
     }
 
+    /// 🔒 SAFETY: 设置默认模型喵
+    pub fn with_default_model(mut self, model: &str) -> Self {
+        self.default_model = model.to_string();
+        self
+    }
+
     /// 🔒 SAFETY: 发送聊天请求（带重试）喵
     async fn send_request_with_retry(
         &self,
@@ -209,6 +429,13 @@ impl AnthropicClient {
             }
 
             let error_text = response.text().await.unwrap_or_default();
+            // 429 限流、5xx（含 529 overloaded）可切换备用模型
+            if status.as_u16() == 429 || status.is_server_error() {
+                let message = serde_json::from_str::<AnthropicError>(&error_text)
+                    .map(|e| e.error.message)
+                    .unwrap_or(error_text);
+                return Err(ProviderError::Unavailable(status.as_u16(), message));
+            }
             if let Ok(anthropic_error) = serde_json::from_str::<AnthropicError>(&error_text) {
                 Err(ProviderError::ApiError(anthropic_error.error.message))
             } else {
@@ -233,12 +460,13 @@ impl AnthropicClient {
     /// 直接发送用户消息
     pub async fn chat_simple(&self, prompt: &str) -> Result<String, ProviderError> {
         let request = ClaudeRequest {
-            model: "claude-3-opus-20240229".to_string(),
-            messages: vec![Message::user(prompt.to_string())],
+            model: self.default_model.clone(),
+            messages: vec![ClaudeMessage::user(prompt.to_string())],
             system: None,
             max_tokens: 4096,
             temperature: None,
             top_p: None,
+            tools: None,
         };
 
         let response = self.chat_api(&request).await?;
@@ -246,10 +474,10 @@ impl AnthropicClient {
         // 提取文本内容
         response
             .content
-            .get(0)
+            .first()
             .and_then(|block| block.text.as_ref())
+            .cloned()
             .ok_or_else(|| ProviderError::ApiError("No text content in response".to_string()))
-            .map(|s| s.clone())
     }
 
     /// 🔒 SAFETY: 带系统提示的聊天喵
@@ -259,27 +487,37 @@ impl AnthropicClient {
         prompt: &str,
     ) -> Result<String, ProviderError> {
         let request = ClaudeRequest {
-            model: "claude-3-opus-20240229".to_string(),
-            messages: vec![Message::user(prompt.to_string())],
+            model: self.default_model.clone(),
+            messages: vec![ClaudeMessage::user(prompt.to_string())],
             system: Some(system.to_string()),
             max_tokens: 4096,
             temperature: None,
             top_p: None,
+            tools: None,
         };
 
         let response = self.chat_api(&request).await?;
         response
             .content
-            .get(0)
+            .first()
             .and_then(|block| block.text.as_ref())
+            .cloned()
             .ok_or_else(|| ProviderError::ApiError("No text content in response".to_string()))
-            .map(|s| s.clone())
+    }
+}
+
+#[async_trait]
+impl ChatProvider for AnthropicClient {
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        let request = ClaudeRequest::from_chat(request, &self.default_model);
+        Ok(self.chat_api(&request).await?.into_chat_response())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn test_config_default() {
@@ -292,14 +530,83 @@ mod tests {
     fn test_claude_request() {
         let request = ClaudeRequest {
             model: "claude-3-opus-20240229".to_string(),
-            messages: vec![Message::user("test".to_string())],
+            messages: vec![ClaudeMessage::user("test".to_string())],
             system: Some("You are helpful".to_string()),
             max_tokens: 100,
             temperature: None,
             top_p: None,
+            tools: None,
         };
 
         assert_eq!(request.model, "claude-3-opus-20240229");
         assert!(request.system.is_some());
     }
+
+    #[test]
+    fn test_tool_use_roundtrip() {
+        let tool = ToolDescription {
+            name: "fs_read".to_string(),
+            description: "Read a file".to_string(),
+            input_schema: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+            category: None,
+            dangerous: false,
+            required_permissions: None,
+        };
+        let call = ToolCall {
+            id: "toolu_1".to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall {
+                name: "fs_read".to_string(),
+                arguments: r#"{"path": "a.txt"}"#.to_string(),
+            },
+        };
+        let chat = ChatRequest {
+            model: None,
+            messages: vec![
+                Message::system("persona".to_string()),
+                Message::user("read a.txt".to_string()),
+                Message::assistant("Reading".to_string()).with_tool_calls(Some(vec![call])),
+                Message::tool("toolu_1".to_string(), "hello".to_string()),
+                Message::user("thanks".to_string()),
+            ],
+            temperature: Some(1.5),
+            top_p: None,
+            max_tokens: None,
+            stream: Some(false),
+            tools: Some(vec![ToolSpec::from(&tool)]),
+        };
+
+        let request = serde_json::to_value(ClaudeRequest::from_chat(&chat, "claude-sonnet")).unwrap();
+        assert_eq!(request["model"], "claude-sonnet");
+        assert_eq!(request["system"], "persona");
+        assert_eq!(request["temperature"], 1.0);
+        assert_eq!(request["tools"][0]["input_schema"]["properties"]["path"]["type"], "string");
+        assert_eq!(request["messages"][1]["content"][1], json!({"type": "tool_use", "id": "toolu_1", "name": "fs_read", "input": {"path": "a.txt"}}));
+        // 工具结果与随后的用户消息合并为一条 user 消息喵
+        assert_eq!(request["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(request["messages"][2]["content"][0], json!({"type": "tool_result", "tool_use_id": "toolu_1", "content": "hello"}));
+        assert_eq!(request["messages"][2]["content"][1]["text"], "thanks");
+
+        let response: ClaudeResponse = serde_json::from_value(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet",
+            "content": [
+                {"type": "text", "text": "Let me check"},
+                {"type": "tool_use", "id": "toolu_2", "name": "fs_read", "input": {"path": "b.txt"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        }))
+        .unwrap();
+        let response = response.into_chat_response();
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(choice.message.content, "Let me check");
+        let request = choice.message.tool_calls.as_ref().unwrap()[0].to_request();
+        assert_eq!(request.call_id.as_deref(), Some("toolu_2"));
+        assert_eq!(request.arguments, json!({"path": "b.txt"}));
+        assert_eq!(response.usage.total_tokens, 15);
+    }
 }
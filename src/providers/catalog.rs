@@ -0,0 +1,185 @@
+/// 模型目录 📚
+///
+/// 从 provider 实时拉取模型列表，统一为表格展示喵
+///
+/// 功能：
+/// - OpenRouter: 含上下文长度与价格
+/// - OpenAI 兼容端点（OpenAI / NVIDIA / llama.cpp）: GET /models
+/// - Ollama: GET /api/tags（本地已下载的模型）
+/// - 用一次极小的请求验证模型可用
+///
+/// 🔒 SAFETY: API Key 只用于请求头，不会出现在输出中
+use super::ollama::{is_openai_compatible, OllamaModel};
+use super::{
+    ChatProvider, ChatRequest, Message, ModelInfo, OllamaClient, OllamaConfig, OpenAIClient, OpenAIConfig,
+    OpenAIModel, OpenRouterClient, OpenRouterConfig, ProviderError,
+};
+use crate::core::traits::ProviderConfig;
+
+/// 🔒 SAFETY: 统一的模型条目喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogEntry {
+    /// 模型 ID
+    pub id: String,
+    /// 上下文长度
+    pub context_length: Option<u32>,
+    /// 输入价格（美元 / 百万 token）
+    pub prompt_price: Option<f64>,
+    /// 输出价格（美元 / 百万 token）
+    pub completion_price: Option<f64>,
+}
+
+impl From<ModelInfo> for CatalogEntry {
+    fn from(model: ModelInfo) -> Self {
+        // OpenRouter 返回的是每 token 价格字符串喵
+        let per_million = |price: &str| price.parse::<f64>().ok().map(|p| p * 1_000_000.0);
+        Self {
+            prompt_price: per_million(&model.pricing.prompt),
+            completion_price: per_million(&model.pricing.completion),
+            context_length: Some(model.context_length),
+            id: model.id,
+        }
+    }
+}
+
+impl From<OpenAIModel> for CatalogEntry {
+    fn from(model: OpenAIModel) -> Self {
+        Self {
+            id: model.id,
+            context_length: model.context_length,
+            prompt_price: None,
+            completion_price: None,
+        }
+    }
+}
+
+impl From<OllamaModel> for CatalogEntry {
+    fn from(model: OllamaModel) -> Self {
+        Self {
+            id: model.name,
+            context_length: None,
+            prompt_price: None,
+            completion_price: None,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 拉取 provider 的模型目录（按 ID 排序）喵
+pub async fn fetch_catalog(
+    provider: &str,
+    config: &ProviderConfig,
+) -> Result<Vec<CatalogEntry>, ProviderError> {
+    let client: Box<dyn ChatProvider> = if provider == "openrouter" {
+        Box::new(OpenRouterClient::new(OpenRouterConfig {
+            api_key: config.api_key.clone(),
+            base_url: config.base_url.clone(),
+            timeout: config.timeout,
+            ..OpenRouterConfig::default()
+        }))
+    } else {
+        chat_client(provider, config, config.max_retries)
+    };
+    let mut entries = client.list_models().await?;
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+/// 🔒 SAFETY: 用 1 token 的请求验证模型可用喵
+/// 不重试、不回退，避免把兜底模型误判为可用
+pub async fn validate_model(provider: &str, config: &ProviderConfig, model: &str) -> Result<(), ProviderError> {
+    let request = ChatRequest {
+        model: Some(model.to_string()),
+        messages: vec![Message::user("ping".to_string())],
+        temperature: Some(0.0),
+        top_p: None,
+        max_tokens: Some(1),
+        stream: Some(false),
+        tools: None,
+    };
+    chat_client(provider, config, 0).chat(&request).await.map(|_| ())
+}
+
+/// 🔒 SAFETY: OpenAI 兼容客户端，或 Ollama 原生 API（而不是 `/v1` 兼容端点）的客户端喵
+fn chat_client(provider: &str, config: &ProviderConfig, max_retries: u8) -> Box<dyn ChatProvider> {
+    if provider == "ollama" && !is_openai_compatible(&config.base_url) {
+        return Box::new(OllamaClient::new(OllamaConfig {
+            api_key: config.api_key.clone(),
+            base_url: config.base_url.clone(),
+            timeout: config.timeout,
+            max_retries,
+        }));
+    }
+    Box::new(OpenAIClient::new(OpenAIConfig {
+        api_key: config.api_key.clone(),
+        base_url: config.base_url.clone(),
+        timeout: config.timeout,
+        max_retries,
+    }))
+}
+
+/// 🔒 SAFETY: 格式化为文本表格喵
+pub fn format_table(entries: &[CatalogEntry]) -> String {
+    let id_width = entries
+        .iter()
+        .map(|e| e.id.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("MODEL".len());
+    let price = |p: Option<f64>| p.map(|p| format!("${:.2}", p)).unwrap_or_else(|| "-".to_string());
+
+    let mut out = format!(
+        "{:<id_width$}  {:>9}  {:>10}  {:>10}\n",
+        "MODEL", "CONTEXT", "IN/1M", "OUT/1M"
+    );
+    for entry in entries {
+        out.push_str(&format!(
+            "{:<id_width$}  {:>9}  {:>10}  {:>10}\n",
+            entry.id,
+            entry
+                .context_length
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            price(entry.prompt_price),
+            price(entry.completion_price),
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::Pricing;
+
+    #[test]
+    fn test_openrouter_pricing_per_million() {
+        let entry = CatalogEntry::from(ModelInfo {
+            id: "openai/gpt-4o-mini".to_string(),
+            name: "GPT-4o mini".to_string(),
+            description: String::new(),
+            pricing: Pricing {
+                prompt: "0.00000015".to_string(),
+                completion: "0.0000006".to_string(),
+            },
+            context_length: 128000,
+        });
+        assert!((entry.prompt_price.unwrap() - 0.15).abs() < 1e-9);
+        assert!((entry.completion_price.unwrap() - 0.6).abs() < 1e-9);
+        assert_eq!(entry.context_length, Some(128000));
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&[CatalogEntry {
+            id: "gpt-4".to_string(),
+            context_length: None,
+            prompt_price: Some(30.0),
+            completion_price: None,
+        }]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("MODEL"));
+        assert!(lines[1].starts_with("gpt-4"));
+        assert!(lines[1].contains("$30.00"));
+        assert!(lines[1].trim_end().ends_with('-'));
+    }
+}
@@ -0,0 +1,280 @@
+/// 统一的对话 Provider 接口 🧩
+///
+/// @诺诺 的 Provider 抽象喵
+///
+/// 功能：
+/// - `ChatProvider`：所有客户端（OpenAI 兼容 / Anthropic / Gemini / Ollama / OpenRouter）都实现的异步 trait，
+///   请求与响应统一为 OpenAI 格式，调用方只依赖 `Arc<dyn ChatProvider>`，不再按枚举逐个匹配
+/// - `text_stream`：SSE / NDJSON 流式响应解析为文本片段
+/// - `ModelProvider`：把任意 `ChatProvider` 绑定模型后适配为 `core::traits::Provider`（网关 / 渠道机器人使用）
+///
+/// 🔒 SAFETY: trait 只描述请求与响应，不持有 API Key
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use super::catalog::CatalogEntry;
+use super::failover::FailoverChain;
+use super::openai::{ChatRequest, ChatResponse, Message, ProviderError};
+use super::tokenizer::{count_message_tokens, count_tokens};
+
+/// 流式回复（逐个文本片段）
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<String, ProviderError>> + Send>>;
+
+/// 🔒 SAFETY: 对话 Provider 喵
+#[async_trait]
+pub trait ChatProvider: Send + Sync + std::fmt::Debug {
+    /// 发送聊天请求（含原生工具调用）；请求未指定模型时使用客户端的默认模型
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError>;
+
+    /// 流式聊天，逐个返回文本片段
+    ///
+    /// 默认实现一次性返回完整回复
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<ChatStream, ProviderError> {
+        let response = self.chat(request).await?;
+        let content = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .unwrap_or_default();
+        Ok(Box::pin(futures::stream::once(async move { Ok(content) })))
+    }
+
+    /// 可用模型列表
+    async fn list_models(&self) -> Result<Vec<CatalogEntry>, ProviderError> {
+        Err(ProviderError::ApiError("Listing models is not supported by this provider".to_string()))
+    }
+
+    /// 请求的输入 token 数
+    ///
+    /// 默认用进程级计数器本地计算（消息内容 + 工具调用参数 + 工具定义）
+    async fn count_tokens(&self, request: &ChatRequest) -> Result<usize, ProviderError> {
+        let tools: usize = request
+            .tools
+            .iter()
+            .flatten()
+            .map(|tool| count_tokens(&tool.function.description) + count_tokens(&tool.function.parameters.to_string()))
+            .sum();
+        Ok(count_message_tokens(&request.messages) + tools)
+    }
+}
+
+/// 流式响应的分帧方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StreamFraming {
+    /// Server-Sent Events（`data: {...}`，`[DONE]` 结束）
+    Sse,
+    /// 每行一个 JSON（Ollama）
+    NdJson,
+}
+
+impl StreamFraming {
+    /// 取出缓冲区中完整的事件，不完整的行留在缓冲区喵
+    fn drain(self, buffer: &mut String) -> Vec<String> {
+        let mut events = Vec::new();
+        while let Some(end) = buffer.find('\n') {
+            let line: String = buffer.drain(..=end).collect();
+            let line = line.trim();
+            let data = match self {
+                StreamFraming::Sse => match line.strip_prefix("data:") {
+                    Some(data) => data.trim(),
+                    None => continue,
+                },
+                StreamFraming::NdJson => line,
+            };
+            if !data.is_empty() && data != "[DONE]" {
+                events.push(data.to_string());
+            }
+        }
+        events
+    }
+}
+
+/// 🔒 SAFETY: 把流式 HTTP 响应解析为文本片段喵
+///
+/// `parse` 把单个事件解析为文本，返回 None 或空字符串的事件（心跳、结束标记）被跳过
+pub(crate) fn text_stream<F>(response: reqwest::Response, framing: StreamFraming, parse: F) -> ChatStream
+where
+    F: Fn(&str) -> Result<Option<String>, ProviderError> + Send + 'static,
+{
+    let state = (Some(response), String::new(), VecDeque::<String>::new());
+    let events = futures::stream::unfold(state, move |(mut response, mut buffer, mut pending)| async move {
+        loop {
+            if let Some(event) = pending.pop_front() {
+                return Some((Ok(event), (response, buffer, pending)));
+            }
+            let chunk = match response.as_mut()?.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                // 结束时处理没有换行结尾的最后一个事件喵
+                Ok(None) => {
+                    response = None;
+                    buffer.push('\n');
+                    pending.extend(framing.drain(&mut buffer));
+                    continue;
+                }
+                Err(e) => return Some((Err(e.into()), (None, buffer, pending))),
+            };
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            pending.extend(framing.drain(&mut buffer));
+        }
+    });
+    Box::pin(events.filter_map(move |event| {
+        let item = match event.and_then(|data| parse(&data)) {
+            Ok(Some(text)) if !text.is_empty() => Some(Ok(text)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        };
+        futures::future::ready(item)
+    }))
+}
+
+/// 🔒 SAFETY: 绑定模型的通用 Provider 喵
+///
+/// 供渠道机器人等通过 `ChatBackend` 调用模型的场景使用
+#[derive(Debug, Clone)]
+pub struct ModelProvider {
+    name: String,
+    client: Arc<dyn ChatProvider>,
+    model: String,
+    max_tokens: Option<u32>,
+    failover: Option<FailoverChain>,
+}
+
+impl ModelProvider {
+    pub fn new(name: &str, client: Arc<dyn ChatProvider>, model: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            client,
+            model: model.to_string(),
+            max_tokens: None,
+            failover: None,
+        }
+    }
+
+    /// 🔀 模型临时不可用时按备用模型链切换喵（`agents.defaults.model.fallback`）
+    pub fn with_failover(mut self, failover: FailoverChain) -> Self {
+        self.failover = Some(failover);
+        self
+    }
+
+    /// 每次回复的 token 上限喵
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    fn request(&self, messages: &[crate::core::traits::Message]) -> ChatRequest {
+        ChatRequest {
+            model: Some(self.model.clone()),
+            messages: messages
+                .iter()
+                .map(|m| Message::with_role(&m.role, m.content.clone()))
+                .collect(),
+            temperature: None,
+            top_p: None,
+            max_tokens: self.max_tokens,
+            stream: None,
+            tools: None,
+        }
+    }
+}
+
+#[async_trait]
+impl crate::core::traits::Provider for ModelProvider {
+    async fn chat(
+        &self,
+        messages: &[crate::core::traits::Message],
+    ) -> crate::core::traits::Result<String> {
+        let request = self.request(messages);
+        let response = match &self.failover {
+            Some(failover) => failover.chat(self.client.as_ref(), &request).await?,
+            None => self.client.chat(&request).await?,
+        };
+        // 用 Provider 报告的 prompt token 数校准进程级计数器喵
+        super::tokenizer::observe_usage(&request.messages, response.usage.prompt_tokens);
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::ApiError("No choices in response".to_string()))?;
+        Ok(choice.message.content)
+    }
+
+    async fn stream(
+        &self,
+        messages: &[crate::core::traits::Message],
+    ) -> Pin<Box<dyn Stream<Item = crate::core::traits::Result<String>> + Send>> {
+        match self.client.chat_stream(&self.request(messages)).await {
+            Ok(stream) => Box::pin(stream.map(|item| item.map_err(Into::into))),
+            Err(e) => Box::pin(futures::stream::once(async move { Err(e.into()) })),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_framing() {
+        let mut buffer = "data: {\"a\":1}\r\n\r\n: keep-alive\n\ndata: [DONE]\n\ndata: {\"b\"".to_string();
+        assert_eq!(StreamFraming::Sse.drain(&mut buffer), vec!["{\"a\":1}".to_string()]);
+        assert_eq!(buffer, "data: {\"b\"");
+
+        let mut buffer = "{\"n\":1}\n\n{\"n\":2}\n{\"n\"".to_string();
+        assert_eq!(StreamFraming::NdJson.drain(&mut buffer), vec!["{\"n\":1}".to_string(), "{\"n\":2}".to_string()]);
+        assert_eq!(buffer, "{\"n\"");
+    }
+
+    /// 主模型过载、备用模型正常的客户端喵
+    #[derive(Debug)]
+    struct OverloadedClient;
+
+    #[async_trait]
+    impl ChatProvider for OverloadedClient {
+        async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+            let model = request.model.clone().unwrap_or_default();
+            if model == "big" {
+                return Err(ProviderError::Unavailable(503, "overloaded".to_string()));
+            }
+            Ok(serde_json::from_value(serde_json::json!({
+                "id": "r",
+                "object": "chat.completion",
+                "created": 0,
+                "model": model,
+                "choices": [{ "index": 0, "message": { "role": "assistant", "content": format!("from {}", model) }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 }
+            }))
+            .unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_backend_fails_over_through_model_provider() {
+        use crate::core::traits::Message as CoreMessage;
+        use crate::gateway::ChatBackend;
+
+        let config: super::super::failover::AgentModelConfig =
+            toml::from_str("fallback = [\"small\"]\nbackoff_ms = 0").unwrap();
+        let messages = vec![CoreMessage::user("hi".to_string())];
+
+        let plain = ChatBackend::new(Arc::new(ModelProvider::new("test", Arc::new(OverloadedClient), "big")));
+        assert!(plain.complete(messages.clone()).await.is_err());
+
+        let provider = ModelProvider::new("test", Arc::new(OverloadedClient), "big")
+            .with_failover(FailoverChain::new(&config));
+        let backend = ChatBackend::new(Arc::new(provider));
+        assert_eq!(backend.complete(messages).await.unwrap(), "from small");
+    }
+}
@@ -0,0 +1,166 @@
+/// 上下文超限恢复 ✂️
+///
+/// Provider 以 400 拒绝超长上下文时，激进压缩消息后自动重试一次喵
+///
+/// 压缩策略：
+/// - 保留开头的系统消息（截断超长内容）
+/// - 只保留最近几条对话消息，丢弃更早的历史
+/// - 每条保留的消息截断为头尾两段，中间用标记替换
+///
+/// 🔒 SAFETY: 只在内存中生成新请求，不修改调用方的历史
+use super::tokenizer::count_message_tokens;
+use super::{ChatRequest, Message};
+
+/// 压缩后保留的最近对话消息数
+const KEEP_RECENT: usize = 4;
+/// 系统消息的最大字符数
+const MAX_SYSTEM_CHARS: usize = 8_000;
+/// 对话消息的最大字符数
+const MAX_MESSAGE_CHARS: usize = 2_000;
+/// 截断标记
+const TRUNCATION_MARKER: &str = "\n…[truncated]…\n";
+
+/// 🔒 SAFETY: 判断错误是否为上下文长度超限喵
+/// OpenAI 返回 code=context_length_exceeded，其它兼容端点只给出文本
+pub fn is_context_length_error(code: Option<&str>, message: &str) -> bool {
+    if code == Some("context_length_exceeded") {
+        return true;
+    }
+    let message = message.to_lowercase();
+    [
+        "maximum context length",
+        "context length",
+        "context window",
+        "prompt is too long",
+        "too many tokens",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// 🔒 SAFETY: 压缩结果统计（用于遥测事件）喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// 被丢弃的消息数
+    pub dropped_messages: usize,
+    /// 压缩前估算 token 数
+    pub tokens_before: usize,
+    /// 压缩后估算 token 数
+    pub tokens_after: usize,
+}
+
+/// 🔒 SAFETY: 激进压缩请求消息喵
+pub fn compact_request(request: &ChatRequest) -> (ChatRequest, CompactionStats) {
+    let system_len = request
+        .messages
+        .iter()
+        .take_while(|m| m.role == "system")
+        .count();
+    let (system, conversation) = request.messages.split_at(system_len);
+    let keep_from = eviction_point(conversation, KEEP_RECENT);
+
+    let messages: Vec<Message> = system
+        .iter()
+        .map(|m| truncated(m, MAX_SYSTEM_CHARS))
+        .chain(
+            conversation[keep_from..]
+                .iter()
+                .map(|m| truncated(m, MAX_MESSAGE_CHARS)),
+        )
+        .collect();
+
+    let stats = CompactionStats {
+        dropped_messages: keep_from,
+        tokens_before: count_tokens(&request.messages),
+        tokens_after: count_tokens(&messages),
+    };
+    (
+        ChatRequest {
+            messages,
+            ..request.clone()
+        },
+        stats,
+    )
+}
+
+/// 🔒 SAFETY: 只保留最近 `keep_recent` 条对话消息时，被丢弃部分的长度喵
+///
+/// tool 结果不能脱离发起调用的助手消息单独出现，窗口开头的 tool 结果一并丢弃
+pub fn eviction_point(conversation: &[Message], keep_recent: usize) -> usize {
+    let mut keep_from = conversation.len().saturating_sub(keep_recent);
+    while conversation.get(keep_from).is_some_and(|m| m.role == "tool") {
+        keep_from += 1;
+    }
+    keep_from
+}
+
+pub(crate) fn count_tokens(messages: &[Message]) -> usize {
+    count_message_tokens(messages)
+}
+
+/// 保留头尾各一半，中间替换为标记喵
+fn truncated(message: &Message, max_chars: usize) -> Message {
+    let total = message.content.chars().count();
+    if total <= max_chars {
+        return message.clone();
+    }
+    let half = max_chars / 2;
+    let head: String = message.content.chars().take(half).collect();
+    let tail: String = message.content.chars().skip(total - half).collect();
+    Message {
+        content: format!("{}{}{}", head, TRUNCATION_MARKER, tail),
+        ..message.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_context_length_errors() {
+        assert!(is_context_length_error(Some("context_length_exceeded"), ""));
+        assert!(is_context_length_error(
+            None,
+            "This model's maximum context length is 8192 tokens."
+        ));
+        assert!(!is_context_length_error(None, "Invalid API key"));
+    }
+
+    #[test]
+    fn test_compact_request_keeps_system_and_recent() {
+        let mut messages = vec![Message::system("s".repeat(MAX_SYSTEM_CHARS + 100))];
+        for i in 0..10 {
+            messages.push(Message::user(format!("question {}", i)));
+        }
+        messages.push(Message::user("x".repeat(MAX_MESSAGE_CHARS * 3)));
+        let request = ChatRequest {
+            model: Some("gpt-4".to_string()),
+            messages,
+            temperature: Some(0.7),
+            top_p: None,
+            max_tokens: Some(100),
+            stream: Some(false),
+            tools: None,
+        };
+
+        let (compacted, stats) = compact_request(&request);
+        assert_eq!(compacted.messages.len(), 1 + KEEP_RECENT);
+        assert_eq!(compacted.messages[0].role, "system");
+        assert!(compacted.messages[0].content.contains(TRUNCATION_MARKER));
+        assert_eq!(compacted.messages[1].content, "question 7");
+        assert!(compacted.messages.last().unwrap().content.chars().count() < MAX_MESSAGE_CHARS + 20);
+        assert_eq!(stats.dropped_messages, 7);
+        assert!(stats.tokens_after < stats.tokens_before);
+        assert_eq!(compacted.model, request.model);
+
+        // 窗口开头的 tool 结果会失去对应的调用，一并丢弃喵
+        let mut messages = vec![Message::system("s".to_string()), Message::user("q".to_string())];
+        messages.push(Message::tool("call_1".to_string(), "a".to_string()));
+        messages.push(Message::tool("call_2".to_string(), "b".to_string()));
+        messages.extend([Message::assistant("done".to_string()), Message::user("next".to_string())]);
+        let (compacted, stats) = compact_request(&ChatRequest { messages, ..request });
+        assert_eq!(stats.dropped_messages, 3);
+        assert_eq!(compacted.messages[1].content, "done");
+    }
+}
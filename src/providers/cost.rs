@@ -0,0 +1,385 @@
+/// 费用统计与预算 💰
+///
+/// @诺诺 的模型调用记账喵
+///
+/// 功能：
+/// - `ModelPricing`：每个模型的输入 / 输出价格（美元 / 百万 token），按 `usage` 计算单次请求费用
+/// - `CostLedger`：每次请求的 token 与费用写入 SQLite，按会话 / Agent / 当天汇总
+/// - `CostTracker`：绑定会话与 Agent，请求前检查预算，超出任一预算时拒绝继续请求
+/// - `nekoclaw status --verbose` 展示当天与最近会话的花费
+///
+/// ```toml
+/// [costs]
+/// session_budget = 1.0
+/// daily_budget = 5.0
+///
+/// [costs.agent_budgets]
+/// nia = 2.0
+///
+/// [costs.pricing."gpt-4o"]
+/// input_price = 2.5
+/// output_price = 10.0
+/// ```
+///
+/// 未配置价格的模型只记录 token，费用按 0 计算；“当天”按本地时区划分
+///
+/// 🔒 SAFETY: 账本只记录模型名与 token 数，不记录消息内容
+use chrono::{DateTime, Local, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::debug;
+
+use super::openai::Usage;
+
+/// 🔒 SAFETY: 费用统计错误喵
+#[derive(Debug, Error)]
+pub enum CostError {
+    /// 数据库错误
+    #[error("Cost ledger error: {0}")]
+    Store(#[from] rusqlite::Error),
+
+    /// 无法创建数据目录
+    #[error("Cost ledger directory error: {0}")]
+    Io(String),
+
+    /// 已超出预算
+    #[error("{scope} budget exceeded: ${spent:.4} spent of ${limit:.4}")]
+    BudgetExceeded { scope: String, spent: f64, limit: f64 },
+}
+
+/// 🔒 SAFETY: 模型价格喵（美元 / 百万 token）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// 输入价格
+    pub input_price: f64,
+    /// 输出价格
+    pub output_price: f64,
+}
+
+impl ModelPricing {
+    /// 单次请求的费用喵
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input_price + usage.completion_tokens as f64 * self.output_price)
+            / 1_000_000.0
+    }
+}
+
+/// 🔒 SAFETY: 费用配置喵（预算单位为美元，未设置表示不限制）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostConfig {
+    /// 模型价格（键为模型名，可带 `provider/` 前缀）
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
+    /// 单个会话的预算
+    #[serde(default)]
+    pub session_budget: Option<f64>,
+    /// 每天所有 Agent 合计的预算
+    #[serde(default)]
+    pub daily_budget: Option<f64>,
+    /// 每个 Agent 每天的预算
+    #[serde(default)]
+    pub agent_budgets: HashMap<String, f64>,
+}
+
+impl CostConfig {
+    /// 查找模型价格喵
+    ///
+    /// 依次尝试：完整模型名 → 去掉 `provider/` 前缀 → 最长的前缀匹配（`gpt-4o` 匹配 `gpt-4o-2024-08-06`）
+    pub fn pricing_for(&self, model: &str) -> Option<ModelPricing> {
+        let bare = model.rsplit('/').next().unwrap_or(model);
+        self.pricing
+            .get(model)
+            .or_else(|| self.pricing.get(bare))
+            .or_else(|| {
+                self.pricing
+                    .iter()
+                    .filter(|(key, _)| bare.starts_with(key.rsplit('/').next().unwrap_or(key)))
+                    .max_by_key(|(key, _)| key.len())
+                    .map(|(_, pricing)| pricing)
+            })
+            .copied()
+    }
+}
+
+/// 花费汇总的范围喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostScope<'a> {
+    /// 单个会话（全部时间）
+    Session(&'a str),
+    /// 单个 Agent（当天）
+    Agent(&'a str),
+    /// 所有 Agent（当天）
+    Day,
+}
+
+/// 🔒 SAFETY: 分组花费喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostBreakdown {
+    /// 分组键（会话 ID / Agent / 模型）
+    pub key: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+/// 本地时区当天零点（UTC）喵
+pub fn day_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    let midnight = now.with_timezone(&Local).date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now)
+}
+
+/// 🔒 SAFETY: 费用账本喵
+pub struct CostLedger {
+    conn: Mutex<Connection>,
+}
+
+impl CostLedger {
+    /// 打开（或创建）账本数据库喵
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CostError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).map_err(|e| CostError::Io(e.to_string()))?;
+        }
+        Self::initialize(Connection::open(path)?)
+    }
+
+    /// 内存账本喵（无痕模式：只统计本次会话，不落盘）
+    pub fn in_memory() -> Result<Self, CostError> {
+        Self::initialize(Connection::open_in_memory()?)
+    }
+
+    fn initialize(conn: Connection) -> Result<Self, CostError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cost_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session TEXT NOT NULL,
+                agent TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                cost REAL NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_cost_entries_session ON cost_entries(session);
+            CREATE INDEX IF NOT EXISTS idx_cost_entries_created_at ON cost_entries(created_at);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 记录一次请求喵
+    pub fn record(
+        &self,
+        session: &str,
+        agent: &str,
+        model: &str,
+        usage: &Usage,
+        cost: f64,
+        now: DateTime<Utc>,
+    ) -> Result<(), CostError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO cost_entries (session, agent, model, prompt_tokens, completion_tokens, cost, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![session, agent, model, usage.prompt_tokens, usage.completion_tokens, cost, now.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// 指定范围的累计花费喵
+    pub fn spent(&self, scope: CostScope<'_>, now: DateTime<Utc>) -> Result<f64, CostError> {
+        let today = day_start(now).timestamp();
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let sum = "SELECT COALESCE(SUM(cost), 0) FROM cost_entries";
+        let spent = match scope {
+            CostScope::Session(session) => {
+                conn.query_row(&format!("{} WHERE session = ?1", sum), params![session], |row| row.get(0))?
+            }
+            CostScope::Agent(agent) => conn.query_row(
+                &format!("{} WHERE agent = ?1 AND created_at >= ?2", sum),
+                params![agent, today],
+                |row| row.get(0),
+            )?,
+            CostScope::Day => conn.query_row(&format!("{} WHERE created_at >= ?1", sum), params![today], |row| row.get(0))?,
+        };
+        Ok(spent)
+    }
+
+    /// `since` 之后某个 Agent 的请求数与 token 总数喵
+    pub fn agent_usage(&self, agent: &str, since: DateTime<Utc>) -> Result<(u64, u64), CostError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let (requests, tokens): (i64, i64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(prompt_tokens + completion_tokens), 0)
+             FROM cost_entries WHERE agent = ?1 AND created_at >= ?2",
+            params![agent, since.timestamp()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((requests as u64, tokens as u64))
+    }
+
+    /// `since` 之后某个 Agent 的第 `n` 条请求（从 0 开始，按时间先后）的时间喵
+    pub fn nth_request_since(&self, agent: &str, since: DateTime<Utc>, n: u64) -> Result<Option<DateTime<Utc>>, CostError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let created_at: Option<i64> = conn
+            .query_row(
+                "SELECT created_at FROM cost_entries WHERE agent = ?1 AND created_at >= ?2
+                 ORDER BY created_at, id LIMIT 1 OFFSET ?3",
+                params![agent, since.timestamp(), n as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(created_at.and_then(|t| DateTime::from_timestamp(t, 0)))
+    }
+
+    /// `since` 之后按列分组的花费（花费从高到低）喵
+    ///
+    /// `column` 只能是 `session` / `agent` / `model`
+    pub fn breakdown(&self, column: &str, since: DateTime<Utc>, limit: usize) -> Result<Vec<CostBreakdown>, CostError> {
+        if !matches!(column, "session" | "agent" | "model") {
+            return Ok(Vec::new());
+        }
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {0}, COUNT(*), SUM(prompt_tokens), SUM(completion_tokens), SUM(cost)
+             FROM cost_entries WHERE created_at >= ?1
+             GROUP BY {0} ORDER BY SUM(cost) DESC, MAX(created_at) DESC LIMIT ?2",
+            column
+        ))?;
+        let rows = stmt
+            .query_map(params![since.timestamp(), limit as i64], |row| {
+                Ok(CostBreakdown {
+                    key: row.get(0)?,
+                    requests: row.get::<_, i64>(1)? as u64,
+                    prompt_tokens: row.get::<_, i64>(2)? as u64,
+                    completion_tokens: row.get::<_, i64>(3)? as u64,
+                    cost: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+}
+
+/// 🔒 SAFETY: 单个会话的费用追踪喵
+pub struct CostTracker {
+    ledger: Arc<CostLedger>,
+    config: CostConfig,
+    session: String,
+    agent: String,
+}
+
+impl CostTracker {
+    pub fn new(ledger: Arc<CostLedger>, config: CostConfig, session: &str, agent: &str) -> Self {
+        Self {
+            ledger,
+            config,
+            session: session.to_string(),
+            agent: agent.to_string(),
+        }
+    }
+
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    pub fn ledger(&self) -> Arc<CostLedger> {
+        self.ledger.clone()
+    }
+
+    /// 🔒 SAFETY: 请求前检查预算喵（已达到任一预算时返回 `BudgetExceeded`）
+    pub fn check(&self) -> Result<(), CostError> {
+        let now = Utc::now();
+        let budgets = [
+            (format!("Session {}", self.session), CostScope::Session(&self.session), self.config.session_budget),
+            (
+                format!("Agent {} daily", self.agent),
+                CostScope::Agent(&self.agent),
+                self.config.agent_budgets.get(&self.agent).copied(),
+            ),
+            ("Daily".to_string(), CostScope::Day, self.config.daily_budget),
+        ];
+        for (scope, kind, limit) in budgets {
+            let Some(limit) = limit else { continue };
+            let spent = self.ledger.spent(kind, now)?;
+            if spent >= limit {
+                return Err(CostError::BudgetExceeded { scope, spent, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录一次请求的用量，返回本次费用喵
+    pub fn charge(&self, model: &str, usage: &Usage) -> Result<f64, CostError> {
+        let cost = match self.config.pricing_for(model) {
+            Some(pricing) => pricing.cost(usage),
+            None => {
+                debug!("No pricing configured for model {}, recording tokens only", model);
+                0.0
+            }
+        };
+        self.ledger.record(&self.session, &self.agent, model, usage, cost, Utc::now())?;
+        Ok(cost)
+    }
+
+    /// 本会话的累计花费喵
+    pub fn session_spent(&self) -> Result<f64, CostError> {
+        self.ledger.spent(CostScope::Session(&self.session), Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_tokens: u32, completion_tokens: u32) -> Usage {
+        Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    #[test]
+    fn test_budgets_stop_requests() {
+        let mut config = CostConfig {
+            session_budget: Some(0.05),
+            daily_budget: Some(0.08),
+            ..CostConfig::default()
+        };
+        config.pricing.insert("gpt-4o".to_string(), ModelPricing { input_price: 2.5, output_price: 10.0 });
+        config.pricing.insert("openai/gpt-4o-mini".to_string(), ModelPricing { input_price: 0.15, output_price: 0.6 });
+        assert_eq!(config.pricing_for("openai/gpt-4o").unwrap().input_price, 2.5);
+        assert_eq!(config.pricing_for("gpt-4o-mini-2024-07-18").unwrap().input_price, 0.15);
+        assert!(config.pricing_for("llama3").is_none());
+
+        let ledger = Arc::new(CostLedger::in_memory().unwrap());
+        let first = CostTracker::new(ledger.clone(), config.clone(), "s1", "nia");
+        first.check().unwrap();
+        // 10k 输入 + 2k 输出 = 0.025 + 0.02 喵
+        assert!((first.charge("gpt-4o", &usage(10_000, 2_000)).unwrap() - 0.045).abs() < 1e-9);
+        assert_eq!(first.charge("llama3", &usage(10_000, 2_000)).unwrap(), 0.0);
+        first.check().unwrap();
+        first.charge("gpt-4o", &usage(4_000, 0)).unwrap();
+        assert!(matches!(first.check(), Err(CostError::BudgetExceeded { limit, .. }) if limit == 0.05));
+
+        // 新会话不受会话预算影响，但当天合计仍受限喵
+        let second = CostTracker::new(ledger.clone(), config, "s2", "nia");
+        second.check().unwrap();
+        second.charge("gpt-4o", &usage(12_000, 0)).unwrap();
+        assert!(matches!(second.check(), Err(CostError::BudgetExceeded { ref scope, .. }) if scope == "Daily"));
+
+        let sessions = ledger.breakdown("session", day_start(Utc::now()), 10).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!((sessions[0].key.as_str(), sessions[0].requests), ("s1", 3));
+        assert_eq!(ledger.breakdown("agent", day_start(Utc::now()), 10).unwrap()[0].prompt_tokens, 36_000);
+    }
+}
@@ -0,0 +1,233 @@
+/// Provider 故障转移 🔀
+///
+/// @诺诺 的备用模型链喵
+///
+/// 功能：
+/// - 主模型返回 429 / 5xx、超时或连接失败时，按顺序改用 `fallback` 中的模型
+/// - 每次切换前指数退避（`backoff_ms`、2 倍、4 倍……）
+/// - 每次切换写入 telemetry 事件 `provider_failover`（from / to / reason）
+///
+/// ```toml
+/// [agents.defaults.model]
+/// primary = "gpt-4o"
+/// fallback = ["gpt-4o-mini", "deepseek-chat"]
+/// backoff_ms = 500
+/// ```
+///
+/// 🔒 SAFETY: 认证失败、上下文超限等非临时错误不切换，原样返回
+use super::openai::{ChatRequest, ChatResponse, ProviderError};
+use super::chat::ChatProvider;
+use crate::telemetry::MetricsRecorder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// 切换事件名
+pub const FAILOVER_EVENT: &str = "provider_failover";
+
+/// 退避倍数的指数上限（最多 backoff × 32）
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+/// Agents 配置喵（OpenClaw 兼容的 `agents.defaults` / `agents.agent.<name>`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentsConfig {
+    #[serde(default)]
+    pub defaults: AgentDefaults,
+    /// 未指定 `--agent` 时使用的 Agent
+    #[serde(default)]
+    pub default: Option<String>,
+    /// 命名的 Agent 人设
+    #[serde(default)]
+    pub agent: BTreeMap<String, crate::core::AgentProfile>,
+}
+
+/// Agent 默认配置喵
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentDefaults {
+    #[serde(default)]
+    pub model: AgentModelConfig,
+}
+
+/// 🔒 SAFETY: 主模型与备用模型链喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentModelConfig {
+    /// 主模型（未设置时使用 `default_model`）
+    #[serde(default)]
+    pub primary: Option<String>,
+    /// 备用模型，按顺序尝试
+    #[serde(default)]
+    pub fallback: Vec<String>,
+    /// 第一次切换前的等待（毫秒），之后每次翻倍
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+impl Default for AgentModelConfig {
+    fn default() -> Self {
+        Self {
+            primary: None,
+            fallback: Vec::new(),
+            backoff_ms: default_backoff_ms(),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 按备用模型链发送请求喵
+#[derive(Debug, Clone)]
+pub struct FailoverChain {
+    fallback: Vec<String>,
+    backoff: Duration,
+    recorder: Option<MetricsRecorder>,
+}
+
+impl FailoverChain {
+    pub fn new(config: &AgentModelConfig) -> Self {
+        Self {
+            fallback: config.fallback.clone(),
+            backoff: Duration::from_millis(config.backoff_ms),
+            recorder: None,
+        }
+    }
+
+    /// 把切换事件写入 telemetry喵
+    pub fn with_recorder(mut self, recorder: MetricsRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 发送聊天请求，主模型临时不可用时切换到备用模型喵
+    pub async fn chat(&self, provider: &dyn ChatProvider, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        self.run(request, |request| async move { provider.chat(&request).await })
+            .await
+    }
+
+    /// 故障转移核心：`call` 负责发送单个模型的请求喵
+    ///
+    /// ## Returns
+    /// 第一个成功的响应；全部失败时返回最后一个错误
+    pub async fn run<F, Fut>(&self, request: &ChatRequest, mut call: F) -> Result<ChatResponse, ProviderError>
+    where
+        F: FnMut(ChatRequest) -> Fut,
+        Fut: Future<Output = Result<ChatResponse, ProviderError>>,
+    {
+        let mut from = request.model.clone().unwrap_or_default();
+        let mut result = call(request.clone()).await;
+        let candidates = self.fallback.iter().filter(|model| **model != from).cloned().collect::<Vec<_>>();
+        for (attempt, model) in candidates.into_iter().enumerate() {
+            let reason = match &result {
+                Err(e) => match e.transient_reason() {
+                    Some(reason) => reason,
+                    None => break,
+                },
+                Ok(_) => break,
+            };
+            let delay = self.backoff * 2_u32.pow((attempt as u32).min(MAX_BACKOFF_EXPONENT));
+            warn!("🔀 模型 {} 不可用（{}），{:?} 后改用 {} 喵", from, reason, delay, model);
+            if let Some(recorder) = &self.recorder {
+                recorder.event(
+                    FAILOVER_EVENT,
+                    &[("from", from.as_str()), ("to", model.as_str()), ("reason", reason.as_str())],
+                );
+            }
+            tokio::time::sleep(delay).await;
+
+            let mut next = request.clone();
+            next.model = Some(model.clone());
+            result = call(next).await;
+            from = model;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{MetricsCollector, MetricsConfig};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    fn response(model: &str) -> ChatResponse {
+        serde_json::from_value(json!({
+            "id": "1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": model,
+            "choices": [],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_next_model() {
+        let metrics = Arc::new(
+            MetricsCollector::new(MetricsConfig {
+                db_path: ":memory:".to_string(),
+                monitor_interval_sec: 5,
+            })
+            .await
+            .unwrap(),
+        );
+        let config: AgentModelConfig = toml::from_str(
+            "primary = \"big\"\nfallback = [\"big\", \"medium\", \"small\"]\nbackoff_ms = 0",
+        )
+        .unwrap();
+        let chain = FailoverChain::new(&config).with_recorder(MetricsRecorder::new(metrics.clone()));
+        let request = ChatRequest {
+            model: Some("big".to_string()),
+            messages: Vec::new(),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            tools: None,
+        };
+
+        let tried = Mutex::new(Vec::new());
+        let result = chain
+            .run(&request, |request| {
+                let model = request.model.unwrap();
+                tried.lock().unwrap().push(model.clone());
+                async move {
+                    match model.as_str() {
+                        "big" => Err(ProviderError::Unavailable(503, "overloaded".to_string())),
+                        "medium" => Err(ProviderError::Timeout),
+                        _ => Ok(response(&model)),
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap().model, "small");
+        assert_eq!(*tried.lock().unwrap(), ["big", "medium", "small"]);
+
+        let mut events: Vec<(String, String)> = metrics
+            .get_recent_custom_metrics(Some(FAILOVER_EVENT), 10)
+            .unwrap()
+            .iter()
+            .map(|e| (e.labels["to"].clone(), e.labels["reason"].clone()))
+            .collect();
+        events.sort();
+        assert_eq!(
+            events,
+            [("medium".to_string(), "http_503".to_string()), ("small".to_string(), "timeout".to_string())]
+        );
+
+        // 非临时错误不切换喵
+        tried.lock().unwrap().clear();
+        let result = chain
+            .run(&request, |request| {
+                tried.lock().unwrap().push(request.model.unwrap());
+                async { Err(ProviderError::AuthError) }
+            })
+            .await;
+        assert!(matches!(result, Err(ProviderError::AuthError)));
+        assert_eq!(tried.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,647 @@
+use super::chat::{text_stream, ChatProvider, ChatStream, StreamFraming};
+use super::openai::{
+    ChatRequest, ChatResponse, Choice, FunctionCall, Message, ProviderError, ToolCall, ToolSpec,
+    Usage as ChatUsage,
+};
+/// Gemini Provider 实现模块 ✨
+///
+/// @诺诺 的 Google Generative Language API 客户端实现喵
+///
+/// 功能：
+/// - `generateContent` 对话与 `streamGenerateContent`（SSE）流式输出
+/// - 系统提示走 `systemInstruction`
+/// - 原生函数调用（`functionCall` / `functionResponse` 部件），
+///   通过 `ChatProvider` 接受 OpenAI 格式的请求并返回 OpenAI 格式的响应，Agent 工具循环无需区分 provider
+/// - `countTokens` 精确计算输入 token 数
+///
+/// 🔒 SAFETY: API Key 只放在 `x-goog-api-key` 请求头，不拼进 URL（避免出现在日志中）
+///
+/// 实现者: 诺诺 (Nono) ⚡
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 未指定模型时使用的默认模型
+const DEFAULT_MODEL: &str = "gemini-1.5-flash";
+
+/// 🔒 SAFETY: Gemini 配置结构体喵
+#[derive(Debug, Clone)]
+pub struct GeminiConfig {
+    /// 🔐 PERMISSION: API Key，必须通过安全模块加载
+    pub api_key: String,
+    /// API 基础 URL
+    pub base_url: String,
+    /// 请求超时时间（秒）
+    pub timeout: u64,
+    /// 最大重试次数
+    pub max_retries: u8,
+}
+
+impl Default for GeminiConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            base_url: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            timeout: 30,
+            max_retries: 3,
+        }
+    }
+}
+
+/// 🔒 SAFETY: Gemini 请求结构喵
+/// 遵循 Generative Language API v1beta 规范
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiRequest {
+    /// 对话内容（role 为 user / model）
+    pub contents: Vec<GeminiContent>,
+    /// 系统提示
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<GeminiContent>,
+    /// 可调用的函数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<GeminiTools>>,
+    /// 采样参数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GenerationConfig>,
+}
+
+/// 🔒 SAFETY: 一条对话内容（由多个部件组成）喵
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GeminiContent {
+    /// 角色（user / model；systemInstruction 不带角色）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<GeminiPart>,
+}
+
+impl GeminiContent {
+    /// 🔒 SAFETY: 纯文本内容喵
+    pub fn text(role: Option<&str>, text: String) -> Self {
+        Self {
+            role: role.map(str::to_string),
+            parts: vec![GeminiPart::text(text)],
+        }
+    }
+}
+
+/// 🔒 SAFETY: 内容部件喵（文本、函数调用、函数结果三选一）
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiPart {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// 模型发起的函数调用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<GeminiFunctionCall>,
+    /// 函数执行结果（放在 user 内容中）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    fn text(text: String) -> Self {
+        Self {
+            text: Some(text),
+            ..Self::default()
+        }
+    }
+}
+
+/// 🔒 SAFETY: 函数调用喵（Gemini 没有调用 ID，按名称对应结果）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GeminiFunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: JsonValue,
+}
+
+/// 🔒 SAFETY: 函数执行结果喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GeminiFunctionResponse {
+    pub name: String,
+    /// 结果必须是 JSON object
+    pub response: JsonValue,
+}
+
+/// 🔒 SAFETY: 函数声明列表喵
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiTools {
+    pub function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+/// 🔒 SAFETY: 函数声明（名称 + 描述 + OpenAPI 子集的参数 schema）喵
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GeminiFunctionDeclaration {
+    pub name: String,
+    pub description: String,
+    pub parameters: JsonValue,
+}
+
+impl From<&ToolSpec> for GeminiFunctionDeclaration {
+    fn from(spec: &ToolSpec) -> Self {
+        Self {
+            name: spec.function.name.clone(),
+            description: spec.function.description.clone(),
+            parameters: strip_unsupported_schema(&spec.function.parameters),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 去掉 Gemini 不接受的 JSON Schema 关键字喵（否则整个请求 400）
+fn strip_unsupported_schema(schema: &JsonValue) -> JsonValue {
+    match schema {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "$schema" | "additionalProperties" | "default"))
+                .map(|(key, value)| (key.clone(), strip_unsupported_schema(value)))
+                .collect(),
+        ),
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(strip_unsupported_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 🔒 SAFETY: 采样参数喵
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+}
+
+impl GeminiRequest {
+    /// 🔒 SAFETY: 由 OpenAI 格式的请求转换喵
+    ///
+    /// - system 消息合并为 `systemInstruction`
+    /// - 助手的结构化工具调用转为 `functionCall` 部件，`tool` 消息转为 user 内容中的 `functionResponse` 部件
+    /// - 相邻的同角色内容合并（Gemini 要求 user / model 交替）
+    pub fn from_chat(request: &ChatRequest) -> Self {
+        let mut system = Vec::new();
+        let mut contents: Vec<GeminiContent> = Vec::new();
+        // functionResponse 需要函数名，按调用 ID 找回喵
+        let mut call_names: HashMap<&str, &str> = HashMap::new();
+        for message in &request.messages {
+            let (role, parts) = match message.role.as_str() {
+                "system" => {
+                    system.push(message.content.clone());
+                    continue;
+                }
+                "assistant" => {
+                    let mut parts = Vec::new();
+                    if !message.content.is_empty() {
+                        parts.push(GeminiPart::text(message.content.clone()));
+                    }
+                    for call in message.tool_calls.iter().flatten() {
+                        call_names.insert(&call.id, &call.function.name);
+                        let args = serde_json::from_str(&call.function.arguments)
+                            .ok()
+                            .filter(JsonValue::is_object)
+                            .unwrap_or_else(|| serde_json::json!({}));
+                        parts.push(GeminiPart {
+                            function_call: Some(GeminiFunctionCall {
+                                name: call.function.name.clone(),
+                                args,
+                            }),
+                            ..GeminiPart::default()
+                        });
+                    }
+                    ("model", parts)
+                }
+                "tool" => {
+                    let name = message
+                        .tool_call_id
+                        .as_deref()
+                        .and_then(|id| call_names.get(id))
+                        .copied()
+                        .unwrap_or_default();
+                    let part = GeminiPart {
+                        function_response: Some(GeminiFunctionResponse {
+                            name: name.to_string(),
+                            response: serde_json::json!({ "content": message.content }),
+                        }),
+                        ..GeminiPart::default()
+                    };
+                    ("user", vec![part])
+                }
+                _ => ("user", vec![GeminiPart::text(message.content.clone())]),
+            };
+            if parts.is_empty() {
+                continue;
+            }
+            match contents.last_mut() {
+                Some(last) if last.role.as_deref() == Some(role) => last.parts.extend(parts),
+                _ => contents.push(GeminiContent {
+                    role: Some(role.to_string()),
+                    parts,
+                }),
+            }
+        }
+
+        let generation_config = GenerationConfig {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            max_output_tokens: request.max_tokens,
+        };
+        Self {
+            contents,
+            system_instruction: Some(system.join("\n\n"))
+                .filter(|s| !s.is_empty())
+                .map(|s| GeminiContent::text(None, s)),
+            tools: request.tools.as_ref().filter(|tools| !tools.is_empty()).map(|tools| {
+                vec![GeminiTools {
+                    function_declarations: tools.iter().map(GeminiFunctionDeclaration::from).collect(),
+                }]
+            }),
+            generation_config: Some(generation_config).filter(|c| *c != GenerationConfig::default()),
+        }
+    }
+}
+
+/// 🔒 SAFETY: Gemini 错误结构体喵
+#[derive(Debug, Deserialize)]
+pub struct GeminiError {
+    pub error: GeminiErrorDetail,
+}
+
+/// 🔒 SAFETY: Gemini 错误详情结构体喵
+#[derive(Debug, Deserialize)]
+pub struct GeminiErrorDetail {
+    pub message: String,
+}
+
+/// 🔒 SAFETY: Gemini 响应结构体喵（流式输出的每个事件也是这个结构）
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiResponse {
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+    #[serde(default)]
+    pub usage_metadata: Option<UsageMetadata>,
+    #[serde(default)]
+    pub model_version: Option<String>,
+}
+
+/// 🔒 SAFETY: 候选回复喵
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    #[serde(default)]
+    pub content: GeminiContent,
+    /// 停止原因（STOP / MAX_TOKENS / SAFETY ...）
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+/// 🔒 SAFETY: 使用情况结构体喵
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    #[serde(default)]
+    pub candidates_token_count: u32,
+    #[serde(default)]
+    pub total_token_count: u32,
+}
+
+impl GeminiResponse {
+    /// 🔒 SAFETY: 第一个候选的文本喵
+    pub fn text(&self) -> String {
+        self.candidates
+            .first()
+            .map(|c| c.content.parts.iter().filter_map(|p| p.text.as_deref()).collect())
+            .unwrap_or_default()
+    }
+
+    /// 🔒 SAFETY: 转换为 OpenAI 格式的响应喵（`functionCall` 部件转为结构化工具调用）
+    pub fn into_chat_response(self, model: &str) -> ChatResponse {
+        let text = self.text();
+        let candidate = self.candidates.into_iter().next();
+        let finish_reason = candidate.as_ref().and_then(|c| c.finish_reason.clone());
+        let tool_calls: Vec<ToolCall> = candidate
+            .into_iter()
+            .flat_map(|c| c.content.parts)
+            .filter_map(|part| part.function_call)
+            .enumerate()
+            .map(|(index, call)| ToolCall {
+                // Gemini 不返回调用 ID，按序号生成喵
+                id: format!("call_{}", index),
+                kind: "function".to_string(),
+                function: FunctionCall {
+                    name: call.name,
+                    arguments: call.args.to_string(),
+                },
+            })
+            .collect();
+        let finish_reason = match finish_reason.as_deref() {
+            _ if !tool_calls.is_empty() => "tool_calls",
+            Some("MAX_TOKENS") => "length",
+            Some("SAFETY") | Some("RECITATION") | Some("PROHIBITED_CONTENT") => "content_filter",
+            _ => "stop",
+        };
+        let usage = self.usage_metadata.unwrap_or_default();
+
+        ChatResponse {
+            id: format!("gemini-{}", uuid::Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp().max(0) as u64,
+            model: self.model_version.unwrap_or_else(|| model.to_string()),
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text).with_tool_calls(Some(tool_calls)),
+                finish_reason: Some(finish_reason.to_string()),
+            }],
+            usage: ChatUsage {
+                prompt_tokens: usage.prompt_token_count,
+                completion_tokens: usage.candidates_token_count,
+                total_tokens: usage.total_token_count,
+            },
+        }
+    }
+}
+
+/// 🔒 SAFETY: Gemini 客户端结构体喵
+#[derive(Debug, Clone)]
+pub struct GeminiClient {
+    /// HTTP 客户端
+    client: Client,
+    /// 配置
+    config: GeminiConfig,
+    /// 请求未指定模型时使用的模型
+    default_model: String,
+}
+
+impl GeminiClient {
+    /// 🔒 SAFETY: 创建新的 Gemini 客户端喵
+    pub fn new(config: GeminiConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            config,
+            default_model: DEFAULT_MODEL.to_string(),
+        }
+    }
+
+    /// 🔒 SAFETY: 设置默认模型喵
+    pub fn with_default_model(mut self, model: &str) -> Self {
+        self.default_model = model.to_string();
+        self
+    }
+
+    /// 🔒 SAFETY: 模型端点 URL 喵（兼容带 `models/` 前缀的模型名）
+    fn endpoint(&self, model: &str, method: &str) -> String {
+        let model = model.strip_prefix("models/").unwrap_or(model);
+        format!("{}/models/{}:{}", self.config.base_url, model, method)
+    }
+
+    /// 🔒 SAFETY: 发送请求并检查状态码喵
+    /// 异常处理: 认证错误、限流与 5xx（可切换备用模型）、其他 API 错误
+    async fn post<T: Serialize>(&self, url: &str, body: &T) -> Result<reqwest::Response, ProviderError> {
+        let response = self
+            .client
+            .post(url)
+            .header("x-goog-api-key", &self.config.api_key)
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        if status.as_u16() == 401 || status.as_u16() == 403 {
+            return Err(ProviderError::AuthError);
+        }
+
+        let error_text = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<GeminiError>(&error_text)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| format!("HTTP {}: {}", status, error_text));
+        if status.as_u16() == 429 || status.is_server_error() {
+            Err(ProviderError::Unavailable(status.as_u16(), message))
+        } else {
+            Err(ProviderError::ApiError(message))
+        }
+    }
+
+    /// 🔒 SAFETY: 发送对话请求（带重试）喵
+    async fn send_request_with_retry(
+        &self,
+        request: &GeminiRequest,
+        model: &str,
+    ) -> Result<GeminiResponse, ProviderError> {
+        let url = self.endpoint(model, "generateContent");
+        let mut last_error = None;
+
+        for attempt in 0..=self.config.max_retries {
+            match self.post(&url, request).await {
+                Ok(response) => return response.json().await.map_err(ProviderError::from),
+                Err(e) => {
+                    // 认证错误和请求错误不重试
+                    let retryable = e.transient_reason().is_some();
+                    last_error = Some(e);
+                    if !retryable {
+                        break;
+                    }
+                    if attempt < self.config.max_retries {
+                        // 指数退避
+                        tokio::time::sleep(Duration::from_millis(100 * (2_u64.pow(attempt as u32)))).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ProviderError::ApiError("Unknown error".to_string())))
+    }
+}
+
+/// 🔒 SAFETY: Gemini 客户端公开接口喵
+impl GeminiClient {
+    /// 🔒 SAFETY: 聊天接口喵
+    /// 异常处理: 所有错误返回 ProviderError
+    pub async fn chat_api(&self, request: &GeminiRequest, model: &str) -> Result<GeminiResponse, ProviderError> {
+        self.send_request_with_retry(request, model).await
+    }
+
+    /// 🔒 SAFETY: 快捷接口喵
+    /// 直接发送用户消息
+    pub async fn chat_simple(&self, prompt: &str) -> Result<String, ProviderError> {
+        let request = GeminiRequest {
+            contents: vec![GeminiContent::text(Some("user"), prompt.to_string())],
+            ..GeminiRequest::default()
+        };
+        Ok(self.chat_api(&request, &self.default_model).await?.text())
+    }
+
+    /// 🔒 SAFETY: 带系统提示的聊天喵
+    pub async fn chat_with_system(
+        &self,
+        system: &str,
+        prompt: &str,
+    ) -> Result<String, ProviderError> {
+        let request = GeminiRequest {
+            contents: vec![GeminiContent::text(Some("user"), prompt.to_string())],
+            system_instruction: Some(GeminiContent::text(None, system.to_string())),
+            ..GeminiRequest::default()
+        };
+        Ok(self.chat_api(&request, &self.default_model).await?.text())
+    }
+}
+
+/// Gemini `countTokens` 请求（`generateContentRequest` 形式才能带上系统提示与工具）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CountTokensRequest {
+    generate_content_request: CountTokensContent,
+}
+
+#[derive(Debug, Serialize)]
+struct CountTokensContent {
+    model: String,
+    #[serde(flatten)]
+    request: GeminiRequest,
+}
+
+/// Gemini `countTokens` 响应
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CountTokensResponse {
+    total_tokens: usize,
+}
+
+#[async_trait]
+impl ChatProvider for GeminiClient {
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        let model = request.model.as_deref().unwrap_or(&self.default_model);
+        let gemini_request = GeminiRequest::from_chat(request);
+        Ok(self.chat_api(&gemini_request, model).await?.into_chat_response(model))
+    }
+
+    /// 调用 `streamGenerateContent?alt=sse`，逐个返回文本片段
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<ChatStream, ProviderError> {
+        let model = request.model.as_deref().unwrap_or(&self.default_model);
+        let url = format!("{}?alt=sse", self.endpoint(model, "streamGenerateContent"));
+        let response = self.post(&url, &GeminiRequest::from_chat(request)).await?;
+        Ok(text_stream(response, StreamFraming::Sse, |data| {
+            Ok(Some(serde_json::from_str::<GeminiResponse>(data)?.text()))
+        }))
+    }
+
+    /// 调用 `countTokens`（包含系统提示与工具定义），与计费口径一致
+    async fn count_tokens(&self, request: &ChatRequest) -> Result<usize, ProviderError> {
+        let model = request.model.as_deref().unwrap_or(&self.default_model);
+        let model = model.strip_prefix("models/").unwrap_or(model);
+        let body = CountTokensRequest {
+            generate_content_request: CountTokensContent {
+                model: format!("models/{}", model),
+                request: GeminiRequest {
+                    generation_config: None,
+                    ..GeminiRequest::from_chat(request)
+                },
+            },
+        };
+        let response = self.post(&self.endpoint(model, "countTokens"), &body).await?;
+        Ok(response.json::<CountTokensResponse>().await?.total_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_function_call_roundtrip() {
+        let call = ToolCall {
+            id: "call_7".to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall {
+                name: "fs_read".to_string(),
+                arguments: r#"{"path": "a.txt"}"#.to_string(),
+            },
+        };
+        let chat = ChatRequest {
+            model: None,
+            messages: vec![
+                Message::system("persona".to_string()),
+                Message::user("read a.txt".to_string()),
+                Message::assistant(String::new()).with_tool_calls(Some(vec![call])),
+                Message::tool("call_7".to_string(), "hello".to_string()),
+                Message::user("thanks".to_string()),
+            ],
+            temperature: Some(0.5),
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            tools: None,
+        };
+
+        let body = serde_json::to_value(GeminiRequest::from_chat(&chat)).unwrap();
+        assert_eq!(body["systemInstruction"], json!({"parts": [{"text": "persona"}]}));
+        assert_eq!(body["generationConfig"], json!({"temperature": 0.5}));
+        let contents = body["contents"].as_array().unwrap();
+        // 函数结果与下一条用户消息合并为同一个 user 内容喵
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["parts"][0]["functionCall"], json!({"name": "fs_read", "args": {"path": "a.txt"}}));
+        assert_eq!(contents[2]["parts"][0]["functionResponse"]["name"], "fs_read");
+        assert_eq!(contents[2]["parts"][1]["text"], "thanks");
+
+        let response: GeminiResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"functionCall": {"name": "fs_read", "args": {"path": "b.txt"}}}]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 5, "totalTokenCount": 15}
+        }))
+        .unwrap();
+        let chat_response = response.into_chat_response("gemini-1.5-pro");
+        let choice = &chat_response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        let calls = choice.message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].function.name, "fs_read");
+        assert_eq!(calls[0].function.arguments, r#"{"path":"b.txt"}"#);
+        assert_eq!(chat_response.usage.total_tokens, 15);
+    }
+
+    #[test]
+    fn test_count_tokens_request_shape() {
+        let chat = ChatRequest {
+            model: None,
+            messages: vec![Message::system("persona".to_string()), Message::user("hi".to_string())],
+            temperature: Some(0.5),
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            tools: None,
+        };
+        let body = CountTokensRequest {
+            generate_content_request: CountTokensContent {
+                model: "models/gemini-1.5-pro".to_string(),
+                request: GeminiRequest {
+                    generation_config: None,
+                    ..GeminiRequest::from_chat(&chat)
+                },
+            },
+        };
+
+        let body = serde_json::to_value(body).unwrap();
+        let inner = &body["generateContentRequest"];
+        assert_eq!(inner["model"], "models/gemini-1.5-pro");
+        assert_eq!(inner["systemInstruction"], json!({"parts": [{"text": "persona"}]}));
+        assert!(inner.get("generationConfig").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+/// Provider 健康探测 🩺
+///
+/// @诺诺 的上游可用性主动探测喵
+///
+/// 功能：
+/// - 每隔 N 分钟向每个已配置的 provider 发送一次极小的请求（max_tokens = 1）
+/// - 结果写入 telemetry（`provider_up` / `provider_latency_ms`），Dashboard 据此展示
+/// - Gateway `/health` 返回各 provider 的最新状态，任一不可用时整体为 `degraded`
+/// - 变为不可用时推送出站 Webhook 的 `service_down` 事件（只含 provider 名称）
+///
+/// ```toml
+/// [provider_health]
+/// enabled = true
+/// interval_minutes = 5
+/// timeout_secs = 10
+/// ```
+///
+/// 🔒 SAFETY: 上游错误详情只进本地 telemetry，不出现在公开的 `/health` 中
+use super::openai::{ChatRequest, Message, OpenAIClient, OpenAIConfig};
+use crate::core::traits::{Config, ProviderConfig};
+use crate::gateway::webhook::{AgentEventKind, WebhookNotifier};
+use crate::telemetry::MetricsRecorder;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 探测结果在线状态指标名
+pub const UP_METRIC: &str = "provider_up";
+/// 探测延迟指标名
+pub const LATENCY_METRIC: &str = "provider_latency_ms";
+
+/// 写入 telemetry 的错误信息最大长度
+const MAX_ERROR_LEN: usize = 200;
+
+fn default_enabled() -> bool {
+    true
+}
+fn default_interval_minutes() -> u64 {
+    5
+}
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// 🔒 SAFETY: 健康探测配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderHealthConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 探测间隔（分钟）
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+    /// 单次探测超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 探测使用的模型（默认 `default_model`）
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl Default for ProviderHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_minutes: default_interval_minutes(),
+            timeout_secs: default_timeout_secs(),
+            model: None,
+        }
+    }
+}
+
+impl ProviderHealthConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_minutes.max(1) * 60)
+    }
+}
+
+/// 🔒 SAFETY: 单个 provider 的最新探测结果喵
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeResult {
+    pub provider: String,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    pub checked_at: DateTime<Utc>,
+    /// 连续失败次数
+    pub consecutive_failures: u32,
+    /// 失败原因（不对外公开）
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+/// 🔒 SAFETY: Provider 健康探测器喵
+#[derive(Debug)]
+pub struct ProviderHealth {
+    targets: Vec<(String, OpenAIClient)>,
+    model: String,
+    results: RwLock<BTreeMap<String, ProbeResult>>,
+    recorder: Option<MetricsRecorder>,
+    notifier: Option<WebhookNotifier>,
+}
+
+impl ProviderHealth {
+    /// 🔒 SAFETY: 为所有已配置的 provider 创建探测器喵（探测不重试）
+    pub fn from_config(config: &Config) -> Self {
+        let settings = &config.provider_health;
+        let providers = config.providers.as_ref();
+        let configured: [(&str, Option<&ProviderConfig>); 3] = [
+            ("nvidia", providers.and_then(|p| p.nvidia.as_ref())),
+            ("openai", providers.and_then(|p| p.openai.as_ref())),
+            ("openrouter", providers.and_then(|p| p.openrouter.as_ref())),
+        ];
+        let targets = configured
+            .into_iter()
+            .filter_map(|(name, provider)| {
+                provider?;
+                // 经 Config::provider 取得，`enc:` API Key 已解密喵
+                let provider = config.provider(name)?;
+                let client = OpenAIClient::new(OpenAIConfig {
+                    api_key: provider.api_key.clone(),
+                    base_url: provider.base_url.clone(),
+                    timeout: settings.timeout_secs,
+                    max_retries: 0,
+                });
+                Some((name.to_string(), client))
+            })
+            .collect();
+        Self {
+            targets,
+            model: settings.model.clone().unwrap_or_else(|| config.default_model.clone()),
+            results: RwLock::new(BTreeMap::new()),
+            recorder: None,
+            notifier: None,
+        }
+    }
+
+    /// 把探测结果写入 telemetry喵
+    pub fn with_recorder(mut self, recorder: MetricsRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// provider 变为不可用时推送 `service_down` 事件喵
+    pub fn with_notifier(mut self, notifier: WebhookNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// 是否有需要探测的 provider喵
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// 🔒 SAFETY: 并发探测所有 provider喵
+    pub async fn probe_all(&self) {
+        let probes = self.targets.iter().map(|(name, client)| async move {
+            let request = ChatRequest {
+                model: Some(self.model.clone()),
+                messages: vec![Message::user("ping".to_string())],
+                temperature: Some(0.0),
+                top_p: None,
+                max_tokens: Some(1),
+                stream: Some(false),
+                tools: None,
+            };
+            let started = Instant::now();
+            let result = client.chat_api(&request).await;
+            (name, started.elapsed(), result.err().map(|e| e.to_string()))
+        });
+        for (name, elapsed, error) in futures::future::join_all(probes).await {
+            self.update(name, elapsed, error, Utc::now());
+        }
+    }
+
+    /// 记录一次探测结果喵
+    fn update(&self, provider: &str, elapsed: Duration, error: Option<String>, now: DateTime<Utc>) {
+        let mut results = self.results.write().unwrap();
+        let previous = results.get(provider);
+        let was_healthy = previous.is_none_or(|p| p.healthy);
+        let consecutive_failures = match &error {
+            Some(_) => previous.map_or(0, |p| p.consecutive_failures) + 1,
+            None => 0,
+        };
+        let latency_ms = elapsed.as_millis() as u64;
+
+        match (&error, was_healthy) {
+            (Some(e), true) => {
+                warn!("🩺 Provider {} is down: {}", provider, e);
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(
+                        AgentEventKind::ServiceDown,
+                        serde_json::json!({ "provider": provider, "checked_at": now.to_rfc3339() }),
+                    );
+                }
+            }
+            (None, false) => info!("🩺 Provider {} recovered ({}ms)", provider, latency_ms),
+            _ => {}
+        }
+        if let Some(recorder) = &self.recorder {
+            let mut labels = vec![("provider", provider)];
+            let error_label: Option<String> = error.as_ref().map(|e| e.chars().take(MAX_ERROR_LEN).collect());
+            if let Some(e) = &error_label {
+                labels.push(("error", e.as_str()));
+            }
+            recorder.gauge(UP_METRIC, if error.is_none() { 1.0 } else { 0.0 }, &labels);
+            recorder.gauge(LATENCY_METRIC, latency_ms as f64, &[("provider", provider)]);
+        }
+
+        results.insert(
+            provider.to_string(),
+            ProbeResult {
+                provider: provider.to_string(),
+                healthy: error.is_none(),
+                latency_ms,
+                checked_at: now,
+                consecutive_failures,
+                error,
+            },
+        );
+    }
+
+    /// 各 provider 的最新状态喵（尚未探测的不出现）
+    pub fn snapshot(&self) -> Vec<ProbeResult> {
+        self.results.read().unwrap().values().cloned().collect()
+    }
+
+    /// 是否有 provider 不可用喵
+    pub fn is_degraded(&self) -> bool {
+        self.results.read().unwrap().values().any(|r| !r.healthy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consecutive_failures_and_recovery() {
+        let health = ProviderHealth::from_config(&Config::default());
+        let now = Utc::now();
+        assert!(health.snapshot().is_empty());
+
+        health.update("nvidia", Duration::from_millis(120), None, now);
+        health.update("openai", Duration::from_millis(900), Some("HTTP 503".to_string()), now);
+        health.update("openai", Duration::from_millis(900), Some("HTTP 503".to_string()), now);
+        assert!(health.is_degraded());
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[1].consecutive_failures, 2);
+        // 错误详情不会出现在公开的 JSON 中喵
+        assert!(!serde_json::to_string(&snapshot).unwrap().contains("503"));
+
+        health.update("openai", Duration::from_millis(80), None, now);
+        assert!(!health.is_degraded());
+        assert_eq!(health.snapshot()[1].consecutive_failures, 0);
+    }
+}
@@ -1,4 +1,12 @@
 pub mod anthropic;
+pub mod catalog;
+pub mod chat;
+pub mod context;
+pub mod cost;
+pub mod failover;
+pub mod gemini;
+pub mod health;
+pub mod ollama;
 /// Provider 适配器模块导出 🤖
 ///
 /// @诺诺 的 Provider 模块统一入口喵
@@ -6,28 +14,40 @@ pub mod anthropic;
 /// 功能：
 /// - 导出所有 Provider 实现
 /// - 统一错误处理
-/// - Provider 工厂函数
+/// - `ChatProvider` trait 与 Provider 工厂函数
 ///
 /// 🔒 SAFETY: 模块级访问控制，防止非法访问
 ///
 /// 模块作者: 诺诺 (Nono) ⚡
 pub mod openai;
 pub mod openrouter;
+pub mod tokenizer;
 
 // 🔒 SAFETY: 重新导出公共接口喵
 pub use anthropic::{
     AnthropicClient, AnthropicConfig, ClaudeRequest, ClaudeResponse, ContentBlock,
 };
+pub use chat::{ChatProvider, ModelProvider};
+pub use cost::{CostConfig, CostError, CostLedger, CostTracker};
+pub use gemini::{GeminiClient, GeminiConfig};
 pub use openai::{
-    ChatRequest, ChatResponse, Choice, Message, OpenAIClient, OpenAIConfig, OpenAIError, Usage,
+    ChatRequest, ChatResponse, Choice, FunctionCall, FunctionSpec, Message, OpenAIClient,
+    OpenAIConfig, OpenAIError, OpenAIModel, ToolCall, ToolSpec, Usage,
 };
 pub use openrouter::{
     ModelInfo, OpenRouterClient, OpenRouterConfig, OpenRouterRequest, Pricing, ProviderPreference,
 };
 
+pub use failover::{AgentsConfig, FailoverChain};
+pub use health::{ProbeResult, ProviderHealth, ProviderHealthConfig};
+pub use ollama::{OllamaClient, OllamaConfig};
+pub use tokenizer::{TokenCounter, TokenizerConfig};
+
 // 🔒 SAFETY: 统一错误类型喵
 pub use openai::ProviderError;
 
+use std::sync::Arc;
+
 // 🔒 SAFETY: 为了兼容性，定义类型别名
 pub type ProviderManager = ProviderFactory;
 
@@ -41,16 +61,23 @@ pub enum ProviderType {
     Anthropic,
     /// OpenRouter（聚合提供商）
     OpenRouter,
+    /// Google Gemini（Generative Language API）
+    Gemini,
+    /// 本地 Ollama
+    Ollama,
 }
 
 impl ProviderType {
     /// 🔒 SAFETY: 从字符串解析 Provider 类型喵
     /// 支持大小写不敏感
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "openai" | "gpt" => Some(ProviderType::OpenAI),
             "anthropic" | "claude" => Some(ProviderType::Anthropic),
             "openrouter" => Some(ProviderType::OpenRouter),
+            "gemini" | "google" => Some(ProviderType::Gemini),
+            "ollama" => Some(ProviderType::Ollama),
             _ => None,
         }
     }
@@ -61,6 +88,8 @@ impl ProviderType {
             ProviderType::OpenAI => "openai",
             ProviderType::Anthropic => "anthropic",
             ProviderType::OpenRouter => "openrouter",
+            ProviderType::Gemini => "gemini",
+            ProviderType::Ollama => "ollama",
         }
     }
 }
@@ -75,6 +104,10 @@ pub struct ProviderFactory {
     anthropic_config: Option<AnthropicConfig>,
     /// OpenRouter 配置
     openrouter_config: Option<OpenRouterConfig>,
+    /// Gemini 配置
+    gemini_config: Option<GeminiConfig>,
+    /// Ollama 配置
+    ollama_config: Option<OllamaConfig>,
 }
 
 impl Default for ProviderFactory {
@@ -84,6 +117,8 @@ impl Default for ProviderFactory {
             openai_config: None,
             anthropic_config: None,
             openrouter_config: None,
+            gemini_config: None,
+            ollama_config: None,
         }
     }
 }
@@ -113,6 +148,18 @@ impl ProviderFactory {
         self
     }
 
+    /// 🔒 SAFETY: 设置 Gemini 配置喵
+    pub fn with_gemini_config(mut self, config: GeminiConfig) -> Self {
+        self.gemini_config = Some(config);
+        self
+    }
+
+    /// 🔒 SAFETY: 设置 Ollama 配置喵
+    pub fn with_ollama_config(mut self, config: OllamaConfig) -> Self {
+        self.ollama_config = Some(config);
+        self
+    }
+
     /// 🔒 SAFETY: 创建 OpenAI 客户端喵
     /// 异常处理: 如果配置不存在则返回错误
     pub fn create_openai_client(&self) -> Result<OpenAIClient, ProviderError> {
@@ -140,65 +187,36 @@ impl ProviderFactory {
             })
     }
 
+    /// 🔒 SAFETY: 创建 Gemini 客户端喵
+    pub fn create_gemini_client(&self) -> Result<GeminiClient, ProviderError> {
+        self.gemini_config
+            .as_ref()
+            .map(|config| GeminiClient::new(config.clone()))
+            .ok_or_else(|| ProviderError::ApiError("Gemini configuration not found".to_string()))
+    }
+
+    /// 🔒 SAFETY: 创建 Ollama 客户端喵
+    pub fn create_ollama_client(&self) -> Result<OllamaClient, ProviderError> {
+        self.ollama_config
+            .as_ref()
+            .map(|config| OllamaClient::new(config.clone()))
+            .ok_or_else(|| ProviderError::ApiError("Ollama configuration not found".to_string()))
+    }
+
     /// 🔒 SAFETY: 根据 Provider 类型创建客户端喵
     /// 异常处理: 配置不存在或类型不支持时返回错误
+    /// 调用方只依赖 `ChatProvider`，不需要区分具体的客户端类型
     pub fn create_client(
         &self,
         provider_type: ProviderType,
-    ) -> Result<ProviderClient, ProviderError> {
-        match provider_type {
-            ProviderType::OpenAI => {
-                let client = self.create_openai_client()?;
-                Ok(ProviderClient::OpenAI(client))
-            }
-            ProviderType::Anthropic => {
-                let client = self.create_anthropic_client()?;
-                Ok(ProviderClient::Anthropic(client))
-            }
-            ProviderType::OpenRouter => {
-                let client = self.create_openrouter_client()?;
-                Ok(ProviderClient::OpenRouter(client))
-            }
-        }
-    }
-}
-
-/// 🔒 SAFETY: Provider 客户端枚举喵
-/// 封装所有 Provider 客户端类型
-#[derive(Debug, Clone)]
-pub enum ProviderClient {
-    /// OpenAI 客户端
-    OpenAI(OpenAIClient),
-    /// Anthropic 客户端
-    Anthropic(AnthropicClient),
-    /// OpenRouter 客户端
-    OpenRouter(OpenRouterClient),
-}
-
-/// 🔒 SAFETY: ProviderClient 统一接口喵
-/// 提供跨 Provider 的统一调用方式（简化版）
-impl ProviderClient {
-    /// 🔒 SAFETY: 提供商类型喵
-    pub fn provider_type(&self) -> ProviderType {
-        match self {
-            ProviderClient::OpenAI(_) => ProviderType::OpenAI,
-            ProviderClient::Anthropic(_) => ProviderType::Anthropic,
-            ProviderClient::OpenRouter(_) => ProviderType::OpenRouter,
-        }
-    }
-
-    /// 🔒 SAFETY: 简单聊天接口（仅支持基础消息）喵
-    /// 异常处理: 所有 Provider 错误统一转换为 ProviderError
-    /// 注意: 不同 Provider 的 API 行为可能略有差异
-    pub async fn chat_simple(&self, prompt: &str) -> Result<String, ProviderError> {
-        match self {
-            ProviderClient::OpenAI(client) => client.chat_simple(prompt).await,
-            ProviderClient::Anthropic(client) => client.chat_simple(prompt).await,
-            ProviderClient::OpenRouter(client) => {
-                // 默认使用 OpenRouter 的 GPT-3.5-Turbo
-                client.chat_simple("openai/gpt-3.5-turbo", prompt).await
-            }
-        }
+    ) -> Result<Arc<dyn ChatProvider>, ProviderError> {
+        Ok(match provider_type {
+            ProviderType::OpenAI => Arc::new(self.create_openai_client()?),
+            ProviderType::Anthropic => Arc::new(self.create_anthropic_client()?),
+            ProviderType::OpenRouter => Arc::new(self.create_openrouter_client()?),
+            ProviderType::Gemini => Arc::new(self.create_gemini_client()?),
+            ProviderType::Ollama => Arc::new(self.create_ollama_client()?),
+        })
     }
 }
 
@@ -219,6 +237,8 @@ mod tests {
             ProviderType::from_str("openrouter"),
             Some(ProviderType::OpenRouter)
         );
+        assert_eq!(ProviderType::from_str("Gemini"), Some(ProviderType::Gemini));
+        assert_eq!(ProviderType::from_str("ollama"), Some(ProviderType::Ollama));
         assert_eq!(ProviderType::from_str("unknown"), None);
     }
 
@@ -227,6 +247,7 @@ mod tests {
         assert_eq!(ProviderType::OpenAI.as_str(), "openai");
         assert_eq!(ProviderType::Anthropic.as_str(), "anthropic");
         assert_eq!(ProviderType::OpenRouter.as_str(), "openrouter");
+        assert_eq!(ProviderType::Gemini.as_str(), "gemini");
     }
 
     #[test]
@@ -245,5 +266,7 @@ mod tests {
         assert!(factory.openai_config.is_some());
         assert!(factory.create_openai_client().is_ok());
         assert!(factory.create_anthropic_client().is_ok());
+        assert!(factory.create_client(ProviderType::OpenAI).is_ok());
+        assert!(factory.create_client(ProviderType::Gemini).is_err());
     }
 }
@@ -0,0 +1,479 @@
+use super::catalog::CatalogEntry;
+use super::chat::{text_stream, ChatProvider, ChatStream, StreamFraming};
+use super::openai::{
+    ChatRequest, ChatResponse, Choice, FunctionCall, Message, ProviderError, ToolCall, ToolSpec,
+    Usage as ChatUsage,
+};
+/// Ollama Provider 实现模块 🦙
+///
+/// @诺诺 的本地模型客户端实现喵（低资源环境不依赖云端 API）
+///
+/// 功能：
+/// - Ollama REST API：`/api/chat` 对话（含原生工具调用）、NDJSON 流式输出、`/api/tags` 模型列表
+/// - `base_url` 以 `/v1` 结尾时视为 OpenAI 兼容端点（llama.cpp server、Ollama 的 `/v1`），
+///   由调用方改用 `OpenAIClient`，见 [`is_openai_compatible`]
+///
+/// 🔒 SAFETY: 本地服务通常无需认证；配置了 API Key 时（反向代理）才发送 Bearer 头
+///
+/// 实现者: 诺诺 (Nono) ⚡
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 🔒 SAFETY: Ollama 配置结构体喵
+#[derive(Debug, Clone)]
+pub struct OllamaConfig {
+    /// 可选的 API Key（反向代理认证用）
+    pub api_key: String,
+    /// 服务地址
+    pub base_url: String,
+    /// 请求超时时间（秒，本地 CPU 推理较慢）
+    pub timeout: u64,
+    /// 最大重试次数
+    pub max_retries: u8,
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            base_url: "http://localhost:11434".to_string(),
+            timeout: 300,
+            max_retries: 1,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 端点是否为 OpenAI 兼容 API 喵（llama.cpp server 或 Ollama 的 `/v1`）
+pub fn is_openai_compatible(base_url: &str) -> bool {
+    base_url.trim_end_matches('/').ends_with("/v1")
+}
+
+/// 🔒 SAFETY: `/api/chat` 请求喵
+#[derive(Debug, Serialize, Clone)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    pub messages: Vec<OllamaMessage>,
+    pub stream: bool,
+    /// 可调用的工具（与 OpenAI 格式相同）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolSpec>>,
+    /// 采样参数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
+}
+
+/// 🔒 SAFETY: 采样参数喵
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// 最大生成 token 数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+}
+
+/// 🔒 SAFETY: Ollama 消息喵
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OllamaMessage {
+    /// 角色（system、user、assistant、tool）
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    /// 助手发起的工具调用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<OllamaToolCall>>,
+    /// tool 消息对应的工具名（Ollama 没有调用 ID）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+}
+
+/// 🔒 SAFETY: 工具调用喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OllamaToolCall {
+    pub function: OllamaFunctionCall,
+}
+
+/// 🔒 SAFETY: 函数调用（参数为 JSON object，不是字符串）喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OllamaFunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: JsonValue,
+}
+
+impl OllamaChatRequest {
+    /// 🔒 SAFETY: 由 OpenAI 格式的请求转换喵
+    ///
+    /// 工具调用参数从 JSON 字符串转为 object，`tool` 消息按调用 ID 找回工具名
+    pub fn from_chat(request: &ChatRequest, default_model: &str) -> Self {
+        let mut call_names: HashMap<&str, &str> = HashMap::new();
+        let messages = request
+            .messages
+            .iter()
+            .map(|message| {
+                let tool_calls = message.tool_calls.as_ref().map(|calls| {
+                    calls
+                        .iter()
+                        .map(|call| {
+                            call_names.insert(&call.id, &call.function.name);
+                            OllamaToolCall {
+                                function: OllamaFunctionCall {
+                                    name: call.function.name.clone(),
+                                    arguments: serde_json::from_str(&call.function.arguments)
+                                        .ok()
+                                        .filter(JsonValue::is_object)
+                                        .unwrap_or_else(|| serde_json::json!({})),
+                                },
+                            }
+                        })
+                        .collect()
+                });
+                let tool_name = message
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| call_names.get(id))
+                    .map(|name| name.to_string());
+                OllamaMessage {
+                    role: message.role.clone(),
+                    content: message.content.clone(),
+                    tool_calls,
+                    tool_name,
+                }
+            })
+            .collect();
+
+        let options = OllamaOptions {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            num_predict: request.max_tokens,
+        };
+        Self {
+            model: request.model.clone().unwrap_or_else(|| default_model.to_string()),
+            messages,
+            // 流式输出由 `chat_stream` 打开喵
+            stream: false,
+            tools: request.tools.clone().filter(|tools| !tools.is_empty()),
+            options: Some(options).filter(|o| *o != OllamaOptions::default()),
+        }
+    }
+}
+
+/// 🔒 SAFETY: `/api/chat` 响应喵（流式输出的每一行也是这个结构）
+#[derive(Debug, Deserialize, Default)]
+pub struct OllamaChatResponse {
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub message: OllamaMessage,
+    /// 停止原因（stop / length）
+    #[serde(default)]
+    pub done_reason: Option<String>,
+    /// 输入 token 数
+    #[serde(default)]
+    pub prompt_eval_count: Option<u32>,
+    /// 输出 token 数
+    #[serde(default)]
+    pub eval_count: Option<u32>,
+}
+
+impl OllamaChatResponse {
+    /// 🔒 SAFETY: 转换为 OpenAI 格式的响应喵（工具调用按序号生成 ID）
+    pub fn into_chat_response(self) -> ChatResponse {
+        let tool_calls: Vec<ToolCall> = self
+            .message
+            .tool_calls
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| ToolCall {
+                id: format!("call_{}", index),
+                kind: "function".to_string(),
+                function: FunctionCall {
+                    name: call.function.name,
+                    arguments: call.function.arguments.to_string(),
+                },
+            })
+            .collect();
+        let finish_reason = match self.done_reason.as_deref() {
+            _ if !tool_calls.is_empty() => "tool_calls",
+            Some("length") => "length",
+            _ => "stop",
+        };
+        let prompt_tokens = self.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = self.eval_count.unwrap_or(0);
+
+        ChatResponse {
+            id: format!("ollama-{}", uuid::Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp().max(0) as u64,
+            model: self.model,
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(self.message.content).with_tool_calls(Some(tool_calls)),
+                finish_reason: Some(finish_reason.to_string()),
+            }],
+            usage: ChatUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            },
+        }
+    }
+}
+
+/// 🔒 SAFETY: `/api/tags` 响应喵
+#[derive(Debug, Deserialize)]
+pub struct OllamaTags {
+    #[serde(default)]
+    pub models: Vec<OllamaModel>,
+}
+
+/// 🔒 SAFETY: 本地已下载的模型喵
+#[derive(Debug, Deserialize, Clone)]
+pub struct OllamaModel {
+    /// 模型名（含标签，如 `llama3.1:8b`）
+    pub name: String,
+}
+
+/// 🔒 SAFETY: Ollama 错误结构体喵
+#[derive(Debug, Deserialize)]
+struct OllamaError {
+    error: String,
+}
+
+/// 🔒 SAFETY: Ollama 客户端结构体喵
+#[derive(Debug, Clone)]
+pub struct OllamaClient {
+    /// HTTP 客户端
+    client: Client,
+    /// 配置
+    config: OllamaConfig,
+    /// 请求未指定模型时使用的模型
+    default_model: String,
+}
+
+impl OllamaClient {
+    /// 🔒 SAFETY: 创建新的 Ollama 客户端喵
+    pub fn new(config: OllamaConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            config,
+            default_model: String::new(),
+        }
+    }
+
+    /// 🔒 SAFETY: 设置默认模型喵（Ollama 没有内置默认模型，需由配置指定）
+    pub fn with_default_model(mut self, model: &str) -> Self {
+        self.default_model = model.to_string();
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.base_url.trim_end_matches('/'), path)
+    }
+
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.config.api_key.is_empty() {
+            true => builder,
+            false => builder.bearer_auth(&self.config.api_key),
+        }
+    }
+
+    /// 🔒 SAFETY: 检查状态码喵
+    /// 异常处理: 认证错误、5xx（模型加载失败等，可切换备用模型）、其他 API 错误（如模型未下载）
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, ProviderError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        if status.as_u16() == 401 || status.as_u16() == 403 {
+            return Err(ProviderError::AuthError);
+        }
+
+        let error_text = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<OllamaError>(&error_text)
+            .map(|e| e.error)
+            .unwrap_or_else(|_| format!("HTTP {}: {}", status, error_text));
+        if status.as_u16() == 429 || status.is_server_error() {
+            Err(ProviderError::Unavailable(status.as_u16(), message))
+        } else {
+            Err(ProviderError::ApiError(message))
+        }
+    }
+
+    /// 🔒 SAFETY: 发送 `/api/chat` 请求喵
+    async fn post_chat(&self, request: &OllamaChatRequest) -> Result<reqwest::Response, ProviderError> {
+        let response = self
+            .authorize(self.client.post(self.url("/api/chat")))
+            .json(request)
+            .send()
+            .await?;
+        Self::check(response).await
+    }
+
+    /// 🔒 SAFETY: 发送对话请求（带重试）喵
+    async fn send_request_with_retry(
+        &self,
+        request: &OllamaChatRequest,
+    ) -> Result<OllamaChatResponse, ProviderError> {
+        let mut last_error = None;
+
+        for attempt in 0..=self.config.max_retries {
+            match self.post_chat(request).await {
+                Ok(response) => return response.json().await.map_err(ProviderError::from),
+                Err(e) => {
+                    // 模型未下载等请求错误不重试
+                    let retryable = e.transient_reason().is_some();
+                    last_error = Some(e);
+                    if !retryable {
+                        break;
+                    }
+                    if attempt < self.config.max_retries {
+                        // 指数退避
+                        tokio::time::sleep(Duration::from_millis(100 * (2_u64.pow(attempt as u32)))).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ProviderError::ApiError("Unknown error".to_string())))
+    }
+}
+
+#[async_trait]
+impl ChatProvider for OllamaClient {
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        let request = OllamaChatRequest::from_chat(request, &self.default_model);
+        Ok(self.send_request_with_retry(&request).await?.into_chat_response())
+    }
+
+    /// `/api/chat` 的 `stream: true` 返回 NDJSON，逐行返回文本片段
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<ChatStream, ProviderError> {
+        let mut stream_request = OllamaChatRequest::from_chat(request, &self.default_model);
+        stream_request.stream = true;
+        let response = self.post_chat(&stream_request).await?;
+        Ok(text_stream(response, StreamFraming::NdJson, |line| {
+            Ok(Some(serde_json::from_str::<OllamaChatResponse>(line)?.message.content))
+        }))
+    }
+
+    /// 本地已下载的模型（GET /api/tags）
+    async fn list_models(&self) -> Result<Vec<CatalogEntry>, ProviderError> {
+        let response = self.authorize(self.client.get(self.url("/api/tags"))).send().await?;
+        let tags: OllamaTags = Self::check(response).await?.json().await?;
+        Ok(tags.models.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_call_roundtrip() {
+        assert!(is_openai_compatible("http://localhost:8080/v1/"));
+        assert!(!is_openai_compatible("http://localhost:11434"));
+
+        let call = ToolCall {
+            id: "call_3".to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall {
+                name: "fs_read".to_string(),
+                arguments: r#"{"path": "a.txt"}"#.to_string(),
+            },
+        };
+        let chat = ChatRequest {
+            model: None,
+            messages: vec![
+                Message::user("read a.txt".to_string()),
+                Message::assistant(String::new()).with_tool_calls(Some(vec![call])),
+                Message::tool("call_3".to_string(), "hello".to_string()),
+            ],
+            temperature: None,
+            top_p: None,
+            max_tokens: Some(64),
+            stream: None,
+            tools: None,
+        };
+
+        let body = serde_json::to_value(OllamaChatRequest::from_chat(&chat, "llama3.1")).unwrap();
+        assert_eq!(body["model"], "llama3.1");
+        assert_eq!(body["options"], json!({"num_predict": 64}));
+        assert_eq!(body["messages"][1]["tool_calls"][0]["function"]["arguments"], json!({"path": "a.txt"}));
+        assert_eq!(body["messages"][2]["tool_name"], "fs_read");
+
+        let response: OllamaChatResponse = serde_json::from_value(json!({
+            "model": "llama3.1",
+            "message": {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "fs_read", "arguments": {"path": "b.txt"}}}
+            ]},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 4
+        }))
+        .unwrap();
+        let chat_response = response.into_chat_response();
+        let choice = &chat_response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(choice.message.tool_calls.as_ref().unwrap()[0].function.arguments, r#"{"path":"b.txt"}"#);
+        assert_eq!(chat_response.usage.total_tokens, 16);
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_ndjson() {
+        use futures::StreamExt;
+
+        // 按行返回 NDJSON 喵（最后一行没有换行结尾）
+        let app = axum::Router::new().route(
+            "/api/chat",
+            axum::routing::post(|axum::Json(body): axum::Json<serde_json::Value>| async move {
+                assert_eq!(body["stream"], true);
+                concat!(
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n",
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}",
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = OllamaClient::new(OllamaConfig {
+            base_url: format!("http://{}", addr),
+            ..OllamaConfig::default()
+        })
+        .with_default_model("llama3.1");
+        let request = ChatRequest {
+            model: None,
+            messages: vec![Message::user("hi".to_string())],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            tools: None,
+        };
+
+        let chunks: Vec<String> = client
+            .chat_stream(&request)
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks, vec!["Hel".to_string(), "lo".to_string()]);
+    }
+}
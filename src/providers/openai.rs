@@ -4,8 +4,9 @@
 ///
 /// 功能：
 /// - GPT-4 / GPT-3.5 Turbo 兼容
-/// - 流式响应支持（可选）
+/// - SSE 流式响应
 /// - 错误重试机制
+/// - 原生 function calling（`tools` / `tool_calls`）
 ///
 /// 🔒 SAFETY: API Key 加密存储，请求参数严格验证
 ///
@@ -15,6 +16,12 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use thiserror::Error;
+use tracing::{info, warn};
+
+use super::catalog::CatalogEntry;
+use super::chat::{text_stream, ChatProvider, ChatStream, StreamFraming};
+use super::context::{compact_request, is_context_length_error};
+use crate::tools::{ToolCallRequest, ToolDescription};
 
 /// 🔒 SAFETY: OpenAI 配置结构体喵
 /// 从安全配置中加载 API Key
@@ -54,47 +61,153 @@ pub struct ChatRequest {
     /// 温度参数（0.0-2.0）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
+    /// 核采样参数（0.0-1.0）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
     /// 最大生成 token 数
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
-    /// 流式响应（暂未实现）
+    /// 流式响应（由 `chat_stream` 设置）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    /// 原生 function calling 的工具列表
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolSpec>>,
+}
+
+/// 🔒 SAFETY: 原生 function calling 工具定义喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolSpec {
+    /// 固定为 "function"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: FunctionSpec,
+}
+
+/// 🔒 SAFETY: 函数定义（名称 + 描述 + JSON Schema 参数）喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FunctionSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl From<&ToolDescription> for ToolSpec {
+    fn from(tool: &ToolDescription) -> Self {
+        // 没有声明参数的工具也必须给出 object schema喵
+        let parameters = if tool.input_schema.is_object() {
+            tool.input_schema.clone()
+        } else {
+            serde_json::json!({ "type": "object", "properties": {} })
+        };
+        Self {
+            kind: "function".to_string(),
+            function: FunctionSpec {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters,
+            },
+        }
+    }
+}
+
+/// 🔒 SAFETY: 模型返回的结构化工具调用喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "default_tool_kind")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+/// 🔒 SAFETY: 函数调用（参数为 JSON 字符串）喵
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: String,
+}
+
+fn default_tool_kind() -> String {
+    "function".to_string()
+}
+
+impl ToolCall {
+    /// 🔒 SAFETY: 转换为工具注册表的调用请求喵
+    /// 参数不是合法 JSON 时原样作为字符串传入，由工具自行报错
+    pub fn to_request(&self) -> ToolCallRequest {
+        let raw = self.function.arguments.trim();
+        let arguments = if raw.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+        };
+        ToolCallRequest {
+            tool_name: self.function.name.clone(),
+            arguments,
+            call_id: Some(self.id.clone()),
+        }
+    }
 }
 
 /// 🔒 SAFETY: 消息结构体喵
 /// 支持多轮对话
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Message {
-    /// 角色（system、user、assistant）
+    /// 角色（system、user、assistant、tool）
     pub role: String,
-    /// 消息内容
+    /// 消息内容（只有工具调用时 provider 会返回 null）
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub content: String,
+    /// 助手发起的结构化工具调用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// tool 消息对应的调用 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 impl Message {
     /// 🔒 SAFETY: 创建用户消息喵
     /// 内容参数必须经过 XSS 过滤
     pub fn user(content: String) -> Self {
-        Self {
-            role: "user".to_string(),
-            content,
-        }
+        Self::with_role("user", content)
     }
 
     /// 🔒 SAFETY: 创建助手消息喵
     pub fn assistant(content: String) -> Self {
-        Self {
-            role: "assistant".to_string(),
-            content,
-        }
+        Self::with_role("assistant", content)
     }
 
     /// 🔒 SAFETY: 创建系统消息喵
     pub fn system(content: String) -> Self {
+        Self::with_role("system", content)
+    }
+
+    /// 🔒 SAFETY: 创建工具结果消息（回应结构化工具调用）喵
+    pub fn tool(call_id: String, content: String) -> Self {
         Self {
-            role: "system".to_string(),
+            tool_call_id: Some(call_id),
+            ..Self::with_role("tool", content)
+        }
+    }
+
+    /// 附带结构化工具调用（空列表视为没有）喵
+    pub fn with_tool_calls(mut self, calls: Option<Vec<ToolCall>>) -> Self {
+        self.tool_calls = calls.filter(|c| !c.is_empty());
+        self
+    }
+
+    pub(crate) fn with_role(role: &str, content: String) -> Self {
+        Self {
+            role: role.to_string(),
             content,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
 }
@@ -138,6 +251,44 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
+/// 🔒 SAFETY: /models 端点的模型条目喵
+#[derive(Debug, Deserialize, Clone)]
+pub struct OpenAIModel {
+    /// 模型 ID
+    pub id: String,
+    /// 所属组织
+    #[serde(default)]
+    pub owned_by: Option<String>,
+    /// 上下文长度（部分兼容端点会返回）
+    #[serde(default, alias = "context_window", alias = "max_model_len")]
+    pub context_length: Option<u32>,
+}
+
+/// 🔒 SAFETY: /models 响应包装喵
+#[derive(Debug, Deserialize)]
+struct OpenAIModelList {
+    data: Vec<OpenAIModel>,
+}
+
+/// 🔒 SAFETY: /embeddings 请求喵
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// 🔒 SAFETY: /embeddings 响应喵
+#[derive(Debug, Deserialize)]
+struct EmbeddingList {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 /// 🔒 SAFETY: OpenAI 错误结构体喵
 #[derive(Debug, Deserialize)]
 pub struct OpenAIError {
@@ -177,6 +328,31 @@ pub enum ProviderError {
     /// 超时错误
     #[error("Request timeout")]
     Timeout,
+    /// 上下文长度超限（HTTP 400）
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+    /// 端点不支持原生 function calling（HTTP 400）
+    #[error("Native tool calling unsupported: {0}")]
+    ToolsUnsupported(String),
+    /// 上游暂时不可用（HTTP 429 / 5xx）
+    #[error("Provider unavailable (HTTP {0}): {1}")]
+    Unavailable(u16, String),
+}
+
+impl ProviderError {
+    /// 可以切换到备用模型的临时错误喵（限流、5xx、超时、连接失败）
+    ///
+    /// ## Returns
+    /// 写入遥测的原因，如 `http_503` / `timeout`
+    pub fn transient_reason(&self) -> Option<String> {
+        match self {
+            Self::Unavailable(status, _) => Some(format!("http_{}", status)),
+            Self::Timeout => Some("timeout".to_string()),
+            Self::HttpError(e) if e.is_timeout() => Some("timeout".to_string()),
+            Self::HttpError(e) if e.is_connect() => Some("connect".to_string()),
+            _ => None,
+        }
+    }
 }
 
 /// 🔒 SAFETY: OpenAI 客户端结构体喵
@@ -213,8 +389,15 @@ impl OpenAIClient {
                 Ok(response) => return Ok(response),
                 Err(e) => {
                     last_error = Some(e);
-                    // 如果是认证错误，不重试
-                    if matches!(last_error, Some(ProviderError::AuthError)) {
+                    // 认证错误、上下文超限、不支持 tools，原样重试没有意义
+                    if matches!(
+                        last_error,
+                        Some(
+                            ProviderError::AuthError
+                                | ProviderError::ContextLengthExceeded(_)
+                                | ProviderError::ToolsUnsupported(_)
+                        )
+                    ) {
                         break;
                     }
                     // 最后一次不等待
@@ -256,8 +439,27 @@ impl OpenAIClient {
             }
 
             let error_text = response.text().await.unwrap_or_default();
+            if status.as_u16() == 429 || status.is_server_error() {
+                let message = serde_json::from_str::<OpenAIError>(&error_text)
+                    .map(|e| e.error.message)
+                    .unwrap_or(error_text);
+                return Err(ProviderError::Unavailable(status.as_u16(), message));
+            }
             if let Ok(openai_error) = serde_json::from_str::<OpenAIError>(&error_text) {
-                Err(ProviderError::ApiError(openai_error.error.message))
+                let detail = openai_error.error;
+                if status.as_u16() == 400
+                    && is_context_length_error(detail.code.as_deref(), &detail.message)
+                {
+                    return Err(ProviderError::ContextLengthExceeded(detail.message));
+                }
+                if status.as_u16() == 400 && request.tools.is_some() && is_tools_unsupported_error(&detail.message) {
+                    return Err(ProviderError::ToolsUnsupported(detail.message));
+                }
+                Err(ProviderError::ApiError(detail.message))
+            } else if status.as_u16() == 400 && is_context_length_error(None, &error_text) {
+                Err(ProviderError::ContextLengthExceeded(error_text))
+            } else if status.as_u16() == 400 && request.tools.is_some() && is_tools_unsupported_error(&error_text) {
+                Err(ProviderError::ToolsUnsupported(error_text))
             } else {
                 Err(ProviderError::ApiError(format!(
                     "HTTP {}: {}",
@@ -268,38 +470,118 @@ impl OpenAIClient {
     }
 }
 
+/// 🔒 SAFETY: 判断 400 错误是否因为端点不支持 tools 参数喵
+/// 各兼容端点措辞不一，只能按关键词匹配
+pub fn is_tools_unsupported_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    (message.contains("tool") || message.contains("function"))
+        && ["not support", "unsupported", "unrecognized", "unknown", "not allowed", "extra"]
+            .iter()
+            .any(|needle| message.contains(needle))
+}
+
 /// 🔒 SAFETY: 实现 Provider Trait（待 traits.rs 定义后连接）喵
 /// 注意：这里暂时使用自己的 Result 喵
 impl OpenAIClient {
     /// 🔒 SAFETY: 聊天接口喵
     /// 异常处理: 所有错误返回 ProviderError
+    /// 上下文超限时压缩消息并自动重试一次
     pub async fn chat_api(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
-        self.send_request_with_retry(request).await
+        match self.send_request_with_retry(request).await {
+            Err(ProviderError::ContextLengthExceeded(reason)) => {
+                warn!("Context length exceeded, retrying with compacted history: {}", reason);
+                let (compacted, stats) = compact_request(request);
+                info!(
+                    target: "telemetry",
+                    event = "context_length_retry",
+                    model = request.model.as_deref().unwrap_or_default(),
+                    dropped_messages = stats.dropped_messages,
+                    tokens_before = stats.tokens_before,
+                    tokens_after = stats.tokens_after,
+                    "Retrying request with reduced context"
+                );
+                self.send_request_with_retry(&compacted).await
+            }
+            other => other,
+        }
     }
 
-    /// 🌊 流式输出喵 - Agent 功能核心
-    /// 返回流式响应，支持实时输出
-    pub async fn chat_stream(
-        &self,
-        request: &ChatRequest,
-    ) -> Result<impl futures::Stream<Item = Result<String, ProviderError>>, ProviderError> {
-        let url = format!("{}/chat/completions", self.config.base_url);
-        
-        let mut stream_request = request.clone();
-        stream_request.stream = Some(true);
-        
+    /// 🔒 SAFETY: 获取可用模型列表喵
+    /// 调用 OpenAI 兼容的 GET /models 端点
+    pub async fn list_models(&self) -> Result<Vec<OpenAIModel>, ProviderError> {
+        let url = format!("{}/models", self.config.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(&self.config.api_key)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if status.is_success() {
+            let list: OpenAIModelList = response.json().await?;
+            Ok(list.data)
+        } else if status.as_u16() == 401 {
+            Err(ProviderError::AuthError)
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(ProviderError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )))
+        }
+    }
+
+    /// 🔒 SAFETY: 文本向量化喵
+    /// 调用 OpenAI 兼容的 POST /embeddings 端点，按输入顺序返回向量
+    pub async fn embed(&self, model: &str, input: &[String]) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let url = format!("{}/embeddings", self.config.base_url);
+
         let response = self
             .client
             .post(&url)
             .bearer_auth(&self.config.api_key)
-            .header("Content-Type", "application/json")
-            .json(&stream_request)
+            .json(&EmbeddingRequest { model, input })
             .send()
             .await?;
-        
-        // TODO: 实现 SSE 解析流式数据喵
-        // 当前返回简单的行流
-        Ok(futures::stream::empty())
+
+        let status = response.status();
+
+        if status.is_success() {
+            let mut list: EmbeddingList = response.json().await?;
+            list.data.sort_by_key(|d| d.index);
+            Ok(list.data.into_iter().map(|d| d.embedding).collect())
+        } else if status.as_u16() == 401 {
+            Err(ProviderError::AuthError)
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<OpenAIError>(&error_text)
+                .map(|e| e.error.message)
+                .unwrap_or_else(|_| format!("HTTP {}: {}", status, error_text));
+            if status.as_u16() == 429 || status.is_server_error() {
+                Err(ProviderError::Unavailable(status.as_u16(), message))
+            } else {
+                Err(ProviderError::ApiError(message))
+            }
+        }
+    }
+
+    /// API 基础 URL喵
+    pub fn base_url(&self) -> &str {
+        &self.config.base_url
+    }
+
+    /// 🔒 SAFETY: 预先建立连接喵（DNS + TLS 握手），连接留在连接池里供之后的请求复用
+    ///
+    /// `ping = true` 时调用不计费的 `GET /models` 并检查凭证，否则只发 HEAD 请求、忽略状态码
+    pub async fn warm_up(&self, ping: bool) -> Result<(), ProviderError> {
+        if ping {
+            return self.list_models().await.map(|_| ());
+        }
+        self.client.head(&self.config.base_url).send().await?;
+        Ok(())
     }
 
     /// 🔒 SAFETY: 快捷接口喵
@@ -309,14 +591,16 @@ impl OpenAIClient {
             model: Some("gpt-3.5-turbo".to_string()),
             messages: vec![Message::user(prompt.to_string())],
             temperature: None,
+            top_p: None,
             max_tokens: None,
             stream: None,
+            tools: None,
         };
 
         let response = self.chat_api(&request).await?;
         Ok(response
             .choices
-            .get(0)
+            .first()
             .ok_or_else(|| ProviderError::ApiError("No choices in response".to_string()))?
             .message
             .content
@@ -324,6 +608,73 @@ impl OpenAIClient {
     }
 }
 
+/// 流式响应中的增量片段
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+}
+
+#[async_trait]
+impl ChatProvider for OpenAIClient {
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        self.chat_api(request).await
+    }
+
+    /// 🌊 SSE 流式输出喵，逐个返回 `delta.content`
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<ChatStream, ProviderError> {
+        let url = format!("{}/chat/completions", self.config.base_url);
+
+        let mut stream_request = request.clone();
+        stream_request.stream = Some(true);
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.config.api_key)
+            .header("Content-Type", "application/json")
+            .json(&stream_request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.as_u16() == 401 {
+            return Err(ProviderError::AuthError);
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<OpenAIError>(&error_text)
+                .map(|e| e.error.message)
+                .unwrap_or_else(|_| format!("HTTP {}: {}", status, error_text));
+            if status.as_u16() == 429 || status.is_server_error() {
+                return Err(ProviderError::Unavailable(status.as_u16(), message));
+            }
+            return Err(ProviderError::ApiError(message));
+        }
+
+        Ok(text_stream(response, StreamFraming::Sse, |data| {
+            let chunk: StreamChunk = serde_json::from_str(data)?;
+            Ok(chunk.choices.into_iter().next().and_then(|choice| choice.delta.content))
+        }))
+    }
+
+    async fn list_models(&self) -> Result<Vec<CatalogEntry>, ProviderError> {
+        let models = OpenAIClient::list_models(self).await?;
+        Ok(models.into_iter().map(Into::into).collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -335,6 +686,27 @@ mod tests {
         assert_eq!(msg.content, "test");
     }
 
+    #[test]
+    fn test_tool_calls_round_trip() {
+        let raw = r#"{"role": "assistant", "content": null, "tool_calls": [
+            {"id": "call_1", "type": "function",
+             "function": {"name": "fs_read", "arguments": "{\"path\": \"a.txt\"}"}}
+        ]}"#;
+        let msg: Message = serde_json::from_str(raw).unwrap();
+        assert_eq!(msg.content, "");
+        let request = msg.tool_calls.as_ref().unwrap()[0].to_request();
+        assert_eq!(request.tool_name, "fs_read");
+        assert_eq!(request.arguments["path"], "a.txt");
+        assert_eq!(request.call_id.as_deref(), Some("call_1"));
+
+        let reply = serde_json::to_value(Message::tool("call_1".to_string(), "ok".to_string())).unwrap();
+        assert_eq!(reply["tool_call_id"], "call_1");
+        assert!(serde_json::to_value(Message::user("hi".to_string())).unwrap().get("tool_calls").is_none());
+
+        assert!(is_tools_unsupported_error("This model does not support tools"));
+        assert!(!is_tools_unsupported_error("Invalid API key"));
+    }
+
     #[test]
     fn test_config_default() {
         let config = OpenAIConfig::default();
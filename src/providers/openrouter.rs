@@ -1,3 +1,5 @@
+use super::catalog::CatalogEntry;
+use super::chat::ChatProvider;
 use super::openai::{ChatRequest, ChatResponse, Message, ProviderError};
 /// OpenRouter Provider 实现模块 🌐
 ///
@@ -83,6 +85,7 @@ pub struct ModelInfo {
     /// 模型名称
     pub name: String,
     /// 描述
+    #[serde(default)]
     pub description: String,
     /// 定价信息
     pub pricing: Pricing,
@@ -93,12 +96,18 @@ pub struct ModelInfo {
 /// 🔒 SAFETY: 定价信息结构体喵
 #[derive(Debug, Deserialize, Clone)]
 pub struct Pricing {
-    /// 输入价格（每百万 token，美元）
+    /// 输入价格（每 token，美元）
     pub prompt: String,
-    /// 输出价格（每百万 token，美元）
+    /// 输出价格（每 token，美元）
     pub completion: String,
 }
 
+/// 🔒 SAFETY: models 端点的响应包装喵
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+}
+
 /// 🔒 SAFETY: OpenRouter 错误结构体喵
 #[derive(Debug, Deserialize)]
 pub struct OpenRouterError {
@@ -155,7 +164,8 @@ impl OpenRouterClient {
         let status = response.status();
 
         if status.is_success() {
-            response.json().await.map_err(ProviderError::from)
+            let list: ModelList = response.json().await?;
+            Ok(list.data)
         } else {
             let error_text = response.text().await.unwrap_or_default();
             Err(ProviderError::ApiError(format!(
@@ -288,8 +298,10 @@ impl OpenRouterClient {
                 model: Some(model.to_string()),
                 messages: vec![Message::user(prompt.to_string())],
                 temperature: None,
+                top_p: None,
                 max_tokens: None,
                 stream: None,
+                tools: None,
             },
             provider: None,
             route: None,
@@ -299,7 +311,7 @@ impl OpenRouterClient {
         let response = self.chat_api(&request).await?;
         Ok(response
             .choices
-            .get(0)
+            .first()
             .ok_or_else(|| ProviderError::ApiError("No choices in response".to_string()))?
             .message
             .content
@@ -318,8 +330,10 @@ impl OpenRouterClient {
                 model: Some(model.to_string()),
                 messages: vec![Message::user(prompt.to_string())],
                 temperature: None,
+                top_p: None,
                 max_tokens: None,
                 stream: None,
+                tools: None,
             },
             provider: Some(ProviderPreference {
                 order: Some(preferred_providers),
@@ -333,7 +347,7 @@ impl OpenRouterClient {
         let response = self.chat_api(&request).await?;
         Ok(response
             .choices
-            .get(0)
+            .first()
             .ok_or_else(|| ProviderError::ApiError("No choices in response".to_string()))?
             .message
             .content
@@ -364,6 +378,18 @@ impl OpenRouterClient {
     }
 }
 
+#[async_trait]
+impl ChatProvider for OpenRouterClient {
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        self.chat_openai_compatible(request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<CatalogEntry>, ProviderError> {
+        let models = OpenRouterClient::list_models(self).await?;
+        Ok(models.into_iter().map(Into::into).collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,402 @@
+/// Token 计数 🔢
+///
+/// @诺诺 的 Token 计数后端喵
+///
+/// 功能：
+/// - `TokenCounter`：上下文超限检查、压缩阈值、摘要触发统一使用的计数接口
+/// - `BpeCounter`：tiktoken 格式的 BPE 词表（`cl100k_base` / `o200k_base`），OpenAI 模型精确计数
+/// - `HeuristicCounter`：没有词表的模型按字符数估算，并用 provider 返回的 `usage.prompt_tokens` 校准
+/// - 进程级计数器：Agent 启动时按模型安装，`count_tokens` 在任意位置使用
+///
+/// 词表文件不随程序分发，放在 `<配置目录>/tokenizers/<encoding>.tiktoken`
+/// （与 tiktoken 使用的文件相同，每行 `base64 token + 空格 + rank`）
+///
+/// 🔒 SAFETY: 计数只读取文本，不修改消息
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+use base64::Engine;
+
+use super::Message;
+use crate::tools::estimate_tokens;
+
+/// 每条消息的格式开销（role、分隔符）
+const MESSAGE_OVERHEAD: usize = 4;
+/// 校准时新观测值的权重
+const CALIBRATION_WEIGHT: f32 = 0.3;
+/// 校准系数的上下限
+const CALIBRATION_RANGE: (f32, f32) = (0.5, 3.0);
+
+/// cl100k_base 预分词规则（`\s+(?!\S)` 由 `split_pieces` 处理）
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+";
+/// o200k_base 预分词规则
+const O200K_PATTERN: &str = r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+";
+
+/// 进程级计数器（未安装时使用启发式估算）
+static GLOBAL: RwLock<Option<Arc<dyn TokenCounter>>> = RwLock::new(None);
+
+/// 🔒 SAFETY: Token 计数接口喵
+pub trait TokenCounter: Send + Sync + std::fmt::Debug {
+    /// 文本的 token 数
+    fn count(&self, text: &str) -> usize;
+
+    /// 计数后端名称（日志用）
+    fn name(&self) -> String;
+
+    /// provider 报告的实际输入 token 数（`estimated` 为本计数器对同一请求的计数）
+    ///
+    /// 默认忽略；估算型计数器据此校准
+    fn observe_usage(&self, _estimated: usize, _reported: usize) {}
+}
+
+/// 🔒 SAFETY: 分词词表喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// GPT-4 / GPT-3.5 / text-embedding-3
+    Cl100kBase,
+    /// GPT-4o / GPT-4.1 / o 系列 / GPT-5
+    O200kBase,
+}
+
+impl Encoding {
+    /// 🔒 SAFETY: 模型使用的词表喵（不认识的模型返回 None）
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        let o200k = ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "chatgpt-4o", "o1", "o3", "o4"];
+        let cl100k = ["gpt-4", "gpt-3.5", "gpt-35", "text-embedding-3", "text-embedding-ada-002"];
+        if o200k.iter().any(|prefix| model.starts_with(prefix)) {
+            Some(Encoding::O200kBase)
+        } else if cl100k.iter().any(|prefix| model.starts_with(prefix)) {
+            Some(Encoding::Cl100kBase)
+        } else {
+            None
+        }
+    }
+
+    /// 词表名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => CL100K_PATTERN,
+            Encoding::O200kBase => O200K_PATTERN,
+        }
+    }
+}
+
+/// 🔒 SAFETY: Token 计数配置喵
+///
+/// ```toml
+/// [tokenizer]
+/// encodings_dir = "~/.nekoclaw/tokenizers"   # 默认 <配置目录>/tokenizers
+/// calibrate = true                           # 没有词表时按 provider 返回的 usage 校准估算
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizerConfig {
+    /// tiktoken 词表目录（None = `<配置目录>/tokenizers`）
+    #[serde(default)]
+    pub encodings_dir: Option<PathBuf>,
+    /// 强制使用的词表（None = 按模型名选择）
+    #[serde(default)]
+    pub encoding: Option<Encoding>,
+    /// 是否用 provider 返回的 usage 校准估算
+    #[serde(default = "default_calibrate")]
+    pub calibrate: bool,
+}
+
+fn default_calibrate() -> bool {
+    true
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        Self {
+            encodings_dir: None,
+            encoding: None,
+            calibrate: default_calibrate(),
+        }
+    }
+}
+
+impl TokenizerConfig {
+    /// 🔒 SAFETY: 为模型选择计数器喵
+    ///
+    /// 有对应词表文件时使用 BPE，否则回退到启发式估算
+    pub fn counter_for_model(&self, config_dir: &Path, model: &str) -> Arc<dyn TokenCounter> {
+        let heuristic = Arc::new(HeuristicCounter::new(self.calibrate));
+        let Some(encoding) = self.encoding.or_else(|| Encoding::for_model(model)) else {
+            return heuristic;
+        };
+        let dir = self.encodings_dir.clone().unwrap_or_else(|| config_dir.join("tokenizers"));
+        let path = dir.join(format!("{}.tiktoken", encoding.as_str()));
+        if !path.exists() {
+            debug!("Tokenizer file {} not found, estimating tokens", path.display());
+            return heuristic;
+        }
+        match BpeCounter::from_file(encoding, &path) {
+            Ok(counter) => Arc::new(counter),
+            Err(e) => {
+                warn!("词表 {} 加载失败，改用估算喵: {}", path.display(), e);
+                heuristic
+            }
+        }
+    }
+}
+
+/// 🔒 SAFETY: 启发式计数器喵（字符数估算 × 校准系数）
+#[derive(Debug)]
+pub struct HeuristicCounter {
+    calibrate: bool,
+    /// 校准系数（f32 位模式）
+    ratio: AtomicU32,
+}
+
+impl HeuristicCounter {
+    pub fn new(calibrate: bool) -> Self {
+        Self {
+            calibrate,
+            ratio: AtomicU32::new(1.0f32.to_bits()),
+        }
+    }
+
+    /// 当前校准系数
+    pub fn ratio(&self) -> f32 {
+        f32::from_bits(self.ratio.load(Ordering::Relaxed))
+    }
+}
+
+impl TokenCounter for HeuristicCounter {
+    fn count(&self, text: &str) -> usize {
+        (estimate_tokens(text) as f32 * self.ratio()).ceil() as usize
+    }
+
+    fn name(&self) -> String {
+        format!("heuristic(x{:.2})", self.ratio())
+    }
+
+    fn observe_usage(&self, estimated: usize, reported: usize) {
+        if !self.calibrate || estimated == 0 || reported == 0 {
+            return;
+        }
+        // estimated 已乘以当前系数，换算回原始估算再求新系数
+        let current = self.ratio();
+        let observed = reported as f32 / (estimated as f32 / current);
+        let next = (current * (1.0 - CALIBRATION_WEIGHT) + observed * CALIBRATION_WEIGHT)
+            .clamp(CALIBRATION_RANGE.0, CALIBRATION_RANGE.1);
+        self.ratio.store(next.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// 🔒 SAFETY: tiktoken 格式的 BPE 计数器喵
+#[derive(Debug)]
+pub struct BpeCounter {
+    encoding: Encoding,
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl BpeCounter {
+    /// 🔒 SAFETY: 由 rank 表构造喵
+    pub fn new(encoding: Encoding, ranks: HashMap<Vec<u8>, u32>) -> Result<Self, String> {
+        let pattern = Regex::new(encoding.pattern()).map_err(|e| format!("Invalid split pattern: {}", e))?;
+        Ok(Self {
+            encoding,
+            ranks,
+            pattern,
+        })
+    }
+
+    /// 🔒 SAFETY: 读取 `.tiktoken` 词表文件喵
+    pub fn from_file(encoding: Encoding, path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::new(encoding, parse_ranks(&raw)?)
+    }
+
+    /// 预分词：按规则切分为互不合并的片段
+    ///
+    /// `regex` 不支持 `\s+(?!\S)`：连续空白后跟非空白时，最后一个空白留给后面的词
+    fn split_pieces<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while let Some(found) = self.pattern.find_at(text, start) {
+            let mut end = found.end();
+            let piece = found.as_str();
+            let trailing_space = piece.chars().all(char::is_whitespace) && !piece.ends_with(['\r', '\n']);
+            if trailing_space && text[end..].chars().next().is_some_and(|c| !c.is_whitespace()) {
+                if let Some((last, _)) = piece.char_indices().last().filter(|(last, _)| *last > 0) {
+                    end = found.start() + last;
+                }
+            }
+            pieces.push(&text[found.start()..end]);
+            start = end;
+        }
+        pieces
+    }
+
+    /// 单个片段合并后的 token 数
+    fn piece_tokens(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return 1;
+        }
+        byte_pair_merge(&self.ranks, piece).len() - 1
+    }
+}
+
+impl TokenCounter for BpeCounter {
+    fn count(&self, text: &str) -> usize {
+        self.split_pieces(text).into_iter().map(|piece| self.piece_tokens(piece.as_bytes())).sum()
+    }
+
+    fn name(&self) -> String {
+        self.encoding.as_str().to_string()
+    }
+}
+
+/// 解析 tiktoken 词表（每行 `base64 rank`）
+fn parse_ranks(raw: &str) -> Result<HashMap<Vec<u8>, u32>, String> {
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(n, line)| {
+            let (token, rank) = line.split_once(' ').ok_or_else(|| format!("Malformed line {}", n + 1))?;
+            let token = base64::engine::general_purpose::STANDARD
+                .decode(token)
+                .map_err(|e| format!("Invalid token on line {}: {}", n + 1, e))?;
+            let rank = rank.trim().parse().map_err(|e| format!("Invalid rank on line {}: {}", n + 1, e))?;
+            Ok((token, rank))
+        })
+        .collect()
+}
+
+/// BPE 合并（与 tiktoken 相同：每次合并 rank 最小的相邻对），返回各 token 的起始位置 + 末尾
+fn byte_pair_merge(ranks: &HashMap<Vec<u8>, u32>, piece: &[u8]) -> Vec<(usize, u32)> {
+    let rank_of = |bytes: &[u8]| ranks.get(bytes).copied().unwrap_or(u32::MAX);
+    let mut parts: Vec<(usize, u32)> = (0..piece.len() - 1).map(|i| (i, rank_of(&piece[i..i + 2]))).collect();
+    parts.push((piece.len() - 1, u32::MAX));
+    parts.push((piece.len(), u32::MAX));
+
+    // parts[i] 与后一个 token 合并后的 rank
+    let merged_rank = |parts: &[(usize, u32)], i: usize| match parts.get(i + 3) {
+        Some(end) => rank_of(&piece[parts[i].0..end.0]),
+        None => u32::MAX,
+    };
+    while let Some((i, _)) = parts[..parts.len() - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, rank))| *rank != u32::MAX)
+        .min_by_key(|(_, (_, rank))| *rank)
+    {
+        if i > 0 {
+            parts[i - 1].1 = merged_rank(&parts, i - 1);
+        }
+        parts[i].1 = merged_rank(&parts, i);
+        parts.remove(i + 1);
+    }
+    parts
+}
+
+/// 🔒 SAFETY: 安装进程级计数器喵
+pub fn install(counter: Arc<dyn TokenCounter>) {
+    debug!("Token counter: {}", counter.name());
+    if let Ok(mut global) = GLOBAL.write() {
+        *global = Some(counter);
+    }
+}
+
+/// 🔒 SAFETY: 当前进程级计数器喵（未安装时为不校准的估算）
+pub fn counter() -> Arc<dyn TokenCounter> {
+    match GLOBAL.read().ok().and_then(|global| global.clone()) {
+        Some(counter) => counter,
+        None => Arc::new(HeuristicCounter::new(false)),
+    }
+}
+
+/// 🔒 SAFETY: 用进程级计数器计算文本 token 数喵
+pub fn count_tokens(text: &str) -> usize {
+    match GLOBAL.read().ok().and_then(|global| global.clone()) {
+        Some(counter) => counter.count(text),
+        None => estimate_tokens(text),
+    }
+}
+
+/// 🔒 SAFETY: 用 provider 报告的输入 token 数校准进程级计数器喵（未报告时忽略）
+pub fn observe_usage(messages: &[Message], reported_prompt_tokens: u32) {
+    if reported_prompt_tokens > 0 {
+        counter().observe_usage(count_message_tokens(messages), reported_prompt_tokens as usize);
+    }
+}
+
+/// 🔒 SAFETY: 消息列表的 token 数喵（内容 + 工具调用参数 + 每条消息的格式开销）
+pub fn count_message_tokens(messages: &[Message]) -> usize {
+    let counter = counter();
+    messages
+        .iter()
+        .map(|message| {
+            let calls: usize = message
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| counter.count(&call.function.name) + counter.count(&call.function.arguments))
+                .sum();
+            counter.count(&message.content) + calls + MESSAGE_OVERHEAD
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpe_counter() {
+        let mut ranks: HashMap<Vec<u8>, u32> = (0u8..=255).map(|b| (vec![b], b as u32)).collect();
+        for (rank, token) in ["he", "ll", "llo", "hello", " w", "or", " wor", "ld", " world"].iter().enumerate() {
+            ranks.insert(token.as_bytes().to_vec(), 256 + rank as u32);
+        }
+        let raw: String = ranks
+            .iter()
+            .map(|(token, rank)| format!("{} {}\n", base64::engine::general_purpose::STANDARD.encode(token), rank))
+            .collect();
+        let counter = BpeCounter::new(Encoding::Cl100kBase, parse_ranks(&raw).unwrap()).unwrap();
+
+        assert_eq!(counter.split_pieces("hello   world!!\n\n x"), ["hello", "  ", " world", "!!\n\n", " x"]);
+        assert_eq!(counter.split_pieces("it's 12345"), ["it", "'s", " ", "123", "45"]);
+        assert_eq!(counter.count("hello world"), 2);
+        // 词表中只有 he：helo → he + l + o 喵
+        assert_eq!(counter.count("helo"), 3);
+        assert_eq!(counter.count(""), 0);
+        assert_eq!(counter.count("你"), 3);
+
+        assert_eq!(Encoding::for_model("gpt-4o-mini"), Some(Encoding::O200kBase));
+        assert_eq!(Encoding::for_model("openai/gpt-4-turbo"), Some(Encoding::Cl100kBase));
+        assert_eq!(Encoding::for_model("claude-3-5-sonnet"), None);
+    }
+
+    #[test]
+    fn test_heuristic_calibration() {
+        let counter = HeuristicCounter::new(true);
+        let text = "a".repeat(400);
+        assert_eq!(counter.count(&text), 100);
+        for _ in 0..20 {
+            counter.observe_usage(counter.count(&text), 150);
+        }
+        assert!((counter.ratio() - 1.5).abs() < 0.05);
+        counter.observe_usage(10, 10_000);
+        assert!(counter.ratio() <= CALIBRATION_RANGE.1);
+
+        let fixed = HeuristicCounter::new(false);
+        fixed.observe_usage(100, 150);
+        assert_eq!(fixed.count(&text), 100);
+    }
+}
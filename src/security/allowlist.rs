@@ -12,10 +12,13 @@
 //! - 文件系统访问控制喵
 //! - API 端点权限验证喵
 
+use super::path_rules::{PathAccess, PathDecision, PathRuleSet, PatternKind, RuleAccess, RuleEffect};
+use super::policy::PolicyViolation;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::path::PathBuf;
 use thiserror::Error;
+use tracing::warn;
 
 /// 白名单错误类型
 #[derive(Error, Debug, Clone)]
@@ -31,8 +34,26 @@ pub enum AllowlistError {
     /// 路径遍历攻击尝试喵
     #[error("Path traversal attack detected: {0}")]
     PathTraversalAttempt(String),
+
+    /// 路径命中拒绝规则喵
+    #[error("Path denied by rule '{1}': {0}")]
+    PathDenied(String, String),
+
+    /// 参数未通过校验喵
+    #[error("Arguments rejected for '{0}': {1}")]
+    ArgumentRejected(String, String),
 }
 
+/// 默认最大参数个数喵
+pub const DEFAULT_MAX_ARGS: usize = 32;
+/// 默认单个参数最大长度（字节）喵
+pub const DEFAULT_MAX_ARG_LENGTH: usize = 1024;
+
+/// Shell 元字符（命令不经过 shell 执行，但仍默认拒绝以防被下游再次解释喵）
+const SHELL_METACHARACTERS: &[char] = &[
+    '|', ';', '&', '$', '`', '<', '>', '(', ')', '{', '}', '\\', '\'', '"', '*', '?', '~',
+];
+
 /// 命令白名单条目喵
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommandAllowlistEntry {
@@ -42,19 +63,39 @@ pub struct CommandAllowlistEntry {
     pub description: String,
     /// 是否允许带参数喵
     pub allow_args: bool,
-    /// 允许的参数模式（正则表达式，空表示不允许参数喵）
+    /// 允许的参数模式（正则表达式，每个参数都必须完整匹配喵）
     pub arg_pattern: Option<String>,
+    /// 最大参数个数（默认 32）喵
+    #[serde(default)]
+    pub max_args: Option<usize>,
+    /// 单个参数最大长度（默认 1024 字节）喵
+    #[serde(default)]
+    pub max_arg_length: Option<usize>,
+    /// 是否允许 shell 元字符（默认拒绝）喵
+    #[serde(default)]
+    pub allow_metacharacters: bool,
 }
 
-/// 路径白名单条目喵
+/// 路径规则条目喵
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathAllowlistEntry {
-    /// 路径模式（支持 glob 风格喵）
+    /// 路径模式（glob 或正则，见 `kind` 喵）
     pub pattern: String,
     /// 路径描述喵
+    #[serde(default)]
     pub description: String,
-    /// 是否允许递归访问喵
+    /// 是否允许递归访问（glob 模式自动追加 `/**` 喵）
+    #[serde(default)]
     pub recursive: bool,
+    /// 模式类型（默认 glob）喵
+    #[serde(default)]
+    pub kind: PatternKind,
+    /// 允许或拒绝（拒绝优先）喵
+    #[serde(default)]
+    pub effect: RuleEffect,
+    /// 规则约束的访问类型（默认读写）喵
+    #[serde(default)]
+    pub access: RuleAccess,
 }
 
 /// 白名单配置喵
@@ -78,8 +119,10 @@ pub struct AllowlistService {
     command_set: HashSet<String>,
     /// 命令详情映射喵
     command_details: HashMap<String, CommandAllowlistEntry>,
-    /// 路径白名单喵
-    path_set: HashSet<String>,
+    /// 编译后的参数模式（None = 模式无效，拒绝所有参数喵）
+    arg_patterns: HashMap<String, Option<Regex>>,
+    /// 编译后的路径规则喵
+    path_rules: PathRuleSet,
     /// 默认拒绝策略喵
     default_deny: bool,
 }
@@ -99,21 +142,27 @@ impl AllowlistService {
     pub fn new(config: AllowlistConfig) -> Self {
         let mut command_set = HashSet::new();
         let mut command_details = HashMap::new();
+        let mut arg_patterns = HashMap::new();
 
         for entry in config.commands {
+            if let Some(pattern) = &entry.arg_pattern {
+                // 🔒 SAFETY: 强制完整匹配，无效模式失败即关闭喵
+                let compiled = Regex::new(&format!("^(?:{})$", pattern))
+                    .map_err(|e| warn!("Invalid arg_pattern for '{}': {}", entry.command, e))
+                    .ok();
+                arg_patterns.insert(entry.command.clone(), compiled);
+            }
             command_set.insert(entry.command.clone());
             command_details.insert(entry.command.clone(), entry);
         }
 
-        let mut path_set = HashSet::new();
-        for entry in config.paths {
-            path_set.insert(entry.pattern);
-        }
+        let path_rules = PathRuleSet::compile(&config.paths);
 
         Self {
             command_set,
             command_details,
-            path_set,
+            arg_patterns,
+            path_rules,
             default_deny: config.default_deny,
         }
     }
@@ -127,16 +176,17 @@ impl AllowlistService {
     /// Ok(CommandAllowlistEntry) = 允许喵，Err = 拒绝喵
     ///
     /// 🔐 PERMISSION: 需要对执行命令进行安全检查喵
+    ///
+    /// ⚠️ SAFETY: 带路径的命令一律拒绝喵（`/tmp/x/git` 不是白名单里的 `git`）；
+    /// 调用方应执行返回条目中的 `command`，而不是原始输入喵
     pub fn check_command(&self, command: &str) -> Result<CommandAllowlistEntry, AllowlistError> {
-        // 标准化命令名称（小写，移除路径喵）
+        if command.contains(['/', '\\']) {
+            return Err(AllowlistError::CommandNotAllowed(command.to_string()));
+        }
+
+        // 标准化命令名称（小写，取第一个词喵）
         let normalized = command.to_lowercase();
-        let normalized = normalized
-            .split_whitespace()
-            .next()
-            .unwrap_or("")
-            .split('/')
-            .last()
-            .unwrap_or("");
+        let normalized = normalized.split_whitespace().next().unwrap_or("");
 
         if self.command_set.contains(normalized) {
             // 🔒 SAFETY: 使用 ok_or 替代 unwrap() 喵
@@ -147,14 +197,68 @@ impl AllowlistService {
             Err(AllowlistError::CommandNotAllowed(command.to_string()))
         } else {
             Ok(CommandAllowlistEntry {
-                command: command.to_string(),
+                command: normalized.to_string(),
                 description: "Default allowed".to_string(),
                 allow_args: false,
                 arg_pattern: None,
+                max_args: None,
+                max_arg_length: None,
+                allow_metacharacters: false,
             })
         }
     }
 
+    /// 校验命令的完整参数列表喵
+    ///
+    /// ## Arguments
+    /// * `command` - 命令名称喵
+    /// * `args` - 参数列表喵
+    ///
+    /// ## Returns
+    /// Ok(()) = 参数合法喵，Err = 拒绝喵
+    ///
+    /// ⚠️ SAFETY: 依次检查参数个数、长度、控制字符、元字符与 arg_pattern 喵
+    pub fn check_arguments(&self, command: &str, args: &[&str]) -> Result<(), AllowlistError> {
+        let entry = self.check_command(command)?;
+        let reject = |reason: String| Err(AllowlistError::ArgumentRejected(command.to_string(), reason));
+
+        if args.is_empty() {
+            return Ok(());
+        }
+        if !entry.allow_args {
+            return reject("command does not accept arguments".to_string());
+        }
+
+        let max_args = entry.max_args.unwrap_or(DEFAULT_MAX_ARGS);
+        if args.len() > max_args {
+            return reject(format!("{} arguments exceeds limit of {}", args.len(), max_args));
+        }
+
+        let max_len = entry.max_arg_length.unwrap_or(DEFAULT_MAX_ARG_LENGTH);
+        let pattern = self.arg_patterns.get(&entry.command);
+
+        for arg in args {
+            if arg.len() > max_len {
+                return reject(format!("argument longer than {} bytes", max_len));
+            }
+            if arg.chars().any(|c| c.is_control()) {
+                return reject(format!("control character in argument {:?}", arg));
+            }
+            if !entry.allow_metacharacters && arg.contains(SHELL_METACHARACTERS) {
+                return reject(format!("shell metacharacter in argument {:?}", arg));
+            }
+            match pattern {
+                Some(Some(re)) if !re.is_match(arg) => {
+                    return reject(format!("argument {:?} does not match allowed pattern", arg));
+                }
+                Some(None) => return reject("arg_pattern is invalid".to_string()),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
     /// 检查命令是否允许（简化接口）喵
     ///
     /// ## Arguments
@@ -177,91 +281,135 @@ impl AllowlistService {
         self.check_path(path).is_ok()
     }
 
-    /// 检查环境变量是否允许喵
+    /// 获取允许的命令列表喵
+    ///
+    /// ## Returns
+    /// 允许的命令名称列表喵
+    pub fn get_allowed_commands(&self) -> Vec<String> {
+        self.command_set.iter().cloned().collect()
+    }
+
+    /// 生成临时放行某次调用的白名单副本喵
     ///
     /// ## Arguments
-    /// * `key` - 环境变量名喵
+    /// * `command` - 经主人批准放行的命令喵
+    /// * `args` - 批准时的参数，副本只接受这些参数喵
     ///
-    /// ## Returns
-    /// true = 允许喵（目前默认允许安全的环境变量）
-    pub fn is_env_var_allowed(&self, key: &str) -> bool {
-        // 安全的环境变量白名单喵
-        let safe_vars = ["HOME", "USER", "PATH", "LANG", "TZ", "TERM", "SHELL", "PWD"];
-        safe_vars.contains(&key)
+    /// 🔐 PERMISSION: 仅用于已审计的一次性放行令牌喵
+    pub fn with_command_override(&self, command: &str, args: &[String]) -> Self {
+        let normalized = command
+            .to_lowercase()
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_string();
+
+        // 🔒 SAFETY: 参数只能取自批准时的 argv 喵
+        let arg_pattern = args.iter().map(|a| regex::escape(a)).collect::<Vec<_>>().join("|");
+        let mut service = self.clone();
+        service.command_set.insert(normalized.clone());
+        service
+            .arg_patterns
+            .insert(normalized.clone(), Regex::new(&format!("^(?:{})$", arg_pattern)).ok());
+        service.command_details.insert(
+            normalized.clone(),
+            CommandAllowlistEntry {
+                command: normalized,
+                description: "Approved escalation override".to_string(),
+                allow_args: !args.is_empty(),
+                arg_pattern: Some(arg_pattern),
+                max_args: Some(args.len()),
+                max_arg_length: None,
+                allow_metacharacters: false,
+            },
+        );
+        service
     }
 
-    /// 获取允许的命令列表喵
+    /// 将拒绝原因转换为结构化的策略信息喵
+    ///
+    /// ## Arguments
+    /// * `error` - 白名单检查返回的错误喵
     ///
     /// ## Returns
-    /// 允许的命令名称列表喵
-    pub fn get_allowed_commands(&self) -> Vec<String> {
-        self.command_set.iter().cloned().collect()
+    /// 包含替代建议与提权可能性的策略信息喵
+    pub fn explain(&self, error: &AllowlistError) -> PolicyViolation {
+        match error {
+            AllowlistError::CommandNotAllowed(command) => {
+                let mut allowed = self.get_allowed_commands();
+                allowed.sort();
+                let violation = PolicyViolation::new(
+                    "command_allowlist",
+                    command.as_str(),
+                    "command is not in the allowlist",
+                )
+                .with_escalation(true);
+                if allowed.is_empty() {
+                    violation
+                } else {
+                    violation.with_suggestion(format!("Allowed commands: {}", allowed.join(", ")))
+                }
+            }
+            AllowlistError::PathNotAllowed(path) => {
+                let patterns = self.path_rules.allow_patterns();
+                PolicyViolation::new("path_allowlist", path.as_str(), "path is not in the allowlist")
+                    .with_suggestion(format!("Allowed paths: {}", patterns.join(", ")))
+                    .with_escalation(true)
+            }
+            AllowlistError::PathTraversalAttempt(path) => PolicyViolation::new(
+                "sensitive_path",
+                path.as_str(),
+                "path traversal or sensitive location",
+            )
+            .with_suggestion("Use a path inside the workspace"),
+            AllowlistError::ArgumentRejected(command, reason) => PolicyViolation::new(
+                "argument_policy",
+                command.as_str(),
+                reason.as_str(),
+            )
+            .with_suggestion(
+                "Pass plain arguments: commands run without a shell, so quoting, $(...), backticks and $VARS are rejected",
+            ),
+            AllowlistError::PathDenied(path, rule) => PolicyViolation::new(
+                "path_denylist",
+                path.as_str(),
+                format!("path matches deny rule '{}'", rule),
+            ),
+        }
     }
 
-    /// 检查路径是否在白名单中喵
+    /// 检查路径是否允许读取喵
     ///
     /// ## Arguments
     /// * `path` - 要检查的路径喵
     ///
     /// ## Returns
     /// Ok(()) = 允许喵，Err = 拒绝喵
-    ///
-    /// ⚠️ SAFETY: 必须检测路径遍历攻击喵
-    /// 🔐 PERMISSION: 需要对文件系统访问进行安全检查喵
     pub fn check_path(&self, path: &str) -> Result<(), AllowlistError> {
-        // 1. 检测路径遍历攻击喵
-        if path.contains("..")
-            || path.starts_with("/etc")
-            || path.starts_with("/root")
-            || path.contains(".ssh")
-            || path.contains(".aws")
-            || path.contains("password")
-        {
-            return Err(AllowlistError::PathTraversalAttempt(path.to_string()));
-        }
-
-        // 2. 标准化路径喵
-        let normalized = PathBuf::from(path);
-        let normalized_str = normalized.to_string_lossy().to_lowercase();
-
-        // 3. 检查白名单喵
-        for allowed_pattern in &self.path_set {
-            if self.path_matches(&normalized_str, allowed_pattern) {
-                return Ok(());
-            }
-        }
-
-        if self.default_deny {
-            Err(AllowlistError::PathNotAllowed(path.to_string()))
-        } else {
-            Ok(())
-        }
+        self.check_path_access(path, PathAccess::Read)
     }
 
-    /// 路径匹配检查喵（简化版 glob 匹配）
-    fn path_matches(&self, path: &str, pattern: &str) -> bool {
-        // 精确匹配喵
-        if path == pattern {
-            return true;
-        }
-
-        // 前缀匹配喵（支持递归访问喵）
-        if pattern.ends_with("/**") {
-            let prefix = &pattern[..pattern.len() - 3];
-            if path.starts_with(prefix) {
-                return true;
-            }
-        }
-
-        // 后缀匹配喵
-        if pattern.starts_with("**") {
-            let suffix = &pattern[2..];
-            if path.ends_with(suffix) {
-                return true;
+    /// 检查路径的指定访问类型喵
+    ///
+    /// ## Arguments
+    /// * `path` - 要检查的路径喵
+    /// * `access` - 读或写喵
+    ///
+    /// ## Returns
+    /// Ok(()) = 允许喵，Err = 拒绝喵
+    ///
+    /// ⚠️ SAFETY: deny 规则优先，符号链接解析后的路径同样要通过喵
+    /// 🔐 PERMISSION: 需要对文件系统访问进行安全检查喵
+    pub fn check_path_access(&self, path: &str, access: PathAccess) -> Result<(), AllowlistError> {
+        match self.path_rules.evaluate(path, access) {
+            PathDecision::Allowed => Ok(()),
+            PathDecision::Traversal => Err(AllowlistError::PathTraversalAttempt(path.to_string())),
+            PathDecision::Denied(rule) => Err(AllowlistError::PathDenied(path.to_string(), rule)),
+            PathDecision::NoMatch if self.default_deny => {
+                Err(AllowlistError::PathNotAllowed(path.to_string()))
             }
+            PathDecision::NoMatch => Ok(()),
         }
-
-        false
     }
 }
 
@@ -275,60 +423,90 @@ impl Default for AllowlistConfig {
                     description: "Git 版本控制".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/.= ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "ls".to_string(),
                     description: "列出目录内容".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/. ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "cat".to_string(),
                     description: "查看文件内容".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/.]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "grep".to_string(),
                     description: "搜索文件内容".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/.= ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "cargo".to_string(),
                     description: "Rust 构建工具".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/.= ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "npm".to_string(),
                     description: "Node 包管理器".to_string(),
                     allow_args: true,
                     arg_pattern: Some(r"^[-a-zA-Z0-9_/.= ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "echo".to_string(),
                     description: "输出文本".to_string(),
                     allow_args: true,
-                    arg_pattern: Some(r"^[-a-zA-Z0-9_/.= ]+$".to_string()),
+                    arg_pattern: Some(r"^[-a-zA-Z0-9_/.=,!: ]+$".to_string()),
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "pwd".to_string(),
                     description: "显示当前目录".to_string(),
                     allow_args: false,
                     arg_pattern: None,
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "date".to_string(),
                     description: "显示日期时间".to_string(),
                     allow_args: false,
                     arg_pattern: None,
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
                 CommandAllowlistEntry {
                     command: "whoami".to_string(),
                     description: "显示当前用户".to_string(),
                     allow_args: false,
                     arg_pattern: None,
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
                 },
             ],
             paths: vec![
@@ -336,16 +514,25 @@ impl Default for AllowlistConfig {
                     pattern: "/home/ubuntu/.openclaw/**".to_string(),
                     description: "OpenClaw 工作目录".to_string(),
                     recursive: true,
+                    kind: PatternKind::Glob,
+                    effect: RuleEffect::Allow,
+                    access: RuleAccess::ReadWrite,
                 },
                 PathAllowlistEntry {
                     pattern: "/tmp/**".to_string(),
                     description: "临时文件目录".to_string(),
                     recursive: true,
+                    kind: PatternKind::Glob,
+                    effect: RuleEffect::Allow,
+                    access: RuleAccess::ReadWrite,
                 },
                 PathAllowlistEntry {
                     pattern: "/var/log/**".to_string(),
                     description: "日志目录（只读）".to_string(),
                     recursive: true,
+                    kind: PatternKind::Glob,
+                    effect: RuleEffect::Allow,
+                    access: RuleAccess::Read,
                 },
             ],
             default_deny: true,
@@ -358,7 +545,7 @@ mod tests {
     use super::*;
 
     /// 测试命令白名单检查喵
-    #[tokio::test]
+    #[test]
     fn test_command_whitelist() {
         let config = AllowlistConfig::default();
         let service = AllowlistService::new(config);
@@ -375,7 +562,7 @@ mod tests {
     }
 
     /// 测试路径白名单检查喵
-    #[tokio::test]
+    #[test]
     fn test_path_whitelist() {
         let config = AllowlistConfig::default();
         let service = AllowlistService::new(config);
@@ -395,4 +582,106 @@ mod tests {
             .check_path("/home/ubuntu/.openclaw/../../../etc/passwd")
             .is_err());
     }
+
+    /// 测试参数绕过尝试喵
+    #[test]
+    fn test_argument_bypass_attempts() {
+        let service = AllowlistService::new(AllowlistConfig::default());
+
+        assert!(service.check_arguments("echo", &["Hello, Neko-Claw!"]).is_ok());
+        assert!(service.check_arguments("git", &["status", "--short"]).is_ok());
+
+        let attempts = [
+            "$(id)",
+            "`id`",
+            "${HOME}",
+            "$HOME",
+            "'; rm -rf / '",
+            "\"quoted\"",
+            "a && b",
+            "a | sh",
+            "> /tmp/out",
+            "line\nbreak",
+            "nul\0byte",
+            "~/.ssh/id_rsa",
+            "*",
+        ];
+        for arg in attempts {
+            assert!(
+                matches!(
+                    service.check_arguments("echo", &[arg]),
+                    Err(AllowlistError::ArgumentRejected(_, _))
+                ),
+                "bypass accepted: {:?}",
+                arg
+            );
+        }
+
+        // 路径限定的同名程序不是白名单里的命令喵
+        for command in ["/tmp/x/git", "./git", "../bin/echo", "C:\\tmp\\git.exe"] {
+            assert!(
+                matches!(service.check_arguments(command, &[]), Err(AllowlistError::CommandNotAllowed(_))),
+                "bypass accepted: {:?}",
+                command
+            );
+        }
+        let permissive = AllowlistService::new(AllowlistConfig {
+            default_deny: false,
+            ..AllowlistConfig::default()
+        });
+        assert!(permissive.check_command("/tmp/x/git").is_err());
+        assert_eq!(service.check_command("GIT").unwrap().command, "git");
+    }
+
+    /// 测试参数数量/长度上限与模式完整匹配喵
+    #[test]
+    fn test_argument_limits_and_pattern() {
+        let config = AllowlistConfig {
+            commands: vec![CommandAllowlistEntry {
+                command: "tool".to_string(),
+                description: String::new(),
+                allow_args: true,
+                arg_pattern: Some("[a-z]+".to_string()),
+                max_args: Some(2),
+                max_arg_length: Some(4),
+                allow_metacharacters: false,
+            }],
+            ..AllowlistConfig::default()
+        };
+        let service = AllowlistService::new(config);
+
+        assert!(service.check_arguments("tool", &["ab", "cd"]).is_ok());
+        assert!(service.check_arguments("tool", &["a", "b", "c"]).is_err());
+        assert!(service.check_arguments("tool", &["abcde"]).is_err());
+        // 部分匹配不算通过喵
+        assert!(service.check_arguments("tool", &["ab1"]).is_err());
+    }
+
+    /// 测试放行副本只接受批准时的参数喵
+    #[test]
+    fn test_command_override_pins_arguments() {
+        let service = AllowlistService::new(AllowlistConfig::default());
+        let approved = vec!["-r".to_string(), "build.cache".to_string()];
+        let overridden = service.with_command_override("rm", &approved);
+
+        assert!(overridden.check_arguments("rm", &["-r", "build.cache"]).is_ok());
+        assert!(overridden.check_arguments("rm", &["-r", "/"]).is_err());
+        assert!(overridden.check_arguments("rm", &["-r", "build.cache", "-f"]).is_err());
+        assert!(service.check_command("rm").is_err());
+    }
+
+    /// 测试关闭默认拒绝时，未列出的命令只能无参数执行喵
+    #[test]
+    fn test_default_allow_rejects_arguments() {
+        let service = AllowlistService::new(AllowlistConfig {
+            default_deny: false,
+            ..AllowlistConfig::default()
+        });
+
+        assert!(service.check_command("uptime").is_ok());
+        assert!(service.check_arguments("uptime", &[]).is_ok());
+        assert!(service.check_arguments("uptime", &["-p"]).is_err());
+        // 列出的命令仍按自己的规则检查喵
+        assert!(service.check_arguments("git", &["status"]).is_ok());
+    }
 }
@@ -0,0 +1,230 @@
+//! # Tool Approval ⚠️
+//!
+//! 标记为 `dangerous` 的工具执行前需要主人确认喵
+//!
+//! ## 放行顺序
+//! 1. 非危险工具直接执行
+//! 2. 配置 `auto_approve` 中列出的工具
+//! 3. `nekoclaw agent --yes`：本次运行全部放行
+//! 4. 本会话中已回答"总是允许"的工具
+//! 5. 交互确认 `[y/N/a]`；无法交互（gateway、管道输入）时拒绝
+//!
+//! 危险工具的每次决定都记入遥测事件 `tool_approval`（tool / decision / source）喵
+//!
+//! ```toml
+//! [security.tool_approval]
+//! auto_approve = ["fs_write"]
+//! ```
+
+use crate::telemetry::MetricsRecorder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tracing::info;
+
+/// 危险工具确认配置喵
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolApprovalConfig {
+    /// 无需确认即可执行的危险工具喵
+    #[serde(default)]
+    pub auto_approve: Vec<String>,
+}
+
+/// 主人对确认提示的回答喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalAnswer {
+    Yes,
+    No,
+    /// 本会话内总是允许该工具
+    Always,
+}
+
+/// 决定的来源喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalSource {
+    /// 配置 `auto_approve`
+    Config,
+    /// `--yes`
+    YesFlag,
+    /// 本会话之前回答过"总是允许"
+    Session,
+    /// 主人当场确认或拒绝
+    User,
+    /// 无法询问主人
+    NonInteractive,
+}
+
+impl ApprovalSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::YesFlag => "yes_flag",
+            Self::Session => "session",
+            Self::User => "user",
+            Self::NonInteractive => "non_interactive",
+        }
+    }
+}
+
+/// 确认提示：参数为工具名与调用参数，返回 None 表示无法交互喵
+pub type ApprovalPrompt = Box<dyn Fn(&str, &JsonValue) -> Option<ApprovalAnswer> + Send + Sync>;
+
+/// 🔒 SAFETY: 危险工具执行前的确认喵
+pub struct ToolApproval {
+    auto_approve: HashSet<String>,
+    assume_yes: bool,
+    /// 本会话中回答过"总是允许"的工具
+    always: Mutex<HashSet<String>>,
+    prompt: Option<ApprovalPrompt>,
+    recorder: Option<MetricsRecorder>,
+}
+
+impl ToolApproval {
+    pub fn new(config: &ToolApprovalConfig) -> Self {
+        Self {
+            auto_approve: config.auto_approve.iter().cloned().collect(),
+            assume_yes: false,
+            always: Mutex::new(HashSet::new()),
+            prompt: None,
+            recorder: None,
+        }
+    }
+
+    /// ⚠️ SAFETY: `--yes`，放行所有危险工具喵
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// 交互确认方式（未设置时无法确认的调用一律拒绝）喵
+    pub fn with_prompt<F>(mut self, prompt: F) -> Self
+    where
+        F: Fn(&str, &JsonValue) -> Option<ApprovalAnswer> + Send + Sync + 'static,
+    {
+        self.prompt = Some(Box::new(prompt));
+        self
+    }
+
+    /// 把决定记入遥测喵
+    pub fn with_recorder(mut self, recorder: MetricsRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 🔒 SAFETY: 决定是否执行一次工具调用喵
+    ///
+    /// ## Returns
+    /// 拒绝时返回给模型看的原因
+    pub fn authorize(&self, tool: &str, dangerous: bool, input: &JsonValue) -> Result<(), String> {
+        if !dangerous {
+            return Ok(());
+        }
+        let (approved, source) = self.decide(tool, input);
+        let decision = if approved { "approved" } else { "denied" };
+        info!("⚠️ 危险工具 {} {}（{}）喵", tool, decision, source.as_str());
+        if let Some(recorder) = &self.recorder {
+            recorder.event(
+                "tool_approval",
+                &[("tool", tool), ("decision", decision), ("source", source.as_str())],
+            );
+        }
+
+        match (approved, source) {
+            (true, _) => Ok(()),
+            (false, ApprovalSource::NonInteractive) => Err(format!(
+                "{} is marked dangerous and needs Master's confirmation, which is not possible in this context \
+                (it can be allowed with security.tool_approval.auto_approve or `nekoclaw agent --yes`)",
+                tool
+            )),
+            (false, _) => Err(format!("Master declined to run {}", tool)),
+        }
+    }
+
+    fn decide(&self, tool: &str, input: &JsonValue) -> (bool, ApprovalSource) {
+        if self.auto_approve.contains(tool) {
+            return (true, ApprovalSource::Config);
+        }
+        if self.assume_yes {
+            return (true, ApprovalSource::YesFlag);
+        }
+        if self.always.lock().unwrap().contains(tool) {
+            return (true, ApprovalSource::Session);
+        }
+        match self.prompt.as_ref().and_then(|prompt| prompt(tool, input)) {
+            Some(ApprovalAnswer::Yes) => (true, ApprovalSource::User),
+            Some(ApprovalAnswer::Always) => {
+                self.always.lock().unwrap().insert(tool.to_string());
+                (true, ApprovalSource::User)
+            }
+            Some(ApprovalAnswer::No) => (false, ApprovalSource::User),
+            None => (false, ApprovalSource::NonInteractive),
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolApproval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolApproval")
+            .field("auto_approve", &self.auto_approve)
+            .field("assume_yes", &self.assume_yes)
+            .field("interactive", &self.prompt.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{MetricsCollector, MetricsConfig};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_dangerous_tools_need_approval() {
+        let metrics = Arc::new(
+            MetricsCollector::new(MetricsConfig {
+                db_path: ":memory:".to_string(),
+                monitor_interval_sec: 5,
+            })
+            .await
+            .unwrap(),
+        );
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let approval = ToolApproval::new(&ToolApprovalConfig {
+            auto_approve: vec!["fs_write".to_string()],
+        })
+        .with_recorder(MetricsRecorder::new(metrics.clone()))
+        .with_prompt(move |tool, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Some(if tool == "shell" { ApprovalAnswer::Always } else { ApprovalAnswer::No })
+        });
+
+        let input = json!({});
+        assert!(approval.authorize("echo", false, &input).is_ok());
+        assert!(approval.authorize("fs_write", true, &input).is_ok());
+        assert!(approval.authorize("shell", true, &input).is_ok());
+        assert!(approval.authorize("shell", true, &input).is_ok());
+        assert_eq!(approval.authorize("rm_rf", true, &input).unwrap_err(), "Master declined to run rm_rf");
+        // "总是允许"之后不再询问喵
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+
+        let events = metrics.get_recent_custom_metrics(Some("tool_approval"), 10).unwrap();
+        let mut sources: Vec<(&str, &str)> = events
+            .iter()
+            .map(|e| (e.labels["decision"].as_str(), e.labels["source"].as_str()))
+            .collect();
+        sources.sort();
+        assert_eq!(
+            sources,
+            [("approved", "config"), ("approved", "session"), ("approved", "user"), ("denied", "user")]
+        );
+
+        // 无法交互时拒绝，--yes 时放行喵
+        let headless = ToolApproval::new(&ToolApprovalConfig::default());
+        assert!(headless.authorize("shell", true, &input).unwrap_err().contains("auto_approve"));
+        assert!(headless.with_assume_yes(true).authorize("shell", true, &input).is_ok());
+    }
+}
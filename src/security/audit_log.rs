@@ -0,0 +1,727 @@
+//!
+//! # Audit Log
+//!
+//! ⚠️ SAFETY: 防篡改的审计日志 - 工具执行、Shell 命令、配置变更与认证事件喵
+//!
+//! ## 功能说明
+//! - 事件写入 `<工作区>/audit.db`（SQLite），记录时间、类型、执行者、动作、参数与结果状态喵
+//! - 表只能追加：触发器拒绝 UPDATE / DELETE 喵
+//! - 每条记录的 `hash = HMAC-SHA256(审计密钥, prev_hash + 库中原样保存的字段)`，串成哈希链；
+//!   审计密钥保存在 `<工作区>/keys/audit.keyring`（权限 0600），只能改库、拿不到密钥的人无法重算哈希，
+//!   绕过触发器直接改库会在 `verify` 时暴露第一条被改动的记录喵
+//! - 类型、状态、时间或参数无法解析的记录同样视为被篡改，查询与校验都不会用默认值掩盖喵
+//! - 参数中 `password` / `token` / `secret` / `api_key` 类字段的值被替换为 `[redacted]`，过长的字符串被截断喵
+//! - 按用户删除数据（`privacy forget` / 保留期）时不删行，而是把执行者、参数与附加信息替换为 `[erased]`
+//!   并重新计算哈希链，时间、类型、动作与状态仍保留在链上喵
+//!
+//! ```text
+//! nekoclaw audit tail -n 20
+//! nekoclaw audit search rm --kind shell
+//! nekoclaw audit verify
+//! ```
+
+use super::crypto::{derive_key, CryptoError};
+use super::keyring::KeyRing;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use rusqlite::{params, Connection, Row, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// 审计数据库文件名喵
+pub const AUDIT_DB_FILE: &str = "audit.db";
+
+/// 第一条记录的 prev_hash 喵
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 审计密钥环的用途名喵（`<工作区>/keys/audit.keyring`，不参与密钥轮换）
+const AUDIT_KEYRING: &str = "audit";
+
+/// 从审计密钥派生哈希链密钥时使用的 context 喵
+const CHAIN_KEY_CONTEXT: &str = "audit-chain";
+
+/// 被抹去的个人数据喵
+pub const ERASED: &str = "[erased]";
+
+/// 参数中单个字符串的最大长度（字符）喵
+const MAX_ARGUMENT_CHARS: usize = 2048;
+
+/// 值会被隐藏的参数名片段喵
+const SECRET_KEY_PARTS: &[&str] = &["password", "passwd", "token", "secret", "api_key", "apikey", "authorization"];
+
+/// 审计日志错误喵
+#[derive(Debug, Error)]
+pub enum AuditError {
+    /// 数据库错误喵
+    #[error("Audit store error: {0}")]
+    Store(#[from] rusqlite::Error),
+
+    /// 无法创建数据目录喵
+    #[error("Audit directory error: {0}")]
+    Io(String),
+
+    /// 哈希链断裂或记录无法解析：该记录或之前的记录被改动喵
+    #[error("Audit log tampered at entry {0}")]
+    Tampered(i64),
+
+    /// 无法读取审计密钥喵
+    #[error("Audit key unavailable: {0}")]
+    Key(#[from] CryptoError),
+
+    /// 未知事件类型喵
+    #[error("Unknown audit kind: {0} (tool, shell, config, auth, admin)")]
+    InvalidKind(String),
+}
+
+/// 审计事件类型喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditKind {
+    /// 工具执行
+    Tool,
+    /// Shell 命令
+    Shell,
+    /// 配置变更
+    Config,
+    /// 认证事件（登录失败、锁定、配对）
+    Auth,
+    /// 管理操作（角色、密钥、放行）
+    Admin,
+}
+
+impl AuditKind {
+    /// 工具调用的事件类型喵（`shell` 工具记为 Shell）
+    pub fn for_tool(name: &str) -> Self {
+        match name {
+            "shell" => AuditKind::Shell,
+            _ => AuditKind::Tool,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditKind::Tool => "tool",
+            AuditKind::Shell => "shell",
+            AuditKind::Config => "config",
+            AuditKind::Auth => "auth",
+            AuditKind::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditKind {
+    type Err = AuditError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tool" => Ok(AuditKind::Tool),
+            "shell" => Ok(AuditKind::Shell),
+            "config" => Ok(AuditKind::Config),
+            "auth" => Ok(AuditKind::Auth),
+            "admin" => Ok(AuditKind::Admin),
+            _ => Err(AuditError::InvalidKind(s.to_string())),
+        }
+    }
+}
+
+/// 结果状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditStatus {
+    Ok,
+    /// 被策略 / 权限 / 确认拒绝
+    Denied,
+    Failed,
+}
+
+impl AuditStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditStatus::Ok => "ok",
+            AuditStatus::Denied => "denied",
+            AuditStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for AuditStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditStatus {
+    type Err = AuditError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ok" => Ok(AuditStatus::Ok),
+            "denied" => Ok(AuditStatus::Denied),
+            "failed" => Ok(AuditStatus::Failed),
+            _ => Err(AuditError::InvalidKind(s.to_string())),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 待写入的审计事件喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub kind: AuditKind,
+    /// 执行者（`cli:<用户>`、`telegram:<ID>`、`gateway:<Key 名称>` 等）
+    pub actor: String,
+    /// 动作（工具名、命令、配置键、认证事件名）
+    pub action: String,
+    pub arguments: JsonValue,
+    pub status: AuditStatus,
+    /// 失败原因等附加信息
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditKind, actor: &str, action: &str) -> Self {
+        Self {
+            kind,
+            actor: actor.to_string(),
+            action: action.to_string(),
+            arguments: JsonValue::Null,
+            status: AuditStatus::Ok,
+            detail: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 附带参数喵（写入前隐藏密钥、截断长字符串）
+    pub fn with_arguments(mut self, arguments: JsonValue) -> Self {
+        self.arguments = redact(arguments);
+        self
+    }
+
+    pub fn with_status(mut self, status: AuditStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(truncate(detail.into()));
+        self
+    }
+}
+
+/// 已写入的审计记录喵
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub time: DateTime<Utc>,
+    pub event: AuditEvent,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// 搜索条件喵（字段为 None 时不过滤）
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// 匹配动作、参数或附加信息的子串
+    pub text: Option<String>,
+    pub kind: Option<AuditKind>,
+    pub actor: Option<String>,
+    pub status: Option<AuditStatus>,
+    /// 最多返回的条数（最新的在后）
+    pub limit: usize,
+}
+
+/// 库中原样保存的一行喵（哈希针对这些原始列计算，不经过解析）
+struct StoredRow {
+    id: i64,
+    time: String,
+    kind: String,
+    actor: String,
+    action: String,
+    arguments: String,
+    status: String,
+    detail: Option<String>,
+    prev_hash: String,
+    hash: String,
+}
+
+impl StoredRow {
+    const COLUMNS: &'static str = "id, time, kind, actor, action, arguments, status, detail, prev_hash, hash";
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            time: row.get(1)?,
+            kind: row.get(2)?,
+            actor: row.get(3)?,
+            action: row.get(4)?,
+            arguments: row.get(5)?,
+            status: row.get(6)?,
+            detail: row.get(7)?,
+            prev_hash: row.get(8)?,
+            hash: row.get(9)?,
+        })
+    }
+
+    fn fields(&self) -> [&str; 7] {
+        [
+            &self.time,
+            &self.kind,
+            &self.actor,
+            &self.action,
+            &self.arguments,
+            &self.status,
+            self.detail.as_deref().unwrap_or(""),
+        ]
+    }
+
+    /// 🔒 SAFETY: 严格解析喵（任何一列不合法都返回 `Tampered`，不使用默认值）
+    fn parse(self) -> Result<AuditRecord, AuditError> {
+        let id = self.id;
+        Ok(AuditRecord {
+            id,
+            time: DateTime::parse_from_rfc3339(&self.time)
+                .map_err(|_| AuditError::Tampered(id))?
+                .with_timezone(&Utc),
+            event: AuditEvent {
+                kind: self.kind.parse().map_err(|_| AuditError::Tampered(id))?,
+                actor: self.actor,
+                action: self.action,
+                arguments: serde_json::from_str(&self.arguments).map_err(|_| AuditError::Tampered(id))?,
+                status: self.status.parse().map_err(|_| AuditError::Tampered(id))?,
+                detail: self.detail,
+            },
+            prev_hash: self.prev_hash,
+            hash: self.hash,
+        })
+    }
+}
+
+/// 🔒 SAFETY: 审计哈希链的 HMAC 密钥喵（`<root>/keys/audit.keyring`，不存在时生成）
+pub fn audit_chain_key(root: &Path) -> Result<[u8; 32], AuditError> {
+    let ring = KeyRing::open(&KeyRing::path_for(root, AUDIT_KEYRING), None)?;
+    Ok(derive_key(&ring.current_key()?, CHAIN_KEY_CONTEXT))
+}
+
+/// 🔒 SAFETY: 只能追加、以 HMAC 串链的审计日志喵
+pub struct AuditLog {
+    conn: Mutex<Connection>,
+    key: hmac::Key,
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog").finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    /// 打开（或创建）工作区的审计日志喵（`<root>/audit.db`，密钥来自 `<root>/keys/audit.keyring`）
+    pub fn open_workspace(root: &Path) -> Result<Self, AuditError> {
+        Self::open(root.join(AUDIT_DB_FILE), &audit_chain_key(root)?)
+    }
+
+    /// 打开（或创建）审计数据库喵
+    pub fn open<P: AsRef<Path>>(path: P, chain_key: &[u8]) -> Result<Self, AuditError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).map_err(|e| AuditError::Io(e.to_string()))?;
+        }
+        Self::initialize(Connection::open(path)?, chain_key)
+    }
+
+    /// 内存数据库（测试用）喵
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self, AuditError> {
+        Self::initialize(Connection::open_in_memory()?, &[7u8; 32])
+    }
+
+    fn initialize(conn: Connection, chain_key: &[u8]) -> Result<Self, AuditError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                time TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                arguments TEXT NOT NULL,
+                status TEXT NOT NULL,
+                detail TEXT,
+                prev_hash TEXT NOT NULL,
+                hash TEXT NOT NULL
+            );
+            CREATE TRIGGER IF NOT EXISTS audit_events_no_update BEFORE UPDATE ON audit_events
+            BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+            CREATE TRIGGER IF NOT EXISTS audit_events_no_delete BEFORE DELETE ON audit_events
+            BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            key: hmac::Key::new(hmac::HMAC_SHA256, chain_key),
+        })
+    }
+
+    /// 🔒 SAFETY: 追加一条事件喵（接在链尾）
+    pub fn record(&self, event: &AuditEvent) -> Result<i64, AuditError> {
+        self.record_at(event, Utc::now())
+    }
+
+    fn record_at(&self, event: &AuditEvent, time: DateTime<Utc>) -> Result<i64, AuditError> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        // 读链尾与写入放在同一写事务中，多个进程并发写入也不会分叉喵
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let prev_hash = tx
+            .query_row("SELECT hash FROM audit_events ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(GENESIS_HASH.to_string()),
+                e => Err(e),
+            })?;
+        let time = time.to_rfc3339();
+        let arguments = event.arguments.to_string();
+        let hash = self.chain_hash(
+            &prev_hash,
+            [
+                &time,
+                event.kind.as_str(),
+                &event.actor,
+                &event.action,
+                &arguments,
+                event.status.as_str(),
+                event.detail.as_deref().unwrap_or(""),
+            ],
+        );
+        tx.execute(
+            "INSERT INTO audit_events (time, kind, actor, action, arguments, status, detail, prev_hash, hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                time,
+                event.kind.as_str(),
+                event.actor,
+                event.action,
+                arguments,
+                event.status.as_str(),
+                event.detail,
+                prev_hash,
+                hash
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// 写入失败只记日志喵（审计不应让正在执行的操作失败）
+    pub fn record_or_warn(&self, event: &AuditEvent) {
+        if let Err(e) = self.record(event) {
+            tracing::warn!("Failed to write audit event {} {}: {}", event.kind, event.action, e);
+        }
+    }
+
+    fn rows(conn: &Connection, filter: &str, args: &[&dyn rusqlite::ToSql]) -> Result<Vec<StoredRow>, AuditError> {
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM audit_events {}", StoredRow::COLUMNS, filter))?;
+        let rows = stmt
+            .query_map(args, StoredRow::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    fn query(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> Result<Vec<AuditRecord>, AuditError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        Self::rows(&conn, filter, args)?.into_iter().map(StoredRow::parse).collect()
+    }
+
+    /// 最近的 n 条记录喵（按时间顺序）
+    pub fn tail(&self, n: usize) -> Result<Vec<AuditRecord>, AuditError> {
+        self.search(&AuditQuery {
+            limit: n,
+            ..Default::default()
+        })
+    }
+
+    /// 🔒 SAFETY: 按条件搜索喵（返回最近的 `limit` 条，按时间顺序）
+    pub fn search(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        let text = query.text.as_ref().map(|t| format!("%{}%", t));
+        let kind = query.kind.map(|k| k.as_str());
+        let status = query.status.map(|s| s.as_str());
+        let limit = query.limit as i64;
+        let mut records = self.query(
+            "WHERE (?1 IS NULL OR action LIKE ?1 OR arguments LIKE ?1 OR detail LIKE ?1)
+               AND (?2 IS NULL OR kind = ?2)
+               AND (?3 IS NULL OR actor = ?3)
+               AND (?4 IS NULL OR status = ?4)
+             ORDER BY id DESC LIMIT ?5",
+            &[&text, &kind, &query.actor, &status, &limit],
+        )?;
+        records.reverse();
+        Ok(records)
+    }
+
+    /// 🔒 SAFETY: 抹去某个执行者的全部记录喵（`privacy forget`）
+    ///
+    /// ## Returns
+    /// 被抹去的记录数
+    pub fn erase_actor(&self, actor: &str) -> Result<usize, AuditError> {
+        self.erase_where("actor = ?1", &[&actor])
+    }
+
+    /// 🔒 SAFETY: 抹去某渠道在 cutoff 之前的记录喵（保留期）
+    pub fn erase_channel_before(&self, channel: &str, cutoff: DateTime<Utc>) -> Result<usize, AuditError> {
+        let prefix = format!("{}:%", channel.replace('%', "\\%").replace('_', "\\_"));
+        self.erase_where("actor LIKE ?1 ESCAPE '\\' AND time < ?2", &[&prefix, &cutoff.to_rfc3339()])
+    }
+
+    /// 替换个人数据并从第一条被改动的记录起重新串链喵
+    ///
+    /// 临时移除 UPDATE 触发器，整个过程在同一写事务中完成；待重新串链的记录已被篡改时返回 `Tampered`，不做任何改动喵
+    fn erase_where(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> Result<usize, AuditError> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let first: Option<i64> = tx.query_row(
+            &format!("SELECT MIN(id) FROM audit_events WHERE actor != '{}' AND {}", ERASED, filter),
+            args,
+            |row| row.get(0),
+        )?;
+        let Some(first) = first else {
+            return Ok(0);
+        };
+        let mut prev_hash: String = tx
+            .query_row(
+                "SELECT hash FROM audit_events WHERE id < ?1 ORDER BY id DESC LIMIT 1",
+                [first],
+                |row| row.get(0),
+            )
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(GENESIS_HASH.to_string()),
+                e => Err(e),
+            })?;
+        // 先确认要重新串链的部分没有被改过，避免抹去数据时顺带把篡改洗白喵
+        self.verify_chain(&prev_hash, &Self::rows(&tx, "WHERE id >= ?1 ORDER BY id", &[&first])?)?;
+
+        tx.execute_batch("DROP TRIGGER IF EXISTS audit_events_no_update;")?;
+        let erased = tx.execute(
+            &format!(
+                "UPDATE audit_events SET actor = '{0}', arguments = '\"{0}\"', detail = NULL
+                 WHERE actor != '{0}' AND {1}",
+                ERASED, filter
+            ),
+            args,
+        )?;
+
+        for row in Self::rows(&tx, "WHERE id >= ?1 ORDER BY id", &[&first])? {
+            let hash = self.chain_hash(&prev_hash, row.fields());
+            tx.execute(
+                "UPDATE audit_events SET prev_hash = ?1, hash = ?2 WHERE id = ?3",
+                params![prev_hash, hash, row.id],
+            )?;
+            prev_hash = hash;
+        }
+        tx.execute_batch(
+            "CREATE TRIGGER audit_events_no_update BEFORE UPDATE ON audit_events
+             BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+        )?;
+        tx.commit()?;
+        Ok(erased)
+    }
+
+    /// 🔒 SAFETY: 校验整条哈希链喵
+    ///
+    /// ## Returns
+    /// Ok(记录数)；某条记录的哈希或链接不匹配、或无法解析时返回 `Tampered(该记录 ID)`
+    pub fn verify(&self) -> Result<usize, AuditError> {
+        let rows = {
+            let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+            Self::rows(&conn, "ORDER BY id", &[])?
+        };
+        self.verify_chain(GENESIS_HASH, &rows)?;
+        let count = rows.len();
+        for row in rows {
+            row.parse()?;
+        }
+        Ok(count)
+    }
+
+    /// 校验一段连续记录的链接与哈希喵
+    fn verify_chain(&self, prev_hash: &str, rows: &[StoredRow]) -> Result<(), AuditError> {
+        let mut prev_hash = prev_hash;
+        for row in rows {
+            if row.prev_hash != prev_hash || row.hash != self.chain_hash(prev_hash, row.fields()) {
+                return Err(AuditError::Tampered(row.id));
+            }
+            prev_hash = &row.hash;
+        }
+        Ok(())
+    }
+
+    /// 记录哈希喵：HMAC-SHA256，字段之间以 `\x1f` 分隔，避免拼接歧义
+    fn chain_hash(&self, prev_hash: &str, fields: [&str; 7]) -> String {
+        let mut context = hmac::Context::with_key(&self.key);
+        for field in std::iter::once(prev_hash).chain(fields) {
+            context.update(field.as_bytes());
+            context.update(&[0x1f]);
+        }
+        context
+            .sign()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// 隐藏密钥字段、截断长字符串喵
+fn redact(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let lower = key.to_ascii_lowercase();
+                    let value = match SECRET_KEY_PARTS.iter().any(|part| lower.contains(part)) {
+                        true => JsonValue::String("[redacted]".to_string()),
+                        false => redact(value),
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        JsonValue::Array(items) => JsonValue::Array(items.into_iter().map(redact).collect()),
+        JsonValue::String(s) => JsonValue::String(truncate(s)),
+        other => other,
+    }
+}
+
+fn truncate(s: String) -> String {
+    match s.char_indices().nth(MAX_ARGUMENT_CHARS) {
+        Some((end, _)) => format!("{}…", &s[..end]),
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_audit_log_chains_searches_and_detects_tampering() {
+        let log = AuditLog::in_memory().unwrap();
+        log.record(
+            &AuditEvent::new(AuditKind::Shell, "cli:nono", "shell")
+                .with_arguments(json!({ "command": "ls", "env": { "API_TOKEN": "sk-123" } })),
+        )
+        .unwrap();
+        log.record(
+            &AuditEvent::new(AuditKind::Auth, "gateway", "login")
+                .with_status(AuditStatus::Denied)
+                .with_detail("invalid API key"),
+        )
+        .unwrap();
+        log.record(&AuditEvent::new(AuditKind::Admin, "cli:nono", "rbac.assign").with_arguments(json!("discord:42")))
+            .unwrap();
+        assert_eq!(log.verify().unwrap(), 3);
+
+        let tail = log.tail(2).unwrap();
+        assert_eq!(tail.iter().map(|r| r.id).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(tail[1].prev_hash, log.tail(3).unwrap()[1].hash);
+
+        // 密钥字段被隐藏喵
+        let shell = &log.tail(3).unwrap()[0];
+        assert_eq!(shell.event.arguments["env"]["API_TOKEN"], "[redacted]");
+        assert_eq!(shell.prev_hash, GENESIS_HASH);
+
+        let search = |query: AuditQuery| log.search(&AuditQuery { limit: 10, ..query }).unwrap();
+        assert_eq!(search(AuditQuery { text: Some("invalid".into()), ..Default::default() }).len(), 1);
+        assert_eq!(search(AuditQuery { actor: Some("cli:nono".into()), ..Default::default() }).len(), 2);
+        let denied = search(AuditQuery {
+            kind: Some(AuditKind::Auth),
+            status: Some(AuditStatus::Denied),
+            ..Default::default()
+        });
+        assert_eq!(denied[0].event.action, "login");
+
+        // 只能追加：UPDATE / DELETE 被触发器拒绝喵
+        let conn = log.conn.lock().unwrap();
+        assert!(conn.execute("DELETE FROM audit_events WHERE id = 1", []).is_err());
+        assert!(conn.execute("UPDATE audit_events SET status = 'ok' WHERE id = 2", []).is_err());
+
+        // 绕过触发器改库会被哈希链发现喵
+        conn.execute_batch(
+            "DROP TRIGGER audit_events_no_update;
+             UPDATE audit_events SET status = 'ok' WHERE id = 2;",
+        )
+        .unwrap();
+        drop(conn);
+        assert!(matches!(log.verify(), Err(AuditError::Tampered(2))));
+    }
+
+    #[test]
+    fn test_erase_actor_keeps_chain_valid() {
+        let log = AuditLog::in_memory().unwrap();
+        let old = Utc::now() - chrono::Duration::days(40);
+        log.record_at(&AuditEvent::new(AuditKind::Tool, "discord:7", "web_fetch"), old).unwrap();
+        log.record(&AuditEvent::new(AuditKind::Tool, "discord:42", "shell").with_detail("rm notes.txt"))
+            .unwrap();
+        log.record(&AuditEvent::new(AuditKind::Auth, "gateway", "login")).unwrap();
+
+        assert_eq!(log.erase_actor("discord:42").unwrap(), 1);
+        assert_eq!(log.erase_actor("discord:42").unwrap(), 0);
+        assert_eq!(log.verify().unwrap(), 3);
+        let erased = &log.tail(3).unwrap()[1];
+        assert_eq!(erased.event.actor, ERASED);
+        assert_eq!(erased.event.action, "shell");
+        assert_eq!(erased.event.detail, None);
+
+        // 保留期只抹去该渠道过期的记录喵
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        assert_eq!(log.erase_channel_before("discord", cutoff).unwrap(), 1);
+        assert_eq!(log.erase_channel_before("gateway", Utc::now()).unwrap(), 0);
+        assert_eq!(log.verify().unwrap(), 3);
+
+        // 抹去之后仍然只能追加喵
+        let conn = log.conn.lock().unwrap();
+        assert!(conn.execute("UPDATE audit_events SET status = 'ok' WHERE id = 2", []).is_err());
+
+        // 已被篡改的链不会因为抹去数据而被重新串链洗白喵
+        conn.execute_batch(
+            "DROP TRIGGER audit_events_no_update;
+             UPDATE audit_events SET action = 'noop' WHERE id = 3;",
+        )
+        .unwrap();
+        drop(conn);
+        assert!(matches!(log.erase_actor("gateway"), Err(AuditError::Tampered(3))));
+        assert_eq!(log.tail(1).unwrap()[0].event.actor, "gateway");
+        assert!(matches!(log.verify(), Err(AuditError::Tampered(3))));
+    }
+
+    #[test]
+    fn test_chain_is_keyed_and_rows_parse_strictly() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open_workspace(dir.path()).unwrap();
+        log.record(&AuditEvent::new(AuditKind::Shell, "cli:nono", "shell")).unwrap();
+        log.record(&AuditEvent::new(AuditKind::Auth, "gateway", "login")).unwrap();
+        assert_eq!(log.verify().unwrap(), 2);
+        assert_eq!(AuditLog::open_workspace(dir.path()).unwrap().verify().unwrap(), 2);
+
+        // 没有审计密钥就算不出有效的链喵
+        let forged = AuditLog::open(dir.path().join(AUDIT_DB_FILE), &[1u8; 32]).unwrap();
+        assert!(matches!(forged.verify(), Err(AuditError::Tampered(1))));
+
+        // 哈希有效但类型不合法的记录不会被当成 admin 显示喵
+        let conn = log.conn.lock().unwrap();
+        let row = AuditLog::rows(&conn, "WHERE id = 2", &[]).unwrap().remove(0);
+        let fields = [row.time.as_str(), "bogus", &row.actor, &row.action, &row.arguments, &row.status, ""];
+        let hash = log.chain_hash(&row.prev_hash, fields);
+        conn.execute_batch("DROP TRIGGER audit_events_no_update;").unwrap();
+        conn.execute("UPDATE audit_events SET kind = 'bogus', hash = ?1 WHERE id = 2", [&hash])
+            .unwrap();
+        drop(conn);
+        assert!(matches!(log.verify(), Err(AuditError::Tampered(2))));
+        assert!(matches!(log.tail(2), Err(AuditError::Tampered(2))));
+    }
+}
@@ -16,14 +16,29 @@
 //! 1. 从密文头部提取 12 字节 IV喵
 //! 2. 使用主密钥解密剩余部分喵
 //! 3. 验证 GCM 认证标签，确保数据完整性喵
+//!
+//! ## 密钥版本
+//! 由 [`KeyRing`](super::keyring::KeyRing) 创建的服务会在密文前加 `k<版本>:` 前缀，
+//! 解密时按前缀选择对应版本的密钥；无前缀的密文视为版本 0（历史数据）喵
+//!
+//! ## 配置密钥
+//! 配置中的 API Key 等密钥以 `enc:` 前缀存放，主密钥来自 `<配置目录>/keys/secrets.keyring`，
+//! 加载 provider 配置时透明解密喵
 
+use super::keyring::KeyRing;
 use aes_gcm::aead::Aead;
 use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
 use base64::{engine::general_purpose::STANDARD as BASE64_STD, Engine as _};
 use rand::rngs::OsRng;
 use rand::RngCore;
+use ring::hmac;
+use std::collections::BTreeMap;
+use std::path::Path;
 use thiserror::Error;
 
+/// 配置中加密值的前缀喵
+pub const SECRET_PREFIX: &str = "enc:";
+
 /// 加密错误类型
 #[derive(Error, Debug, Clone)]
 pub enum CryptoError {
@@ -42,6 +57,10 @@ pub enum CryptoError {
     /// 密文格式错误喵
     #[error("Invalid ciphertext format")]
     InvalidCiphertext,
+
+    /// 无法获取密钥喵
+    #[error("Key unavailable: {0}")]
+    KeyUnavailable(String),
 }
 
 /// 加密服务结构体
@@ -52,6 +71,10 @@ pub struct CryptoService {
     /// AES-256 加密密钥喵
     /// ⚠️ SAFETY: 核心敏感数据，仅限安全模块内部使用喵
     cipher: Aes256Gcm,
+    /// 当前密钥版本（0 = 不加版本前缀）喵
+    version: u32,
+    /// 轮换前的旧版本密钥，仅用于解密喵
+    previous: BTreeMap<u32, Aes256Gcm>,
 }
 
 impl CryptoService {
@@ -68,9 +91,46 @@ impl CryptoService {
         if key_bytes.len() != 32 {
             return Err(CryptoError::InvalidKeyLength);
         }
-        let key = Key::<Aes256Gcm>::from_slice(key_bytes);
-        let cipher = Aes256Gcm::new(key);
-        Ok(Self { cipher })
+        Ok(Self {
+            cipher: Self::cipher(key_bytes)?,
+            version: 0,
+            previous: BTreeMap::new(),
+        })
+    }
+
+    /// 创建带版本的加密服务喵
+    ///
+    /// ## Arguments
+    /// * `keys` - 版本号 → 32 字节密钥喵
+    /// * `current` - 加密使用的版本（必须存在于 `keys` 中）喵
+    ///
+    /// 🔐 PERMISSION: 仅允许 [`KeyRing`](super::keyring::KeyRing) 调用喵
+    pub fn versioned(keys: &BTreeMap<u32, Vec<u8>>, current: u32) -> Result<Self, CryptoError> {
+        let current_key = keys
+            .get(&current)
+            .ok_or_else(|| CryptoError::KeyUnavailable(format!("key version {} not found", current)))?;
+        let previous = keys
+            .iter()
+            .filter(|(version, _)| **version != current)
+            .map(|(version, key)| Ok((*version, Self::cipher(key)?)))
+            .collect::<Result<_, CryptoError>>()?;
+        Ok(Self {
+            cipher: Self::cipher(current_key)?,
+            version: current,
+            previous,
+        })
+    }
+
+    fn cipher(key_bytes: &[u8]) -> Result<Aes256Gcm, CryptoError> {
+        if key_bytes.len() != 32 {
+            return Err(CryptoError::InvalidKeyLength);
+        }
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes)))
+    }
+
+    /// 密文是否已使用当前版本密钥加密（否则需要重新加密）喵
+    pub fn is_current(&self, encrypted_data: &str) -> bool {
+        split_version(encrypted_data).0 == self.version
     }
 
     /// 加密明文喵
@@ -95,8 +155,11 @@ impl CryptoService {
             .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
 
         // 3. 组合 IV + Ciphertext + Tag，返回 Base64 编码喵
-        let combined = [&iv_bytes[..], &ciphertext].concat();
-        Ok(BASE64_STD.encode(combined))
+        let combined = BASE64_STD.encode([&iv_bytes[..], &ciphertext].concat());
+        if self.version == 0 {
+            return Ok(combined);
+        }
+        Ok(format!("k{}:{}", self.version, combined))
     }
 
     /// 解密密文喵
@@ -112,6 +175,16 @@ impl CryptoService {
     /// ## Panics
     /// 如果密文格式错误或认证失败，会返回错误喵（不会 panic）
     pub fn decrypt(&self, encrypted_data: &str) -> Result<String, CryptoError> {
+        // 0. 按版本前缀选择密钥喵
+        let (version, encrypted_data) = split_version(encrypted_data);
+        let cipher = if version == self.version {
+            &self.cipher
+        } else {
+            self.previous
+                .get(&version)
+                .ok_or_else(|| CryptoError::KeyUnavailable(format!("key version {} not found", version)))?
+        };
+
         // 1. Base64 解码喵
         let combined = BASE64_STD
             .decode(encrypted_data)
@@ -126,8 +199,7 @@ impl CryptoService {
         let nonce = Nonce::from_slice(iv_bytes);
 
         // 3. 执行解密喵
-        let plaintext = self
-            .cipher
+        let plaintext = cipher
             .decrypt(nonce, ciphertext_with_tag.as_ref())
             .map_err(|e| CryptoError::DecryptionError(e.to_string()))?;
 
@@ -136,6 +208,40 @@ impl CryptoService {
     }
 }
 
+/// 配置值是否为 `enc:` 加密值喵
+pub fn is_encrypted_secret(value: &str) -> bool {
+    value.starts_with(SECRET_PREFIX)
+}
+
+/// 配置密钥的密钥环喵（`<config_dir>/keys/secrets.keyring`，不存在时创建）
+pub fn secret_keyring(config_dir: &Path) -> Result<KeyRing, CryptoError> {
+    KeyRing::open(&KeyRing::path_for(config_dir, "secrets"), None)
+}
+
+/// 加密配置值喵（返回 `enc:` 前缀的密文）
+pub fn encrypt_secret(crypto: &CryptoService, plaintext: &str) -> Result<String, CryptoError> {
+    Ok(format!("{}{}", SECRET_PREFIX, crypto.encrypt(plaintext)?))
+}
+
+/// 解密配置值喵（没有 `enc:` 前缀的值原样返回）
+///
+/// ⚠️ SAFETY: 只有值确实加密时才打开密钥环，明文配置不会因此创建密钥文件喵
+pub fn decrypt_secret(config_dir: &Path, value: &str) -> Result<String, CryptoError> {
+    match value.strip_prefix(SECRET_PREFIX) {
+        Some(encrypted) => secret_keyring(config_dir)?.crypto()?.decrypt(encrypted),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// 拆分 `k<版本>:` 前缀喵（Base64 字母表不含 `:`，不会误判）
+fn split_version(encrypted_data: &str) -> (u32, &str) {
+    encrypted_data
+        .strip_prefix('k')
+        .and_then(|rest| rest.split_once(':'))
+        .and_then(|(version, data)| Some((version.parse().ok()?, data)))
+        .unwrap_or((0, encrypted_data))
+}
+
 /// 生成随机加密密钥喵
 ///
 /// ## Returns
@@ -148,12 +254,201 @@ pub fn generate_key() -> String {
     BASE64_STD.encode(key_bytes)
 }
 
+/// 🔒 SAFETY: 原子地写入只有本用户可读的文件喵（密钥环、密文配置）
+///
+/// 临时文件创建时即为 0600，写完并落盘后再改名替换目标文件；
+/// 任何一步失败都返回错误，目标文件保持原样喵
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = std::path::PathBuf::from(tmp);
+    // 残留的临时文件可能权限过宽，重新创建才能保证 0600 喵
+    match std::fs::remove_file(&tmp) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options.open(&tmp).and_then(|mut file| {
+        std::io::Write::write_all(&mut file, contents)?;
+        file.sync_all()
+    });
+    match result.and_then(|()| std::fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/// 🔒 SAFETY: 跨进程锁住 `path` 的读改写喵（阻塞等待 `<path>.lock` 上的排他 flock）
+///
+/// `write_private` 每次都会改名替换目标文件，所以锁加在不会被替换的旁路文件上；
+/// 返回的文件关闭时释放锁喵
+pub(crate) fn lock_sidecar(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = std::path::PathBuf::from(lock_path);
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true).write(true).create(true).truncate(false);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(&lock_path)?;
+    lock_exclusive(&file)?;
+    Ok(file)
+}
+
+/// 阻塞等待排他锁喵（文件关闭时自动释放）
+#[cfg(unix)]
+fn lock_exclusive(file: &std::fs::File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    loop {
+        // 🔒 SAFETY: fd 在 file 存活期间有效喵
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(());
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// 非 Unix 平台没有 flock，只有进程内串行化喵
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &std::fs::File) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// 解析十六进制字符串喵（签名、公钥）
+///
+/// ## Returns
+/// 长度为奇数或含非十六进制字符时返回 None 喵
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// 🔒 SAFETY: 常量时间比较喵（比较凭证时不按首个不同字节提前返回，只泄露长度）
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// 解析 32 字节主密钥喵
+///
+/// ## Arguments
+/// * `env_var` - 存放 Base64 密钥的环境变量名喵
+/// * `key_file` - 密钥文件路径（不存在时自动生成喵）
+///
+/// ## Returns
+/// 32 字节密钥喵
+///
+/// ⚠️ SAFETY: 环境变量优先于密钥文件；自动生成的密钥文件权限为 0600喵
+pub fn resolve_key(env_var: &str, key_file: Option<&Path>) -> Result<Vec<u8>, CryptoError> {
+    // 1. 环境变量优先喵
+    if let Ok(encoded) = std::env::var(env_var) {
+        return decode_key(encoded.trim());
+    }
+
+    // 2. 密钥文件喵
+    let path = key_file.ok_or_else(|| {
+        CryptoError::KeyUnavailable(format!("{} is not set and no key file configured", env_var))
+    })?;
+
+    if path.exists() {
+        let encoded = std::fs::read_to_string(path)
+            .map_err(|e| CryptoError::KeyUnavailable(format!("{}: {}", path.display(), e)))?;
+        return decode_key(encoded.trim());
+    }
+
+    // 3. 首次使用时生成新密钥喵
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| CryptoError::KeyUnavailable(format!("{}: {}", parent.display(), e)))?;
+    }
+    // 🔒 SAFETY: 创建时即为 0600，不存在先以默认权限写入密钥的窗口；
+    // 另一个进程抢先创建时改用它生成的密钥喵
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            let encoded = std::fs::read_to_string(path)
+                .map_err(|e| CryptoError::KeyUnavailable(format!("{}: {}", path.display(), e)))?;
+            return decode_key(encoded.trim());
+        }
+        Err(e) => return Err(CryptoError::KeyUnavailable(format!("{}: {}", path.display(), e))),
+    };
+    let encoded = generate_key();
+    std::io::Write::write_all(&mut file, encoded.as_bytes())
+        .and_then(|()| file.sync_all())
+        .map_err(|e| CryptoError::KeyUnavailable(format!("{}: {}", path.display(), e)))?;
+
+    decode_key(&encoded)
+}
+
+/// 从主密钥派生子密钥喵（HMAC-SHA256(master, context)）
+///
+/// ## Arguments
+/// * `master` - 主密钥喵
+/// * `context` - 用途标识（例如 `session:<id>`），不同 context 得到互不相关的密钥喵
+///
+/// ## Returns
+/// 32 字节子密钥喵
+///
+/// ⚠️ SAFETY: 子密钥泄露不会暴露主密钥或其他子密钥喵
+/// （与已加密的会话兼容，不能换成其他派生算法）
+pub fn derive_key(master: &[u8], context: &str) -> [u8; 32] {
+    let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, master), context.as_bytes());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(tag.as_ref());
+    key
+}
+
+/// 解码 Base64 密钥并校验长度喵
+pub(crate) fn decode_key(encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    let bytes = BASE64_STD
+        .decode(encoded)
+        .map_err(|_| CryptoError::KeyUnavailable("key is not valid base64".to_string()))?;
+    if bytes.len() != 32 {
+        return Err(CryptoError::InvalidKeyLength);
+    }
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     /// 测试加密解密循环喵
-    #[tokio::test]
+    #[test]
     fn test_encrypt_decrypt_cycle() {
         let key = generate_key();
         let crypto = CryptoService::new(&BASE64_STD.decode(&key).unwrap()).unwrap();
@@ -166,7 +461,7 @@ mod tests {
     }
 
     /// 测试空字符串加密喵
-    #[tokio::test]
+    #[test]
     fn test_empty_string() {
         let key = generate_key();
         let crypto = CryptoService::new(&BASE64_STD.decode(&key).unwrap()).unwrap();
@@ -176,4 +471,89 @@ mod tests {
 
         assert_eq!("", decrypted);
     }
+
+    /// 测试多版本密钥解密喵
+    #[test]
+    fn test_versioned_decrypt() {
+        let legacy = CryptoService::new(&[1u8; 32]).unwrap();
+        let old = legacy.encrypt("旧数据").unwrap();
+
+        let keys = BTreeMap::from([(0, vec![1u8; 32]), (2, vec![2u8; 32])]);
+        let crypto = CryptoService::versioned(&keys, 2).unwrap();
+        let new = crypto.encrypt("新数据").unwrap();
+
+        assert!(new.starts_with("k2:"));
+        assert!(crypto.is_current(&new));
+        assert!(!crypto.is_current(&old));
+        assert_eq!(crypto.decrypt(&old).unwrap(), "旧数据");
+        assert_eq!(crypto.decrypt(&new).unwrap(), "新数据");
+        assert!(matches!(legacy.decrypt(&new), Err(CryptoError::KeyUnavailable(_))));
+    }
+
+    /// 测试子密钥派生喵
+    #[test]
+    fn test_derive_key() {
+        // RFC 4231 测试用例 2喵
+        let derived = derive_key(b"Jefe", "what do ya want for nothing?");
+        let hex: String = derived.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        // RFC 4231 测试用例 6（超过块长度的密钥）喵
+        let derived = derive_key(&[0xaa; 131], "Test Using Larger Than Block-Size Key - Hash Key First");
+        let hex: String = derived.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+
+        let master = [7u8; 32];
+        assert_ne!(derive_key(&master, "session:a"), derive_key(&master, "session:b"));
+    }
+
+    /// 测试密钥文件自动生成与复用喵
+    #[test]
+    fn test_resolve_key_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.key");
+
+        let first = resolve_key("NEKOCLAW_TEST_UNSET_KEY", Some(&path)).unwrap();
+        let second = resolve_key("NEKOCLAW_TEST_UNSET_KEY", Some(&path)).unwrap();
+
+        assert_eq!(first.len(), 32);
+        assert_eq!(first, second);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 无法创建密钥文件时报错，而不是静默继续喵
+        let missing = dir.path().join("memory.key").join("nested.key");
+        assert!(resolve_key("NEKOCLAW_TEST_UNSET_KEY", Some(&missing)).is_err());
+    }
+
+    /// 测试原子私有写入喵
+    #[test]
+    fn test_write_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("secrets.json");
+        write_private(&path, b"old").unwrap();
+
+        // 残留的宽权限临时文件不会被沿用喵
+        let stale = dir.path().join("keys").join("secrets.json.tmp");
+        std::fs::write(&stale, "stale").unwrap();
+        write_private(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(!stale.exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 目标是目录时失败，不会留下临时文件喵
+        assert!(write_private(dir.path().join("keys").as_path(), b"x").is_err());
+        assert!(!dir.path().join("keys.tmp").exists());
+    }
 }
@@ -0,0 +1,266 @@
+//! # 工具执行环境变量模块
+//!
+//! ⚠️ SAFETY: 控制 Shell / 技能子进程能看到哪些环境变量喵
+//!
+//! ## 功能说明
+//! - 子进程默认从空环境启动（`env_clear`），不再继承守护进程的全部变量喵
+//! - `inherit`: 显式放行的守护进程变量名喵
+//! - `set`: 静态值喵
+//! - `secrets`: 模板化密钥，支持 `${env:NAME}` 与 `${file:/path}` 喵
+//!
+//! ## 配置示例
+//! ```json
+//! "security": {
+//!   "tool_env": {
+//!     "inherit": ["PATH", "LANG"],
+//!     "set": { "CI": "1" },
+//!     "secrets": { "GITHUB_TOKEN": "${env:NEKOCLAW_GITHUB_TOKEN}" }
+//!   }
+//! }
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// 环境变量策略错误喵
+#[derive(Error, Debug)]
+pub enum EnvPolicyError {
+    /// 变量名不合法喵
+    #[error("Invalid environment variable name: {0}")]
+    InvalidName(String),
+
+    /// 模板来源不支持喵
+    #[error("Unsupported secret source '{0}' in {1}")]
+    UnknownSource(String, String),
+
+    /// 模板引用的值不存在喵
+    #[error("Secret for {0} could not be resolved: {1}")]
+    Unresolved(String, String),
+}
+
+/// 工具环境变量配置喵
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ToolEnvConfig {
+    /// 从守护进程继承的变量名喵
+    #[serde(default = "default_inherit")]
+    pub inherit: Vec<String>,
+    /// 静态变量喵
+    #[serde(default)]
+    pub set: HashMap<String, String>,
+    /// 模板化密钥（`${env:NAME}` / `${file:/path}`）喵
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+}
+
+fn default_inherit() -> Vec<String> {
+    ["PATH", "HOME", "USER", "LANG", "TZ"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl Default for ToolEnvConfig {
+    fn default() -> Self {
+        Self {
+            inherit: default_inherit(),
+            set: HashMap::new(),
+            secrets: HashMap::new(),
+        }
+    }
+}
+
+/// 解析后的子进程环境喵
+///
+/// 🔒 SAFETY: Debug 输出只列出变量名，不打印值喵
+#[derive(Clone, Default)]
+pub struct ToolEnvironment {
+    vars: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for ToolEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolEnvironment")
+            .field("vars", &self.vars.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ToolEnvironment {
+    /// 最小环境（默认继承列表）喵
+    pub fn minimal() -> Self {
+        Self::inherit_from(&default_inherit(), |name| std::env::var(name).ok())
+    }
+
+    /// 按配置解析环境喵
+    ///
+    /// ## Returns
+    /// 解析结果喵，任一密钥无法解析时返回错误（不会静默传空值喵）
+    pub fn resolve(config: &ToolEnvConfig) -> Result<Self, EnvPolicyError> {
+        Self::resolve_with(config, |name| std::env::var(name).ok())
+    }
+
+    fn inherit_from(names: &[String], lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let vars = names
+            .iter()
+            .filter_map(|name| lookup(name).map(|v| (name.clone(), v)))
+            .collect();
+        Self { vars }
+    }
+
+    fn resolve_with(
+        config: &ToolEnvConfig,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, EnvPolicyError> {
+        for name in config
+            .inherit
+            .iter()
+            .chain(config.set.keys())
+            .chain(config.secrets.keys())
+        {
+            validate_name(name)?;
+        }
+
+        let mut env = Self::inherit_from(&config.inherit, &lookup);
+        for (name, value) in &config.set {
+            env.vars.insert(name.clone(), value.clone());
+        }
+        for (name, template) in &config.secrets {
+            let value = expand_template(name, template, &lookup)?;
+            env.vars.insert(name.clone(), value);
+        }
+        Ok(env)
+    }
+
+    /// 变量名列表喵
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.vars.keys().map(|s| s.as_str())
+    }
+
+    /// 变量迭代器（供 `Command::envs` 使用喵）
+    pub fn vars(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.vars.iter()
+    }
+}
+
+/// 检查变量名喵
+fn validate_name(name: &str) -> Result<(), EnvPolicyError> {
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(EnvPolicyError::InvalidName(name.to_string()))
+    }
+}
+
+/// 🔒 SAFETY: 展开单个模板值喵（`name` 只用于错误信息）
+pub fn resolve_secret(name: &str, template: &str) -> Result<String, EnvPolicyError> {
+    expand_template(name, template, &|key: &str| std::env::var(key).ok())
+}
+
+/// 展开 `${source:key}` 模板喵
+fn expand_template(
+    name: &str,
+    template: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String, EnvPolicyError> {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    let re = PLACEHOLDER.get_or_init(|| Regex::new(r"\$\{([a-z]+):([^}]+)\}").unwrap());
+
+    let mut out = String::with_capacity(template.len());
+    let mut last = 0;
+    for caps in re.captures_iter(template) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&template[last..whole.start()]);
+        let key = &caps[2];
+        let value = match &caps[1] {
+            "env" => lookup(key)
+                .ok_or_else(|| EnvPolicyError::Unresolved(name.to_string(), format!("${} unset", key)))?,
+            "file" => std::fs::read_to_string(key)
+                .map(|s| s.trim_end_matches(['\n', '\r']).to_string())
+                .map_err(|e| EnvPolicyError::Unresolved(name.to_string(), e.to_string()))?,
+            other => {
+                return Err(EnvPolicyError::UnknownSource(
+                    other.to_string(),
+                    name.to_string(),
+                ))
+            }
+        };
+        out.push_str(&value);
+        last = whole.end();
+    }
+    out.push_str(&template[last..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_env(name: &str) -> Option<String> {
+        match name {
+            "PATH" => Some("/usr/bin".to_string()),
+            "OPENAI_API_KEY" => Some("sk-daemon".to_string()),
+            "NEKO_GH" => Some("ghp_test".to_string()),
+            _ => None,
+        }
+    }
+
+    /// 测试默认只继承白名单变量，不泄露密钥喵
+    #[test]
+    fn test_default_does_not_leak_daemon_secrets() {
+        let env = ToolEnvironment::resolve_with(&ToolEnvConfig::default(), fake_env).unwrap();
+        let names: Vec<&str> = env.names().collect();
+        assert_eq!(names, vec!["PATH"]);
+    }
+
+    /// 测试静态值与模板化密钥喵
+    #[test]
+    fn test_static_and_templated_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_file = dir.path().join("token");
+        std::fs::write(&secret_file, "file-secret\n").unwrap();
+
+        let mut config = ToolEnvConfig::default();
+        config.set.insert("CI".to_string(), "1".to_string());
+        config
+            .secrets
+            .insert("GH_TOKEN".to_string(), "${env:NEKO_GH}".to_string());
+        config.secrets.insert(
+            "AUTH".to_string(),
+            format!("Bearer ${{file:{}}}", secret_file.display()),
+        );
+
+        let env = ToolEnvironment::resolve_with(&config, fake_env).unwrap();
+        let vars: BTreeMap<_, _> = env.vars().collect();
+        assert_eq!(vars[&"CI".to_string()], "1");
+        assert_eq!(vars[&"GH_TOKEN".to_string()], "ghp_test");
+        assert_eq!(vars[&"AUTH".to_string()], "Bearer file-secret");
+        assert!(!format!("{:?}", env).contains("ghp_test"));
+    }
+
+    /// 测试无法解析的模板与非法变量名喵
+    #[test]
+    fn test_unresolved_and_invalid() {
+        let mut config = ToolEnvConfig::default();
+        config
+            .secrets
+            .insert("X".to_string(), "${env:MISSING}".to_string());
+        assert!(matches!(
+            ToolEnvironment::resolve_with(&config, fake_env),
+            Err(EnvPolicyError::Unresolved(_, _))
+        ));
+
+        let mut config = ToolEnvConfig::default();
+        config.set.insert("BAD=NAME".to_string(), "1".to_string());
+        assert!(matches!(
+            ToolEnvironment::resolve_with(&config, fake_env),
+            Err(EnvPolicyError::InvalidName(_))
+        ));
+    }
+}
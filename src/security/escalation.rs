@@ -0,0 +1,577 @@
+//! # Escalation Workflow 🔑
+//!
+//! 被策略拦截的操作可以申请主人放行喵
+//!
+//! ## 流程
+//! 1. 工具调用被拦截（`PolicyViolation::escalation_possible = true`）喵
+//! 2. Agent 通过 `request_escalation` 工具提交申请，推送到主人的渠道（Discord / Telegram）喵
+//! 3. 主人回复 `/approve <id>` 或 `/deny <id>`（或 `nekoclaw escalation approve <id>`）喵
+//! 4. 批准后生成一次性放行令牌；Agent 再次调用 `request_escalation` 取得令牌，
+//!    带着令牌以完全相同的参数重试原操作，消费令牌并执行一次喵
+//!
+//! 放行绑定到被拦截时的完整调用（argv），主人看到的也是完整调用喵
+//!
+//! 所有状态变化写入审计日志（JSON Lines）喵
+
+use super::crypto::{lock_sidecar, write_private};
+use super::policy::PolicyViolation;
+use crate::core::traits::Channel;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// 放行申请错误类型喵
+#[derive(Error, Debug)]
+pub enum EscalationError {
+    /// 申请不存在喵
+    #[error("Escalation request not found: {0}")]
+    NotFound(String),
+
+    /// 申请已处理喵
+    #[error("Escalation request {0} is already {1:?}")]
+    AlreadyDecided(String, EscalationStatus),
+
+    /// 该策略不允许申请放行喵
+    #[error("Policy '{0}' does not allow escalation")]
+    NotEscalatable(String),
+
+    /// 没有与之对应的拦截记录喵
+    #[error("No recent '{0}' denial for '{1}' to escalate")]
+    NoMatchingDenial(String, String),
+
+    /// 存储读写失败喵
+    #[error("Escalation store error: {0}")]
+    Store(String),
+}
+
+/// 申请状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscalationStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// 令牌已被使用喵
+    Consumed,
+}
+
+/// 🔒 SAFETY: 放行申请喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationRequest {
+    pub id: String,
+    /// 被拦截的工具
+    pub tool_name: String,
+    /// 拦截详情
+    pub violation: PolicyViolation,
+    /// 被拦截的完整调用（argv），放行只对完全相同的调用生效
+    #[serde(default)]
+    pub invocation: Vec<String>,
+    /// Agent 给出的申请理由
+    pub justification: String,
+    pub status: EscalationStatus,
+    pub requested_at: DateTime<Utc>,
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub decided_by: Option<String>,
+    /// 一次性放行令牌（批准后生成）
+    #[serde(default)]
+    pub override_token: Option<String>,
+}
+
+impl EscalationRequest {
+    /// 推送给主人的消息喵
+    pub fn owner_message(&self) -> String {
+        format!(
+            "🔑 放行申请 `{}`\n工具: {}\n调用: {:?}\n策略: {}\n对象: {}\n原因: {}\n理由: {}\n\n回复 `/approve {}` 批准（仅执行一次）或 `/deny {}` 拒绝喵",
+            self.id,
+            self.tool_name,
+            self.invocation,
+            self.violation.policy,
+            self.violation.blocked,
+            self.violation.reason,
+            self.justification,
+            self.id,
+            self.id
+        )
+    }
+
+    fn matches(&self, tool_name: &str, violation: &PolicyViolation, invocation: &[String]) -> bool {
+        self.tool_name == tool_name
+            && self.violation.policy == violation.policy
+            && self.violation.blocked == violation.blocked
+            && self.invocation == invocation
+    }
+}
+
+/// 主人通知渠道喵
+struct OwnerChannel {
+    channel: Arc<dyn Channel>,
+    target: String,
+}
+
+/// 🔒 SAFETY: 放行申请管理器喵
+///
+/// 申请持久化在 JSON 文件中，daemon 与 CLI 可共享同一份状态喵
+/// 每次读改写都持有 `escalations.json.lock` 上的 flock，审批与消费令牌不会在进程间交错喵
+pub struct EscalationManager {
+    store_path: Option<PathBuf>,
+    audit_path: Option<PathBuf>,
+    requests: Mutex<BTreeMap<String, EscalationRequest>>,
+    /// 近期真实发生的拦截（只能为这些申请放行）
+    denials: Mutex<Vec<Denial>>,
+    owner: Option<OwnerChannel>,
+}
+
+/// 一次真实发生的拦截喵
+#[derive(Debug, Clone, PartialEq)]
+struct Denial {
+    tool_name: String,
+    violation: PolicyViolation,
+    invocation: Vec<String>,
+}
+
+/// 读改写期间持有的锁喵（drop 时释放进程内锁与 flock）
+struct StoreGuard<'a> {
+    requests: MutexGuard<'a, BTreeMap<String, EscalationRequest>>,
+    _file: Option<File>,
+}
+
+/// 保留的近期拦截记录数量喵
+const MAX_RECENT_DENIALS: usize = 32;
+
+impl std::fmt::Debug for EscalationManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EscalationManager")
+            .field("store_path", &self.store_path)
+            .field("owner", &self.owner.as_ref().map(|o| o.channel.name().to_string()))
+            .finish()
+    }
+}
+
+impl EscalationManager {
+    /// 纯内存管理器（测试 / 无痕模式）喵
+    pub fn in_memory() -> Self {
+        Self {
+            store_path: None,
+            audit_path: None,
+            requests: Mutex::new(BTreeMap::new()),
+            denials: Mutex::new(Vec::new()),
+            owner: None,
+        }
+    }
+
+    /// 在指定目录下持久化申请与审计日志喵
+    pub fn open(dir: PathBuf) -> Result<Self, EscalationError> {
+        let store_path = dir.join("escalations.json");
+        let requests = match std::fs::read_to_string(&store_path) {
+            Ok(content) => {
+                serde_json::from_str(&content).map_err(|e| EscalationError::Store(e.to_string()))?
+            }
+            Err(_) => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            store_path: Some(store_path),
+            audit_path: Some(dir.join("escalation_audit.log")),
+            requests: Mutex::new(requests),
+            denials: Mutex::new(Vec::new()),
+            owner: None,
+        })
+    }
+
+    /// 设置主人通知渠道喵
+    pub fn with_owner_channel(mut self, channel: Arc<dyn Channel>, target: impl Into<String>) -> Self {
+        self.owner = Some(OwnerChannel {
+            channel,
+            target: target.into(),
+        });
+        self
+    }
+
+    /// 🔒 SAFETY: 加锁并重新读取磁盘状态喵（其他进程可能已审批或消费令牌）
+    ///
+    /// 先进程内互斥锁，再阻塞等待 `lock_sidecar` 的排他 flock；
+    /// 磁盘状态读不出来时报错而不是沿用内存中的旧状态（fail closed）喵
+    fn lock_store(&self) -> Result<StoreGuard<'_>, EscalationError> {
+        let mut requests = self.requests.lock().unwrap();
+        let Some(path) = &self.store_path else {
+            return Ok(StoreGuard { requests, _file: None });
+        };
+        let file = lock_sidecar(path).map_err(|e| EscalationError::Store(format!("{}.lock: {}", path.display(), e)))?;
+        *requests = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| EscalationError::Store(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(EscalationError::Store(format!("{}: {}", path.display(), e))),
+        };
+        Ok(StoreGuard { requests, _file: Some(file) })
+    }
+
+    /// 🔒 SAFETY: 文件里有未使用的放行令牌，原子写入且仅所有者可读（0600）喵
+    fn persist(&self, requests: &BTreeMap<String, EscalationRequest>) -> Result<(), EscalationError> {
+        if let Some(path) = &self.store_path {
+            let content = serde_json::to_string_pretty(requests)
+                .map_err(|e| EscalationError::Store(e.to_string()))?;
+            write_private(path, content.as_bytes()).map_err(|e| EscalationError::Store(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 写审计日志喵
+    fn audit(&self, event: &str, request: &EscalationRequest) {
+        info!(
+            "🔑 Escalation {} {} ({} / {})",
+            event, request.id, request.violation.policy, request.violation.blocked
+        );
+        let Some(path) = &self.audit_path else {
+            return;
+        };
+        let line = serde_json::json!({
+            "time": Utc::now().to_rfc3339(),
+            "event": event,
+            "request": request,
+        });
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            warn!("Failed to write escalation audit log: {}", e);
+        }
+    }
+
+    /// 记录一次拦截（工具在拒绝时调用，`invocation` 为完整调用）喵
+    pub fn note_denial(&self, tool_name: &str, violation: &PolicyViolation, invocation: &[String]) {
+        let denial = Denial {
+            tool_name: tool_name.to_string(),
+            violation: violation.clone(),
+            invocation: invocation.to_vec(),
+        };
+        let mut denials = self.denials.lock().unwrap();
+        denials.retain(|d| d != &denial);
+        denials.push(denial);
+        if denials.len() > MAX_RECENT_DENIALS {
+            denials.remove(0);
+        }
+    }
+
+    /// 🔒 SAFETY: 为近期真实发生的拦截提交放行申请并通知主人喵
+    ///
+    /// 同一调用已有待处理或已批准（未使用）的申请时直接返回它，已批准的申请带有放行令牌喵
+    pub async fn submit(
+        &self,
+        tool_name: &str,
+        policy: &str,
+        blocked: &str,
+        justification: &str,
+    ) -> Result<EscalationRequest, EscalationError> {
+        let Denial { violation, invocation, .. } = self
+            .denials
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|d| d.tool_name == tool_name && d.violation.policy == policy && d.violation.blocked == blocked)
+            .cloned()
+            .ok_or_else(|| EscalationError::NoMatchingDenial(policy.to_string(), blocked.to_string()))?;
+        if !violation.escalation_possible {
+            return Err(EscalationError::NotEscalatable(violation.policy));
+        }
+
+        let request = {
+            let mut store = self.lock_store()?;
+            let requests = &mut store.requests;
+
+            // 同一调用已有待处理或已批准的申请时直接复用喵
+            if let Some(existing) = requests.values().find(|r| {
+                matches!(r.status, EscalationStatus::Pending | EscalationStatus::Approved)
+                    && r.matches(tool_name, &violation, &invocation)
+            }) {
+                return Ok(existing.clone());
+            }
+
+            let request = EscalationRequest {
+                id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+                tool_name: tool_name.to_string(),
+                violation,
+                invocation,
+                justification: justification.to_string(),
+                status: EscalationStatus::Pending,
+                requested_at: Utc::now(),
+                decided_at: None,
+                decided_by: None,
+                override_token: None,
+            };
+            requests.insert(request.id.clone(), request.clone());
+            self.persist(requests)?;
+            request
+        };
+        self.audit("requested", &request);
+
+        if let Some(owner) = &self.owner {
+            if let Err(e) = owner
+                .channel
+                .send(&request.owner_message(), Some(&owner.target))
+                .await
+            {
+                warn!("Failed to deliver escalation {} to owner: {}", request.id, e);
+            }
+        }
+
+        Ok(request)
+    }
+
+    fn decide(
+        &self,
+        id: &str,
+        approver: &str,
+        status: EscalationStatus,
+    ) -> Result<EscalationRequest, EscalationError> {
+        let mut store = self.lock_store()?;
+        let request = store
+            .requests
+            .get_mut(id)
+            .ok_or_else(|| EscalationError::NotFound(id.to_string()))?;
+        if request.status != EscalationStatus::Pending {
+            return Err(EscalationError::AlreadyDecided(id.to_string(), request.status));
+        }
+
+        request.status = status;
+        request.decided_at = Some(Utc::now());
+        request.decided_by = Some(approver.to_string());
+        if status == EscalationStatus::Approved {
+            request.override_token = Some(uuid::Uuid::new_v4().to_string());
+        }
+
+        let request = request.clone();
+        self.persist(&store.requests)?;
+        drop(store);
+
+        self.audit(
+            if status == EscalationStatus::Approved { "approved" } else { "denied" },
+            &request,
+        );
+        Ok(request)
+    }
+
+    /// 批准申请（生成一次性令牌）喵
+    pub fn approve(&self, id: &str, approver: &str) -> Result<EscalationRequest, EscalationError> {
+        self.decide(id, approver, EscalationStatus::Approved)
+    }
+
+    /// 拒绝申请喵
+    pub fn deny(&self, id: &str, approver: &str) -> Result<EscalationRequest, EscalationError> {
+        self.decide(id, approver, EscalationStatus::Denied)
+    }
+
+    /// 🔒 SAFETY: 消费与本次拦截匹配的放行令牌（一次性）喵
+    ///
+    /// 令牌必须与批准时生成的一致，且调用与申请时完全相同；返回 Some = 本次调用获得放行喵
+    /// 磁盘状态读不出来或消费状态写不进磁盘时拒绝放行（否则令牌可能被重复使用）
+    pub fn redeem(
+        &self,
+        tool_name: &str,
+        violation: &PolicyViolation,
+        invocation: &[String],
+        token: &str,
+    ) -> Option<EscalationRequest> {
+        let mut store = match self.lock_store() {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("Refusing override: {}", e);
+                return None;
+            }
+        };
+
+        let request = store.requests.values_mut().find(|r| {
+            r.status == EscalationStatus::Approved
+                && r.override_token.as_deref() == Some(token)
+                && r.matches(tool_name, violation, invocation)
+        })?;
+        request.status = EscalationStatus::Consumed;
+        let request = request.clone();
+
+        if let Err(e) = self.persist(&store.requests) {
+            warn!("Refusing override {}: failed to persist consumed state: {}", request.id, e);
+            return None;
+        }
+        drop(store);
+
+        self.audit("override_used", &request);
+        Some(request)
+    }
+
+    /// 列出申请（按提交时间排序）喵
+    pub fn list(&self, status: Option<EscalationStatus>) -> Result<Vec<EscalationRequest>, EscalationError> {
+        let store = self.lock_store()?;
+        let mut list: Vec<EscalationRequest> = store
+            .requests
+            .values()
+            .filter(|r| status.is_none_or(|s| r.status == s))
+            .cloned()
+            .collect();
+        list.sort_by_key(|r| r.requested_at);
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked_rm() -> PolicyViolation {
+        PolicyViolation::new("command_allowlist", "rm", "command is not in the allowlist")
+            .with_escalation(true)
+    }
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_approved_override_is_single_use() {
+        let manager = EscalationManager::in_memory();
+        let rm_target = argv(&["rm", "-rf", "target"]);
+        manager.note_denial("shell", &blocked_rm(), &rm_target);
+        let request = manager
+            .submit("shell", "command_allowlist", "rm", "clean build cache")
+            .await
+            .unwrap();
+        assert_eq!(request.invocation, rm_target);
+        assert!(request.owner_message().contains(r#"["rm", "-rf", "target"]"#));
+
+        let approved = manager.approve(&request.id, "owner").unwrap();
+        let token = approved.override_token.clone().unwrap();
+
+        // 再次申请同一调用时取回带令牌的已批准申请喵
+        let fetched = manager
+            .submit("shell", "command_allowlist", "rm", "clean build cache")
+            .await
+            .unwrap();
+        assert_eq!(fetched.override_token.as_deref(), Some(token.as_str()));
+
+        // 令牌错误或调用不同（同一命令名换了参数）都不放行喵
+        assert!(manager.redeem("shell", &blocked_rm(), &rm_target, "guess").is_none());
+        assert!(manager.redeem("shell", &blocked_rm(), &argv(&["rm", "-rf", "/"]), &token).is_none());
+
+        assert!(manager.redeem("shell", &blocked_rm(), &rm_target, &token).is_some());
+        assert!(manager.redeem("shell", &blocked_rm(), &rm_target, &token).is_none());
+        assert!(matches!(
+            manager.deny(&request.id, "owner"),
+            Err(EscalationError::AlreadyDecided(_, EscalationStatus::Consumed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_only_real_denials_can_be_escalated() {
+        let manager = EscalationManager::in_memory();
+        let sensitive = PolicyViolation::new("sensitive_path", "/etc/shadow", "sensitive location");
+        manager.note_denial("fs_read", &sensitive, &argv(&["/etc/shadow"]));
+
+        assert!(matches!(
+            manager.submit("fs_read", "sensitive_path", "/etc/shadow", "need it").await,
+            Err(EscalationError::NotEscalatable(_))
+        ));
+        assert!(matches!(
+            manager.submit("shell", "command_allowlist", "curl", "made up").await,
+            Err(EscalationError::NoMatchingDenial(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_state_shared_through_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+        daemon.note_denial("shell", &blocked_rm(), &argv(&["rm", "cache"]));
+        let request = daemon
+            .submit("shell", "command_allowlist", "rm", "cleanup")
+            .await
+            .unwrap();
+
+        let cli = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+        let token = cli.approve(&request.id, "cli").unwrap().override_token.unwrap();
+
+        assert!(daemon.redeem("shell", &blocked_rm(), &argv(&["rm", "cache"]), &token).is_some());
+        let audit = std::fs::read_to_string(dir.path().join("escalation_audit.log")).unwrap();
+        assert_eq!(audit.lines().count(), 3);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("escalations.json")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redeem_fails_closed_when_store_is_unwritable() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+        let rm_cache = argv(&["rm", "cache"]);
+        manager.note_denial("shell", &blocked_rm(), &rm_cache);
+        let request = manager
+            .submit("shell", "command_allowlist", "rm", "cleanup")
+            .await
+            .unwrap();
+        let token = manager.approve(&request.id, "owner").unwrap().override_token.unwrap();
+
+        // 存储路径被目录占用，消费状态无法落盘喵
+        let store = dir.path().join("escalations.json");
+        std::fs::remove_file(&store).unwrap();
+        std::fs::create_dir(&store).unwrap();
+        assert!(manager.redeem("shell", &blocked_rm(), &rm_cache, &token).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_store_fails_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+        let rm_cache = argv(&["rm", "cache"]);
+        manager.note_denial("shell", &blocked_rm(), &rm_cache);
+        let request = manager
+            .submit("shell", "command_allowlist", "rm", "cleanup")
+            .await
+            .unwrap();
+        let token = manager.approve(&request.id, "owner").unwrap().override_token.unwrap();
+
+        // 文件损坏时不能沿用内存中带令牌的旧状态喵
+        std::fs::write(dir.path().join("escalations.json"), "{ not json").unwrap();
+        assert!(manager.redeem("shell", &blocked_rm(), &rm_cache, &token).is_none());
+        assert!(matches!(manager.deny(&request.id, "owner"), Err(EscalationError::Store(_))));
+        assert!(manager.list(None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_redeem_across_managers_is_single_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+        let rm_cache = argv(&["rm", "cache"]);
+        daemon.note_denial("shell", &blocked_rm(), &rm_cache);
+        let request = daemon
+            .submit("shell", "command_allowlist", "rm", "cleanup")
+            .await
+            .unwrap();
+        let token = daemon.approve(&request.id, "owner").unwrap().override_token.unwrap();
+
+        // 每个管理器模拟一个进程，只有 flock 能串行化它们喵
+        let redeemed: usize = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let manager = EscalationManager::open(dir.path().to_path_buf()).unwrap();
+                    let (rm_cache, token) = (&rm_cache, &token);
+                    scope.spawn(move || manager.redeem("shell", &blocked_rm(), rm_cache, token).is_some())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap() as usize).sum()
+        });
+        assert_eq!(redeemed, 1);
+    }
+}
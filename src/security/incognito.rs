@@ -0,0 +1,85 @@
+//! # Incognito Session 🕶️
+//!
+//! 访客 / 无痕模式喵
+//!
+//! - 不写入记忆、会话与遥测明细（调用方依据 `is_active` 跳过持久化）喵
+//! - 工具写入被限制在临时目录，会话结束（Drop）时整体销毁喵
+//! - 系统提示中注明当前为临时模式喵
+
+use std::path::{Path, PathBuf};
+
+/// 临时目录名前缀（维护任务据此清理崩溃遗留的目录）喵
+pub const INCOGNITO_DIR_PREFIX: &str = "nekoclaw-incognito-";
+
+/// 🔒 SAFETY: 无痕会话喵
+#[derive(Debug)]
+pub struct IncognitoSession {
+    scratch_dir: PathBuf,
+}
+
+impl IncognitoSession {
+    /// 🔒 SAFETY: 创建仅当前用户可访问的临时目录喵
+    pub fn start() -> std::io::Result<Self> {
+        let scratch_dir =
+            std::env::temp_dir().join(format!("{}{}", INCOGNITO_DIR_PREFIX, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&scratch_dir)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&scratch_dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+
+        Ok(Self { scratch_dir })
+    }
+
+    /// 工具可写入的临时目录喵
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch_dir
+    }
+
+    /// 追加到系统提示的无痕说明喵
+    pub fn prompt_notice(&self) -> String {
+        "===== INCOGNITO MODE =====\n\
+        This is an ephemeral session: nothing from this conversation is saved to memory, \
+        sessions or telemetry. File writes go to a temporary directory that is destroyed \
+        when the session ends. Do not promise Master to remember anything from it.\n\
+        ===== END INCOGNITO MODE ====="
+            .to_string()
+    }
+}
+
+impl Drop for IncognitoSession {
+    /// 🔒 SAFETY: 会话结束时销毁临时目录喵
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.scratch_dir) {
+            tracing::warn!(
+                "Failed to remove incognito scratch dir {}: {}",
+                self.scratch_dir.display(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_dir_destroyed_on_drop() {
+        let session = IncognitoSession::start().unwrap();
+        let dir = session.scratch_dir().to_path_buf();
+        std::fs::write(dir.join("secret.txt"), "one-off question").unwrap();
+        assert!(dir.exists());
+
+        drop(session);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_prompt_notice_mentions_ephemeral() {
+        let session = IncognitoSession::start().unwrap();
+        assert!(session.prompt_notice().contains("INCOGNITO"));
+    }
+}
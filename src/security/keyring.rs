@@ -0,0 +1,177 @@
+//! # 版本化密钥环
+//!
+//! ⚠️ SAFETY: 持久保存加密密钥的全部历史版本，轮换后旧数据仍可解密喵
+//!
+//! ## 文件格式
+//! `<根目录>/keys/<用途>.keyring`（JSON，权限 0600）：
+//! ```json
+//! { "current": 2, "keys": { "1": { "key": "<Base64>", "created_at": "..." }, "2": { ... } } }
+//! ```
+//!
+//! ## 轮换流程
+//! 1. `rotate()` 生成新版本并立即落盘，之后的加密都使用新版本喵
+//! 2. 调用方把旧版本密文逐条重新加密（中途中断也不会丢数据喵）
+//! 3. 全部迁移成功后 `retire_old()` 删除旧版本喵
+
+use super::crypto::{decode_key, generate_key, write_private, CryptoError, CryptoService};
+use base64::{engine::general_purpose::STANDARD as BASE64_STD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 单个版本的密钥喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyEntry {
+    /// Base64 编码的 32 字节密钥喵
+    key: String,
+    created_at: DateTime<Utc>,
+}
+
+/// 🔒 SAFETY: 版本化密钥环喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRing {
+    current: u32,
+    keys: BTreeMap<u32, KeyEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl KeyRing {
+    /// 某用途的密钥环路径喵（`<root>/keys/<purpose>.keyring`）
+    pub fn path_for(root: &Path, purpose: &str) -> PathBuf {
+        root.join("keys").join(format!("{}.keyring", purpose))
+    }
+
+    /// 打开密钥环喵（不存在时创建）
+    ///
+    /// ## Arguments
+    /// * `path` - 密钥环文件路径喵
+    /// * `legacy` - 引入密钥环之前使用的密钥，作为版本 0 保留以便解密历史数据喵
+    ///
+    /// ⚠️ SAFETY: 新建时总是生成随机的版本 1 作为当前密钥喵
+    pub fn open(path: &Path, legacy: Option<&[u8]>) -> Result<Self, CryptoError> {
+        if path.exists() {
+            let json = std::fs::read_to_string(path).map_err(|e| unavailable(path, e))?;
+            let mut ring: Self = serde_json::from_str(&json).map_err(|e| unavailable(path, e))?;
+            if !ring.keys.contains_key(&ring.current) {
+                return Err(unavailable(path, format!("current key version {} is missing", ring.current)));
+            }
+            ring.path = path.to_path_buf();
+            return Ok(ring);
+        }
+
+        let mut ring = Self {
+            current: 0,
+            keys: BTreeMap::new(),
+            path: path.to_path_buf(),
+        };
+        if let Some(legacy) = legacy {
+            if legacy.len() != 32 {
+                return Err(CryptoError::InvalidKeyLength);
+            }
+            ring.keys.insert(
+                0,
+                KeyEntry {
+                    key: BASE64_STD.encode(legacy),
+                    created_at: Utc::now(),
+                },
+            );
+        }
+        ring.rotate()?;
+        Ok(ring)
+    }
+
+    /// 构建加密服务喵（当前版本加密，所有版本可解密）
+    pub fn crypto(&self) -> Result<CryptoService, CryptoError> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|(version, entry)| Ok((*version, decode_key(&entry.key)?)))
+            .collect::<Result<BTreeMap<_, _>, CryptoError>>()?;
+        CryptoService::versioned(&keys, self.current)
+    }
+
+    /// 当前版本的原始密钥喵（派生 HMAC 等子密钥用）
+    pub fn current_key(&self) -> Result<Vec<u8>, CryptoError> {
+        let entry = self
+            .keys
+            .get(&self.current)
+            .ok_or_else(|| unavailable(&self.path, format!("current key version {} is missing", self.current)))?;
+        decode_key(&entry.key)
+    }
+
+    /// 生成新版本密钥并设为当前版本喵
+    ///
+    /// ## Returns
+    /// 新版本号喵
+    pub fn rotate(&mut self) -> Result<u32, CryptoError> {
+        let version = self.keys.keys().next_back().map_or(1, |v| v + 1);
+        self.keys.insert(
+            version,
+            KeyEntry {
+                key: generate_key(),
+                created_at: Utc::now(),
+            },
+        );
+        self.current = version;
+        self.save()?;
+        Ok(version)
+    }
+
+    /// 删除当前版本以外的所有密钥喵
+    ///
+    /// ⚠️ SAFETY: 仅在全部数据已重新加密后调用，否则旧数据将永久无法解密喵
+    ///
+    /// ## Returns
+    /// 删除的版本数量喵
+    pub fn retire_old(&mut self) -> Result<usize, CryptoError> {
+        let before = self.keys.len();
+        let current = self.current;
+        self.keys.retain(|version, _| *version == current);
+        self.save()?;
+        Ok(before - self.keys.len())
+    }
+
+    /// 原子写入密钥环（先写临时文件再改名，权限 0600）喵
+    fn save(&self) -> Result<(), CryptoError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| unavailable(&self.path, e))?;
+        write_private(&self.path, json.as_bytes()).map_err(|e| unavailable(&self.path, e))
+    }
+}
+
+fn unavailable(path: &Path, e: impl std::fmt::Display) -> CryptoError {
+    CryptoError::KeyUnavailable(format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试密钥环持久化与轮换喵
+    #[test]
+    fn test_keyring_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = KeyRing::path_for(dir.path(), "credentials");
+
+        let legacy = CryptoService::new(&[0u8; 32]).unwrap().encrypt("legacy").unwrap();
+        let mut ring = KeyRing::open(&path, Some(&[0u8; 32])).unwrap();
+        assert_eq!(ring.current, 1);
+        assert_eq!(ring.keys.keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+        let v1 = ring.crypto().unwrap().encrypt("v1").unwrap();
+
+        // 重新打开得到同一把密钥喵
+        let reopened = KeyRing::open(&path, None).unwrap();
+        assert_eq!(reopened.crypto().unwrap().decrypt(&v1).unwrap(), "v1");
+
+        assert_eq!(ring.rotate().unwrap(), 2);
+        let crypto = KeyRing::open(&path, None).unwrap().crypto().unwrap();
+        assert_eq!(crypto.decrypt(&legacy).unwrap(), "legacy");
+        assert_eq!(crypto.decrypt(&v1).unwrap(), "v1");
+        assert!(!crypto.is_current(&v1));
+
+        assert_eq!(ring.retire_old().unwrap(), 2);
+        let crypto = KeyRing::open(&path, None).unwrap().crypto().unwrap();
+        assert!(crypto.decrypt(&v1).is_err());
+    }
+}
@@ -5,8 +5,19 @@
 //!
 //! ## 模块结构
 //! - `crypto`: AES-256-GCM 加密服务 - API Key 和敏感配置保护喵
+//! - `keyring`: 版本化密钥环 - 密钥持久化与轮换喵
+//! - `secrets`: 加密的配置密钥存储（`nekoclaw secret set`）喵
 //! - `allowlist`: 命令和路径白名单检查 - 访问控制喵
+//! - `audit_log`: 防篡改的审计日志（哈希链，只能追加）- `nekoclaw audit` 喵
+//! - `approval`: 危险工具执行前的确认 - `--yes` / 配置放行 / 交互确认喵
 //! - `sandbox`: 命令沙箱执行环境 - 安全命令执行喵
+//! - `env_policy`: 工具子进程环境变量 - 默认空环境，防止密钥泄露喵
+//! - `escalation`: 被拦截操作的放行申请与一次性令牌喵
+//! - `path_rules`: glob / 正则路径规则，拒绝优先喵
+//! - `incognito`: 无痕会话 - 临时目录与零持久化喵
+//! - `scratch`: 每个对话独立的临时工作区 - 中间文件隔离与大小上限喵
+//! - `policy`: 结构化策略拒绝信息 - 告知模型与用户被拦截的原因喵
+//! - `self_audit`: `doctor --security` 安全自检与评分喵
 //!
 //! ## 安全原则
 //! 1. **零信任**: 所有输入都不可信喵
@@ -17,9 +28,31 @@
 //! 所有安全相关的功能都通过此模块暴露喵
 
 pub mod allowlist;
+pub mod approval;
+pub mod audit_log;
 pub mod crypto;
+pub mod env_policy;
+pub mod escalation;
+pub mod incognito;
+pub mod keyring;
+pub mod path_rules;
+pub mod policy;
 pub mod sandbox;
+pub mod scratch;
+pub mod secrets;
+pub mod self_audit;
 
 pub use allowlist::{AllowlistConfig, AllowlistError, AllowlistService};
-pub use crypto::{generate_key, CryptoError, CryptoService};
+pub use approval::{ApprovalAnswer, ToolApproval, ToolApprovalConfig};
+pub use audit_log::{AuditError, AuditEvent, AuditKind, AuditLog, AuditQuery, AuditStatus, AUDIT_DB_FILE, ERASED};
+pub use crypto::{derive_key, generate_key, resolve_key, CryptoError, CryptoService};
+pub use env_policy::{resolve_secret, ToolEnvConfig, ToolEnvironment};
+pub use escalation::{EscalationError, EscalationManager, EscalationRequest, EscalationStatus};
+pub use incognito::IncognitoSession;
+pub use keyring::KeyRing;
+pub use path_rules::{PathAccess, PatternKind, RuleAccess, RuleEffect};
+pub use policy::PolicyViolation;
 pub use sandbox::{SandboxConfig, SandboxError, SandboxResult, SandboxService};
+pub use scratch::{ScratchConfig, ScratchSpace};
+pub use secrets::SecretStore;
+pub use self_audit::SecurityAudit;
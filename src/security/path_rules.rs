@@ -0,0 +1,342 @@
+//! # 路径规则引擎
+//!
+//! ⚠️ SAFETY: `AllowlistService` 的路径检查核心喵
+//!
+//! ## 规则语义
+//! - 模式类型: glob（globset，`*` 不跨越 `/`）或正则喵
+//! - 效果: allow / deny，**deny 永远优先于 allow**，与规则顺序无关喵
+//! - 访问类型: 规则可只约束读、只约束写或两者喵
+//! - 内置拒绝规则（/etc、/root、.ssh、.aws、password）始终生效喵
+//!
+//! ## 路径标准化
+//! - Unicode NFC 标准化，防止分解字符绕过规则喵
+//! - 去除 `.` 与重复分隔符，含 `..` 的路径直接视为遍历攻击喵
+//! - 已存在的路径（或其最近的已存在祖先）会解析符号链接，
+//!   原始路径与解析后路径都必须通过检查喵
+
+use super::allowlist::PathAllowlistEntry;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+use unicode_normalization::UnicodeNormalization;
+
+/// 内置拒绝规则（不可被 allow 覆盖）喵
+const BUILTIN_DENY_GLOBS: &[&str] = &[
+    "/etc",
+    "/etc/**",
+    "/root",
+    "/root/**",
+    "**/.ssh",
+    "**/.ssh/**",
+    "**/.aws",
+    "**/.aws/**",
+    "**/*password*",
+    "**/*password*/**",
+];
+
+/// 模式类型喵
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternKind {
+    #[default]
+    Glob,
+    Regex,
+}
+
+/// 规则效果喵
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleEffect {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// 规则约束的访问类型喵
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAccess {
+    Read,
+    Write,
+    #[default]
+    ReadWrite,
+}
+
+/// 请求的访问类型喵
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathAccess {
+    Read,
+    Write,
+}
+
+impl RuleAccess {
+    fn covers(self, access: PathAccess) -> bool {
+        matches!(
+            (self, access),
+            (RuleAccess::ReadWrite, _)
+                | (RuleAccess::Read, PathAccess::Read)
+                | (RuleAccess::Write, PathAccess::Write)
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Glob(GlobSet),
+    Regex(Regex),
+    /// 无法编译的 deny 规则：失败即关闭，匹配一切喵
+    Everything,
+}
+
+impl Matcher {
+    fn is_match(&self, path: &str) -> bool {
+        match self {
+            Matcher::Glob(set) => set.is_match(path),
+            Matcher::Regex(re) => re.is_match(path),
+            Matcher::Everything => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    pattern: String,
+    effect: RuleEffect,
+    access: RuleAccess,
+    matcher: Matcher,
+}
+
+/// 路径检查结果喵
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathDecision {
+    /// 命中 allow 规则喵
+    Allowed,
+    /// 命中 deny 规则（附规则模式）喵
+    Denied(String),
+    /// 路径包含 `..` 等遍历片段喵
+    Traversal,
+    /// 没有规则匹配喵
+    NoMatch,
+}
+
+/// 🔐 SAFETY: 编译后的路径规则集喵
+#[derive(Clone, Debug, Default)]
+pub struct PathRuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+fn compile_glob(pattern: &str, recursive: bool) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    let normalized: String = pattern.nfc().collect();
+    builder.add(GlobBuilder::new(&normalized).literal_separator(true).build()?);
+    if recursive && !normalized.ends_with("/**") {
+        let nested = format!("{}/**", normalized.trim_end_matches('/'));
+        builder.add(GlobBuilder::new(&nested).literal_separator(true).build()?);
+    }
+    builder.build()
+}
+
+impl PathRuleSet {
+    /// 编译配置中的规则（附加内置拒绝规则）喵
+    pub fn compile(entries: &[PathAllowlistEntry]) -> Self {
+        let mut rules = Vec::with_capacity(entries.len() + BUILTIN_DENY_GLOBS.len());
+
+        for entry in entries {
+            let matcher = match entry.kind {
+                PatternKind::Glob => compile_glob(&entry.pattern, entry.recursive)
+                    .map(Matcher::Glob)
+                    .map_err(|e| e.to_string()),
+                PatternKind::Regex => Regex::new(&entry.pattern.nfc().collect::<String>())
+                    .map(Matcher::Regex)
+                    .map_err(|e| e.to_string()),
+            };
+
+            let matcher = match (matcher, entry.effect) {
+                (Ok(matcher), _) => matcher,
+                (Err(e), RuleEffect::Deny) => {
+                    warn!("Invalid deny rule '{}' ({}), denying everything", entry.pattern, e);
+                    Matcher::Everything
+                }
+                (Err(e), RuleEffect::Allow) => {
+                    warn!("Invalid allow rule '{}' ignored: {}", entry.pattern, e);
+                    continue;
+                }
+            };
+
+            rules.push(CompiledRule {
+                pattern: entry.pattern.clone(),
+                effect: entry.effect,
+                access: entry.access,
+                matcher,
+            });
+        }
+
+        for pattern in BUILTIN_DENY_GLOBS {
+            rules.push(CompiledRule {
+                pattern: pattern.to_string(),
+                effect: RuleEffect::Deny,
+                access: RuleAccess::ReadWrite,
+                matcher: Matcher::Glob(compile_glob(pattern, false).expect("builtin glob is valid")),
+            });
+        }
+
+        Self { rules }
+    }
+
+    /// allow 规则的模式列表（用于提示）喵
+    pub fn allow_patterns(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|r| r.effect == RuleEffect::Allow)
+            .map(|r| r.pattern.as_str())
+            .collect()
+    }
+
+    /// 对单个已标准化路径求值（deny 优先）喵
+    fn evaluate_normalized(&self, path: &str, access: PathAccess) -> PathDecision {
+        let mut allowed = false;
+        for rule in self.rules.iter().filter(|r| r.access.covers(access)) {
+            if !rule.matcher.is_match(path) {
+                continue;
+            }
+            match rule.effect {
+                RuleEffect::Deny => return PathDecision::Denied(rule.pattern.clone()),
+                RuleEffect::Allow => allowed = true,
+            }
+        }
+        if allowed {
+            PathDecision::Allowed
+        } else {
+            PathDecision::NoMatch
+        }
+    }
+
+    /// 🔐 SAFETY: 检查路径（原始路径与符号链接解析后路径都要通过）喵
+    pub fn evaluate(&self, path: &str, access: PathAccess) -> PathDecision {
+        let Some(lexical) = normalize_lexically(path) else {
+            return PathDecision::Traversal;
+        };
+
+        let mut candidates = vec![lexical.clone()];
+        if let Some(resolved) = resolve_symlinks(Path::new(&lexical)) {
+            let resolved: String = resolved.to_string_lossy().nfc().collect();
+            if resolved != lexical {
+                candidates.push(resolved);
+            }
+        }
+
+        let mut decision = PathDecision::Allowed;
+        for candidate in &candidates {
+            match self.evaluate_normalized(candidate, access) {
+                denied @ PathDecision::Denied(_) => return denied,
+                PathDecision::NoMatch => decision = PathDecision::NoMatch,
+                _ => {}
+            }
+        }
+        decision
+    }
+}
+
+/// 词法标准化: NFC + 去除 `.` / 重复分隔符，遇到 `..` 返回 None 喵
+fn normalize_lexically(path: &str) -> Option<String> {
+    let nfc: String = path.nfc().collect();
+    let mut normalized = PathBuf::new();
+    for component in Path::new(&nfc).components() {
+        match component {
+            Component::ParentDir => return None,
+            Component::CurDir => {}
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    Some(normalized.to_string_lossy().into_owned())
+}
+
+/// 解析符号链接: 对最近的已存在祖先做 canonicalize，再拼回剩余部分喵
+fn resolve_symlinks(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for part in rest.iter().rev() {
+                resolved.push(part);
+            }
+            return Some(resolved);
+        }
+        rest.push(existing.file_name()?.to_os_string());
+        existing = existing.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, kind: PatternKind, effect: RuleEffect, access: RuleAccess) -> PathAllowlistEntry {
+        PathAllowlistEntry {
+            pattern: pattern.to_string(),
+            description: String::new(),
+            recursive: false,
+            kind,
+            effect,
+            access,
+        }
+    }
+
+    #[test]
+    fn test_deny_takes_precedence_over_allow() {
+        let rules = PathRuleSet::compile(&[
+            rule("/srv/**", PatternKind::Glob, RuleEffect::Allow, RuleAccess::ReadWrite),
+            rule(r"^/srv/.*\.key$", PatternKind::Regex, RuleEffect::Deny, RuleAccess::ReadWrite),
+            rule("/srv/logs/**", PatternKind::Glob, RuleEffect::Deny, RuleAccess::Write),
+        ]);
+
+        assert_eq!(rules.evaluate("/srv/app/main.rs", PathAccess::Write), PathDecision::Allowed);
+        assert!(matches!(rules.evaluate("/srv/app/tls.key", PathAccess::Read), PathDecision::Denied(_)));
+        assert_eq!(rules.evaluate("/srv/logs/app.log", PathAccess::Read), PathDecision::Allowed);
+        assert!(matches!(rules.evaluate("/srv/logs/app.log", PathAccess::Write), PathDecision::Denied(_)));
+        assert!(matches!(rules.evaluate("/home/u/.ssh/id_rsa", PathAccess::Read), PathDecision::Denied(_)));
+        assert_eq!(rules.evaluate("/srv/./app/../../etc", PathAccess::Read), PathDecision::Traversal);
+        assert_eq!(rules.evaluate("/opt/other", PathAccess::Read), PathDecision::NoMatch);
+    }
+
+    #[test]
+    fn test_unicode_normalization_cannot_bypass_rules() {
+        let rules = PathRuleSet::compile(&[
+            rule("/srv/**", PatternKind::Glob, RuleEffect::Allow, RuleAccess::ReadWrite),
+            rule("/srv/secr\u{e9}t/**", PatternKind::Glob, RuleEffect::Deny, RuleAccess::ReadWrite),
+        ]);
+
+        // 分解形式 e + U+0301 与组合形式 é 视为同一路径喵
+        assert!(matches!(
+            rules.evaluate("/srv/secre\u{301}t/plan.txt", PathAccess::Read),
+            PathDecision::Denied(_)
+        ));
+        assert_eq!(rules.evaluate("/srv/caf\u{e9}/menu.txt", PathAccess::Read), PathDecision::Allowed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_is_denied() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("allowed")).unwrap();
+        std::fs::create_dir_all(root.join("outside")).unwrap();
+        std::os::unix::fs::symlink(root.join("outside"), root.join("allowed/link")).unwrap();
+
+        let allowed = format!("{}/allowed/**", root.display());
+        let rules = PathRuleSet::compile(&[rule(
+            &allowed,
+            PatternKind::Glob,
+            RuleEffect::Allow,
+            RuleAccess::ReadWrite,
+        )]);
+
+        let direct = format!("{}/allowed/notes.txt", root.display());
+        let via_link = format!("{}/allowed/link/new-file.txt", root.display());
+        assert_eq!(rules.evaluate(&direct, PathAccess::Write), PathDecision::Allowed);
+        assert_eq!(rules.evaluate(&via_link, PathAccess::Write), PathDecision::NoMatch);
+    }
+}
@@ -0,0 +1,124 @@
+//! # Policy Violations 🚫
+//!
+//! 结构化的策略拒绝信息喵
+//!
+//! 白名单 / 工作区边界等策略拦截操作时，不再只返回一句错误，而是给出:
+//! - 触发的策略名称喵
+//! - 被拦截的对象喵
+//! - 建议的替代方案喵
+//! - 是否可以申请提权（由管理员放行）喵
+//!
+//! 同一份信息分别格式化给模型（避免无意义重试）和用户喵
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 🔒 SAFETY: 策略拒绝详情喵
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    /// 策略名称（如 "command_allowlist"）
+    pub policy: String,
+    /// 被拦截的对象（命令、路径等）
+    pub blocked: String,
+    /// 拦截原因
+    pub reason: String,
+    /// 建议的替代方案
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    /// 是否可以申请管理员放行
+    pub escalation_possible: bool,
+}
+
+impl PolicyViolation {
+    pub fn new(
+        policy: impl Into<String>,
+        blocked: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            policy: policy.into(),
+            blocked: blocked.into(),
+            reason: reason.into(),
+            suggestion: None,
+            escalation_possible: false,
+        }
+    }
+
+    /// 设置替代方案喵
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    /// 标记为可申请放行喵
+    pub fn with_escalation(mut self, possible: bool) -> Self {
+        self.escalation_possible = possible;
+        self
+    }
+
+    /// 🔒 SAFETY: 给模型的工具结果（明确告知不要重试）喵
+    pub fn for_model(&self) -> String {
+        let details = serde_json::to_string(self).unwrap_or_default();
+        let next_step = if self.escalation_possible {
+            "Do not retry the same action. Use the suggested alternative, or ask Master to approve it with @request_escalation."
+        } else {
+            "Do not retry the same action. Use the suggested alternative or explain why it cannot be done."
+        };
+        format!("POLICY_DENIED {}\n{}", details, next_step)
+    }
+
+    /// 🔒 SAFETY: 给用户看的说明喵
+    pub fn for_user(&self) -> String {
+        let mut message = format!(
+            "🚫 操作被策略 [{}] 拦截喵: {}（{}）",
+            self.policy, self.blocked, self.reason
+        );
+        if let Some(suggestion) = &self.suggestion {
+            message.push_str(&format!("\n   💡 建议: {}", suggestion));
+        }
+        if self.escalation_possible {
+            message.push_str("\n   🔑 可由管理员在白名单中放行");
+        }
+        message
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Blocked by policy '{}': {} ({})",
+            self.policy, self.blocked, self.reason
+        )
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_format_is_structured() {
+        let violation = PolicyViolation::new("command_allowlist", "rm", "not in allowlist")
+            .with_suggestion("Allowed commands: ls, cat")
+            .with_escalation(true);
+
+        let text = violation.for_model();
+        let json = text
+            .strip_prefix("POLICY_DENIED ")
+            .and_then(|rest| rest.lines().next())
+            .unwrap();
+        let parsed: PolicyViolation = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, violation);
+        assert!(text.contains("Do not retry"));
+    }
+
+    #[test]
+    fn test_user_format_mentions_escalation() {
+        let violation = PolicyViolation::new("workspace_boundary", "../x", "outside workspace");
+        assert!(!violation.for_user().contains("管理员"));
+        assert!(violation.with_escalation(true).for_user().contains("管理员"));
+    }
+}
@@ -19,7 +19,7 @@ use std::time::Duration;
 use thiserror::Error;
 use tokio::process::Command as AsyncCommand;
 
-use super::{AllowlistConfig, AllowlistError, AllowlistService};
+use super::{AllowlistError, AllowlistService, ToolEnvironment};
 
 /// 沙箱错误类型
 #[derive(Error, Debug)]
@@ -58,8 +58,8 @@ pub struct SandboxConfig {
     pub max_output_size: usize,
     /// 工作目录喵
     pub working_directory: Option<String>,
-    /// 环境变量白名单喵
-    pub env_whitelist: Vec<String>,
+    /// 子进程环境（从空环境开始，仅注入这些变量喵）
+    pub environment: ToolEnvironment,
 }
 
 /// 命令执行结果喵
@@ -115,29 +115,26 @@ impl SandboxService {
     /// 🔐 PERMISSION: 需要经过白名单验证喵
     /// ⚠️ SAFETY: 此函数可能阻塞，建议使用 async 版本喵
     pub fn execute(&self, command: &str, args: &[&str]) -> Result<SandboxResult, SandboxError> {
-        // 1. 命令白名单检查喵
-        let _cmd_entry = self.allowlist_service.check_command(command)?;
+        // 1. 命令白名单检查喵（执行白名单中的命令名，而不是原始输入）
+        let program = self.check_command(command)?;
 
         // 2. 参数注入检查喵
-        self.validate_parameters(args)?;
+        self.validate_parameters(command, args)?;
 
         // 3. 记录开始时间喵
         let start = std::time::Instant::now();
 
         // 4. 构建命令喵
-        let mut cmd = Command::new(command);
+        let mut cmd = Command::new(&program);
 
         // 设置工作目录喵
         if let Some(ref wd) = self.config.working_directory {
             cmd.current_dir(wd);
         }
 
-        // 5. 注入环境变量（仅白名单内的喵）
-        for env in &self.config.env_whitelist {
-            if let Ok(val) = std::env::var(env) {
-                cmd.env(env, val);
-            }
-        }
+        // 5. 清空继承的环境，仅注入配置的变量喵
+        cmd.env_clear();
+        cmd.envs(self.config.environment.vars());
 
         // 6. 设置参数喵
         cmd.args(args);
@@ -186,26 +183,23 @@ impl SandboxService {
         work_dir: Option<&str>,
         timeout: Option<Duration>,
     ) -> Result<SandboxResult, SandboxError> {
-        // 1. 命令白名单检查喵
-        let _cmd_entry = self.allowlist_service.check_command(command)?;
+        // 1. 命令白名单检查喵（执行白名单中的命令名，而不是原始输入）
+        let program = self.check_command(command)?;
 
         // 2. 参数注入检查喵
-        self.validate_parameters(args)?;
+        self.validate_parameters(command, args)?;
 
         // 3. 构建异步命令喵
-        let mut cmd = AsyncCommand::new(command);
+        let mut cmd = AsyncCommand::new(&program);
 
         // 设置工作目录喵 - 优先使用参数，否则使用配置
         let working_dir =
             work_dir.unwrap_or_else(|| self.config.working_directory.as_deref().unwrap_or("."));
         cmd.current_dir(working_dir);
 
-        // 注入环境变量喵
-        for env in &self.config.env_whitelist {
-            if let Ok(val) = std::env::var(env) {
-                cmd.env(env, val);
-            }
-        }
+        // 清空继承的环境，仅注入配置的变量喵
+        cmd.env_clear();
+        cmd.envs(self.config.environment.vars());
 
         // 设置参数喵
         cmd.args(args);
@@ -213,6 +207,8 @@ impl SandboxService {
         // 捕获输出喵
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
+        // 超时放弃等待时一并杀死子进程喵
+        cmd.kill_on_drop(true);
 
         // 4. 设置超时喵 - 优先使用参数，否则使用配置
         let timeout = timeout.unwrap_or_else(|| Duration::from_secs(self.config.timeout_seconds));
@@ -223,12 +219,12 @@ impl SandboxService {
             Ok(Ok(o)) => o,
             Ok(Err(e)) => return Err(SandboxError::ExecutionFailed(e.to_string())),
             Err(_) => {
-                // 超时，尝试杀死进程喵
+                // 超时，子进程随 future 一起被杀死喵
                 return Ok(SandboxResult {
                     exit_code: -1,
                     stdout: String::new(),
                     stderr: String::from("Command timeout"),
-                    duration_ms: self.config.timeout_seconds as u128 * 1000,
+                    duration_ms: timeout.as_millis(),
                     timed_out: true,
                 });
             }
@@ -253,38 +249,31 @@ impl SandboxService {
     /// 参数注入检查喵
     ///
     /// ## Arguments
+    /// * `command` - 命令名称喵
     /// * `args` - 要检查的参数喵
     ///
     /// ## Returns
     /// Ok(()) = 安全喵，Err = 检测到注入攻击喵
     ///
-    /// 🔐 PERMISSION: 安全检查喵
-    fn validate_parameters(&self, args: &[&str]) -> Result<(), SandboxError> {
-        // 检测危险字符喵
-        let dangerous_patterns = [
-            "|",  // 管道注入喵
-            ";",  // 命令分隔喵
-            "&",  // 后台执行喵
-            "$(", // 命令替换喵
-            "`",  // 反引号注入喵
-            ">",  // 输出重定向喵
-            "<",  // 输入重定向喵
-            ">>", // 追加重定向喵
-            "&&", // 条件执行喵
-            "||", // 条件执行喵
-            "\n", // 换行注入喵
-            "\r", // 回车注入喵
-        ];
-
-        for arg in args {
-            for pattern in &dangerous_patterns {
-                if arg.contains(pattern) {
-                    return Err(SandboxError::ParameterInjection(arg.to_string()));
+    /// 🔐 PERMISSION: 安全检查，委托白名单的 arg_pattern/长度/元字符校验喵
+    fn validate_parameters(&self, command: &str, args: &[&str]) -> Result<(), SandboxError> {
+        self.allowlist_service
+            .check_arguments(command, args)
+            .map_err(|e| match e {
+                AllowlistError::ArgumentRejected(_, reason) => {
+                    SandboxError::ParameterInjection(reason)
                 }
-            }
-        }
+                other => other.into(),
+            })
+    }
 
-        Ok(())
+    /// 命令白名单检查喵（返回要执行的命令名）
+    fn check_command(&self, command: &str) -> Result<String, SandboxError> {
+        match self.allowlist_service.check_command(command) {
+            Ok(entry) => Ok(entry.command),
+            Err(AllowlistError::CommandNotAllowed(cmd)) => Err(SandboxError::CommandNotAllowed(cmd)),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
@@ -295,13 +284,7 @@ impl Default for SandboxConfig {
             timeout_seconds: 30,
             max_output_size: 1024 * 1024, // 1MB
             working_directory: Some("/home/ubuntu/.openclaw/workspace".to_string()),
-            env_whitelist: vec![
-                "HOME".to_string(),
-                "USER".to_string(),
-                "PATH".to_string(),
-                "LANG".to_string(),
-                "TZ".to_string(),
-            ],
+            environment: ToolEnvironment::minimal(),
         }
     }
 }
@@ -309,13 +292,18 @@ impl Default for SandboxConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::AllowlistConfig;
 
     /// 测试沙箱执行喵
-    #[tokio::test]
+    #[test]
     fn test_sandbox_execution() {
         let allowlist_config = AllowlistConfig::default();
         let allowlist_service = AllowlistService::new(allowlist_config);
-        let sandbox_config = SandboxConfig::default();
+        // 默认工作目录只存在于部署机器上，测试时不切换目录喵
+        let sandbox_config = SandboxConfig {
+            working_directory: None,
+            ..SandboxConfig::default()
+        };
         let sandbox = SandboxService::new(allowlist_service, sandbox_config);
 
         // 测试允许的命令喵
@@ -327,7 +315,7 @@ mod tests {
     }
 
     /// 测试命令白名单喵
-    #[tokio::test]
+    #[test]
     fn test_command_whitelist() {
         let allowlist_config = AllowlistConfig::default();
         let allowlist_service = AllowlistService::new(allowlist_config);
@@ -344,7 +332,7 @@ mod tests {
     }
 
     /// 测试参数注入防护喵
-    #[tokio::test]
+    #[test]
     fn test_parameter_injection_protection() {
         let allowlist_config = AllowlistConfig::default();
         let allowlist_service = AllowlistService::new(allowlist_config);
@@ -0,0 +1,168 @@
+//! # 会话临时工作区 🧺
+//!
+//! ⚠️ SAFETY: 每个对话独立的草稿目录，隔离中间文件喵
+//!
+//! - 目录位于 `<工作区>/scratch/<会话 ID>`，首次使用时自动创建（权限 0700）喵
+//! - `fs_write` 默认写入这里，`shell` 默认在这里执行，主 workspace 保持干净喵
+//! - 总大小超过上限后拒绝继续写入喵
+//! - 超过 TTL 未使用的目录由维护任务清理喵
+//!
+//! ```toml
+//! [scratch]
+//! enabled = true
+//! max_mb = 256
+//! ttl_hours = 24
+//! ```
+
+use super::PolicyViolation;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+fn default_enabled() -> bool {
+    true
+}
+fn default_max_mb() -> u64 {
+    256
+}
+fn default_ttl_hours() -> u64 {
+    24
+}
+
+/// 🔒 SAFETY: 临时工作区配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 单个会话目录的大小上限（MiB）
+    #[serde(default = "default_max_mb")]
+    pub max_mb: u64,
+    /// 未使用多久后清理（小时）
+    #[serde(default = "default_ttl_hours")]
+    pub ttl_hours: u64,
+}
+
+impl Default for ScratchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_mb: default_max_mb(),
+            ttl_hours: default_ttl_hours(),
+        }
+    }
+}
+
+impl ScratchConfig {
+    pub fn max_bytes(&self) -> u64 {
+        self.max_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_hours.saturating_mul(3600))
+    }
+}
+
+/// 🔒 SAFETY: 单个对话的临时工作区喵
+#[derive(Debug, Clone)]
+pub struct ScratchSpace {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl ScratchSpace {
+    /// 打开（必要时创建）某个会话的临时目录喵
+    ///
+    /// ⚠️ SAFETY: 会话 ID 必须是单个路径分量，防止逃逸到根目录之外喵
+    pub fn open(root: &Path, session_id: &str, max_bytes: u64) -> std::io::Result<Self> {
+        let mut components = Path::new(session_id).components();
+        if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid scratch directory name: {}", session_id),
+            ));
+        }
+        Self::new(root.join(session_id), max_bytes)
+    }
+
+    /// 使用指定目录作为临时工作区喵
+    pub fn new(dir: PathBuf, max_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+        // 刷新修改时间，恢复的会话重新计算 TTL喵
+        if let Err(e) = std::fs::File::open(&dir).and_then(|f| f.set_modified(SystemTime::now())) {
+            tracing::debug!("Failed to touch scratch dir {}: {}", dir.display(), e);
+        }
+        Ok(Self { dir, max_bytes })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 当前占用的字节数喵
+    pub fn usage(&self) -> u64 {
+        let mut total = 0;
+        let mut pending = vec![self.dir.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                match entry.file_type() {
+                    Ok(t) if t.is_dir() => pending.push(entry.path()),
+                    Ok(t) if t.is_file() => total += entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    _ => {}
+                }
+            }
+        }
+        total
+    }
+
+    /// 🔒 SAFETY: 检查写入后是否超出上限喵
+    ///
+    /// ## Arguments
+    /// * `adding` - 将要写入的字节数喵
+    /// * `replacing` - 将被覆盖的已有文件大小喵
+    pub fn check_quota(&self, adding: u64, replacing: u64) -> Result<(), PolicyViolation> {
+        let after = self.usage().saturating_sub(replacing).saturating_add(adding);
+        if after <= self.max_bytes {
+            return Ok(());
+        }
+        Err(PolicyViolation::new(
+            "scratch_quota",
+            self.dir.display().to_string(),
+            format!(
+                "scratch directory would grow to {} bytes (limit {} bytes)",
+                after, self.max_bytes
+            ),
+        )
+        .with_suggestion("Delete intermediate files you no longer need, or write the result to the workspace"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_quota_and_isolation() {
+        let root = tempfile::tempdir().unwrap();
+        let a = ScratchSpace::open(root.path(), "conversation-a", 10).unwrap();
+        let b = ScratchSpace::open(root.path(), "conversation-b", 10).unwrap();
+        assert_ne!(a.dir(), b.dir());
+        assert!(ScratchSpace::open(root.path(), "../escape", 10).is_err());
+        assert!(ScratchSpace::open(root.path(), "a/b", 10).is_err());
+
+        std::fs::write(a.dir().join("notes.txt"), "12345678").unwrap();
+        assert_eq!(a.usage(), 8);
+        assert_eq!(b.usage(), 0);
+        assert!(a.check_quota(2, 0).is_ok());
+        assert_eq!(a.check_quota(3, 0).unwrap_err().policy, "scratch_quota");
+        // 覆盖已有文件只计算差值喵
+        assert!(a.check_quota(10, 8).is_ok());
+    }
+}
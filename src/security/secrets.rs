@@ -0,0 +1,221 @@
+//! # 配置密钥存储
+//!
+//! ⚠️ SAFETY: API Key 等密钥不写在 config.json 里，而是加密后放在单独的 `secrets.json` 中喵
+//!
+//! ## 文件格式
+//! `<配置目录>/secrets.json`（JSON，权限 0600），键为配置字段的点路径：
+//! ```json
+//! { "providers.nvidia.api_key": "enc:k1:<Base64>" }
+//! ```
+//!
+//! ## 加载
+//! 加载配置时把这些值（仍是密文）覆盖到对应字段，构建 `ProviderConfig` 时再解密喵；
+//! 主密钥来自基础配置目录的 `keys/secrets.keyring`，`security rotate-key` 一并轮换喵
+
+use super::crypto::{encrypt_secret, is_encrypted_secret, secret_keyring, write_private, CryptoService, SECRET_PREFIX};
+use crate::core::traits::Config;
+use crate::core::workspace::set_path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 密钥存储文件名喵
+pub const SECRETS_FILE: &str = "secrets.json";
+
+/// 🔒 SAFETY: 加密的配置密钥存储喵
+#[derive(Clone)]
+pub struct SecretStore {
+    path: PathBuf,
+    crypto: CryptoService,
+}
+
+impl SecretStore {
+    /// 打开密钥存储喵
+    ///
+    /// ## Arguments
+    /// * `store_dir` - `secrets.json` 所在目录（工作区根目录）喵
+    /// * `key_dir` - 密钥环所在目录（基础配置目录，所有工作区共用）喵
+    pub fn open(store_dir: &Path, key_dir: &Path) -> Result<Self, String> {
+        let crypto = secret_keyring(key_dir).and_then(|ring| ring.crypto()).map_err(|e| e.to_string())?;
+        Ok(Self::with_crypto(store_dir, crypto))
+    }
+
+    /// 使用指定的加密服务喵（轮换密钥时传入新版本）
+    pub fn with_crypto(store_dir: &Path, crypto: CryptoService) -> Self {
+        Self {
+            path: store_dir.join(SECRETS_FILE),
+            crypto,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 加密并保存一个配置值喵
+    ///
+    /// ## Returns
+    /// 规范化后的字段路径（如 `providers.nvidia.apiKey` → `providers.nvidia.api_key`）
+    pub fn set(&self, key: &str, plaintext: &str) -> Result<String, String> {
+        let key = normalize_key(key)?;
+        if !is_supported_key(&key) {
+            return Err(format!("{} cannot be stored as a secret (supported: providers.<name>.api_key)", key));
+        }
+        let mut entries = read_entries(&self.path)?;
+        entries.insert(key.clone(), encrypt_secret(&self.crypto, plaintext).map_err(|e| e.to_string())?);
+        write_entries(&self.path, &entries)?;
+        Ok(key)
+    }
+
+    /// 删除一个配置值喵（不存在时返回 false）
+    pub fn remove(&self, key: &str) -> Result<bool, String> {
+        let key = normalize_key(key)?;
+        let mut entries = read_entries(&self.path)?;
+        if entries.remove(&key).is_none() {
+            return Ok(false);
+        }
+        write_entries(&self.path, &entries)?;
+        Ok(true)
+    }
+
+    /// 已保存的字段路径喵（不含值）
+    pub fn keys(&self) -> Result<Vec<String>, String> {
+        Ok(read_entries(&self.path)?.into_keys().collect())
+    }
+
+    /// 用当前版本密钥重新加密旧版本的值喵
+    ///
+    /// ## Returns
+    /// (重新加密数, 失败数)
+    pub fn reencrypt(&self) -> Result<(usize, usize), String> {
+        let mut entries = read_entries(&self.path)?;
+        let mut migrated = 0;
+        let mut failed = 0;
+        for (key, value) in entries.iter_mut() {
+            let encrypted = value.strip_prefix(SECRET_PREFIX).unwrap_or(value);
+            if self.crypto.is_current(encrypted) {
+                continue;
+            }
+            match self.crypto.decrypt(encrypted).and_then(|plain| encrypt_secret(&self.crypto, &plain)) {
+                Ok(resealed) => {
+                    *value = resealed;
+                    migrated += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping undecryptable secret {}: {}", key, e);
+                    failed += 1;
+                }
+            }
+        }
+        if migrated > 0 {
+            write_entries(&self.path, &entries)?;
+        }
+        Ok((migrated, failed))
+    }
+}
+
+/// 把 `store_dir/secrets.json` 中的密文覆盖到配置文档喵（不解密）
+///
+/// provider 只在密钥存储中出现时补上默认端点，保证配置能完整解析
+pub fn apply_secrets(store_dir: &Path, doc: &mut serde_json::Value) -> Result<(), String> {
+    let entries = read_entries(&store_dir.join(SECRETS_FILE))?;
+    let defaults = Config::default();
+    for (key, value) in entries {
+        set_path(doc, &key, serde_json::Value::String(value)).map_err(|e| format!("secrets.json: {}", e))?;
+        let mut segments = key.split('.');
+        if let (Some("providers"), Some(name)) = (segments.next(), segments.next()) {
+            let pointer = format!("/providers/{}/base_url", name);
+            if doc.pointer(&pointer).is_none() {
+                if let Some(provider) = defaults.provider(name) {
+                    set_path(doc, &format!("providers.{}.base_url", name), provider.base_url.into())
+                        .map_err(|e| format!("secrets.json: {}", e))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 目前只有 provider 的 API Key 会在使用时解密喵
+fn is_supported_key(key: &str) -> bool {
+    match key.split('.').collect::<Vec<_>>()[..] {
+        ["providers", name, "api_key"] => Config::default().provider(name).is_some(),
+        _ => false,
+    }
+}
+
+/// 规范化字段路径喵（OpenClaw 的驼峰字段名转为下划线）
+pub fn normalize_key(key: &str) -> Result<String, String> {
+    let segments: Vec<String> = key
+        .split('.')
+        .map(|segment| {
+            segment.chars().fold(String::new(), |mut out, c| {
+                if c.is_ascii_uppercase() {
+                    out.push('_');
+                    out.push(c.to_ascii_lowercase());
+                } else {
+                    out.push(c);
+                }
+                out
+            })
+        })
+        .collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(format!("Invalid secret key: {:?}", key));
+    }
+    Ok(segments.join("."))
+}
+
+fn read_entries(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let entries: BTreeMap<String, String> =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    match entries.iter().find(|(_, value)| !is_encrypted_secret(value)) {
+        Some((key, _)) => Err(format!("{}: value of {} is not encrypted", path.display(), key)),
+        None => Ok(entries),
+    }
+}
+
+/// 原子写入（先写临时文件再改名，权限 0600）喵
+fn write_entries(path: &Path, entries: &BTreeMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    write_private(path, json.as_bytes()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_store_round_trip_through_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SecretStore::open(dir.path(), dir.path()).unwrap();
+
+        let key = store.set("providers.nvidia.apiKey", "nvapi-123").unwrap();
+        assert_eq!(key, "providers.nvidia.api_key");
+        let raw = std::fs::read_to_string(store.path()).unwrap();
+        assert!(raw.contains("enc:k1:") && !raw.contains("nvapi-123"));
+
+        // 加载后字段仍是密文，构建 ProviderConfig 时才解密喵
+        let config = crate::core::config::load(dir.path()).unwrap();
+        let stored = &config.providers.as_ref().unwrap().nvidia.as_ref().unwrap().api_key;
+        assert!(is_encrypted_secret(stored));
+        let provider = config.provider("nvidia").unwrap();
+        assert_eq!(provider.api_key, "nvapi-123");
+        assert_eq!(provider.base_url, "https://integrate.api.nvidia.com/v1");
+
+        // 轮换后旧值重新加密喵
+        let mut ring = secret_keyring(dir.path()).unwrap();
+        ring.rotate().unwrap();
+        let rotated = SecretStore::with_crypto(dir.path(), ring.crypto().unwrap());
+        assert_eq!(rotated.reencrypt().unwrap(), (1, 0));
+        assert!(std::fs::read_to_string(store.path()).unwrap().contains("enc:k2:"));
+        assert_eq!(crate::core::config::load(dir.path()).unwrap().provider("nvidia").unwrap().api_key, "nvapi-123");
+
+        assert!(store.set("discord.token", "x").is_err());
+        assert!(store.remove("providers.nvidia.api_key").unwrap());
+        assert!(store.keys().unwrap().is_empty());
+    }
+}
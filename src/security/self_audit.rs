@@ -0,0 +1,380 @@
+//! # 安全自检
+//!
+//! ⚠️ SAFETY: `nekoclaw doctor --security` 的检查项与评分喵
+//!
+//! ## 检查项
+//! - 配置 / 凭证 / 密钥文件权限（不允许组和其他用户访问喵）
+//! - Shell 白名单是否默认拒绝喵
+//! - 每个 Agent 启用的危险工具喵
+//! - 配置文件中的明文密钥喵
+//! - Gateway 绑定非本机地址时是否有 TLS 与认证喵
+//!
+//! ## 评分
+//! 每项按严重程度加权（高 5 / 中 3），得分 = 通过权重 / 总权重 × 100喵
+
+use crate::core::traits::Config;
+use crate::security::crypto::is_encrypted_secret;
+use crate::security::AllowlistConfig;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 被视为危险的白名单命令（可执行任意代码、联网或破坏数据）喵
+const DANGEROUS_COMMANDS: &[&str] = &[
+    "sh", "bash", "zsh", "fish", "sudo", "su", "doas", "rm", "dd", "mkfs", "chmod", "chown", "curl",
+    "wget", "nc", "ncat", "ssh", "scp", "python", "python3", "node", "perl", "ruby", "eval", "xargs",
+    "env", "docker", "kubectl", "systemctl", "kill", "pkill",
+];
+
+/// 🔒 SAFETY: 严重程度喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn weight(&self) -> u32 {
+        match self {
+            Self::Medium => 3,
+            Self::High => 5,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// 🔒 SAFETY: 单项检查结果喵
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// 检查项名称（如 `permissions:config.toml`）
+    pub check: String,
+    pub severity: Severity,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// 🔒 SAFETY: 自检报告喵
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecurityReport {
+    pub findings: Vec<Finding>,
+}
+
+impl SecurityReport {
+    fn push(&mut self, check: impl Into<String>, severity: Severity, passed: bool, detail: impl Into<String>) {
+        self.findings.push(Finding {
+            check: check.into(),
+            severity,
+            passed,
+            detail: detail.into(),
+        });
+    }
+
+    /// 加权得分（0~100，没有检查项时为 100）喵
+    pub fn score(&self) -> u32 {
+        let total: u32 = self.findings.iter().map(|f| f.severity.weight()).sum();
+        if total == 0 {
+            return 100;
+        }
+        let passed: u32 = self
+            .findings
+            .iter()
+            .filter(|f| f.passed)
+            .map(|f| f.severity.weight())
+            .sum();
+        passed * 100 / total
+    }
+
+    /// 未通过的检查项（严重的在前）喵
+    pub fn failures(&self) -> Vec<&Finding> {
+        let mut failures: Vec<&Finding> = self.findings.iter().filter(|f| !f.passed).collect();
+        failures.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        failures
+    }
+}
+
+/// 🔒 SAFETY: 安全自检喵
+pub struct SecurityAudit<'a> {
+    config: &'a Config,
+    /// 需要检查权限的配置目录（基础目录 + 工作区目录）
+    config_dirs: Vec<PathBuf>,
+    agents: Vec<String>,
+}
+
+impl<'a> SecurityAudit<'a> {
+    pub fn new(config: &'a Config, config_dirs: &[&Path]) -> Self {
+        let mut dirs: Vec<PathBuf> = config_dirs.iter().map(|d| d.to_path_buf()).collect();
+        dirs.dedup();
+        Self {
+            config,
+            config_dirs: dirs,
+            agents: Vec::new(),
+        }
+    }
+
+    /// 要列出危险工具的 Agent喵
+    pub fn with_agents(mut self, agents: &[&str]) -> Self {
+        self.agents = agents.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// 执行全部检查喵
+    pub fn run(&self) -> SecurityReport {
+        let mut report = SecurityReport::default();
+        self.check_permissions(&mut report);
+        self.check_default_deny(&mut report);
+        self.check_dangerous_tools(&mut report);
+        self.check_plaintext_secrets(&mut report);
+        self.check_gateway(&mut report);
+        report
+    }
+
+    /// 需要检查权限的敏感路径喵：(路径, 是否目录)
+    fn sensitive_paths(&self) -> Vec<(PathBuf, bool)> {
+        let mut paths = Vec::new();
+        for dir in &self.config_dirs {
+            for file in ["config.toml", "config.json", "session.key"] {
+                paths.push((dir.join(file), false));
+            }
+            for sub in ["credentials", "keys"] {
+                paths.push((dir.join(sub), true));
+            }
+            if let Ok(entries) = std::fs::read_dir(dir.join("keys")) {
+                paths.extend(entries.flatten().map(|e| (e.path(), false)));
+            }
+        }
+        let key_files = [
+            self.config.memory.as_ref().and_then(|m| m.encryption.as_ref()),
+            self.config.session_encryption.as_ref(),
+        ];
+        for key_file in key_files.into_iter().flatten().filter_map(|e| e.key_file.clone()) {
+            paths.push((key_file, false));
+        }
+        paths.retain(|(path, _)| path.exists());
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    #[cfg(unix)]
+    fn check_permissions(&self, report: &mut SecurityReport) {
+        use std::os::unix::fs::PermissionsExt;
+        for (path, _) in self.sensitive_paths() {
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let mode = metadata.permissions().mode() & 0o777;
+            report.push(
+                format!("permissions:{}", path.display()),
+                Severity::High,
+                mode & 0o077 == 0,
+                format!("mode {:o}", mode),
+            );
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn check_permissions(&self, _report: &mut SecurityReport) {}
+
+    /// 修复敏感路径权限喵（文件 0600、目录 0700）
+    ///
+    /// ## Returns
+    /// 修改的路径数量喵
+    #[cfg(unix)]
+    pub fn fix_permissions(&self) -> std::io::Result<usize> {
+        use std::os::unix::fs::PermissionsExt;
+        let mut fixed = 0;
+        for (path, is_dir) in self.sensitive_paths() {
+            let mode = std::fs::metadata(&path)?.permissions().mode() & 0o777;
+            if mode & 0o077 == 0 {
+                continue;
+            }
+            let target = if is_dir { 0o700 } else { 0o600 };
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(target))?;
+            fixed += 1;
+        }
+        Ok(fixed)
+    }
+
+    #[cfg(not(unix))]
+    pub fn fix_permissions(&self) -> std::io::Result<usize> {
+        Ok(0)
+    }
+
+    fn allowlist(&self) -> Option<&AllowlistConfig> {
+        self.config.security.as_ref().and_then(|s| s.allowlist.as_ref())
+    }
+
+    fn check_default_deny(&self, report: &mut SecurityReport) {
+        match self.allowlist() {
+            None => report.push("default_deny", Severity::High, true, "shell tool disabled (no allowlist)"),
+            Some(allowlist) if allowlist.default_deny => {
+                report.push("default_deny", Severity::High, true, "enabled")
+            }
+            Some(_) => report.push(
+                "default_deny",
+                Severity::High,
+                false,
+                "security.allowlist.default_deny = false: unlisted commands and paths are allowed",
+            ),
+        }
+    }
+
+    /// 白名单中的危险命令喵
+    fn dangerous_commands(&self) -> Vec<String> {
+        let Some(allowlist) = self.allowlist() else {
+            return Vec::new();
+        };
+        allowlist
+            .commands
+            .iter()
+            .filter_map(|entry| {
+                let mut reasons = Vec::new();
+                if DANGEROUS_COMMANDS.contains(&entry.command.as_str()) {
+                    reasons.push("dangerous command");
+                }
+                if entry.allow_metacharacters {
+                    reasons.push("shell metacharacters");
+                }
+                if entry.allow_args && entry.arg_pattern.is_none() {
+                    reasons.push("unrestricted args");
+                }
+                (!reasons.is_empty()).then(|| format!("shell:{} ({})", entry.command, reasons.join(", ")))
+            })
+            .collect()
+    }
+
+    fn check_dangerous_tools(&self, report: &mut SecurityReport) {
+        let dangerous = self.dangerous_commands();
+        for agent in &self.agents {
+            let detail = if dangerous.is_empty() {
+                "none".to_string()
+            } else {
+                dangerous.join("; ")
+            };
+            report.push(format!("dangerous_tools:{}", agent), Severity::Medium, dangerous.is_empty(), detail);
+        }
+    }
+
+    /// 配置中明文写入的密钥喵（只报告字段名，不输出内容）
+    fn check_plaintext_secrets(&self, report: &mut SecurityReport) {
+        let config = self.config;
+        let mut fields = Vec::new();
+        let plaintext = |value: &str| !value.is_empty() && !is_encrypted_secret(value);
+        if config.api_key.as_deref().is_some_and(plaintext) {
+            fields.push("api_key".to_string());
+        }
+        if let Some(providers) = &config.providers {
+            let named = [
+                ("nvidia", &providers.nvidia),
+                ("openai", &providers.openai),
+                ("openrouter", &providers.openrouter),
+                ("anthropic", &providers.anthropic),
+                ("gemini", &providers.gemini),
+                ("ollama", &providers.ollama),
+            ];
+            for (name, provider) in named {
+                if provider.as_ref().is_some_and(|p| plaintext(&p.api_key)) {
+                    fields.push(format!("providers.{}.api_key", name));
+                }
+            }
+        }
+        if config.discord_config.as_ref().is_some_and(|d| !d.token.is_empty()) {
+            fields.push("discord.token".to_string());
+        }
+
+        let detail = if fields.is_empty() {
+            "none".to_string()
+        } else {
+            format!("{} (move to environment variables or `nekoclaw secret set`)", fields.join(", "))
+        };
+        report.push("plaintext_secrets", Severity::High, fields.is_empty(), detail);
+    }
+
+    fn check_gateway(&self, report: &mut SecurityReport) {
+        let bind = self.config.gateway_bind.as_deref().unwrap_or("127.0.0.1");
+        let local = matches!(bind, "localhost" | "::1") || bind.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback());
+        // Gateway 本身只提供明文 HTTP，对外暴露时必须在前面加 TLS 反向代理喵
+        let detail = if local {
+            format!("bound to {}", bind)
+        } else {
+            format!("bound to {} over plain HTTP; terminate TLS in a reverse proxy or bind to 127.0.0.1", bind)
+        };
+        report.push("gateway_tls", Severity::High, local, detail);
+
+        let has_token = self.config.api_key.as_deref().is_some_and(|k| !k.is_empty());
+        report.push(
+            "gateway_auth",
+            Severity::High,
+            has_token,
+            if has_token { "bearer token configured" } else { "empty bearer token: protected endpoints accept `Bearer ` with no token" },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::allowlist::CommandAllowlistEntry;
+
+    #[test]
+    fn test_audit_flags_and_scores() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.toml"), "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir.path().join("config.toml"), std::fs::Permissions::from_mode(0o644)).unwrap();
+        }
+
+        let mut config = Config {
+            api_key: Some("sk-test".to_string()),
+            gateway_bind: Some("0.0.0.0".to_string()),
+            ..Config::default()
+        };
+        config.security = Some(crate::core::traits::SecuritySettings {
+            allowlist: Some(AllowlistConfig {
+                commands: vec![CommandAllowlistEntry {
+                    command: "curl".to_string(),
+                    description: String::new(),
+                    allow_args: true,
+                    arg_pattern: None,
+                    max_args: None,
+                    max_arg_length: None,
+                    allow_metacharacters: false,
+                }],
+                paths: Vec::new(),
+                default_deny: false,
+            }),
+            escalation: None,
+            tool_env: None,
+            tool_approval: Default::default(),
+            shell: Default::default(),
+        });
+
+        let audit = SecurityAudit::new(&config, &[dir.path()]).with_agents(&["nia"]);
+        let report = audit.run();
+        let failed: Vec<&str> = report.failures().iter().map(|f| f.check.as_str()).collect();
+        assert!(failed.contains(&"default_deny"));
+        assert!(failed.contains(&"plaintext_secrets"));
+        assert!(failed.contains(&"gateway_tls"));
+        assert!(failed.contains(&"dangerous_tools:nia"));
+        assert!(!failed.contains(&"gateway_auth"));
+        assert!(report.score() < 50);
+        assert!(!report.findings.iter().any(|f| f.detail.contains("sk-test")));
+
+        #[cfg(unix)]
+        {
+            assert!(failed.iter().any(|c| c.starts_with("permissions:")));
+            assert_eq!(audit.fix_permissions().unwrap(), 1);
+            assert!(audit.run().findings.iter().filter(|f| f.check.starts_with("permissions:")).all(|f| f.passed));
+        }
+
+        assert_eq!(SecurityReport::default().score(), 100);
+    }
+}
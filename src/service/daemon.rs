@@ -0,0 +1,202 @@
+//!
+//! # Daemon Process
+//!
+//! ⚠️ SAFETY: `daemon --background` 的后台运行与 PID 文件喵
+//!
+//! ## 功能说明
+//! - 后台模式以新会话（setsid）重新启动自身，脱离终端，stdin 置空，stdout/stderr 追加到日志文件喵
+//! - 守护进程持有 PID 文件的排他锁（flock），同一个 PID 文件只能有一个实例喵
+//! - `status` 通过 PID 文件与锁判断守护进程是否在运行，崩溃遗留的 PID 文件不会误判喵
+//!
+//! 不使用 fork：Tokio 运行时已经启动了工作线程，fork 出的子进程只保留当前线程喵
+
+use crate::core::traits::Result;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// 标记后台子进程的环境变量（子进程不再分离）喵
+pub const DAEMON_CHILD_ENV: &str = "NEKOCLAW_DAEMON_CHILD";
+/// 默认 PID 文件名（位于工作区根目录）喵
+pub const DEFAULT_PID_FILE: &str = "daemon.pid";
+/// 后台模式的日志文件（位于工作区 `logs/`）喵
+pub const DAEMON_LOG_FILE: &str = "daemon.log";
+
+/// 持有锁的 PID 文件喵
+///
+/// 🔐 SAFETY: 锁随文件句柄释放，进程崩溃时由内核自动解锁喵
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    _file: File,
+}
+
+impl PidFile {
+    /// 加锁并写入当前进程 PID 喵（已有实例持有锁时返回错误）
+    pub fn acquire(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if !try_lock(&file)? {
+            let pid = read_pid(path).map(|pid| pid.to_string()).unwrap_or_else(|| "?".to_string());
+            return Err(format!("Daemon already running (pid {}, {})", pid, path.display()).into());
+        }
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+        file.sync_all()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            _file: file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 读取 PID 文件中的 PID 喵
+pub fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// 正在运行的守护进程 PID 喵（PID 文件不存在或无人持锁时为 None）
+pub fn running_pid(path: &Path) -> Option<u32> {
+    let pid = read_pid(path)?;
+    let file = File::open(path).ok()?;
+    // 能拿到锁说明持有者已退出，PID 文件是遗留的喵（非 Unix 平台无法判断，以文件为准）
+    match cfg!(unix) && try_lock(&file).unwrap_or(false) {
+        true => None,
+        false => Some(pid),
+    }
+}
+
+/// 是否为后台模式启动的子进程喵
+pub fn is_daemon_child() -> bool {
+    std::env::var_os(DAEMON_CHILD_ENV).is_some()
+}
+
+/// 以相同参数在后台重新启动自身喵
+///
+/// 子进程在新会话中运行（Unix setsid / Windows DETACHED_PROCESS），输出追加到 `log_path`
+pub fn spawn_detached(log_path: &Path) -> Result<Child> {
+    if let Some(dir) = log_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let log = OpenOptions::new().create(true).append(true).open(log_path)?;
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(std::env::args_os().skip(1))
+        .env(DAEMON_CHILD_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // 🔒 SAFETY: setsid 是 async-signal-safe 的，可以在 fork 与 exec 之间调用喵
+        unsafe {
+            command.pre_exec(|| match libc::setsid() {
+                -1 => Err(std::io::Error::last_os_error()),
+                _ => Ok(()),
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    Ok(command.spawn()?)
+}
+
+/// 等待后台子进程写入 PID 文件喵
+///
+/// 子进程提前退出时返回错误；超时仍未就绪时返回 None（可能仍在初始化）
+pub fn wait_until_ready(child: &mut Child, pid_path: &Path, timeout: Duration) -> Result<Option<u32>> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            return Err(format!("Daemon exited during startup ({})", status).into());
+        }
+        if running_pid(pid_path) == Some(child.id()) {
+            return Ok(Some(child.id()));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(None)
+}
+
+/// 等待停止信号喵（Ctrl+C，Unix 上还包括 systemctl / launchctl 发送的 SIGTERM）
+pub async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+/// 尝试加排他锁喵（已被其他句柄持有时返回 false）
+#[cfg(unix)]
+fn try_lock(file: &File) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+    // 🔒 SAFETY: fd 在 file 存活期间有效喵
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = std::io::Error::last_os_error();
+    match error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        true => Ok(false),
+        false => Err(error),
+    }
+}
+
+/// 非 Unix 平台没有 flock，总是视为加锁成功喵
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_lock_and_stale_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join(DEFAULT_PID_FILE);
+
+        let pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+        assert_eq!(running_pid(&path), Some(std::process::id()));
+        assert!(PidFile::acquire(&path).unwrap_err().to_string().contains("already running"));
+
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // 崩溃遗留（无人持锁）的 PID 文件不算运行中，可以重新获取喵
+        std::fs::write(&path, "999999\n").unwrap();
+        assert_eq!(running_pid(&path), None);
+        let _pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+}
@@ -0,0 +1,364 @@
+//!
+//! # Environment Doctor
+//!
+//! ⚠️ SAFETY: `nekoclaw doctor` 的环境检查与自动修复喵
+//!
+//! ## 检查项
+//! - 配置文件能否解析、能否通过 `ConfigValidator` 校验喵
+//! - 每个启用的 provider 是否配置了 API Key、端点能否连通喵
+//! - 工作区 / 会话 / 记忆库目录是否存在且可写喵
+//! - 记忆库的结构版本（PRAGMA user_version）喵
+//! - Gateway 端口是否可用喵
+//!
+//! ## 自动修复（`--fix`）
+//! - 缺少配置文件时写入默认配置喵
+//! - 创建缺少的目录喵
+//! - 迁移旧版本记忆库喵
+//!
+//! 连通性检查只发送 HEAD 请求，不消耗 token喵
+
+use crate::core::traits::Config;
+use crate::core::WorkspaceProfile;
+use crate::memory::sqlite::{schema_version, SCHEMA_VERSION};
+use crate::memory::MemoryFactory;
+use crate::service::daemon;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 已知的 provider（顺序即输出顺序）喵
+const PROVIDERS: &[&str] = &["nvidia", "openai", "openrouter", "anthropic", "gemini", "ollama"];
+
+/// 默认连通性检查超时喵
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 🔒 SAFETY: 检查结果状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Ok => "✅",
+            Self::Warn => "⚠️",
+            Self::Fail => "❌",
+        }
+    }
+}
+
+/// 可自动修复的问题喵
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    /// 写入默认配置文件
+    WriteDefaultConfig,
+    /// 创建目录
+    CreateDir(PathBuf),
+    /// 打开记忆库以执行迁移
+    MigrateMemory(PathBuf),
+}
+
+/// 🔒 SAFETY: 单项检查结果喵
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// 检查项名称（如 `provider:openai`）
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip)]
+    pub repair: Option<Repair>,
+}
+
+impl DoctorCheck {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            repair: None,
+        }
+    }
+
+    fn with_repair(mut self, repair: Repair) -> Self {
+        self.repair = Some(repair);
+        self
+    }
+
+    /// `--fix` 能否修复喵
+    pub fn fixable(&self) -> bool {
+        self.status != CheckStatus::Ok && self.repair.is_some()
+    }
+}
+
+/// 🔒 SAFETY: 环境诊断喵
+pub struct Doctor<'a> {
+    profile: &'a WorkspaceProfile,
+    config: &'a Config,
+    timeout: Duration,
+}
+
+impl<'a> Doctor<'a> {
+    pub fn new(profile: &'a WorkspaceProfile, config: &'a Config) -> Self {
+        Self {
+            profile,
+            config,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// 连通性检查超时喵
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 执行所有检查喵
+    pub async fn run(&self) -> Vec<DoctorCheck> {
+        let mut checks = vec![self.check_config()];
+        checks.extend(self.check_providers().await);
+        checks.extend(self.check_dirs());
+        checks.push(self.check_memory_schema());
+        checks.push(self.check_gateway_port());
+        checks
+    }
+
+    /// 执行一项修复喵
+    ///
+    /// ## Returns
+    /// 修复说明
+    pub fn repair(&self, repair: &Repair) -> Result<String, String> {
+        match repair {
+            Repair::WriteDefaultConfig => {
+                let (path, _) = self.profile.reset_config().map_err(|e| e.to_string())?;
+                Ok(format!("wrote default config to {}", path.display()))
+            }
+            Repair::CreateDir(dir) => {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                Ok(format!("created {}", dir.display()))
+            }
+            Repair::MigrateMemory(path) => {
+                let settings = self.config.memory.clone().unwrap_or_default();
+                MemoryFactory::open_sqlite(&path.to_string_lossy(), &settings).map_err(|e| e.to_string())?;
+                Ok(format!("migrated {} to schema v{}", path.display(), SCHEMA_VERSION))
+            }
+        }
+    }
+
+    /// 配置文件解析与校验喵
+    fn check_config(&self) -> DoctorCheck {
+        let path = self.profile.config_file();
+        if !path.exists() {
+            return DoctorCheck::new("config", CheckStatus::Warn, format!("{} not found, using defaults", path.display()))
+                .with_repair(Repair::WriteDefaultConfig);
+        }
+        let checked = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| self.profile.check_config_content(&path, &content).map_err(|e| e.to_string()));
+        match checked {
+            Ok(_) => DoctorCheck::new("config", CheckStatus::Ok, format!("{} is valid", path.display())),
+            Err(e) => DoctorCheck::new("config", CheckStatus::Fail, format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// 启用的 provider：显式配置的，加上 `default_provider` 喵
+    fn enabled_providers(&self) -> Vec<&'static str> {
+        let providers = self.config.providers.as_ref();
+        PROVIDERS
+            .iter()
+            .copied()
+            .filter(|name| {
+                let configured = providers.is_some_and(|p| match *name {
+                    "nvidia" => p.nvidia.is_some(),
+                    "openai" => p.openai.is_some(),
+                    "openrouter" => p.openrouter.is_some(),
+                    "anthropic" => p.anthropic.is_some(),
+                    "gemini" => p.gemini.is_some(),
+                    _ => p.ollama.is_some(),
+                });
+                configured || *name == self.config.default_provider
+            })
+            .collect()
+    }
+
+    /// API Key 与端点连通性喵（HTTP 有响应即视为连通，状态码不论）
+    async fn check_providers(&self) -> Vec<DoctorCheck> {
+        let client = match reqwest::Client::builder().timeout(self.timeout).build() {
+            Ok(client) => client,
+            Err(e) => return vec![DoctorCheck::new("providers", CheckStatus::Fail, e.to_string())],
+        };
+        let probes = self.enabled_providers().into_iter().map(|name| {
+            let client = client.clone();
+            async move {
+                let check = format!("provider:{}", name);
+                let Some(provider) = self.config.provider(name) else {
+                    return DoctorCheck::new(check, CheckStatus::Fail, "unknown provider");
+                };
+                if provider.api_key.is_empty() && name != "ollama" {
+                    return DoctorCheck::new(
+                        check,
+                        CheckStatus::Fail,
+                        format!("no API key (providers.{}.api_key or {}_API_KEY)", name, name.to_uppercase()),
+                    );
+                }
+                let started = Instant::now();
+                match client.head(&provider.base_url).send().await {
+                    Ok(response) => DoctorCheck::new(
+                        check,
+                        CheckStatus::Ok,
+                        format!("{} reachable (HTTP {}, {}ms)", provider.base_url, response.status().as_u16(), started.elapsed().as_millis()),
+                    ),
+                    Err(e) => DoctorCheck::new(check, CheckStatus::Fail, format!("{} unreachable: {}", provider.base_url, e)),
+                }
+            }
+        });
+        futures::future::join_all(probes).await
+    }
+
+    /// 目录存在且可写喵
+    fn check_dirs(&self) -> Vec<DoctorCheck> {
+        let memory_dir = self
+            .config
+            .memory_db_path()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        [
+            ("dir:workspace", self.profile.root.clone()),
+            ("dir:agent", self.profile.workspace_dir()),
+            ("dir:sessions", self.profile.sessions_dir()),
+            ("dir:memory", memory_dir),
+        ]
+        .into_iter()
+        .map(|(name, dir)| {
+            if !dir.is_dir() {
+                return DoctorCheck::new(name, CheckStatus::Fail, format!("{} does not exist", dir.display()))
+                    .with_repair(Repair::CreateDir(dir));
+            }
+            match probe_writable(&dir) {
+                Ok(()) => DoctorCheck::new(name, CheckStatus::Ok, format!("{} is writable", dir.display())),
+                Err(e) => DoctorCheck::new(name, CheckStatus::Fail, format!("{} is not writable: {}", dir.display(), e)),
+            }
+        })
+        .collect()
+    }
+
+    /// 记忆库结构版本喵
+    fn check_memory_schema(&self) -> DoctorCheck {
+        let path = self.config.memory_db_path();
+        if !path.exists() {
+            return DoctorCheck::new("memory:schema", CheckStatus::Ok, format!("{} not created yet", path.display()));
+        }
+        match schema_version(&path) {
+            Ok(version) if version == SCHEMA_VERSION => {
+                DoctorCheck::new("memory:schema", CheckStatus::Ok, format!("schema v{}", version))
+            }
+            Ok(version) if version < SCHEMA_VERSION => DoctorCheck::new(
+                "memory:schema",
+                CheckStatus::Fail,
+                format!("schema v{} needs migration to v{}", version, SCHEMA_VERSION),
+            )
+            .with_repair(Repair::MigrateMemory(path)),
+            Ok(version) => DoctorCheck::new(
+                "memory:schema",
+                CheckStatus::Fail,
+                format!("schema v{} is newer than supported v{}, upgrade nekoclaw", version, SCHEMA_VERSION),
+            ),
+            Err(e) => DoctorCheck::new("memory:schema", CheckStatus::Fail, format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// Gateway 端口可用性喵（被本工作区的守护进程占用时不算问题）
+    fn check_gateway_port(&self) -> DoctorCheck {
+        let bind = self.config.gateway_bind.as_deref().unwrap_or("127.0.0.1");
+        let port = self.config.gateway_port.unwrap_or(8080);
+        match std::net::TcpListener::bind((bind, port)) {
+            Ok(_) => DoctorCheck::new("gateway:port", CheckStatus::Ok, format!("{}:{} is available", bind, port)),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                match daemon::running_pid(&self.profile.root.join(daemon::DEFAULT_PID_FILE)) {
+                    Some(pid) => DoctorCheck::new(
+                        "gateway:port",
+                        CheckStatus::Ok,
+                        format!("{}:{} is in use by the daemon (pid {})", bind, port, pid),
+                    ),
+                    None => DoctorCheck::new("gateway:port", CheckStatus::Fail, format!("{}:{} is already in use", bind, port)),
+                }
+            }
+            Err(e) => DoctorCheck::new("gateway:port", CheckStatus::Fail, format!("cannot bind {}:{}: {}", bind, port, e)),
+        }
+    }
+}
+
+/// 在目录中创建并删除一个临时文件喵
+fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".doctor-{}.tmp", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::{MemorySettings, ProviderConfig, ProvidersConfig};
+
+    #[tokio::test]
+    async fn test_doctor_detects_and_repairs() {
+        let base = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(base.path(), None).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = Config {
+            gateway_port: Some(listener.local_addr().unwrap().port()),
+            memory: Some(MemorySettings {
+                path: Some(base.path().join("data/memory.db")),
+                ..Default::default()
+            }),
+            providers: Some(ProvidersConfig {
+                openai: Some(ProviderConfig {
+                    base_url: "http://127.0.0.1:1/v1".to_string(),
+                    api_key: String::new(),
+                    timeout: 1,
+                    max_retries: 0,
+                }),
+                ollama: Some(ProviderConfig {
+                    base_url: "http://127.0.0.1:1".to_string(),
+                    api_key: String::new(),
+                    timeout: 1,
+                    max_retries: 0,
+                }),
+                ..Default::default()
+            }),
+            ..Config::default()
+        };
+
+        // 旧版本记忆库喵
+        std::fs::create_dir_all(base.path().join("data")).unwrap();
+        rusqlite::Connection::open(base.path().join("data/memory.db"))
+            .unwrap()
+            .execute_batch("CREATE TABLE memory (id TEXT PRIMARY KEY, content TEXT NOT NULL, embedding BLOB, metadata TEXT, created_at TEXT NOT NULL)")
+            .unwrap();
+
+        let doctor = Doctor::new(&profile, &config).with_timeout(Duration::from_secs(1));
+        let checks = doctor.run().await;
+        let status = |name: &str| checks.iter().find(|c| c.name == name).unwrap().status;
+        assert_eq!(status("config"), CheckStatus::Warn);
+        assert!(checks.iter().find(|c| c.name == "provider:openai").unwrap().detail.contains("no API key"));
+        assert_eq!(status("provider:ollama"), CheckStatus::Fail);
+        assert_eq!(status("dir:sessions"), CheckStatus::Fail);
+        assert_eq!(status("dir:memory"), CheckStatus::Ok);
+        assert_eq!(status("memory:schema"), CheckStatus::Fail);
+        assert_eq!(status("gateway:port"), CheckStatus::Fail);
+
+        for check in checks.iter().filter(|c| c.fixable()) {
+            doctor.repair(check.repair.as_ref().unwrap()).unwrap();
+        }
+        let checks = doctor.run().await;
+        let status = |name: &str| checks.iter().find(|c| c.name == name).unwrap().status;
+        assert_eq!(status("config"), CheckStatus::Ok);
+        assert_eq!(status("dir:sessions"), CheckStatus::Ok);
+        assert_eq!(status("memory:schema"), CheckStatus::Ok);
+        assert!(checks.iter().all(|c| !c.fixable()));
+    }
+}
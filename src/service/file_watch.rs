@@ -0,0 +1,225 @@
+//!
+//! # Workspace File Watcher
+//!
+//! ⚠️ SAFETY: 监视目录的变化，并作为系统事件通知 Agent 会话喵
+//!
+//! ## 功能说明
+//! - 定时扫描目录（mtime + 大小），不依赖平台的文件通知 API喵
+//! - 去抖：最后一次变化之后安静 `debounce_secs` 秒才合并成一个事件，
+//!   写到一半的文件不会触发多次喵
+//! - 事件以系统消息注入目标 Chat 的 Agent 对话，Agent 的回应发回该 Chat喵
+//!
+//! ```toml
+//! [[watch]]
+//! path = "~/inbox"
+//! chat_id = 123456789
+//! recursive = false
+//! debounce_secs = 5
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 单个事件中最多列出的文件数喵
+const MAX_LISTED_FILES: usize = 20;
+
+fn default_debounce_secs() -> u64 {
+    5
+}
+
+/// 🔒 SAFETY: 单个监视目录配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchConfig {
+    /// 监视的目录（支持 `~/`）
+    pub path: PathBuf,
+    /// 接收事件的 Telegram Chat（需在 `allowed_chat_ids` 中）
+    pub chat_id: i64,
+    /// 是否包含子目录
+    #[serde(default)]
+    pub recursive: bool,
+    /// 去抖时间（秒）
+    #[serde(default = "default_debounce_secs")]
+    pub debounce_secs: u64,
+}
+
+impl WatchConfig {
+    /// 展开 `~/` 后的目录喵
+    pub fn resolved_path(&self) -> PathBuf {
+        match (self.path.strip_prefix("~"), dirs::home_dir()) {
+            (Ok(rest), Some(home)) => home.join(rest),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+/// 一批合并后的文件变化喵
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileChanges {
+    pub root: PathBuf,
+    pub created: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl FileChanges {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// 注入 Agent 对话的系统事件文本喵
+    pub fn to_event_message(&self) -> String {
+        let mut lines = vec![format!("[system event] Files changed in {}:", self.root.display())];
+        for (label, files) in [("created", &self.created), ("modified", &self.modified), ("removed", &self.removed)] {
+            if files.is_empty() {
+                continue;
+            }
+            let mut listed = files.iter().take(MAX_LISTED_FILES).cloned().collect::<Vec<_>>().join(", ");
+            if files.len() > MAX_LISTED_FILES {
+                listed.push_str(&format!(" (+{} more)", files.len() - MAX_LISTED_FILES));
+            }
+            lines.push(format!("- {}: {}", label, listed));
+        }
+        lines.push("This is an automatic notification, not a message from Master. React if it matters to ongoing work; otherwise reply briefly.".to_string());
+        lines.join("\n")
+    }
+}
+
+/// 文件状态快照（相对路径 → mtime, 大小）喵
+type Snapshot = BTreeMap<String, (Option<SystemTime>, u64)>;
+
+/// 🔒 SAFETY: 轮询式目录监视器喵（守护进程定时调用 `poll`）
+#[derive(Debug)]
+pub struct FileWatcher {
+    config: WatchConfig,
+    root: PathBuf,
+    /// 上次通知时的状态（首次扫描作为基线，不产生事件）
+    settled: Option<Snapshot>,
+    /// 最近一次扫描的状态
+    latest: Snapshot,
+    /// 最近一次发现变化的时间
+    last_change: Option<Instant>,
+}
+
+impl FileWatcher {
+    pub fn new(config: WatchConfig) -> Self {
+        Self {
+            root: config.resolved_path(),
+            config,
+            settled: None,
+            latest: Snapshot::new(),
+            last_change: None,
+        }
+    }
+
+    /// 扫描一次；变化稳定超过去抖时间后返回合并的变化喵
+    pub fn poll(&mut self, now: Instant) -> Option<FileChanges> {
+        let scan = scan_dir(&self.root, self.config.recursive);
+        let Some(settled) = &self.settled else {
+            self.settled = Some(scan.clone());
+            self.latest = scan;
+            return None;
+        };
+        if scan != self.latest {
+            self.latest = scan;
+            self.last_change = Some(now);
+        }
+
+        let debounce = Duration::from_secs(self.config.debounce_secs);
+        let quiet = self.last_change.is_some_and(|t| now.duration_since(t) >= debounce);
+        if !quiet {
+            return None;
+        }
+        self.last_change = None;
+        let changes = diff(&self.root, settled, &self.latest);
+        self.settled = Some(self.latest.clone());
+        Some(changes).filter(|c| !c.is_empty())
+    }
+}
+
+/// 比较两个快照喵
+fn diff(root: &Path, before: &Snapshot, after: &Snapshot) -> FileChanges {
+    let mut changes = FileChanges {
+        root: root.to_path_buf(),
+        ..FileChanges::default()
+    };
+    for (path, state) in after {
+        match before.get(path) {
+            None => changes.created.push(path.clone()),
+            Some(previous) if previous != state => changes.modified.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    changes.removed = before.keys().filter(|p| !after.contains_key(*p)).cloned().collect();
+    changes
+}
+
+/// 扫描目录中的文件（跳过隐藏文件）喵；目录不存在时为空
+fn scan_dir(root: &Path, recursive: bool) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if metadata.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(root) {
+                snapshot.insert(
+                    relative.to_string_lossy().to_string(),
+                    (metadata.modified().ok(), metadata.len()),
+                );
+            }
+        }
+    }
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_debounced_and_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("old.txt"), "old").unwrap();
+        std::fs::write(dir.path().join("gone.txt"), "bye").unwrap();
+        let mut watcher = FileWatcher::new(WatchConfig {
+            path: dir.path().to_path_buf(),
+            chat_id: 1,
+            recursive: false,
+            debounce_secs: 5,
+        });
+        let start = Instant::now();
+        assert_eq!(watcher.poll(start), None);
+
+        std::fs::write(dir.path().join("report.pdf"), "part").unwrap();
+        std::fs::remove_file(dir.path().join("gone.txt")).unwrap();
+        assert_eq!(watcher.poll(start + Duration::from_secs(1)), None);
+        // 仍在写入：重新计时喵
+        std::fs::write(dir.path().join("report.pdf"), "partial report").unwrap();
+        std::fs::write(dir.path().join("old.txt"), "old but longer").unwrap();
+        assert_eq!(watcher.poll(start + Duration::from_secs(4)), None);
+        assert_eq!(watcher.poll(start + Duration::from_secs(8)), None);
+
+        let changes = watcher.poll(start + Duration::from_secs(9)).unwrap();
+        assert_eq!(changes.created, ["report.pdf"]);
+        assert_eq!(changes.modified, ["old.txt"]);
+        assert_eq!(changes.removed, ["gone.txt"]);
+        assert!(changes.to_event_message().contains("- created: report.pdf"));
+        assert_eq!(watcher.poll(start + Duration::from_secs(20)), None);
+    }
+}
@@ -0,0 +1,373 @@
+//!
+//! # Service Installer
+//!
+//! ⚠️ SAFETY: 把 `nekoclaw daemon` 安装为系统服务喵
+//!
+//! ## 功能说明
+//! - Linux：生成 systemd 用户单元 `~/.config/systemd/user/nekoclaw.service`，通过 `systemctl --user` 管理喵
+//! - macOS：生成 launchd 代理 `~/Library/LaunchAgents/com.nekoclaw.daemon.plist`，通过 `launchctl` 管理喵
+//! - 单元指向当前可执行文件与配置目录，非默认工作区使用独立的服务名喵
+//!
+//! 只安装到当前用户，不需要 root 权限喵
+
+use crate::core::traits::Result;
+use crate::core::WorkspaceProfile;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// systemd 单元 / launchd 标签的基础名称喵
+const SERVICE_NAME: &str = "nekoclaw";
+/// launchd 标签前缀喵
+const LAUNCHD_LABEL: &str = "com.nekoclaw.daemon";
+
+/// 服务管理器类型喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServicePlatform {
+    /// Linux systemd（用户单元）喵
+    Systemd,
+    /// macOS launchd（LaunchAgent）喵
+    Launchd,
+}
+
+impl ServicePlatform {
+    /// 当前系统使用的服务管理器喵
+    pub fn current() -> Result<Self> {
+        if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else {
+            Err(format!("Service install is not supported on {}", std::env::consts::OS).into())
+        }
+    }
+}
+
+/// 服务控制操作喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+    Status,
+}
+
+/// 服务安装器喵
+///
+/// 🔐 SAFETY: 只写入当前用户的单元目录喵
+#[derive(Debug, Clone)]
+pub struct ServiceInstaller {
+    platform: ServicePlatform,
+    /// 服务名（systemd 单元名 / launchd 标签）喵
+    name: String,
+    binary: PathBuf,
+    base_dir: PathBuf,
+    workspace: Option<String>,
+    log_dir: PathBuf,
+    unit_dir: PathBuf,
+}
+
+impl ServiceInstaller {
+    /// 为当前可执行文件与工作区创建安装器喵
+    pub fn new(platform: ServicePlatform, profile: &WorkspaceProfile) -> Result<Self> {
+        let binary = std::env::current_exe()
+            .and_then(|path| path.canonicalize())
+            .map_err(|e| format!("Cannot locate the nekoclaw binary: {}", e))?;
+        let home = dirs::home_dir().ok_or("Cannot find home directory")?;
+        let unit_dir = match platform {
+            ServicePlatform::Systemd => home.join(".config/systemd/user"),
+            ServicePlatform::Launchd => home.join("Library/LaunchAgents"),
+        };
+        Ok(Self::with_paths(platform, profile, binary, unit_dir))
+    }
+
+    /// 指定可执行文件与单元目录喵（测试使用）
+    pub fn with_paths(platform: ServicePlatform, profile: &WorkspaceProfile, binary: PathBuf, unit_dir: PathBuf) -> Self {
+        let workspace = (!profile.is_default()).then(|| profile.name.clone());
+        let name = match (platform, &workspace) {
+            (ServicePlatform::Systemd, None) => SERVICE_NAME.to_string(),
+            (ServicePlatform::Systemd, Some(ws)) => format!("{}-{}", SERVICE_NAME, ws),
+            (ServicePlatform::Launchd, None) => LAUNCHD_LABEL.to_string(),
+            (ServicePlatform::Launchd, Some(ws)) => format!("{}.{}", LAUNCHD_LABEL, ws),
+        };
+        Self {
+            platform,
+            name,
+            binary,
+            base_dir: profile.base_dir.clone(),
+            workspace,
+            log_dir: profile.root.join("logs"),
+            unit_dir,
+        }
+    }
+
+    /// 服务名喵
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 单元文件路径喵
+    pub fn unit_path(&self) -> PathBuf {
+        match self.platform {
+            ServicePlatform::Systemd => self.unit_dir.join(format!("{}.service", self.name)),
+            ServicePlatform::Launchd => self.unit_dir.join(format!("{}.plist", self.name)),
+        }
+    }
+
+    /// 是否已安装喵
+    pub fn is_installed(&self) -> bool {
+        self.unit_path().is_file()
+    }
+
+    /// 服务进程的命令行参数喵
+    fn program_arguments(&self) -> Vec<String> {
+        let mut args = vec![
+            self.binary.to_string_lossy().to_string(),
+            "--config-dir".to_string(),
+            self.base_dir.to_string_lossy().to_string(),
+        ];
+        if let Some(workspace) = &self.workspace {
+            args.extend(["--workspace".to_string(), workspace.clone()]);
+        }
+        args.push("daemon".to_string());
+        args
+    }
+
+    /// 生成单元文件内容喵
+    pub fn render(&self) -> String {
+        match self.platform {
+            ServicePlatform::Systemd => self.render_systemd(),
+            ServicePlatform::Launchd => self.render_launchd(),
+        }
+    }
+
+    fn render_systemd(&self) -> String {
+        let exec = self
+            .program_arguments()
+            .iter()
+            .map(|arg| systemd_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "[Unit]\n\
+             Description=Neko-Claw daemon ({workspace})\n\
+             After=network-online.target\n\
+             Wants=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={exec}\n\
+             WorkingDirectory={dir}\n\
+             Restart=on-failure\n\
+             RestartSec=5\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            workspace = self.workspace.as_deref().unwrap_or("default"),
+            exec = exec,
+            dir = systemd_quote(&self.base_dir.to_string_lossy()),
+        )
+    }
+
+    fn render_launchd(&self) -> String {
+        let args = self
+            .program_arguments()
+            .iter()
+            .map(|arg| format!("        <string>{}</string>", xml_escape(arg)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let log = |file: &str| xml_escape(&self.log_dir.join(file).to_string_lossy());
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}
+    </array>
+    <key>WorkingDirectory</key>
+    <string>{dir}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+</dict>
+</plist>
+"#,
+            label = xml_escape(&self.name),
+            args = args,
+            dir = xml_escape(&self.base_dir.to_string_lossy()),
+            stdout = log("daemon.out.log"),
+            stderr = log("daemon.err.log"),
+        )
+    }
+
+    /// 写入单元文件并注册（systemd 同时 enable，开机自启）喵
+    pub fn install(&self) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.unit_dir)?;
+        if self.platform == ServicePlatform::Launchd {
+            std::fs::create_dir_all(&self.log_dir)?;
+        }
+        let path = self.unit_path();
+        std::fs::write(&path, self.render())?;
+        if self.platform == ServicePlatform::Systemd {
+            run(&self.systemctl(&["daemon-reload"]))?;
+            run(&self.systemctl(&["enable", &self.unit_name()]))?;
+        }
+        Ok(path)
+    }
+
+    /// 停止并删除单元文件喵（未安装时返回 false）
+    pub fn uninstall(&self) -> Result<bool> {
+        if !self.is_installed() {
+            return Ok(false);
+        }
+        match self.platform {
+            ServicePlatform::Systemd => {
+                run(&self.systemctl(&["disable", "--now", &self.unit_name()]))?;
+            }
+            ServicePlatform::Launchd => {
+                // 未加载时 unload 会失败，删除前忽略喵
+                let _ = run(&self.command(ServiceAction::Stop));
+            }
+        }
+        std::fs::remove_file(self.unit_path())?;
+        if self.platform == ServicePlatform::Systemd {
+            run(&self.systemctl(&["daemon-reload"]))?;
+        }
+        Ok(true)
+    }
+
+    /// 执行控制操作，返回命令输出喵
+    ///
+    /// `Status` 在服务未运行时也返回输出（systemctl 此时退出码非 0）
+    pub fn control(&self, action: ServiceAction) -> Result<String> {
+        if !self.is_installed() {
+            return Err(format!("Service {} is not installed, run `nekoclaw service --install` first", self.name).into());
+        }
+        if action == ServiceAction::Restart && self.platform == ServicePlatform::Launchd {
+            let _ = run(&self.command(ServiceAction::Stop));
+            return run(&self.command(ServiceAction::Start));
+        }
+        let command = self.command(action);
+        match action {
+            ServiceAction::Status => output(&command).map(|(_, text)| text),
+            _ => run(&command),
+        }
+    }
+
+    /// 控制操作对应的命令行喵
+    pub fn command(&self, action: ServiceAction) -> Vec<String> {
+        match self.platform {
+            ServicePlatform::Systemd => {
+                let unit = self.unit_name();
+                match action {
+                    ServiceAction::Start => self.systemctl(&["start", &unit]),
+                    ServiceAction::Stop => self.systemctl(&["stop", &unit]),
+                    ServiceAction::Restart => self.systemctl(&["restart", &unit]),
+                    ServiceAction::Status => self.systemctl(&["status", "--no-pager", &unit]),
+                }
+            }
+            ServicePlatform::Launchd => {
+                let plist = self.unit_path().to_string_lossy().to_string();
+                let args: Vec<&str> = match action {
+                    ServiceAction::Start | ServiceAction::Restart => vec!["load", "-w", &plist],
+                    ServiceAction::Stop => vec!["unload", &plist],
+                    ServiceAction::Status => vec!["list", &self.name],
+                };
+                std::iter::once("launchctl").chain(args).map(String::from).collect()
+            }
+        }
+    }
+
+    fn unit_name(&self) -> String {
+        format!("{}.service", self.name)
+    }
+
+    fn systemctl(&self, args: &[&str]) -> Vec<String> {
+        ["systemctl", "--user"].iter().chain(args).map(|s| s.to_string()).collect()
+    }
+}
+
+/// 执行命令，返回 (是否成功, stdout + stderr) 喵
+fn output(command: &[String]) -> Result<(bool, String)> {
+    let (program, args) = command.split_first().ok_or("Empty command")?;
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text.trim_end().to_string()))
+}
+
+/// 执行命令，失败时返回带输出的错误喵
+fn run(command: &[String]) -> Result<String> {
+    match output(command)? {
+        (true, text) => Ok(text),
+        (false, text) => Err(format!("`{}` failed: {}", command.join(" "), text).into()),
+    }
+}
+
+/// systemd 参数引用喵（空格、引号、反斜杠与 `%` 说明符）
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%");
+    if escaped.contains(char::is_whitespace) || escaped != arg {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// XML 转义喵
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_render_units_for_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(Path::new("/home/neko/.nekoclaw"), Some("work")).unwrap();
+        let binary = PathBuf::from("/opt/neko claw/nekoclaw");
+
+        let systemd = ServiceInstaller::with_paths(ServicePlatform::Systemd, &profile, binary.clone(), dir.path().into());
+        assert_eq!(systemd.unit_path(), dir.path().join("nekoclaw-work.service"));
+        let unit = systemd.render();
+        assert!(unit.contains(
+            "ExecStart=\"/opt/neko claw/nekoclaw\" --config-dir /home/neko/.nekoclaw --workspace work daemon"
+        ));
+        assert_eq!(
+            systemd.command(ServiceAction::Status),
+            ["systemctl", "--user", "status", "--no-pager", "nekoclaw-work.service"]
+        );
+
+        let launchd = ServiceInstaller::with_paths(ServicePlatform::Launchd, &profile, binary, dir.path().into());
+        assert_eq!(launchd.name(), "com.nekoclaw.daemon.work");
+        let plist = launchd.render();
+        assert!(plist.contains("<string>/opt/neko claw/nekoclaw</string>"));
+        assert!(plist.contains("/home/neko/.nekoclaw/workspaces/work/logs/daemon.err.log"));
+        assert_eq!(launchd.command(ServiceAction::Status), ["launchctl", "list", "com.nekoclaw.daemon.work"]);
+
+        // 未安装时控制操作直接报错，不调用 systemctl 喵
+        assert!(systemd.control(ServiceAction::Start).is_err());
+        assert!(!systemd.uninstall().unwrap());
+        assert_eq!(systemd_quote("100%"), "\"100%%\"");
+    }
+}
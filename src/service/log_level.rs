@@ -0,0 +1,264 @@
+//!
+//! # Runtime Log Level Control
+//!
+//! ⚠️ SAFETY: 运行中动态调整 tracing 过滤级别，无需重启守护进程喵
+//!
+//! ## 功能说明
+//! - 日志过滤器通过 `reload::Layer` 安装，可随时替换喵
+//! - CLI (`nekoclaw logs level debug --module gateway`) 写入控制文件，
+//!   守护进程定时检查并应用；删除控制文件即恢复启动时的级别喵
+//! - Gateway 提供 `/admin/log-level` 端点，直接调整 Gateway 进程喵
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+use tracing::info;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+/// 控制文件名喵
+pub const LOG_FILTER_FILE: &str = "log_filter.json";
+/// 允许的级别喵
+const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+/// 本 crate 的 target 前缀喵
+const CRATE_TARGET: &str = "nekoclaw";
+
+/// 全局句柄（tracing 订阅者本身就是全局的）喵
+static GLOBAL: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// 过滤级别状态喵
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogFilterState {
+    /// 全局默认级别（None 时沿用启动级别）
+    #[serde(default)]
+    pub default: Option<String>,
+    /// 模块级覆盖（完整 target → 级别）
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl LogFilterState {
+    /// 设置级别；`module` 为 None 时修改全局默认级别喵
+    pub fn set(&mut self, level: &str, module: Option<&str>) -> Result<(), String> {
+        let level = parse_level(level)?;
+        match module {
+            Some(module) => {
+                self.modules.insert(module_target(module)?, level);
+            }
+            None => self.default = Some(level),
+        }
+        Ok(())
+    }
+
+    /// 渲染为 EnvFilter 指令，例如 `info,nekoclaw::gateway=debug` 喵
+    /// 未设置全局级别时使用 `fallback`
+    pub fn directive(&self, fallback: &str) -> String {
+        std::iter::once(self.default.clone().unwrap_or_else(|| fallback.to_string()))
+            .chain(self.modules.iter().map(|(target, level)| format!("{}={}", target, level)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn parse_level(level: &str) -> Result<String, String> {
+    let level = level.to_ascii_lowercase();
+    if LEVELS.contains(&level.as_str()) {
+        Ok(level)
+    } else {
+        Err(format!("未知日志级别 '{}'，可选: {}", level, LEVELS.join(", ")))
+    }
+}
+
+/// `gateway` → `nekoclaw::gateway`，已带 `::` 的完整路径原样保留喵
+fn module_target(module: &str) -> Result<String, String> {
+    let valid = !module.is_empty()
+        && module
+            .split("::")
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    if !valid {
+        return Err(format!("模块名 '{}' 不合法喵", module));
+    }
+    if module.contains("::") || module == CRATE_TARGET {
+        Ok(module.to_string())
+    } else {
+        Ok(format!("{}::{}", CRATE_TARGET, module))
+    }
+}
+
+/// 可重载过滤器句柄喵
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    reload: reload::Handle<EnvFilter, Registry>,
+    /// 启动时的全局级别
+    startup: String,
+    state: Arc<Mutex<LogFilterState>>,
+}
+
+impl std::fmt::Debug for LogLevelHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogLevelHandle").field("filter", &self.directive()).finish()
+    }
+}
+
+impl LogLevelHandle {
+    /// 创建句柄与对应的过滤层喵（调用方负责把层装进订阅者）
+    pub fn new(default: &str) -> (Self, reload::Layer<EnvFilter, Registry>) {
+        let (layer, reload) = reload::Layer::new(EnvFilter::new(default));
+        let handle = Self {
+            reload,
+            startup: default.to_string(),
+            state: Arc::new(Mutex::new(LogFilterState::default())),
+        };
+        (handle, layer)
+    }
+
+    /// 当前生效的状态喵
+    pub fn current(&self) -> LogFilterState {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 当前生效的 EnvFilter 指令喵
+    pub fn directive(&self) -> String {
+        self.current().directive(&self.startup)
+    }
+
+    /// 应用一份完整状态喵
+    pub fn apply(&self, state: LogFilterState) -> Result<(), String> {
+        let directive = state.directive(&self.startup);
+        let filter = EnvFilter::try_new(&directive).map_err(|e| e.to_string())?;
+        self.reload.reload(filter).map_err(|e| e.to_string())?;
+        info!("🔧 日志级别已调整为 {}", directive);
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = state;
+        Ok(())
+    }
+
+    /// 调整单个级别喵
+    pub fn set(&self, level: &str, module: Option<&str>) -> Result<LogFilterState, String> {
+        let mut state = self.current();
+        state.set(level, module)?;
+        self.apply(state.clone())?;
+        Ok(state)
+    }
+
+    /// 恢复启动时的级别喵
+    pub fn reset(&self) -> Result<(), String> {
+        if self.current() != LogFilterState::default() {
+            self.apply(LogFilterState::default())?;
+        }
+        Ok(())
+    }
+}
+
+/// 安装全局订阅者并返回句柄喵
+///
+/// `stderr` 为 true 时日志写到 stderr（stdout 留给 `agent --output json` 的事件流）
+pub fn init_logging(default: &str, stderr: bool) -> Option<&'static LogLevelHandle> {
+    let (handle, layer) = LogLevelHandle::new(default);
+    let (writer, is_terminal) = match stderr {
+        true => (BoxMakeWriter::new(std::io::stderr), std::io::IsTerminal::is_terminal(&std::io::stderr())),
+        false => (BoxMakeWriter::new(std::io::stdout), std::io::IsTerminal::is_terminal(&std::io::stdout())),
+    };
+    tracing_subscriber::registry()
+        .with(layer)
+        // 输出重定向到日志文件（daemon --background）时不写入颜色控制符喵
+        .with(tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(is_terminal))
+        .try_init()
+        .ok()?;
+    Some(GLOBAL.get_or_init(|| handle))
+}
+
+/// 全局句柄（未通过 `init_logging` 初始化时为 None）喵
+pub fn global() -> Option<&'static LogLevelHandle> {
+    GLOBAL.get()
+}
+
+/// 读取控制文件喵
+pub fn read_control_file(path: &Path) -> Option<LogFilterState> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 写入控制文件喵
+pub fn write_control_file(path: &Path, state: &LogFilterState) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(state).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// 控制文件监视器喵（守护进程定时调用 `poll`）
+pub struct ControlFileWatcher {
+    path: PathBuf,
+    handle: LogLevelHandle,
+    last_modified: Mutex<Option<SystemTime>>,
+}
+
+impl ControlFileWatcher {
+    pub fn new(path: PathBuf, handle: LogLevelHandle) -> Self {
+        Self {
+            path,
+            handle,
+            last_modified: Mutex::new(None),
+        }
+    }
+
+    /// 文件变化时应用新状态；文件被删除时恢复默认级别喵
+    pub fn poll(&self) -> Result<(), String> {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let mut last = self.last_modified.lock().unwrap_or_else(|e| e.into_inner());
+        if modified == *last {
+            return Ok(());
+        }
+        *last = modified;
+        drop(last);
+
+        match modified {
+            Some(_) => {
+                let state = read_control_file(&self.path)
+                    .ok_or_else(|| format!("无法解析 {}", self.path.display()))?;
+                self.handle.apply(state)
+            }
+            None => self.handle.reset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_state_directive() {
+        let mut state = LogFilterState::default();
+        state.set("DEBUG", Some("gateway")).unwrap();
+        state.set("trace", Some("tower_http::trace")).unwrap();
+        assert_eq!(state.directive("info"), "info,nekoclaw::gateway=debug,tower_http::trace=trace");
+        state.set("warn", None).unwrap();
+        assert_eq!(state.directive("info"), "warn,nekoclaw::gateway=debug,tower_http::trace=trace");
+
+        assert!(state.set("loud", None).is_err());
+        assert!(state.set("debug", Some("gate way")).is_err());
+        assert!(state.set("debug", Some("gateway::")).is_err());
+    }
+
+    #[test]
+    fn test_watcher_applies_and_resets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOG_FILTER_FILE);
+        let (handle, _layer) = LogLevelHandle::new("info");
+        let watcher = ControlFileWatcher::new(path.clone(), handle.clone());
+
+        watcher.poll().unwrap();
+        assert_eq!(handle.directive(), "info");
+
+        let mut state = LogFilterState::default();
+        state.set("debug", Some("gateway")).unwrap();
+        write_control_file(&path, &state).unwrap();
+        watcher.poll().unwrap();
+        assert_eq!(handle.directive(), "info,nekoclaw::gateway=debug");
+
+        std::fs::remove_file(&path).unwrap();
+        watcher.poll().unwrap();
+        assert_eq!(handle.directive(), "info");
+    }
+}
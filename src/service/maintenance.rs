@@ -0,0 +1,383 @@
+//!
+//! # Maintenance / Garbage Collection
+//!
+//! ⚠️ SAFETY: 清理不再被引用的磁盘数据喵
+//!
+//! ## 功能说明
+//! - 会话产物：`artifacts/` 下没有任何会话文件引用的文件喵
+//! - 凭证：已过期且没有 refresh token 的 `.cred` 文件喵
+//! - 配对申请：`pairing_requests.json` 中过期的设备配对申请喵
+//! - 沙箱临时目录：崩溃遗留的无痕会话目录喵
+//! - 会话临时工作区：超过 TTL 未使用的 `scratch/<会话 ID>` 目录喵
+//!
+//! 新产生的文件有宽限期，避免误删尚未写入会话的产物喵
+
+use crate::auth::CredentialStore;
+use crate::core::traits::Result;
+use crate::core::WorkspaceProfile;
+use crate::gateway::DeviceCodes;
+use crate::security::incognito::INCOGNITO_DIR_PREFIX;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// 产物宽限期喵
+const DEFAULT_ARTIFACT_GRACE: Duration = Duration::from_secs(3600);
+/// 临时目录最长保留时间喵
+const DEFAULT_SANDBOX_MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+/// 会话临时工作区默认 TTL喵
+const DEFAULT_SCRATCH_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// 单类清理结果喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CleanupStats {
+    pub removed: usize,
+    pub bytes: u64,
+}
+
+impl CleanupStats {
+    fn add(&mut self, bytes: u64) {
+        self.removed += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// 一次维护的汇总报告喵
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceReport {
+    pub artifacts: CleanupStats,
+    pub credentials: CleanupStats,
+    pub pairing_codes: usize,
+    pub sandbox_dirs: CleanupStats,
+    pub scratch_dirs: CleanupStats,
+}
+
+impl MaintenanceReport {
+    /// 释放的总空间喵
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.artifacts.bytes + self.credentials.bytes + self.sandbox_dirs.bytes + self.scratch_dirs.bytes
+    }
+
+    /// 是否清理了任何东西喵
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.removed
+            + self.credentials.removed
+            + self.pairing_codes
+            + self.sandbox_dirs.removed
+            + self.scratch_dirs.removed
+            == 0
+    }
+}
+
+/// 🔐 SAFETY: 维护任务喵
+pub struct Maintenance {
+    sessions_dir: PathBuf,
+    artifacts_dir: PathBuf,
+    temp_dir: PathBuf,
+    scratch_dir: PathBuf,
+    credentials: Option<CredentialStore>,
+    pairing: Option<DeviceCodes>,
+    artifact_grace: Duration,
+    sandbox_max_age: Duration,
+    scratch_ttl: Duration,
+}
+
+impl Maintenance {
+    /// 针对某个工作区创建维护任务喵
+    pub fn new(profile: &WorkspaceProfile) -> Self {
+        Self {
+            sessions_dir: profile.sessions_dir(),
+            artifacts_dir: profile.artifacts_dir(),
+            temp_dir: std::env::temp_dir(),
+            scratch_dir: profile.scratch_dir(),
+            credentials: None,
+            pairing: None,
+            artifact_grace: DEFAULT_ARTIFACT_GRACE,
+            sandbox_max_age: DEFAULT_SANDBOX_MAX_AGE,
+            scratch_ttl: DEFAULT_SCRATCH_TTL,
+        }
+    }
+
+    /// 同时清理过期凭证喵
+    pub fn with_credentials(mut self, store: CredentialStore) -> Self {
+        self.credentials = Some(store);
+        self
+    }
+
+    /// 同时清理过期的设备配对申请喵
+    pub fn with_pairing(mut self, codes: DeviceCodes) -> Self {
+        self.pairing = Some(codes);
+        self
+    }
+
+    /// 指定沙箱临时目录所在位置喵
+    pub fn with_temp_dir(mut self, dir: PathBuf) -> Self {
+        self.temp_dir = dir;
+        self
+    }
+
+    /// 调整产物宽限期与临时目录保留时间喵
+    pub fn with_ages(mut self, artifact_grace: Duration, sandbox_max_age: Duration) -> Self {
+        self.artifact_grace = artifact_grace;
+        self.sandbox_max_age = sandbox_max_age;
+        self
+    }
+
+    /// 调整会话临时工作区的 TTL喵
+    pub fn with_scratch_ttl(mut self, ttl: Duration) -> Self {
+        self.scratch_ttl = ttl;
+        self
+    }
+
+    /// 执行一次完整维护喵
+    pub async fn run(&self) -> Result<MaintenanceReport> {
+        let now = SystemTime::now();
+        let mut report = MaintenanceReport {
+            artifacts: self.collect_artifacts(now)?,
+            sandbox_dirs: self.collect_sandbox_dirs(now),
+            scratch_dirs: self.collect_scratch_dirs(now),
+            ..Default::default()
+        };
+        if let Some(store) = &self.credentials {
+            let (removed, bytes) = store.purge_expired(chrono::Utc::now()).await?;
+            report.credentials = CleanupStats { removed, bytes };
+        }
+        if let Some(pairing) = &self.pairing {
+            report.pairing_codes = pairing.purge_expired()?;
+        }
+        Ok(report)
+    }
+
+    /// 删除没有被任何会话引用的产物喵
+    fn collect_artifacts(&self, now: SystemTime) -> Result<CleanupStats> {
+        let mut stats = CleanupStats::default();
+        if !self.artifacts_dir.is_dir() {
+            return Ok(stats);
+        }
+
+        let mut sessions = Vec::new();
+        for path in walk_files(&self.sessions_dir) {
+            match std::fs::read(&path) {
+                Ok(bytes) => sessions.push(String::from_utf8_lossy(&bytes).into_owned()),
+                Err(e) => {
+                    // 读不到会话就无法判断引用关系，宁可不删喵
+                    return Err(format!("无法读取会话 {}: {}", path.display(), e).into());
+                }
+            }
+        }
+
+        // 加密的会话内容看不到引用关系，宁可不删喵
+        if sessions.iter().any(|s| s.contains(r#""encrypted":true"#)) {
+            warn!("存在加密的会话内容，跳过产物清理喵");
+            return Ok(stats);
+        }
+
+        for path in walk_files(&self.artifacts_dir) {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !older_than(&path, now, self.artifact_grace)
+                || sessions.iter().any(|s| s.contains(name))
+            {
+                continue;
+            }
+            let size = disk_usage(&path);
+            match std::fs::remove_file(&path) {
+                Ok(()) => stats.add(size),
+                Err(e) => warn!("删除产物 {} 失败喵: {}", path.display(), e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// 删除崩溃遗留的沙箱临时目录喵
+    fn collect_sandbox_dirs(&self, now: SystemTime) -> CleanupStats {
+        let mut stats = CleanupStats::default();
+        let Ok(entries) = std::fs::read_dir(&self.temp_dir) else {
+            return stats;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            let is_sandbox = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(INCOGNITO_DIR_PREFIX));
+            if !is_sandbox || !path.is_dir() || !older_than(&path, now, self.sandbox_max_age) {
+                continue;
+            }
+            let size = disk_usage(&path);
+            match std::fs::remove_dir_all(&path) {
+                Ok(()) => stats.add(size),
+                Err(e) => warn!("删除临时目录 {} 失败喵: {}", path.display(), e),
+            }
+        }
+        stats
+    }
+
+    /// 删除超过 TTL 未使用的会话临时工作区喵（目录及其中所有文件都未被修改）
+    fn collect_scratch_dirs(&self, now: SystemTime) -> CleanupStats {
+        let mut stats = CleanupStats::default();
+        let Ok(entries) = std::fs::read_dir(&self.scratch_dir) else {
+            return stats;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if !path.is_dir()
+                || !older_than(&path, now, self.scratch_ttl)
+                || !walk_files(&path).iter().all(|f| older_than(f, now, self.scratch_ttl))
+            {
+                continue;
+            }
+            let size = disk_usage(&path);
+            match std::fs::remove_dir_all(&path) {
+                Ok(()) => stats.add(size),
+                Err(e) => warn!("删除会话临时目录 {} 失败喵: {}", path.display(), e),
+            }
+        }
+        stats
+    }
+}
+
+/// 递归列出目录下所有普通文件喵（不跟随符号链接）
+fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => pending.push(entry.path()),
+                Ok(t) if t.is_file() => files.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+    files
+}
+
+/// 文件或目录占用的字节数喵
+fn disk_usage(path: &Path) -> u64 {
+    if path.is_dir() {
+        walk_files(path)
+            .iter()
+            .filter_map(|f| f.metadata().ok())
+            .map(|m| m.len())
+            .sum()
+    } else {
+        path.metadata().map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+/// 最后修改时间是否早于 `now - age` 喵
+fn older_than(path: &Path, now: SystemTime, age: Duration) -> bool {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|elapsed| elapsed >= age)
+}
+
+/// 把字节数格式化为人类可读的大小喵
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{master_crypto, TokenInfo};
+
+    fn token(expires_in_hours: i64, refresh: bool) -> TokenInfo {
+        TokenInfo {
+            access_token: "access".to_string(),
+            refresh_token: refresh.then(|| "refresh".to_string()),
+            token_type: "Bearer".to_string(),
+            expires_at: chrono::Utc::now() + chrono::Duration::hours(expires_in_hours),
+            scopes: vec![],
+            user_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collects_orphans_and_expired_credentials() {
+        let base = tempfile::tempdir().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(base.path(), None).unwrap();
+        profile.create().unwrap();
+        std::fs::create_dir_all(profile.artifacts_dir()).unwrap();
+
+        std::fs::write(profile.sessions_dir().join("s1.json"), r#"{"file":"chart.png"}"#).unwrap();
+        std::fs::write(profile.artifacts_dir().join("chart.png"), b"keep").unwrap();
+        std::fs::write(profile.artifacts_dir().join("orphan.txt"), b"12345").unwrap();
+
+        let sandbox = temp.path().join(format!("{}crashed", INCOGNITO_DIR_PREFIX));
+        std::fs::create_dir_all(&sandbox).unwrap();
+        std::fs::write(sandbox.join("scratch"), b"abc").unwrap();
+        std::fs::create_dir_all(temp.path().join("unrelated")).unwrap();
+        let scratch = profile.scratch_dir().join("old-conversation");
+        std::fs::create_dir_all(&scratch).unwrap();
+        std::fs::write(scratch.join("draft.md"), b"draft").unwrap();
+
+        let store = CredentialStore::new(profile.credentials_dir(), master_crypto(&profile.root).unwrap()).unwrap();
+        store.save("expired", &token(-1, false)).await.unwrap();
+        store.save("refreshable", &token(-1, true)).await.unwrap();
+        store.save("valid", &token(1, false)).await.unwrap();
+
+        let report = Maintenance::new(&profile)
+            .with_credentials(store.clone())
+            .with_temp_dir(temp.path().to_path_buf())
+            .with_ages(Duration::ZERO, Duration::ZERO)
+            .with_scratch_ttl(Duration::ZERO)
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(report.artifacts, CleanupStats { removed: 1, bytes: 5 });
+        assert_eq!(report.sandbox_dirs, CleanupStats { removed: 1, bytes: 3 });
+        assert_eq!(report.scratch_dirs, CleanupStats { removed: 1, bytes: 5 });
+        assert_eq!(report.credentials.removed, 1);
+        assert_eq!(
+            report.reclaimed_bytes(),
+            5 + 3 + 5 + report.credentials.bytes
+        );
+        assert!(profile.artifacts_dir().join("chart.png").exists());
+        assert!(!sandbox.exists());
+        assert!(temp.path().join("unrelated").exists());
+        assert!(store.load("expired").await.is_none());
+        assert!(store.load("refreshable").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_grace_period_keeps_fresh_files() {
+        let base = tempfile::tempdir().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let profile = WorkspaceProfile::resolve(base.path(), None).unwrap();
+        std::fs::create_dir_all(profile.artifacts_dir()).unwrap();
+        std::fs::write(profile.artifacts_dir().join("fresh.txt"), b"new").unwrap();
+        std::fs::create_dir_all(temp.path().join(format!("{}live", INCOGNITO_DIR_PREFIX))).unwrap();
+        std::fs::create_dir_all(profile.scratch_dir().join("active-conversation")).unwrap();
+
+        let report = Maintenance::new(&profile)
+            .with_temp_dir(temp.path().to_path_buf())
+            .run()
+            .await
+            .unwrap();
+
+        assert!(report.is_empty());
+        assert!(profile.artifacts_dir().join("fresh.txt").exists());
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+    }
+}
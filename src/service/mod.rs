@@ -8,6 +8,12 @@
 //! - 服务状态监控与健康检查喵
 //! - Graceful Shutdown 支持喵
 //! - 服务依赖顺序管理喵
+//! - 安装为 systemd / launchd 系统服务喵
+//! - 后台运行与 PID 文件喵
+//! - 环境诊断（`nekoclaw doctor`）喵
+//! - 配置热重载：订阅 `ConfigWatcher` 推送的配置并通知各服务喵
+//! - cron 定时任务（技能 / 白名单命令 / Agent 消息）喵
+//! - 关闭时在宽限期内排空进行中的 Agent 请求（`ShutdownCoordinator`）喵
 //!
 //! ## 核心组件
 //! - `ServiceManager`: 服务管理器主结构喵
@@ -16,7 +22,7 @@
 //! - `ServiceError`: 服务错误类型喵
 //!
 //! ## 使用示例
-//! ```rust
+//! ```ignore
 //! use nekoclaw::service::{ServiceManager, Service, ServiceState};
 //!
 //! let manager = ServiceManager::new();
@@ -24,20 +30,34 @@
 //! manager.start_all().await;
 //! ```
 
-use crate::channels::discord::DiscordBot;
-use crate::channels::telegram::TelegramBot;
+pub mod daemon;
+pub mod doctor;
+pub mod file_watch;
+pub mod installer;
+pub mod log_level;
+pub mod maintenance;
+pub mod scheduler;
+pub mod shutdown;
+pub mod supervisor;
+pub mod warmup;
+
+pub use daemon::PidFile;
+pub use doctor::{CheckStatus, Doctor};
+pub use file_watch::{FileWatcher, WatchConfig};
+pub use installer::{ServiceAction, ServiceInstaller, ServicePlatform};
+pub use scheduler::{CronSchedule, ScheduleState, ScheduledTask, Scheduler, TaskAction, TaskRun};
+pub use shutdown::{DrainReport, InFlight, ShutdownConfig, ShutdownCoordinator};
+pub use supervisor::{read_health_snapshot, TaskSupervisor};
+pub use warmup::{Warmup, WarmupConfig};
+
 use crate::core::traits::Config;
-use crate::gateway::GatewayServer;
-use crate::memory::MemoryManager;
-use crate::providers::ProviderManager;
-use crate::tools::ToolChain;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 use tokio::signal;
-use tokio::sync::RwLock;
-use tracing::{error, info};
+use tokio::sync::{watch, RwLock};
+use tracing::{info, warn};
 
 /// 服务状态喵
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -119,6 +139,13 @@ pub trait Service: Send + Sync {
 
     /// 设置服务状态喵
     fn set_state(&self, state: ServiceState);
+
+    /// 配置热重载喵
+    ///
+    /// 默认忽略，需要按新配置重建客户端的服务覆盖此方法喵
+    async fn reload(&self, _config: &Config) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 /// 服务管理器主结构喵
@@ -138,6 +165,9 @@ pub struct ServiceManager {
     /// 是否正在关闭喵
     shutting_down: Arc<RwLock<bool>>,
 
+    /// 进行中请求的排空协调器喵
+    coordinator: ShutdownCoordinator,
+
     /// 健康检查间隔喵
     health_check_interval: Duration,
 
@@ -148,6 +178,12 @@ pub struct ServiceManager {
     stop_timeout: Duration,
 }
 
+impl Default for ServiceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ServiceManager {
     /// 创建服务管理器喵
     ///
@@ -161,6 +197,7 @@ impl ServiceManager {
             state: Arc::new(RwLock::new(ServiceState::Stopped)),
             config: Arc::new(RwLock::new(Config::default())),
             shutting_down: Arc::new(RwLock::new(false)),
+            coordinator: ShutdownCoordinator::new(),
             health_check_interval: Duration::from_secs(30),
             start_timeout: Duration::from_secs(60),
             stop_timeout: Duration::from_secs(30),
@@ -179,6 +216,7 @@ impl ServiceManager {
             state: Arc::new(RwLock::new(ServiceState::Stopped)),
             config: Arc::new(RwLock::new(config)),
             shutting_down: Arc::new(RwLock::new(false)),
+            coordinator: ShutdownCoordinator::new(),
             health_check_interval: Duration::from_secs(30),
             start_timeout: Duration::from_secs(60),
             stop_timeout: Duration::from_secs(30),
@@ -303,7 +341,7 @@ impl ServiceManager {
         service
             .start()
             .await
-            .map_err(|e| ServiceError::StartFailed(e))?;
+            .map_err(ServiceError::StartFailed)?;
 
         service.set_state(ServiceState::Running);
         Ok(())
@@ -354,7 +392,7 @@ impl ServiceManager {
         service
             .stop()
             .await
-            .map_err(|e| ServiceError::StopFailed(e))?;
+            .map_err(ServiceError::StopFailed)?;
 
         service.set_state(ServiceState::Stopped);
         Ok(())
@@ -419,29 +457,68 @@ impl ServiceManager {
     /// 启动健康检查循环喵
     ///
     /// ## Arguments
+    /// * `supervisor` - 托管后台任务的监督器喵
     /// * `interval` - 检查间隔喵
     ///
     /// 🔐 PERMISSION: 后台任务喵
-    pub async fn start_health_check(&self, interval: Option<Duration>) {
+    pub fn start_health_check(&self, supervisor: &mut TaskSupervisor, interval: Option<Duration>) {
         let interval = interval.unwrap_or(self.health_check_interval);
         let manager = self.clone();
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(interval);
-            loop {
-                interval.tick().await;
-
+        supervisor.spawn_periodic("service_health_check", interval, move || {
+            let manager = manager.clone();
+            async move {
                 if *manager.shutting_down.read().await {
-                    break;
+                    return Ok(());
                 }
+                manager.health_check().await.map_err(|e| e.to_string())
+            }
+        });
+    }
 
-                if let Err(e) = manager.health_check().await {
-                    log::warn!("Health check failed: {}", e);
+    /// 订阅配置更新喵
+    ///
+    /// 每次收到新配置时替换当前配置，并依次调用各服务的 `reload`；
+    /// 单个服务重载失败只记录日志，不影响其他服务继续运行喵
+    ///
+    /// 🔐 PERMISSION: 后台任务喵
+    pub fn watch_config(&self, supervisor: &mut TaskSupervisor, updates: watch::Receiver<Arc<Config>>) {
+        let manager = self.clone();
+
+        supervisor.spawn("config_reload", move || {
+            let manager = manager.clone();
+            let mut updates = updates.clone();
+            async move {
+                while updates.changed().await.is_ok() {
+                    let config = updates.borrow_and_update().clone();
+                    manager.apply_config(&config).await;
                 }
+                Ok(())
             }
         });
     }
 
+    /// 应用新配置喵
+    ///
+    /// 🔐 PERMISSION: 内部使用喵
+    async fn apply_config(&self, config: &Config) {
+        *self.config.write().await = config.clone();
+        let services: Vec<Arc<dyn Service>> = self.services.read().await.values().cloned().collect();
+        for service in services {
+            match service.reload(config).await {
+                Ok(()) => info!("🔄 服务 '{}' 已应用新配置喵", service.name()),
+                Err(e) => warn!("服务 '{}' 重载配置失败喵: {}", service.name(), e),
+            }
+        }
+    }
+
+    /// 当前配置喵
+    ///
+    /// 🔐 PERMISSION: 公开接口喵
+    pub async fn config(&self) -> Config {
+        self.config.read().await.clone()
+    }
+
     /// 启动 Graceful Shutdown 监听喵
     ///
     /// ## Arguments
@@ -462,21 +539,37 @@ impl ServiceManager {
         }
     }
 
+    /// 进行中请求的排空协调器喵（渠道与 Gateway 处理请求前领取凭证）
+    pub fn shutdown_coordinator(&self) -> ShutdownCoordinator {
+        self.coordinator.clone()
+    }
+
     /// 执行 Graceful Shutdown喵
     ///
+    /// 先停止接收新请求并在 `[shutdown] grace_period_secs` 内等待进行中的请求完成，
+    /// 超时后强制取消，再停止所有服务喵
+    ///
     /// 🔐 PERMISSION: 关闭阶段喵
-    pub async fn shutdown(&self) {
+    pub async fn shutdown(&self) -> DrainReport {
         log::info!("Starting graceful shutdown...");
 
         // 设置关闭标志喵
         *self.shutting_down.write().await = true;
 
+        // 先排空进行中的请求，再停止服务喵
+        let grace = self.config.read().await.shutdown.grace_period();
+        let report = self.coordinator.drain(grace).await;
+        if report.cancelled > 0 {
+            log::warn!("{} in-flight requests cancelled after {:?} grace period", report.cancelled, grace);
+        }
+
         // 停止所有服务喵
         if let Err(e) = self.stop_all().await {
             log::error!("Failed to stop services during shutdown: {}", e);
         }
 
         log::info!("Graceful shutdown complete");
+        report
     }
 
     /// 获取拓扑排序顺序喵
@@ -516,6 +609,7 @@ impl ServiceManager {
             state: Arc::clone(&self.state),
             config: Arc::clone(&self.config),
             shutting_down: Arc::clone(&self.shutting_down),
+            coordinator: self.coordinator.clone(),
             health_check_interval: self.health_check_interval,
             start_timeout: self.start_timeout,
             stop_timeout: self.stop_timeout,
@@ -0,0 +1,407 @@
+//!
+//! # Scheduler
+//!
+//! ⚠️ SAFETY: 守护进程中的定时任务喵
+//!
+//! ## 功能说明
+//! - `schedule.<name>` 用 5 段 cron 表达式（分 时 日 月 周，本地时区）或 `@daily` 等别名定义运行时间喵
+//! - 每个任务执行一种动作：技能（`skill` + `args`）、白名单命令（`command` + `args`）
+//!   或交给 Agent 的一条消息（`prompt`，可选 `agent`）喵
+//! - 守护进程每 30 秒检查一次，上次检查以来到点的任务各运行一次（错过多次也只补跑一次）喵
+//! - 最近一次运行结果写入 `schedule.json`，`nekoclaw schedule list` 展示，`schedule run-now` 立即运行喵
+//!
+//! ```toml
+//! [schedule.nightly_status]
+//! cron = "0 3 * * *"
+//! command = "git"
+//! args = ["status", "--short"]
+//!
+//! [schedule.morning_brief]
+//! cron = "30 8 * * mon-fri"
+//! prompt = "总结昨天的待办喵"
+//! agent = "muse"
+//! ```
+//!
+//! 🔒 SAFETY: 命令与技能仍然经过 `[security.allowlist]` 检查，未配置白名单时拒绝运行喵
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// 最近运行结果文件名喵
+pub const SCHEDULE_STATE_FILE: &str = "schedule.json";
+
+/// 结果中保留的最多字符数喵
+pub const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// 查找下一次运行时间的最远范围（覆盖 2 月 29 日）喵
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 5;
+
+const MONTH_NAMES: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// 定时任务错误喵
+#[derive(Error, Debug)]
+pub enum ScheduleError {
+    #[error("Invalid cron expression '{0}': {1}")]
+    InvalidCron(String, String),
+
+    #[error("Unknown scheduled task: {0}")]
+    UnknownTask(String),
+
+    #[error("Schedule state error: {0}")]
+    State(String),
+}
+
+/// 单个定时任务喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    /// cron 表达式（分 时 日 月 周）或 `@hourly` / `@daily` / `@weekly` / `@monthly` / `@yearly`
+    pub cron: String,
+    /// 是否启用（`schedule run-now` 不受影响）
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub action: TaskAction,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 定时任务的动作喵（按出现的字段区分）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TaskAction {
+    /// 运行技能
+    Skill {
+        skill: String,
+        #[serde(default)]
+        args: serde_json::Map<String, Value>,
+    },
+    /// 运行白名单中的命令
+    Shell {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// 把消息交给 Agent（未指定时使用默认 Agent）
+    Prompt {
+        prompt: String,
+        #[serde(default)]
+        agent: Option<String>,
+    },
+}
+
+impl TaskAction {
+    /// 动作类型喵（指标标签使用）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Skill { .. } => "skill",
+            Self::Shell { .. } => "shell",
+            Self::Prompt { .. } => "prompt",
+        }
+    }
+
+    /// 一行描述喵
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Skill { skill, .. } => format!("skill {}", skill),
+            Self::Shell { command, args } if args.is_empty() => format!("shell {}", command),
+            Self::Shell { command, args } => format!("shell {} {}", command, args.join(" ")),
+            Self::Prompt { prompt, agent } => {
+                let preview: String = prompt.chars().take(40).collect();
+                let ellipsis = if prompt.chars().count() > 40 { "…" } else { "" };
+                match agent {
+                    Some(agent) => format!("prompt → {}: {}{}", agent, preview, ellipsis),
+                    None => format!("prompt: {}{}", preview, ellipsis),
+                }
+            }
+        }
+    }
+}
+
+/// 解析后的 cron 表达式喵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// 日 / 周字段是否为 `*`（都有限制时满足其一即可，与标准 cron 一致）
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// 解析 cron 表达式喵
+    pub fn parse(expr: &str) -> Result<Self, ScheduleError> {
+        let expanded = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let invalid = |reason: String| ScheduleError::InvalidCron(expr.to_string(), reason);
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(format!("expected 5 fields, got {}", fields.len())));
+        };
+        let weekdays = parse_field(weekday, 0, 7, WEEKDAY_NAMES, 0).map_err(&invalid)?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0).map_err(&invalid)?,
+            hours: parse_field(hour, 0, 23, &[], 0).map_err(&invalid)? as u32,
+            days: parse_field(day, 1, 31, &[], 0).map_err(&invalid)? as u32,
+            months: parse_field(month, 1, 12, MONTH_NAMES, 1).map_err(&invalid)? as u16,
+            // 周日可以写作 0 或 7 喵
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// `after` 之后（不含）的下一次运行时间喵（夏令时跳过的时刻不运行）
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(MAX_LOOKAHEAD_DAYS);
+        let mut time = start;
+        while time < limit {
+            let midnight = time.date().and_hms_opt(0, 0, 0)?;
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_date(time.date()) {
+                time = midnight + Duration::days(1);
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                match after.timezone().from_local_datetime(&time).earliest() {
+                    Some(next) => return Some(next),
+                    None => time += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 解析一个字段为位掩码喵（`*`、`a`、`a-b`、`*/n`、`a-b/n`、逗号列表与名称）
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], name_base: u32) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_ascii_lowercase();
+        let parsed = match names.iter().position(|n| *n == lower) {
+            Some(index) => index as u32 + name_base,
+            None => s.parse::<u32>().map_err(|_| format!("invalid value '{}'", s))?,
+        };
+        match (min..=max).contains(&parsed) {
+            true => Ok(parsed),
+            false => Err(format!("value {} out of range {}-{}", parsed, min, max)),
+        }
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("invalid step in '{}'", part)),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("invalid range '{}'", range));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+/// 一次运行的结果喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub ok: bool,
+    /// 输出或错误（最多 `MAX_OUTPUT_CHARS` 个字符）
+    pub output: String,
+}
+
+impl TaskRun {
+    pub fn new(started_at: DateTime<Utc>, duration_ms: u64, result: Result<String, String>) -> Self {
+        let (ok, output) = match result {
+            Ok(output) => (true, output),
+            Err(error) => (false, error),
+        };
+        let output = output.trim();
+        let mut kept: String = output.chars().take(MAX_OUTPUT_CHARS).collect();
+        if kept.len() < output.len() {
+            kept.push('…');
+        }
+        Self {
+            started_at,
+            duration_ms,
+            ok,
+            output: kept,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 每个任务最近一次的运行结果喵（守护进程与 `schedule run-now` 共用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleState {
+    #[serde(default)]
+    pub last_runs: BTreeMap<String, TaskRun>,
+}
+
+impl ScheduleState {
+    /// 读取结果文件喵（不存在时为空）
+    pub fn load(path: &Path) -> Result<Self, ScheduleError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| ScheduleError::State(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ScheduleError::State(e.to_string())),
+        }
+    }
+
+    /// 记录一次运行并写回文件喵（重新读取，避免覆盖其他进程写入的结果）
+    pub fn record(path: &Path, task: &str, run: TaskRun) -> Result<(), ScheduleError> {
+        let mut state = Self::load(path).unwrap_or_default();
+        state.last_runs.insert(task.to_string(), run);
+        let content = serde_json::to_string_pretty(&state).map_err(|e| ScheduleError::State(e.to_string()))?;
+        std::fs::write(path, content).map_err(|e| ScheduleError::State(e.to_string()))
+    }
+}
+
+/// 🔒 SAFETY: 守护进程中的调度器喵
+pub struct Scheduler {
+    /// 上次检查的时间
+    last_tick: Mutex<DateTime<Local>>,
+}
+
+impl Scheduler {
+    /// `started` 之前到点的任务不会补跑喵
+    pub fn new(started: DateTime<Local>) -> Self {
+        Self {
+            last_tick: Mutex::new(started),
+        }
+    }
+
+    /// 上次检查以来（不含上次检查的时刻）到点的已启用任务喵
+    ///
+    /// 无效的 cron 表达式直接跳过，启动时由 `invalid_tasks` 报告
+    pub fn due(&self, tasks: &BTreeMap<String, ScheduledTask>, now: DateTime<Local>) -> Vec<String> {
+        let mut last_tick = self.last_tick.lock().unwrap_or_else(|e| e.into_inner());
+        let since = std::mem::replace(&mut *last_tick, now);
+        tasks
+            .iter()
+            .filter(|(_, task)| task.enabled)
+            .filter(|(_, task)| {
+                CronSchedule::parse(&task.cron)
+                    .ok()
+                    .and_then(|cron| cron.next_after(&since))
+                    .is_some_and(|next| next <= now)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// cron 表达式无效的任务喵
+pub fn invalid_tasks(tasks: &BTreeMap<String, ScheduledTask>) -> Vec<(String, ScheduleError)> {
+    tasks
+        .iter()
+        .filter_map(|(name, task)| CronSchedule::parse(&task.cron).err().map(|e| (name.clone(), e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_next_run_and_task_config() {
+        let weekdays = CronSchedule::parse("30 8 * * mon-fri").unwrap();
+        // 2026-10-16 是周五喵
+        assert_eq!(weekdays.next_after(&at("2026-10-16T08:29:59Z")), Some(at("2026-10-16T08:30:00Z")));
+        assert_eq!(weekdays.next_after(&at("2026-10-16T08:30:00Z")), Some(at("2026-10-19T08:30:00Z")));
+
+        let every_15 = CronSchedule::parse("*/15 9-17 * * *").unwrap();
+        assert_eq!(every_15.next_after(&at("2026-10-16T17:50:00Z")), Some(at("2026-10-17T09:00:00Z")));
+        let leap = CronSchedule::parse("0 0 29 feb *").unwrap();
+        assert_eq!(leap.next_after(&at("2026-10-16T00:00:00Z")), Some(at("2028-02-29T00:00:00Z")));
+        // 日与周都有限制时满足其一即可，周日写作 7 喵
+        let either = CronSchedule::parse("0 12 1 * 7").unwrap();
+        assert_eq!(either.next_after(&at("2026-10-16T00:00:00Z")), Some(at("2026-10-18T12:00:00Z")));
+        assert_eq!(CronSchedule::parse("@daily").unwrap(), CronSchedule::parse("0 0 * * *").unwrap());
+        for bad in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "0 0 * foo *"] {
+            assert!(CronSchedule::parse(bad).is_err(), "{}", bad);
+        }
+
+        let tasks: BTreeMap<String, ScheduledTask> = toml::from_str(
+            r#"
+            [status]
+            cron = "*/5 * * * *"
+            command = "git"
+            args = ["status"]
+
+            [brief]
+            cron = "0 9 * * *"
+            prompt = "总结昨天的待办"
+            agent = "muse"
+
+            [report]
+            cron = "bad"
+            skill = "weekly_report"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(tasks["status"].action.describe(), "shell git status");
+        assert_eq!(tasks["brief"].action.kind(), "prompt");
+        assert!(matches!(&tasks["report"].action, TaskAction::Skill { skill, .. } if skill == "weekly_report"));
+        assert_eq!(invalid_tasks(&tasks).len(), 1);
+
+        let start = Local.with_ymd_and_hms(2026, 10, 16, 8, 58, 30).unwrap();
+        let scheduler = Scheduler::new(start);
+        assert!(scheduler.due(&tasks, start + Duration::seconds(20)).is_empty());
+        assert_eq!(scheduler.due(&tasks, start + Duration::minutes(2)), vec!["brief", "status"]);
+        assert!(scheduler.due(&tasks, start + Duration::minutes(3)).is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//!
+//! # Shutdown Coordinator
+//!
+//! ⚠️ SAFETY: 关闭时排空正在处理的 Agent 请求喵
+//!
+//! ## 功能说明
+//! - 每个 Agent 请求（渠道消息、Gateway 请求）开始前 `try_begin` 领取一个 `InFlight` 凭证喵
+//! - 收到停止信号后 `drain`：不再发放新凭证（新请求被拒绝），等待已有请求在宽限期内完成喵
+//! - 超过宽限期仍未完成的请求被强制取消（`InFlight::run` 中的 future 被 drop），
+//!   凭证随之释放，之后再停止后台任务并刷新遥测喵
+//!
+//! ```toml
+//! [shutdown]
+//! grace_period_secs = 30
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// 强制取消后等待凭证释放的时间喵
+const CANCEL_CLEANUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn default_grace_period_secs() -> u64 {
+    30
+}
+
+/// 关闭配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// 等待进行中请求完成的宽限期（秒），超时后强制取消
+    #[serde(default = "default_grace_period_secs")]
+    pub grace_period_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_secs: default_grace_period_secs(),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn grace_period(&self) -> Duration {
+        Duration::from_secs(self.grace_period_secs)
+    }
+}
+
+/// 一次排空的结果喵
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrainReport {
+    /// 开始排空时进行中的请求数
+    pub in_flight: usize,
+    /// 超过宽限期被强制取消的请求数
+    pub cancelled: usize,
+    pub elapsed: Duration,
+}
+
+impl DrainReport {
+    /// 在宽限期内正常完成的请求数喵
+    pub fn completed(&self) -> usize {
+        self.in_flight.saturating_sub(self.cancelled)
+    }
+}
+
+struct Inner {
+    draining: AtomicBool,
+    in_flight: watch::Sender<usize>,
+    cancel: watch::Sender<bool>,
+}
+
+/// 🔒 SAFETY: 关闭协调器喵（可廉价克隆，渠道 / Gateway / 守护进程共享同一个）
+#[derive(Clone)]
+pub struct ShutdownCoordinator {
+    inner: Arc<Inner>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ShutdownCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShutdownCoordinator")
+            .field("draining", &self.is_draining())
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                draining: AtomicBool::new(false),
+                in_flight: watch::channel(0).0,
+                cancel: watch::channel(false).0,
+            }),
+        }
+    }
+
+    /// 🔒 SAFETY: 领取一个进行中凭证喵（正在关闭时返回 None，调用方应拒绝新请求）
+    pub fn try_begin(&self) -> Option<InFlight> {
+        // 先计数再检查，保证 `drain` 看到的计数不会漏掉刚开始的请求喵
+        self.inner.in_flight.send_modify(|n| *n += 1);
+        let guard = InFlight {
+            inner: self.inner.clone(),
+        };
+        match self.is_draining() {
+            true => None,
+            false => Some(guard),
+        }
+    }
+
+    /// 是否已开始关闭喵
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// 进行中的请求数喵
+    pub fn in_flight(&self) -> usize {
+        *self.inner.in_flight.borrow()
+    }
+
+    /// 🔒 SAFETY: 停止接收新请求，等待进行中的请求完成，超过宽限期后强制取消喵
+    pub async fn drain(&self, grace: Duration) -> DrainReport {
+        let started = Instant::now();
+        self.inner.draining.store(true, Ordering::SeqCst);
+        let in_flight = self.in_flight();
+        let mut count = self.inner.in_flight.subscribe();
+
+        let drained = tokio::time::timeout(grace, count.wait_for(|n| *n == 0)).await.is_ok();
+        let cancelled = match drained {
+            true => 0,
+            false => {
+                let remaining = self.in_flight();
+                self.inner.cancel.send_replace(true);
+                // 被取消的 future 在各自任务中 drop，稍等凭证释放喵
+                let _ = tokio::time::timeout(CANCEL_CLEANUP_TIMEOUT, count.wait_for(|n| *n == 0)).await;
+                remaining
+            }
+        };
+        DrainReport {
+            in_flight: in_flight.max(cancelled),
+            cancelled,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 进行中请求的凭证喵（drop 时计数减一）
+pub struct InFlight {
+    inner: Arc<Inner>,
+}
+
+impl InFlight {
+    /// 🔒 SAFETY: 运行一个请求喵；宽限期结束后被强制取消时返回 None
+    pub async fn run<F: Future>(self, work: F) -> Option<F::Output> {
+        let mut cancel = self.inner.cancel.subscribe();
+        tokio::select! {
+            output = work => Some(output),
+            _ = cancel.wait_for(|cancelled| *cancelled) => None,
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.inner.in_flight.send_modify(|n| *n -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_then_cancels() {
+        let coordinator = ShutdownCoordinator::new();
+
+        // 宽限期内完成的请求不会被取消喵
+        let quick = coordinator.try_begin().unwrap();
+        let quick = tokio::spawn(quick.run(tokio::time::sleep(Duration::from_millis(20))));
+        let slow = coordinator.try_begin().unwrap();
+        let slow = tokio::spawn(slow.run(tokio::time::sleep(Duration::from_secs(60))));
+        assert_eq!(coordinator.in_flight(), 2);
+
+        let report = coordinator.drain(Duration::from_millis(200)).await;
+        assert_eq!((report.in_flight, report.cancelled, report.completed()), (2, 1, 1));
+        assert_eq!(quick.await.unwrap(), Some(()));
+        assert_eq!(slow.await.unwrap(), None);
+        assert_eq!(coordinator.in_flight(), 0);
+
+        // 关闭后不再接收新请求喵
+        assert!(coordinator.is_draining());
+        assert!(coordinator.try_begin().is_none());
+        assert_eq!(coordinator.in_flight(), 0);
+        let report = coordinator.drain(Duration::from_millis(10)).await;
+        assert_eq!((report.in_flight, report.cancelled), (0, 0));
+    }
+}
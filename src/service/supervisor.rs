@@ -0,0 +1,279 @@
+//!
+//! # Background Task Supervisor
+//!
+//! ⚠️ SAFETY: 统一托管所有后台循环任务喵
+//!
+//! ## 功能说明
+//! - 所有后台任务由 `TaskSupervisor` 持有（JoinSet），不再 detached spawn 喵
+//! - 关闭时广播取消信号，超时后强制 abort 喵
+//! - 任务返回错误或 panic 时按指数退避自动重启喵
+//! - 任务健康状态写入快照文件，供 `status --verbose` 展示喵
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// 首次重启延迟喵
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+/// 最大重启延迟喵
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// 任务运行状态喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// 运行中喵
+    Running,
+    /// 失败后等待重启喵
+    Restarting,
+    /// 正常结束或已取消喵
+    Stopped,
+}
+
+/// 单个任务的健康信息喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskHealth {
+    pub state: TaskState,
+    /// 重启次数喵
+    pub restarts: u32,
+    /// 最近一次失败原因喵
+    pub last_error: Option<String>,
+    /// 本轮启动时间喵
+    pub started_at: DateTime<Utc>,
+}
+
+type HealthMap = Arc<Mutex<BTreeMap<String, TaskHealth>>>;
+
+/// 后台任务监督器喵
+///
+/// 🔐 SAFETY: 持有全部任务句柄，保证关闭时不会遗留孤儿任务喵
+pub struct TaskSupervisor {
+    tasks: JoinSet<()>,
+    shutdown_tx: watch::Sender<bool>,
+    health: HealthMap,
+    health_file: Option<PathBuf>,
+}
+
+impl Default for TaskSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskSupervisor {
+    /// 创建监督器喵
+    pub fn new() -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            tasks: JoinSet::new(),
+            shutdown_tx,
+            health: Arc::new(Mutex::new(BTreeMap::new())),
+            health_file: None,
+        }
+    }
+
+    /// 状态变化时把健康快照写入文件喵
+    pub fn with_health_file(mut self, path: PathBuf) -> Self {
+        self.health_file = Some(path);
+        self
+    }
+
+    /// 托管一个任务喵
+    ///
+    /// `factory` 每次（重新）启动时调用一次；返回 Ok 视为正常结束，
+    /// 返回 Err 或 panic 时按退避重启喵
+    pub fn spawn<F, Fut>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let name = name.to_string();
+        let health = self.health.clone();
+        let health_file = self.health_file.clone();
+        let mut shutdown = self.shutdown_tx.subscribe();
+
+        self.tasks.spawn(async move {
+            let update = |state: TaskState, error: Option<String>| {
+                let mut map = health.lock().unwrap_or_else(|e| e.into_inner());
+                let entry = map.entry(name.clone()).or_insert(TaskHealth {
+                    state,
+                    restarts: 0,
+                    last_error: None,
+                    started_at: Utc::now(),
+                });
+                if state == TaskState::Running && entry.state == TaskState::Restarting {
+                    entry.restarts += 1;
+                    entry.started_at = Utc::now();
+                }
+                entry.state = state;
+                if error.is_some() {
+                    entry.last_error = error;
+                }
+                if let Some(path) = &health_file {
+                    write_snapshot(path, &map);
+                }
+            };
+
+            let mut delay = INITIAL_RESTART_DELAY;
+            loop {
+                update(TaskState::Running, None);
+                // 在独立任务中运行，panic 不会波及监督循环喵
+                let mut run = tokio::spawn(factory());
+                let outcome = tokio::select! {
+                    result = &mut run => result,
+                    _ = shutdown.wait_for(|stop| *stop) => {
+                        run.abort();
+                        update(TaskState::Stopped, None);
+                        return;
+                    }
+                };
+
+                let error = match outcome {
+                    Ok(Ok(())) => {
+                        update(TaskState::Stopped, None);
+                        return;
+                    }
+                    Ok(Err(e)) => e,
+                    Err(e) if e.is_panic() => format!("panicked: {}", e),
+                    Err(e) => e.to_string(),
+                };
+                warn!("后台任务 {} 失败，{:?} 后重启喵: {}", name, delay, error);
+                update(TaskState::Restarting, Some(error));
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.wait_for(|stop| *stop) => {
+                        update(TaskState::Stopped, None);
+                        return;
+                    }
+                }
+                delay = (delay * 2).min(MAX_RESTART_DELAY);
+            }
+        });
+    }
+
+    /// 托管一个定时循环喵
+    ///
+    /// 单次执行的错误只记录日志，不会中断循环喵
+    pub fn spawn_periodic<F, Fut>(&mut self, name: &str, interval: Duration, tick: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let tick = Arc::new(tick);
+        let task_name = name.to_string();
+        self.spawn(name, move || {
+            let tick = tick.clone();
+            let name = task_name.clone();
+            async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    if let Err(e) = tick().await {
+                        warn!("后台任务 {} 执行失败喵: {}", name, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 当前任务健康状态喵
+    #[cfg(test)]
+    pub fn health(&self) -> BTreeMap<String, TaskHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 取消所有任务并等待退出，超时后强制 abort 喵
+    pub async fn shutdown(mut self, timeout: Duration) {
+        let _ = self.shutdown_tx.send(true);
+        let drained = tokio::time::timeout(timeout, async {
+            while self.tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!("部分后台任务未能在 {:?} 内退出，强制终止喵", timeout);
+            self.tasks.shutdown().await;
+        }
+        info!("✅ 后台任务已全部停止喵");
+    }
+}
+
+/// 写入健康快照喵
+fn write_snapshot(path: &Path, health: &BTreeMap<String, TaskHealth>) {
+    let result = serde_json::to_string_pretty(health)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("写入任务健康快照失败喵: {}", e);
+    }
+}
+
+/// 读取健康快照（`status --verbose`）喵
+pub fn read_health_snapshot(path: &Path) -> Option<BTreeMap<String, TaskHealth>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_task_is_restarted() {
+        let mut supervisor = TaskSupervisor::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("flaky", move || {
+            let counter = counter.clone();
+            async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err("boom".to_string()),
+                    1 => panic!("kaboom"),
+                    _ => Ok(()),
+                }
+            }
+        });
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let health = supervisor.health();
+        let flaky = &health["flaky"];
+        assert_eq!(flaky.state, TaskState::Stopped);
+        assert_eq!(flaky.restarts, 2);
+        assert!(flaky.last_error.as_deref().unwrap().contains("panicked"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_cancels_periodic_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut supervisor = TaskSupervisor::new().with_health_file(path.clone());
+        let ticks = Arc::new(AtomicU32::new(0));
+        let counter = ticks.clone();
+        supervisor.spawn_periodic("ticker", Duration::from_secs(1), move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        supervisor.shutdown(Duration::from_secs(1)).await;
+        let seen = ticks.load(Ordering::SeqCst);
+        assert!(seen >= 2);
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), seen);
+        let snapshot = read_health_snapshot(&path).unwrap();
+        assert_eq!(snapshot["ticker"].state, TaskState::Stopped);
+    }
+}
@@ -0,0 +1,229 @@
+//!
+//! # Startup Warm-up
+//!
+//! ⚠️ SAFETY: Daemon 启动后的预热阶段，降低第一次请求的延迟喵
+//!
+//! ## 功能说明
+//! - 预先解析 provider 域名（DNS）喵
+//! - 与 provider 建立 TLS 连接，留在连接池里供第一条消息复用喵
+//! - 打开记忆数据库（建表 / 迁移 / 密钥环）喵
+//! - 加载 Skills 喵
+//! - 可选：调用不计费的 `GET /models` 确认凭证可用喵
+//!
+//! 各步骤并发执行，失败只记录日志，不影响 daemon 运行喵
+//!
+//! ```toml
+//! [warmup]
+//! enabled = true
+//! ping = false
+//! timeout_secs = 10
+//! ```
+
+use crate::core::traits::MemorySettings;
+use crate::memory::MemoryFactory;
+use crate::providers::OpenAIClient;
+use crate::skills::SkillsManager;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+fn default_enabled() -> bool {
+    true
+}
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// 🔒 SAFETY: 预热配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 建立连接后再调用 `GET /models` 确认凭证（不消耗 token）
+    #[serde(default)]
+    pub ping: bool,
+    /// 单个步骤的超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            ping: false,
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+/// 单个预热步骤的结果喵
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WarmupStep {
+    pub name: String,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 🔒 SAFETY: 启动预热喵
+pub struct Warmup {
+    config: WarmupConfig,
+    clients: Vec<(String, OpenAIClient)>,
+    memory: Option<(PathBuf, MemorySettings)>,
+    skills_dir: Option<PathBuf>,
+}
+
+impl Warmup {
+    pub fn new(config: WarmupConfig) -> Self {
+        Self {
+            config,
+            clients: Vec::new(),
+            memory: None,
+            skills_dir: None,
+        }
+    }
+
+    /// 预热某个 provider 客户端喵（传入之后实际使用的客户端，连接池才能复用）
+    pub fn with_client(mut self, name: &str, client: OpenAIClient) -> Self {
+        self.clients.push((name.to_string(), client));
+        self
+    }
+
+    /// 打开记忆数据库喵
+    pub fn with_memory(mut self, path: PathBuf, settings: MemorySettings) -> Self {
+        self.memory = Some((path, settings));
+        self
+    }
+
+    /// 加载 Skills 目录喵
+    pub fn with_skills_dir(mut self, dir: PathBuf) -> Self {
+        self.skills_dir = Some(dir);
+        self
+    }
+
+    /// 🔒 SAFETY: 并发执行所有预热步骤喵
+    ///
+    /// ## Returns
+    /// 每个步骤的耗时与错误（不会中断 daemon）
+    pub async fn run(&self) -> Vec<WarmupStep> {
+        let started = Instant::now();
+        let mut steps: Vec<(String, BoxFuture<'_, Result<(), String>>)> = Vec::new();
+
+        for (name, client) in &self.clients {
+            let ping = self.config.ping;
+            steps.push((
+                format!("provider:{}", name),
+                async move {
+                    resolve_host(client.base_url()).await?;
+                    client.warm_up(ping).await.map_err(|e| e.to_string())
+                }
+                .boxed(),
+            ));
+        }
+        if let Some((path, settings)) = self.memory.clone() {
+            steps.push((
+                "memory".to_string(),
+                blocking(move || {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                    }
+                    MemoryFactory::open_sqlite(&path.to_string_lossy(), &settings)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }),
+            ));
+        }
+        if let Some(dir) = self.skills_dir.clone().filter(|d| d.is_dir()) {
+            steps.push((
+                "skills".to_string(),
+                blocking(move || SkillsManager::new(dir).load_all().map_err(|e| e.to_string())),
+            ));
+        }
+
+        let timeout = Duration::from_secs(self.config.timeout_secs.max(1));
+        let results = futures::future::join_all(steps.into_iter().map(|(name, step)| async move {
+            let step_started = Instant::now();
+            let error = match tokio::time::timeout(timeout, step).await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e),
+                Err(_) => Some(format!("timed out after {:?}", timeout)),
+            };
+            WarmupStep {
+                name,
+                elapsed_ms: step_started.elapsed().as_millis() as u64,
+                error,
+            }
+        }))
+        .await;
+
+        for step in &results {
+            match &step.error {
+                Some(e) => warn!("☕ 预热 {} 失败喵（{}ms）: {}", step.name, step.elapsed_ms, e),
+                None => info!("☕ 预热 {} 完成喵（{}ms）", step.name, step.elapsed_ms),
+            }
+        }
+        info!(
+            "☕ 预热结束喵：{} 个步骤，{} 个失败，共 {}ms",
+            results.len(),
+            results.iter().filter(|s| s.error.is_some()).count(),
+            started.elapsed().as_millis()
+        );
+        results
+    }
+}
+
+/// 预先解析 URL 中的主机名喵
+async fn resolve_host(url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    let host = url.host_str().ok_or_else(|| format!("{} has no host", url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let mut addrs = tokio::net::lookup_host((host, port)).await.map_err(|e| e.to_string())?;
+    match addrs.next() {
+        Some(_) => Ok(()),
+        None => Err(format!("{} resolved to no addresses", host)),
+    }
+}
+
+/// 在阻塞线程池中执行同步步骤喵
+fn blocking<F>(f: F) -> BoxFuture<'static, Result<(), String>>
+where
+    F: FnOnce() -> Result<(), String> + Send + 'static,
+{
+    async move { tokio::task::spawn_blocking(f).await.map_err(|e| e.to_string())? }.boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::OpenAIConfig;
+
+    #[tokio::test]
+    async fn test_warmup_reports_each_step() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("skills")).unwrap();
+        // 没有任何服务监听的端口：DNS 成功，建立连接失败喵
+        let client = OpenAIClient::new(OpenAIConfig {
+            api_key: String::new(),
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            timeout: 2,
+            max_retries: 0,
+        });
+
+        let steps = Warmup::new(WarmupConfig::default())
+            .with_client("local", client)
+            .with_memory(dir.path().join("data/memory.db"), MemorySettings::default())
+            .with_skills_dir(dir.path().join("skills"))
+            .run()
+            .await;
+
+        let names: Vec<&str> = steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["provider:local", "memory", "skills"]);
+        assert!(steps[0].error.is_some());
+        assert_eq!(steps[1].error, None);
+        assert_eq!(steps[2].error, None);
+        assert!(dir.path().join("data/memory.db").exists());
+    }
+}
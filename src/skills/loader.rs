@@ -101,7 +101,7 @@ impl SkillLoader {
             prompt.push('\n');
         }
         
-        prompt.push_str("调用 @shell 执行技能脚本喵！\n");
+        prompt.push_str("调用 @skill({\"name\": 技能名, \"args\": {参数}}) 执行技能喵！\n");
         prompt
     }
 }
@@ -176,27 +176,25 @@ fn parse_markdown(content: &str) -> Result<(String, String, Option<String>, Vec<
         let line = line.trim();
         
         // 标题
-        if line.starts_with("# ") {
-            name = line[2..].to_string();
+        if let Some(title) = line.strip_prefix("# ") {
+            name = title.to_string();
             section = "description";
             continue;
         }
         
         // 二级标题 - 切换 section
-        if line.starts_with("## ") {
-            section = &line[3..];
+        if let Some(heading) = line.strip_prefix("## ") {
+            section = heading;
             continue;
         }
         
         // 根据当前 section 处理
         match section {
-            "description" => {
-                if !line.is_empty() && !line.starts_with('#') {
-                    if !description.is_empty() {
-                        description.push('\n');
-                    }
-                    description.push_str(line);
+            "description" if !line.is_empty() && !line.starts_with('#') => {
+                if !description.is_empty() {
+                    description.push('\n');
                 }
+                description.push_str(line);
             }
             "执行" | "Execute" | "Execution" => {
                 // 解析命令，格式: `command` 或直接写命令
@@ -206,12 +204,10 @@ fn parse_markdown(content: &str) -> Result<(String, String, Option<String>, Vec<
                     command = Some(line.to_string());
                 }
             }
-            "参数" | "Parameters" | "Params" => {
-                // 解析参数，格式: - `name` (必填/可选): 说明 [默认: value]
-                if line.starts_with("- `") {
-                    if let Some(param) = parse_parameter_line(line) {
-                        parameters.push(param);
-                    }
+            // 解析参数，格式: - `name` (必填/可选): 说明 [默认: value]
+            "参数" | "Parameters" | "Params" if line.starts_with("- `") => {
+                if let Some(param) = parse_parameter_line(line) {
+                    parameters.push(param);
                 }
             }
             _ => {}
@@ -245,7 +241,7 @@ fn parse_parameter_line(line: &str) -> Option<SkillParameter> {
     
     // 提取默认值
     let default = if let Some(start) = description.find("[默认: ") {
-        let rest = &description[start + 5..];
+        let rest = &description[start + "[默认: ".len()..];
         if let Some(end) = rest.find(']') {
             let default_val = rest[..end].to_string();
             description = description[..start].trim().to_string();
@@ -254,7 +250,7 @@ fn parse_parameter_line(line: &str) -> Option<SkillParameter> {
             None
         }
     } else if let Some(start) = description.find("[default: ") {
-        let rest = &description[start + 10..];
+        let rest = &description[start + "[default: ".len()..];
         if let Some(end) = rest.find(']') {
             let default_val = rest[..end].to_string();
             description = description[..start].trim().to_string();
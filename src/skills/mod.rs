@@ -1,20 +1,31 @@
 //! 🔧 Skills System - 动态技能加载喵
 //! 
 //! Skills 是 NekoClaw 的插件系统，通过 SKILL.md 文件定义技能
-//! AI 读取技能描述后，通过工具调用执行脚本
+//! AI 读取技能描述后，通过 `skill` 工具在沙箱中执行脚本
+//!
+//! 守护进程通过 `SharedSkills` 共享技能集合，技能目录变化后重新加载，无需重启
 
 pub mod loader;
+pub mod state;
 
 // 重新导出主要类型
 pub use loader::{Skill, SkillLoader, SkillsConfig, SkillParameter, load_skills};
+pub use state::SkillState;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 /// 🎒 Skills 管理器
 pub struct SkillsManager {
     skills: Vec<Skill>,
+    /// 被禁用的技能（`skills list` 仍然显示）
+    disabled: Vec<Skill>,
     skills_dir: PathBuf,
+    /// 技能内容指纹（重新加载后内容变化时改变）
+    fingerprint: u64,
 }
 
 impl SkillsManager {
@@ -22,13 +33,23 @@ impl SkillsManager {
     pub fn new(skills_dir: PathBuf) -> Self {
         Self {
             skills: Vec::new(),
+            disabled: Vec::new(),
             skills_dir,
+            fingerprint: 0,
         }
     }
     
-    /// 加载所有技能
+    /// 加载所有技能（跳过 `skills_state.json` 中禁用的技能）
     pub fn load_all(&mut self) -> Result<()> {
-        self.skills = loader::load_skills(&self.skills_dir)?;
+        let state = SkillState::load(&self.skills_dir)?;
+        let (disabled, skills) = loader::load_skills(&self.skills_dir)?
+            .into_iter()
+            .partition(|s| state.is_disabled(&s.name));
+        self.skills = skills;
+        self.disabled = disabled;
+        let mut hasher = DefaultHasher::new();
+        self.generate_skills_prompt().hash(&mut hasher);
+        self.fingerprint = hasher.finish();
         log::info!("✅ 加载了 {} 个技能喵", self.skills.len());
         Ok(())
     }
@@ -37,6 +58,42 @@ impl SkillsManager {
     pub fn get_skills(&self) -> &[Skill] {
         &self.skills
     }
+
+    /// 被禁用的技能
+    pub fn disabled_skills(&self) -> &[Skill] {
+        &self.disabled
+    }
+
+    /// 按技能名或目录名查找（返回技能和是否启用）
+    pub fn find(&self, name: &str) -> Option<(&Skill, bool)> {
+        let matches = |s: &&Skill| s.name == name || s.path.file_name().is_some_and(|d| d == name);
+        self.skills
+            .iter()
+            .find(matches)
+            .map(|s| (s, true))
+            .or_else(|| self.disabled.iter().find(matches).map(|s| (s, false)))
+    }
+
+    /// 启用或禁用技能，写入状态文件后重新加载
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        let Some((skill, _)) = self.find(name) else {
+            bail!("找不到技能喵: {}", name);
+        };
+        let skill_name = skill.name.clone();
+        let mut state = SkillState::load(&self.skills_dir)?;
+        if enabled {
+            state.disabled.remove(&skill_name);
+        } else {
+            state.disabled.insert(skill_name);
+        }
+        state.save(&self.skills_dir)?;
+        self.load_all()
+    }
+
+    /// 技能内容指纹（用作提示词缓存的键）
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
     
     /// 生成 AI 可读的技能描述（注入 system prompt）
     pub fn generate_skills_prompt(&self) -> String {
@@ -69,7 +126,95 @@ impl SkillsManager {
             prompt.push('\n');
         }
         
-        prompt.push_str("使用技能时，调用 @shell 执行对应脚本喵！\n");
+        prompt.push_str("使用技能时，调用 @skill({\"name\": 技能名, \"args\": {参数}}) 执行，不要用 @shell 拼命令喵！\n");
         prompt
     }
 }
+
+/// 🔄 可热重载的共享技能集合
+///
+/// 克隆后共享同一份技能，`reload` 之后所有持有者立即看到新技能
+#[derive(Clone)]
+pub struct SharedSkills {
+    inner: Arc<RwLock<SkillsManager>>,
+}
+
+impl SharedSkills {
+    pub fn new(manager: SkillsManager) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(manager)),
+        }
+    }
+
+    /// 当前启用的技能
+    pub fn skills(&self) -> Vec<Skill> {
+        self.inner.read().unwrap().get_skills().to_vec()
+    }
+
+    /// 按名称查找启用的技能
+    pub fn get(&self, name: &str) -> Option<Skill> {
+        self.inner.read().unwrap().get_skills().iter().find(|s| s.name == name).cloned()
+    }
+
+    /// 当前技能的提示词片段
+    pub fn prompt(&self) -> String {
+        self.inner.read().unwrap().generate_skills_prompt()
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.inner.read().unwrap().fingerprint()
+    }
+
+    /// 重新扫描技能目录
+    ///
+    /// ## Returns
+    /// 技能内容是否发生变化
+    pub fn reload(&self) -> Result<bool> {
+        let mut manager = self.inner.write().unwrap();
+        let before = manager.fingerprint();
+        manager.load_all()?;
+        Ok(manager.fingerprint() != before)
+    }
+}
+
+impl std::fmt::Debug for SharedSkills {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let manager = self.inner.read().unwrap();
+        f.debug_struct("SharedSkills")
+            .field("skills_dir", &manager.skills_dir)
+            .field("skills", &manager.skills.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disable_scaffold_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        state::scaffold(dir.path(), "greet").unwrap();
+        assert!(state::scaffold(dir.path(), "greet").is_err());
+        assert!(state::scaffold(dir.path(), "../escape").is_err());
+
+        let mut manager = SkillsManager::new(dir.path().to_path_buf());
+        manager.load_all().unwrap();
+        assert_eq!(manager.get_skills().len(), 1);
+        assert_eq!(manager.get_skills()[0].command.as_deref(), Some("echo {message}"));
+
+        // 按目录名禁用，状态文件不会被当成技能喵
+        manager.set_enabled("greet", false).unwrap();
+        assert!(manager.get_skills().is_empty());
+        assert!(matches!(manager.find("greet"), Some((_, false))));
+        assert!(manager.set_enabled("missing", true).is_err());
+        manager.set_enabled("greet", true).unwrap();
+
+        let shared = SharedSkills::new(manager);
+        assert!(!shared.reload().unwrap());
+        state::scaffold(dir.path(), "farewell").unwrap();
+        assert!(shared.reload().unwrap());
+        assert_eq!(shared.skills().len(), 2);
+        assert!(shared.get("farewell").is_some());
+    }
+}
@@ -0,0 +1,76 @@
+//! 🗂️ Skills State - 启用 / 禁用状态与新技能脚手架喵
+//!
+//! 禁用列表保存在技能目录下的 `skills_state.json`；
+//! 守护进程监视整个技能目录，所以 `skills enable/disable/reload` 写入该文件后会被自动重新加载
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 状态文件名（位于技能目录根部，不会被当作技能加载）
+pub const SKILLS_STATE_FILE: &str = "skills_state.json";
+
+/// 📋 技能启用状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkillState {
+    /// 被禁用的技能名称
+    #[serde(default)]
+    pub disabled: BTreeSet<String>,
+    /// 最近一次修改时间（每次保存都会变化，用来通知守护进程重新加载）
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SkillState {
+    /// 读取状态（文件不存在时全部启用）
+    pub fn load(skills_dir: &Path) -> Result<Self> {
+        let path = skills_dir.join(SKILLS_STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("读取 {:?} 失败喵", path))?;
+        serde_json::from_str(&content).with_context(|| format!("解析 {:?} 失败喵", path))
+    }
+
+    /// 保存状态（同时刷新 `updated_at`）
+    pub fn save(&mut self, skills_dir: &Path) -> Result<()> {
+        fs::create_dir_all(skills_dir).with_context(|| format!("创建 {:?} 失败喵", skills_dir))?;
+        self.updated_at = Some(Utc::now());
+        let path = skills_dir.join(SKILLS_STATE_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?).with_context(|| format!("写入 {:?} 失败喵", path))
+    }
+
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.disabled.contains(name)
+    }
+}
+
+/// 创建新技能的目录和 SKILL.md 模板
+///
+/// ## Returns
+/// 新建的 SKILL.md 路径
+pub fn scaffold(skills_dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("技能名只能包含字母、数字、- 和 _ 喵: {:?}", name);
+    }
+    let dir = skills_dir.join(name);
+    if dir.exists() {
+        bail!("技能目录已存在喵: {:?}", dir);
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("创建 {:?} 失败喵", dir))?;
+
+    let path = dir.join("SKILL.md");
+    let template = format!(
+        "# {name}\n\n\
+        一句话说明这个技能做什么、什么时候用喵。\n\n\
+        ## 执行\n\
+        `echo {{message}}`\n\n\
+        ## 参数\n\
+        - `message` (必填): 要输出的内容\n"
+    );
+    fs::write(&path, template).with_context(|| format!("写入 {:?} 失败喵", path))?;
+    Ok(path)
+}
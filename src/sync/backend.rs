@@ -0,0 +1,164 @@
+//! 同步后端 ☁️
+//!
+//! - `WebDavBackend`: 通过 WebDAV GET/PUT 单个快照文件喵
+//! - `DirectoryBackend`: 共享目录（NFS / Syncthing / 直连对端挂载）喵
+
+use super::SyncSnapshot;
+use crate::core::traits::{Result, SyncConfig};
+use reqwest::{Client, StatusCode};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 远端快照文件名喵
+pub const SNAPSHOT_FILE: &str = "nekoclaw-sync.json";
+
+/// 🔒 SAFETY: 同步后端 trait喵
+#[async_trait::async_trait]
+pub trait SyncBackend: Send + Sync {
+    /// 后端名称喵
+    fn name(&self) -> &str;
+
+    /// 拉取远端快照（不存在时返回 None）喵
+    async fn pull(&self) -> Result<Option<SyncSnapshot>>;
+
+    /// 推送合并后的快照喵
+    async fn push(&self, snapshot: &SyncSnapshot) -> Result<()>;
+}
+
+/// WebDAV 后端喵
+pub struct WebDavBackend {
+    client: Client,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavBackend {
+    pub fn new(
+        collection_url: &str,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            url: format!("{}/{}", collection_url.trim_end_matches('/'), SNAPSHOT_FILE),
+            username,
+            password,
+        })
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.username {
+            Some(user) => request.basic_auth(user, self.password.as_deref()),
+            None => request,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncBackend for WebDavBackend {
+    fn name(&self) -> &str {
+        "webdav"
+    }
+
+    async fn pull(&self) -> Result<Option<SyncSnapshot>> {
+        let response = self.authorize(self.client.get(&self.url)).send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("WebDAV GET failed: {}", response.status()).into());
+        }
+
+        Ok(Some(response.json::<SyncSnapshot>().await?))
+    }
+
+    async fn push(&self, snapshot: &SyncSnapshot) -> Result<()> {
+        let body = serde_json::to_vec(snapshot)?;
+        let response = self
+            .authorize(self.client.put(&self.url))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(format!("WebDAV PUT failed: {}", response.status()).into());
+        }
+        Ok(())
+    }
+}
+
+/// 共享目录后端喵
+pub struct DirectoryBackend {
+    path: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            path: directory.join(SNAPSHOT_FILE),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncBackend for DirectoryBackend {
+    fn name(&self) -> &str {
+        "directory"
+    }
+
+    async fn pull(&self) -> Result<Option<SyncSnapshot>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    async fn push(&self, snapshot: &SyncSnapshot) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // 先写临时文件再 rename，避免对端读到半个文件喵
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(snapshot)?).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+/// 按配置创建后端喵
+pub fn create_backend(config: &SyncConfig) -> Result<Box<dyn SyncBackend>> {
+    match config.backend.as_str() {
+        "webdav" => {
+            let url = config
+                .url
+                .as_deref()
+                .ok_or("sync.url is required for the webdav backend")?;
+            let password = config
+                .password_env
+                .as_deref()
+                .and_then(|var| std::env::var(var).ok());
+            Ok(Box::new(WebDavBackend::new(
+                url,
+                config.username.clone(),
+                password,
+            )?))
+        }
+        "directory" => {
+            let dir = config
+                .directory
+                .clone()
+                .ok_or("sync.directory is required for the directory backend")?;
+            Ok(Box::new(DirectoryBackend::new(dir)))
+        }
+        other => Err(format!("Unknown sync backend: {}", other).into()),
+    }
+}
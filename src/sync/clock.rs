@@ -0,0 +1,94 @@
+//! 向量时钟 ⏱️
+//!
+//! 用于判断两台机器上同一条记录的修改先后关系喵
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 两个向量时钟的比较结果喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockOrdering {
+    /// 完全相同
+    Equal,
+    /// 左侧严格早于右侧
+    Before,
+    /// 左侧严格晚于右侧
+    After,
+    /// 并发修改（需要 last-writer-wins 裁决）
+    Concurrent,
+}
+
+/// 🔒 SAFETY: 向量时钟（节点 ID → 计数器）喵
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+impl VectorClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 本节点计数器 +1喵
+    pub fn increment(&mut self, node: &str) {
+        *self.0.entry(node.to_string()).or_insert(0) += 1;
+    }
+
+    /// 获取某节点的计数器喵
+    pub fn get(&self, node: &str) -> u64 {
+        self.0.get(node).copied().unwrap_or(0)
+    }
+
+    /// 逐项取最大值合并喵
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (node, &count) in &other.0 {
+            let entry = self.0.entry(node.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+
+    /// 比较两个时钟喵
+    pub fn compare(&self, other: &VectorClock) -> ClockOrdering {
+        let mut less = false;
+        let mut greater = false;
+
+        for node in self.0.keys().chain(other.0.keys()) {
+            let (a, b) = (self.get(node), other.get(node));
+            if a < b {
+                less = true;
+            } else if a > b {
+                greater = true;
+            }
+        }
+
+        match (less, greater) {
+            (false, false) => ClockOrdering::Equal,
+            (true, false) => ClockOrdering::Before,
+            (false, true) => ClockOrdering::After,
+            (true, true) => ClockOrdering::Concurrent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_ordering() {
+        let mut a = VectorClock::new();
+        a.increment("laptop");
+        let mut b = a.clone();
+        assert_eq!(a.compare(&b), ClockOrdering::Equal);
+
+        b.increment("server");
+        assert_eq!(a.compare(&b), ClockOrdering::Before);
+        assert_eq!(b.compare(&a), ClockOrdering::After);
+
+        a.increment("laptop");
+        assert_eq!(a.compare(&b), ClockOrdering::Concurrent);
+
+        a.merge(&b);
+        assert_eq!(a.get("laptop"), 2);
+        assert_eq!(a.get("server"), 1);
+        assert_eq!(a.compare(&b), ClockOrdering::After);
+    }
+}
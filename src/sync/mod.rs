@@ -0,0 +1,694 @@
+//! # Sync Module 🔄
+//!
+//! 多台机器之间的状态同步（笔记本 ⇄ 服务器）喵
+//!
+//! ## 功能说明
+//! - 同步记忆条目、会话与配置（可选）喵
+//! - 配置按工作区配置文件原样同步，本机路径与含密钥的字段（provider、渠道凭据等）不离开本机喵
+//! - 每条记录携带向量时钟，严格先后关系直接采用新版本喵
+//! - 并发修改时按 last-writer-wins（updated_at，再按节点 ID）裁决喵
+//! - 删除以墓碑记录传播，防止已删除的记忆被对端复活喵
+//! - 记忆按存储原样同步（加密记忆保持密文，对端需要同一把记忆密钥才能解密）喵
+//! - 会话消息按存储原样同步（加密会话保持密文，对端需要同一把会话密钥才能解密）喵
+//!
+//! ## 使用说明
+//! - 手动: `nekoclaw sync now`
+//! - 定时: daemon 模式下按 `sync.interval_minutes` 自动执行
+
+pub mod backend;
+pub mod clock;
+
+pub use backend::{create_backend, DirectoryBackend, SyncBackend, WebDavBackend};
+pub use clock::{ClockOrdering, VectorClock};
+
+use crate::core::session::StoredMessage;
+use crate::core::traits::{Config, Memory, Result};
+use crate::core::{SessionInfo, SessionStore, WorkspaceProfile};
+use crate::memory::{MemoryFactory, SqliteMemory, StoredMemory};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 记忆记录键前缀喵
+const MEMORY_PREFIX: &str = "memory/";
+/// 会话记录键前缀喵
+const SESSION_PREFIX: &str = "session/";
+/// 配置记录键喵
+const CONFIG_KEY: &str = "config";
+/// 墓碑哈希喵
+const TOMBSTONE: &str = "tombstone";
+/// 不参与同步的配置字段喵（本机路径、同步设置与含密钥的字段）
+const LOCAL_CONFIG_KEYS: [&str; 10] = [
+    "workspace",
+    "sync",
+    "api_key",
+    "providers",
+    "discord_config",
+    "telegram",
+    "email",
+    "webhooks",
+    "outbound_webhooks",
+    "auth",
+];
+
+/// 🔒 SAFETY: 单条同步记录喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    /// 记录内容（None = 已删除的墓碑）
+    pub payload: Option<Value>,
+    /// 向量时钟
+    pub clock: VectorClock,
+    /// 最后修改时间（LWW 裁决依据）
+    pub updated_at: DateTime<Utc>,
+    /// 最后修改的节点
+    pub origin: String,
+}
+
+/// 🔒 SAFETY: 完整同步快照喵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncSnapshot {
+    pub node_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub records: BTreeMap<String, SyncRecord>,
+}
+
+/// 会话记录内容喵
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionPayload {
+    info: SessionInfo,
+    messages: Vec<StoredMessage>,
+}
+
+/// 单次同步报告喵
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// 本地新产生的修改数
+    pub local_changes: usize,
+    /// 从远端应用到本地的修改数
+    pub applied: usize,
+    /// 并发冲突（LWW 裁决）数
+    pub conflicts: usize,
+    /// 同步后的记录总数
+    pub total_records: usize,
+}
+
+/// 本地同步状态（记录上次同步时的时钟与哈希）喵
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SyncState {
+    node_id: String,
+    records: BTreeMap<String, RecordState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordState {
+    clock: VectorClock,
+    hash: String,
+    updated_at: DateTime<Utc>,
+    origin: String,
+}
+
+/// 计算内容哈希喵
+fn payload_hash(payload: &Option<Value>) -> String {
+    match payload {
+        Some(value) => {
+            let mut hasher = Sha256::new();
+            hasher.update(value.to_string().as_bytes());
+            format!("{:x}", hasher.finalize())
+        }
+        None => TOMBSTONE.to_string(),
+    }
+}
+
+/// 裁决同一条记录的两个版本喵
+///
+/// 返回 (胜出版本, 是否为并发冲突)
+pub fn resolve(local: &SyncRecord, remote: &SyncRecord) -> (SyncRecord, bool) {
+    let mut clock = local.clock.clone();
+    clock.merge(&remote.clock);
+
+    let (winner, conflict) = match local.clock.compare(&remote.clock) {
+        ClockOrdering::Equal | ClockOrdering::After => (local, false),
+        ClockOrdering::Before => (remote, false),
+        ClockOrdering::Concurrent => {
+            let local_key = (local.updated_at, &local.origin);
+            let remote_key = (remote.updated_at, &remote.origin);
+            if remote_key > local_key {
+                (remote, true)
+            } else {
+                (local, true)
+            }
+        }
+    };
+
+    (
+        SyncRecord {
+            clock,
+            ..winner.clone()
+        },
+        conflict,
+    )
+}
+
+/// 合并本地与远端记录集喵
+pub fn merge_records(
+    local: &BTreeMap<String, SyncRecord>,
+    remote: &BTreeMap<String, SyncRecord>,
+) -> (BTreeMap<String, SyncRecord>, usize) {
+    let mut merged = local.clone();
+    let mut conflicts = 0;
+
+    for (key, remote_record) in remote {
+        match local.get(key) {
+            Some(local_record) => {
+                let (winner, conflict) = resolve(local_record, remote_record);
+                if conflict {
+                    conflicts += 1;
+                }
+                merged.insert(key.clone(), winner);
+            }
+            None => {
+                merged.insert(key.clone(), remote_record.clone());
+            }
+        }
+    }
+
+    (merged, conflicts)
+}
+
+/// 🔒 SAFETY: 同步引擎喵
+pub struct SyncEngine {
+    backend: Box<dyn SyncBackend>,
+    memory: Arc<SqliteMemory>,
+    state_path: PathBuf,
+    node_id: Option<String>,
+    /// 会话存储，None = 不同步会话
+    sessions: Option<SessionStore>,
+    /// 配置文件所在的工作区，None = 不同步配置
+    config: Option<WorkspaceProfile>,
+}
+
+impl SyncEngine {
+    pub fn new(
+        backend: Box<dyn SyncBackend>,
+        memory: Arc<SqliteMemory>,
+        state_path: PathBuf,
+    ) -> Self {
+        Self {
+            backend,
+            memory,
+            state_path,
+            node_id: None,
+            sessions: None,
+            config: None,
+        }
+    }
+
+    /// 指定节点 ID（默认使用主机名）喵
+    pub fn with_node_id(mut self, node_id: Option<String>) -> Self {
+        self.node_id = node_id;
+        self
+    }
+
+    /// 同时同步会话喵
+    pub fn with_sessions(mut self, sessions: SessionStore) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// 同时同步工作区配置文件喵
+    pub fn with_config(mut self, profile: WorkspaceProfile) -> Self {
+        self.config = Some(profile);
+        self
+    }
+
+    /// 按工作区配置构建引擎喵
+    pub fn from_config(config: &Config, profile: &WorkspaceProfile) -> Result<Self> {
+        let sync = config
+            .sync
+            .as_ref()
+            .filter(|s| s.enabled)
+            .ok_or("sync is not enabled in config")?;
+
+        let backend = create_backend(sync)?;
+        let memory_path = config.memory_db_path();
+        if let Some(parent) = memory_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let settings = config.memory.clone().unwrap_or_default();
+        let memory = Arc::new(MemoryFactory::open_sqlite(&memory_path.to_string_lossy(), &settings)?);
+
+        let mut engine = Self::new(backend, memory, profile.root.join("sync_state.json"))
+            .with_node_id(sync.node_id.clone())
+            .with_sessions(SessionStore::new(profile.sessions_dir()));
+        if sync.include_config {
+            engine = engine.with_config(profile.clone());
+        }
+        Ok(engine)
+    }
+
+    fn load_state(&self) -> SyncState {
+        let mut state: SyncState = std::fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        if let Some(node_id) = &self.node_id {
+            state.node_id = node_id.clone();
+        }
+        if state.node_id.is_empty() {
+            state.node_id = std::env::var("HOSTNAME")
+                .ok()
+                .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        }
+        state
+    }
+
+    fn save_state(&self, state: &SyncState) -> Result<()> {
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.state_path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// 可同步的配置文件内容（剔除 `LOCAL_CONFIG_KEYS`）喵
+    fn config_payload(mut doc: Value) -> Value {
+        if let Some(obj) = doc.as_object_mut() {
+            obj.retain(|key, _| !LOCAL_CONFIG_KEYS.contains(&key.as_str()));
+        }
+        doc
+    }
+
+    /// 收集本地当前内容喵
+    fn collect_local(&self) -> Result<BTreeMap<String, Option<Value>>> {
+        let mut payloads = BTreeMap::new();
+
+        // 按存储原样收集，加密记忆不会以明文离开本机喵
+        for item in self.memory.list_stored()? {
+            payloads.insert(
+                format!("{}{}", MEMORY_PREFIX, item.id),
+                Some(serde_json::to_value(&item)?),
+            );
+        }
+
+        if let Some(sessions) = &self.sessions {
+            for info in sessions.list()? {
+                let payload = SessionPayload {
+                    messages: sessions.load_stored_messages(&info.session_id)?,
+                    info,
+                };
+                payloads.insert(
+                    format!("{}{}", SESSION_PREFIX, payload.info.session_id),
+                    Some(serde_json::to_value(&payload)?),
+                );
+            }
+        }
+
+        if let Some(doc) = self.config.as_ref().map(|p| p.config_document()).transpose()?.flatten() {
+            payloads.insert(CONFIG_KEY.to_string(), Some(Self::config_payload(doc)));
+        }
+
+        Ok(payloads)
+    }
+
+    /// 将胜出版本写回本地喵
+    async fn apply(&self, key: &str, payload: &Option<Value>) -> Result<()> {
+        if let Some(id) = key.strip_prefix(MEMORY_PREFIX) {
+            match payload {
+                Some(value) => {
+                    let item: StoredMemory = serde_json::from_value(value.clone())?;
+                    if item.id != id {
+                        return Err(format!("Sync record {} carries memory {}", key, item.id).into());
+                    }
+                    self.memory.upsert_stored(&item)?;
+                }
+                None => self.memory.forget(id).await?,
+            }
+        } else if let Some(id) = key.strip_prefix(SESSION_PREFIX) {
+            let Some(sessions) = &self.sessions else {
+                return Ok(());
+            };
+            match payload {
+                Some(value) => {
+                    let payload: SessionPayload = serde_json::from_value(value.clone())?;
+                    if payload.info.session_id != id {
+                        return Err(format!("Sync record {} carries session {}", key, payload.info.session_id).into());
+                    }
+                    sessions.import(&payload.info, &payload.messages)?;
+                }
+                None => {
+                    sessions.delete(id)?;
+                }
+            }
+        } else if key == CONFIG_KEY {
+            let (Some(profile), Some(Value::Object(remote))) = (&self.config, payload) else {
+                return Ok(());
+            };
+            // 只改写配置文件本身：本机字段保留，其余字段以远端为准喵
+            profile.update_config(|doc| {
+                let local = doc.as_object_mut().ok_or("配置文件顶层必须是对象喵")?;
+                local.retain(|key, _| LOCAL_CONFIG_KEYS.contains(&key.as_str()));
+                for (key, value) in remote {
+                    if !LOCAL_CONFIG_KEYS.contains(&key.as_str()) {
+                        local.insert(key.clone(), value.clone());
+                    }
+                }
+                Ok(())
+            })?;
+        } else {
+            warn!("Unknown sync record key: {}", key);
+        }
+        Ok(())
+    }
+
+    /// 执行一次完整同步喵
+    pub async fn run_once(&self) -> Result<SyncReport> {
+        let mut state = self.load_state();
+        let node = state.node_id.clone();
+        let now = Utc::now();
+        let mut report = SyncReport::default();
+
+        // 1. 本地内容 → 带时钟的记录
+        let local_payloads = self.collect_local()?;
+        let mut local_records = BTreeMap::new();
+
+        let keys: Vec<String> = local_payloads
+            .keys()
+            .chain(state.records.keys())
+            .cloned()
+            .collect();
+
+        for key in keys {
+            if local_records.contains_key(&key) {
+                continue;
+            }
+            let payload = local_payloads.get(&key).cloned().flatten();
+            let hash = payload_hash(&payload);
+
+            let record = match state.records.get(&key) {
+                Some(prev) if prev.hash == hash => SyncRecord {
+                    payload,
+                    clock: prev.clock.clone(),
+                    updated_at: prev.updated_at,
+                    origin: prev.origin.clone(),
+                },
+                prev => {
+                    let mut clock = prev.map(|p| p.clock.clone()).unwrap_or_default();
+                    clock.increment(&node);
+                    report.local_changes += 1;
+                    SyncRecord {
+                        payload,
+                        clock,
+                        updated_at: now,
+                        origin: node.clone(),
+                    }
+                }
+            };
+            local_records.insert(key, record);
+        }
+
+        // 2. 拉取并合并远端
+        let remote = self.backend.pull().await?;
+        let (merged, conflicts) = match &remote {
+            Some(snapshot) => merge_records(&local_records, &snapshot.records),
+            None => (local_records.clone(), 0),
+        };
+        report.conflicts = conflicts;
+
+        // 3. 应用远端胜出的版本
+        for (key, record) in &merged {
+            let local_payload = local_payloads.get(key).cloned().flatten();
+            if payload_hash(&record.payload) != payload_hash(&local_payload) {
+                self.apply(key, &record.payload).await?;
+                report.applied += 1;
+            }
+        }
+
+        // 4. 推送合并结果
+        let snapshot = SyncSnapshot {
+            node_id: node.clone(),
+            generated_at: now,
+            records: merged.clone(),
+        };
+        self.backend.push(&snapshot).await?;
+
+        // 5. 记录同步状态
+        state.records = merged
+            .iter()
+            .map(|(key, record)| {
+                (
+                    key.clone(),
+                    RecordState {
+                        clock: record.clock.clone(),
+                        hash: payload_hash(&record.payload),
+                        updated_at: record.updated_at,
+                        origin: record.origin.clone(),
+                    },
+                )
+            })
+            .collect();
+        self.save_state(&state)?;
+
+        report.total_records = merged.len();
+        info!(
+            "🔄 Sync via {} done: {} local changes, {} applied, {} conflicts",
+            self.backend.name(),
+            report.local_changes,
+            report.applied,
+            report.conflicts
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::MemoryItem;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn record(payload: Option<Value>, node: &str, ticks: u64, secs: i64) -> SyncRecord {
+        let mut clock = VectorClock::new();
+        for _ in 0..ticks {
+            clock.increment(node);
+        }
+        SyncRecord {
+            payload,
+            clock,
+            updated_at: DateTime::from_timestamp(secs, 0).unwrap(),
+            origin: node.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_concurrent_last_writer_wins() {
+        let laptop = record(Some(json!("laptop edit")), "laptop", 1, 100);
+        let server = record(Some(json!("server edit")), "server", 1, 200);
+
+        let (winner, conflict) = resolve(&laptop, &server);
+        assert!(conflict);
+        assert_eq!(winner.payload, Some(json!("server edit")));
+        assert_eq!(winner.clock.get("laptop"), 1);
+        assert_eq!(winner.clock.get("server"), 1);
+    }
+
+    #[test]
+    fn test_resolve_causal_order_beats_timestamp() {
+        let old = record(Some(json!("v1")), "laptop", 1, 500);
+        let mut newer = old.clone();
+        newer.clock.increment("server");
+        newer.payload = None; // 对端删除
+        newer.updated_at = DateTime::from_timestamp(10, 0).unwrap(); // 时钟漂移
+
+        let (winner, conflict) = resolve(&old, &newer);
+        assert!(!conflict);
+        assert_eq!(winner.payload, None);
+    }
+
+    #[tokio::test]
+    async fn test_directory_sync_between_nodes() {
+        let shared = tempfile::tempdir().unwrap();
+        let laptop_dir = tempfile::tempdir().unwrap();
+        let server_dir = tempfile::tempdir().unwrap();
+
+        let laptop_mem = Arc::new(SqliteMemory::new(":memory:").unwrap());
+        let server_mem = Arc::new(SqliteMemory::new(":memory:").unwrap());
+        laptop_mem
+            .save(MemoryItem {
+                id: "m1".to_string(),
+                content: "remember the nginx fix".to_string(),
+                embedding: None,
+                metadata: None,
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        let laptop = SyncEngine::new(
+            Box::new(DirectoryBackend::new(shared.path().to_path_buf())),
+            laptop_mem.clone(),
+            laptop_dir.path().join("state.json"),
+        )
+        .with_node_id(Some("laptop".to_string()));
+        let server = SyncEngine::new(
+            Box::new(DirectoryBackend::new(shared.path().to_path_buf())),
+            server_mem.clone(),
+            server_dir.path().join("state.json"),
+        )
+        .with_node_id(Some("server".to_string()));
+
+        assert_eq!(laptop.run_once().await.unwrap().local_changes, 1);
+        assert_eq!(server.run_once().await.unwrap().applied, 1);
+        assert_eq!(
+            server_mem.list(None).unwrap()[0].content,
+            "remember the nginx fix"
+        );
+
+        // 服务器删除后，笔记本同步不应复活该记忆
+        server_mem.forget("m1").await.unwrap();
+        server.run_once().await.unwrap();
+        laptop.run_once().await.unwrap();
+        assert!(laptop_mem.list(None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_memory_syncs_as_ciphertext() {
+        use crate::security::CryptoService;
+
+        let shared = tempfile::tempdir().unwrap();
+        let laptop_dir = tempfile::tempdir().unwrap();
+        let server_dir = tempfile::tempdir().unwrap();
+        let cipher = || CryptoService::new(&[9u8; 32]).unwrap();
+        let laptop_mem = Arc::new(SqliteMemory::new(":memory:").unwrap().with_encryption(cipher()));
+        let server_mem = Arc::new(SqliteMemory::new(":memory:").unwrap().with_encryption(cipher()));
+        laptop_mem
+            .save(MemoryItem {
+                id: "m1".to_string(),
+                content: "secret nginx password".to_string(),
+                embedding: None,
+                metadata: Some(json!({"note": "secret metadata"})),
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        let engine = |dir: &tempfile::TempDir, memory: &Arc<SqliteMemory>, node: &str| {
+            SyncEngine::new(
+                Box::new(DirectoryBackend::new(shared.path().to_path_buf())),
+                memory.clone(),
+                dir.path().join("state.json"),
+            )
+            .with_node_id(Some(node.to_string()))
+        };
+        let laptop = engine(&laptop_dir, &laptop_mem, "laptop");
+        let server = engine(&server_dir, &server_mem, "server");
+
+        laptop.run_once().await.unwrap();
+        let remote = DirectoryBackend::new(shared.path().to_path_buf()).pull().await.unwrap().unwrap();
+        let pushed = serde_json::to_string(&remote).unwrap();
+        assert!(!pushed.contains("secret nginx password"));
+        assert!(!pushed.contains("secret metadata"));
+
+        // 持有同一把密钥的对端可以解密喵
+        assert_eq!(server.run_once().await.unwrap().applied, 1);
+        let items = server_mem.list(None).unwrap();
+        assert_eq!(items[0].content, "secret nginx password");
+        assert_eq!(items[0].metadata, Some(json!({"note": "secret metadata"})));
+
+        // 再次同步时密文不变，不产生新的本地修改喵
+        assert_eq!(laptop.run_once().await.unwrap().local_changes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_config_syncs_file_document_without_secrets() {
+        let shared = tempfile::tempdir().unwrap();
+        let write_config = |dir: &Path, model: &str, key: &str| {
+            let mut doc = serde_json::to_value(Config::default()).unwrap();
+            doc["default_model"] = json!(model);
+            doc["providers"] = json!({ "nvidia": { "api_key": key, "base_url": "https://integrate.api.nvidia.com/v1" } });
+            std::fs::write(dir.join("config.json"), serde_json::to_string_pretty(&doc).unwrap()).unwrap();
+        };
+        let laptop_dir = tempfile::tempdir().unwrap();
+        let server_dir = tempfile::tempdir().unwrap();
+        write_config(laptop_dir.path(), "laptop-model", "sk-laptop");
+        write_config(server_dir.path(), "server-model", "sk-server");
+
+        let engine = |dir: &tempfile::TempDir, node: &str| {
+            SyncEngine::new(
+                Box::new(DirectoryBackend::new(shared.path().to_path_buf())),
+                Arc::new(SqliteMemory::new(":memory:").unwrap()),
+                dir.path().join("state.json"),
+            )
+            .with_node_id(Some(node.to_string()))
+            .with_config(WorkspaceProfile::resolve(dir.path(), None).unwrap())
+        };
+        let laptop = engine(&laptop_dir, "laptop");
+        laptop.run_once().await.unwrap();
+        let remote = DirectoryBackend::new(shared.path().to_path_buf()).pull().await.unwrap().unwrap();
+        assert!(!serde_json::to_string(&remote).unwrap().contains("sk-laptop"));
+
+        // 服务器先同步，本机的修改（更晚）覆盖远端；再由笔记本拉取喵
+        let server = engine(&server_dir, "server");
+        server.run_once().await.unwrap();
+        assert_eq!(laptop.run_once().await.unwrap().applied, 1);
+
+        let laptop_doc: Value =
+            serde_json::from_str(&std::fs::read_to_string(laptop_dir.path().join("config.json")).unwrap()).unwrap();
+        assert_eq!(laptop_doc["default_model"], json!("server-model"));
+        assert_eq!(laptop_doc["providers"]["nvidia"]["api_key"], json!("sk-laptop"));
+
+        // 写回后的文件与远端一致，下一轮不会把旧配置推回去喵
+        assert_eq!(laptop.run_once().await.unwrap().local_changes, 0);
+        assert_eq!(server.run_once().await.unwrap().applied, 0);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_sessions_sync_as_ciphertext() {
+        use crate::providers::Message;
+
+        let shared = tempfile::tempdir().unwrap();
+        let laptop_dir = tempfile::tempdir().unwrap();
+        let server_dir = tempfile::tempdir().unwrap();
+        let key = vec![7u8; 32];
+        let laptop_sessions = SessionStore::new(laptop_dir.path().join("sessions")).with_encryption(key.clone());
+        let server_sessions = SessionStore::new(server_dir.path().join("sessions")).with_encryption(key);
+        laptop_sessions
+            .save_as("nginx", "default", &[Message::user("secret nginx password".to_string())])
+            .unwrap();
+
+        let engine = |dir: &tempfile::TempDir, sessions: &SessionStore, node: &str| {
+            SyncEngine::new(
+                Box::new(DirectoryBackend::new(shared.path().to_path_buf())),
+                Arc::new(SqliteMemory::new(":memory:").unwrap()),
+                dir.path().join("state.json"),
+            )
+            .with_node_id(Some(node.to_string()))
+            .with_sessions(sessions.clone())
+        };
+        let laptop = engine(&laptop_dir, &laptop_sessions, "laptop");
+        let server = engine(&server_dir, &server_sessions, "server");
+
+        laptop.run_once().await.unwrap();
+        let remote = DirectoryBackend::new(shared.path().to_path_buf()).pull().await.unwrap().unwrap();
+        assert!(!serde_json::to_string(&remote).unwrap().contains("secret nginx password"));
+
+        assert_eq!(server.run_once().await.unwrap().applied, 1);
+        assert_eq!(server_sessions.load("nginx").unwrap().unwrap().message_count, 1);
+        assert_eq!(server_sessions.load_messages("nginx").unwrap()[0].content, "secret nginx password");
+
+        // 删除以墓碑传播喵
+        assert!(server_sessions.delete("nginx").unwrap());
+        server.run_once().await.unwrap();
+        laptop.run_once().await.unwrap();
+        assert!(laptop_sessions.load("nginx").unwrap().is_none());
+    }
+}
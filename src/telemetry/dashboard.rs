@@ -1,32 +1,95 @@
-/// Dashboard 生成器 📊
-///
-/// @缪斯 的可视化 Dashboard 实现喵
-///
-/// 功能：
-/// - 生成轻量 HTML Dashboard
-/// - 实时显示 Agent 指标
-/// - 工具调用统计与耗时分布
-/// - 系统资源监控（内存、CPU）
-/// - 无需外部依赖，纯静态 HTML + JS
-///
-/// 🔒 SAFETY: 所有输出都是安全的静态 HTML
-///
-/// 实现者: 缪斯 (Muse) 💜
-
+//! Dashboard 生成器 📊
+//!
+//! @缪斯 的可视化 Dashboard 实现喵
+//!
+//! 功能：
+//! - 生成轻量 HTML Dashboard
+//! - 实时显示 Agent 指标
+//! - 工具调用统计与耗时分布
+//! - 系统资源监控（内存、CPU）
+//! - 调用链火焰图（Agent 请求 → 工具执行 → MCP 请求）
+//! - 用户反馈（👍 / 👎）汇总
+//! - 上游 Provider 健康状态（最近一次探测）
+//! - 可选自动刷新：页面脚本轮询 JSON 快照，数据变化时原地替换内容
+//! - 无需外部依赖，纯静态 HTML + JS
+//!
+//! 🔒 SAFETY: 所有输出都是安全的静态 HTML
+//!
+//! 实现者: 缪斯 (Muse) 💜
+
+use crate::providers::health::{LATENCY_METRIC, UP_METRIC};
+use crate::telemetry::{CustomMetric, FeedbackSummary};
 use crate::telemetry::metrics::MetricsCollector;
+use crate::telemetry::tracer::{flame_rows, Span};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 use tracing::debug;
 
+/// 自动刷新设置
+struct AutoRefresh {
+    page_url: String,
+    api_url: String,
+    interval_secs: u64,
+}
+
 /// 🔒 SAFETY: Dashboard 生成器喵
-pub struct DashboardGenerator;
+pub struct DashboardGenerator {
+    refresh: Option<AutoRefresh>,
+}
+
+impl Default for DashboardGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl DashboardGenerator {
     /// 🔒 SAFETY: 创建新的 Dashboard 生成器喵
     pub fn new() -> Self {
-        Self
+        Self { refresh: None }
+    }
+
+    /// 🔒 SAFETY: 页面内嵌自动刷新脚本喵
+    ///
+    /// 每隔 `interval_secs` 秒请求 `api_url` 的快照，与上次不同时重新获取 `page_url` 并替换页面内容
+    pub fn with_auto_refresh(mut self, page_url: &str, api_url: &str, interval_secs: u64) -> Self {
+        self.refresh = Some(AutoRefresh {
+            page_url: page_url.to_string(),
+            api_url: api_url.to_string(),
+            interval_secs: interval_secs.max(1),
+        });
+        self
+    }
+
+    /// 🔒 SAFETY: 生成指标的 JSON 快照喵（自动刷新脚本据此判断数据是否变化）
+    pub fn snapshot(&self, metrics: &MetricsCollector, spans: &[Span]) -> Result<serde_json::Value, String> {
+        let agent_metrics = metrics.get_recent_agent_metrics(20).map_err(|e| e.to_string())?;
+        let tool_metrics = metrics.get_recent_tool_metrics(50).map_err(|e| e.to_string())?;
+        let system_metrics = metrics.get_recent_system_metrics(1).map_err(|e| e.to_string())?;
+        let tool_stats = metrics.get_tool_statistics().map_err(|e| e.to_string())?;
+        let feedback = metrics.get_feedback_summary().map_err(|e| e.to_string())?;
+
+        let tools: Vec<_> = tool_stats
+            .iter()
+            .map(|(name, calls, avg_duration)| {
+                serde_json::json!({ "name": name, "calls": calls, "avg_duration_ms": avg_duration })
+            })
+            .collect();
+        let traces: BTreeSet<&str> = spans.iter().map(|s| s.trace_id.as_str()).collect();
+
+        Ok(serde_json::json!({
+            "generated_at": chrono::Utc::now().to_rfc3339(),
+            "stats": self.calculate_stats(&agent_metrics, &tool_metrics),
+            "tools": tools,
+            "feedback": feedback,
+            "memory_mb": system_metrics.first().map(|m| m.memory_mb),
+            "spans": spans.len(),
+            "traces": traces.len(),
+        }))
     }
 
     /// 🔒 SAFETY: 生成完整的 HTML Dashboard 喵
-    pub fn generate_html(&self, metrics: &MetricsCollector) -> Result<String, String> {
+    pub fn generate_html(&self, metrics: &MetricsCollector, spans: &[Span]) -> Result<String, String> {
         debug!("📊 生成 Dashboard HTML 喵...");
 
         // 获取各类指标数据
@@ -34,12 +97,15 @@ impl DashboardGenerator {
         let tool_metrics = metrics.get_recent_tool_metrics(50).map_err(|e| e.to_string())?;
         let system_metrics = metrics.get_recent_system_metrics(100).map_err(|e| e.to_string())?;
         let tool_stats = metrics.get_tool_statistics().map_err(|e| e.to_string())?;
+        let feedback = metrics.get_feedback_summary().map_err(|e| e.to_string())?;
+        let mut provider_probes = metrics.get_recent_custom_metrics(Some(UP_METRIC), 200).map_err(|e| e.to_string())?;
+        provider_probes.extend(metrics.get_recent_custom_metrics(Some(LATENCY_METRIC), 200).map_err(|e| e.to_string())?);
 
         // 计算统计数据
         let stats = self.calculate_stats(&agent_metrics, &tool_metrics);
 
         // 生成 HTML
-        let html = self.render_html(&agent_metrics, &tool_metrics, &system_metrics, &tool_stats, &stats);
+        let html = self.render_html(&agent_metrics, &tool_metrics, &system_metrics, &tool_stats, &stats, &feedback, &provider_probes, spans);
 
         debug!("✅ Dashboard HTML 生成完成喵！");
 
@@ -97,13 +163,17 @@ impl DashboardGenerator {
     }
 
     /// 🔒 SAFETY: 渲染 HTML 喵
+    #[allow(clippy::too_many_arguments)]
     fn render_html(
         &self,
         agent_metrics: &[crate::telemetry::metrics::AgentMetrics],
-        tool_metrics: &[crate::telemetry::metrics::ToolMetrics],
+        _tool_metrics: &[crate::telemetry::metrics::ToolMetrics],
         system_metrics: &[crate::telemetry::metrics::SystemMetrics],
         tool_stats: &[(String, i64, f64)],
         stats: &DashboardStats,
+        feedback: &BTreeMap<String, FeedbackSummary>,
+        provider_probes: &[CustomMetric],
+        spans: &[Span],
     ) -> String {
         format!(
             r#"<!DOCTYPE html>
@@ -195,6 +265,21 @@ impl DashboardGenerator {
         }}
         .status-success {{ color: #4CAF50; }}
         .status-failed {{ color: #f44336; }}
+        .trace {{ margin-bottom: 16px; }}
+        .trace-title {{ color: #888; font-size: 0.85em; margin-bottom: 4px; }}
+        .flame-row {{ position: relative; height: 22px; margin: 2px 0; }}
+        .flame-bar {{
+            position: absolute;
+            height: 100%;
+            background: rgba(147, 112, 219, 0.6);
+            border-radius: 3px;
+            padding: 0 6px;
+            font-size: 0.8em;
+            line-height: 22px;
+            white-space: nowrap;
+            overflow: hidden;
+        }}
+        .flame-bar.failed {{ background: rgba(244, 67, 54, 0.6); }}
         .refresh-info {{
             text-align: center;
             color: #888;
@@ -251,6 +336,13 @@ impl DashboardGenerator {
                     </div>
                 </div>
             </div>
+
+            <div class="card">
+                <h2>👍 用户反馈</h2>
+                <div class="stat-grid">
+                    {}
+                </div>
+            </div>
         </div>
 
         <div class="grid">
@@ -288,6 +380,29 @@ impl DashboardGenerator {
             </div>
         </div>
 
+        <div class="card">
+            <h2>🩺 Provider 健康</h2>
+            <table class="table">
+                <thead>
+                    <tr>
+                        <th>Provider</th>
+                        <th>状态</th>
+                        <th>延迟</th>
+                        <th>最后探测</th>
+                        <th>错误</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {}
+                </tbody>
+            </table>
+        </div>
+
+        <div class="card">
+            <h2>🔥 调用链（最近的 Trace）</h2>
+            {}
+        </div>
+
         <div class="card">
             <h2>🖥️ 系统资源监控（最近 100 个采样点）</h2>
             <table class="table">
@@ -307,6 +422,7 @@ impl DashboardGenerator {
             最后更新: {} 📚 Generated by 缪斯 (Muse) 💜
         </div>
     </div>
+    {}
 </body>
 </html>"#,
             stats.total_requests,
@@ -323,13 +439,146 @@ impl DashboardGenerator {
             stats.avg_tool_duration.unwrap_or(0.0),
             if stats.failed_tools > 0 { "error" } else { "" },
             stats.failed_tools,
+            self.render_feedback(feedback),
             self.render_tool_stats(tool_stats),
             self.render_agent_metrics(agent_metrics),
+            self.render_provider_health(provider_probes),
+            self.render_traces(spans),
             self.render_system_metrics(system_metrics),
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            self.render_refresh_script()
+        )
+    }
+
+    /// 🔒 SAFETY: 渲染自动刷新脚本喵（未启用时为空）
+    ///
+    /// 快照去掉 `generated_at` 后与上次比较，请求失败时保留当前页面等待下一轮
+    fn render_refresh_script(&self) -> String {
+        let Some(refresh) = &self.refresh else {
+            return String::new();
+        };
+        format!(
+            r#"<script>
+    (function () {{
+        const pageUrl = {};
+        const apiUrl = {};
+        let last = null;
+        async function poll() {{
+            try {{
+                const response = await fetch(apiUrl, {{ cache: "no-store" }});
+                if (!response.ok) return;
+                const snapshot = await response.json();
+                delete snapshot.generated_at;
+                const current = JSON.stringify(snapshot);
+                if (last !== null && current !== last) {{
+                    const page = await fetch(pageUrl, {{ cache: "no-store" }});
+                    if (!page.ok) return;
+                    const doc = new DOMParser().parseFromString(await page.text(), "text/html");
+                    const fresh = doc.querySelector(".container");
+                    if (fresh) document.querySelector(".container").replaceWith(fresh);
+                }}
+                last = current;
+            }} catch (e) {{
+                console.debug("dashboard refresh failed", e);
+            }}
+        }}
+        poll();
+        setInterval(poll, {});
+    }})();
+    </script>"#,
+            serde_json::Value::from(refresh.page_url.as_str()),
+            serde_json::Value::from(refresh.api_url.as_str()),
+            refresh.interval_secs * 1000
         )
     }
 
+    /// 🔒 SAFETY: 渲染用户反馈汇总喵（总计 + 各渠道好评率）
+    fn render_feedback(&self, feedback: &BTreeMap<String, FeedbackSummary>) -> String {
+        let total = feedback.values().fold(FeedbackSummary::default(), |acc, s| FeedbackSummary {
+            up: acc.up + s.up,
+            down: acc.down + s.down,
+        });
+        let item = |label: &str, value: String, class: &str| {
+            format!(
+                r#"<div class="stat-item">
+                        <div class="stat-label">{}</div>
+                        <div class="stat-value {}">{}</div>
+                    </div>"#,
+                escape_html(label),
+                class,
+                value
+            )
+        };
+        let rate = |summary: &FeedbackSummary| {
+            summary
+                .satisfaction()
+                .map(|r| format!("{:.1}%", r * 100.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let mut items = vec![
+            item("👍 好评", total.up.to_string(), "success"),
+            item("👎 差评", total.down.to_string(), if total.down > 0 { "error" } else { "" }),
+            item("好评率", rate(&total), ""),
+        ];
+        items.extend(
+            feedback
+                .iter()
+                .map(|(channel, summary)| item(&format!("{} 好评率", channel), rate(summary), "")),
+        );
+        items.join("")
+    }
+
+    /// 🔒 SAFETY: 渲染 Provider 健康表格喵（每个 provider 取最近一次探测）
+    fn render_provider_health(&self, probes: &[CustomMetric]) -> String {
+        let latest = |name: &str| {
+            let mut latest: BTreeMap<&str, &CustomMetric> = BTreeMap::new();
+            for metric in probes.iter().filter(|m| m.name == name) {
+                let Some(provider) = metric.labels.get("provider") else {
+                    continue;
+                };
+                let entry = latest.entry(provider.as_str()).or_insert(metric);
+                if metric.recorded_at > entry.recorded_at {
+                    *entry = metric;
+                }
+            }
+            latest
+        };
+        let up = latest(UP_METRIC);
+        let latency = latest(LATENCY_METRIC);
+        if up.is_empty() {
+            return String::from("<tr><td colspan=\"5\" style=\"text-align:center;color:#888;\">暂无数据</td></tr>");
+        }
+
+        up.iter()
+            .map(|(provider, metric)| {
+                let (class, status) = match metric.value > 0.0 {
+                    true => ("status-success", "✅ 正常"),
+                    false => ("status-failed", "❌ 不可用"),
+                };
+                format!(
+                    r#"<tr>
+                        <td>{}</td>
+                        <td class="{}">{}</td>
+                        <td>{}</td>
+                        <td>{}</td>
+                        <td>{}</td>
+                    </tr>"#,
+                    escape_html(provider),
+                    class,
+                    status,
+                    latency
+                        .get(provider)
+                        .map(|m| format!("{:.0}ms", m.value))
+                        .unwrap_or_else(|| "-".to_string()),
+                    metric.recorded_at.format("%Y-%m-%d %H:%M:%S"),
+                    escape_html(metric.labels.get("error").map(String::as_str).unwrap_or("")),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
     /// 🔒 SAFETY: 渲染工具统计表格喵
     fn render_tool_stats(&self, tool_stats: &[(String, i64, f64)]) -> String {
         if tool_stats.is_empty() {
@@ -390,6 +639,60 @@ impl DashboardGenerator {
             .join("")
     }
 
+    /// 🔒 SAFETY: 渲染调用链火焰图喵（每个 Trace 一组，按调用树缩进）
+    fn render_traces(&self, spans: &[Span]) -> String {
+        if spans.is_empty() {
+            return String::from("<div style=\"text-align:center;color:#888;\">暂无数据</div>");
+        }
+
+        let mut traces: BTreeMap<&str, Vec<Span>> = BTreeMap::new();
+        for span in spans {
+            traces.entry(span.trace_id.as_str()).or_default().push(span.clone());
+        }
+
+        traces
+            .iter()
+            .take(5)
+            .map(|(trace_id, spans)| {
+                let rows = flame_rows(spans);
+                let total = rows
+                    .iter()
+                    .map(|r| r.offset_ms + r.duration_ms)
+                    .max()
+                    .unwrap_or(0)
+                    .max(1) as f64;
+                let bars = rows
+                    .iter()
+                    .map(|row| {
+                        let links = row
+                            .span
+                            .links
+                            .iter()
+                            .map(|l| format!("{} → {}", l.relation, l.span_id))
+                            .collect::<Vec<_>>()
+                            .join("&#10;");
+                        format!(
+                            r#"<div class="flame-row"><div class="flame-bar{}" style="left:{:.1}%;width:{:.1}%;margin-left:{}px" title="{}">{} ({}ms)</div></div>"#,
+                            if row.span.status == super::tracer::SpanStatus::Failed { " failed" } else { "" },
+                            row.offset_ms as f64 / total * 100.0,
+                            (row.duration_ms as f64 / total * 100.0).max(1.0),
+                            row.depth * 12,
+                            links,
+                            escape_html(&row.span.name),
+                            row.duration_ms
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+                format!(
+                    r#"<div class="trace"><div class="trace-title">trace {}</div>{}</div>"#,
+                    trace_id, bars
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
     /// 🔒 SAFETY: 渲染系统指标表格喵
     fn render_system_metrics(
         &self,
@@ -421,8 +724,16 @@ impl DashboardGenerator {
     }
 }
 
+/// 🔒 SAFETY: 转义 HTML 特殊字符喵
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /// 🔒 SAFETY: Dashboard 统计数据喵
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct DashboardStats {
     total_requests: usize,
     total_tokens: u32,
@@ -460,8 +771,32 @@ mod tests {
         };
 
         // 测试渲染不会崩溃
-        let html = generator.render_html(&[], &[], &[], &[], &stats);
+        let html = generator.render_html(&[], &[], &[], &[], &stats, &BTreeMap::new(), &[], &[]);
         assert!(html.contains("NekoClow Metrics Dashboard"));
         assert!(html.contains("暂无数据"));
     }
+
+    #[test]
+    fn test_provider_health_uses_latest_probe() {
+        let probe = |name: &str, value: f64, minutes_ago: i64, labels: &[(&str, &str)]| CustomMetric {
+            name: name.to_string(),
+            kind: crate::telemetry::MetricKind::Gauge,
+            value,
+            labels: labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            source: "provider_health".to_string(),
+            recorded_at: chrono::Utc::now() - chrono::Duration::minutes(minutes_ago),
+        };
+        let probes = vec![
+            probe(UP_METRIC, 1.0, 0, &[("provider", "nvidia")]),
+            probe(UP_METRIC, 0.0, 5, &[("provider", "nvidia"), ("error", "old <failure>")]),
+            probe(UP_METRIC, 0.0, 0, &[("provider", "openai"), ("error", "HTTP 503")]),
+            probe(LATENCY_METRIC, 230.0, 0, &[("provider", "nvidia")]),
+        ];
+
+        let html = DashboardGenerator::new().render_provider_health(&probes);
+        assert_eq!(html.matches("<tr>").count(), 2);
+        assert!(html.contains("230ms"));
+        assert!(html.contains("HTTP 503"));
+        assert!(!html.contains("old"));
+    }
 }
@@ -0,0 +1,151 @@
+//! 用户反馈 👍👎
+//!
+//! @缪斯 按回复 ID 记录的好评 / 差评喵
+//!
+//! 来源：
+//! - Discord / Telegram 对机器人回复的 👍 / 👎 表情回应
+//! - CLI REPL 的 `/feedback up|down [备注]`
+//! - Gateway `POST /feedback`
+//!
+//! 同一用户对同一回复重复评价时以最后一次为准喵
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 回复 ID 最大长度
+const MAX_RESPONSE_ID_LEN: usize = 128;
+/// 备注最大长度
+const MAX_COMMENT_LEN: usize = 1000;
+
+/// 🔒 SAFETY: 评价喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+impl Rating {
+    /// 表情回应 → 评价喵（其他表情返回 None）
+    pub fn from_emoji(emoji: &str) -> Option<Self> {
+        // 去掉肤色修饰符与变体选择符喵
+        let base: String = emoji
+            .chars()
+            .filter(|c| !matches!(*c as u32, 0x1F3FB..=0x1F3FF | 0xFE0F))
+            .collect();
+        match base.as_str() {
+            "👍" => Some(Self::Up),
+            "👎" => Some(Self::Down),
+            _ => None,
+        }
+    }
+
+    /// 解析文本评价（up / down / +1 / -1 / 👍 / 👎）喵
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "up" | "+1" | "good" => Some(Self::Up),
+            "down" | "-1" | "bad" => Some(Self::Down),
+            other => Self::from_emoji(other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+
+    /// 数值（+1 / -1）喵
+    pub fn value(&self) -> i64 {
+        match self {
+            Self::Up => 1,
+            Self::Down => -1,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 一条反馈喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Feedback {
+    /// 被评价的回复 ID（渠道消息 ID / chatcmpl ID / CLI 回复 ID）
+    pub response_id: String,
+    pub rating: Rating,
+    /// 来源渠道（discord / telegram / cli / gateway）
+    pub channel: String,
+    /// 渠道内的用户 ID（用于按用户清除数据）
+    pub user_id: Option<String>,
+    pub comment: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl Feedback {
+    pub fn new(response_id: &str, rating: Rating, channel: &str) -> Self {
+        Self {
+            response_id: response_id.to_string(),
+            rating,
+            channel: channel.to_string(),
+            user_id: None,
+            comment: None,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    pub fn with_user(mut self, user_id: &str) -> Self {
+        self.user_id = Some(user_id.to_string());
+        self
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        let comment = comment.trim();
+        self.comment = (!comment.is_empty()).then(|| comment.to_string());
+        self
+    }
+
+    /// 🔒 SAFETY: 校验长度喵
+    pub fn validate(&self) -> Result<(), String> {
+        if self.response_id.is_empty() || self.response_id.len() > MAX_RESPONSE_ID_LEN {
+            return Err(format!("response_id 长度必须在 1~{} 之间喵", MAX_RESPONSE_ID_LEN));
+        }
+        if self.comment.as_ref().is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+            return Err(format!("备注不能超过 {} 个字符喵", MAX_COMMENT_LEN));
+        }
+        Ok(())
+    }
+}
+
+/// 🔒 SAFETY: 按渠道汇总的反馈喵
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FeedbackSummary {
+    pub up: usize,
+    pub down: usize,
+}
+
+impl FeedbackSummary {
+    /// 好评率（无数据时为 None）喵
+    pub fn satisfaction(&self) -> Option<f64> {
+        let total = self.up + self.down;
+        (total > 0).then(|| self.up as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_parsing() {
+        assert_eq!(Rating::from_emoji("👍"), Some(Rating::Up));
+        assert_eq!(Rating::from_emoji("👍🏽"), Some(Rating::Up));
+        assert_eq!(Rating::from_emoji("👎"), Some(Rating::Down));
+        assert_eq!(Rating::from_emoji("🎉"), None);
+        assert_eq!(Rating::parse("UP"), Some(Rating::Up));
+        assert_eq!(Rating::parse("-1"), Some(Rating::Down));
+        assert_eq!(Rating::parse("meh"), None);
+
+        let feedback = Feedback::new("msg-1", Rating::Down, "cli").with_comment("  ");
+        assert_eq!(feedback.comment, None);
+        assert!(feedback.validate().is_ok());
+        assert!(Feedback::new("", Rating::Up, "cli").validate().is_err());
+    }
+}
@@ -2,12 +2,15 @@
 //! 
 //! @缪斯 的指标收集与存储实现喵
 
-use rusqlite::{Connection, params, Result as SqliteResult};
+use rusqlite::{Connection, params};
 use chrono::{DateTime, Utc};
 use tracing::{debug, info};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
+use super::feedback::{Feedback, FeedbackSummary, Rating};
+use super::recorder::{CustomMetric, MetricKind};
+
 /// 🔒 SAFETY: Metrics 配置喵
 #[derive(Debug, Clone)]
 pub struct MetricsConfig {
@@ -27,6 +30,12 @@ pub struct AgentMetrics {
     pub model: String,
     pub status: String,
     pub error: Option<String>,
+    /// 来源渠道（用于按用户清除数据）
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// 渠道内的用户 ID
+    #[serde(default)]
+    pub user_id: Option<String>,
 }
 
 /// 🔒 SAFETY: 工具调用指标喵
@@ -88,7 +97,9 @@ impl MetricsCollector {
                 total_tokens INTEGER,
                 model TEXT NOT NULL,
                 status TEXT NOT NULL,
-                error TEXT
+                error TEXT,
+                channel TEXT,
+                user_id TEXT
             );
             CREATE TABLE IF NOT EXISTS tool_metrics (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -99,6 +110,25 @@ impl MetricsCollector {
                 status TEXT NOT NULL,
                 error TEXT
             );
+            CREATE TABLE IF NOT EXISTS custom_metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                value REAL NOT NULL,
+                labels TEXT NOT NULL,
+                source TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_custom_metrics_name ON custom_metrics(name, recorded_at);
+            CREATE TABLE IF NOT EXISTS feedback (
+                response_id TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                user_id TEXT NOT NULL DEFAULT '',
+                rating TEXT NOT NULL,
+                comment TEXT,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (response_id, channel, user_id)
+            );
             CREATE TABLE IF NOT EXISTS system_metrics (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 sample_time TEXT NOT NULL,
@@ -106,6 +136,18 @@ impl MetricsCollector {
                 cpu_usage REAL
             );
         ").map_err(|e| format!("创建表失败: {}", e))?;
+
+        // 旧数据库迁移：补充用户归属列喵
+        for column in ["channel", "user_id"] {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('agent_metrics') WHERE name = ?1")
+                .and_then(|mut stmt| stmt.exists(params![column]))
+                .map_err(|e| format!("查询表结构失败: {}", e))?;
+            if !exists {
+                conn.execute(&format!("ALTER TABLE agent_metrics ADD COLUMN {} TEXT", column), [])
+                    .map_err(|e| format!("迁移失败: {}", e))?;
+            }
+        }
         
         Ok(())
     }
@@ -113,7 +155,7 @@ impl MetricsCollector {
     pub fn record_agent_metrics(&self, metrics: &AgentMetrics) -> Result<(), String> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO agent_metrics (request_id, start_time, end_time, input_tokens, output_tokens, total_tokens, model, status, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO agent_metrics (request_id, start_time, end_time, input_tokens, output_tokens, total_tokens, model, status, error, channel, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 &metrics.request_id,
                 metrics.start_time.to_rfc3339(),
@@ -124,6 +166,8 @@ impl MetricsCollector {
                 &metrics.model,
                 &metrics.status,
                 &metrics.error,
+                &metrics.channel,
+                &metrics.user_id,
             ],
         ).map_err(|e| format!("插入失败: {}", e))?;
         Ok(())
@@ -159,7 +203,7 @@ impl MetricsCollector {
     pub fn get_recent_agent_metrics(&self, limit: u32) -> Result<Vec<AgentMetrics>, String> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT request_id, start_time, end_time, input_tokens, output_tokens, total_tokens, model, status, error FROM agent_metrics ORDER BY start_time DESC LIMIT ?1"
+            "SELECT request_id, start_time, end_time, input_tokens, output_tokens, total_tokens, model, status, error, channel, user_id FROM agent_metrics ORDER BY start_time DESC LIMIT ?1"
         ).map_err(|e| format!("查询失败: {}", e))?;
         
         let rows = stmt.query_map(params![limit], |row| {
@@ -173,6 +217,8 @@ impl MetricsCollector {
                 model: row.get(6)?,
                 status: row.get(7)?,
                 error: row.get(8)?,
+                channel: row.get(9)?,
+                user_id: row.get(10)?,
             })
         }).map_err(|e| format!("解析失败: {}", e))?;
         
@@ -230,6 +276,137 @@ impl MetricsCollector {
     }
 }
 
+impl MetricsCollector {
+    /// 🔒 SAFETY: 写入外部组件上报的自定义指标喵（调用方负责校验）
+    pub fn record_custom_metric(&self, metric: &CustomMetric) -> Result<(), String> {
+        let labels = serde_json::to_string(&metric.labels).map_err(|e| format!("序列化标签失败: {}", e))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO custom_metrics (name, kind, value, labels, source, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                &metric.name,
+                metric.kind.as_str(),
+                metric.value,
+                labels,
+                &metric.source,
+                metric.recorded_at.to_rfc3339(),
+            ],
+        ).map_err(|e| format!("插入失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 查询最近的自定义指标喵（name 为 None 时返回全部）
+    pub fn get_recent_custom_metrics(&self, name: Option<&str>, limit: u32) -> Result<Vec<CustomMetric>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, kind, value, labels, source, recorded_at FROM custom_metrics WHERE ?1 IS NULL OR name = ?1 ORDER BY id DESC LIMIT ?2"
+        ).map_err(|e| format!("查询失败: {}", e))?;
+
+        let rows = stmt.query_map(params![name, limit], |row| {
+            Ok(CustomMetric {
+                name: row.get(0)?,
+                kind: MetricKind::parse(&row.get::<_, String>(1)?).unwrap_or(MetricKind::Event),
+                value: row.get(2)?,
+                labels: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+                source: row.get(4)?,
+                recorded_at: parse_time(&row.get::<_, String>(5)?),
+            })
+        }).map_err(|e| format!("解析失败: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("收集失败: {}", e))
+    }
+
+    /// 🔒 SAFETY: 记录用户反馈喵（同一用户对同一回复以最后一次为准）
+    pub fn record_feedback(&self, feedback: &Feedback) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO feedback (response_id, channel, user_id, rating, comment, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(response_id, channel, user_id) DO UPDATE SET
+                rating = excluded.rating, comment = excluded.comment, recorded_at = excluded.recorded_at",
+            params![
+                &feedback.response_id,
+                &feedback.channel,
+                feedback.user_id.as_deref().unwrap_or_default(),
+                feedback.rating.as_str(),
+                &feedback.comment,
+                feedback.recorded_at.to_rfc3339(),
+            ],
+        ).map_err(|e| format!("插入失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 查询最近的反馈喵
+    pub fn get_recent_feedback(&self, limit: u32) -> Result<Vec<Feedback>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT response_id, channel, user_id, rating, comment, recorded_at FROM feedback ORDER BY recorded_at DESC LIMIT ?1"
+        ).map_err(|e| format!("查询失败: {}", e))?;
+
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok(Feedback {
+                response_id: row.get(0)?,
+                channel: row.get(1)?,
+                user_id: Some(row.get::<_, String>(2)?).filter(|u| !u.is_empty()),
+                rating: if row.get::<_, String>(3)? == "up" { Rating::Up } else { Rating::Down },
+                comment: row.get(4)?,
+                recorded_at: parse_time(&row.get::<_, String>(5)?),
+            })
+        }).map_err(|e| format!("解析失败: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("收集失败: {}", e))
+    }
+
+    /// 🔒 SAFETY: 按渠道汇总反馈喵
+    pub fn get_feedback_summary(&self) -> Result<std::collections::BTreeMap<String, FeedbackSummary>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT channel, SUM(rating = 'up'), SUM(rating = 'down') FROM feedback GROUP BY channel"
+        ).map_err(|e| format!("查询失败: {}", e))?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                FeedbackSummary {
+                    up: row.get::<_, i64>(1)? as usize,
+                    down: row.get::<_, i64>(2)? as usize,
+                },
+            ))
+        }).map_err(|e| format!("解析失败: {}", e))?;
+
+        rows.collect::<Result<_, _>>().map_err(|e| format!("收集失败: {}", e))
+    }
+
+    /// 🔒 SAFETY: 删除某用户的全部指标（含关联的工具调用与反馈）喵
+    pub fn purge_user(&self, channel: &str, user_id: &str) -> Result<usize, String> {
+        let feedback = self.purge_feedback_rows("channel = ?1 AND user_id = ?2", params![channel, user_id])?;
+        Ok(feedback + self.purge_agent_rows("channel = ?1 AND user_id = ?2", params![channel, user_id])?)
+    }
+
+    /// 🔒 SAFETY: 删除某渠道在 cutoff 之前的指标（保留期）喵
+    pub fn purge_channel_before(&self, channel: &str, cutoff: DateTime<Utc>) -> Result<usize, String> {
+        let cutoff = cutoff.to_rfc3339();
+        let feedback = self.purge_feedback_rows("channel = ?1 AND recorded_at < ?2", params![channel, cutoff])?;
+        Ok(feedback + self.purge_agent_rows("channel = ?1 AND start_time < ?2", params![channel, cutoff])?)
+    }
+
+    fn purge_feedback_rows(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> Result<usize, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(&format!("DELETE FROM feedback WHERE {}", filter), args)
+            .map_err(|e| format!("删除失败: {}", e))
+    }
+
+    fn purge_agent_rows(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> Result<usize, String> {
+        let conn = self.conn.lock().unwrap();
+        let tools = conn.execute(
+            &format!("DELETE FROM tool_metrics WHERE request_id IN (SELECT request_id FROM agent_metrics WHERE {})", filter),
+            args,
+        ).map_err(|e| format!("删除失败: {}", e))?;
+        let agents = conn.execute(&format!("DELETE FROM agent_metrics WHERE {}", filter), args)
+            .map_err(|e| format!("删除失败: {}", e))?;
+        Ok(tools + agents)
+    }
+}
+
 fn parse_time(s: &str) -> DateTime<Utc> {
     DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
 }
@@ -1,35 +1,45 @@
-/// Telemetry 模块 📊
-///
-/// @缪斯 的可观测性深度监控系统喵
-///
-/// 功能：
-/// - 收集 Agent 运行指标（Token 消耗、工具耗时、内存使用）
-/// - SQLite 本地存储（零外部依赖）
-/// - OpenTelemetry 风格的 Span 追踪
-/// - 轻量 HTML Dashboard 可视化
-///
-/// 配置：
-/// - 10% Tracing 采样率（平衡性能与监控密度）
-/// - 5 秒内存监控间隔
-/// - 自动指标聚合与存储
-///
-/// 🔒 SAFETY: 所有 I/O 操作都经过错误处理，崩溃不影响主流程
-///
-/// 模块作者: 缪斯 (Muse) 💜
-
+//! Telemetry 模块 📊
+//!
+//! @缪斯 的可观测性深度监控系统喵
+//!
+//! 功能：
+//! - 收集 Agent 运行指标（Token 消耗、工具耗时、内存使用）
+//! - 渠道 / 工具 / 插件通过 `MetricsRecorder` 上报带标签的自定义指标
+//! - 按回复 ID 记录用户反馈（👍 / 👎）
+//! - SQLite 本地存储（零外部依赖）
+//! - OpenTelemetry 风格的 Span 追踪（可选 OTLP/HTTP 导出到 Jaeger / Tempo）
+//! - 轻量 HTML Dashboard 可视化
+//!
+//! 配置：
+//! - 10% Tracing 采样率（平衡性能与监控密度）
+//! - 5 秒内存监控间隔
+//! - 自动指标聚合与存储
+//!
+//! 🔒 SAFETY: 所有 I/O 操作都经过错误处理，崩溃不影响主流程
+//!
+//! 模块作者: 缪斯 (Muse) 💜
+
+mod feedback;
 mod metrics;
+mod otlp;
+mod recorder;
 mod tracer;
 mod dashboard;
 
 pub use metrics::{
     MetricsCollector, MetricsConfig, AgentMetrics, ToolMetrics, SystemMetrics,
 };
-pub use tracer::{Tracer, Span, TracerConfig};
+pub use feedback::{Feedback, FeedbackSummary, Rating};
+pub use otlp::{OtlpConfig, OtlpExporter};
+pub use recorder::{CustomMetric, MetricKind, MetricsRecorder};
+pub use tracer::{
+    flame_rows, to_otlp_json, FlameRow, Span, SpanGuard, SpanLink, Tracer, TracerConfig,
+};
 pub use dashboard::DashboardGenerator;
 
-use tracing::{info, error, debug};
+use crate::service::TaskSupervisor;
+use tracing::{info, debug};
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 /// 🔒 SAFETY: 可观测性配置喵
 #[derive(Debug, Clone)]
@@ -44,6 +54,8 @@ pub struct TelemetryConfig {
     pub monitor_interval_sec: u64,
     /// SQLite 数据库路径
     pub db_path: String,
+    /// Span 的 OTLP 导出（None 或未启用时只保存在进程内）
+    pub otlp: Option<OtlpConfig>,
 }
 
 impl Default for TelemetryConfig {
@@ -54,6 +66,7 @@ impl Default for TelemetryConfig {
             trace_sampling: 0.1,
             monitor_interval_sec: 5,
             db_path: "metrics.db".to_string(),
+            otlp: None,
         }
     }
 }
@@ -61,7 +74,7 @@ impl Default for TelemetryConfig {
 /// 🔒 SAFETY: Telemetry 主结构体喵
 pub struct Telemetry {
     config: TelemetryConfig,
-    metrics: Arc<RwLock<MetricsCollector>>,
+    metrics: Arc<MetricsCollector>,
     tracer: Arc<Tracer>,
 }
 
@@ -79,13 +92,17 @@ impl Telemetry {
         ).await
             .map_err(|e| format!("初始化的 Metrics Collector 失败: {}", e))?;
 
-        let metrics = Arc::new(RwLock::new(metrics));
+        let metrics = Arc::new(metrics);
 
         // 初始化 Tracer
-        let tracer = Tracer::new(TracerConfig {
+        let mut tracer = Tracer::new(TracerConfig {
             sampling_rate: config.trace_sampling,
             enable_tracing: config.enable_tracing,
         });
+        if let Some(otlp) = config.otlp.clone().filter(|o| o.enabled) {
+            info!("📡 Span 导出到 OTLP collector: {}", otlp.endpoint);
+            tracer = tracer.with_exporter(OtlpExporter::spawn(otlp)?);
+        }
 
         let tracer = Arc::new(tracer);
 
@@ -99,28 +116,20 @@ impl Telemetry {
     }
 
     /// 🔒 SAFETY: 启动后台监控任务喵
-    pub async fn start_monitoring(&self) -> Result<(), String> {
+    /// 任务由监督器托管，关闭时统一取消喵
+    pub async fn start_monitoring(&self, supervisor: &mut TaskSupervisor) -> Result<(), String> {
         debug!("📊 启动后台监控任务喵...");
 
         let metrics = self.metrics.clone();
+        let interval = std::time::Duration::from_secs(self.config.monitor_interval_sec.max(1));
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(
-                tokio::time::Duration::from_secs(5)
-            );
-
-            loop {
-                interval.tick().await;
-
+        supervisor.spawn_periodic("telemetry_sampling", interval, move || {
+            let metrics = metrics.clone();
+            async move {
                 // 🔒 SAFETY: 现在是同步方法了喵
-                let result = {
-                    let metrics_guard = metrics.write().await;
-                    metrics_guard.sample_system_metrics()
-                };
-
-                if let Err(e) = result {
-                    error!("采样系统指标失败: {}", e);
-                }
+                metrics
+                    .sample_system_metrics()
+                    .map_err(|e| format!("采样系统指标失败: {}", e))
             }
         });
 
@@ -130,10 +139,15 @@ impl Telemetry {
     }
 
     /// 🔒 SAFETY: 获取 Metrics Collector 喵
-    pub fn metrics(&self) -> Arc<RwLock<MetricsCollector>> {
+    pub fn metrics(&self) -> Arc<MetricsCollector> {
         self.metrics.clone()
     }
 
+    /// 🔒 SAFETY: 获取自定义指标记录器（供渠道 / 工具 / 插件上报）喵
+    pub fn recorder(&self) -> MetricsRecorder {
+        MetricsRecorder::new(self.metrics.clone())
+    }
+
     /// 🔒 SAFETY: 获取 Tracer 喵
     pub fn tracer(&self) -> Arc<Tracer> {
         self.tracer.clone()
@@ -150,11 +164,11 @@ impl Telemetry {
 
     /// 🔒 SAFETY: 获取 Dashboard 生成器喵
     pub async fn get_dashboard(&self) -> Result<String, String> {
-        let metrics = self.metrics.read().await;
         let generator = DashboardGenerator::new();
+        let spans = self.tracer.get_recent_spans(200).await;
 
         generator
-            .generate_html(&metrics)
+            .generate_html(&self.metrics, &spans)
             .map_err(|e| format!("生成 Dashboard 失败: {}", e))
     }
 }
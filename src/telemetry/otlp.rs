@@ -0,0 +1,225 @@
+//! OTLP Exporter - Span 导出到 Jaeger / Tempo 📡
+//!
+//! 完成的 Span 进入有界队列，后台任务按批次（或定时）以 OTLP/HTTP JSON
+//! 发送到 collector 的 `/v1/traces`，Jaeger（1.35+）与 Tempo 都可直接接收喵
+//!
+//! ```toml
+//! [otlp]
+//! enabled = true
+//! endpoint = "http://localhost:4318/v1/traces"
+//! sampling_rate = 1.0
+//!
+//! [otlp.headers]
+//! X-Scope-OrgID = "nekoclaw"
+//! ```
+//!
+//! 队列满或 collector 不可用时丢弃 Span，不阻塞请求喵
+
+use super::tracer::{to_otlp_json, Span};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+fn default_endpoint() -> String {
+    "http://localhost:4318/v1/traces".to_string()
+}
+fn default_sampling_rate() -> f64 {
+    1.0
+}
+fn default_batch_size() -> usize {
+    128
+}
+fn default_flush_interval_secs() -> u64 {
+    5
+}
+fn default_timeout_secs() -> u64 {
+    10
+}
+fn default_max_queue() -> usize {
+    2048
+}
+
+/// 🔒 SAFETY: OTLP 导出配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// collector 的 traces 端点（OTLP/HTTP）
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// 附加请求头（认证、租户等）
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 根 Span 采样率（0.0~1.0），子 Span 跟随父 Span
+    #[serde(default = "default_sampling_rate")]
+    pub sampling_rate: f64,
+    /// 每批最多 Span 数
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// 不满一批时的发送间隔（秒）
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// 单次发送超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 等待发送的 Span 上限
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_endpoint(),
+            headers: BTreeMap::new(),
+            sampling_rate: default_sampling_rate(),
+            batch_size: default_batch_size(),
+            flush_interval_secs: default_flush_interval_secs(),
+            timeout_secs: default_timeout_secs(),
+            max_queue: default_max_queue(),
+        }
+    }
+}
+
+enum Command {
+    Export(Span),
+    Flush(oneshot::Sender<()>),
+}
+
+/// 🔒 SAFETY: OTLP/HTTP Span 导出器喵
+#[derive(Debug, Clone)]
+pub struct OtlpExporter {
+    sender: mpsc::Sender<Command>,
+}
+
+impl OtlpExporter {
+    /// 🔒 SAFETY: 启动后台发送任务喵（需在 Tokio 运行时内调用）
+    pub fn spawn(config: OtlpConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .map_err(|e| format!("Failed to build OTLP client: {}", e))?;
+        let (sender, receiver) = mpsc::channel(config.max_queue.max(1));
+        tokio::spawn(run(client, config, receiver));
+        Ok(Self { sender })
+    }
+
+    /// 加入发送队列喵（队列已满时丢弃）
+    pub fn export(&self, span: Span) {
+        if let Err(e) = self.sender.try_send(Command::Export(span)) {
+            debug!("OTLP queue full or closed, dropping span: {}", e);
+        }
+    }
+
+    /// 立即发送队列中的 Span 并等待完成喵
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Command::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+/// 后台发送循环喵
+async fn run(client: reqwest::Client, config: OtlpConfig, mut receiver: mpsc::Receiver<Command>) {
+    let mut batch: Vec<Span> = Vec::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.flush_interval_secs.max(1)));
+    loop {
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::Export(span)) => {
+                    batch.push(span);
+                    if batch.len() >= config.batch_size.max(1) {
+                        send(&client, &config, std::mem::take(&mut batch)).await;
+                    }
+                }
+                Some(Command::Flush(done)) => {
+                    send(&client, &config, std::mem::take(&mut batch)).await;
+                    let _ = done.send(());
+                }
+                None => {
+                    send(&client, &config, std::mem::take(&mut batch)).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => send(&client, &config, std::mem::take(&mut batch)).await,
+        }
+    }
+}
+
+/// 发送一批 Span 喵（失败时丢弃该批）
+async fn send(client: &reqwest::Client, config: &OtlpConfig, spans: Vec<Span>) {
+    if spans.is_empty() {
+        return;
+    }
+    let mut request = client.post(&config.endpoint).json(&to_otlp_json(&spans));
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => debug!("Exported {} spans via OTLP", spans.len()),
+        Ok(response) => warn!("OTLP collector rejected {} spans: HTTP {}", spans.len(), response.status()),
+        Err(e) => warn!("Failed to export {} spans via OTLP: {}", spans.len(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{Tracer, TracerConfig};
+    use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(Option<String>, serde_json::Value)>>>;
+
+    #[tokio::test]
+    async fn test_exporter_posts_batches() {
+        let received: Received = Arc::default();
+        let app = Router::new()
+            .route(
+                "/v1/traces",
+                post(|State(received): State<Received>, headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                    let tenant = headers.get("x-scope-orgid").and_then(|v| v.to_str().ok()).map(String::from);
+                    received.lock().unwrap().push((tenant, body));
+                }),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let exporter = OtlpExporter::spawn(OtlpConfig {
+            enabled: true,
+            endpoint: format!("http://{}/v1/traces", addr),
+            headers: BTreeMap::from([("X-Scope-OrgID".to_string(), "neko".to_string())]),
+            batch_size: 2,
+            ..OtlpConfig::default()
+        })
+        .unwrap();
+        let tracer = Tracer::new(TracerConfig {
+            sampling_rate: 1.0,
+            enable_tracing: true,
+        })
+        .with_exporter(exporter);
+
+        let root = tracer.start_span("gateway.request").unwrap();
+        let child = tracer.start_child(&root, "provider.chat").unwrap();
+        let lone = tracer.start_span("agent.request").unwrap();
+        tracer.finish_span(child.clone()).await;
+        tracer.finish_span(root.clone()).await;
+        tracer.finish_span(lone).await;
+        tracer.flush().await;
+
+        // 满 2 个发送一批，剩下的在 flush 时发送喵
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0.as_deref(), Some("neko"));
+        let spans = &received[0].1["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["parentSpanId"], root.span_id);
+        assert_eq!(spans[1]["spanId"], root.span_id);
+        assert_eq!(received[1].1["resourceSpans"][0]["scopeSpans"][0]["spans"][0]["name"], "agent.request");
+    }
+}
@@ -0,0 +1,280 @@
+//! Metrics Recorder - 外部组件写入 API ✍️
+//!
+//! @缪斯 给渠道、工具、插件用的自定义指标上报接口喵
+//!
+//! ```ignore
+//! let recorder = telemetry.recorder().scoped("discord");
+//! recorder.counter("messages_received", 1.0, &[("guild", "123")]);
+//! recorder.gauge("queue_depth", 4.0, &[]);
+//! recorder.event("reconnected", &[("reason", "timeout")]);
+//! ```
+//!
+//! 🔒 SAFETY: 便捷方法只记录日志不返回错误，遥测故障不会打断调用方
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::feedback::Feedback;
+use super::metrics::MetricsCollector;
+
+/// 指标名最大长度
+const MAX_NAME_LEN: usize = 128;
+/// 单条指标最多标签数
+const MAX_LABELS: usize = 16;
+/// 标签值最大长度
+const MAX_LABEL_VALUE_LEN: usize = 256;
+
+/// 🔒 SAFETY: 指标类型喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    /// 累加计数（值必须非负）
+    Counter,
+    /// 瞬时值
+    Gauge,
+    /// 离散事件（值固定为 1）
+    Event,
+}
+
+impl MetricKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Event => "event",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "counter" => Some(Self::Counter),
+            "gauge" => Some(Self::Gauge),
+            "event" => Some(Self::Event),
+            _ => None,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 自定义指标喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomMetric {
+    pub name: String,
+    pub kind: MetricKind,
+    pub value: f64,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// 上报来源（渠道 / 工具 / 插件名）
+    pub source: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl CustomMetric {
+    /// 🔒 SAFETY: 校验名称、标签与数值喵
+    pub fn validate(&self) -> Result<(), String> {
+        validate_identifier("指标名", &self.name, MAX_NAME_LEN)?;
+        if !self.value.is_finite() {
+            return Err(format!("指标 {} 的值必须是有限数喵", self.name));
+        }
+        if self.kind == MetricKind::Counter && self.value < 0.0 {
+            return Err(format!("计数器 {} 不能为负数喵", self.name));
+        }
+        if self.labels.len() > MAX_LABELS {
+            return Err(format!("指标 {} 的标签超过 {} 个喵", self.name, MAX_LABELS));
+        }
+        for (key, value) in &self.labels {
+            validate_identifier("标签名", key, MAX_NAME_LEN)?;
+            if value.chars().count() > MAX_LABEL_VALUE_LEN {
+                return Err(format!("标签 {} 的值超过 {} 个字符喵", key, MAX_LABEL_VALUE_LEN));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 名称只允许 `[A-Za-z_][A-Za-z0-9_.:]*`，与 Prometheus 指标名兼容喵
+fn validate_identifier(what: &str, name: &str, max_len: usize) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    let valid_rest = chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':'));
+    if !valid_start || !valid_rest || name.len() > max_len {
+        return Err(format!("{} '{}' 不合法喵", what, name));
+    }
+    Ok(())
+}
+
+/// 🔒 SAFETY: 自定义指标记录器喵
+///
+/// 可廉价克隆，按组件 `scoped` 后分发给渠道 / 工具 / 插件
+#[derive(Clone)]
+pub struct MetricsRecorder {
+    collector: Arc<MetricsCollector>,
+    source: String,
+    labels: BTreeMap<String, String>,
+}
+
+impl std::fmt::Debug for MetricsRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsRecorder")
+            .field("source", &self.source)
+            .field("labels", &self.labels)
+            .finish()
+    }
+}
+
+impl MetricsRecorder {
+    pub fn new(collector: Arc<MetricsCollector>) -> Self {
+        Self {
+            collector,
+            source: "core".to_string(),
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// 🔒 SAFETY: 派生一个指定来源的记录器喵
+    pub fn scoped(&self, source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// 🔒 SAFETY: 附加一个默认标签（每条指标都会带上）喵
+    pub fn with_label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// 🔒 SAFETY: 校验并写入一条指标喵
+    pub fn record(
+        &self,
+        name: &str,
+        kind: MetricKind,
+        value: f64,
+        labels: &[(&str, &str)],
+    ) -> Result<(), String> {
+        let mut merged = self.labels.clone();
+        merged.extend(labels.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        let metric = CustomMetric {
+            name: name.to_string(),
+            kind,
+            value: if kind == MetricKind::Event { 1.0 } else { value },
+            labels: merged,
+            source: self.source.clone(),
+            recorded_at: Utc::now(),
+        };
+        self.record_metric(&metric)
+    }
+
+    /// 🔒 SAFETY: 写入一条已构造好的指标喵（HTTP 上报入口使用）
+    pub fn record_metric(&self, metric: &CustomMetric) -> Result<(), String> {
+        metric.validate()?;
+        self.collector.record_custom_metric(metric)
+    }
+
+    /// 🔒 SAFETY: 底层的指标收集器喵（Dashboard 读取汇总数据）
+    pub fn collector(&self) -> &MetricsCollector {
+        &self.collector
+    }
+
+    /// 🔒 SAFETY: 校验并写入一条用户反馈喵
+    pub fn record_feedback(&self, feedback: &Feedback) -> Result<(), String> {
+        feedback.validate()?;
+        self.collector.record_feedback(feedback)
+    }
+
+    pub fn counter(&self, name: &str, value: f64, labels: &[(&str, &str)]) {
+        self.log_failure(name, self.record(name, MetricKind::Counter, value, labels));
+    }
+
+    pub fn gauge(&self, name: &str, value: f64, labels: &[(&str, &str)]) {
+        self.log_failure(name, self.record(name, MetricKind::Gauge, value, labels));
+    }
+
+    pub fn event(&self, name: &str, labels: &[(&str, &str)]) {
+        self.log_failure(name, self.record(name, MetricKind::Event, 1.0, labels));
+    }
+
+    fn log_failure(&self, name: &str, result: Result<(), String>) {
+        if let Err(e) = result {
+            warn!("记录自定义指标 {}/{} 失败喵: {}", self.source, name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::MetricsConfig;
+
+    async fn recorder() -> MetricsRecorder {
+        let collector = MetricsCollector::new(MetricsConfig {
+            db_path: ":memory:".to_string(),
+            monitor_interval_sec: 5,
+        })
+        .await
+        .unwrap();
+        MetricsRecorder::new(Arc::new(collector))
+    }
+
+    #[tokio::test]
+    async fn test_scoped_recorder_persists_labels() {
+        let recorder = recorder().await;
+        let discord = recorder.scoped("discord").with_label("bot", "neko");
+        discord.counter("messages_received", 2.0, &[("guild", "42")]);
+        discord.event("reconnected", &[]);
+
+        let all = recorder.collector.get_recent_custom_metrics(None, 10).unwrap();
+        assert_eq!(all.len(), 2);
+        let counters = recorder
+            .collector
+            .get_recent_custom_metrics(Some("messages_received"), 10)
+            .unwrap();
+        assert_eq!(counters[0].source, "discord");
+        assert_eq!(counters[0].kind, MetricKind::Counter);
+        assert_eq!(counters[0].value, 2.0);
+        assert_eq!(counters[0].labels["bot"], "neko");
+        assert_eq!(counters[0].labels["guild"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_feedback_upsert_summary_and_purge() {
+        use crate::telemetry::Rating;
+
+        let recorder = recorder().await;
+        let vote = |rating, user| Feedback::new("msg-1", rating, "discord").with_user(user);
+        recorder.record_feedback(&vote(Rating::Up, "alice")).unwrap();
+        // 改主意：以最后一次为准喵
+        recorder.record_feedback(&vote(Rating::Down, "alice")).unwrap();
+        recorder.record_feedback(&vote(Rating::Up, "bob")).unwrap();
+        recorder
+            .record_feedback(&Feedback::new("r-9", Rating::Up, "cli").with_comment("nice"))
+            .unwrap();
+        assert!(recorder.record_feedback(&Feedback::new("", Rating::Up, "cli")).is_err());
+
+        let summary = recorder.collector.get_feedback_summary().unwrap();
+        assert_eq!((summary["discord"].up, summary["discord"].down), (1, 1));
+        assert_eq!(summary["cli"].satisfaction(), Some(1.0));
+        assert_eq!(recorder.collector.get_recent_feedback(10).unwrap().len(), 3);
+
+        assert_eq!(recorder.collector.purge_user("discord", "alice").unwrap(), 1);
+        assert_eq!(recorder.collector.get_feedback_summary().unwrap()["discord"].down, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_metrics_are_rejected() {
+        let recorder = recorder().await;
+        assert!(recorder.record("bad name", MetricKind::Gauge, 1.0, &[]).is_err());
+        assert!(recorder.record("ok", MetricKind::Counter, -1.0, &[]).is_err());
+        assert!(recorder.record("ok", MetricKind::Gauge, f64::NAN, &[]).is_err());
+        assert!(recorder.record("ok", MetricKind::Gauge, 1.0, &[("9lives", "x")]).is_err());
+
+        // 便捷方法失败时只记日志，不写入也不 panic 喵
+        recorder.gauge("bad name", 1.0, &[]);
+        assert!(recorder.collector.get_recent_custom_metrics(None, 10).unwrap().is_empty());
+    }
+}
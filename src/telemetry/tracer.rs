@@ -1,10 +1,16 @@
 //! Tracer - OpenTelemetry 风格 Span 追踪 🔍
+//!
+//! Span 通过 `parent_span_id` 组成调用树（Agent 请求 → 工具执行 → MCP 请求），
+//! 并可用 `links` 显式引用相关 Span，支持火焰图展示与 OTLP/JSON 导出喵
+//!
+//! 挂载 `OtlpExporter` 时，完成的 Span 同时发送到 OTLP collector
 
+use super::otlp::OtlpExporter;
 use chrono::{DateTime, Utc};
-use tracing::{debug, trace};
 use uuid::Uuid;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use std::collections::HashMap;
 use std::fmt;
 
 /// 🔒 SAFETY: Tracer 配置喵
@@ -43,6 +49,27 @@ pub struct Span {
     pub parent_span_id: Option<String>,
     pub attributes: Vec<(String, String)>,
     pub events: Vec<(DateTime<Utc>, String)>,
+    /// 指向相关 Span 的链接（可跨 Trace）
+    pub links: Vec<SpanLink>,
+}
+
+/// 🔒 SAFETY: Span 链接喵
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanLink {
+    pub trace_id: String,
+    pub span_id: String,
+    /// 关系说明，例如 "triggered_by" / "mcp_request"
+    pub relation: String,
+}
+
+/// 32 位十六进制 Trace ID（OTLP 格式）
+fn new_trace_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// 16 位十六进制 Span ID（OTLP 格式）
+fn new_span_id() -> String {
+    Uuid::new_v4().simple().to_string()[..16].to_string()
 }
 
 impl Span {
@@ -65,9 +92,18 @@ impl Span {
         self.events.push((Utc::now(), message));
     }
 
+    /// 🔒 SAFETY: 添加指向另一个 Span 的链接喵
+    pub fn add_link(&mut self, target: &Span, relation: &str) {
+        self.links.push(SpanLink {
+            trace_id: target.trace_id.clone(),
+            span_id: target.span_id.clone(),
+            relation: relation.to_string(),
+        });
+    }
+
     pub fn create_child(&self, name: &str) -> Self {
         Self {
-            span_id: Uuid::new_v4().to_string(),
+            span_id: new_span_id(),
             trace_id: self.trace_id.clone(),
             name: name.to_string(),
             start_time: Utc::now(),
@@ -76,6 +112,7 @@ impl Span {
             parent_span_id: Some(self.span_id.clone()),
             attributes: Vec::new(),
             events: Vec::new(),
+            links: Vec::new(),
         }
     }
 }
@@ -84,12 +121,14 @@ impl Span {
 pub struct Tracer {
     config: TracerConfig,
     active_spans: Arc<RwLock<Vec<Span>>>,
+    exporter: Option<OtlpExporter>,
 }
 
 impl fmt::Debug for Tracer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Tracer")
             .field("config", &self.config)
+            .field("exporter", &self.exporter.is_some())
             .finish()
     }
 }
@@ -99,27 +138,29 @@ impl Tracer {
         Self {
             config,
             active_spans: Arc::new(RwLock::new(Vec::new())),
+            exporter: None,
         }
     }
 
+    /// 🔒 SAFETY: 完成的 Span 同时导出到 OTLP collector 喵
+    pub fn with_exporter(mut self, exporter: OtlpExporter) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
     pub fn start_span(&self, name: &str) -> Option<Span> {
         if !self.config.enable_tracing {
             return None;
         }
 
-        // 采样判断
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        use std::hash::{Hash, Hasher};
-        name.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        if (hash as f64 / u64::MAX as f64) > self.config.sampling_rate {
+        // 按 Trace 采样（子 Span 跟随根 Span）
+        if rand::random::<f64>() >= self.config.sampling_rate {
             return None;
         }
 
         Some(Span {
-            span_id: Uuid::new_v4().to_string(),
-            trace_id: Uuid::new_v4().to_string(),
+            span_id: new_span_id(),
+            trace_id: new_trace_id(),
             name: name.to_string(),
             start_time: Utc::now(),
             end_time: None,
@@ -127,21 +168,34 @@ impl Tracer {
             parent_span_id: None,
             attributes: Vec::new(),
             events: Vec::new(),
+            links: Vec::new(),
         })
     }
 
+    /// 🔒 SAFETY: 开始一个子 Span 喵
+    /// 父 Span 已被采样时子 Span 一定记录，保证调用树完整
+    pub fn start_child(&self, parent: &Span, name: &str) -> Option<Span> {
+        if !self.config.enable_tracing {
+            return None;
+        }
+        Some(parent.create_child(name))
+    }
+
     pub async fn finish_span(&self, mut span: Span) {
         span.finish();
-        let mut spans = self.active_spans.write().await;
-        spans.push(span);
-        if spans.len() > 1000 {
-            let to_remove = spans.len() - 1000;
-            spans.drain(0..to_remove);
-        }
+        self.store(span).await;
     }
 
     pub async fn finish_span_with_error(&self, mut span: Span, error: &str) {
         span.finish_with_error(error);
+        self.store(span).await;
+    }
+
+    /// 保存已完成的 Span（最多 1000 个）并交给导出器喵
+    async fn store(&self, span: Span) {
+        if let Some(exporter) = &self.exporter {
+            exporter.export(span.clone());
+        }
         let mut spans = self.active_spans.write().await;
         spans.push(span);
         if spans.len() > 1000 {
@@ -150,10 +204,123 @@ impl Tracer {
         }
     }
 
+    /// 🔒 SAFETY: 立即导出等待中的 Span 喵（退出前调用）
+    pub async fn flush(&self) {
+        if let Some(exporter) = &self.exporter {
+            exporter.flush().await;
+        }
+    }
+
     pub async fn get_recent_spans(&self, limit: u32) -> Vec<Span> {
         let spans = self.active_spans.read().await;
         spans.iter().rev().take(limit as usize).cloned().collect()
     }
+
+    /// 🔒 SAFETY: 获取某个 Trace 下已完成的全部 Span 喵
+    pub async fn get_trace(&self, trace_id: &str) -> Vec<Span> {
+        let spans = self.active_spans.read().await;
+        spans.iter().filter(|s| s.trace_id == trace_id).cloned().collect()
+    }
+}
+
+/// 🔒 SAFETY: 火焰图中的一行喵
+#[derive(Debug, Clone)]
+pub struct FlameRow {
+    /// 调用深度（根为 0）
+    pub depth: usize,
+    /// 相对 Trace 起点的偏移（毫秒）
+    pub offset_ms: i64,
+    /// 持续时间（毫秒）
+    pub duration_ms: i64,
+    pub span: Span,
+}
+
+/// 🔒 SAFETY: 把 Span 按调用树展开为火焰图行喵
+///
+/// 深度优先，兄弟节点按开始时间排序；父 Span 不在列表中时视为根节点
+pub fn flame_rows(spans: &[Span]) -> Vec<FlameRow> {
+    let ids: std::collections::HashSet<&str> = spans.iter().map(|s| s.span_id.as_str()).collect();
+    let mut children: HashMap<Option<&str>, Vec<&Span>> = HashMap::new();
+    for span in spans {
+        let parent = span.parent_span_id.as_deref().filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(span);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|s| s.start_time);
+    }
+
+    let origin = spans.iter().map(|s| s.start_time).min();
+    let mut rows = Vec::new();
+    let mut stack: Vec<(usize, &Span)> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().map(|s| (0, *s)).collect())
+        .unwrap_or_default();
+    while let Some((depth, span)) = stack.pop() {
+        let end = span.end_time.unwrap_or(span.start_time);
+        rows.push(FlameRow {
+            depth,
+            offset_ms: origin.map_or(0, |o| (span.start_time - o).num_milliseconds()),
+            duration_ms: (end - span.start_time).num_milliseconds(),
+            span: span.clone(),
+        });
+        if let Some(kids) = children.get(&Some(span.span_id.as_str())) {
+            stack.extend(kids.iter().rev().map(|s| (depth + 1, *s)));
+        }
+    }
+    rows
+}
+
+/// 🔒 SAFETY: 导出为 OTLP/JSON（`ExportTraceServiceRequest`）喵
+pub fn to_otlp_json(spans: &[Span]) -> serde_json::Value {
+    fn nanos(time: DateTime<Utc>) -> String {
+        time.timestamp_nanos_opt().unwrap_or_default().to_string()
+    }
+    fn string_attr(key: &str, value: &str) -> serde_json::Value {
+        serde_json::json!({ "key": key, "value": { "stringValue": value } })
+    }
+
+    let spans: Vec<serde_json::Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = serde_json::json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": 1,
+                "startTimeUnixNano": nanos(span.start_time),
+                "endTimeUnixNano": nanos(span.end_time.unwrap_or(span.start_time)),
+                "attributes": span.attributes.iter().map(|(k, v)| string_attr(k, v)).collect::<Vec<_>>(),
+                "events": span.events.iter().map(|(time, message)| serde_json::json!({
+                    "timeUnixNano": nanos(*time),
+                    "name": message,
+                })).collect::<Vec<_>>(),
+                "links": span.links.iter().map(|link| serde_json::json!({
+                    "traceId": link.trace_id,
+                    "spanId": link.span_id,
+                    "attributes": [string_attr("relation", &link.relation)],
+                })).collect::<Vec<_>>(),
+                "status": { "code": match span.status {
+                    SpanStatus::InProgress => 0,
+                    SpanStatus::Completed => 1,
+                    SpanStatus::Failed => 2,
+                } },
+            });
+            if let Some(parent) = &span.parent_span_id {
+                value["parentSpanId"] = serde_json::json!(parent);
+            }
+            value
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [string_attr("service.name", "nekoclaw")] },
+            "scopeSpans": [{
+                "scope": { "name": "nekoclaw", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
 }
 
 /// 🔒 SAFETY: Span Guard - 自动完成 Span 喵
@@ -189,15 +356,79 @@ impl Drop for SpanGuard {
         if let (Some(mut span), Some(tracer)) = (self.span.take(), self.tracer.take()) {
             span.finish();
             if let Ok(handle) = tokio::runtime::Handle::try_current() {
-                handle.spawn(async move {
-                    let mut spans = tracer.active_spans.write().await;
-                    spans.push(span);
-                    if spans.len() > 1000 {
-                        let to_remove = spans.len() - 1000;
-                        spans.drain(0..to_remove);
-                    }
-                });
+                handle.spawn(async move { tracer.store(span).await });
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracer() -> Tracer {
+        Tracer::new(TracerConfig {
+            sampling_rate: 1.0,
+            enable_tracing: true,
+        })
+    }
+
+    fn finished(mut span: Span, start_ms: i64, duration_ms: i64, origin: DateTime<Utc>) -> Span {
+        span.start_time = origin + chrono::Duration::milliseconds(start_ms);
+        span.end_time = Some(span.start_time + chrono::Duration::milliseconds(duration_ms));
+        span.status = SpanStatus::Completed;
+        span
+    }
+
+    #[test]
+    fn test_flame_rows_follow_call_tree() {
+        let tracer = tracer();
+        let origin = Utc::now();
+        let root = tracer.start_span("agent.request").unwrap();
+        let second = tracer.start_child(&root, "tool.second").unwrap();
+        let first = tracer.start_child(&root, "tool.first").unwrap();
+        let rpc = tracer.start_child(&first, "mcp.request").unwrap();
+        assert_eq!(root.trace_id.len(), 32);
+        assert_eq!(rpc.span_id.len(), 16);
+
+        // 乱序输入，按调用树深度优先、兄弟按开始时间排列喵
+        let spans = vec![
+            finished(rpc, 12, 5, origin),
+            finished(second, 40, 10, origin),
+            finished(root, 0, 60, origin),
+            finished(first, 10, 20, origin),
+        ];
+        let rows = flame_rows(&spans);
+        let summary: Vec<(usize, &str, i64)> = rows
+            .iter()
+            .map(|r| (r.depth, r.span.name.as_str(), r.offset_ms))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, "agent.request", 0),
+                (1, "tool.first", 10),
+                (2, "mcp.request", 12),
+                (1, "tool.second", 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_otlp_export_includes_parent_and_links() {
+        let tracer = tracer();
+        let root = tracer.start_span("agent.request").unwrap();
+        let mut tool = tracer.start_child(&root, "tool.execute").unwrap();
+        tool.add_link(&root, "triggered_by");
+        tool.finish_with_error("boom");
+
+        let export = to_otlp_json(&[root.clone(), tool.clone()]);
+        let spans = &export["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert!(spans[0].get("parentSpanId").is_none());
+        assert_eq!(spans[1]["parentSpanId"], root.span_id);
+        assert_eq!(spans[1]["traceId"], root.trace_id);
+        assert_eq!(spans[1]["links"][0]["spanId"], root.span_id);
+        assert_eq!(spans[1]["links"][0]["attributes"][0]["value"]["stringValue"], "triggered_by");
+        assert_eq!(spans[1]["status"]["code"], 2);
+    }
+}
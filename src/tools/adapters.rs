@@ -15,7 +15,10 @@
 
 use super::mcp::{Tool, ToolDescription, ToolError, ToolResult};
 use super::shell::{ShellError, ShellRequest, ShellTool};
+use crate::core::{HistoryIndex, HistoryQuery};
+use crate::security::EscalationManager;
 use serde_json::json;
+use std::sync::{Arc, Mutex};
 
 /// 🔒 SAFETY: MCP 兼容的 Shell 工具喵
 pub struct McpShellTool {
@@ -38,9 +41,20 @@ impl McpShellTool {
 impl Tool for McpShellTool {
     /// 🔒 SAFETY: 获取工具描述喵
     fn describe(&self) -> ToolDescription {
+        let limits = self.inner.limits();
+        let mut allowed = self.inner.allowed_commands();
+        allowed.sort();
+        let allowed = allowed.join(", ");
         ToolDescription {
             name: "shell".to_string(),
-            description: "Execute shell commands in a safe sandbox. Commands must be in the allowlist.".to_string(),
+            description: format!(
+                "Run an allowlisted command (e.g. `git status`) in a sandbox. Allowed commands: {}. \
+                Output longer than {} bytes per stream is truncated (head and tail kept); \
+                commands are stopped after their timeout (at most {}s).",
+                allowed,
+                limits.max_output_bytes,
+                limits.max_timeout_secs
+            ),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -57,8 +71,18 @@ impl Tool for McpShellTool {
                     },
                     "timeout": {
                         "type": "integer",
-                        "description": "Timeout in seconds (default: 30)",
-                        "default": 30
+                        "description": format!(
+                            "Timeout in seconds (default: {}, max: {})",
+                            limits.default_timeout_secs, limits.max_timeout_secs
+                        )
+                    },
+                    "work_dir": {
+                        "type": "string",
+                        "description": "Working directory (must be an allowed path; default: the workspace)"
+                    },
+                    "override_token": {
+                        "type": "string",
+                        "description": "One-time token from an approved request_escalation; only valid for the exact command and args that were blocked"
                     }
                 },
                 "required": ["command"]
@@ -111,6 +135,13 @@ impl Tool for McpShellTool {
             }
         }
 
+        // 验证 work_dir 与 override_token 字段
+        for field in ["work_dir", "override_token"] {
+            if input.get(field).is_some_and(|v| !v.is_string()) {
+                return Err(ToolError::ValidationError(format!("'{}' must be a string", field)));
+            }
+        }
+
         Ok(())
     }
 
@@ -133,25 +164,41 @@ impl Tool for McpShellTool {
         let timeout_secs: u64 = input
             .get("timeout")
             .and_then(|t| t.as_u64())
-            .unwrap_or(30);
+            .unwrap_or_else(|| self.inner.limits().default_timeout(command));
 
         // 创建 ShellRequest
-        let mut request = ShellRequest::default();
-        request.command = command.to_string();
-        request.args = args;
-        request.timeout_secs = timeout_secs;
+        let request = ShellRequest {
+            command: command.to_string(),
+            args,
+            timeout_secs,
+            work_dir: input.get("work_dir").and_then(|w| w.as_str()).map(String::from),
+            // 交给 ShellTool 显式拒绝，而不是静默丢弃喵
+            env: input.get("env").and_then(|e| e.as_object()).map(|vars| {
+                vars.iter()
+                    .map(|(k, v)| (k.clone(), v.as_str().map(String::from).unwrap_or_else(|| v.to_string())))
+                    .collect()
+            }),
+            override_token: input.get("override_token").and_then(|t| t.as_str()).map(String::from),
+        };
 
         // 执行
-        let shell_result = self.inner.execute(request).await.map_err(|e| {
-            ToolError::ExecutionFailed(match e {
-                ShellError::CommandNotAllowed(cmd) => {
-                    format!("Command '{}' is not in allowlist", cmd)
-                }
-                ShellError::ExecutionFailed(msg) => format!("Execution failed: {}", msg),
-                ShellError::Timeout(secs) => format!("Command timed out after {}s", secs),
-                ShellError::PathTraversal => "Path traversal detected".to_string(),
-                ShellError::Io(err) => format!("IO error: {}", err),
-            })
+        let shell_result = self.inner.execute(request).await.map_err(|e| match e {
+            ShellError::PolicyDenied(violation) => ToolError::PolicyDenied(violation),
+            ShellError::CommandNotAllowed(cmd) => {
+                ToolError::ExecutionFailed(format!("Command '{}' is not in allowlist", cmd))
+            }
+            ShellError::ExecutionFailed(msg) => {
+                ToolError::ExecutionFailed(format!("Execution failed: {}", msg))
+            }
+            ShellError::Timeout(secs) => ToolError::ExecutionFailed(format!(
+                "Command timed out after {}s and was stopped; retry with a larger 'timeout' \
+                if it legitimately needs longer, or narrow the command down",
+                secs
+            )),
+            ShellError::PathTraversal => {
+                ToolError::ExecutionFailed("Path traversal detected".to_string())
+            }
+            ShellError::Io(err) => ToolError::ExecutionFailed(format!("IO error: {}", err)),
         })?;
 
         // 转换结果
@@ -160,13 +207,202 @@ impl Tool for McpShellTool {
             "stdout": shell_result.stdout,
             "stderr": shell_result.stderr,
             "duration_ms": shell_result.duration_ms,
-            "success": shell_result.success
+            "success": shell_result.success,
+            "truncated": shell_result.truncated
         });
 
         Ok(ToolResult::success(data, start.elapsed().as_millis() as u64))
     }
 }
 
+/// 🔒 SAFETY: 放行申请工具喵
+/// 被策略拦截后，Agent 可以用它向主人申请一次性放行
+pub struct EscalationTool {
+    manager: Arc<EscalationManager>,
+}
+
+impl EscalationTool {
+    pub fn new(manager: Arc<EscalationManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for EscalationTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "request_escalation".to_string(),
+            description: "Ask Master to approve an action that was blocked by policy (only when POLICY_DENIED says escalation_possible). Call again after Master approves to receive an override_token, then retry the exact same call once with it.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "tool": {
+                        "type": "string",
+                        "description": "Name of the tool whose call was blocked"
+                    },
+                    "policy": {
+                        "type": "string",
+                        "description": "Policy name from the POLICY_DENIED result"
+                    },
+                    "blocked": {
+                        "type": "string",
+                        "description": "Blocked item from the POLICY_DENIED result"
+                    },
+                    "justification": {
+                        "type": "string",
+                        "description": "Why the action is needed"
+                    }
+                },
+                "required": ["tool", "policy", "blocked", "justification"]
+            }),
+            category: Some("security".to_string()),
+            dangerous: false,
+            required_permissions: None,
+        }
+    }
+
+    fn validate_input(&self, input: &serde_json::Value) -> Result<(), ToolError> {
+        for field in ["tool", "policy", "blocked", "justification"] {
+            if input.get(field).and_then(|v| v.as_str()).is_none() {
+                return Err(ToolError::ValidationError(format!(
+                    "Missing required field: '{}'",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+        let field = |name: &str| input.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+
+        let request = self
+            .manager
+            .submit(
+                field("tool"),
+                field("policy"),
+                field("blocked"),
+                field("justification"),
+            )
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let data = match &request.override_token {
+            Some(token) => json!({
+                "escalation_id": request.id,
+                "status": request.status,
+                "override_token": token,
+                "note": "Approved once. Retry the exact same call with this override_token."
+            }),
+            None => json!({
+                "escalation_id": request.id,
+                "status": request.status,
+                "note": "Master has been asked. Wait for approval, then call request_escalation again."
+            }),
+        };
+        Ok(ToolResult::success(
+            data,
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+}
+
+/// `history_search` 默认返回条数喵
+const HISTORY_DEFAULT_LIMIT: u64 = 5;
+/// `history_search` 最多返回条数喵
+const HISTORY_MAX_LIMIT: u64 = 20;
+
+/// 🔒 SAFETY: 检索过去的对话，让 Agent 准确引用之前聊过的内容喵
+pub struct HistorySearchTool {
+    index: Mutex<HistoryIndex>,
+}
+
+impl HistorySearchTool {
+    pub fn new(index: HistoryIndex) -> Self {
+        Self {
+            index: Mutex::new(index),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for HistorySearchTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "history_search".to_string(),
+            description: "Full-text search over past conversations with Master (all sessions). Use it before answering questions about what was discussed or decided earlier, and quote the excerpts instead of guessing. All space-separated terms must match; matches are wrapped in **.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search terms, e.g. \"nginx timeout\""
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "Only search this session (ID or ID prefix)"
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Only messages newer than this window, e.g. 12h, 7d, 2w"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of matches (default 5, max 20)"
+                    }
+                },
+                "required": ["query"]
+            }),
+            category: Some("memory".to_string()),
+            dangerous: false,
+            required_permissions: None,
+        }
+    }
+
+    fn validate_input(&self, input: &serde_json::Value) -> Result<(), ToolError> {
+        match input.get("query").and_then(|v| v.as_str()) {
+            Some(query) if !query.trim().is_empty() => Ok(()),
+            _ => Err(ToolError::ValidationError(
+                "Missing required field: 'query'".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+        let field = |name: &str| input.get(name).and_then(|v| v.as_str());
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(HISTORY_DEFAULT_LIMIT)
+            .clamp(1, HISTORY_MAX_LIMIT);
+
+        let mut query = HistoryQuery::new(field("query").unwrap_or_default(), limit as usize);
+        if let Some(session) = field("session") {
+            query = query.with_session(session);
+        }
+        if let Some(since) = field("since") {
+            let since = crate::memory::parse_since(since, chrono::Utc::now()).map_err(ToolError::ValidationError)?;
+            query = query.with_since(since);
+        }
+
+        let hits = self
+            .index
+            .lock()
+            .unwrap()
+            .search(&query, "**", "**")
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        Ok(ToolResult::success(
+            json!({
+                "count": hits.len(),
+                "matches": hits,
+            }),
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+}
+
 /// 🔒 SAFETY: Echo 工具（测试用）喵
 pub struct EchoTool;
 
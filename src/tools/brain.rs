@@ -16,7 +16,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::{mpsc, RwLock};
-use tracing::{info, warn};
+use tracing::info;
 use uuid::Uuid;
 
 /// 🔒 SAFETY: 消息类型枚举喵
@@ -158,8 +158,6 @@ struct BrainState {
     agents: HashMap<String, AgentInfo>,
     /// 消息通道（agent_id -> sender）
     message_channels: HashMap<String, mpsc::UnboundedSender<AgentMessage>>,
-    /// 子 Agents（session_key -> agent_id）
-    sub_agents: HashMap<String, String>,
 }
 
 /// 🔒 SAFETY: Brain 工具结构体喵
@@ -178,7 +176,6 @@ impl BrainTool {
         let state = Arc::new(RwLock::new(BrainState {
             agents: HashMap::new(),
             message_channels: HashMap::new(),
-            sub_agents: HashMap::new(),
         }));
 
         Self {
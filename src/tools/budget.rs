@@ -0,0 +1,174 @@
+//! # Tool Budget ⛽
+//!
+//! 每个对话的工具调用预算喵
+//!
+//! 在循环次数上限之外再限制:
+//! - 工具调用总次数
+//! - Shell 执行次数
+//! - 写入文件的总字节数
+//!
+//! 预算耗尽时 `ToolRegistry` 直接拒绝调用，并明确告诉模型收尾，而不是反复重试喵
+//!
+//! ```toml
+//! [tool_budget]
+//! max_calls = 50
+//! max_shell_calls = 20
+//! max_bytes_written = 10485760
+//! ```
+//!
+//! 任一项设为 0 表示不限制喵
+
+use super::mcp::{ToolDescription, ToolError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Mutex;
+
+fn default_max_calls() -> u32 {
+    50
+}
+fn default_max_shell_calls() -> u32 {
+    20
+}
+fn default_max_bytes_written() -> u64 {
+    10 * 1024 * 1024
+}
+
+/// 🔒 SAFETY: 工具预算配置喵（0 表示不限制）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolBudgetConfig {
+    /// 工具调用总次数上限
+    #[serde(default = "default_max_calls")]
+    pub max_calls: u32,
+    /// Shell 执行次数上限
+    #[serde(default = "default_max_shell_calls")]
+    pub max_shell_calls: u32,
+    /// 写入文件的总字节数上限
+    #[serde(default = "default_max_bytes_written")]
+    pub max_bytes_written: u64,
+}
+
+impl Default for ToolBudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_calls: default_max_calls(),
+            max_shell_calls: default_max_shell_calls(),
+            max_bytes_written: default_max_bytes_written(),
+        }
+    }
+}
+
+/// 已使用的预算喵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BudgetUsage {
+    pub calls: u32,
+    pub shell_calls: u32,
+    pub bytes_written: u64,
+}
+
+/// 🔒 SAFETY: 单个对话的工具预算喵
+#[derive(Debug)]
+pub struct ToolBudget {
+    config: ToolBudgetConfig,
+    used: Mutex<BudgetUsage>,
+}
+
+impl ToolBudget {
+    pub fn new(config: ToolBudgetConfig) -> Self {
+        Self {
+            config,
+            used: Mutex::new(BudgetUsage::default()),
+        }
+    }
+
+    /// 当前用量喵
+    pub fn usage(&self) -> BudgetUsage {
+        *self.used.lock().unwrap()
+    }
+
+    /// 🔒 SAFETY: 扣除一次调用的预算喵（超出任一上限时不扣除并返回错误）
+    ///
+    /// Shell 调用按 `shell.execute` 权限识别，写入字节按 `fs.write` 工具的 `content` 长度计算喵
+    pub fn charge(&self, tool: &ToolDescription, input: &JsonValue) -> Result<(), ToolError> {
+        let has_permission = |permission: &str| {
+            tool.required_permissions
+                .as_ref()
+                .is_some_and(|p| p.iter().any(|p| p == permission))
+        };
+        let is_shell = has_permission("shell.execute");
+        let bytes = match has_permission("fs.write") {
+            true => input.get("content").and_then(|c| c.as_str()).map_or(0, |c| c.len() as u64),
+            false => 0,
+        };
+
+        let mut used = self.used.lock().unwrap();
+        let exceeds = |limit: u64, after: u64| limit > 0 && after > limit;
+        if exceeds(self.config.max_calls as u64, used.calls as u64 + 1) {
+            return Err(ToolError::BudgetExhausted(format!(
+                "all {} tool calls for this conversation have been used",
+                self.config.max_calls
+            )));
+        }
+        if is_shell && exceeds(self.config.max_shell_calls as u64, used.shell_calls as u64 + 1) {
+            return Err(ToolError::BudgetExhausted(format!(
+                "all {} shell invocations for this conversation have been used",
+                self.config.max_shell_calls
+            )));
+        }
+        if exceeds(self.config.max_bytes_written, used.bytes_written + bytes) {
+            return Err(ToolError::BudgetExhausted(format!(
+                "writing {} more bytes would exceed the {} byte write budget ({} already written)",
+                bytes, self.config.max_bytes_written, used.bytes_written
+            )));
+        }
+
+        used.calls += 1;
+        used.shell_calls += is_shell as u32;
+        used.bytes_written += bytes;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, permission: Option<&str>) -> ToolDescription {
+        ToolDescription {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({}),
+            category: None,
+            dangerous: false,
+            required_permissions: permission.map(|p| vec![p.to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_budget_limits() {
+        let budget = ToolBudget::new(ToolBudgetConfig {
+            max_calls: 4,
+            max_shell_calls: 1,
+            max_bytes_written: 10,
+        });
+        let shell = tool("shell", Some("shell.execute"));
+        let write = tool("fs_write", Some("fs.write"));
+
+        budget.charge(&shell, &json!({"command": "ls"})).unwrap();
+        assert!(matches!(
+            budget.charge(&shell, &json!({"command": "ls"})),
+            Err(ToolError::BudgetExhausted(_))
+        ));
+        budget.charge(&write, &json!({"path": "a", "content": "12345678"})).unwrap();
+        // 超出写入预算的调用不扣除次数喵
+        assert!(budget.charge(&write, &json!({"path": "b", "content": "123"})).is_err());
+        budget.charge(&write, &json!({"path": "b", "content": "12"})).unwrap();
+        assert_eq!(
+            budget.usage(),
+            BudgetUsage { calls: 3, shell_calls: 1, bytes_written: 10 }
+        );
+
+        budget.charge(&tool("echo", None), &json!({})).unwrap();
+        assert!(budget.charge(&tool("echo", None), &json!({})).is_err());
+    }
+}
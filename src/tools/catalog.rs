@@ -0,0 +1,166 @@
+/// 工具能力目录 📇
+///
+/// 把注册表中的工具与已加载的 Skills 汇总成机器可读的目录，
+/// 供 `nekoclaw tools list --format json` 与网关 `GET /v1/tools` 输出，
+/// 外部 UI 和审计可以据此准确列出 Agent 能做的事情喵
+///
+/// 🔒 SAFETY: 目录只包含描述信息，不包含任何执行句柄
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use super::mcp::{ToolDescription, ToolRegistry};
+use crate::skills::Skill;
+
+/// 🔒 SAFETY: 能力的来源喵
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum ToolSource {
+    /// 内置工具
+    Builtin,
+    /// 工作区 skills 目录下的技能
+    Skill,
+    /// 外部 MCP server 提供的工具
+    Mcp { server: String },
+    /// 插件提供的工具（预留）
+    Plugin { plugin: String },
+}
+
+impl ToolSource {
+    /// 🔒 SAFETY: 来源名称喵（builtin / skill / mcp / plugin）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolSource::Builtin => "builtin",
+            ToolSource::Skill => "skill",
+            ToolSource::Mcp { .. } => "mcp",
+            ToolSource::Plugin { .. } => "plugin",
+        }
+    }
+}
+
+/// 🔒 SAFETY: 目录中的一项能力喵
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub description: String,
+    /// 输入参数（JSON Schema）
+    pub input_schema: JsonValue,
+    pub category: Option<String>,
+    /// 执行前是否需要确认
+    pub dangerous: bool,
+    pub required_permissions: Vec<String>,
+    #[serde(flatten)]
+    pub source: ToolSource,
+}
+
+impl CatalogEntry {
+    /// 🔒 SAFETY: 由工具描述构造喵
+    pub fn from_description(description: ToolDescription, source: ToolSource) -> Self {
+        Self {
+            name: description.name,
+            description: description.description,
+            input_schema: description.input_schema,
+            category: description.category,
+            dangerous: description.dangerous,
+            required_permissions: description.required_permissions.unwrap_or_default(),
+            source,
+        }
+    }
+
+    /// 🔒 SAFETY: 由 Skill 构造喵
+    ///
+    /// 带执行命令的 Skill 会在本机运行命令，视为危险操作
+    pub fn from_skill(skill: &Skill) -> Self {
+        let properties: serde_json::Map<String, JsonValue> = skill
+            .parameters
+            .iter()
+            .map(|p| {
+                let mut schema = json!({ "type": "string", "description": p.description });
+                if let Some(default) = &p.default {
+                    schema["default"] = json!(default);
+                }
+                (p.name.clone(), schema)
+            })
+            .collect();
+        let required: Vec<&str> = skill
+            .parameters
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.name.as_str())
+            .collect();
+        Self {
+            name: skill.name.clone(),
+            description: skill.description.clone(),
+            input_schema: json!({ "type": "object", "properties": properties, "required": required }),
+            category: Some("skill".to_string()),
+            dangerous: skill.command.is_some(),
+            required_permissions: Vec::new(),
+            source: ToolSource::Skill,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 工具与技能目录喵（按名称排序，输出稳定）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolCatalog {
+    pub tools: Vec<CatalogEntry>,
+}
+
+impl ToolCatalog {
+    /// 🔒 SAFETY: 由注册表构造喵
+    pub fn from_registry(registry: &ToolRegistry) -> Self {
+        let mut catalog = Self {
+            tools: registry.catalog_entries(),
+        };
+        catalog.sort();
+        catalog
+    }
+
+    /// 🔒 SAFETY: 加入已加载的 Skills 喵
+    pub fn with_skills(mut self, skills: &[Skill]) -> Self {
+        self.tools.extend(skills.iter().map(CatalogEntry::from_skill));
+        self.sort();
+        self
+    }
+
+    fn sort(&mut self) {
+        self.tools.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.source.as_str().cmp(b.source.as_str())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skills::SkillParameter;
+    use crate::tools::EchoTool;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_catalog_json() {
+        let mut registry = ToolRegistry::new();
+        registry.register(EchoTool).unwrap();
+        let skill = Skill {
+            name: "deploy".to_string(),
+            description: "Deploy the site".to_string(),
+            path: PathBuf::from("skills/deploy"),
+            command: Some("./deploy.sh".to_string()),
+            parameters: vec![SkillParameter {
+                name: "env".to_string(),
+                description: "Target environment".to_string(),
+                required: true,
+                default: None,
+            }],
+        };
+
+        let catalog = ToolCatalog::from_registry(&registry).with_skills(&[skill]);
+        let value = serde_json::to_value(&catalog).unwrap();
+        let tools = value["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0]["name"], "deploy");
+        assert_eq!(tools[0]["source"], "skill");
+        assert_eq!(tools[0]["dangerous"], true);
+        assert_eq!(tools[0]["input_schema"]["required"], json!(["env"]));
+        assert_eq!(tools[1]["name"], "echo");
+        assert_eq!(tools[1]["source"], "builtin");
+        assert!(tools[1]["input_schema"].is_object());
+    }
+}
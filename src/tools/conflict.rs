@@ -0,0 +1,195 @@
+//! # Write Conflict Detection ✋
+//!
+//! 防止 `fs_write` 悄悄覆盖主人在 Agent 读取之后做的修改喵
+//!
+//! - `fs_read` / `fs_write` 把看到的文件内容与 mtime 记进 `ReadTracker`
+//! - 再次写入前比较磁盘上的当前状态：mtime 与大小不变直接放行，否则再比较内容
+//! - 内容不同则拒绝写入，把 diff 交给模型决定合并还是放弃
+//!
+//! 冲突报告之后当前内容即视为"已读"，模型带着合并后的内容重新调用 `fs_write` 即可写入；
+//! 如果文件在此期间又被改动，会再次报告冲突喵
+
+use super::mcp::ToolError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// diff 最多展示的行数
+const MAX_DIFF_LINES: usize = 200;
+
+/// 超过此行数的文件不计算逐行 diff（LCS 是平方复杂度）
+const MAX_DIFF_INPUT_LINES: usize = 2_000;
+
+/// Agent 上次看到的文件状态喵
+#[derive(Debug, Clone)]
+struct Snapshot {
+    modified: Option<SystemTime>,
+    content: String,
+}
+
+/// 🔒 SAFETY: 单个会话中 Agent 看到过的文件喵
+#[derive(Debug, Default)]
+pub struct ReadTracker {
+    seen: Mutex<HashMap<PathBuf, Snapshot>>,
+}
+
+impl ReadTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录 Agent 读取或写入后的文件内容喵
+    pub fn record(&self, full_path: &Path, content: &str) {
+        let modified = std::fs::metadata(full_path).and_then(|m| m.modified()).ok();
+        self.seen.lock().unwrap().insert(
+            normalize(full_path),
+            Snapshot {
+                modified,
+                content: content.to_string(),
+            },
+        );
+    }
+
+    /// 🔒 SAFETY: 写入前检查文件是否在 Agent 看到之后被改动喵
+    ///
+    /// 没看过的文件（新建或盲写）不检查；有冲突时返回带 diff 的 `WriteConflict`，
+    /// 并把当前内容记为已看到，之后带着合并结果重写即可通过喵
+    pub fn check_write(&self, full_path: &Path, display_path: &str) -> Result<(), ToolError> {
+        let key = normalize(full_path);
+        let mut seen = self.seen.lock().unwrap();
+        let Some(snapshot) = seen.get(&key) else {
+            return Ok(());
+        };
+
+        let Ok(metadata) = std::fs::metadata(full_path) else {
+            seen.remove(&key);
+            return Err(ToolError::WriteConflict(format!(
+                "{} was deleted after you last read it",
+                display_path
+            )));
+        };
+        let untouched = snapshot.modified.is_some() && metadata.modified().ok() == snapshot.modified;
+        if untouched && metadata.len() == snapshot.content.len() as u64 {
+            return Ok(());
+        }
+        let current = std::fs::read_to_string(full_path).unwrap_or_default();
+        if current == snapshot.content {
+            return Ok(());
+        }
+
+        let diff = line_diff(&snapshot.content, &current);
+        seen.insert(
+            key,
+            Snapshot {
+                modified: metadata.modified().ok(),
+                content: current,
+            },
+        );
+        Err(ToolError::WriteConflict(format!(
+            "{} changed after you last read it (- what you read, + what is on disk now):\n{}",
+            display_path, diff
+        )))
+    }
+}
+
+/// 已存在的文件用规范路径作为键，`a.txt` 与 `./a.txt` 视为同一文件喵
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 逐行 diff（`-` 旧内容，`+` 新内容，保留一行上下文）喵
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len() > MAX_DIFF_INPUT_LINES || new.len() > MAX_DIFF_INPUT_LINES {
+        return format!(
+            "(file too large for a line diff: {} lines before, {} lines now; read it again with fs_read)",
+            old.len(),
+            new.len()
+        );
+    }
+
+    // LCS 表：lcs[i][j] = old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', old[i]));
+            i += 1;
+        } else {
+            ops.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    // 只保留改动行及其前后一行喵
+    let changed: Vec<bool> = ops.iter().map(|(op, _)| *op != ' ').collect();
+    let keep = |k: usize| changed[k.saturating_sub(1)..(k + 2).min(ops.len())].iter().any(|c| *c);
+    let mut out = Vec::new();
+    let mut skipped = false;
+    for (k, (op, line)) in ops.iter().enumerate() {
+        if !keep(k) {
+            skipped = true;
+            continue;
+        }
+        if skipped && !out.is_empty() {
+            out.push("...".to_string());
+        }
+        skipped = false;
+        out.push(format!("{}{}", op, line));
+    }
+    if out.len() > MAX_DIFF_LINES {
+        let more = out.len() - MAX_DIFF_LINES;
+        out.truncate(MAX_DIFF_LINES);
+        out.push(format!("... ({} more diff lines)", more));
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc\nd\ne\nf\n", "a\nb\nC\nd\ne\nf\ng\n");
+        assert_eq!(diff, " b\n-c\n+C\n d\n...\n f\n+g");
+    }
+
+    #[test]
+    fn test_conflict_detected_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "agent saw this\n").unwrap();
+
+        let tracker = ReadTracker::new();
+        assert!(tracker.check_write(&path, "notes.md").is_ok());
+        tracker.record(&path, "agent saw this\n");
+        assert!(tracker.check_write(&path, "notes.md").is_ok());
+
+        // 主人在读取之后改了文件喵
+        std::fs::write(&path, "master edited this\n").unwrap();
+        let Err(ToolError::WriteConflict(detail)) = tracker.check_write(&path, "notes.md") else {
+            panic!("expected a write conflict");
+        };
+        assert!(detail.contains("-agent saw this\n+master edited this"));
+        // 冲突报告后视为已看到，合并后的重写可以通过喵
+        assert!(tracker.check_write(&path, "notes.md").is_ok());
+    }
+}
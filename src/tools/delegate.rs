@@ -0,0 +1,516 @@
+//! # Sub-Agent Delegation
+//!
+//! 🧬 把子任务交给独立的子 Agent 完成喵
+//!
+//! @诺诺 的子 Agent 委派工具实现喵
+//!
+//! ## 功能
+//! - `@delegate({"task": "总结 README", "agent": "muse"})` 启动一个子 Agent 执行任务，
+//!   子 Agent 的最终回复作为工具结果返回给父对话
+//! - `agent` 选择 `agents.agent.<name>` 的人设（模型 / 提示词 / 工具 / 限额），`model` 可单独覆盖模型
+//! - 子 Agent 可以使用父 Agent 的工具（同一份工具预算与确认策略），按 `max_depth` 限制嵌套委派
+//! - 每个子 Agent 受 `max_rounds` 工具轮数与 `timeout_secs` 超时约束
+//! - 所有子 Agent 的 token 用量合并统计，可用 `token_budget` 限制总量，并计入父会话的费用
+//! - 每次委派通过 `BrainTool::spawn_sub_agent` 登记，结果中返回子 Agent 的会话 ID
+//!
+//! ```toml
+//! [delegation]
+//! max_depth = 2
+//! max_rounds = 5
+//! timeout_secs = 300
+//! token_budget = 50000
+//! ```
+//!
+//! 🔒 SAFETY: 子 Agent 的工具调用与父 Agent 走同一个注册器，危险工具同样需要确认
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use super::brain::{BrainTool, SubAgentConfig};
+use super::mcp::{
+    format_tool_error_for_llm, format_tool_result_for_llm, parse_tool_calls, Tool, ToolDescription, ToolError,
+    ToolRegistry, ToolResult,
+};
+use super::pager::OutputPager;
+use super::prompt::{format_tools_compact, render_tools_prompt};
+use crate::core::{AgentProfile, FewShotLibrary};
+use crate::providers::{ChatProvider, ChatRequest, CostTracker, Message, Usage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 工具名称
+const DELEGATE_TOOL: &str = "delegate";
+
+/// 未选择 Agent 人设时子 Agent 的系统提示词
+const SUB_AGENT_PROMPT: &str = "You are a sub-agent working on one task delegated by another agent. \
+Complete the task on your own and reply with the final result only; your reply is returned to the delegating agent.";
+
+fn default_max_depth() -> usize {
+    2
+}
+
+fn default_max_rounds() -> usize {
+    5
+}
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
+/// 🔒 SAFETY: 委派配置喵（`[delegation]`，未配置时不提供 `delegate` 工具）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegateConfig {
+    /// 最多嵌套几层子 Agent（1 = 子 Agent 不能再委派）
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// 每个子 Agent 最多的工具调用轮数
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: usize,
+    /// 单次委派的超时（秒，请求中的 `timeout_seconds` 不能超过它）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 子 Agent 单次回复的 token 上限
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// 本会话所有子 Agent 合计的 token 上限
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+}
+
+impl Default for DelegateConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_depth(),
+            max_rounds: default_max_rounds(),
+            timeout_secs: default_timeout_secs(),
+            max_tokens: None,
+            token_budget: None,
+        }
+    }
+}
+
+/// 🔒 SAFETY: 子 Agent 的合计用量喵（嵌套的子 Agent 共享同一份）
+#[derive(Debug, Default)]
+pub struct DelegationUsage {
+    requests: AtomicU64,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+}
+
+impl DelegationUsage {
+    fn record(&self, usage: &Usage) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.prompt_tokens.fetch_add(u64::from(usage.prompt_tokens), Ordering::Relaxed);
+        self.completion_tokens.fetch_add(u64::from(usage.completion_tokens), Ordering::Relaxed);
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn prompt_tokens(&self) -> u64 {
+        self.prompt_tokens.load(Ordering::Relaxed)
+    }
+
+    pub fn completion_tokens(&self) -> u64 {
+        self.completion_tokens.load(Ordering::Relaxed)
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens() + self.completion_tokens()
+    }
+}
+
+/// 🔒 SAFETY: 子 Agent 委派工具喵
+#[derive(Clone)]
+pub struct DelegateTool {
+    client: Arc<dyn ChatProvider>,
+    /// 未指定人设与模型时使用的模型
+    model: String,
+    config: DelegateConfig,
+    /// 子 Agent 可用的工具（不含 delegate 本身）
+    tools: ToolRegistry,
+    profiles: BTreeMap<String, AgentProfile>,
+    cost_tracker: Option<Arc<CostTracker>>,
+    brain: BrainTool,
+    /// 发起委派的 Agent
+    parent: String,
+    /// 当前委派层级（父对话中的工具为 1）
+    depth: usize,
+    usage: Arc<DelegationUsage>,
+    /// 子 Agent 工具输出的分页器（与父对话共享游标）
+    pager: Option<Arc<OutputPager>>,
+}
+
+impl DelegateTool {
+    /// 🔒 SAFETY: 创建委派工具喵
+    ///
+    /// ## Arguments
+    /// * `client` - 子 Agent 使用的 Provider 客户端喵
+    /// * `model` - 默认模型（通常与父 Agent 相同）喵
+    pub fn new(client: Arc<dyn ChatProvider>, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            config: DelegateConfig::default(),
+            tools: ToolRegistry::new(),
+            profiles: BTreeMap::new(),
+            cost_tracker: None,
+            brain: BrainTool::new(Vec::new()),
+            parent: "system".to_string(),
+            depth: 1,
+            usage: Arc::default(),
+            pager: None,
+        }
+    }
+
+    pub fn with_config(mut self, config: DelegateConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 🔒 SAFETY: 子 Agent 可用的工具喵（克隆的注册器共享预算与确认策略）
+    pub fn with_tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// 可选择的 Agent 人设喵
+    pub fn with_profiles(mut self, profiles: BTreeMap<String, AgentProfile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// 🔒 SAFETY: 子 Agent 的费用计入父会话，并受父会话预算约束喵
+    pub fn with_cost_tracker(mut self, tracker: Arc<CostTracker>) -> Self {
+        self.cost_tracker = Some(tracker);
+        self
+    }
+
+    /// 通过 Brain 登记子 Agent 喵
+    pub fn with_brain(mut self, brain: BrainTool, parent: &str) -> Self {
+        self.brain = brain;
+        self.parent = parent.to_string();
+        self
+    }
+
+    /// 🔒 SAFETY: 子 Agent 的超长工具输出同样分页喵
+    pub fn with_output_pager(mut self, pager: Arc<OutputPager>) -> Self {
+        self.pager = Some(pager);
+        self
+    }
+
+    /// 所有子 Agent 的合计用量喵
+    pub fn usage(&self) -> Arc<DelegationUsage> {
+        self.usage.clone()
+    }
+
+    /// 下一层子 Agent 使用的委派工具喵
+    fn child(&self, parent: &str) -> Self {
+        Self {
+            parent: parent.to_string(),
+            depth: self.depth + 1,
+            ..self.clone()
+        }
+    }
+
+    /// 🔒 SAFETY: 请求前检查合计 token 与父会话预算喵
+    fn check_budget(&self) -> Result<(), ToolError> {
+        if let Some(budget) = self.config.token_budget {
+            let used = self.usage.total_tokens();
+            if used >= budget {
+                return Err(ToolError::BudgetExhausted(format!(
+                    "sub-agents used {} of {} delegation tokens",
+                    used, budget
+                )));
+            }
+        }
+        if let Some(tracker) = &self.cost_tracker {
+            tracker.check().map_err(|e| ToolError::BudgetExhausted(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 子 Agent 的对话循环喵
+    ///
+    /// ## Returns
+    /// (最终回复, 使用的模型)
+    async fn run(&self, spec: &SubAgentConfig, session: &str) -> Result<(String, String), ToolError> {
+        let profile = spec.label.as_ref().and_then(|name| self.profiles.get(name));
+        let model = spec
+            .model
+            .clone()
+            .or_else(|| profile.and_then(|p| p.model.clone()))
+            .unwrap_or_else(|| self.model.clone());
+
+        let mut tools = self.tools.clone();
+        tools.retain(|name| name != DELEGATE_TOOL && profile.is_none_or(|p| p.allows_tool(name)));
+        if self.depth < self.config.max_depth && profile.is_none_or(|p| p.allows_tool(DELEGATE_TOOL)) {
+            let _ = tools.register(self.child(session));
+        }
+
+        let mut system = profile
+            .and_then(|p| p.prompts.system.clone())
+            .unwrap_or_else(|| SUB_AGENT_PROMPT.to_string());
+        let descriptions = tools.all_descriptions();
+        if !descriptions.is_empty() {
+            // Agent 配置了 tool_prompt 时按其模式渲染，否则使用精简格式喵
+            let rendered = match profile.and_then(|p| p.tool_prompt.as_ref()) {
+                Some(config) => render_tools_prompt(&descriptions, config).0,
+                None => format_tools_compact(&descriptions),
+            };
+            system.push_str(&format!(
+                "\n\nAvailable Tools:\n{}\nCall tools as @tool_name({{\"key\": \"value\"}}).",
+                rendered
+            ));
+        }
+        if let Some(suffix) = profile.and_then(|p| p.prompts.suffix.as_ref()) {
+            system.push_str(&format!("\n\n{}", suffix));
+        }
+        let mut messages = vec![Message::system(system)];
+        if let Some(few_shot) = profile.and_then(|p| p.few_shot.clone()) {
+            messages.extend(FewShotLibrary::new(few_shot).select_messages());
+        }
+        messages.push(Message::user(spec.task.clone()));
+
+        let max_rounds = profile.map_or(self.config.max_rounds, |p| p.tool_rounds().min(self.config.max_rounds));
+        let max_tokens = match (self.config.max_tokens, profile.and_then(|p| p.limits.max_tokens)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        for round in 0..=max_rounds {
+            self.check_budget()?;
+            let request = ChatRequest {
+                model: Some(model.clone()),
+                messages: messages.clone(),
+                temperature: None,
+                top_p: None,
+                max_tokens,
+                stream: Some(false),
+                tools: None,
+            };
+            let response = self
+                .client
+                .chat(&request)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Sub-agent request failed: {}", e)))?;
+            self.usage.record(&response.usage);
+            if let Some(tracker) = &self.cost_tracker {
+                let charged = if response.model.is_empty() { &model } else { &response.model };
+                if let Err(e) = tracker.charge(charged, &response.usage) {
+                    warn!("Failed to record sub-agent cost: {}", e);
+                }
+            }
+
+            let reply = response
+                .choices
+                .into_iter()
+                .next()
+                .map(|c| c.message.content)
+                .unwrap_or_default();
+            let calls = parse_tool_calls(&reply);
+            if calls.is_empty() || round == max_rounds {
+                return Ok((reply, model));
+            }
+            messages.push(Message::assistant(reply));
+            for call in calls {
+                let result = match tools.execute(&call.tool_name, call.arguments).await {
+                    Ok(result) => format_tool_result_for_llm(&result, self.pager.as_deref()),
+                    Err(e) => format_tool_error_for_llm(&e),
+                };
+                messages.push(Message::user(format!("Tool result for {}: {}", call.tool_name, result)));
+            }
+        }
+        unreachable!("the last round always returns")
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for DelegateTool {
+    fn describe(&self) -> ToolDescription {
+        let agents: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        let mut properties = json!({
+            "task": {
+                "type": "string",
+                "description": "Self-contained task description, including all context the sub-agent needs"
+            },
+            "model": {
+                "type": "string",
+                "description": "Model for the sub-agent (default: the agent profile's or the current model)"
+            },
+            "timeout_seconds": {
+                "type": "integer",
+                "description": format!("Timeout in seconds (max: {})", self.config.timeout_secs)
+            }
+        });
+        if !agents.is_empty() {
+            properties["agent"] = json!({
+                "type": "string",
+                "enum": agents,
+                "description": "Agent profile to run the task with"
+            });
+        }
+        ToolDescription {
+            name: DELEGATE_TOOL.to_string(),
+            description: "Delegate a bounded sub-task to a sub-agent and get its final answer back. \
+                The sub-agent starts with an empty conversation and can use the same tools."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": properties,
+                "required": ["task"]
+            }),
+            category: Some("agent".to_string()),
+            dangerous: false,
+            required_permissions: None,
+        }
+    }
+
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        match input.get("task").and_then(|t| t.as_str()) {
+            Some(task) if !task.trim().is_empty() => {}
+            _ => return Err(ToolError::ValidationError("Missing required field: 'task'".to_string())),
+        }
+        if let Some(agent) = input.get("agent") {
+            let name = agent
+                .as_str()
+                .ok_or_else(|| ToolError::ValidationError("'agent' must be a string".to_string()))?;
+            if !self.profiles.contains_key(name) {
+                return Err(ToolError::ValidationError(format!("Unknown agent '{}'", name)));
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+        let spec = SubAgentConfig {
+            task: input.get("task").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+            label: input.get("agent").and_then(|a| a.as_str()).map(String::from),
+            agent_id: Some(self.parent.clone()),
+            model: input.get("model").and_then(|m| m.as_str()).map(String::from),
+            thinking: None,
+            timeout_seconds: input.get("timeout_seconds").and_then(|t| t.as_u64()),
+        };
+        let timeout = spec
+            .timeout_seconds
+            .unwrap_or(self.config.timeout_secs)
+            .min(self.config.timeout_secs);
+        let session = self
+            .brain
+            .spawn_sub_agent(spec.clone())
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        info!("Delegating to sub-agent {} (depth {})", session, self.depth);
+
+        let (requests, prompt, completion) =
+            (self.usage.requests(), self.usage.prompt_tokens(), self.usage.completion_tokens());
+        let (reply, model) = tokio::time::timeout(Duration::from_secs(timeout), self.run(&spec, &session))
+            .await
+            .map_err(|_| {
+                warn!("Sub-agent {} timed out after {}s", session, timeout);
+                ToolError::Timeout
+            })??;
+
+        Ok(ToolResult::success(
+            json!({
+                "session": session,
+                "agent": spec.label,
+                "model": model,
+                "depth": self.depth,
+                "reply": reply,
+                "usage": {
+                    "requests": self.usage.requests() - requests,
+                    "prompt_tokens": self.usage.prompt_tokens() - prompt,
+                    "completion_tokens": self.usage.completion_tokens() - completion,
+                },
+            }),
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ChatResponse, ProviderError};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// 按脚本回复，记录每次请求的模型喵
+    #[derive(Debug, Default)]
+    struct ScriptedClient {
+        replies: Mutex<VecDeque<&'static str>>,
+        models: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChatProvider for ScriptedClient {
+        async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+            self.models.lock().unwrap().push(request.model.clone().unwrap_or_default());
+            let reply = self.replies.lock().unwrap().pop_front().unwrap_or("done");
+            Ok(serde_json::from_value(json!({
+                "id": "r",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "",
+                "choices": [{ "index": 0, "message": { "role": "assistant", "content": reply }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+            }))
+            .unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delegate_nests_until_max_depth_and_sums_usage() {
+        // 第一层子 Agent 再委派一次；第二层到达 max_depth，没有 delegate 工具可用喵
+        let client = Arc::new(ScriptedClient {
+            replies: Mutex::new(VecDeque::from([
+                r#"@delegate({"task": "inner"})"#,
+                r#"@delegate({"task": "too deep"})"#,
+                "inner result",
+                "outer result",
+            ])),
+            ..Default::default()
+        });
+        let mut profiles = BTreeMap::new();
+        profiles.insert(
+            "muse".to_string(),
+            AgentProfile {
+                model: Some("muse-model".to_string()),
+                ..Default::default()
+            },
+        );
+        let tool = DelegateTool::new(client.clone(), "main-model")
+            .with_config(DelegateConfig {
+                max_depth: 2,
+                ..Default::default()
+            })
+            .with_profiles(profiles);
+
+        assert!(tool.validate_input(&json!({ "task": " " })).is_err());
+        assert!(tool.validate_input(&json!({ "task": "x", "agent": "nobody" })).is_err());
+
+        let result = tool.execute(json!({ "task": "outer", "agent": "muse" })).await.unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["reply"], "outer result");
+        assert_eq!(data["model"], "muse-model");
+        assert_eq!(data["usage"]["requests"], 4);
+        assert_eq!(tool.usage().total_tokens(), 60);
+        // 嵌套的子 Agent 没有选择人设，使用默认模型喵
+        assert_eq!(*client.models.lock().unwrap(), ["muse-model", "main-model", "main-model", "muse-model"]);
+
+        let capped = tool.clone().with_config(DelegateConfig {
+            token_budget: Some(60),
+            ..Default::default()
+        });
+        assert!(matches!(
+            capped.execute(json!({ "task": "more" })).await,
+            Err(ToolError::BudgetExhausted(_))
+        ));
+    }
+}
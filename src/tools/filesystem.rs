@@ -12,16 +12,57 @@
 //!
 //! 🔒 SAFETY: 受路径遍历保护，操作限制在 workspace
 //!
+//! 挂载了会话临时工作区时，`fs_write` 默认写入临时目录，`fs_read` 优先读取临时目录喵
+//!
+//! 共享同一个 `ReadTracker` 时，`fs_write` 拒绝覆盖读取之后被改动的文件，并把 diff 交给模型喵
+//!
 //! Author: 诺诺 (Nono) ⚡
 
+use super::conflict::ReadTracker;
 use super::mcp::{Tool, ToolDescription, ToolError, ToolResult};
+use crate::security::{PolicyViolation, ScratchSpace};
 use serde_json::json;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 文件所在位置喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Scratch,
+    Workspace,
+}
+
+impl Location {
+    /// 解析 `location` 字段喵（未指定时返回 None）
+    fn from_input(input: &serde_json::Value) -> Result<Option<Self>, ToolError> {
+        match input.get("location").and_then(|l| l.as_str()) {
+            None => Ok(None),
+            Some("scratch") => Ok(Some(Self::Scratch)),
+            Some("workspace") => Ok(Some(Self::Workspace)),
+            Some(other) => Err(ToolError::ValidationError(format!(
+                "Invalid 'location': {} (expected 'scratch' or 'workspace')",
+                other
+            ))),
+        }
+    }
+}
+
+fn location_schema() -> serde_json::Value {
+    json!({
+        "type": "string",
+        "enum": ["scratch", "workspace"],
+        "description": "'scratch' is this conversation's private directory for intermediate files; 'workspace' is the shared workspace"
+    })
+}
 
 /// 🔒 SAFETY: FileSystem 工具喵
 pub struct FileSystemTool {
     /// 工作目录（限制访问范围）
     workspace: PathBuf,
+    /// 会话临时工作区
+    scratch: Option<ScratchSpace>,
+    /// 记录读到的内容（写入冲突检测）
+    tracker: Option<Arc<ReadTracker>>,
 }
 
 impl FileSystemTool {
@@ -29,28 +70,65 @@ impl FileSystemTool {
     pub fn new(workspace: &Path) -> Self {
         Self {
             workspace: workspace.to_path_buf(),
+            scratch: None,
+            tracker: None,
+        }
+    }
+
+    /// 同时允许读取会话临时工作区喵
+    pub fn with_scratch(mut self, scratch: ScratchSpace) -> Self {
+        self.scratch = Some(scratch);
+        self
+    }
+
+    /// 把读到的内容记进写入冲突检测喵（与 `FsWriteTool` 共享）
+    pub fn with_tracker(mut self, tracker: Arc<ReadTracker>) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+
+    /// 🔒 SAFETY: 按位置解析路径喵（未指定位置时，临时目录中存在该文件则优先读取）
+    fn locate(&self, path: &str, location: Option<Location>) -> Result<PathBuf, ToolError> {
+        match (location, &self.scratch) {
+            (Some(Location::Workspace), _) | (None, None) => self.resolve_path(&self.workspace, path),
+            (Some(Location::Scratch), None) => Err(ToolError::ValidationError(
+                "No scratch directory is available in this session".to_string(),
+            )),
+            (Some(Location::Scratch), Some(scratch)) => self.resolve_path(scratch.dir(), path),
+            (None, Some(scratch)) => {
+                let in_scratch = self.resolve_path(scratch.dir(), path)?;
+                if in_scratch.exists() {
+                    Ok(in_scratch)
+                } else {
+                    self.resolve_path(&self.workspace, path)
+                }
+            }
         }
     }
 
     /// 🔒 SAFETY: 解析路径（防止路径遍历）喵
-    fn resolve_path(&self, path: &str) -> Result<PathBuf, ToolError> {
+    fn resolve_path(&self, root: &Path, path: &str) -> Result<PathBuf, ToolError> {
         let input_path = Path::new(path);
 
         // 检测路径遍历攻击
         if path.contains("..") {
-            return Err(ToolError::Other("Path traversal detected".to_string()));
+            return Err(ToolError::PolicyDenied(
+                PolicyViolation::new("path_traversal", path, "parent directory references are not allowed")
+                    .with_suggestion("Use a path relative to the workspace without '..'"),
+            ));
         }
 
         // 构建完整路径
-        let full_path = self.workspace.join(input_path);
+        let full_path = root.join(input_path);
 
         // 确保在工作目录内 - 检查完整路径而不是输入路径
         let canonical_full = full_path.canonicalize().unwrap_or_else(|_| full_path.clone());
-        let canonical_workspace = self.workspace.canonicalize().unwrap_or_else(|_| self.workspace.clone());
+        let canonical_workspace = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
 
         if !canonical_full.starts_with(&canonical_workspace) {
-            return Err(ToolError::PermissionDenied(
-                "Access outside workspace not allowed".to_string(),
+            return Err(ToolError::PolicyDenied(
+                PolicyViolation::new("workspace_boundary", path, "access outside workspace not allowed")
+                    .with_suggestion("Use a path relative to the workspace"),
             ));
         }
 
@@ -70,7 +148,8 @@ impl Tool for FileSystemTool {
                     "path": {
                         "type": "string",
                         "description": "File path relative to workspace"
-                    }
+                    },
+                    "location": location_schema()
                 },
                 "required": ["path"]
             }),
@@ -105,12 +184,15 @@ impl Tool for FileSystemTool {
             .ok_or_else(|| ToolError::ValidationError("Invalid 'path' field".to_string()))?;
 
         // 解析并验证路径
-        let full_path = self.resolve_path(path)?;
+        let full_path = self.locate(path, Location::from_input(&input)?)?;
 
         // 读取文件
         let content = tokio::fs::read_to_string(&full_path)
             .await
             .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read file: {}", e)))?;
+        if let Some(tracker) = &self.tracker {
+            tracker.record(&full_path, &content);
+        }
 
         let data = json!({
             "path": path,
@@ -125,27 +207,49 @@ impl Tool for FileSystemTool {
 /// 🔒 SAFETY: 写文件工具喵
 pub struct FsWriteTool {
     workspace: PathBuf,
+    /// 会话临时工作区（默认写入位置）
+    scratch: Option<ScratchSpace>,
+    /// 写入冲突检测
+    tracker: Option<Arc<ReadTracker>>,
 }
 
 impl FsWriteTool {
     pub fn new(workspace: &Path) -> Self {
         Self {
             workspace: workspace.to_path_buf(),
+            scratch: None,
+            tracker: None,
         }
     }
 
-    fn resolve_path(&self, path: &str) -> Result<PathBuf, ToolError> {
+    /// 🔒 SAFETY: 默认写入会话临时工作区（受大小上限约束）喵
+    pub fn with_scratch(mut self, scratch: ScratchSpace) -> Self {
+        self.scratch = Some(scratch);
+        self
+    }
+
+    /// 🔒 SAFETY: 拒绝覆盖读取之后被改动的文件喵（与 `FileSystemTool` 共享）
+    pub fn with_tracker(mut self, tracker: Arc<ReadTracker>) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+
+    fn resolve_path(&self, root: &Path, path: &str) -> Result<PathBuf, ToolError> {
         if path.contains("..") {
-            return Err(ToolError::Other("Path traversal detected".to_string()));
+            return Err(ToolError::PolicyDenied(
+                PolicyViolation::new("path_traversal", path, "parent directory references are not allowed")
+                    .with_suggestion("Use a path relative to the workspace without '..'"),
+            ));
         }
 
-        let full_path = self.workspace.join(path);
+        let full_path = root.join(path);
         let canonical_input = full_path.canonicalize().unwrap_or(full_path.clone());
-        let canonical_workspace = self.workspace.canonicalize().unwrap_or(self.workspace.clone());
+        let canonical_workspace = root.canonicalize().unwrap_or(root.to_path_buf());
 
         if !canonical_input.starts_with(&canonical_workspace) {
-            return Err(ToolError::PermissionDenied(
-                "Access outside workspace not allowed".to_string(),
+            return Err(ToolError::PolicyDenied(
+                PolicyViolation::new("workspace_boundary", path, "access outside workspace not allowed")
+                    .with_suggestion("Use a path relative to the workspace"),
             ));
         }
 
@@ -158,7 +262,7 @@ impl Tool for FsWriteTool {
     fn describe(&self) -> ToolDescription {
         ToolDescription {
             name: "fs_write".to_string(),
-            description: "Write content to a file in workspace. Overwrites existing files.".to_string(),
+            description: "Write content to a file. Overwrites existing files, but refuses (and shows a diff) if the file changed since you read it. Intermediate files go to this conversation's scratch directory by default; use location 'workspace' for results Master should keep.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -169,7 +273,8 @@ impl Tool for FsWriteTool {
                     "content": {
                         "type": "string",
                         "description": "Content to write to the file"
-                    }
+                    },
+                    "location": location_schema()
                 },
                 "required": ["path", "content"]
             }),
@@ -208,7 +313,24 @@ impl Tool for FsWriteTool {
             .and_then(|c| c.as_str())
             .ok_or_else(|| ToolError::ValidationError("Invalid 'content' field".to_string()))?;
 
-        let full_path = self.resolve_path(path)?;
+        let (full_path, location) = match (Location::from_input(&input)?, &self.scratch) {
+            (Some(Location::Workspace), _) | (None, None) => {
+                (self.resolve_path(&self.workspace, path)?, "workspace")
+            }
+            (Some(Location::Scratch), None) => {
+                return Err(ToolError::ValidationError(
+                    "No scratch directory is available in this session".to_string(),
+                ))
+            }
+            (_, Some(scratch)) => {
+                let full_path = self.resolve_path(scratch.dir(), path)?;
+                let replacing = std::fs::metadata(&full_path).map(|m| m.len()).unwrap_or(0);
+                scratch
+                    .check_quota(content.len() as u64, replacing)
+                    .map_err(ToolError::PolicyDenied)?;
+                (full_path, "scratch")
+            }
+        };
 
         // 确保父目录存在
         if let Some(parent) = full_path.parent() {
@@ -217,13 +339,22 @@ impl Tool for FsWriteTool {
                 .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create directory: {}", e)))?;
         }
 
+        // 读取之后被改动的文件交给模型决定合并还是放弃
+        if let Some(tracker) = &self.tracker {
+            tracker.check_write(&full_path, path)?;
+        }
+
         // 写入文件
         tokio::fs::write(&full_path, content)
             .await
             .map_err(|e| ToolError::ExecutionFailed(format!("Failed to write file: {}", e)))?;
+        if let Some(tracker) = &self.tracker {
+            tracker.record(&full_path, content);
+        }
 
         let data = json!({
             "path": path,
+            "location": location,
             "size": content.len(),
             "status": "written"
         });
@@ -0,0 +1,587 @@
+//! # File Search & Patch Tools
+//!
+//! 🔍 在工作区中搜索文件、精确修改文件喵
+//!
+//! @诺诺 的 fs_search / fs_patch 工具实现喵
+//!
+//! ## 功能
+//! - `fs_search`：按 glob 筛选文件，可选按正则搜索内容（返回行号与所在行）
+//! - `fs_patch`：对单个文件应用 unified diff，或一组 search/replace 编辑；
+//!   `dry_run` 只返回 diff 预览，不写入
+//!
+//! ```text
+//! @fs_search({"glob": "src/**/*.rs", "pattern": "fn main"})
+//! @fs_patch({"path": "README.md", "edits": [{"search": "v0.1", "replace": "v0.2"}], "dry_run": true})
+//! ```
+//!
+//! 🔒 SAFETY: 路径限制在 workspace，配置了 `[security.allowlist]` 时逐个文件检查路径规则
+//! （搜索跳过不允许读取的文件，修改要求写权限）；隐藏目录与符号链接不进入搜索
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use super::conflict::{line_diff, ReadTracker};
+use super::mcp::{Tool, ToolDescription, ToolError, ToolResult};
+use crate::security::{AllowlistService, PathAccess, PolicyViolation};
+use globset::{Glob, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 默认返回的最多结果数
+const DEFAULT_MAX_RESULTS: usize = 50;
+
+/// 结果数上限
+const MAX_RESULTS_LIMIT: usize = 500;
+
+/// 超过此大小的文件不搜索内容
+const MAX_SEARCH_FILE_BYTES: u64 = 1024 * 1024;
+
+/// 匹配行最多展示的字符数
+const MAX_LINE_CHARS: usize = 300;
+
+/// 🔒 SAFETY: 解析工作区内的路径喵（拒绝 `..` 与工作区外的路径）
+fn resolve_path(root: &Path, path: &str) -> Result<PathBuf, ToolError> {
+    if path.contains("..") {
+        return Err(ToolError::PolicyDenied(
+            PolicyViolation::new("path_traversal", path, "parent directory references are not allowed")
+                .with_suggestion("Use a path relative to the workspace without '..'"),
+        ));
+    }
+
+    let full_path = root.join(path);
+    let canonical_full = full_path.canonicalize().unwrap_or_else(|_| full_path.clone());
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    if !canonical_full.starts_with(&canonical_root) {
+        return Err(ToolError::PolicyDenied(
+            PolicyViolation::new("workspace_boundary", path, "access outside workspace not allowed")
+                .with_suggestion("Use a path relative to the workspace"),
+        ));
+    }
+    Ok(full_path)
+}
+
+/// 🔒 SAFETY: 按路径规则检查访问喵（未配置白名单时放行）
+fn check_access(allowlist: Option<&AllowlistService>, full_path: &Path, access: PathAccess) -> Result<(), ToolError> {
+    match allowlist {
+        Some(allowlist) => allowlist
+            .check_path_access(&full_path.to_string_lossy(), access)
+            .map_err(|e| ToolError::PolicyDenied(allowlist.explain(&e))),
+        None => Ok(()),
+    }
+}
+
+/// 🔒 SAFETY: 文件搜索工具喵
+pub struct FsSearchTool {
+    workspace: PathBuf,
+    allowlist: Option<Arc<AllowlistService>>,
+}
+
+impl FsSearchTool {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            allowlist: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 跳过路径规则不允许读取的文件喵
+    pub fn with_allowlist(mut self, allowlist: Arc<AllowlistService>) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+}
+
+/// 一次搜索的参数喵
+struct SearchQuery {
+    root: PathBuf,
+    /// 不含 `/` 的 glob 只匹配文件名，否则匹配相对工作区的路径
+    glob: Option<(GlobMatcher, bool)>,
+    pattern: Option<Regex>,
+    max_results: usize,
+}
+
+/// 搜索结果喵
+#[derive(Default)]
+struct SearchOutcome {
+    results: Vec<JsonValue>,
+    files_scanned: usize,
+    /// 路径规则拒绝的文件数
+    skipped: usize,
+    truncated: bool,
+}
+
+impl SearchQuery {
+    fn run(&self, workspace: &Path, allowlist: Option<&AllowlistService>) -> SearchOutcome {
+        let mut outcome = SearchOutcome::default();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+            let mut entries: Vec<_> = entries.flatten().collect();
+            entries.sort_by_key(|e| e.file_name());
+            for entry in entries {
+                let Ok(file_type) = entry.file_type() else { continue };
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let path = entry.path();
+                if file_type.is_dir() {
+                    if !name.starts_with('.') {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+                if !file_type.is_file() {
+                    continue;
+                }
+                let relative = path.strip_prefix(workspace).unwrap_or(&path).to_string_lossy().into_owned();
+                if let Some((glob, by_name)) = &self.glob {
+                    if !glob.is_match(if *by_name { &name } else { &relative }) {
+                        continue;
+                    }
+                }
+                if check_access(allowlist, &path, PathAccess::Read).is_err() {
+                    outcome.skipped += 1;
+                    continue;
+                }
+                outcome.files_scanned += 1;
+                if self.collect(&path, relative, &mut outcome.results) {
+                    outcome.truncated = true;
+                    return outcome;
+                }
+            }
+        }
+        outcome
+    }
+
+    /// 收集一个文件的结果喵（达到上限时返回 true）
+    fn collect(&self, path: &Path, relative: String, results: &mut Vec<JsonValue>) -> bool {
+        let Some(pattern) = &self.pattern else {
+            if results.len() == self.max_results {
+                return true;
+            }
+            results.push(json!(relative));
+            return false;
+        };
+        let too_large = std::fs::metadata(path).map(|m| m.len() > MAX_SEARCH_FILE_BYTES).unwrap_or(true);
+        // 二进制或非 UTF-8 文件跳过喵
+        let Some(content) = (!too_large).then(|| std::fs::read_to_string(path).ok()).flatten() else {
+            return false;
+        };
+        for (index, line) in content.lines().enumerate() {
+            if !pattern.is_match(line) {
+                continue;
+            }
+            if results.len() == self.max_results {
+                return true;
+            }
+            let text: String = line.trim_end().chars().take(MAX_LINE_CHARS).collect();
+            results.push(json!({ "path": relative, "line": index + 1, "text": text }));
+        }
+        false
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for FsSearchTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "fs_search".to_string(),
+            description: "Find files in the workspace by glob and optionally search their content with a regex. \
+                Without 'pattern' returns matching file paths; with it returns matching lines with line numbers."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "glob": {
+                        "type": "string",
+                        "description": "File glob, e.g. \"*.rs\" (matches file names) or \"src/**/*.rs\" (matches paths)"
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regex to search for in file content, line by line"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to search, relative to workspace (default: whole workspace)"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Case-insensitive content search"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default 50)"
+                    }
+                }
+            }),
+            category: Some("filesystem".to_string()),
+            dangerous: false,
+            required_permissions: None,
+        }
+    }
+
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        if !input.is_object() {
+            return Err(ToolError::ValidationError("Input must be a JSON object".to_string()));
+        }
+        if input.get("glob").is_none() && input.get("pattern").is_none() {
+            return Err(ToolError::ValidationError(
+                "Provide at least one of 'glob' or 'pattern'".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+
+        let dir = input.get("path").and_then(|p| p.as_str()).unwrap_or(".");
+        let root = resolve_path(&self.workspace, dir)?;
+        if !root.is_dir() {
+            return Err(ToolError::ValidationError(format!("'{}' is not a directory", dir)));
+        }
+        let glob = match input.get("glob").and_then(|g| g.as_str()) {
+            Some(glob) => {
+                let matcher = Glob::new(glob)
+                    .map_err(|e| ToolError::ValidationError(format!("Invalid 'glob': {}", e)))?
+                    .compile_matcher();
+                Some((matcher, !glob.contains('/')))
+            }
+            None => None,
+        };
+        let pattern = match input.get("pattern").and_then(|p| p.as_str()) {
+            Some(pattern) => Some(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(input.get("case_insensitive").and_then(|c| c.as_bool()).unwrap_or(false))
+                    .build()
+                    .map_err(|e| ToolError::ValidationError(format!("Invalid 'pattern': {}", e)))?,
+            ),
+            None => None,
+        };
+        let max_results = input
+            .get("max_results")
+            .and_then(|m| m.as_u64())
+            .map_or(DEFAULT_MAX_RESULTS, |m| (m as usize).clamp(1, MAX_RESULTS_LIMIT));
+
+        let query = SearchQuery {
+            root,
+            glob,
+            pattern,
+            max_results,
+        };
+        let workspace = self.workspace.clone();
+        let allowlist = self.allowlist.clone();
+        let outcome = tokio::task::spawn_blocking(move || query.run(&workspace, allowlist.as_deref()))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Search failed: {}", e)))?;
+
+        let key = if input.get("pattern").is_some() { "matches" } else { "files" };
+        let data = json!({
+            key: outcome.results,
+            "files_scanned": outcome.files_scanned,
+            "skipped_by_policy": outcome.skipped,
+            "truncated": outcome.truncated
+        });
+        Ok(ToolResult::success(data, start.elapsed().as_millis() as u64))
+    }
+}
+
+/// 🔒 SAFETY: 文件修改工具喵
+pub struct FsPatchTool {
+    workspace: PathBuf,
+    allowlist: Option<Arc<AllowlistService>>,
+    /// 写入后更新 Agent 看到的内容（与 fs_read / fs_write 共享）
+    tracker: Option<Arc<ReadTracker>>,
+}
+
+impl FsPatchTool {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            allowlist: None,
+            tracker: None,
+        }
+    }
+
+    /// 🔒 SAFETY: 修改前要求路径规则允许写入喵
+    pub fn with_allowlist(mut self, allowlist: Arc<AllowlistService>) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+
+    pub fn with_tracker(mut self, tracker: Arc<ReadTracker>) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+}
+
+/// 依次应用 search/replace 编辑喵（每个 `search` 必须恰好出现一次）
+pub fn apply_edits(content: &str, edits: &[JsonValue]) -> Result<String, String> {
+    let mut patched = content.to_string();
+    for (index, edit) in edits.iter().enumerate() {
+        let search = edit
+            .get("search")
+            .and_then(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| format!("edit {}: missing non-empty 'search'", index + 1))?;
+        let replace = edit
+            .get("replace")
+            .and_then(|r| r.as_str())
+            .ok_or_else(|| format!("edit {}: missing 'replace'", index + 1))?;
+        match patched.matches(search).count() {
+            1 => patched = patched.replacen(search, replace, 1),
+            0 => return Err(format!("edit {}: search text not found", index + 1)),
+            n => {
+                return Err(format!(
+                    "edit {}: search text appears {} times, include more surrounding lines to make it unique",
+                    index + 1,
+                    n
+                ))
+            }
+        }
+    }
+    Ok(patched)
+}
+
+/// 解析后的 diff 块喵
+struct Hunk {
+    /// 原文件中的起始行（从 0 开始）
+    old_start: usize,
+    /// 上下文与删除行
+    old: Vec<String>,
+    /// 上下文与新增行
+    new: Vec<String>,
+}
+
+/// 解析 unified diff 喵（忽略 `---` / `+++` 文件头）
+fn parse_hunks(diff: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@") {
+            let old_start = header
+                .trim()
+                .strip_prefix('-')
+                .and_then(|range| range.split([',', ' ']).next())
+                .and_then(|start| start.parse::<usize>().ok())
+                .ok_or_else(|| format!("invalid hunk header: {}", line))?;
+            hunks.push(Hunk {
+                old_start: old_start.saturating_sub(1),
+                old: Vec::new(),
+                new: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            // 第一个块之前的文件头与说明喵
+            continue;
+        };
+        let text = line.get(1..).unwrap_or_default().to_string();
+        match line.chars().next() {
+            Some(' ') => {
+                hunk.old.push(text.clone());
+                hunk.new.push(text);
+            }
+            Some('-') => hunk.old.push(text),
+            Some('+') => hunk.new.push(text),
+            Some('\\') => {}
+            // 部分模型会丢掉空上下文行前的空格喵
+            None => {
+                hunk.old.push(String::new());
+                hunk.new.push(String::new());
+            }
+            Some(_) => return Err(format!("invalid diff line: {}", line)),
+        }
+    }
+    if hunks.is_empty() {
+        return Err("diff contains no hunks (expected '@@ -start,count +start,count @@')".to_string());
+    }
+    Ok(hunks)
+}
+
+/// 🔒 SAFETY: 应用 unified diff 喵
+///
+/// 每个块先在标注的行号处匹配，对不上时在之后的内容中查找唯一的位置；
+/// 上下文不匹配时拒绝整个补丁，不会部分写入
+pub fn apply_unified_diff(content: &str, diff: &str) -> Result<String, String> {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    // 已应用的块使行号产生的偏移
+    let mut shift: isize = 0;
+    // 下一个块只能从这里开始匹配
+    let mut floor = 0;
+    for (index, hunk) in parse_hunks(diff)?.into_iter().enumerate() {
+        let matches_at = |at: usize| at + hunk.old.len() <= lines.len() && lines[at..at + hunk.old.len()] == hunk.old[..];
+        let expected = (hunk.old_start as isize + shift).max(floor as isize) as usize;
+        let at = if matches_at(expected) {
+            expected
+        } else {
+            let candidates: Vec<usize> = (floor..=lines.len().saturating_sub(hunk.old.len()))
+                .filter(|&at| matches_at(at))
+                .collect();
+            match candidates[..] {
+                [at] => at,
+                [] => return Err(format!("hunk {}: context does not match the file", index + 1)),
+                _ => return Err(format!("hunk {}: context matches {} places", index + 1, candidates.len())),
+            }
+        };
+        let added = hunk.new.len();
+        shift += added as isize - hunk.old.len() as isize;
+        lines.splice(at..at + hunk.old.len(), hunk.new);
+        floor = at + added;
+    }
+
+    let mut patched = lines.join(newline);
+    if content.ends_with('\n') || (content.is_empty() && !patched.is_empty()) {
+        patched.push_str(newline);
+    }
+    Ok(patched)
+}
+
+#[async_trait::async_trait]
+impl Tool for FsPatchTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "fs_patch".to_string(),
+            description: "Edit one workspace file without rewriting it: either a unified diff ('diff') or a list of \
+                search/replace edits ('edits', each search must match exactly once). Set dry_run to preview the diff \
+                without writing."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File path relative to workspace"
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "Unified diff hunks for this file (@@ -start,count +start,count @@ ...)"
+                    },
+                    "edits": {
+                        "type": "array",
+                        "description": "Search/replace edits applied in order",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "search": { "type": "string" },
+                                "replace": { "type": "string" }
+                            },
+                            "required": ["search", "replace"]
+                        }
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only return the resulting diff, do not write"
+                    }
+                },
+                "required": ["path"]
+            }),
+            category: Some("filesystem".to_string()),
+            dangerous: true,
+            required_permissions: Some(vec!["fs.write".to_string()]),
+        }
+    }
+
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        if !input.is_object() {
+            return Err(ToolError::ValidationError("Input must be a JSON object".to_string()));
+        }
+        if input.get("path").is_none() {
+            return Err(ToolError::ValidationError("Missing required field: 'path'".to_string()));
+        }
+        match (input.get("diff"), input.get("edits")) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(ToolError::ValidationError(
+                "Provide exactly one of 'diff' or 'edits'".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+
+        let path = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| ToolError::ValidationError("Invalid 'path' field".to_string()))?;
+        let dry_run = input.get("dry_run").and_then(|d| d.as_bool()).unwrap_or(false);
+        let full_path = resolve_path(&self.workspace, path)?;
+        check_access(self.allowlist.as_deref(), &full_path, PathAccess::Write)?;
+
+        let content = tokio::fs::read_to_string(&full_path)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read file: {}", e)))?;
+        let patched = match (input.get("diff").and_then(|d| d.as_str()), input.get("edits")) {
+            (Some(diff), _) => apply_unified_diff(&content, diff),
+            (None, Some(JsonValue::Array(edits))) => apply_edits(&content, edits),
+            _ => return Err(ToolError::ValidationError("'diff' must be a string, 'edits' an array".to_string())),
+        }
+        .map_err(|e| ToolError::ExecutionFailed(format!("Patch not applied to {}: {}", path, e)))?;
+
+        let diff = line_diff(&content, &patched);
+        if !dry_run && patched != content {
+            tokio::fs::write(&full_path, &patched)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to write file: {}", e)))?;
+            if let Some(tracker) = &self.tracker {
+                tracker.record(&full_path, &patched);
+            }
+        }
+
+        let status = match (dry_run, patched == content) {
+            (_, true) => "unchanged",
+            (true, false) => "preview",
+            (false, false) => "patched",
+        };
+        let data = json!({
+            "path": path,
+            "status": status,
+            "diff": diff
+        });
+        Ok(ToolResult::success(data, start.elapsed().as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_and_patch_in_workspace() {
+        let workspace = std::env::temp_dir().join(format!("nekoclaw-fs-edit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(workspace.join("src/.git")).unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+        std::fs::write(workspace.join("src/.git/HEAD"), "fn main").unwrap();
+        std::fs::write(workspace.join("notes.txt"), "fn main is here\n").unwrap();
+
+        let search = FsSearchTool::new(&workspace);
+        let found = search.execute(json!({ "glob": "*.rs", "pattern": "println" })).await.unwrap();
+        let data = found.data.unwrap();
+        assert_eq!(data["matches"], json!([{ "path": "src/main.rs", "line": 2, "text": "    println!(\"hi\");" }]));
+        let files = search.execute(json!({ "pattern": "fn main" })).await.unwrap().data.unwrap();
+        assert_eq!(files["matches"].as_array().unwrap().len(), 2, "hidden directories are skipped");
+        assert!(search.execute(json!({ "glob": "*", "path": "../" })).await.is_err());
+
+        let patch = FsPatchTool::new(&workspace);
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"hi\");\n+    println!(\"hello\");\n }\n";
+        let preview = patch
+            .execute(json!({ "path": "src/main.rs", "diff": diff, "dry_run": true }))
+            .await
+            .unwrap();
+        assert_eq!(preview.data.unwrap()["status"], "preview");
+        assert!(std::fs::read_to_string(workspace.join("src/main.rs")).unwrap().contains("\"hi\""));
+
+        patch.execute(json!({ "path": "src/main.rs", "diff": diff })).await.unwrap();
+        let edits = json!([{ "search": "fn main()", "replace": "pub fn main()" }]);
+        patch.execute(json!({ "path": "src/main.rs", "edits": edits })).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("src/main.rs")).unwrap(),
+            "pub fn main() {\n    println!(\"hello\");\n}\n"
+        );
+        // 上下文不匹配时整个补丁被拒绝喵
+        assert!(patch.execute(json!({ "path": "src/main.rs", "diff": diff })).await.is_err());
+        let ambiguous = json!([{ "search": "(", "replace": "[" }]);
+        assert!(patch.execute(json!({ "path": "src/main.rs", "edits": ambiguous })).await.is_err());
+
+        std::fs::remove_dir_all(&workspace).unwrap();
+    }
+}
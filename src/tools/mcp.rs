@@ -28,11 +28,18 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{ChildStdin, ChildStdout, Command};
+use super::catalog::{CatalogEntry, ToolSource};
+use super::pager::OutputPager;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::Command;
 use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 
+use super::budget::{BudgetUsage, ToolBudget};
+use super::mcp_http::HttpTransport;
+use crate::security::{AuditEvent, AuditKind, AuditLog, AuditStatus, PolicyViolation, ToolApproval};
+
 /// 🔒 SAFETY: Tool 执行错误类型喵
 #[derive(Debug, Error)]
 pub enum ToolError {
@@ -52,10 +59,26 @@ pub enum ToolError {
     #[error("Permission denied for tool '{0}'")]
     PermissionDenied(String),
 
+    /// 被安全策略拦截（结构化详情）
+    #[error("{0}")]
+    PolicyDenied(PolicyViolation),
+
     /// 超时
     #[error("Tool execution timed out")]
     Timeout,
 
+    /// 对话的工具预算已用尽
+    #[error("Tool budget exhausted: {0}")]
+    BudgetExhausted(String),
+
+    /// 写入目标在读取之后被改动（附带 diff）
+    #[error("Write conflict: {0}")]
+    WriteConflict(String),
+
+    /// 危险工具未获主人确认
+    #[error("Not approved: {0}")]
+    NotApproved(String),
+
     /// 其他错误
     #[error("Tool error: {0}")]
     Other(String),
@@ -144,6 +167,11 @@ pub trait Tool: Sync + Send {
 
     /// 执行工具
     async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError>;
+
+    /// 工具来源（能力目录使用，默认为内置工具）
+    fn source(&self) -> ToolSource {
+        ToolSource::Builtin
+    }
 }
 
 /// 🔒 SAFETY: 工具注册器喵
@@ -156,6 +184,18 @@ pub struct ToolRegistry {
 
     /// 工具分类映射
     categories: HashMap<String, Vec<String>>,
+
+    /// 对话的工具预算（克隆的注册器共享同一份用量）
+    budget: Option<Arc<ToolBudget>>,
+
+    /// 危险工具的执行确认
+    approval: Option<Arc<ToolApproval>>,
+
+    /// 审计日志与执行者
+    audit: Option<(Arc<AuditLog>, String)>,
+
+    /// 工具集合的版本（每次注册递增，提示词缓存据此失效）
+    revision: u64,
 }
 
 impl ToolRegistry {
@@ -164,9 +204,41 @@ impl ToolRegistry {
         Self {
             tools: HashMap::new(),
             categories: HashMap::new(),
+            budget: None,
+            approval: None,
+            audit: None,
+            revision: 0,
         }
     }
 
+    /// 工具集合的版本喵（注册新工具后改变）
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 🔒 SAFETY: 启用工具预算喵
+    pub fn with_budget(mut self, budget: ToolBudget) -> Self {
+        self.budget = Some(Arc::new(budget));
+        self
+    }
+
+    /// 🔒 SAFETY: 执行 `dangerous` 工具前需要确认喵
+    pub fn with_approval(mut self, approval: Arc<ToolApproval>) -> Self {
+        self.approval = Some(approval);
+        self
+    }
+
+    /// 🔒 SAFETY: 每次执行（含被拒绝的）写入审计日志喵，`actor` 为执行者
+    pub fn with_audit(mut self, audit: Arc<AuditLog>, actor: &str) -> Self {
+        self.audit = Some((audit, actor.to_string()));
+        self
+    }
+
+    /// 预算用量喵（未启用预算时为 None）
+    pub fn budget_usage(&self) -> Option<BudgetUsage> {
+        self.budget.as_ref().map(|b| b.usage())
+    }
+
     /// 🔒 SAFETY: 注册工具喵
     pub fn register<T: Tool + 'static>(&mut self, tool: T) -> Result<(), ToolError> {
         let description = tool.describe();
@@ -183,12 +255,13 @@ impl ToolRegistry {
 
         // 注册工具
         self.tools.insert(name.clone(), Arc::new(tool));
+        self.revision += 1;
 
         // 添加到分类
         if let Some(cat) = category {
             self.categories
                 .entry(cat)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(name.clone());
         }
 
@@ -196,6 +269,19 @@ impl ToolRegistry {
         Ok(())
     }
 
+    /// 🔒 SAFETY: 只保留满足条件的工具喵（按 Agent 限制可用工具）
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        let before = self.tools.len();
+        self.tools.retain(|name, _| keep(name));
+        if self.tools.len() == before {
+            return;
+        }
+        for names in self.categories.values_mut() {
+            names.retain(|name| keep(name));
+        }
+        self.revision += 1;
+    }
+
     /// 🔒 SAFETY: 获取工具描述喵
     pub fn get_description(&self, name: &str) -> Option<ToolDescription> {
         self.tools.get(name).map(|tool| tool.describe())
@@ -206,6 +292,14 @@ impl ToolRegistry {
         self.tools.values().map(|tool| tool.describe()).collect()
     }
 
+    /// 🔒 SAFETY: 能力目录条目喵（描述 + 来源）
+    pub fn catalog_entries(&self) -> Vec<CatalogEntry> {
+        self.tools
+            .values()
+            .map(|tool| CatalogEntry::from_description(tool.describe(), tool.source()))
+            .collect()
+    }
+
     /// 🔒 SAFETY: 获取分类下的工具喵
     pub fn tools_by_category(&self, category: &str) -> Vec<ToolDescription> {
         self.categories
@@ -221,17 +315,48 @@ impl ToolRegistry {
 
     /// 🔒 SAFETY: 执行工具喵
     pub async fn execute(&self, name: &str, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let Some((audit, actor)) = &self.audit else {
+            return self.execute_unaudited(name, input).await;
+        };
+        let event = AuditEvent::new(AuditKind::for_tool(name), actor, name).with_arguments(input.clone());
+        let result = self.execute_unaudited(name, input).await;
+        let event = match &result {
+            Ok(result) if result.success => event,
+            Ok(result) => event
+                .with_status(AuditStatus::Failed)
+                .with_detail(result.error.clone().unwrap_or_default()),
+            Err(e @ (ToolError::NotApproved(_) | ToolError::PolicyDenied(_) | ToolError::PermissionDenied(_))) => {
+                event.with_status(AuditStatus::Denied).with_detail(e.to_string())
+            }
+            Err(e) => event.with_status(AuditStatus::Failed).with_detail(e.to_string()),
+        };
+        audit.record_or_warn(&event);
+        result
+    }
+
+    async fn execute_unaudited(&self, name: &str, input: JsonValue) -> Result<ToolResult, ToolError> {
         // 查找工具
         let tool = self
             .tools
             .get(name)
             .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
 
-        let start = std::time::Instant::now();
-
         // 验证输入
         tool.validate_input(&input)?;
 
+        // 危险工具需要确认（拒绝时不扣预算）
+        let description = tool.describe();
+        if let Some(approval) = &self.approval {
+            approval
+                .authorize(name, description.dangerous, &input)
+                .map_err(ToolError::NotApproved)?;
+        }
+
+        // 扣除预算（耗尽时不再执行）
+        if let Some(budget) = &self.budget {
+            budget.charge(&description, &input)?;
+        }
+
         // 执行工具
         let result = tool.execute(input).await?;
 
@@ -336,10 +461,18 @@ pub fn format_tool_call_for_llm(call: &ToolCallRequest) -> String {
 }
 
 /// 🔒 SAFETY: 格式化工具结果为 LLM 可读字符串喵
-pub fn format_tool_result_for_llm(result: &ToolResult) -> String {
+/// 字符串结果原样输出；传入 `pager` 时超出上限的输出只返回第一段与 `@read_more` 游标
+pub fn format_tool_result_for_llm(result: &ToolResult, pager: Option<&OutputPager>) -> String {
     if result.success {
         if let Some(data) = &result.data {
-            serde_json::to_string_pretty(data).unwrap_or_else(|_| "{}".to_string())
+            let text = match data {
+                JsonValue::String(text) => text.clone(),
+                data => serde_json::to_string_pretty(data).unwrap_or_else(|_| "{}".to_string()),
+            };
+            match pager {
+                Some(pager) => pager.paginate(text),
+                None => text,
+            }
         } else {
             "Tool executed successfully (no output)".to_string()
         }
@@ -348,6 +481,30 @@ pub fn format_tool_result_for_llm(result: &ToolResult) -> String {
     }
 }
 
+/// 🔒 SAFETY: 格式化工具错误为 LLM 可读字符串喵
+/// 策略拦截会附带结构化详情，避免模型无意义地重试
+pub fn format_tool_error_for_llm(error: &ToolError) -> String {
+    match error {
+        ToolError::PolicyDenied(violation) => violation.for_model(),
+        ToolError::BudgetExhausted(detail) => format!(
+            "BUDGET_EXHAUSTED {}\nDo not call this tool again in this conversation. \
+            Wrap up now: give Master your best answer with what you have and say what is left unfinished.",
+            detail
+        ),
+        ToolError::WriteConflict(detail) => format!(
+            "WRITE_CONFLICT {}\nNothing was written. To merge, call fs_write again with content that keeps both \
+            your changes and the ones shown above; to abort, leave the file alone and tell Master about the conflict.",
+            detail
+        ),
+        ToolError::NotApproved(detail) => format!(
+            "NOT_APPROVED {}\nThe tool was not run. Do not retry the same call; continue without it \
+            or explain to Master what you wanted to do and why.",
+            detail
+        ),
+        other => format!("Tool failed: {}", other),
+    }
+}
+
 /// 🔒 SAFETY: 从文本中解析工具调用指令喵
 pub fn parse_tool_calls(text: &str) -> Vec<ToolCallRequest> {
     let mut calls = Vec::new();
@@ -381,12 +538,17 @@ pub fn parse_tool_calls(text: &str) -> Vec<ToolCallRequest> {
 // MCP Client Implementation (by 缪斯 📚)
 // ============================================================================
 
+/// 🔒 SAFETY: 按行分帧的写端（子进程 stdin 或进程内管道）喵
+pub type FrameWriter = Box<dyn AsyncWrite + Send + Unpin>;
+/// 🔒 SAFETY: 按行分帧的读端（子进程 stdout 或进程内管道）喵
+pub type FrameReader = Box<dyn AsyncRead + Send + Unpin>;
+
 /// 🔒 SAFETY: MCP 传输层类型喵
 pub enum McpTransport {
-    /// stdio 传输（子进程）
-    Stdio { stdin: Arc<Mutex<ChildStdin>>, stdout: Arc<Mutex<ChildStdout>> },
-    /// HTTP 传输（未来扩展）
-    Http { url: String },
+    /// stdio 传输（子进程或进程内管道）
+    Stdio { stdin: Arc<Mutex<FrameWriter>>, stdout: Arc<Mutex<FrameReader>> },
+    /// Streamable HTTP 传输（远程 server）
+    Http(HttpTransport),
 }
 
 /// 🔒 SAFETY: MCP 传输层错误喵
@@ -406,6 +568,9 @@ pub enum McpTransportError {
 
     #[error("Transport closed")]
     Closed,
+
+    #[error("HTTP error: {0}")]
+    Http(String),
 }
 
 /// 🔒 SAFETY: JSON-RPC 2.0 请求喵
@@ -463,6 +628,33 @@ pub struct JsonRpcError {
     pub data: Option<JsonValue>,
 }
 
+/// 🔒 SAFETY: 编码一帧 JSON-RPC 消息喵
+/// stdio 传输按行分帧：一行一个 JSON，serde_json 会转义内容中的换行
+pub fn encode_frame<T: Serialize>(message: &T) -> Result<String, serde_json::Error> {
+    Ok(format!("{}\n", serde_json::to_string(message)?))
+}
+
+/// 🔒 SAFETY: 解码一帧 JSON-RPC 响应喵
+/// 异常处理: 空行、非 2.0 版本、RPC 错误都返回错误而不是 panic
+pub fn decode_frame(line: &str) -> Result<JsonRpcResponse, McpClientError> {
+    let response_json = line.trim();
+    if response_json.is_empty() {
+        return Err(McpClientError::InvalidResponse);
+    }
+
+    let response: JsonRpcResponse =
+        serde_json::from_str(response_json).map_err(McpClientError::Serialization)?;
+    if response.jsonrpc != "2.0" {
+        return Err(McpClientError::InvalidResponse);
+    }
+
+    if let Some(error) = response.error {
+        return Err(McpClientError::RpcError(error.code, error.message));
+    }
+
+    Ok(response)
+}
+
 /// 🔒 SAFETY: JSON-RPC 2.0 通知喵
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -503,6 +695,7 @@ pub struct ServerCapabilities {
 
 /// 🔒 SAFETY: MCP 工具描述（来自 server）喵
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct McpTool {
     /// 工具名称
     pub name: String,
@@ -516,6 +709,21 @@ pub struct McpTool {
     /// 输出 schema（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_schema: Option<JsonValue>,
+    /// 行为提示（可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<McpToolAnnotations>,
+}
+
+/// MCP 工具行为提示喵（server 自行声明，仅作参考）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolAnnotations {
+    /// 只读，不修改环境
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    /// 可能做出破坏性修改
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
 }
 
 /// 🔒 SAFETY: MCP 工具结果内容类型喵
@@ -549,6 +757,7 @@ pub enum McpContentItem {
 
 /// 🔒 SAFETY: MCP 工具调用结果喵
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct McpToolResult {
     /// 内容列表
     pub content: Vec<McpContentItem>,
@@ -692,11 +901,29 @@ impl McpClient {
     ///
     /// 通过 stdio 传输连接到 MCP server（启动子进程）
     pub async fn connect_stdio(&mut self, command: &str, args: &[&str]) -> Result<(), McpClientError> {
-        let mut child = Command::new(command)
-            .args(args)
+        self.spawn_stdio(Command::new(command).args(args), command, args)
+    }
+
+    /// 🔒 SAFETY: 以受控环境启动 stdio server 喵
+    ///
+    /// 子进程从空环境启动，只能看到 `environment` 中的变量
+    pub async fn connect_stdio_with_env(
+        &mut self,
+        command: &str,
+        args: &[&str],
+        environment: &crate::security::ToolEnvironment,
+    ) -> Result<(), McpClientError> {
+        let mut process = Command::new(command);
+        process.args(args).env_clear().envs(environment.vars());
+        self.spawn_stdio(&mut process, command, args)
+    }
+
+    fn spawn_stdio(&mut self, process: &mut Command, command: &str, args: &[&str]) -> Result<(), McpClientError> {
+        let mut child = process
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
+            // 没有人读取 stderr：丢弃，避免 server 写日志时因管道关闭而退出
+            .stderr(std::process::Stdio::null())
             .spawn()
             .map_err(|e| McpTransportError::Process(format!("Failed to spawn {}: {}", command, e)))?;
 
@@ -709,26 +936,40 @@ impl McpClient {
             .take()
             .ok_or_else(|| McpTransportError::Process("Failed to get stdout".to_string()))?;
 
-        self.transport = Some(McpTransport::Stdio {
-            stdin: Arc::new(Mutex::new(stdin)),
-            stdout: Arc::new(Mutex::new(stdout)),
-        });
-
+        self.connect_streams(Box::new(stdout), Box::new(stdin));
         tracing::info!("Connected to MCP server via stdio: {} {:?}", command, args);
         Ok(())
     }
 
+    /// 🔒 SAFETY: 通过 Streamable HTTP 连接远程 server 喵
+    ///
+    /// `headers` 附加在每个请求上（如 `Authorization`），连接在第一次请求时才建立
+    pub fn connect_http(&mut self, url: &str, headers: &HashMap<String, String>) -> Result<(), McpClientError> {
+        self.transport = Some(McpTransport::Http(HttpTransport::new(url, headers)?));
+        tracing::info!("Using MCP server via HTTP: {}", url);
+        Ok(())
+    }
+
+    /// 🔒 SAFETY: 通过任意读写流连接喵
+    ///
+    /// 与 stdio 使用相同的按行分帧，测试中可直接接入进程内的脚本化 server
+    pub fn connect_streams(&mut self, reader: FrameReader, writer: FrameWriter) {
+        self.transport = Some(McpTransport::Stdio {
+            stdin: Arc::new(Mutex::new(writer)),
+            stdout: Arc::new(Mutex::new(reader)),
+        });
+    }
+
     /// 🔒 SAFETY: 发送 JSON-RPC 请求喵
     pub async fn send_request(&self, request: &JsonRpcRequest) -> Result<JsonRpcResponse, McpClientError> {
         let transport = self
             .transport
             .as_ref()
-            .ok_or_else(|| McpTransportError::Closed)?;
+            .ok_or(McpTransportError::Closed)?;
 
-        let request_json = serde_json::to_string(request)?;
-        let request_line = format!("{}\n", request_json);
+        let request_line = encode_frame(request)?;
 
-        tracing::debug!("MCP Request: {}", request_json);
+        tracing::debug!("MCP Request: {}", request_line.trim_end());
 
         match transport {
             McpTransport::Stdio { stdin, stdout } => {
@@ -738,8 +979,8 @@ impl McpClient {
                     stdin_guard
                         .write_all(request_line.as_bytes())
                         .await
-                        .map_err(|e| McpTransportError::Io(e))?;
-                    stdin_guard.flush().await.map_err(|e| McpTransportError::Io(e))?;
+                        .map_err(McpTransportError::Io)?;
+                    stdin_guard.flush().await.map_err(McpTransportError::Io)?;
                 }
 
                 // 读取响应（按行读取）
@@ -750,32 +991,15 @@ impl McpClient {
                     reader
                         .read_line(&mut line)
                         .await
-                        .map_err(|e| McpTransportError::Io(e))?;
+                        .map_err(McpTransportError::Io)?;
                     line
                 };
 
-                let response_json = line.trim();
-                if response_json.is_empty() {
-                    return Err(McpClientError::InvalidResponse);
-                }
+                tracing::debug!("MCP Response: {}", line.trim());
 
-                tracing::debug!("MCP Response: {}", response_json);
-
-                let response: JsonRpcResponse =
-                    serde_json::from_str(response_json).map_err(McpClientError::Serialization)?;
-
-                if let Some(error) = response.error {
-                    return Err(McpClientError::RpcError(error.code, error.message));
-                }
-
-                Ok(response)
-            }
-            McpTransport::Http { .. } => {
-                // HTTP 传输未来实现
-                Err(McpClientError::Transport(McpTransportError::Process(
-                    "HTTP transport not yet implemented".to_string(),
-                )))
+                decode_frame(&line)
             }
+            McpTransport::Http(http) => http.request(request).await,
         }
     }
 
@@ -809,7 +1033,7 @@ impl McpClient {
 
         let init_result: InitializeResult = response
             .result
-            .ok_or_else(|| McpClientError::InvalidResponse)
+            .ok_or(McpClientError::InvalidResponse)
             .and_then(|v| serde_json::from_value(v).map_err(McpClientError::Serialization))?;
 
         tracing::info!(
@@ -823,15 +1047,22 @@ impl McpClient {
 
         // 发送 initialized 通知
         let notification = JsonRpcNotification::new("notifications/initialized".to_string(), JsonValue::Null);
-        let notification_json = serde_json::to_string(&notification)?;
-
-        if let Some(McpTransport::Stdio { stdin, .. }) = &self.transport {
-            let mut stdin_guard = stdin.lock().await;
-            stdin_guard
-                .write_all(format!("{}\n", notification_json).as_bytes())
-                .await
-                .map_err(|e| McpTransportError::Io(e))?;
-            stdin_guard.flush().await.map_err(|e| McpTransportError::Io(e))?;
+        match &self.transport {
+            Some(McpTransport::Stdio { stdin, .. }) => {
+                let notification_json = serde_json::to_string(&notification)?;
+                let mut stdin_guard = stdin.lock().await;
+                stdin_guard
+                    .write_all(format!("{}\n", notification_json).as_bytes())
+                    .await
+                    .map_err(McpTransportError::Io)?;
+                stdin_guard.flush().await.map_err(McpTransportError::Io)?;
+            }
+            Some(McpTransport::Http(http)) => {
+                // 之后的请求都带上协商好的协议版本喵
+                http.set_protocol_version(&init_result.protocol_version);
+                http.notify(&notification).await?;
+            }
+            None => {}
         }
 
         // 标记为已初始化
@@ -855,7 +1086,7 @@ impl McpClient {
 
         let result: ListToolsResult = response
             .result
-            .ok_or_else(|| McpClientError::InvalidResponse)
+            .ok_or(McpClientError::InvalidResponse)
             .and_then(|v| serde_json::from_value(v).map_err(McpClientError::Serialization))?;
 
         // 缓存工具列表
@@ -894,7 +1125,7 @@ impl McpClient {
 
         let tool_result: McpToolResult = response
             .result
-            .ok_or_else(|| McpClientError::InvalidResponse)
+            .ok_or(McpClientError::InvalidResponse)
             .and_then(|v| {
                 if let Some(is_error) = v.get("isError") {
                     if is_error.as_bool().unwrap_or(false) {
@@ -969,10 +1200,21 @@ impl McpClient {
             description: mcp_tool.description.clone(),
             input_schema: mcp_tool.input_schema.clone(),
             category: Some("mcp".to_string()),
-            dangerous: false,
+            // 只有明确声明 destructiveHint 的工具才需要确认喵
+            dangerous: mcp_tool
+                .annotations
+                .as_ref()
+                .and_then(|a| a.destructive_hint)
+                .unwrap_or(false),
             required_permissions: None,
         }
     }
+
+    /// 🔒 SAFETY: 按名称查找已缓存工具的描述喵（需先 `list_tools`）
+    pub async fn describe_tool(&self, name: &str) -> Option<ToolDescription> {
+        let tools = self.tools.read().await;
+        tools.get(name).map(|tool| self.tool_to_description(tool))
+    }
 }
 
 impl Default for McpClient {
@@ -1022,6 +1264,58 @@ mod tests {
         assert!(formatted.contains("test_tool"));
         assert!(formatted.contains("A test tool"));
     }
+
+    #[test]
+    fn test_decode_frame() {
+        let ok = decode_frame("{\"jsonrpc\":\"2.0\",\"id\":\"1\",\"result\":{}}\n").unwrap();
+        assert_eq!(ok.id, "1");
+        assert!(matches!(decode_frame("   \n"), Err(McpClientError::InvalidResponse)));
+        assert!(matches!(
+            decode_frame("{\"jsonrpc\":\"1.0\",\"id\":\"1\"}"),
+            Err(McpClientError::InvalidResponse)
+        ));
+        assert!(matches!(
+            decode_frame("{\"jsonrpc\":\"2.0\",\"id\":\"1\",\"error\":{\"code\":-32601,\"message\":\"nope\"}}"),
+            Err(McpClientError::RpcError(-32601, _))
+        ));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_parse_tool_calls_never_panics(text in ".{0,256}") {
+            for call in parse_tool_calls(&text) {
+                proptest::prop_assert!(!call.tool_name.is_empty());
+            }
+        }
+
+        #[test]
+        fn prop_parse_tool_calls_roundtrip(
+            name in "[a-zA-Z0-9_]{1,20}",
+            args in proptest::collection::btree_map("[a-z]{1,8}", "[a-zA-Z0-9 ]{0,10}", 0..4),
+            prefix in "[a-zA-Z ,.]{0,20}",
+        ) {
+            let json = serde_json::to_string(&args).unwrap();
+            let calls = parse_tool_calls(&format!("{}@{}({})", prefix, name, json));
+            proptest::prop_assert_eq!(calls.len(), 1);
+            proptest::prop_assert_eq!(&calls[0].tool_name, &name);
+            if !args.is_empty() {
+                proptest::prop_assert_eq!(&calls[0].arguments, &serde_json::to_value(&args).unwrap());
+            }
+        }
+
+        #[test]
+        fn prop_mcp_frames_are_single_lines(
+            method in ".{0,32}",
+            value in ".{0,64}",
+            line in ".{0,256}",
+        ) {
+            let request = JsonRpcRequest::new(method, Some(serde_json::json!({ "value": value })));
+            let frame = encode_frame(&request).unwrap();
+            proptest::prop_assert!(frame.ends_with('\n'));
+            proptest::prop_assert_eq!(frame.matches('\n').count(), 1);
+            let _ = decode_frame(&line);
+        }
+    }
 }
 
 // 🔒 SAFETY: MCP 客户端详细测试模块喵
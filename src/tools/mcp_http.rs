@@ -0,0 +1,270 @@
+//! # MCP Streamable HTTP Transport 🌐
+//!
+//! 远程 MCP server 的 HTTP 传输喵（不需要启动子进程）
+//!
+//! ## 协议要点
+//! - 每条 JSON-RPC 消息单独 `POST` 到同一个端点
+//! - 请求的响应可能是 `application/json`（单条响应），也可能是 `text/event-stream`：
+//!   server 先推送通知或反向请求，最后才是本请求的响应
+//! - 通知只需 server 返回 `202 Accepted`
+//! - server 在 `initialize` 响应头中下发 `Mcp-Session-Id`，之后每个请求都要带上；
+//!   同时带上协商好的 `MCP-Protocol-Version`
+//!
+//! 🔒 SAFETY: 自定义请求头（如 `Authorization`）只出现在发往该 server 的请求中，不写日志喵
+
+use super::mcp::{decode_frame, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, McpClientError, McpTransportError};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 会话 ID 请求/响应头
+pub const SESSION_HEADER: &str = "mcp-session-id";
+/// 协议版本请求头
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// 建立连接的超时（工具调用本身不限时，与 stdio 一致）
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 🔒 SAFETY: Streamable HTTP 传输喵
+#[derive(Debug)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    session_id: Mutex<Option<String>>,
+    protocol_version: Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    /// 🔒 SAFETY: 创建传输喵（`headers` 附加在每个请求上）
+    pub fn new(url: &str, headers: &HashMap<String, String>) -> Result<Self, McpTransportError> {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| McpTransportError::Http(format!("invalid header name {}: {}", name, e)))?;
+            let mut value = HeaderValue::from_str(value)
+                .map_err(|_| McpTransportError::Http(format!("invalid value for header {}", name)))?;
+            value.set_sensitive(true);
+            header_map.insert(name, value);
+        }
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .map_err(|e| McpTransportError::Http(e.to_string()))?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            headers: header_map,
+            session_id: Mutex::new(None),
+            protocol_version: Mutex::new(None),
+        })
+    }
+
+    /// server 下发的会话 ID喵
+    pub fn session_id(&self) -> Option<String> {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    /// 记录 initialize 协商出的协议版本，之后的请求都会带上喵
+    pub fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.lock().unwrap() = Some(version.to_string());
+    }
+
+    /// 构造带会话头的 POST 请求喵
+    fn post(&self, body: String) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json, text/event-stream")
+            .body(body);
+        if let Some(session_id) = self.session_id() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        if let Some(version) = self.protocol_version.lock().unwrap().clone() {
+            request = request.header(PROTOCOL_VERSION_HEADER, version);
+        }
+        request
+    }
+
+    /// 发送并检查 HTTP 状态，记录 server 下发的会话 ID喵
+    async fn send(&self, body: String) -> Result<reqwest::Response, McpTransportError> {
+        let response = self
+            .post(body)
+            .send()
+            .await
+            .map_err(|e| McpTransportError::Http(e.to_string()))?;
+        if let Some(session_id) = response.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            *self.session_id.lock().unwrap() = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND && self.session_id().is_some() {
+            // 会话已失效，需要重新 initialize喵
+            *self.session_id.lock().unwrap() = None;
+            return Err(McpTransportError::Http("MCP session expired (HTTP 404)".to_string()));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(McpTransportError::Http(format!(
+                "HTTP {}: {}",
+                status,
+                body.chars().take(200).collect::<String>()
+            )));
+        }
+        Ok(response)
+    }
+
+    /// 🔒 SAFETY: 发送请求并等待对应 ID 的响应喵
+    ///
+    /// SSE 流中先到的通知记录日志，server 的反向请求（如 `ping`）就地回复
+    pub async fn request(&self, request: &JsonRpcRequest) -> Result<JsonRpcResponse, McpClientError> {
+        let mut response = self.send(serde_json::to_string(request)?).await?;
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+
+        if !is_stream {
+            let body = response.text().await.map_err(|e| McpTransportError::Http(e.to_string()))?;
+            tracing::debug!("MCP Response: {}", body.trim());
+            return decode_frame(&body);
+        }
+
+        let mut buffer = Vec::new();
+        loop {
+            let chunk = response.chunk().await.map_err(|e| McpTransportError::Http(e.to_string()))?;
+            let Some(chunk) = chunk else {
+                return Err(McpTransportError::Closed.into());
+            };
+            buffer.extend_from_slice(&chunk);
+            for data in take_sse_events(&mut buffer) {
+                tracing::debug!("MCP Event: {}", data);
+                let Ok(message) = serde_json::from_str::<JsonValue>(&data) else {
+                    continue;
+                };
+                match (message.get("method").and_then(JsonValue::as_str), message.get("id")) {
+                    (Some(method), Some(id)) => self.answer_server_request(method, id).await?,
+                    (Some(method), None) => tracing::debug!("MCP notification from server: {}", method),
+                    (None, Some(id)) if id.as_str() == Some(request.id.as_str()) => return decode_frame(&data),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// 回复 server 的反向请求喵（只支持 `ping`）
+    async fn answer_server_request(&self, method: &str, id: &JsonValue) -> Result<(), McpTransportError> {
+        let reply = match method {
+            "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+            other => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not supported by client: {}", other) }
+            }),
+        };
+        self.send(reply.to_string()).await.map(|_| ())
+    }
+
+    /// 发送通知喵（server 返回 202）
+    pub async fn notify(&self, notification: &JsonRpcNotification) -> Result<(), McpClientError> {
+        self.send(serde_json::to_string(notification)?).await?;
+        Ok(())
+    }
+}
+
+/// 从缓冲区取出所有完整的 SSE 事件的 `data`，不完整的部分留在缓冲区喵
+///
+/// 按字节缓冲，避免多字节字符被 chunk 边界截断
+fn take_sse_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    buffer.retain(|b| *b != b'\r');
+    let mut events = Vec::new();
+    while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let event: Vec<u8> = buffer.drain(..end + 2).collect();
+        let event = String::from_utf8_lossy(&event);
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|d| d.strip_prefix(' ').unwrap_or(d))
+            .collect();
+        if !data.is_empty() {
+            events.push(data.join("\n"));
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::McpClient;
+    use axum::http::{HeaderMap as AxumHeaders, StatusCode as AxumStatus};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+
+    /// 最小的 Streamable HTTP server：initialize 返回 JSON，其余请求走 SSE喵
+    async fn handle(headers: AxumHeaders, Json(message): Json<JsonValue>) -> Response {
+        let Some(id) = message.get("id").cloned() else {
+            return AxumStatus::ACCEPTED.into_response();
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        if method == "initialize" {
+            let result = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": { "protocolVersion": "2025-06-18", "capabilities": { "tools": {} } }
+            });
+            return ([(SESSION_HEADER, "session-1")], Json(result)).into_response();
+        }
+        if headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) != Some("session-1")
+            || headers.get(PROTOCOL_VERSION_HEADER).is_none()
+        {
+            return AxumStatus::BAD_REQUEST.into_response();
+        }
+        let result = match method {
+            "tools/list" => json!({ "tools": [{
+                "name": "remote_echo",
+                "description": "Echo over HTTP",
+                "inputSchema": { "type": "object" }
+            }] }),
+            _ => json!({ "content": [{ "type": "text", "text": message["params"]["arguments"]["text"] }] }),
+        };
+        let progress = json!({ "jsonrpc": "2.0", "method": "notifications/progress", "params": {} });
+        let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+        let body = format!("event: message\ndata: {}\n\ndata: {}\n\n", progress, reply);
+        ([("content-type", "text/event-stream")], body).into_response()
+    }
+
+    #[tokio::test]
+    async fn test_http_transport_session_and_sse() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let app = Router::new().route("/mcp", post(handle));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut client = McpClient::new();
+        client.connect_http(&url, &HashMap::new()).unwrap();
+        client.initialize().await.unwrap();
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "remote_echo");
+
+        let result = client
+            .call_tool("remote_echo".to_string(), json!({"text": "meow"}))
+            .await
+            .unwrap();
+        assert_eq!(client.format_tool_result(&result), "meow");
+    }
+
+    #[test]
+    fn test_take_sse_events() {
+        let mut buffer = b"data: {\"a\":1}\r\n\r\n: comment\n\nevent: message\ndata: line1\ndata: line2\n\ndata: partial".to_vec();
+        assert_eq!(take_sse_events(&mut buffer), ["{\"a\":1}", "line1\nline2"]);
+        assert_eq!(buffer, b"data: partial");
+    }
+}
@@ -0,0 +1,264 @@
+//! # MCP Server Discovery 🔌
+//!
+//! 从配置启动外部 MCP server，并把它们提供的工具注册进 `ToolRegistry` 喵
+//!
+//! 注册后的工具和内置工具一样通过 `@tool_name({...})` 调用喵
+//!
+//! ```json
+//! "mcp_servers": {
+//!   "github": {
+//!     "command": "npx",
+//!     "args": ["-y", "@modelcontextprotocol/server-github"],
+//!     "env": { "GITHUB_TOKEN": "${env:NEKOCLAW_GITHUB_TOKEN}" }
+//!   }
+//! }
+//! ```
+//!
+//! 远程 server 配置 `url`（Streamable HTTP），不启动子进程喵：
+//!
+//! ```json
+//! "mcp_servers": {
+//!   "docs": {
+//!     "url": "https://mcp.example.com/mcp",
+//!     "headers": { "Authorization": "Bearer ${env:NEKOCLAW_DOCS_TOKEN}" }
+//!   }
+//! }
+//! ```
+//!
+//! 🔒 SAFETY: server 进程从最小环境启动（PATH / HOME / USER / LANG / TZ），
+//! 只额外获得 `env` 中列出的变量，值支持 `${env:NAME}` 与 `${file:/path}` 模板喵
+
+use super::catalog::ToolSource;
+use super::mcp::{McpClient, McpClientError, McpTool, Tool, ToolDescription, ToolError, ToolRegistry, ToolResult};
+use crate::security::{resolve_secret, ToolEnvConfig, ToolEnvironment};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 启动 + 握手 + 列出工具的超时喵
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 🔒 SAFETY: 单个 MCP server 配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// 启动命令（stdio server）
+    #[serde(default)]
+    pub command: String,
+    /// 命令参数
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 额外的环境变量（支持 `${env:NAME}` / `${file:/path}`）
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// 远程 server 端点（设置后使用 HTTP 传输，忽略 `command`）
+    #[serde(default)]
+    pub url: Option<String>,
+    /// HTTP 请求头（支持 `${env:NAME}` / `${file:/path}`）
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl McpServerConfig {
+    /// 🔒 SAFETY: 解析 server 进程的环境变量喵
+    pub fn environment(&self) -> Result<ToolEnvironment, McpClientError> {
+        let config = ToolEnvConfig {
+            secrets: self.env.clone(),
+            ..ToolEnvConfig::default()
+        };
+        ToolEnvironment::resolve(&config).map_err(|e| McpClientError::InitializationFailed(e.to_string()))
+    }
+
+    /// 🔒 SAFETY: 展开 HTTP 请求头中的模板喵
+    pub fn resolved_headers(&self) -> Result<HashMap<String, String>, McpClientError> {
+        self.headers
+            .iter()
+            .map(|(name, template)| {
+                resolve_secret(name, template)
+                    .map(|value| (name.clone(), value))
+                    .map_err(|e| McpClientError::InitializationFailed(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// 🔒 SAFETY: 外部 MCP server 提供的工具喵
+pub struct McpServerTool {
+    server: String,
+    client: Arc<McpClient>,
+    tool: McpTool,
+}
+
+impl McpServerTool {
+    pub fn new(server: &str, client: Arc<McpClient>, tool: McpTool) -> Self {
+        Self {
+            server: server.to_string(),
+            client,
+            tool,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for McpServerTool {
+    fn describe(&self) -> ToolDescription {
+        self.client.tool_to_description(&self.tool)
+    }
+
+    fn source(&self) -> ToolSource {
+        ToolSource::Mcp {
+            server: self.server.clone(),
+        }
+    }
+
+    /// 检查输入是对象且包含 schema 中的必填字段喵
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        let object = input
+            .as_object()
+            .ok_or_else(|| ToolError::ValidationError("Input must be a JSON object".to_string()))?;
+        let required = self.tool.input_schema.get("required").and_then(|r| r.as_array());
+        for field in required.into_iter().flatten().filter_map(|f| f.as_str()) {
+            if !object.contains_key(field) {
+                return Err(ToolError::ValidationError(format!(
+                    "Missing required field: '{}'",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = Instant::now();
+        let result = self
+            .client
+            .call_tool(self.tool.name.clone(), input)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("[{}] {}", self.server, e)))?;
+        Ok(ToolResult::success(
+            json!({
+                "server": self.server,
+                "output": self.client.format_tool_result(&result),
+            }),
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+}
+
+/// 🔒 SAFETY: 启动 server，完成握手并列出工具喵
+pub async fn connect_server(config: &McpServerConfig) -> Result<(Arc<McpClient>, Vec<McpTool>), McpClientError> {
+    let mut client = McpClient::new();
+    match &config.url {
+        Some(url) => client.connect_http(url, &config.resolved_headers()?)?,
+        None if config.command.is_empty() => {
+            return Err(McpClientError::InitializationFailed(
+                "either 'command' or 'url' must be set".to_string(),
+            ))
+        }
+        None => {
+            let environment = config.environment()?;
+            let args: Vec<&str> = config.args.iter().map(String::as_str).collect();
+            client.connect_stdio_with_env(&config.command, &args, &environment).await?;
+        }
+    }
+    client.initialize().await?;
+    let tools = client.list_tools().await?;
+    Ok((Arc::new(client), tools))
+}
+
+/// 把一个已连接 server 的工具注册进注册表喵
+///
+/// 与已有工具重名的跳过（内置工具优先）
+///
+/// ## Returns
+/// 成功注册的工具数
+pub fn register_server_tools(
+    registry: &mut ToolRegistry,
+    server: &str,
+    client: Arc<McpClient>,
+    tools: Vec<McpTool>,
+) -> usize {
+    let mut registered = 0;
+    for tool in tools {
+        if registry.has_tool(&tool.name) {
+            warn!("MCP server {} 的工具 {} 与已有工具重名，已跳过喵", server, tool.name);
+            continue;
+        }
+        let name = tool.name.clone();
+        match registry.register(McpServerTool::new(server, client.clone(), tool)) {
+            Ok(()) => registered += 1,
+            Err(e) => warn!("注册 MCP 工具 {} 失败喵: {}", name, e),
+        }
+    }
+    registered
+}
+
+/// 🔒 SAFETY: 启动配置中的所有 MCP server 并注册工具喵
+///
+/// 单个 server 启动失败只记录警告，不影响其他 server 和内置工具
+///
+/// ## Returns
+/// 成功注册的工具总数
+pub async fn register_mcp_servers(registry: &mut ToolRegistry, servers: &BTreeMap<String, McpServerConfig>) -> usize {
+    let mut total = 0;
+    for (name, config) in servers {
+        let connected = tokio::time::timeout(STARTUP_TIMEOUT, connect_server(config)).await;
+        match connected {
+            Ok(Ok((client, tools))) => {
+                let registered = register_server_tools(registry, name, client, tools);
+                info!("🔌 MCP server {} 已连接，注册了 {} 个工具喵", name, registered);
+                total += registered;
+            }
+            Ok(Err(e)) => warn!("🔌 MCP server {} 启动失败喵: {}", name, e),
+            Err(_) => warn!("🔌 MCP server {} 启动超时（{:?}）喵", name, STARTUP_TIMEOUT),
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::testing::ScriptedMcpServer;
+    use crate::tools::EchoTool;
+
+    #[tokio::test]
+    async fn test_register_server_tools() {
+        let (client, calls) = ScriptedMcpServer::new()
+            .with_tool("weather", |args| Ok(format!("sunny in {}", args["city"].as_str().unwrap_or("?"))))
+            .with_tool("echo", |_| Ok("shadowed".to_string()))
+            .connect()
+            .await;
+        let tools = client.list_tools().await.unwrap();
+
+        let mut registry = ToolRegistry::new();
+        registry.register(EchoTool).unwrap();
+        // 与内置 echo 重名的工具被跳过喵
+        assert_eq!(register_server_tools(&mut registry, "scripted", client, tools), 1);
+        assert_eq!(registry.get_description("weather").unwrap().category.as_deref(), Some("mcp"));
+
+        let result = registry.execute("weather", json!({"city": "Tokyo"})).await.unwrap();
+        assert_eq!(result.data.unwrap()["output"], "sunny in Tokyo");
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_server_config_environment() {
+        let config: McpServerConfig = serde_json::from_value(json!({
+            "command": "mcp-server",
+            "env": { "API_TOKEN": "static-value" }
+        }))
+        .unwrap();
+        assert!(config.args.is_empty());
+        let env = config.environment().unwrap();
+        assert!(env.names().any(|n| n == "API_TOKEN"));
+
+        let bad = McpServerConfig {
+            env: HashMap::from([("TOKEN".to_string(), "${env:NEKOCLAW_TEST_UNSET_VAR}".to_string())]),
+            ..config
+        };
+        assert!(bad.environment().is_err());
+    }
+}
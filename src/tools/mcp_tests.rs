@@ -69,6 +69,7 @@ mod mcp_client_tests {
                 "required": ["message"]
             }),
             output_schema: None,
+            annotations: None,
         };
 
         let description = client.tool_to_description(&mcp_tool);
@@ -1,7 +1,17 @@
 pub mod adapters;
 pub mod brain;
+pub mod budget;
+pub mod catalog;
+pub mod conflict;
+pub mod delegate;
 pub mod filesystem;
+pub mod fs_edit;
 pub mod mcp;
+pub mod mcp_http;
+pub mod mcp_servers;
+pub mod pager;
+pub mod prompt;
+pub mod skill;
 /// Tools 模块导出 🔧
 ///
 /// @诺诺 的 Tools 模块统一入口喵
@@ -9,8 +19,11 @@ pub mod mcp;
 /// 功能：
 /// - MCP-compatible tool system
 /// - Shell 命令执行工具（安全保护）
-/// - 文件系统操作工具
+/// - 文件系统操作工具（读写、搜索、补丁式修改）
+/// - Skills 执行工具（按参数定义校验后在沙箱中运行）
 /// - Agent Family 协议通信工具
+/// - 子 Agent 委派工具（深度限制 + 合计 token 统计）
+/// - 工具输出分页（超长输出通过 read_more 分段读取）
 /// - 工具链管理系统
 ///
 /// 🔒 SAFETY: 所有 Tool 都经过安全沙箱保护
@@ -19,18 +32,29 @@ pub mod mcp;
 pub mod shell;
 
 // 🔒 SAFETY: 重新导出公共接口喵
-pub use adapters::{McpShellTool, EchoTool};
+pub use adapters::{McpShellTool, EchoTool, EscalationTool, HistorySearchTool};
 pub use brain::{AgentInfo, AgentMessage, BrainError, BrainTool, MessageKind, SubAgentConfig};
+pub use budget::{ToolBudget, ToolBudgetConfig};
+pub use catalog::{ToolCatalog, ToolSource};
+pub use conflict::ReadTracker;
+pub use delegate::{DelegateConfig, DelegateTool};
 pub use filesystem::{FileSystemTool, FsWriteTool};
+pub use fs_edit::{FsPatchTool, FsSearchTool};
 pub use mcp::{
-    format_tool_call_for_llm, format_tool_result_for_llm, format_tools_for_llm, parse_tool_calls, Tool,
+    format_tool_call_for_llm, format_tool_error_for_llm, format_tool_result_for_llm, format_tools_for_llm, parse_tool_calls, Tool,
     ToolCallRequest, ToolCallResponse, ToolDescription, ToolError, ToolRegistry, ToolResult,
     // MCP Client exports
     McpClient, McpClientError, McpContentItem, McpTool, McpToolResult, JsonRpcRequest, JsonRpcResponse,
     JsonRpcNotification, ServerCapabilities, ClientInfo, InitializeParams, InitializeResult, McpTransport,
     McpTransportError, ListToolsParams, ListToolsResult, CallToolParams,
 };
-pub use shell::{ShellError, ShellRequest, ShellResult, ShellTool};
+pub use mcp_servers::{register_mcp_servers, McpServerConfig};
+pub use pager::{OutputPager, ReadMoreTool, ToolOutputConfig};
+pub use prompt::{
+    estimate_tokens, format_tools_compact, render_tools_prompt, PromptCache, ToolPromptConfig, ToolPromptMode,
+};
+pub use shell::{ShellError, ShellRequest, ShellResult, ShellTool, ShellToolConfig};
+pub use skill::SkillTool;
 
 // 🔒 SAFETY: 为了兼容性，定义类型别名
 pub type ToolChain = ToolsManager;
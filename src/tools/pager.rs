@@ -0,0 +1,254 @@
+//! # Tool Output Pager 📄
+//!
+//! @诺诺 的工具输出分页喵
+//!
+//! 单次交给模型的工具输出超过 `max_bytes` 时只返回第一段，末尾附上游标：
+//!
+//! ```text
+//! [Output truncated: showing bytes 0-15872 of 120034. Call @read_more({"cursor": "out1:15872"}) for the next chunk.]
+//! ```
+//!
+//! 模型需要更多内容时调用 `@read_more({"cursor": "out1:15872"})` 取下一段，
+//! 全文只保存在内存中（最近 `MAX_STORED_OUTPUTS` 份），不会写入对话历史喵
+//!
+//! ```toml
+//! [tool_output]
+//! max_bytes = 16384
+//! ```
+//!
+//! 设为 0 表示不限制喵
+//!
+//! 🔒 SAFETY: 按 UTF-8 字符边界切分，优先在换行处断开
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use super::mcp::{Tool, ToolDescription, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 工具名称
+const READ_MORE_TOOL: &str = "read_more";
+
+/// 最多保留的被截断输出份数（更早的游标失效）
+pub const MAX_STORED_OUTPUTS: usize = 16;
+
+/// `max_bytes` 的下限（过小时每段放不下提示行）
+const MIN_MAX_BYTES: usize = 1024;
+
+/// 为末尾的游标提示预留的字节数
+const FOOTER_RESERVE: usize = 200;
+
+fn default_max_bytes() -> usize {
+    16 * 1024
+}
+
+/// 🔒 SAFETY: 工具输出大小配置喵（0 表示不限制）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolOutputConfig {
+    /// 单次交给模型的工具输出上限（字节，最小 1024）
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for ToolOutputConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_max_bytes(),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 工具输出分页器喵（父对话与子 Agent 共享同一份游标）
+pub struct OutputPager {
+    max_bytes: usize,
+    /// 被截断的输出（游标 ID → 全文），按时间顺序
+    outputs: Mutex<VecDeque<(String, String)>>,
+    next_id: AtomicU64,
+}
+
+impl OutputPager {
+    pub fn new(config: &ToolOutputConfig) -> Self {
+        Self {
+            max_bytes: match config.max_bytes {
+                0 => 0,
+                n => n.max(MIN_MAX_BYTES),
+            },
+            outputs: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 是否启用分页喵
+    pub fn is_enabled(&self) -> bool {
+        self.max_bytes > 0
+    }
+
+    /// 每段正文的字节数（为提示行留出空间，保证每段不超过 `max_bytes`）
+    fn chunk_bytes(&self) -> usize {
+        self.max_bytes - FOOTER_RESERVE
+    }
+
+    /// 🔒 SAFETY: 超出上限时保存全文，只返回第一段与游标喵
+    pub fn paginate(&self, text: String) -> String {
+        if !self.is_enabled() || text.len() <= self.max_bytes {
+            return text;
+        }
+        let id = format!("out{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        let page = self.page(&id, &text, 0);
+        let mut outputs = self.outputs.lock().unwrap_or_else(|e| e.into_inner());
+        outputs.push_back((id, text));
+        while outputs.len() > MAX_STORED_OUTPUTS {
+            outputs.pop_front();
+        }
+        page
+    }
+
+    /// 🔒 SAFETY: 按游标取下一段喵（游标格式 `<id>:<offset>`）
+    pub fn read_more(&self, cursor: &str) -> Result<String, ToolError> {
+        let (id, offset) = cursor
+            .rsplit_once(':')
+            .and_then(|(id, offset)| Some((id, offset.parse::<usize>().ok()?)))
+            .ok_or_else(|| ToolError::ValidationError(format!("Invalid cursor '{}'", cursor)))?;
+        let outputs = self.outputs.lock().unwrap_or_else(|e| e.into_inner());
+        let text = outputs
+            .iter()
+            .find(|(stored, _)| stored == id)
+            .map(|(_, text)| text)
+            .ok_or_else(|| {
+                ToolError::ValidationError(format!(
+                    "Unknown or expired cursor '{}': only the last {} truncated outputs are kept, run the tool again",
+                    cursor, MAX_STORED_OUTPUTS
+                ))
+            })?;
+        if offset >= text.len() || !text.is_char_boundary(offset) {
+            return Err(ToolError::ValidationError(format!(
+                "Cursor '{}' is out of range: the output has {} bytes",
+                cursor,
+                text.len()
+            )));
+        }
+        Ok(self.page(id, text, offset))
+    }
+
+    /// 从 `offset` 开始的一段，附带下一段的游标喵
+    fn page(&self, id: &str, text: &str, offset: usize) -> String {
+        let end = chunk_end(text, offset, self.chunk_bytes());
+        let chunk = &text[offset..end];
+        if end < text.len() {
+            format!(
+                "{}\n\n[Output truncated: showing bytes {}-{} of {}. Call @read_more({{\"cursor\": \"{}:{}\"}}) for the next chunk.]",
+                chunk,
+                offset,
+                end,
+                text.len(),
+                id,
+                end
+            )
+        } else {
+            format!("{}\n\n[End of output: bytes {}-{} of {}.]", chunk, offset, end, text.len())
+        }
+    }
+}
+
+/// 一段的结束位置喵：不超过 `max` 字节，落在字符边界上，后半段有换行时在换行后断开
+fn chunk_end(text: &str, start: usize, max: usize) -> usize {
+    let limit = start + max;
+    if limit >= text.len() {
+        return text.len();
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    match text[start..end].rfind('\n') {
+        Some(newline) if newline >= max / 2 => start + newline + 1,
+        _ => end,
+    }
+}
+
+/// 🔒 SAFETY: 读取被截断输出的下一段喵
+pub struct ReadMoreTool {
+    pager: Arc<OutputPager>,
+}
+
+impl ReadMoreTool {
+    pub fn new(pager: Arc<OutputPager>) -> Self {
+        Self { pager }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for ReadMoreTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: READ_MORE_TOOL.to_string(),
+            description: "Read the next chunk of a truncated tool output, using the cursor given at its end."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "cursor": {
+                        "type": "string",
+                        "description": "Cursor from the truncation notice, e.g. \"out1:15872\""
+                    }
+                },
+                "required": ["cursor"]
+            }),
+            category: Some("system".to_string()),
+            dangerous: false,
+            required_permissions: None,
+        }
+    }
+
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        match input.get("cursor").and_then(|c| c.as_str()) {
+            Some(_) => Ok(()),
+            None => Err(ToolError::ValidationError("Missing required field: 'cursor'".to_string())),
+        }
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+        let cursor = input.get("cursor").and_then(|c| c.as_str()).unwrap_or_default();
+        let page = self.pager.read_more(cursor)?;
+        Ok(ToolResult::success(JsonValue::String(page), start.elapsed().as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::format_tool_result_for_llm;
+
+    #[tokio::test]
+    async fn test_large_output_is_paged_with_cursor() {
+        let pager = Arc::new(OutputPager::new(&ToolOutputConfig { max_bytes: 1024 }));
+        let text: String = (0..200).map(|i| format!("第 {} 行 line\n", i)).collect();
+        assert_eq!(pager.paginate("short".to_string()), "short");
+
+        let mut page = pager.paginate(text.clone());
+        let tool = ReadMoreTool::new(pager.clone());
+        let mut collected = String::new();
+        loop {
+            assert!(page.len() <= 1024, "page too large: {}", page.len());
+            let (chunk, footer) = page.rsplit_once("\n\n[").unwrap();
+            collected.push_str(chunk);
+            let Some(cursor) = footer.split("\"cursor\": \"").nth(1).and_then(|c| c.split('"').next()) else {
+                assert!(footer.starts_with("End of output"));
+                break;
+            };
+            let result = tool.execute(json!({ "cursor": cursor })).await.unwrap();
+            // read_more 的结果原样交给模型，不会再被分页喵
+            page = format_tool_result_for_llm(&result, Some(&pager));
+        }
+        assert_eq!(collected, text);
+
+        assert!(pager.read_more("out1:999999").is_err());
+        assert!(pager.read_more("missing:0").is_err());
+        let disabled = OutputPager::new(&ToolOutputConfig { max_bytes: 0 });
+        assert_eq!(disabled.paginate(text.clone()), text);
+    }
+}
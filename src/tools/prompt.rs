@@ -0,0 +1,285 @@
+/// 工具提示词渲染 📝
+///
+/// 完整模式（Markdown + 参数说明）与精简模式（一行一个工具 + 简写 schema）喵
+///
+/// 功能：
+/// - `full`: 原有的详细格式（默认）
+/// - `compact`: `name(arg: type, opt?: type) - 描述首句`
+/// - `auto`: 完整提示词超出 token 预算时自动切换为精简格式
+/// - `PromptCache`: 按 注册表版本 + 技能指纹 缓存渲染好的提示词段落，
+///   每轮对话不再重新渲染工具与技能说明；工具或技能变化（重新加载）后自动失效
+use super::ToolDescription;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 🔒 SAFETY: 工具提示词模式喵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolPromptMode {
+    /// 详细格式（默认）
+    #[default]
+    Full,
+    /// 精简格式
+    Compact,
+    /// 超预算时自动精简
+    Auto,
+}
+
+/// 🔒 SAFETY: 工具提示词配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPromptConfig {
+    /// 渲染模式
+    #[serde(default)]
+    pub mode: ToolPromptMode,
+    /// auto 模式下完整提示词的 token 预算
+    #[serde(default = "default_token_budget")]
+    pub token_budget: usize,
+    /// 优先使用原生 function calling（provider 不支持时回退到 @tool() 文本格式）
+    #[serde(default = "default_native")]
+    pub native: bool,
+}
+
+fn default_token_budget() -> usize {
+    1500
+}
+
+fn default_native() -> bool {
+    true
+}
+
+impl Default for ToolPromptConfig {
+    fn default() -> Self {
+        Self {
+            mode: ToolPromptMode::default(),
+            token_budget: default_token_budget(),
+            native: default_native(),
+        }
+    }
+}
+
+/// 🔒 SAFETY: 粗略估算 token 数喵
+/// ASCII 约 4 字符 1 token，CJK 等非 ASCII 字符按 1 字符 1 token 计
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text
+        .chars()
+        .fold((0usize, 0usize), |(a, o), c| if c.is_ascii() { (a + 1, o) } else { (a, o + 1) });
+    ascii.div_ceil(4) + other
+}
+
+/// 🔒 SAFETY: 精简版工具列表（每个工具一行）喵
+pub fn format_tools_compact(tools: &[ToolDescription]) -> String {
+    let mut output = String::from("Available tools:\n");
+
+    for tool in tools {
+        let required: Vec<&str> = tool
+            .input_schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        let params = tool
+            .input_schema
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .map(|(name, schema)| {
+                        let ty = schema.get("type").and_then(|t| t.as_str()).unwrap_or("any");
+                        let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+                        format!("{}{}: {}", name, optional, ty)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        let danger = if tool.dangerous { " [dangerous]" } else { "" };
+        output.push_str(&format!(
+            "- {}({}){} - {}\n",
+            tool.name,
+            params,
+            danger,
+            first_sentence(&tool.description)
+        ));
+    }
+
+    output
+}
+
+/// 描述只保留第一句喵
+fn first_sentence(description: &str) -> &str {
+    let line = description.lines().next().unwrap_or("").trim();
+    match line.find(". ") {
+        Some(i) => &line[..=i],
+        None => line,
+    }
+}
+
+/// 🔒 SAFETY: 按配置渲染工具提示词喵
+///
+/// 返回渲染结果与实际使用的模式（auto 会解析为 full 或 compact）
+pub fn render_tools_prompt(
+    tools: &[ToolDescription],
+    config: &ToolPromptConfig,
+) -> (String, ToolPromptMode) {
+    match config.mode {
+        ToolPromptMode::Full => (super::format_tools_for_llm(tools), ToolPromptMode::Full),
+        ToolPromptMode::Compact => (format_tools_compact(tools), ToolPromptMode::Compact),
+        ToolPromptMode::Auto => {
+            let full = super::format_tools_for_llm(tools);
+            if estimate_tokens(&full) <= config.token_budget {
+                (full, ToolPromptMode::Full)
+            } else {
+                (format_tools_compact(tools), ToolPromptMode::Compact)
+            }
+        }
+    }
+}
+
+/// 提示词来源喵：(工具注册表版本, 技能指纹)
+pub type PromptSource = (u64, u64);
+
+/// 🔒 SAFETY: 渲染好的提示词段落缓存喵
+#[derive(Debug, Default)]
+pub struct PromptCache {
+    state: Mutex<PromptCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct PromptCacheState {
+    source: Option<PromptSource>,
+    sections: HashMap<String, Arc<str>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl PromptCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取出段落，未命中时调用 `render` 渲染并缓存喵
+    ///
+    /// `source` 与上次不同时清空所有段落（工具或技能已变化）
+    pub fn section(&self, source: PromptSource, name: &str, render: impl FnOnce() -> String) -> Arc<str> {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.source != Some(source) {
+                state.sections.clear();
+                state.source = Some(source);
+            }
+            if let Some(section) = state.sections.get(name).cloned() {
+                state.hits += 1;
+                return section;
+            }
+            state.misses += 1;
+        }
+        // 渲染时不持有锁，`render` 内可以再取其他段落喵
+        let section: Arc<str> = render().into();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.source == Some(source) {
+            state.sections.insert(name.to_string(), section.clone());
+        }
+        section
+    }
+
+    /// (命中次数, 未命中次数) 喵
+    pub fn stats(&self) -> (u64, u64) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.hits, state.misses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_tool() -> ToolDescription {
+        ToolDescription {
+            name: "fs_write".to_string(),
+            description: "Write a file. Creates parent directories.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "Target path"},
+                    "append": {"type": "boolean", "description": "Append instead"}
+                },
+                "required": ["path"]
+            }),
+            category: Some("filesystem".to_string()),
+            dangerous: true,
+            required_permissions: None,
+        }
+    }
+
+    #[test]
+    fn test_compact_format() {
+        let out = format_tools_compact(&[sample_tool()]);
+        assert!(out.contains("- fs_write(append?: boolean, path: string) [dangerous] - Write a file."));
+        assert!(!out.contains("Creates parent"));
+    }
+
+    #[test]
+    fn test_auto_switches_over_budget() {
+        let tools = vec![sample_tool()];
+        let roomy = ToolPromptConfig { mode: ToolPromptMode::Auto, token_budget: 10_000, native: true };
+        assert_eq!(render_tools_prompt(&tools, &roomy).1, ToolPromptMode::Full);
+
+        let tight = ToolPromptConfig { mode: ToolPromptMode::Auto, token_budget: 10, native: true };
+        let (prompt, mode) = render_tools_prompt(&tools, &tight);
+        assert_eq!(mode, ToolPromptMode::Compact);
+        assert!(estimate_tokens(&prompt) < estimate_tokens(&crate::tools::format_tools_for_llm(&tools)));
+    }
+
+    #[test]
+    fn test_default_mode_is_full() {
+        assert_eq!(ToolPromptConfig::default().mode, ToolPromptMode::Full);
+        let config: ToolPromptConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(render_tools_prompt(&[sample_tool()], &config).1, ToolPromptMode::Full);
+    }
+
+    /// 内置工具上的 full / compact 对比喵：精简格式必须保留每个工具名与必填参数（调用正确性所需的信息）
+    #[test]
+    fn test_compact_keeps_call_surface_of_builtin_tools() {
+        use crate::tools::{EchoTool, FileSystemTool, FsPatchTool, FsSearchTool, FsWriteTool, Tool};
+        let root = std::path::Path::new("/tmp");
+        let tools: Vec<ToolDescription> = vec![
+            FileSystemTool::new(root).describe(),
+            FsWriteTool::new(root).describe(),
+            FsSearchTool::new(root).describe(),
+            FsPatchTool::new(root).describe(),
+            EchoTool.describe(),
+        ];
+
+        let full = crate::tools::format_tools_for_llm(&tools);
+        let compact = format_tools_compact(&tools);
+        for tool in &tools {
+            let line = compact.lines().find(|l| l.starts_with(&format!("- {}(", tool.name))).unwrap();
+            let required = tool.input_schema.get("required").and_then(|r| r.as_array()).cloned().unwrap_or_default();
+            for param in required.iter().filter_map(|p| p.as_str()) {
+                assert!(line.contains(&format!("{}: ", param)), "{} 缺少必填参数 {}", tool.name, param);
+            }
+        }
+        assert!(estimate_tokens(&compact) * 2 < estimate_tokens(&full));
+    }
+
+    #[test]
+    fn test_prompt_cache_invalidates_on_source_change() {
+        let cache = PromptCache::new();
+        let renders = std::cell::Cell::new(0);
+        let render = || {
+            renders.set(renders.get() + 1);
+            format_tools_compact(&[sample_tool()])
+        };
+        let first = cache.section((1, 7), "tools", render);
+        let again = cache.section((1, 7), "tools", render);
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(renders.get(), 1);
+        assert_eq!(cache.stats(), (1, 1));
+
+        // 技能重新加载（指纹变化）后重新渲染喵
+        cache.section((1, 8), "tools", render);
+        assert_eq!(renders.get(), 2);
+    }
+}
@@ -5,18 +5,30 @@
 /// 功能：
 /// - Shell 命令执行（白名单保护）
 /// - 同步/异步执行模式
-/// - 超时控制
-/// - 输出捕获
+/// - 超时控制（按命令配置，模型请求的超时不超过上限）
+/// - 输出捕获（过长时保留首尾截断）
+///
+/// ```toml
+/// [security.shell]
+/// default_timeout_secs = 30
+/// max_timeout_secs = 300
+/// max_output_bytes = 16384
+/// command_timeouts = { cargo = 600 }
+/// ```
 ///
 /// 🔒 SAFETY: 所有命令必须通过 allowlist 检查，禁止任意命令执行
 ///
 /// 实现者: 诺诺 (Nono) ⚡
-use crate::security::{AllowlistService, SandboxService};
+use crate::security::{
+    AllowlistService, EscalationManager, PolicyViolation, SandboxConfig, SandboxService,
+    ScratchSpace, ToolEnvironment,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
-use tracing::warn;
+use tracing::{debug, warn};
 
 /// 🔒 SAFETY: Shell 工具错误类型喵
 #[derive(Debug, Error)]
@@ -36,6 +48,105 @@ pub enum ShellError {
     /// IO 错误
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    /// 被安全策略拦截（结构化详情）
+    #[error("{0}")]
+    PolicyDenied(PolicyViolation),
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_timeout_secs() -> u64 {
+    300
+}
+
+fn default_max_output_bytes() -> usize {
+    16 * 1024
+}
+
+/// 🔒 SAFETY: Shell 工具的超时与输出上限配置喵
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShellToolConfig {
+    /// 未指定超时时使用（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub default_timeout_secs: u64,
+    /// 请求超时的上限（秒）
+    #[serde(default = "default_max_timeout_secs")]
+    pub max_timeout_secs: u64,
+    /// stdout / stderr 各自保留的最大字节数（超出部分截断）
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    /// 按命令覆盖：该命令的默认超时，同时也是它的上限（秒）
+    #[serde(default)]
+    pub command_timeouts: HashMap<String, u64>,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout_secs: default_timeout_secs(),
+            max_timeout_secs: default_max_timeout_secs(),
+            max_output_bytes: default_max_output_bytes(),
+            command_timeouts: HashMap::new(),
+        }
+    }
+}
+
+impl ShellToolConfig {
+    /// 未指定超时时命令的默认超时喵
+    pub fn default_timeout(&self, command: &str) -> u64 {
+        self.command_timeouts
+            .get(command)
+            .copied()
+            .unwrap_or(self.default_timeout_secs)
+    }
+
+    /// 🔒 SAFETY: 把请求的超时限制在 [1, 上限] 内喵
+    pub fn clamp_timeout(&self, command: &str, requested: u64) -> u64 {
+        let max = self
+            .command_timeouts
+            .get(command)
+            .copied()
+            .unwrap_or(self.max_timeout_secs);
+        requested.clamp(1, max.max(1))
+    }
+}
+
+/// 截断过长的输出，保留开头与结尾喵
+///
+/// ## Returns
+/// (截断后的文本, 是否截断)
+pub fn truncate_output(text: &str, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text.to_string(), false);
+    }
+    let head_end = floor_char_boundary(text, max_bytes / 2);
+    let tail_start = ceil_char_boundary(text, text.len() - (max_bytes - max_bytes / 2));
+    let omitted = tail_start - head_end;
+    (
+        format!(
+            "{}\n... [{} bytes truncated] ...\n{}",
+            &text[..head_end],
+            omitted,
+            &text[tail_start..]
+        ),
+        true,
+    )
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
 }
 
 /// 🔒 SAFETY: Shell 执行结果结构体喵
@@ -51,6 +162,9 @@ pub struct ShellResult {
     pub duration_ms: u64,
     /// 是否成功
     pub success: bool,
+    /// 输出是否被截断
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 impl ShellResult {
@@ -62,6 +176,7 @@ impl ShellResult {
             stderr,
             duration_ms,
             success: true,
+            truncated: false,
         }
     }
 
@@ -73,6 +188,7 @@ impl ShellResult {
             stderr,
             duration_ms,
             success: false,
+            truncated: false,
         }
     }
 }
@@ -88,8 +204,10 @@ pub struct ShellRequest {
     pub work_dir: Option<String>,
     /// 超时时间（秒，默认 30）
     pub timeout_secs: u64,
-    /// 环境变量（可选，会经过白名单检查）
+    /// 环境变量（不支持：子进程环境只由 `security.tool_env` 决定，非空时拒绝执行）
     pub env: Option<Vec<(String, String)>>,
+    /// 主人批准后取得的一次性放行令牌（可选）
+    pub override_token: Option<String>,
 }
 
 impl Default for ShellRequest {
@@ -100,6 +218,7 @@ impl Default for ShellRequest {
             work_dir: None,
             timeout_secs: 30,
             env: None,
+            override_token: None,
         }
     }
 }
@@ -111,16 +230,112 @@ pub struct ShellTool {
     allowlist: Arc<AllowlistService>,
     /// 沙箱执行器
     sandbox: Arc<SandboxService>,
+    /// 沙箱配置（放行令牌执行时复用同一环境喵）
+    sandbox_config: SandboxConfig,
+    /// 放行申请管理器（可选）
+    escalation: Option<Arc<EscalationManager>>,
+    /// 会话临时工作区（默认工作目录）
+    scratch: Option<ScratchSpace>,
+    /// 只能在临时工作区中执行（无痕模式）
+    scratch_only: bool,
+    /// 超时与输出上限
+    limits: ShellToolConfig,
 }
 
 impl ShellTool {
     /// 🔒 SAFETY: 创建新的 Shell 工具喵
     pub fn new(allowlist: Arc<AllowlistService>) -> Self {
+        let sandbox_config = SandboxConfig::default();
         let sandbox = Arc::new(SandboxService::new(
             (*allowlist).clone(),
-            Default::default(),
+            sandbox_config.clone(),
+        ));
+        Self {
+            allowlist,
+            sandbox,
+            sandbox_config,
+            escalation: None,
+            scratch: None,
+            scratch_only: false,
+            limits: ShellToolConfig::default(),
+        }
+    }
+
+    /// 设置超时与输出上限喵
+    pub fn with_limits(mut self, limits: ShellToolConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 超时与输出上限喵
+    pub fn limits(&self) -> &ShellToolConfig {
+        &self.limits
+    }
+
+    /// 设置默认工作目录喵（临时工作区优先）
+    pub fn with_working_dir(mut self, dir: &std::path::Path) -> Self {
+        self.sandbox_config.working_directory = Some(dir.to_string_lossy().into_owned());
+        self.sandbox = Arc::new(SandboxService::new(
+            (*self.allowlist).clone(),
+            self.sandbox_config.clone(),
         ));
-        Self { allowlist, sandbox }
+        self
+    }
+
+    /// 🔒 SAFETY: 设置子进程环境（替换默认的最小环境）喵
+    pub fn with_environment(mut self, environment: ToolEnvironment) -> Self {
+        debug!(
+            "Shell tool environment: {:?}",
+            environment.names().collect::<Vec<_>>()
+        );
+        self.sandbox_config.environment = environment;
+        self.sandbox = Arc::new(SandboxService::new(
+            (*self.allowlist).clone(),
+            self.sandbox_config.clone(),
+        ));
+        self
+    }
+
+    /// 🔒 SAFETY: 默认在会话临时工作区中执行命令喵（超出大小上限后拒绝执行）
+    pub fn with_scratch(mut self, scratch: ScratchSpace) -> Self {
+        self.sandbox_config.working_directory = Some(scratch.dir().to_string_lossy().into_owned());
+        self.sandbox = Arc::new(SandboxService::new(
+            (*self.allowlist).clone(),
+            self.sandbox_config.clone(),
+        ));
+        self.scratch = Some(scratch);
+        self
+    }
+
+    /// 🔒 SAFETY: 只在临时工作区中执行命令喵（无痕模式：请求不能用 `work_dir` 指向其他目录）
+    pub fn with_scratch_only(mut self, scratch: ScratchSpace) -> Self {
+        self.scratch_only = true;
+        self.with_scratch(scratch)
+    }
+
+    /// 🔒 SAFETY: 启用放行申请（被拦截的操作可凭一次性令牌执行）喵
+    pub fn with_escalation(mut self, escalation: Arc<EscalationManager>) -> Self {
+        self.escalation = Some(escalation);
+        self
+    }
+
+    /// 尝试消费放行令牌（失败时记录拦截以便申请放行）喵
+    ///
+    /// 🔒 SAFETY: 令牌只对申请时的完整 argv 生效喵
+    fn try_override(&self, violation: &PolicyViolation, request: &ShellRequest) -> bool {
+        let Some(manager) = &self.escalation else {
+            return false;
+        };
+        let invocation: Vec<String> = std::iter::once(request.command.clone())
+            .chain(request.args.iter().cloned())
+            .collect();
+        if let Some(token) = &request.override_token {
+            if manager.redeem("shell", violation, &invocation, token).is_some() {
+                return true;
+            }
+        }
+        manager.note_denial("shell", violation, &invocation);
+        false
     }
 
     /// 🔒 SAFETY: 同步执行 Shell 命令喵
@@ -128,38 +343,89 @@ impl ShellTool {
     pub async fn execute(&self, request: ShellRequest) -> Result<ShellResult, ShellError> {
         let start = std::time::Instant::now();
 
-        // 🔍 检查命令是否在白名单
-        if !self.allowlist.is_command_allowed(&request.command) {
-            warn!("Command not allowed: {}", request.command);
-            return Err(ShellError::CommandNotAllowed(request.command));
+        // 🔍 检查命令是否在白名单（已批准的放行令牌可越过一次）
+        let mut override_sandbox = None;
+        if let Err(e) = self.allowlist.check_command(&request.command) {
+            let violation = self.allowlist.explain(&e);
+            if !self.try_override(&violation, &request) {
+                warn!("Command not allowed: {}", request.command);
+                return Err(ShellError::PolicyDenied(violation));
+            }
+            override_sandbox = Some(SandboxService::new(
+                self.allowlist.with_command_override(&request.command, &request.args),
+                self.sandbox_config.clone(),
+            ));
+        }
+
+        // 🔍 无痕模式的工作目录固定为临时工作区（放行令牌也不能改）
+        if let (true, Some(work_dir)) = (self.scratch_only, &request.work_dir) {
+            warn!("Work directory rejected in scratch-only mode: {}", work_dir);
+            return Err(ShellError::PolicyDenied(
+                PolicyViolation::new(
+                    "scratch_only",
+                    work_dir.as_str(),
+                    "commands run only in the session scratch directory",
+                )
+                .with_suggestion("omit 'work_dir'"),
+            ));
         }
 
         // 🔍 检查工作目录是否在白名单
         if let Some(ref work_dir) = request.work_dir {
-            if !self.allowlist.is_path_allowed(work_dir) {
-                warn!("Work directory not allowed: {}", work_dir);
-                return Err(ShellError::PathTraversal);
+            if let Err(e) = self.allowlist.check_path(work_dir) {
+                let violation = self.allowlist.explain(&e);
+                if !self.try_override(&violation, &request) {
+                    warn!("Work directory not allowed: {}", work_dir);
+                    return Err(ShellError::PolicyDenied(violation));
+                }
             }
         }
 
-        // 🔍 检查环境变量
-        if let Some(ref env_vars) = request.env {
-            for (key, _) in env_vars {
-                if !self.allowlist.is_env_var_allowed(key) {
-                    warn!("Environment variable not allowed: {}", key);
-                    return Err(ShellError::ExecutionFailed(format!(
-                        "Env var '{}' not allowed",
-                        key
-                    )));
-                }
+        // 🔍 临时工作区已超出大小上限时不再执行命令
+        if let (Some(scratch), None) = (&self.scratch, &request.work_dir) {
+            if let Err(violation) = scratch.check_quota(0, 0) {
+                warn!("Scratch directory over quota: {}", scratch.dir().display());
+                return Err(ShellError::PolicyDenied(violation));
             }
         }
 
-        // 🛡️ 使用沙箱执行命令（自动检查参数注入）
-        let timeout = Duration::from_secs(request.timeout_secs);
+        // 🔍 子进程环境只由 `security.tool_env` 决定，请求不能自带变量
+        if let Some((key, _)) = request.env.as_ref().and_then(|vars| vars.first()) {
+            let suggestion = match self.sandbox_config.environment.names().any(|name| name == key) {
+                true => format!("{} is already provided by security.tool_env; omit 'env'", key),
+                false => format!("Ask the owner to add {} to security.tool_env (inherit, set or secrets)", key),
+            };
+            warn!("Environment variable rejected: {}", key);
+            return Err(ShellError::PolicyDenied(
+                PolicyViolation::new(
+                    "tool_env",
+                    key.as_str(),
+                    "the tool environment is fixed by security.tool_env",
+                )
+                .with_suggestion(suggestion),
+            ));
+        }
+
+        // 🔍 检查参数（arg_pattern、个数/长度上限、元字符），放行令牌不豁免参数校验
         let args: Vec<&str> = request.args.iter().map(|s| s.as_str()).collect();
-        let result = self
-            .sandbox
+        let arg_check = match override_sandbox {
+            Some(_) => self
+                .allowlist
+                .with_command_override(&request.command, &request.args)
+                .check_arguments(&request.command, &args),
+            None => self.allowlist.check_arguments(&request.command, &args),
+        };
+        if let Err(e) = arg_check {
+            warn!("Arguments rejected for {}: {}", request.command, e);
+            return Err(ShellError::PolicyDenied(self.allowlist.explain(&e)));
+        }
+
+        // 🛡️ 使用沙箱执行命令
+        let timeout_secs = self.limits.clamp_timeout(&request.command, request.timeout_secs);
+        let timeout = Duration::from_secs(timeout_secs);
+        let result = override_sandbox
+            .as_ref()
+            .unwrap_or(&self.sandbox)
             .execute_async(
                 &request.command,
                 &args,
@@ -169,19 +435,23 @@ impl ShellTool {
             .await
             .map_err(|e| ShellError::ExecutionFailed(e.to_string()))?;
 
+        if result.timed_out {
+            warn!("Command timed out after {}s: {}", timeout_secs, request.command);
+            return Err(ShellError::Timeout(timeout_secs));
+        }
+
         let duration = start.elapsed().as_millis() as u64;
 
-        // 解析结果
-        if result.exit_code == 0 {
-            Ok(ShellResult::success(result.stdout, result.stderr, duration))
+        // 解析结果（过长的输出截断后返回）
+        let (stdout, stdout_truncated) = truncate_output(&result.stdout, self.limits.max_output_bytes);
+        let (stderr, stderr_truncated) = truncate_output(&result.stderr, self.limits.max_output_bytes);
+        let mut shell_result = if result.exit_code == 0 {
+            ShellResult::success(stdout, stderr, duration)
         } else {
-            Ok(ShellResult::failure(
-                result.exit_code,
-                result.stdout,
-                result.stderr,
-                duration,
-            ))
-        }
+            ShellResult::failure(result.exit_code, stdout, stderr, duration)
+        };
+        shell_result.truncated = stdout_truncated || stderr_truncated;
+        Ok(shell_result)
     }
 
     /// 🔒 SAFETY: 快捷接口喵
@@ -216,6 +486,7 @@ impl ShellTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::{AllowlistConfig, ToolEnvConfig};
 
     #[test]
     fn test_shell_result() {
@@ -235,4 +506,78 @@ mod tests {
         assert!(request.args.is_empty());
         assert_eq!(request.timeout_secs, 30);
     }
+
+    #[test]
+    fn test_output_truncation_and_timeout_limits() {
+        let (short, truncated) = truncate_output("clean", 16);
+        assert_eq!((short.as_str(), truncated), ("clean", false));
+
+        let long = format!("{}{}", "é".repeat(20), "z".repeat(20));
+        let (text, truncated) = truncate_output(&long, 20);
+        assert!(truncated);
+        assert!(text.starts_with("éééé"));
+        assert!(text.ends_with(&"z".repeat(10)));
+        assert!(text.contains("[40 bytes truncated]"));
+
+        let limits: ShellToolConfig = toml::from_str("max_timeout_secs = 60\ncommand_timeouts = { cargo = 600 }").unwrap();
+        assert_eq!(limits.default_timeout("git"), 30);
+        assert_eq!(limits.clamp_timeout("git", 3600), 60);
+        assert_eq!(limits.clamp_timeout("git", 0), 1);
+        assert_eq!(limits.default_timeout("cargo"), 600);
+        assert_eq!(limits.clamp_timeout("cargo", 3600), 600);
+    }
+
+    #[tokio::test]
+    async fn test_request_env_is_rejected_against_tool_env() {
+        let mut config = ToolEnvConfig::default();
+        config.set.insert("CI".to_string(), "1".to_string());
+        let tool = ShellTool::new(Arc::new(AllowlistService::new(AllowlistConfig::default())))
+            .with_environment(ToolEnvironment::resolve(&config).unwrap());
+        let request = |key: &str| ShellRequest {
+            command: "pwd".to_string(),
+            env: Some(vec![(key.to_string(), "x".to_string())]),
+            ..ShellRequest::default()
+        };
+
+        let Err(ShellError::PolicyDenied(violation)) = tool.execute(request("CI")).await else {
+            panic!("env override should be rejected");
+        };
+        assert_eq!((violation.policy.as_str(), violation.blocked.as_str()), ("tool_env", "CI"));
+        assert!(violation.suggestion.unwrap().contains("already provided"));
+
+        let Err(ShellError::PolicyDenied(violation)) = tool.execute(request("LD_PRELOAD")).await else {
+            panic!("env override should be rejected");
+        };
+        assert!(violation.suggestion.unwrap().contains("add LD_PRELOAD to security.tool_env"));
+    }
+
+    #[tokio::test]
+    async fn test_scratch_only_pins_work_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let scratch = ScratchSpace::new(dir.path().join("scratch"), 1 << 20).unwrap();
+        let tool = ShellTool::new(Arc::new(AllowlistService::new(AllowlistConfig::default())))
+            .with_scratch_only(scratch.clone());
+
+        let escape = ShellRequest {
+            command: "pwd".to_string(),
+            work_dir: Some(dir.path().to_string_lossy().into_owned()),
+            ..ShellRequest::default()
+        };
+        let Err(ShellError::PolicyDenied(violation)) = tool.execute(escape).await else {
+            panic!("explicit work_dir should be rejected");
+        };
+        assert_eq!(violation.policy, "scratch_only");
+
+        let result = tool
+            .execute(ShellRequest {
+                command: "pwd".to_string(),
+                ..ShellRequest::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            std::path::Path::new(result.stdout.trim()).canonicalize().unwrap(),
+            scratch.dir().canonicalize().unwrap()
+        );
+    }
 }
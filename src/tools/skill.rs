@@ -0,0 +1,355 @@
+//! # Skill Runner
+//!
+//! 🔧 把 SKILL.md 定义的技能作为一等工具执行喵
+//!
+//! @诺诺 的技能执行器实现喵
+//!
+//! ## 功能
+//! - `@skill({"name": "天气查询", "args": {"city": "Tokyo"}})` 按名称查找技能
+//! - 按 `## 参数` 校验入参：未知参数、缺少必填参数都会拒绝，可选参数补全默认值
+//! - 命令模板中的 `{参数名}` 替换为参数值，模板未引用的参数追加为 `--参数名 值`
+//! - 经 SandboxService 执行（白名单 + 参数校验 + 超时），工作目录为技能目录
+//! - 返回结构化结果（exit_code / stdout / stderr / timed_out）
+//! - 技能来自 `SharedSkills`，守护进程重新加载技能目录后立即可用
+//!
+//! 🔒 SAFETY: 不经过 shell 解释，每个参数值只作为一个 argv 传入；
+//! 命令本身仍需在 `[security.allowlist]` 中
+//!
+//! Author: 诺诺 (Nono) ⚡
+
+use super::mcp::{Tool, ToolDescription, ToolError, ToolResult};
+use super::shell::{truncate_output, ShellToolConfig};
+use crate::security::{AllowlistService, SandboxConfig, SandboxService, ToolEnvironment};
+use crate::skills::{SharedSkills, Skill};
+use serde_json::{json, Map, Value as JsonValue};
+use std::time::Duration;
+use tracing::warn;
+
+/// 🔒 SAFETY: 技能执行工具喵
+pub struct SkillTool {
+    /// 已加载的技能（只有声明了命令的技能可以执行）
+    skills: SharedSkills,
+    allowlist: AllowlistService,
+    environment: ToolEnvironment,
+    limits: ShellToolConfig,
+}
+
+impl SkillTool {
+    /// 🔒 SAFETY: 创建技能执行工具喵
+    ///
+    /// ## Arguments
+    /// * `skills` - 共享的技能集合（没有 `## 执行` 的技能会被忽略）喵
+    /// * `allowlist` - 与 Shell 工具共用的命令白名单喵
+    pub fn new(skills: SharedSkills, allowlist: AllowlistService) -> Self {
+        Self {
+            skills,
+            allowlist,
+            environment: ToolEnvironment::minimal(),
+            limits: ShellToolConfig::default(),
+        }
+    }
+
+    /// 🔒 SAFETY: 设置子进程环境（替换默认的最小环境）喵
+    pub fn with_environment(mut self, environment: ToolEnvironment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// 设置超时与输出上限喵（与 Shell 工具共用 `[security.shell]`）
+    pub fn with_limits(mut self, limits: ShellToolConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 没有可执行的技能时不必注册喵
+    pub fn is_empty(&self) -> bool {
+        self.runnable().is_empty()
+    }
+
+    /// 共享的技能集合（对话后端用来生成技能提示词）喵
+    pub fn skills(&self) -> &SharedSkills {
+        &self.skills
+    }
+
+    fn runnable(&self) -> Vec<Skill> {
+        self.skills.skills().into_iter().filter(|s| s.command.is_some()).collect()
+    }
+
+    fn find(&self, name: &str) -> Option<Skill> {
+        self.skills.get(name).filter(|s| s.command.is_some())
+    }
+}
+
+/// 🔒 SAFETY: 按参数定义校验入参并展开命令模板喵
+///
+/// ## Returns
+/// (命令, 参数列表)
+pub fn resolve_command(skill: &Skill, args: &Map<String, JsonValue>) -> Result<(String, Vec<String>), ToolError> {
+    let template = skill
+        .command
+        .as_deref()
+        .ok_or_else(|| ToolError::ValidationError(format!("Skill '{}' has no command", skill.name)))?;
+
+    if let Some(unknown) = args.keys().find(|k| !skill.parameters.iter().any(|p| &p.name == *k)) {
+        return Err(ToolError::ValidationError(format!(
+            "Unknown argument '{}' for skill '{}'",
+            unknown, skill.name
+        )));
+    }
+
+    // 参数名 → 值（补全默认值，缺少必填参数时报错）
+    let mut values: Vec<(&str, Option<String>)> = Vec::new();
+    for param in &skill.parameters {
+        let value = match args.get(&param.name) {
+            Some(JsonValue::String(s)) => Some(s.clone()),
+            Some(v @ (JsonValue::Number(_) | JsonValue::Bool(_))) => Some(v.to_string()),
+            Some(JsonValue::Null) | None => param.default.clone(),
+            Some(_) => {
+                return Err(ToolError::ValidationError(format!(
+                    "Argument '{}' must be a string, number or boolean",
+                    param.name
+                )))
+            }
+        };
+        if value.is_none() && param.required {
+            return Err(ToolError::ValidationError(format!(
+                "Missing required argument '{}' for skill '{}'",
+                param.name, skill.name
+            )));
+        }
+        values.push((param.name.as_str(), value));
+    }
+
+    let mut tokens = template.split_whitespace();
+    let command = tokens
+        .next()
+        .ok_or_else(|| ToolError::ValidationError(format!("Skill '{}' has an empty command", skill.name)))?
+        .to_string();
+
+    let mut argv = Vec::new();
+    let mut referenced = Vec::new();
+    for token in tokens {
+        let mut expanded = token.to_string();
+        let mut dropped = false;
+        for (name, value) in &values {
+            let placeholder = format!("{{{}}}", name);
+            if !token.contains(&placeholder) {
+                continue;
+            }
+            referenced.push(*name);
+            match value {
+                Some(value) => expanded = expanded.replace(&placeholder, value),
+                // 整个 token 就是未提供的可选参数时省略喵
+                None if token == placeholder => dropped = true,
+                None => {
+                    return Err(ToolError::ValidationError(format!(
+                        "Argument '{}' is required by the command template",
+                        name
+                    )))
+                }
+            }
+        }
+        if !dropped {
+            argv.push(expanded);
+        }
+    }
+    for (name, value) in &values {
+        if let (false, Some(value)) = (referenced.contains(name), value) {
+            argv.push(format!("--{}", name));
+            argv.push(value.clone());
+        }
+    }
+    Ok((command, argv))
+}
+
+#[async_trait::async_trait]
+impl Tool for SkillTool {
+    fn describe(&self) -> ToolDescription {
+        let runnable = self.runnable();
+        let names: Vec<&str> = runnable.iter().map(|s| s.name.as_str()).collect();
+        ToolDescription {
+            name: "skill".to_string(),
+            description: format!(
+                "Run a skill in the sandbox. Available skills: {}. Pass the skill's parameters in 'args' \
+                (see the skills section for each skill's parameters); the result contains exit_code, stdout and stderr.",
+                names.join(", ")
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "enum": names,
+                        "description": "Skill name"
+                    },
+                    "args": {
+                        "type": "object",
+                        "description": "Skill parameters as name → value"
+                    },
+                    "timeout": {
+                        "type": "integer",
+                        "description": format!(
+                            "Timeout in seconds (default: {}, max: {})",
+                            self.limits.default_timeout_secs, self.limits.max_timeout_secs
+                        )
+                    }
+                },
+                "required": ["name"]
+            }),
+            category: Some("skill".to_string()),
+            dangerous: true,
+            required_permissions: Some(vec!["shell.execute".to_string()]),
+        }
+    }
+
+    fn validate_input(&self, input: &JsonValue) -> Result<(), ToolError> {
+        let name = input
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| ToolError::ValidationError("Missing required field: 'name'".to_string()))?;
+        if self.find(name).is_none() {
+            return Err(ToolError::ValidationError(format!("Unknown skill '{}'", name)));
+        }
+        match input.get("args") {
+            Some(args) if !args.is_object() && !args.is_null() => {
+                Err(ToolError::ValidationError("'args' must be an object".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn execute(&self, input: JsonValue) -> Result<ToolResult, ToolError> {
+        let start = std::time::Instant::now();
+        let name = input.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+        let skill = self
+            .find(name)
+            .ok_or_else(|| ToolError::ValidationError(format!("Unknown skill '{}'", name)))?;
+        let empty = Map::new();
+        let args = input.get("args").and_then(|a| a.as_object()).unwrap_or(&empty);
+        let (command, argv) = resolve_command(&skill, args)?;
+
+        // 🔍 白名单与参数校验（拦截时返回结构化的策略详情）
+        let arg_refs: Vec<&str> = argv.iter().map(|s| s.as_str()).collect();
+        if let Err(e) = self
+            .allowlist
+            .check_command(&command)
+            .and_then(|_| self.allowlist.check_arguments(&command, &arg_refs))
+        {
+            warn!("Skill {} rejected by allowlist: {}", skill.name, e);
+            return Err(ToolError::PolicyDenied(self.allowlist.explain(&e)));
+        }
+
+        let requested = input
+            .get("timeout")
+            .and_then(|t| t.as_u64())
+            .unwrap_or_else(|| self.limits.default_timeout(&command));
+        let timeout_secs = self.limits.clamp_timeout(&command, requested);
+        let skill_dir = skill.path.to_string_lossy();
+        let sandbox = SandboxService::new(
+            self.allowlist.clone(),
+            SandboxConfig {
+                timeout_seconds: timeout_secs,
+                max_output_size: self.limits.max_output_bytes,
+                working_directory: Some(skill_dir.to_string()),
+                environment: self.environment.clone(),
+            },
+        );
+        let result = sandbox
+            .execute_async(&command, &arg_refs, Some(&skill_dir), Some(Duration::from_secs(timeout_secs)))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Skill '{}' failed: {}", skill.name, e)))?;
+
+        if result.timed_out {
+            warn!("Skill {} timed out after {}s", skill.name, timeout_secs);
+            return Err(ToolError::ExecutionFailed(format!(
+                "Skill '{}' timed out after {}s and was stopped",
+                skill.name, timeout_secs
+            )));
+        }
+
+        let (stdout, stdout_truncated) = truncate_output(&result.stdout, self.limits.max_output_bytes);
+        let (stderr, stderr_truncated) = truncate_output(&result.stderr, self.limits.max_output_bytes);
+        Ok(ToolResult::success(
+            json!({
+                "skill": skill.name,
+                "exit_code": result.exit_code,
+                "success": result.exit_code == 0,
+                "stdout": stdout,
+                "stderr": stderr,
+                "truncated": stdout_truncated || stderr_truncated,
+                "duration_ms": result.duration_ms as u64,
+            }),
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::AllowlistConfig;
+    use crate::skills::{SkillParameter, SkillsManager};
+
+    fn param(name: &str, required: bool, default: Option<&str>) -> SkillParameter {
+        SkillParameter {
+            name: name.to_string(),
+            description: String::new(),
+            required,
+            default: default.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_resolve_command_validates_args() {
+        let skill = Skill {
+            name: "show".to_string(),
+            description: "Show a file".to_string(),
+            path: std::path::PathBuf::from("skills/show"),
+            command: Some("cat {file} {extra}".to_string()),
+            parameters: vec![
+                param("file", true, None),
+                param("extra", false, None),
+                param("lines", false, Some("10")),
+            ],
+        };
+
+        let args = |value: JsonValue| value.as_object().unwrap().clone();
+        assert!(resolve_command(&skill, &args(json!({}))).is_err());
+        assert!(resolve_command(&skill, &args(json!({ "file": "a", "bogus": 1 }))).is_err());
+        assert!(resolve_command(&skill, &args(json!({ "file": ["a"] }))).is_err());
+        let (command, argv) = resolve_command(&skill, &args(json!({ "file": "a b", "lines": 3 }))).unwrap();
+        assert_eq!(command, "cat");
+        assert_eq!(argv, vec!["a b", "--lines", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_skill_tool_runs_in_sandbox() {
+        let skills_dir = tempfile::tempdir().unwrap();
+        let dir = skills_dir.path().join("show");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "hello skill").unwrap();
+        std::fs::write(
+            dir.join("SKILL.md"),
+            "# show\n\nShow a file\n\n## 执行\n`cat {file}`\n\n## 参数\n- `file` (必填): File name\n",
+        )
+        .unwrap();
+        let mut manager = SkillsManager::new(skills_dir.path().to_path_buf());
+        manager.load_all().unwrap();
+        let tool = SkillTool::new(SharedSkills::new(manager), AllowlistService::new(AllowlistConfig::default()));
+
+        assert!(tool.validate_input(&json!({ "name": "missing" })).is_err());
+        let result = tool
+            .execute(json!({ "name": "show", "args": { "file": "notes.txt" } }))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["exit_code"], 0);
+        assert_eq!(data["stdout"], "hello skill");
+
+        // 参数注入被白名单拦截喵
+        let denied = tool
+            .execute(json!({ "name": "show", "args": { "file": "notes.txt; rm -rf /" } }))
+            .await;
+        assert!(matches!(denied, Err(ToolError::PolicyDenied(_))));
+    }
+}
@@ -1,24 +1,64 @@
-/// 配置迁移测试 🧪
-///
-/// @诺诺 的配置迁移测试实现喵
-///
-/// 测试内容：
-/// - OpenClaw → Neko-Claw 配置转换
-/// - 必填项验证
-/// - 类型验证
-/// - 迁移完整性检查
-///
-/// 🔒 SAFETY: 所有测试必须在迁移前通过
-///
-/// 实现者: 诺诺 (Nono) ⚡
+//! 配置迁移测试 🧪
+//!
+//! @诺诺 的配置迁移测试实现喵
+//!
+//! 测试内容：
+//! - OpenClaw → Neko-Claw 配置转换
+//! - 必填项验证
+//! - 类型验证
+//! - 迁移完整性检查
+//!
+//! 🔒 SAFETY: 所有测试必须在迁移前通过
+//!
+//! 实现者: 诺诺 (Nono) ⚡
 
 #[cfg(test)]
 mod config_migration_tests {
-    use super::super::validator::{
+    use nekoclaw::config::validator::{
         ConfigValidator, MigrationValidator, ValidationError, ValidationResult, ValidationRule,
     };
     use serde_json::json;
 
+    /// 完整且格式正确的 OpenClaw 配置喵
+    fn valid_openclaw_config() -> serde_json::Value {
+        json!({
+            "version": "1.0.0",
+            "gateway": {
+                "host": "localhost",
+                "port": 8080
+            },
+            "models": {
+                "providers": {
+                    "nvidia": {
+                        "apiKey": "test-api-key-123456"
+                    }
+                }
+            },
+            "channels": {
+                "discord": {
+                    "accounts": {
+                        "main_bot": {
+                            "token": "MTIzNDU2Nzg5MDEyMzQ1Njc4OTA.GaBcDe.abcdefghijklmnopqrstuvwxyz0123456"
+                        }
+                    }
+                }
+            },
+            "agents": {
+                "defaults": {
+                    "model": {
+                        "primary": "nvidia/z-ai/glm4.7"
+                    }
+                }
+            },
+            "memory": {
+                "enabled": true
+            },
+            "performance": {
+                "maxContextTokens": 8192
+            }
+        })
+    }
+
     /// 🔒 SAFETY: 测试必填项检查喵
     #[test]
     fn test_required_field_validation() {
@@ -184,15 +224,8 @@ mod config_migration_tests {
     fn test_migration_provider_config() {
         let validator = MigrationValidator::new();
 
-        let valid_config = json!({
-            "models": {
-                "providers": {
-                    "nvidia": {
-                        "apiKey": "test-api-key-123456"
-                    }
-                }
-            }
-        });
+        let mut valid_config = valid_openclaw_config();
+        valid_config["models"]["providers"]["nvidia"]["apiKey"] = json!("test-api-key-123456");
 
         let result = validator.validate_openclaw_config(&valid_config);
         assert!(result.is_ok());
@@ -224,15 +257,8 @@ mod config_migration_tests {
     fn test_migration_agent_config() {
         let validator = MigrationValidator::new();
 
-        let valid_config = json!({
-            "agents": {
-                "defaults": {
-                    "model": {
-                        "primary": "nvidia/z-ai/glm4.7"
-                    }
-                }
-            }
-        });
+        let mut valid_config = valid_openclaw_config();
+        valid_config["agents"]["defaults"]["model"]["primary"] = json!("nvidia/z-ai/glm4.7");
 
         let result = validator.validate_openclaw_config(&valid_config);
         assert!(result.is_ok());
@@ -243,42 +269,7 @@ mod config_migration_tests {
     fn test_full_openclaw_config_validation() {
         let validator = MigrationValidator::new();
 
-        let complete_config = json!({
-            "version": "1.0.0",
-            "gateway": {
-                "host": "localhost",
-                "port": 8080
-            },
-            "models": {
-                "providers": {
-                    "nvidia": {
-                        "apiKey": "test-api-key-123456"
-                    }
-                }
-            },
-            "channels": {
-                "discord": {
-                    "accounts": {
-                        "main_bot": {
-                            "token": "DISCORD_BOT_TOKEN_PLACEHOLDER"
-                        }
-                    }
-                }
-            },
-            "agents": {
-                "defaults": {
-                    "model": {
-                        "primary": "nvidia/z-ai/glm4.7"
-                    }
-                }
-            },
-            "memory": {
-                "enabled": true
-            },
-            "performance": {
-                "maxContextTokens": 8192
-            }
-        });
+        let complete_config = valid_openclaw_config();
 
         let result = validator.validate_openclaw_config(&complete_config);
         assert!(result.is_ok());
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 /// Discord 渠道性能测试模块 ⚡
 ///
 /// @诺诺 的 Discord 集成性能验证喵
@@ -20,7 +22,7 @@ pub fn bench_discord_message_parse(c: &mut Criterion) {
     use serde_json;
 
     // 模拟真实的 Discord Gateway 消息
-    let test_messages = vec![
+    let test_messages = [
         r#"{"op":0,"s":1,"t":"MESSAGE_CREATE","d":{"id":"123456789","content":"Hello","author":{"id":"987654321","username":"User"}}}"#,
         r#"{"op":0,"s":2,"t":"MESSAGE_CREATE","d":{"id":"234567890","content":"测试中文","author":{"id":"876543210","username":"用户"}}}"#,
         r#"{"op":11,"d":null}"#, // 心跳确认
@@ -48,7 +50,7 @@ pub fn bench_discord_api_request(c: &mut Criterion) {
         b.iter(|| {
             rt.block_on(async {
                 // 模拟网络请求延迟
-                black_box(tokio::time::sleep(Duration::from_millis(10)).await);
+                tokio::time::sleep(Duration::from_millis(10)).await;
             })
         })
     });
@@ -96,11 +98,10 @@ pub fn bench_discord_websocket_connect(c: &mut Criterion) {
     c.bench_function("websocket_connect_simulated", |b| {
         b.iter(|| {
             // 模拟 WebSocket 握手
-            let _handshake = format!(
-                "GET / HTTP/1.1\r\n\
+            let _handshake = "GET / HTTP/1.1\r\n\
                  Host: gateway.discord.gg\r\n\
                  Upgrade: websocket\r\n\r\n"
-            );
+                .to_string();
             black_box(_handshake);
         })
     });
@@ -0,0 +1,642 @@
+//!
+//! # Gateway 流程集成测试
+//!
+//! ⚠️ SAFETY: 通过 `gateway::testing` 脚手架在临时端口上跑完整的 HTTP 流程喵
+//!
+//! ## 测试范围
+//! - 对话、记忆写入与 MCP 工具调用喵
+//! - 命名 Agent、上下文压缩与回复钩子喵
+//! - 认证、锁定、限流与幂等键喵
+//! - 遥测、反馈、Webhook 与管理接口喵
+//!
+//! ## 运行命令
+//! ```bash
+//! cargo test --test integration gateway_test -- --nocapture
+//! ```
+
+use nekoclaw::auth::{master_crypto, CredentialStore};
+use nekoclaw::core::traits::Message;
+use nekoclaw::gateway::api_keys::ApiKey;
+use nekoclaw::gateway::testing::*;
+use nekoclaw::gateway::{ChatBackend, DeviceCodes, DeviceKeys, DevicePairing, GatewayConfig, RateLimit};
+use nekoclaw::security::{ToolApproval, ToolApprovalConfig};
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn reply_text(body: &JsonValue) -> &str {
+    body["choices"][0]["message"]["content"].as_str().unwrap()
+}
+
+#[tokio::test]
+async fn test_chat_roundtrip_persists_memory() {
+    let gateway = TestGateway::start(
+        ScriptedProvider::new(["喵~ 你好"]),
+        ScriptedMcpServer::new(),
+    )
+    .await;
+
+    let (status, body) = gateway.chat("你好").await;
+    assert_eq!(status, 200);
+    assert_eq!(reply_text(&body), "喵~ 你好");
+
+    let saved = gateway.memory.list(None).unwrap();
+    assert_eq!(saved.len(), 1);
+    assert!(saved[0].content.contains("Q: 你好"));
+    assert!(gateway.tool_calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_chat_executes_mcp_tool() {
+    let gateway = TestGateway::start(
+        ScriptedProvider::new([r#"@echo({"text": "ping"})"#, "工具说 ping 喵"]),
+        ScriptedMcpServer::new().with_tool("echo", |args| {
+            args["text"].as_str().map(String::from).ok_or_else(|| "missing text".to_string())
+        }),
+    )
+    .await;
+
+    let (status, body) = gateway.chat("帮我回显 ping").await;
+    assert_eq!(status, 200);
+    assert_eq!(reply_text(&body), "工具说 ping 喵");
+
+    let calls = gateway.tool_calls.lock().unwrap().clone();
+    assert_eq!(calls, vec![("echo".to_string(), json!({ "text": "ping" }))]);
+    let prompts = gateway.provider.prompts();
+    assert_eq!(prompts.len(), 2);
+    assert_eq!(prompts[1].last().unwrap().content, "Tool result for echo: ping");
+
+    // gateway.request → agent.request → provider.chat / tool.execute → mcp.request，工具 Span 链接到两端喵
+    let spans = gateway.tracer.get_recent_spans(10).await;
+    let find = |name: &str| spans.iter().find(|s| s.name == name).unwrap();
+    let (request, tool, rpc) = (find("agent.request"), find("tool.execute"), find("mcp.request"));
+    assert_eq!(request.parent_span_id.as_ref(), Some(&find("gateway.request").span_id));
+    assert_eq!(spans.iter().filter(|s| s.name == "provider.chat").count(), 2);
+    assert!(spans
+        .iter()
+        .filter(|s| s.name == "provider.chat")
+        .all(|s| s.parent_span_id.as_ref() == Some(&request.span_id)));
+    assert_eq!(tool.parent_span_id.as_ref(), Some(&request.span_id));
+    assert_eq!(rpc.parent_span_id.as_ref(), Some(&tool.span_id));
+    assert!(spans.iter().all(|s| s.trace_id == request.trace_id));
+    let links: Vec<(&str, &str)> = tool
+        .links
+        .iter()
+        .map(|l| (l.relation.as_str(), l.span_id.as_str()))
+        .collect();
+    assert_eq!(
+        links,
+        vec![("triggered_by", request.span_id.as_str()), ("mcp_request", rpc.span_id.as_str())]
+    );
+}
+
+#[tokio::test]
+async fn test_backend_compresses_long_context() {
+    use nekoclaw::performance::compress::{CompressionStrategy, ContextCompressor};
+
+    let provider = Arc::new(ScriptedProvider::new(["好的喵"]));
+    let backend = ChatBackend::new(provider.clone())
+        .with_compression(ContextCompressor::new(CompressionStrategy::TimeBased, 200), None);
+    let mut messages = Vec::new();
+    for turn in 0..10 {
+        messages.push(Message::user(format!("question {} {}", turn, "detail ".repeat(30))));
+        messages.push(Message::assistant(format!("answer {} {}", turn, "detail ".repeat(30))));
+    }
+    messages.push(Message::user("最后的问题".to_string()));
+
+    backend.complete(messages.clone()).await.unwrap();
+    let sent = &provider.prompts()[0];
+    assert!(sent.len() < messages.len());
+    assert_eq!(sent.last().unwrap().content, "最后的问题");
+}
+
+#[tokio::test]
+async fn test_backend_applies_reply_hooks_for_channel_and_agent() {
+    use nekoclaw::core::{HookRegistry, PostProcessChain, PostProcessConfig};
+
+    let config: PostProcessConfig = serde_json::from_value(serde_json::json!({
+        "default": [{ "type": "trim_trailing_whitespace" }],
+        "channels": { "telegram": [{ "type": "strip_markdown" }] },
+        "agents": { "coder": [{ "type": "max_emoji", "max": 1 }] }
+    }))
+    .unwrap();
+    let mut hooks = HookRegistry::new();
+    hooks.register_reply_hook(Arc::new(PostProcessChain::compile(&config).unwrap()));
+
+    let ask = |channel: &'static str, agent: &'static str| {
+        let provider = Arc::new(ScriptedProvider::new(["**好的**喵 😺😸  "]));
+        let backend = ChatBackend::new(provider).with_reply_hooks(hooks.clone(), channel, agent);
+        async move { backend.complete(vec![Message::user("hi".to_string())]).await.unwrap() }
+    };
+
+    assert_eq!(ask("telegram", "coder").await, "好的喵 😺");
+    assert_eq!(ask("discord", "default").await, "**好的**喵 😺😸");
+}
+
+#[tokio::test]
+async fn test_destructive_tool_needs_auto_approve() {
+    let (mcp, calls) = ScriptedMcpServer::new()
+        .with_tool("drop_table", |_| Ok("dropped".to_string()))
+        .destructive("drop_table")
+        .connect()
+        .await;
+    let ask = |approval: ToolApprovalConfig| {
+        let provider = Arc::new(ScriptedProvider::new([r#"@drop_table({"name": "users"})"#, "好的喵"]));
+        let backend = ChatBackend::new(provider.clone())
+            .with_mcp(mcp.clone())
+            .with_approval(Arc::new(ToolApproval::new(&approval)));
+        async move {
+            backend.complete(vec![Message::user("清空 users 表".to_string())]).await.unwrap();
+            provider.prompts()[1].last().unwrap().content.clone()
+        }
+    };
+
+    // Gateway 无法交互确认：未放行时不执行，把原因交给模型喵
+    let denied = ask(ToolApprovalConfig::default()).await;
+    assert!(denied.contains("NOT_APPROVED drop_table is marked dangerous"));
+    assert!(calls.lock().unwrap().is_empty());
+
+    let approved = ask(ToolApprovalConfig {
+        auto_approve: vec!["drop_table".to_string()],
+    })
+    .await;
+    assert_eq!(approved, "Tool result for drop_table: dropped");
+    assert_eq!(calls.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_stream_sends_tool_status_heartbeats() {
+    let gateway = TestGateway::start_with_config(
+        ScriptedProvider::new([r#"@slow_build({"target": "all"})"#, "构建完成喵"]),
+        ScriptedMcpServer::new()
+            .with_tool("slow_build", |_| Ok("ok".to_string()))
+            .with_delay("slow_build", Duration::from_millis(350)),
+        GatewayConfig {
+            stream_heartbeat: Duration::from_millis(100),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let events = gateway.chat_stream("build it").await;
+    let statuses: Vec<&JsonValue> = events
+        .iter()
+        .map(|e| &e["choices"][0]["delta"])
+        .filter(|d| d["role"] == "tool_status")
+        .collect();
+    assert!(statuses.len() >= 3, "expected heartbeats, got {:?}", events);
+    assert!(statuses.iter().all(|d| d["tool"] == "slow_build"));
+
+    let [.., reply, stop, done] = events.as_slice() else {
+        panic!("stream too short: {:?}", events);
+    };
+    assert_eq!(reply["choices"][0]["delta"]["content"], "构建完成喵");
+    assert_eq!(stop["choices"][0]["finish_reason"], "stop");
+    assert_eq!(done, "[DONE]");
+}
+
+#[tokio::test]
+async fn test_gateways_are_isolated() {
+    let (a, b) = tokio::join!(
+        TestGateway::start(ScriptedProvider::new(["A"]), ScriptedMcpServer::new()),
+        TestGateway::start(ScriptedProvider::new(["B"]), ScriptedMcpServer::new()),
+    );
+    assert_ne!(a.base_url, b.base_url);
+
+    let ((_, body_a), (_, body_b)) = tokio::join!(a.chat("hi"), b.chat("hi"));
+    assert_eq!(reply_text(&body_a), "A");
+    assert_eq!(reply_text(&body_b), "B");
+
+    // 脚本用尽时 Provider 报错，Gateway 返回 502 喵
+    let (status, _) = a.chat("again").await;
+    assert_eq!(status, 502);
+    assert_eq!(b.memory.list(None).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_chat_routes_to_named_agent() {
+    let gateway = TestGateway::start(ScriptedProvider::new(["我是缪斯"]), ScriptedMcpServer::new()).await;
+    let send = |agent: &str| {
+        gateway
+            .client
+            .post(format!("{}/v1/chat/completions", gateway.base_url))
+            .bearer_auth(TEST_TOKEN)
+            .json(&json!({
+                "model": "scripted",
+                "agent": agent,
+                "messages": [{ "role": "user", "content": "你是谁" }],
+            }))
+            .send()
+    };
+
+    let response = send(TEST_AGENT.0).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: JsonValue = response.json().await.unwrap();
+    assert_eq!(reply_text(&body), "我是缪斯");
+    let prompt = &gateway.provider.prompts()[0];
+    assert_eq!((prompt[0].role.as_str(), prompt[0].content.as_str()), ("system", TEST_AGENT.1));
+    // Agent 后端没有挂载记忆喵
+    assert!(gateway.memory.list(None).unwrap().is_empty());
+
+    assert_eq!(send("nobody").await.unwrap().status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn test_telemetry_ingest() {
+    let gateway =
+        TestGateway::start(ScriptedProvider::default(), ScriptedMcpServer::new()).await;
+
+    let (status, body) = gateway
+        .send_authorized(
+            reqwest::Method::POST,
+            "/telemetry/events",
+            Some(json!({ "metrics": [
+                { "name": "plugin.jobs", "kind": "counter", "value": 3, "labels": { "queue": "mail" }, "source": "mailer" },
+                { "name": "plugin.started", "kind": "event" },
+            ]})),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(body["accepted"], 2);
+
+    let jobs = gateway.metrics.get_recent_custom_metrics(Some("plugin.jobs"), 10).unwrap();
+    assert_eq!(jobs[0].source, "mailer");
+    assert_eq!(jobs[0].labels["queue"], "mail");
+
+    // 任意一条不合法时整批拒绝喵
+    let (status, _) = gateway
+        .send_authorized(
+            reqwest::Method::POST,
+            "/telemetry/events",
+            Some(json!({ "metrics": [
+                { "name": "ok", "kind": "gauge", "value": 1 },
+                { "name": "bad name", "kind": "gauge", "value": 1 },
+            ]})),
+        )
+        .await;
+    assert_eq!(status, 400);
+    assert_eq!(gateway.metrics.get_recent_custom_metrics(None, 10).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_feedback_on_chat_response() {
+    let gateway =
+        TestGateway::start(ScriptedProvider::new(["Restarted nginx 喵"]), ScriptedMcpServer::new()).await;
+    let (_, chat) = gateway.chat("restart nginx").await;
+    let response_id = chat["id"].as_str().unwrap().to_string();
+
+    let (status, _) = gateway
+        .send_authorized(
+            reqwest::Method::POST,
+            "/feedback",
+            Some(json!({ "response_id": response_id, "rating": "👎", "comment": "too slow", "user": "u1" })),
+        )
+        .await;
+    assert_eq!(status, 200);
+    let (status, _) = gateway
+        .send_authorized(
+            reqwest::Method::POST,
+            "/feedback",
+            Some(json!({ "response_id": response_id, "rating": "meh" })),
+        )
+        .await;
+    assert_eq!(status, 400);
+
+    let stored = gateway.metrics.get_recent_feedback(10).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].response_id, response_id);
+    assert_eq!(stored[0].comment.as_deref(), Some("too slow"));
+    assert_eq!(gateway.metrics.get_feedback_summary().unwrap()["gateway"].down, 1);
+}
+
+#[tokio::test]
+async fn test_dashboard_reflects_new_activity() {
+    let gateway =
+        TestGateway::start(ScriptedProvider::new(["Restarted nginx 喵"]), ScriptedMcpServer::new()).await;
+    let snapshot = |gateway: &TestGateway| {
+        let url = format!("{}/dashboard/api/metrics", gateway.base_url);
+        async move { reqwest::get(url).await.unwrap().json::<JsonValue>().await.unwrap() }
+    };
+
+    let page = reqwest::get(format!("{}/dashboard", gateway.base_url)).await.unwrap();
+    assert_eq!(page.status(), 200);
+    let html = page.text().await.unwrap();
+    assert!(html.contains("NekoClow Metrics Dashboard"));
+    assert!(html.contains("\"/dashboard/api/metrics\""));
+    assert_eq!(snapshot(&gateway).await["traces"], 0);
+
+    // 不重启服务，新的请求出现在下一次快照和页面里喵
+    gateway.chat("restart nginx").await;
+    let after = snapshot(&gateway).await;
+    assert_eq!(after["traces"], 1);
+    assert!(after["stats"]["total_requests"].is_number());
+    let html = reqwest::get(format!("{}/dashboard", gateway.base_url)).await.unwrap().text().await.unwrap();
+    assert!(html.contains("gateway.request"));
+}
+
+#[tokio::test]
+async fn test_chat_requires_authentication() {
+    let gateway = TestGateway::start(ScriptedProvider::new(["不该到达"]), ScriptedMcpServer::new()).await;
+    let response = gateway
+        .client
+        .post(format!("{}/v1/chat/completions", gateway.base_url))
+        .json(&json!({
+            "model": "scripted",
+            "messages": [{ "role": "user", "content": "你好" }],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(gateway.provider.prompts().is_empty());
+}
+
+#[tokio::test]
+async fn test_bad_tokens_trigger_lockout() {
+    let gateway =
+        TestGateway::start(ScriptedProvider::default(), ScriptedMcpServer::new()).await;
+    let status_with = |token: &'static str| {
+        gateway
+            .client
+            .get(format!("{}/status", gateway.base_url))
+            .bearer_auth(token)
+            .send()
+    };
+
+    for _ in 0..4 {
+        assert_eq!(status_with("wrong").await.unwrap().status(), 401);
+    }
+    let locked = status_with("wrong").await.unwrap();
+    assert_eq!(locked.status(), 429);
+    assert_eq!(locked.headers()["retry-after"], "30");
+
+    // 锁定期间正确的 Token 也被拒绝喵
+    let (status, _) = gateway.send_authorized(reqwest::Method::GET, "/status", None).await;
+    assert_eq!(status, 429);
+}
+
+async fn device_pairing(dir: &std::path::Path) -> DevicePairing {
+    let store = CredentialStore::new(dir.join("credentials"), master_crypto(dir).unwrap()).unwrap();
+    DevicePairing::open(DeviceCodes::open(dir), DeviceKeys::new(store)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_bad_device_codes_trigger_lockout() {
+    let dir = tempfile::tempdir().unwrap();
+    let gateway = TestGateway::start_with_device_pairing(device_pairing(dir.path()).await).await;
+    let poll = |code: &'static str| {
+        gateway
+            .client
+            .post(format!("{}/pairing/token", gateway.base_url))
+            .json(&json!({ "device_code": code }))
+            .send()
+    };
+
+    for _ in 0..4 {
+        let response = poll("not-a-device-code").await.unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(response.json::<JsonValue>().await.unwrap()["error"], "invalid_grant");
+    }
+    let locked = poll("still-wrong").await.unwrap();
+    assert_eq!(locked.status(), 429);
+    assert_eq!(locked.headers()["retry-after"], "30");
+    assert_eq!(poll("another-guess").await.unwrap().status(), 429);
+}
+
+#[tokio::test]
+async fn test_device_pairing_requests_are_throttled() {
+    let dir = tempfile::tempdir().unwrap();
+    let gateway = TestGateway::start_with_device_pairing(device_pairing(dir.path()).await).await;
+    let start = |name: &'static str| {
+        gateway
+            .client
+            .post(format!("{}/pairing/device", gateway.base_url))
+            .json(&json!({ "device_name": name }))
+            .send()
+    };
+
+    assert_eq!(start("phone").await.unwrap().status(), 200);
+    assert_eq!(start("tablet").await.unwrap().status(), 200);
+    // 同一来源最多 2 个等待审批的申请喵
+    let pending = start("laptop").await.unwrap();
+    assert_eq!(pending.status(), 429);
+    assert_eq!(pending.json::<JsonValue>().await.unwrap()["error"], "slow_down");
+    // 令牌桶耗尽后直接拒绝，不再读写申请文件喵
+    let limited = start("laptop").await.unwrap();
+    assert_eq!(limited.status(), 429);
+    assert!(limited.headers()["retry-after"].to_str().unwrap().parse::<u64>().unwrap() >= 1);
+    assert_eq!(DeviceCodes::open(dir.path()).pending().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_api_keys_are_rate_limited() {
+    let key = |name: &str, rate_limit| ApiKey {
+        name: name.to_string(),
+        secret: format!("nk_{}", name),
+        rate_limit,
+    };
+    let limit = RateLimit {
+        requests_per_minute: 1,
+        burst: 2,
+    };
+    let gateway = TestGateway::start_with_config(
+        ScriptedProvider::default(),
+        ScriptedMcpServer::new(),
+        GatewayConfig {
+            api_keys: vec![key("ci", Some(limit)), key("ops", None)],
+            ..Default::default()
+        },
+    )
+    .await;
+    let status_with = |header: &'static str, value: &'static str| {
+        gateway
+            .client
+            .get(format!("{}/status", gateway.base_url))
+            .header(header, value)
+            .send()
+    };
+
+    assert_eq!(status_with("authorization", "Bearer nk_ci").await.unwrap().status(), 200);
+    assert_eq!(status_with("x-api-key", "nk_ci").await.unwrap().status(), 200);
+    let limited = status_with("x-api-key", "nk_ci").await.unwrap();
+    assert_eq!(limited.status(), 429);
+    assert!(limited.headers()["retry-after"].to_str().unwrap().parse::<u64>().unwrap() >= 59);
+
+    // 其他 Key 与主 Token 不受影响喵
+    let ops: JsonValue = status_with("x-api-key", "nk_ops").await.unwrap().json().await.unwrap();
+    assert_eq!(ops["api_key"], "ops");
+    let (status, body) = gateway.send_authorized(reqwest::Method::GET, "/status", None).await;
+    assert_eq!(status, 200);
+    assert!(body["api_key"].is_null());
+    assert_eq!(gateway.client.get(format!("{}/status", gateway.base_url)).send().await.unwrap().status(), 401);
+}
+
+#[tokio::test]
+async fn test_idempotency_key_is_scoped_to_caller_and_body() {
+    let key = |name: &str| ApiKey {
+        name: name.to_string(),
+        secret: format!("nk_{}", name),
+        rate_limit: None,
+    };
+    let gateway = TestGateway::start_with_config(
+        ScriptedProvider::new(["给 ci 的回复", "给 ops 的回复"]),
+        ScriptedMcpServer::new(),
+        GatewayConfig {
+            api_keys: vec![key("ci"), key("ops")],
+            ..Default::default()
+        },
+    )
+    .await;
+    let send = |secret: &'static str, content: &'static str| {
+        gateway
+            .client
+            .post(format!("{}/v1/chat/completions", gateway.base_url))
+            .header("x-api-key", secret)
+            .header("idempotency-key", "retry-1")
+            .json(&json!({
+                "model": "scripted",
+                "messages": [{ "role": "user", "content": content }],
+            }))
+            .send()
+    };
+
+    let first: JsonValue = send("nk_ci", "你好").await.unwrap().json().await.unwrap();
+    assert_eq!(reply_text(&first), "给 ci 的回复");
+
+    // 同一调用方重试拿到缓存的响应喵
+    let replayed = send("nk_ci", "你好").await.unwrap();
+    assert_eq!(replayed.headers()["idempotent-replayed"], "true");
+    assert_eq!(reply_text(&replayed.json().await.unwrap()), "给 ci 的回复");
+
+    // 另一个 Key 用同一个 Idempotency-Key 不会拿到别人的响应喵
+    let other: JsonValue = send("nk_ops", "你好").await.unwrap().json().await.unwrap();
+    assert_eq!(reply_text(&other), "给 ops 的回复");
+
+    // 同一个 key 换了请求体返回 422 喵
+    assert_eq!(send("nk_ci", "换个问题").await.unwrap().status(), 422);
+    assert_eq!(gateway.provider.prompts().len(), 2);
+}
+
+#[tokio::test]
+async fn test_inbound_webhook_reaches_agent() {
+    use nekoclaw::gateway::webhook::{InboundWebhooks, SignatureStyle, WebhookSourceConfig};
+    use ring::hmac;
+
+    let secret = "hook-secret";
+    let sources = std::collections::BTreeMap::from([(
+        "github".to_string(),
+        WebhookSourceConfig {
+            secret: secret.to_string(),
+            signature: SignatureStyle::Github,
+            skill: None,
+            tolerance_secs: 300,
+        },
+    )]);
+    let gateway = TestGateway::start_with_webhooks(
+        ScriptedProvider::new(["Triaged 喵"]),
+        InboundWebhooks::new(sources, Vec::new()).unwrap(),
+    )
+    .await;
+    let body = r#"{"action":"opened","sender":{"login":"octocat"}}"#;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let signature: String = hmac::sign(&key, body.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let post = |source: &str, signature: String| {
+        gateway
+            .client
+            .post(format!("{}/webhook/{}", gateway.base_url, source))
+            .header("x-github-event", "issues")
+            .header("x-hub-signature-256", format!("sha256={}", signature))
+            .body(body)
+            .send()
+    };
+
+    assert_eq!(post("github", "00".repeat(32)).await.unwrap().status(), 403);
+    assert_eq!(post("stripe", signature.clone()).await.unwrap().status(), 404);
+    assert!(gateway.provider.prompts().is_empty());
+
+    assert_eq!(post("github", signature).await.unwrap().status(), 202);
+    for _ in 0..100 {
+        if !gateway.provider.prompts().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let prompts = gateway.provider.prompts();
+    let question = &prompts[0].last().unwrap().content;
+    assert!(question.starts_with("[webhook github] issues.opened event from octocat"));
+}
+
+#[tokio::test]
+async fn test_inbound_webhook_holds_drain_guard() {
+    use nekoclaw::gateway::webhook::{InboundWebhooks, SignatureStyle, WebhookSourceConfig};
+    use ring::hmac;
+
+    let secret = "hook-secret";
+    let sources = std::collections::BTreeMap::from([(
+        "github".to_string(),
+        WebhookSourceConfig {
+            secret: secret.to_string(),
+            signature: SignatureStyle::Github,
+            skill: None,
+            tolerance_secs: 300,
+        },
+    )]);
+    let gateway = TestGateway::start_with_webhooks(
+        ScriptedProvider::new(["Triaged 喵"]).with_delay(Duration::from_millis(300)),
+        InboundWebhooks::new(sources, Vec::new()).unwrap(),
+    )
+    .await;
+    let body = r#"{"action":"opened","sender":{"login":"octocat"}}"#;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let signature: String = hmac::sign(&key, body.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let response = gateway
+        .client
+        .post(format!("{}/webhook/github", gateway.base_url))
+        .header("x-github-event", "issues")
+        .header("x-hub-signature-256", format!("sha256={}", signature))
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+
+    // 202 已返回，但后台 Agent 任务仍持有排空凭证，关闭时会等它完成喵
+    let report = gateway.shutdown.drain(Duration::from_secs(5)).await;
+    assert_eq!((report.in_flight, report.cancelled), (1, 0));
+    assert_eq!(gateway.provider.prompts().len(), 1);
+}
+
+#[tokio::test]
+async fn test_admin_log_level() {
+    let gateway =
+        TestGateway::start(ScriptedProvider::default(), ScriptedMcpServer::new()).await;
+    let path = "/admin/log-level";
+
+    let (status, body) = gateway
+        .send_authorized(
+            reqwest::Method::POST,
+            path,
+            Some(json!({ "level": "debug", "module": "gateway" })),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(body["filter"], "info,nekoclaw::gateway=debug");
+    assert_eq!(gateway.log_level.directive(), "info,nekoclaw::gateway=debug");
+
+    let (status, _) = gateway
+        .send_authorized(reqwest::Method::POST, path, Some(json!({ "level": "loud" })))
+        .await;
+    assert_eq!(status, 400);
+
+    let (status, body) = gateway.send_authorized(reqwest::Method::DELETE, path, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["filter"], "info");
+}
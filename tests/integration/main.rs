@@ -5,8 +5,7 @@
 //!
 //! ## 测试覆盖
 //! - `security_test`: 安全模块集成测试 (crypto, allowlist, sandbox)
-//! - `channel_test`: 渠道模块集成测试 (Discord, Telegram)
-//! - `agent_test`: Agent 核心集成测试 (Agent, ToolChain, Memory, Provider)
+//! - `gateway_test`: Gateway 流程集成测试（需要 `test-harness` feature，dev-dependency 已开启）
 //!
 //! ## 运行命令
 //! ```bash
@@ -14,5 +13,4 @@
 //! ```
 
 pub mod security_test;
-pub mod channel_test;
-pub mod agent_test;
+pub mod gateway_test;
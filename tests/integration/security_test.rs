@@ -13,8 +13,7 @@
 //! cargo test --test integration security_test -- --nocapture
 //! ```
 
-use crate::security::{CryptoService, CryptoError, generate_key, AllowlistService, AllowlistConfig, AllowlistError, SandboxService, SandboxConfig, SandboxError};
-use std::time::Duration;
+use nekoclaw::security::{CryptoService, CryptoError, generate_key, AllowlistService, AllowlistConfig, SandboxService, SandboxConfig, SandboxError};
 
 /// 测试加密服务喵
 #[tokio::test]
@@ -24,7 +23,7 @@ async fn test_crypto_service() {
     assert_eq!(key.len(), 44); // Base64 编码的 32 字节
     
     // 2. 测试加密/解密循环喵
-    let crypto = CryptoService::new(&base64::Engine::decode(&base64::Engine::general_purpose::STANDARD, &key).unwrap()).unwrap();
+    let crypto = CryptoService::new(&base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &key).unwrap()).unwrap();
     
     let plaintext = "测试敏感数据喵！😸";
     let encrypted = crypto.encrypt(plaintext).unwrap();
@@ -37,7 +36,7 @@ async fn test_crypto_service() {
 #[tokio::test]
 async fn test_crypto_empty_string() {
     let key = generate_key();
-    let crypto = CryptoService::new(&base64::Engine::decode(&base64::Engine::general_purpose::STANDARD, &key).unwrap()).unwrap();
+    let crypto = CryptoService::new(&base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &key).unwrap()).unwrap();
     
     let encrypted = crypto.encrypt("").unwrap();
     let decrypted = crypto.decrypt(&encrypted).unwrap();
@@ -49,8 +48,7 @@ async fn test_crypto_empty_string() {
 #[tokio::test]
 async fn test_crypto_invalid_key() {
     let result = CryptoService::new(&[1, 2, 3]); // 错误长度的密钥
-    assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), CryptoError::InvalidKeyLength));
+    assert!(matches!(result, Err(CryptoError::InvalidKeyLength)));
 }
 
 /// 测试白名单服务命令检查喵
@@ -93,7 +91,11 @@ async fn test_allowlist_path_check() {
 async fn test_sandbox_execution() {
     let allowlist_config = AllowlistConfig::default();
     let allowlist_service = AllowlistService::new(allowlist_config);
-    let sandbox_config = SandboxConfig::default();
+    // 默认工作目录不一定存在，使用当前目录喵
+    let sandbox_config = SandboxConfig {
+        working_directory: None,
+        ..SandboxConfig::default()
+    };
     let sandbox = SandboxService::new(allowlist_service, sandbox_config);
     
     // 测试允许的命令喵
@@ -141,10 +143,14 @@ async fn test_sandbox_parameter_injection_protection() {
 async fn test_sandbox_async_execution() {
     let allowlist_config = AllowlistConfig::default();
     let allowlist_service = AllowlistService::new(allowlist_config);
-    let sandbox_config = SandboxConfig::default();
+    // 默认工作目录不一定存在，使用当前目录喵
+    let sandbox_config = SandboxConfig {
+        working_directory: None,
+        ..SandboxConfig::default()
+    };
     let sandbox = SandboxService::new(allowlist_service, sandbox_config);
     
-    let result = sandbox.execute_async("echo", &["Async test"]).await;
+    let result = sandbox.execute_async("echo", &["Async test"], None, None).await;
     assert!(result.is_ok());
     let result = result.unwrap();
     assert!(result.stdout.contains("Async test"));
@@ -152,7 +158,7 @@ async fn test_sandbox_async_execution() {
 }
 
 /// 测试白名单服务默认配置喵
-#[tokio::test]
+#[test]
 fn test_allowlist_default_config() {
     let config = AllowlistConfig::default();
     
@@ -163,14 +169,14 @@ fn test_allowlist_default_config() {
     
     // 检查默认路径喵
     assert!(!config.paths.is_empty());
-    assert!(config.paths.iter().any(|p| p.pattern.contains("nekoclaw")));
+    assert!(config.paths.iter().any(|p| p.pattern.contains(".openclaw")));
     
     // 检查默认拒绝策略喵
     assert!(config.default_deny);
 }
 
 /// 测试沙箱服务默认配置喵
-#[tokio::test]
+#[test]
 fn test_sandbox_default_config() {
     let config = SandboxConfig::default();
     
@@ -180,6 +186,6 @@ fn test_sandbox_default_config() {
     // 检查工作目录喵
     assert!(config.working_directory.is_some());
     
-    // 检查环境变量白名单喵
-    assert!(!config.env_whitelist.is_empty());
+    // 检查最小继承环境喵
+    assert!(config.environment.names().next().is_some());
 }
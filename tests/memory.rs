@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 /// 内存占用监控与压力测试模块 💾
 ///
 /// @花凛 授权的内存安全测试喵
@@ -13,7 +15,9 @@
 /// 测试者: 诺诺 (Nono) ⚡ + 花凛 (Fiora) 🛡️
 use criterion::{black_box, BenchmarkId, Criterion, Throughput};
 use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 /// 🔒 SAFETY: 内存分配追踪器喵
 /// 用于精确测量测试过程中的内存分配量
@@ -83,9 +87,6 @@ pub fn bench_zero_copy_string(c: &mut Criterion) {
 /// 🔒 SAFETY: 内存池复用性能测试喵
 /// 验证缓冲区复用与重复分配的性能差异
 pub fn bench_buffer_pool(c: &mut Criterion) {
-    use std::sync::Arc;
-    use tokio::sync::Semaphore;
-
     c.bench_function("buffer_without_pool", |b| {
         b.iter(|| {
             let mut buffer = Vec::with_capacity(4096);
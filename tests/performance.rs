@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 /// 通用性能测试辅助模块 📊
 ///
 /// @诺诺 的性能测试工具箱喵
@@ -10,7 +12,7 @@
 /// 🔒 SECURITY: 纯计算，无外部依赖
 ///
 /// 测试者: 诺诺 (Nono) ⚡
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 /// 🔒 SAFETY: 性能统计结构体喵
 /// 收集并分析测试结果
@@ -85,7 +87,7 @@ impl PerformanceStats {
     }
 
     /// 🔒 SAFETY: 格式化为人类可读的时间字符串喵
-    pub fn format_duration(&self, ns: u64) -> String {
+    pub fn format_duration(ns: u64) -> String {
         if ns < 1_000 {
             format!("{}ns", ns)
         } else if ns < 1_000_000 {
@@ -119,12 +121,12 @@ impl PerformanceStats {
 "#,
             benchmark_name,
             self.sample_count,
-            self.format_duration(self.mean_ns),
-            self.format_duration(self.median_ns),
-            self.format_duration(self.p99_ns),
-            self.format_duration(self.min_ns),
-            self.format_duration(self.max_ns),
-            self.format_duration(self.std_dev_ns),
+            Self::format_duration(self.mean_ns),
+            Self::format_duration(self.median_ns),
+            Self::format_duration(self.p99_ns),
+            Self::format_duration(self.min_ns),
+            Self::format_duration(self.max_ns),
+            Self::format_duration(self.std_dev_ns),
             if self.mean_ns < 50_000_000 {
                 "✅ PASS"
             } else {
@@ -175,7 +177,7 @@ impl Default for Timer {
 #[macro_export]
 macro_rules! bench_loop {
     ($iterations:expr, $code:block) => {{
-        let timer = crate::tests::performance::Timer::new();
+        let timer = $crate::tests::performance::Timer::new();
         for _ in 0..$iterations {
             $code
         }
@@ -114,7 +114,7 @@ fn benchmark_memory_allocation(c: &mut Criterion) {
     });
 }
 
-/// 基准测试组注册
+// 基准测试组注册
 criterion_group!(
     benches,
     benchmark_fibonacci,
@@ -123,6 +123,6 @@ criterion_group!(
     benchmark_memory_allocation
 );
 
-/// 🔒 SAFETY: 基准测试主入口喵
-/// 运行所有性能测试并生成报告
+// 🔒 SAFETY: 基准测试主入口喵
+// 运行所有性能测试并生成报告
 criterion_main!(benches);